@@ -24,7 +24,7 @@ export const useExport = ({ rustUrl, fractalType, params }: UseExportProps) => {
         }
       })
 
-      const response = await fetch(`${rustUrl}/api/fractal?${highResParams}`)
+      const response = await fetch(`${rustUrl}/api/v1/fractal?${highResParams}`)
       if (!response.ok) {
         throw new Error(`Failed to generate high-res image: ${response.status}`)
       }
@@ -57,7 +57,7 @@ export const useOptimizedFractalGenerator = ({
         urlParams.append(key, value.toString())
       })
 
-      const response = await fetch(`${rustUrl}/api/fractal?${urlParams}`, {
+      const response = await fetch(`${rustUrl}/api/v1/fractal?${urlParams}`, {
         signal: abortControllerRef.current.signal,
       })
 
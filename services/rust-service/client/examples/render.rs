@@ -0,0 +1,43 @@
+//! Render a deep Mandelbrot view and a Julia set to PNG files, after checking the estimate.
+//!
+//! cargo run -p rust-service-client --example render [http://localhost:8001]
+
+use rust_service_client::{Client, EstimateOptions, FractalRequest, OutputOptions};
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let url = std::env::args()
+        .nth(1)
+        .unwrap_or_else(|| "http://localhost:8001".to_string());
+    let client = Client::new(&url)?;
+
+    let seahorse = FractalRequest::mandelbrot()
+        .size(800, 600)
+        .center(-0.7436, 0.1318)
+        .zoom(2000.0)
+        .max_iterations(1000)
+        .color_scheme("magma")
+        .color_scale(12.0)
+        .color_spacing("log");
+
+    let estimate = client.estimate(&seahorse, &EstimateOptions::default())?;
+    println!("Predicted render time: {} ms", estimate["predicted_ms"]);
+
+    let png = client.render(&seahorse)?;
+    std::fs::write("seahorse.png", &png.body)?;
+    println!("Wrote seahorse.png ({} bytes)", png.body.len());
+
+    let julia = FractalRequest::julia()
+        .size(600, 600)
+        .julia_c(-0.7, 0.27)
+        .max_iterations(300)
+        .color_scheme("viridis");
+    let output = OutputOptions {
+        annotate: Some(true),
+        ..OutputOptions::default()
+    };
+    let figure = client.render_with(&julia, &output)?;
+    std::fs::write("julia.png", &figure.body)?;
+    println!("Wrote julia.png ({} bytes)", figure.body.len());
+
+    Ok(())
+}
@@ -0,0 +1,157 @@
+//! Blocking HTTP/1.0 over a plain TCP connection: one connection per request, written in full
+//! and read until the service closes it. The service is reached over plain http inside a
+//! deployment, so this is all the transport the client needs.
+
+use crate::Error;
+use std::io::{Read, Write};
+use std::net::TcpStream;
+use std::time::Duration;
+
+/// Where the service listens: `http://host[:port][/prefix]`
+#[derive(Clone, Debug)]
+pub struct Endpoint {
+    /// As written in the URL, for the Host header (IPv6 addresses keep their brackets)
+    host: String,
+    port: u16,
+    /// Path prefix the service is mounted under, without a trailing slash
+    prefix: String,
+}
+
+impl Endpoint {
+    pub fn parse(url: &str) -> Result<Self, Error> {
+        let invalid = |reason: &str| Error::Transport(format!("Invalid URL '{}': {}", url, reason));
+
+        let rest = url
+            .strip_prefix("http://")
+            .ok_or_else(|| invalid("only http:// is supported"))?;
+        let (authority, path) = rest.split_once('/').unwrap_or((rest, ""));
+        let (host, port) = match authority.rsplit_once(':') {
+            Some((host, port)) if !authority.ends_with(']') => {
+                let port = port.parse().map_err(|_| invalid("bad port"))?;
+                (host, port)
+            }
+            _ => (authority, 80),
+        };
+        if host.is_empty() {
+            return Err(invalid("missing host"));
+        }
+
+        let path = path.trim_end_matches('/');
+        Ok(Self {
+            host: host.to_string(),
+            port,
+            prefix: if path.is_empty() {
+                String::new()
+            } else {
+                format!("/{}", path)
+            },
+        })
+    }
+}
+
+/// A response from the service, whatever its status
+#[derive(Clone, Debug)]
+pub struct Response {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl Response {
+    /// First header of that name, matched case-insensitively
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(header, _)| header.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    pub fn content_type(&self) -> Option<&str> {
+        self.header("Content-Type")
+    }
+
+    fn parse(raw: &[u8]) -> Result<Self, Error> {
+        let malformed = || Error::Transport("Malformed HTTP response".to_string());
+
+        let head_end = raw
+            .windows(4)
+            .position(|window| window == b"\r\n\r\n")
+            .ok_or_else(malformed)?;
+        let head = std::str::from_utf8(&raw[..head_end]).map_err(|_| malformed())?;
+        let mut lines = head.split("\r\n");
+
+        // HTTP/1.1 200 OK
+        let status = lines
+            .next()
+            .and_then(|line| line.split_whitespace().nth(1))
+            .and_then(|code| code.parse().ok())
+            .ok_or_else(malformed)?;
+        let headers = lines
+            .filter_map(|line| line.split_once(':'))
+            .map(|(name, value)| (name.trim().to_string(), value.trim().to_string()))
+            .collect();
+
+        Ok(Self {
+            status,
+            headers,
+            body: raw[head_end + 4..].to_vec(),
+        })
+    }
+}
+
+/// A request body and its content type
+pub struct Body<'a> {
+    pub content_type: &'a str,
+    pub bytes: &'a [u8],
+}
+
+/// Send one request to `path` (with its query string) under the endpoint's prefix
+pub fn send(
+    endpoint: &Endpoint,
+    method: &str,
+    path: &str,
+    headers: &[(&str, String)],
+    body: Option<Body>,
+    timeout: Option<Duration>,
+) -> Result<Response, Error> {
+    let transport = |e: std::io::Error| {
+        Error::Transport(format!(
+            "{} {}:{}: {}",
+            method, endpoint.host, endpoint.port, e
+        ))
+    };
+
+    let address = (
+        endpoint.host.trim_start_matches('[').trim_end_matches(']'),
+        endpoint.port,
+    );
+    let mut stream = TcpStream::connect(address).map_err(transport)?;
+    stream.set_read_timeout(timeout).map_err(transport)?;
+    stream.set_write_timeout(timeout).map_err(transport)?;
+
+    // HTTP/1.0 keeps the response unchunked and ends it by closing the connection
+    let mut request = format!(
+        "{} {}{} HTTP/1.0\r\nHost: {}:{}\r\nConnection: close\r\n",
+        method, endpoint.prefix, path, endpoint.host, endpoint.port
+    );
+    for (name, value) in headers {
+        request.push_str(&format!("{}: {}\r\n", name, value));
+    }
+    if let Some(body) = &body {
+        request.push_str(&format!(
+            "Content-Type: {}\r\nContent-Length: {}\r\n",
+            body.content_type,
+            body.bytes.len()
+        ));
+    }
+    request.push_str("\r\n");
+
+    stream.write_all(request.as_bytes()).map_err(transport)?;
+    if let Some(body) = body {
+        stream.write_all(body.bytes).map_err(transport)?;
+    }
+
+    let mut raw = Vec::new();
+    stream.read_to_end(&mut raw).map_err(transport)?;
+    Response::parse(&raw)
+}
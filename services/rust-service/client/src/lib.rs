@@ -0,0 +1,721 @@
+//! Typed blocking client for the rust-service fractal API.
+//!
+//! Requests are built with `FractalRequest` rather than hand-written query strings:
+//!
+//! ```no_run
+//! use rust_service_client::{Client, FractalRequest};
+//!
+//! let client = Client::new("http://localhost:8001")?;
+//! let png = client.render(
+//!     &FractalRequest::mandelbrot()
+//!         .size(800, 600)
+//!         .center(-0.7436, 0.1318)
+//!         .zoom(2000.0)
+//!         .max_iterations(1000)
+//!         .color_scheme("magma"),
+//! )?;
+//! std::fs::write("mandelbrot.png", png.body).unwrap();
+//! # Ok::<(), rust_service_client::Error>(())
+//! ```
+//!
+//! JSON endpoints return `serde_json::Value`, shaped as described by `GET /api/schema/v1`.
+//! Image, audio and text endpoints return the raw `Response`. The WebSocket streams aren't
+//! covered. The service's `tests/client.rs` exercises the client against a running service.
+
+mod http;
+mod request;
+
+pub use http::Response;
+pub use request::FractalRequest;
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use http::{Body, Endpoint};
+use request::encode_query;
+use serde_json::{json, Value};
+use std::fmt;
+use std::time::Duration;
+
+/// Header that bills a request to a tenant
+const API_KEY_HEADER: &str = "X-API-Key";
+
+/// Header that asks for a low-power render
+const POWER_MODE_HEADER: &str = "X-Power-Mode";
+
+/// Header carrying the base64 reproducibility manifest
+const MANIFEST_HEADER: &str = "X-Render-Manifest";
+
+#[derive(Debug)]
+pub enum Error {
+    /// The service couldn't be reached or didn't answer in HTTP
+    Transport(String),
+    /// The service answered with an error status; `message` is its `error` field
+    Api { status: u16, message: String },
+    /// A response body wasn't the expected JSON
+    Decode(String),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Transport(message) | Error::Decode(message) => f.write_str(message),
+            Error::Api { status, message } => write!(f, "{} ({})", message, status),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// Optional query parameters of one endpoint, named after them; unset ones aren't sent
+macro_rules! options {
+    ($(#[$meta:meta])* $name:ident { $($(#[$doc:meta])* $field:ident: $ty:ty),* $(,)? }) => {
+        $(#[$meta])*
+        #[derive(Clone, Debug, Default, PartialEq)]
+        pub struct $name {
+            $($(#[$doc])* pub $field: Option<$ty>,)*
+        }
+
+        impl $name {
+            fn pairs(&self) -> Vec<(&'static str, Value)> {
+                let mut pairs = Vec::new();
+                $(
+                    if let Some(value) = &self.$field {
+                        pairs.push((stringify!($field), Value::from(value.clone())));
+                    }
+                )*
+                pairs
+            }
+        }
+    };
+}
+
+options!(
+    /// Output settings for `render`, `render_post` and `render_bounds`
+    OutputOptions {
+        /// Attach a reproducibility manifest, read back with `Response::manifest`
+        manifest: bool,
+        /// Tag PNG output with its manifest and parameters (default true)
+        embed_metadata: bool,
+        /// zlib level 0-9
+        compression: u32,
+        /// none, sub, up, average, paeth or adaptive
+        png_filter: String,
+        /// Quality 1-100 for format=jpeg, lossy format=webp and format=avif
+        quality: u32,
+        /// Exact pixels for format=webp
+        lossless: bool,
+        /// Draw over the image: comma-separated axes, grid and scalebar
+        overlay: String,
+        /// Text for a corner, with placeholders such as `{zoom}`
+        caption: String,
+        /// top_left, top_right, bottom_left or bottom_right
+        caption_position: String,
+        /// Frame the image with axes, legend and parameter summary
+        annotate: bool,
+        /// png (default), jpeg, webp, avif, tiff, bmp, svg, pdf, eps, raw, npy, ascii,
+        /// ansi or braille
+        format: String,
+        /// iterations (u32, default) or smooth (f32) samples for format=raw and format=npy
+        data: String,
+        /// Characters per line of text output
+        columns: u32,
+        /// standard, detailed or blocks
+        charset: String,
+        /// Dense characters for sparse pixels in text output
+        invert: bool,
+        /// 8 (default) or 16 bits per channel (png and tiff)
+        bit_depth: u32,
+        /// image (default), or json for the image as a data URI with its metadata; see
+        /// `render_json`
+        response: String,
+    }
+);
+
+options!(StatsOptions {
+    /// Locale for the formatted numbers
+    locale: String,
+});
+
+options!(EstimateOptions {
+    /// Share of the pixels rendered for the estimate, 0.0001-0.25, at most 65536 pixels
+    sample: f64,
+});
+
+options!(CropOptions {
+    /// Field size per side relative to the requested image, 1-4
+    field: f64,
+    /// Window positions tried along each axis, 1-32
+    positions: u32,
+});
+
+options!(DeepZoomOptions {
+    /// Tile edge before overlap, 64-1024
+    tile_size: u32,
+    /// Pixels a tile repeats from each neighbour, 0-8
+    overlap: u32,
+    /// Tile format: png, jpeg or webp
+    format: String,
+    /// JPEG and WebP tile quality, 1-100
+    quality: u8,
+});
+
+options!(IterationMatrixOptions {
+    /// Pixels per side averaged into one count, 1-16
+    downsample: u32,
+});
+
+options!(ExploreOptions {
+    /// Number of variants to return
+    count: u32,
+    /// Longest thumbnail edge in pixels
+    thumb_size: u32,
+    /// Perturbation strength, relative to the current view
+    spread: f64,
+    /// Seed for the perturbations (distinct from the fractal's own `seed`)
+    explore_seed: u64,
+    /// Render twice as many candidates and keep the highest-scoring ones
+    curate: bool,
+});
+
+options!(SonifyOptions {
+    /// scanline or orbit
+    mode: String,
+    notes: u32,
+    note_ms: u32,
+    min_freq: f64,
+    max_freq: f64,
+    sample_rate: u32,
+});
+
+options!(AnimateOptions {
+    /// line (default) or circle
+    path: String,
+    /// c in the last frame of path=line
+    to_c_real: f64,
+    to_c_imag: f64,
+    /// Radius of path=circle around julia_c
+    radius: f64,
+    frames: u32,
+    fps: u32,
+});
+
+options!(ZoomAnimationOptions {
+    /// View of the last frame; the first is the request's view
+    to_center_x: f64,
+    to_center_y: f64,
+    to_zoom: f64,
+    frames: u32,
+    fps: u32,
+    /// linear, ease_in, ease_out or ease_in_out (default)
+    easing: String,
+    /// apng (default), webp, or webm on servers built with the video feature
+    format: String,
+    /// Quality 1-100 for lossy webp
+    quality: u32,
+    lossless: bool,
+    /// Frames from one keyframe to the next for webm (default 2 seconds' worth)
+    keyframe_interval: u32,
+    /// Target kilobits per second for webm (default constant quality)
+    bitrate: u32,
+});
+
+options!(CompareOptions {
+    /// Largest per-channel difference still counted as equal (default 0)
+    tolerance: u8,
+    /// Also return an image of where the renders differ
+    include_diff: bool,
+});
+
+options!(BackendComparisonOptions {
+    /// Left panel: scalar (default), simd4, simd8 or adaptive
+    backend_a: String,
+    /// Right panel (default: the service's calibrated kernel)
+    backend_b: String,
+    /// Largest per-channel difference still counted as equal (default 0)
+    tolerance: u8,
+});
+
+/// Plane region for the v2 render API
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Bounds {
+    pub x_min: f64,
+    pub x_max: f64,
+    pub y_min: f64,
+    pub y_max: f64,
+}
+
+/// A grid of renders composed into one image
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Montage {
+    /// grid (default), golden or filmstrip
+    pub layout: Option<String>,
+    pub columns: Option<u32>,
+    pub tile_width: Option<u32>,
+    pub tile_height: Option<u32>,
+    /// Gap between panels and around the edge, in pixels
+    pub border: Option<u32>,
+    /// Write a caption under each panel (its own, or the fractal type)
+    pub captions: Option<bool>,
+    /// Panels with an optional caption each
+    pub panels: Vec<(Option<String>, FractalRequest)>,
+}
+
+impl Montage {
+    pub fn panel(mut self, request: FractalRequest) -> Self {
+        self.panels.push((None, request));
+        self
+    }
+
+    pub fn captioned_panel(mut self, caption: &str, request: FractalRequest) -> Self {
+        self.panels.push((Some(caption.to_string()), request));
+        self
+    }
+
+    fn to_json(&self) -> Value {
+        let panels: Vec<Value> = self
+            .panels
+            .iter()
+            .map(|(caption, request)| {
+                let mut panel = request.to_json();
+                if let (Some(caption), Some(object)) = (caption, panel.as_object_mut()) {
+                    object.insert("caption".to_string(), json!(caption));
+                }
+                panel
+            })
+            .collect();
+        let mut body = json!({
+            "layout": self.layout,
+            "columns": self.columns,
+            "tile_width": self.tile_width,
+            "tile_height": self.tile_height,
+            "border": self.border,
+            "captions": self.captions,
+            "panels": panels,
+        });
+        if let Some(object) = body.as_object_mut() {
+            object.retain(|_, field| !field.is_null());
+        }
+        body
+    }
+}
+
+impl Response {
+    /// The reproducibility manifest, when the render asked for one
+    pub fn manifest(&self) -> Option<Value> {
+        let encoded = self.header(MANIFEST_HEADER)?;
+        let json = STANDARD.decode(encoded).ok()?;
+        serde_json::from_slice(&json).ok()
+    }
+}
+
+/// Blocking client for one service instance
+#[derive(Clone, Debug)]
+pub struct Client {
+    endpoint: Endpoint,
+    api_key: Option<String>,
+    low_power: bool,
+    timeout: Option<Duration>,
+}
+
+impl Client {
+    /// A client for the service at `http://host[:port][/prefix]`
+    pub fn new(base_url: &str) -> Result<Self, Error> {
+        Ok(Self {
+            endpoint: Endpoint::parse(base_url)?,
+            api_key: None,
+            low_power: false,
+            timeout: Some(Duration::from_secs(120)),
+        })
+    }
+
+    /// Bill requests to the tenant of this API key
+    pub fn with_api_key(mut self, api_key: &str) -> Self {
+        self.api_key = Some(api_key.to_string());
+        self
+    }
+
+    /// Ask for low-power renders
+    pub fn with_low_power(mut self) -> Self {
+        self.low_power = true;
+        self
+    }
+
+    /// Read and write timeout per request (default 120s); `None` waits indefinitely
+    pub fn with_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    // Service endpoints
+
+    pub fn health(&self) -> Result<Value, Error> {
+        self.get_json("/health", &[])
+    }
+
+    /// Dependency checks; a 503 comes back as the report rather than an error
+    pub fn readiness(&self) -> Result<Value, Error> {
+        let response = self.send("GET", "/health/ready", None)?;
+        decode_json(&response)
+    }
+
+    pub fn info(&self) -> Result<Value, Error> {
+        self.get_json("/api/info", &[])
+    }
+
+    pub fn schema(&self) -> Result<Value, Error> {
+        self.get_json("/api/schema/v1", &[])
+    }
+
+    pub fn palettes(&self) -> Result<Value, Error> {
+        self.get_json("/api/palettes", &[])
+    }
+
+    pub fn deprecations(&self) -> Result<Value, Error> {
+        self.get_json("/api/deprecations", &[])
+    }
+
+    /// Usage per tenant over `window` (e.g. 24h); other tenants need an admin key
+    pub fn usage(&self, window: Option<&str>, tenant: Option<&str>) -> Result<Value, Error> {
+        let mut pairs = Vec::new();
+        if let Some(window) = window {
+            pairs.push(("window", json!(window)));
+        }
+        if let Some(tenant) = tenant {
+            pairs.push(("tenant", json!(tenant)));
+        }
+        self.get_json("/api/usage", &pairs)
+    }
+
+    /// Reset a tenant's usage; needs an admin key
+    pub fn reset_usage(&self, tenant: &str) -> Result<Value, Error> {
+        let path = with_query("/api/usage/reset", &[("tenant", json!(tenant))]);
+        let response = self.checked("POST", &path, None)?;
+        decode_json(&response)
+    }
+
+    /// Recent public queue renders with thumbnails, newest first
+    pub fn recent_jobs(&self, limit: Option<u32>) -> Result<Value, Error> {
+        let pairs: Vec<_> = limit
+            .map(|limit| ("limit", json!(limit)))
+            .into_iter()
+            .collect();
+        self.get_json("/api/jobs/recent", &pairs)
+    }
+
+    // Rendering
+
+    /// PNG render with the default output settings
+    pub fn render(&self, request: &FractalRequest) -> Result<Response, Error> {
+        self.render_with(request, &OutputOptions::default())
+    }
+
+    pub fn render_with(
+        &self,
+        request: &FractalRequest,
+        output: &OutputOptions,
+    ) -> Result<Response, Error> {
+        let path = render_path("/api/v1/fractal", request, &output.pairs());
+        self.checked("GET", &path, None)
+    }
+
+    /// The same render with the parameters in a JSON body, for long values like IFS transforms
+    pub fn render_post(
+        &self,
+        request: &FractalRequest,
+        output: &OutputOptions,
+    ) -> Result<Response, Error> {
+        let path = with_query("/api/v1/fractal", &output.pairs());
+        self.post_json(&path, &request.to_json())
+    }
+
+    /// v2 render of an explicit plane region; the view parameters (zoom, center) must be unset
+    pub fn render_bounds(
+        &self,
+        request: &FractalRequest,
+        bounds: Bounds,
+        output: &OutputOptions,
+    ) -> Result<Response, Error> {
+        let mut params = request.to_json();
+        if let Some(object) = params.as_object_mut() {
+            object.remove("type");
+        }
+        let body = json!({
+            "type": request.fractal_type(),
+            "bounds": {
+                "x_min": bounds.x_min,
+                "x_max": bounds.x_max,
+                "y_min": bounds.y_min,
+                "y_max": bounds.y_max,
+            },
+            "params": params,
+        });
+        let path = with_query("/api/v2/fractal", &output.pairs());
+        self.post_json(&path, &body)
+    }
+
+    /// The render as JSON: the encoded image as a base64 data URI alongside the parameters
+    /// used, render time and statistics
+    pub fn render_json(
+        &self,
+        request: &FractalRequest,
+        output: &OutputOptions,
+    ) -> Result<Value, Error> {
+        let output = OutputOptions {
+            response: Some("json".to_string()),
+            ..output.clone()
+        };
+        let path = render_path("/api/v1/fractal", request, &output.pairs());
+        decode_json(&self.checked("GET", &path, None)?)
+    }
+
+    /// Render time and pixel statistics without the image
+    pub fn stats(&self, request: &FractalRequest, options: &StatsOptions) -> Result<Value, Error> {
+        let path = render_path("/api/v1/fractal/stats", request, &options.pairs());
+        decode_json(&self.checked("GET", &path, None)?)
+    }
+
+    /// Check the parameters without rendering
+    pub fn validate(&self, request: &FractalRequest) -> Result<Value, Error> {
+        let path = render_path("/api/v1/fractal/validate", request, &[]);
+        decode_json(&self.checked("GET", &path, None)?)
+    }
+
+    /// Predicted render time from a small timing sample
+    pub fn estimate(
+        &self,
+        request: &FractalRequest,
+        options: &EstimateOptions,
+    ) -> Result<Value, Error> {
+        let path = render_path("/api/v1/fractal/estimate", request, &options.pairs());
+        decode_json(&self.checked("GET", &path, None)?)
+    }
+
+    /// Monte-Carlo area estimate of the Mandelbrot set within the view
+    pub fn area(&self, request: &FractalRequest) -> Result<Value, Error> {
+        let path = render_path("/api/v1/analyze/area", request, &[]);
+        decode_json(&self.checked("GET", &path, None)?)
+    }
+
+    /// Per-pixel iteration counts of an escape-time render, for coloring client-side
+    pub fn iterations(
+        &self,
+        request: &FractalRequest,
+        options: &IterationMatrixOptions,
+    ) -> Result<Value, Error> {
+        let path = render_path("/api/v1/fractal/iterations", request, &options.pairs());
+        decode_json(&self.checked("GET", &path, None)?)
+    }
+
+    /// The best-scoring window of a larger field around the view, as PNG
+    pub fn crop(&self, request: &FractalRequest, options: &CropOptions) -> Result<Response, Error> {
+        let path = render_path("/api/v1/fractal/crop", request, &options.pairs());
+        self.checked("GET", &path, None)
+    }
+
+    /// The view's Deep Zoom tile pyramid and .dzi descriptor as a zip; width and height set
+    /// the full image size, up to 16384 per side
+    pub fn deep_zoom(
+        &self,
+        request: &FractalRequest,
+        options: &DeepZoomOptions,
+    ) -> Result<Response, Error> {
+        let path = render_path("/api/v1/fractal/dzi", request, &options.pairs());
+        self.checked("GET", &path, None)
+    }
+
+    /// Nearby variants of the view with thumbnails and aesthetic scores
+    pub fn explore(
+        &self,
+        request: &FractalRequest,
+        options: &ExploreOptions,
+    ) -> Result<Value, Error> {
+        let path = render_path("/api/v1/explore", request, &options.pairs());
+        decode_json(&self.checked("GET", &path, None)?)
+    }
+
+    /// The view as a WAV file
+    pub fn sonify(
+        &self,
+        request: &FractalRequest,
+        options: &SonifyOptions,
+    ) -> Result<Response, Error> {
+        let path = render_path("/api/v1/sonify", request, &options.pairs());
+        self.checked("GET", &path, None)
+    }
+
+    /// A Julia set morphing as c moves, as an animated GIF
+    pub fn animate(
+        &self,
+        request: &FractalRequest,
+        options: &AnimateOptions,
+    ) -> Result<Response, Error> {
+        let path = render_path("/api/v1/animate", request, &options.pairs());
+        self.checked("GET", &path, None)
+    }
+
+    /// The view zooming to another, as an APNG, animated WebP or WebM video
+    pub fn zoom_animation(
+        &self,
+        request: &FractalRequest,
+        options: &ZoomAnimationOptions,
+    ) -> Result<Response, Error> {
+        let path = render_path("/api/v1/animate/zoom", request, &options.pairs());
+        self.checked("GET", &path, None)
+    }
+
+    /// Compare a render against a reference PNG
+    pub fn compare(
+        &self,
+        request: &FractalRequest,
+        reference_png: &[u8],
+        options: &CompareOptions,
+    ) -> Result<Value, Error> {
+        let mut body = request.to_json();
+        if let Some(object) = body.as_object_mut() {
+            object.insert(
+                "reference_png".to_string(),
+                json!(STANDARD.encode(reference_png)),
+            );
+            object.extend(
+                options
+                    .pairs()
+                    .into_iter()
+                    .map(|(name, value)| (name.to_string(), value)),
+            );
+        }
+        decode_json(&self.post_json("/api/v1/fractal/compare", &body)?)
+    }
+
+    /// The view rendered on two backends side by side over a difference heat strip; the
+    /// verdict and timings are in the X-Backend-* and X-Max-Deviation headers
+    pub fn compare_backends(
+        &self,
+        request: &FractalRequest,
+        options: &BackendComparisonOptions,
+    ) -> Result<Response, Error> {
+        let path = render_path(
+            "/api/v1/fractal/compare/backends",
+            request,
+            &options.pairs(),
+        );
+        self.checked("GET", &path, None)
+    }
+
+    pub fn montage(&self, montage: &Montage) -> Result<Response, Error> {
+        self.post_json("/api/v1/montage", &montage.to_json())
+    }
+
+    /// Fractal flame render; `request` is the flame body described by `GET /api/schema/v1`
+    pub fn flame(&self, request: &Value) -> Result<Response, Error> {
+        self.post_json("/api/v1/flame", request)
+    }
+
+    /// Re-render a manifest (see `Response::manifest`) and check it matches
+    pub fn verify_manifest(&self, manifest: &Value) -> Result<Value, Error> {
+        decode_json(&self.post_json("/api/v1/manifest/verify", manifest)?)
+    }
+
+    /// The manifest and text chunks a saved PNG render was tagged with
+    pub fn decode_metadata(&self, png: &[u8]) -> Result<Value, Error> {
+        let body = Body {
+            content_type: "image/png",
+            bytes: png,
+        };
+        decode_json(&self.checked("POST", "/api/v1/decode-metadata", Some(body))?)
+    }
+
+    // Tool server (JSON-RPC)
+
+    pub fn list_tools(&self) -> Result<Value, Error> {
+        self.rpc("tools/list", json!({}))
+    }
+
+    pub fn call_tool(&self, name: &str, arguments: &Value) -> Result<Value, Error> {
+        self.rpc(
+            "tools/call",
+            json!({ "name": name, "arguments": arguments }),
+        )
+    }
+
+    fn rpc(&self, method: &str, params: Value) -> Result<Value, Error> {
+        let body = json!({ "jsonrpc": "2.0", "id": 1, "method": method, "params": params });
+        let mut response = decode_json(&self.post_json("/api/v1/tool", &body)?)?;
+        if let Some(error) = response.get("error") {
+            return Err(Error::Api {
+                status: 200,
+                message: error["message"].as_str().unwrap_or("RPC error").to_string(),
+            });
+        }
+        Ok(response["result"].take())
+    }
+
+    // Transport
+
+    fn get_json(&self, path: &str, pairs: &[(&str, Value)]) -> Result<Value, Error> {
+        decode_json(&self.checked("GET", &with_query(path, pairs), None)?)
+    }
+
+    fn post_json(&self, path: &str, body: &Value) -> Result<Response, Error> {
+        let bytes = serde_json::to_vec(body).map_err(|e| Error::Decode(e.to_string()))?;
+        let body = Body {
+            content_type: "application/json",
+            bytes: &bytes,
+        };
+        self.checked("POST", path, Some(body))
+    }
+
+    /// Send, turning error statuses into `Error::Api`
+    fn checked(&self, method: &str, path: &str, body: Option<Body>) -> Result<Response, Error> {
+        let response = self.send(method, path, body)?;
+        if response.status < 400 {
+            return Ok(response);
+        }
+        let message = serde_json::from_slice::<Value>(&response.body)
+            .ok()
+            .and_then(|body| body["error"].as_str().map(str::to_string))
+            .unwrap_or_else(|| String::from_utf8_lossy(&response.body).into_owned());
+        Err(Error::Api {
+            status: response.status,
+            message,
+        })
+    }
+
+    fn send(&self, method: &str, path: &str, body: Option<Body>) -> Result<Response, Error> {
+        let mut headers = Vec::new();
+        if let Some(api_key) = &self.api_key {
+            headers.push((API_KEY_HEADER, api_key.clone()));
+        }
+        if self.low_power {
+            headers.push((POWER_MODE_HEADER, "low".to_string()));
+        }
+        http::send(&self.endpoint, method, path, &headers, body, self.timeout)
+    }
+}
+
+fn with_query(path: &str, pairs: &[(&str, Value)]) -> String {
+    if pairs.is_empty() {
+        return path.to_string();
+    }
+    let query = encode_query(pairs.iter().map(|(name, value)| (*name, value)));
+    format!("{}?{}", path, query)
+}
+
+/// `path?<render parameters>&<endpoint options>`
+fn render_path(path: &str, request: &FractalRequest, pairs: &[(&str, Value)]) -> String {
+    let mut query = request.to_query();
+    if !pairs.is_empty() {
+        query.push('&');
+        query.push_str(&encode_query(
+            pairs.iter().map(|(name, value)| (*name, value)),
+        ));
+    }
+    format!("{}?{}", path, query)
+}
+
+fn decode_json(response: &Response) -> Result<Value, Error> {
+    serde_json::from_slice(&response.body).map_err(|e| {
+        Error::Decode(format!(
+            "Expected JSON from the service (status {}): {}",
+            response.status, e
+        ))
+    })
+}
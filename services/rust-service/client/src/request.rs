@@ -0,0 +1,244 @@
+//! Typed builder for the render parameters every fractal endpoint takes. Each setter is named
+//! after the query parameter it sets; see `GET /api/schema/v1` for ranges and defaults.
+
+use serde_json::{Map, Value};
+
+/// Render parameters for one fractal, sent as a query string or a JSON body
+#[derive(Clone, Debug, PartialEq)]
+pub struct FractalRequest {
+    params: Map<String, Value>,
+}
+
+impl FractalRequest {
+    /// A request for any type by name, e.g. one listed by `Client::info`
+    pub fn new(fractal_type: &str) -> Self {
+        Self { params: Map::new() }.set("type", fractal_type)
+    }
+
+    /// Strange attractor with the given map (clifford, dejong or lorenz)
+    pub fn attractor(map: &str) -> Self {
+        Self::new("attractor").set("attractor", map)
+    }
+
+    /// Set any parameter by name, for ones added to the service after this client
+    pub fn set(mut self, name: &str, value: impl Into<Value>) -> Self {
+        self.params.insert(name.to_string(), value.into());
+        self
+    }
+
+    pub fn fractal_type(&self) -> &str {
+        self.params
+            .get("type")
+            .and_then(Value::as_str)
+            .unwrap_or("mandelbrot")
+    }
+
+    pub fn size(self, width: u32, height: u32) -> Self {
+        self.width(width).height(height)
+    }
+
+    pub fn center(self, x: f64, y: f64) -> Self {
+        self.center_x(x).center_y(y)
+    }
+
+    /// Julia constant c, for julia, nova and hybrid
+    pub fn julia_c(self, real: f64, imag: f64) -> Self {
+        self.julia_c_real(real).julia_c_imag(imag)
+    }
+
+    /// The parameters as a JSON object, for POST bodies
+    pub fn to_json(&self) -> Value {
+        Value::Object(self.params.clone())
+    }
+
+    /// The parameters as a percent-encoded query string, without the leading `?`
+    pub fn to_query(&self) -> String {
+        encode_query(
+            self.params
+                .iter()
+                .map(|(name, value)| (name.as_str(), value)),
+        )
+    }
+}
+
+/// `name=value&...`, strings unquoted and everything percent-encoded
+pub(crate) fn encode_query<'a>(pairs: impl Iterator<Item = (&'a str, &'a Value)>) -> String {
+    pairs
+        .map(|(name, value)| {
+            let value = match value {
+                Value::String(text) => text.clone(),
+                other => other.to_string(),
+            };
+            format!("{}={}", percent_encode(name), percent_encode(&value))
+        })
+        .collect::<Vec<_>>()
+        .join("&")
+}
+
+/// Everything but the unreserved characters of RFC 3986
+fn percent_encode(text: &str) -> String {
+    let mut encoded = String::with_capacity(text.len());
+    for byte in text.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                encoded.push(byte as char)
+            }
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    encoded
+}
+
+/// Constructors for the built-in types, named after them
+macro_rules! fractal_types {
+    ($($name:ident),* $(,)?) => {
+        impl FractalRequest {
+            $(
+                #[doc = concat!("`type=", stringify!($name), "`")]
+                pub fn $name() -> Self {
+                    Self::new(stringify!($name))
+                }
+            )*
+        }
+    };
+}
+
+fractal_types!(
+    mandelbrot,
+    julia,
+    sierpinski,
+    koch,
+    dragon,
+    hilbert,
+    levy,
+    newton,
+    nova,
+    magnet1,
+    magnet2,
+    lyapunov,
+    buddhabrot,
+    nebulabrot,
+    barnsley,
+    ifs,
+    lsystem,
+    vicsek,
+    carpet,
+    apollonian,
+    htree,
+    bifurcation,
+    plasma,
+    custom,
+    hybrid,
+);
+
+/// One setter per query parameter, named after it
+macro_rules! parameters {
+    ($($(#[$doc:meta])* $name:ident: $ty:ty),* $(,)?) => {
+        impl FractalRequest {
+            $(
+                $(#[$doc])*
+                pub fn $name(self, value: $ty) -> Self {
+                    self.set(stringify!($name), value)
+                }
+            )*
+        }
+    };
+}
+
+parameters!(
+    width: u32,
+    height: u32,
+    zoom: f64,
+    center_x: f64,
+    center_y: f64,
+    max_iterations: u32,
+    /// Built-in scheme, palette file name or `brand:` colors
+    color_scheme: &str,
+    /// Custom gradient as comma-separated hex stops
+    palette: &str,
+    /// 2-5 hex colors joined by an Oklab gradient
+    brand_colors: &str,
+    /// rgb or oklab
+    interpolation: &str,
+    /// linear, histogram, orbit_trap, distance, binary_decomposition, stripe_average,
+    /// atom_domain or period
+    coloring: &str,
+    /// point, cross or circle
+    trap_shape: &str,
+    trap_x: f64,
+    trap_y: f64,
+    trap_radius: f64,
+    boundary_width: f64,
+    stripe_density: f64,
+    color_offset: f64,
+    color_scale: f64,
+    /// linear or log
+    color_spacing: &str,
+    adaptive: bool,
+    /// none or adaptive
+    aa: &str,
+    aa_threshold: f64,
+    /// none or lambert
+    shading: &str,
+    /// Degrees counterclockwise from the right edge
+    light_azimuth: f64,
+    /// Degrees above the image plane
+    light_elevation: f64,
+    variant: &str,
+    julia_c_real: f64,
+    julia_c_imag: f64,
+    /// Complex polynomial coefficients, highest degree first
+    julia_coefficients: &str,
+    hybrid_pattern: &str,
+    recursion_depth: u32,
+    newton_degree: u32,
+    newton_coefficients: &str,
+    relaxation: f64,
+    lyapunov_sequence: &str,
+    samples: u64,
+    seed: u64,
+    /// linear, sqrt, log or aces, for density types
+    tone_map: &str,
+    /// Stops of brightening before the tone curve
+    exposure: f64,
+    red_iterations: u32,
+    green_iterations: u32,
+    blue_iterations: u32,
+    /// JSON array of transforms
+    ifs_transforms: &str,
+    lsystem_preset: &str,
+    lsystem_axiom: &str,
+    lsystem_rules: &str,
+    lsystem_angle: f64,
+    /// plus or x
+    vicsek_variant: &str,
+    max_curvature: f64,
+    line_thickness: u32,
+    /// 6-digit hex
+    background_color: &str,
+    /// 6-digit hex
+    stroke_color: &str,
+    /// 6-digit hex
+    fill_color: &str,
+    attractor_a: f64,
+    attractor_b: f64,
+    attractor_c: f64,
+    attractor_d: f64,
+    bifurcation_r_min: f64,
+    bifurcation_r_max: f64,
+    bifurcation_y_min: f64,
+    bifurcation_y_max: f64,
+    roughness: f64,
+    /// Escape-time formula of z and c, e.g. `z^3 + c*sin(z)`
+    formula: &str,
+    /// Color vision deficiency to simulate
+    simulate: &str,
+    /// Power-law output gamma, 1-3
+    gamma: f64,
+    /// none, ordered or floyd_steinberg
+    dither: &str,
+    /// Gray levels, 2-256
+    dither_levels: u32,
+    /// Histogram-equalize the grays
+    equalize: bool,
+);
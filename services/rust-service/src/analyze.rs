@@ -0,0 +1,143 @@
+//! Monte-Carlo analysis of the Mandelbrot set: uniformly random points of the requested view are
+//! iterated, and the share that stays bounded estimates how much of the view the set covers.
+//! Points still bounded after max_iterations count as inside, so the estimate approaches the true
+//! area (about 1.50659 for the whole set) from above as max_iterations grows.
+
+use crate::fractals::buddhabrot::{DEFAULT_SAMPLES, DEFAULT_SEED};
+use crate::fractals::kernels::{mandelbrot_iterations, MandelbrotVariant};
+use crate::fractals::mandelbrot::MandelbrotSet;
+use crate::fractals::traits::Fractal;
+use crate::query::FractalQuery;
+use crate::utils::rng::Rng;
+use crate::utils::validation::validate_sample_budget;
+use crate::ErrorResponse;
+use axum::{
+    extract::Query,
+    http::StatusCode,
+    response::{IntoResponse, Response},
+};
+use rayon::prelude::*;
+use schemars::JsonSchema;
+use serde::Serialize;
+
+/// Independently seeded sample chunks; fixed so results don't depend on thread count
+const SAMPLE_CHUNKS: u64 = 64;
+
+/// Confidence level of the reported interval
+const CONFIDENCE_LEVEL: f64 = 0.95;
+
+/// Standard normal quantile for `CONFIDENCE_LEVEL`
+const Z_95: f64 = 1.959963984540054;
+
+#[derive(Serialize, JsonSchema)]
+pub struct AreaResponse {
+    fractal_type: String,
+    variant: String,
+    /// Plane region sampled: [x_min, x_max, y_min, y_max]
+    view: [f64; 4],
+    view_area: f64,
+    max_iterations: u32,
+    samples: u64,
+    seed: u64,
+    /// Samples still bounded after max_iterations
+    inside: u64,
+    /// Estimated area of the set within the view
+    area: f64,
+    standard_error: f64,
+    /// Wilson score interval for the area at `confidence_level`
+    confidence_interval: [f64; 2],
+    confidence_level: f64,
+}
+
+fn estimate_area(query: FractalQuery) -> Result<AreaResponse, String> {
+    let fractal_type = query.fractal_type();
+    if fractal_type != "mandelbrot" {
+        return Err(format!(
+            "Area estimates cover type=mandelbrot (any variant); type={} is not supported.",
+            fractal_type
+        ));
+    }
+    let fractal = MandelbrotSet;
+    let params = query.into_params();
+    fractal.validate_params(&params)?;
+
+    let variant = match &params.variant {
+        Some(name) => MandelbrotVariant::parse(name)?,
+        None => MandelbrotVariant::Classic,
+    };
+    let samples = params.samples.unwrap_or(DEFAULT_SAMPLES);
+    let seed = params.seed.unwrap_or(DEFAULT_SEED);
+    let max_iterations = params.max_iterations;
+    validate_sample_budget(samples, max_iterations)?;
+
+    // The same region the matching render shows
+    let bounds = fractal
+        .plane_view()
+        .expect("mandelbrot renders over the plane")
+        .bounds(&params);
+    let inside: u64 = (0..SAMPLE_CHUNKS)
+        .into_par_iter()
+        .map(|chunk| {
+            let mut rng = Rng::for_chunk(seed, chunk);
+            let chunk_samples =
+                samples / SAMPLE_CHUNKS + u64::from(chunk < samples % SAMPLE_CHUNKS);
+            (0..chunk_samples)
+                .filter(|_| {
+                    let cx = rng.range(bounds.x_min, bounds.x_max);
+                    let cy = rng.range(bounds.y_min, bounds.y_max);
+                    mandelbrot_iterations(variant, cx, cy, max_iterations) >= max_iterations
+                })
+                .count() as u64
+        })
+        .sum();
+
+    // Share of the view inside the set, with its binomial standard error and Wilson interval
+    let view_area = (bounds.x_max - bounds.x_min) * (bounds.y_max - bounds.y_min);
+    let n = samples as f64;
+    let share = inside as f64 / n;
+    let standard_error = (share * (1.0 - share) / n).sqrt();
+    let z2 = Z_95 * Z_95;
+    let center = (share + z2 / (2.0 * n)) / (1.0 + z2 / n);
+    let margin = Z_95 / (1.0 + z2 / n) * (share * (1.0 - share) / n + z2 / (4.0 * n * n)).sqrt();
+    // Exact at the ends, where rounding would leave the bound a hair off 0 or 1
+    let lower = if inside == 0 {
+        0.0
+    } else {
+        (center - margin).max(0.0)
+    };
+    let upper = if inside == samples {
+        1.0
+    } else {
+        (center + margin).min(1.0)
+    };
+
+    Ok(AreaResponse {
+        fractal_type,
+        variant: params.variant.unwrap_or_else(|| "classic".to_string()),
+        view: [bounds.x_min, bounds.x_max, bounds.y_min, bounds.y_max],
+        view_area,
+        max_iterations,
+        samples,
+        seed,
+        inside,
+        area: share * view_area,
+        standard_error: standard_error * view_area,
+        confidence_interval: [lower * view_area, upper * view_area],
+        confidence_level: CONFIDENCE_LEVEL,
+    })
+}
+
+// Monte-Carlo estimate of the set's area within the requested view
+pub async fn area(Query(query): Query<FractalQuery>) -> Response {
+    // Sampling is CPU-bound, keep it off the async workers
+    let result = tokio::task::spawn_blocking(move || estimate_area(query))
+        .await
+        .unwrap_or_else(|e| Err(format!("Area task failed: {}", e)));
+
+    match result {
+        Ok(response) => (StatusCode::OK, axum::Json(response)).into_response(),
+        Err(error) => {
+            (StatusCode::BAD_REQUEST, axum::Json(ErrorResponse { error })).into_response()
+        }
+    }
+}
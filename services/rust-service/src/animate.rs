@@ -0,0 +1,674 @@
+//! Animations. `/animate` returns a GIF of a Julia set morphing as c moves along a line or
+//! around a circle: every frame is rendered through the pipeline like a single image, then
+//! quantized to its own 256-color palette, with the buffers for quantizing and indexing
+//! allocated once and reused for every frame. `/animate/zoom` returns an APNG or animated WebP
+//! of any type zooming from one view to another, or with the `video` feature a WebM streamed
+//! as it encodes; its frames are rendered in parallel, at most `ANIMATION_CONCURRENCY`
+//! (default the CPU count) at once across all requests.
+
+use crate::config;
+use crate::fractals::traits::{default_validate_params, FractalParams};
+use crate::pipeline::{render, AppState, RenderError, RenderOptions};
+use crate::query::FractalQuery;
+use crate::rendering::encoder::EncodeOptions;
+use crate::rendering::png_encoder::{encode_apng_frame, ApngWriter, PngOptions};
+use crate::rendering::webm_encoder::VideoOptions;
+#[cfg(feature = "video")]
+use crate::rendering::webm_encoder::WebmEncoder;
+use crate::rendering::webp_encoder::{encode_animation_frame, AnimatedWebPWriter};
+use crate::utils::validation::{validate_animation, validate_julia_params};
+use axum::{
+    body::Body,
+    extract::{Query, State},
+    http::{header, HeaderMap},
+    response::{IntoResponse, Response},
+};
+use color_quant::NeuQuant;
+use gif::{Encoder, Frame, Repeat};
+use image::RgbImage;
+use schemars::JsonSchema;
+use serde::Deserialize;
+use std::borrow::Cow;
+use std::f64::consts::TAU;
+use std::sync::{Arc, OnceLock};
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+
+/// Radius of path=circle unless `radius` is given; around c = 0 it passes through the spirals
+/// and dendrites of the classic animation
+pub const DEFAULT_CIRCLE_RADIUS: f64 = 0.7885;
+
+/// NeuQuant learns from one pixel in this many; 10 is its recommended speed/quality balance
+const QUANTIZE_SAMPLING: i32 = 10;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum AnimationPath {
+    /// From julia_c_real/julia_c_imag to to_c_real/to_c_imag
+    #[default]
+    Line,
+    /// Once around the circle of `radius` centered on julia_c_real/julia_c_imag, so the GIF
+    /// loops without a jump
+    Circle,
+}
+
+/// Pace of a zoom animation over its frames
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Easing {
+    /// Constant speed
+    Linear,
+    /// Starts slowly
+    EaseIn,
+    /// Ends slowly
+    EaseOut,
+    /// Starts and ends slowly
+    #[default]
+    EaseInOut,
+}
+
+impl Easing {
+    /// Progress at time `t`, both from 0 to 1
+    fn apply(self, t: f64) -> f64 {
+        match self {
+            Easing::Linear => t,
+            Easing::EaseIn => t * t,
+            Easing::EaseOut => t * (2.0 - t),
+            Easing::EaseInOut => t * t * (3.0 - 2.0 * t),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ZoomFormat {
+    /// Animated PNG, lossless; viewers without APNG support show the first frame
+    #[default]
+    Apng,
+    /// Animated WebP, smaller and lossy unless lossless=true
+    Webp,
+    /// AV1 video in WebM, streamed as it encodes; only in builds with the `video` feature
+    Webm,
+}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct AnimateOptions {
+    path: Option<AnimationPath>,
+    /// Real part of c in the last frame of path=line
+    to_c_real: Option<f64>,
+    /// Imaginary part of c in the last frame of path=line
+    to_c_imag: Option<f64>,
+    /// Radius of path=circle (default 0.7885)
+    radius: Option<f64>,
+    /// Frames in the animation, 2-300 (default 36)
+    frames: Option<u32>,
+    /// Frames per second, 1-50 (default 12)
+    fps: Option<u32>,
+}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct ZoomAnimationOptions {
+    /// Center of the last frame (default center_x)
+    to_center_x: Option<f64>,
+    /// Center of the last frame (default center_y)
+    to_center_y: Option<f64>,
+    /// Zoom of the last frame; the first uses zoom
+    to_zoom: f64,
+    /// Frames in the animation, 2-300 (default 60)
+    frames: Option<u32>,
+    /// Frames per second, 1-50 (default 24)
+    fps: Option<u32>,
+    easing: Option<Easing>,
+    format: Option<ZoomFormat>,
+    /// Quality 1-100 for lossy format=webp (default 80)
+    quality: Option<u8>,
+    /// Exact pixels for format=webp
+    lossless: Option<bool>,
+    /// Frames from one keyframe to the next for format=webm, 1-300 (default 2 seconds' worth)
+    keyframe_interval: Option<u32>,
+    /// Target kilobits per second for format=webm, 100-50000 (default constant quality)
+    bitrate: Option<u32>,
+}
+
+/// Frames a zoom video starts ahead of the next one its encoder needs
+#[cfg(feature = "video")]
+const VIDEO_FRAME_WINDOW: usize = 16;
+
+/// Frames being rendered across every zoom animation, shared so concurrent requests queue
+/// for the same slots
+fn frame_slots() -> Arc<Semaphore> {
+    static SLOTS: OnceLock<Arc<Semaphore>> = OnceLock::new();
+    SLOTS
+        .get_or_init(|| {
+            let slots = config::var("ANIMATION_CONCURRENCY")
+                .and_then(|value| value.parse::<usize>().ok())
+                .filter(|slots| *slots > 0)
+                .unwrap_or_else(|| {
+                    std::thread::available_parallelism().map_or(4, |threads| threads.get())
+                });
+            Arc::new(Semaphore::new(slots))
+        })
+        .clone()
+}
+
+/// The view of each frame from `start` to `end`. Zoom changes geometrically, at a steady
+/// rate of magnification; the center moves in step with the shrinking view, so the end
+/// point slides steadily to the middle instead of leaving the frame on the way.
+fn zoom_path(
+    start: &FractalParams,
+    end: &FractalParams,
+    easing: Easing,
+    frames: u32,
+) -> Vec<FractalParams> {
+    let ratio = end.zoom / start.zoom;
+    (0..frames)
+        .map(|frame| {
+            let t = easing.apply(frame as f64 / (frames - 1) as f64);
+            let zoom = start.zoom * ratio.powf(t);
+            let travel = if (ratio - 1.0).abs() < 1e-12 {
+                t
+            } else {
+                (1.0 - start.zoom / zoom) / (1.0 - 1.0 / ratio)
+            };
+            FractalParams {
+                center_x: start.center_x + (end.center_x - start.center_x) * travel,
+                center_y: start.center_y + (end.center_y - start.center_y) * travel,
+                zoom,
+                ..start.clone()
+            }
+        })
+        .collect()
+}
+
+/// c for each frame; the path starts from (or circles) `start`
+fn c_path(
+    start: (f64, f64),
+    options: &AnimateOptions,
+    frames: u32,
+) -> Result<Vec<(f64, f64)>, String> {
+    let path: Vec<(f64, f64)> = match options.path.unwrap_or_default() {
+        AnimationPath::Line => {
+            let end = match (options.to_c_real, options.to_c_imag) {
+                (Some(c_real), Some(c_imag)) => (c_real, c_imag),
+                _ => return Err("to_c_real and to_c_imag are required for path=line".to_string()),
+            };
+            // Both ends are frames of their own
+            (0..frames)
+                .map(|frame| {
+                    let t = frame as f64 / (frames - 1) as f64;
+                    (
+                        start.0 + (end.0 - start.0) * t,
+                        start.1 + (end.1 - start.1) * t,
+                    )
+                })
+                .collect()
+        }
+        AnimationPath::Circle => {
+            let radius = options.radius.unwrap_or(DEFAULT_CIRCLE_RADIUS);
+            if !(radius > 0.0 && radius <= 2.0) {
+                return Err("Invalid radius. Must be greater than 0 and at most 2.".to_string());
+            }
+            // The frame after the last would be the first again
+            (0..frames)
+                .map(|frame| {
+                    let angle = TAU * frame as f64 / frames as f64;
+                    (
+                        start.0 + radius * angle.cos(),
+                        start.1 + radius * angle.sin(),
+                    )
+                })
+                .collect()
+        }
+    };
+
+    for &(c_real, c_imag) in &path {
+        validate_julia_params(c_real, c_imag)?;
+    }
+    Ok(path)
+}
+
+/// Appends frames to a looping GIF, reusing the same RGBA and index buffers for each
+struct GifWriter {
+    encoder: Encoder<Vec<u8>>,
+    /// Centiseconds each frame is shown
+    delay: u16,
+    rgba: Vec<u8>,
+    indices: Vec<u8>,
+}
+
+impl GifWriter {
+    fn new(width: u32, height: u32, fps: u32) -> Result<Self, String> {
+        let mut encoder = Encoder::new(Vec::new(), width as u16, height as u16, &[])
+            .map_err(|e| format!("Failed to start GIF: {}", e))?;
+        encoder
+            .set_repeat(Repeat::Infinite)
+            .map_err(|e| format!("Failed to start GIF: {}", e))?;
+
+        let pixels = width as usize * height as usize;
+        Ok(Self {
+            encoder,
+            // GIF delays count whole centiseconds, and viewers slow anything under 2 down
+            delay: (100.0 / fps as f64).round().max(2.0) as u16,
+            rgba: Vec::with_capacity(pixels * 4),
+            indices: Vec::with_capacity(pixels),
+        })
+    }
+
+    fn push(&mut self, img: &RgbImage) -> Result<(), String> {
+        self.rgba.clear();
+        for pixel in img.pixels() {
+            self.rgba
+                .extend_from_slice(&[pixel[0], pixel[1], pixel[2], 255]);
+        }
+        let quantizer = NeuQuant::new(QUANTIZE_SAMPLING, 256, &self.rgba);
+
+        self.indices.clear();
+        self.indices.extend(
+            self.rgba
+                .chunks_exact(4)
+                .map(|pixel| quantizer.index_of(pixel) as u8),
+        );
+
+        let frame = Frame {
+            width: img.width() as u16,
+            height: img.height() as u16,
+            delay: self.delay,
+            palette: Some(quantizer.color_map_rgb()),
+            buffer: Cow::Borrowed(&self.indices),
+            ..Frame::default()
+        };
+        self.encoder
+            .write_frame(&frame)
+            .map_err(|e| format!("Failed to encode GIF frame: {}", e))
+    }
+
+    fn finish(self) -> Result<Vec<u8>, String> {
+        self.encoder
+            .into_inner()
+            .map_err(|e| format!("Failed to finish GIF: {}", e))
+    }
+}
+
+/// Render every frame of the morph and encode the GIF; returns it with its frame count
+fn animate_julia(
+    state: &AppState,
+    query: FractalQuery,
+    options: &AnimateOptions,
+    render_options: &RenderOptions,
+) -> Result<(Vec<u8>, u32), RenderError> {
+    if query
+        .fractal_type
+        .as_deref()
+        .is_some_and(|fractal_type| !fractal_type.eq_ignore_ascii_case("julia"))
+    {
+        return Err(RenderError::BadRequest(
+            "Invalid type. Animation supports julia.".to_string(),
+        ));
+    }
+
+    let frames = options.frames.unwrap_or(36);
+    let fps = options.fps.unwrap_or(12);
+    let params = query.into_params();
+    default_validate_params(&params).map_err(RenderError::BadRequest)?;
+    validate_animation(
+        frames,
+        fps,
+        params.width,
+        params.height,
+        params.max_iterations,
+    )
+    .map_err(RenderError::BadRequest)?;
+
+    let start = (
+        params.julia_c_real.unwrap_or(0.0),
+        params.julia_c_imag.unwrap_or(0.0),
+    );
+    let path = c_path(start, options, frames).map_err(RenderError::BadRequest)?;
+
+    let mut gif =
+        GifWriter::new(params.width, params.height, fps).map_err(RenderError::Internal)?;
+    for (c_real, c_imag) in path {
+        let mut frame_params = params.clone();
+        frame_params.julia_c_real = Some(c_real);
+        frame_params.julia_c_imag = Some(c_imag);
+        let (img, _) = render(state, "julia", frame_params, render_options)?;
+        gif.push(&img).map_err(RenderError::Internal)?;
+    }
+
+    Ok((gif.finish().map_err(RenderError::Internal)?, frames))
+}
+
+// Animation endpoint: a GIF of a Julia set morphing as c moves
+pub async fn animate(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Query(query): Query<FractalQuery>,
+    Query(options): Query<AnimateOptions>,
+) -> Response {
+    let render_options = RenderOptions::billed_to(&headers);
+
+    // Rendering and quantizing are CPU-bound, keep them off the async workers
+    let result = tokio::task::spawn_blocking(move || {
+        animate_julia(&state, query, &options, &render_options)
+    })
+    .await
+    .unwrap_or_else(|e| {
+        Err(RenderError::Internal(format!(
+            "Animation task failed: {}",
+            e
+        )))
+    });
+
+    match result {
+        Ok((gif, frames)) => (
+            [
+                (header::CONTENT_TYPE, "image/gif".to_string()),
+                (
+                    header::HeaderName::from_static("x-animation-frames"),
+                    frames.to_string(),
+                ),
+            ],
+            gif,
+        )
+            .into_response(),
+        Err(e) => e.into_response(),
+    }
+}
+
+/// A validated zoom: the view of every frame and how fast they play
+struct ZoomPlan {
+    fractal_type: String,
+    views: Vec<FractalParams>,
+    fps: u32,
+}
+
+fn plan_zoom(query: FractalQuery, options: &ZoomAnimationOptions) -> Result<ZoomPlan, RenderError> {
+    let fractal_type = query.fractal_type();
+    let frames = options.frames.unwrap_or(60);
+    let fps = options.fps.unwrap_or(24);
+
+    let start = query.into_params();
+    let end = FractalParams {
+        center_x: options.to_center_x.unwrap_or(start.center_x),
+        center_y: options.to_center_y.unwrap_or(start.center_y),
+        zoom: options.to_zoom,
+        ..start.clone()
+    };
+    for view in [&start, &end] {
+        default_validate_params(view).map_err(RenderError::BadRequest)?;
+    }
+    validate_animation(frames, fps, start.width, start.height, start.max_iterations)
+        .map_err(RenderError::BadRequest)?;
+
+    Ok(ZoomPlan {
+        fractal_type,
+        views: zoom_path(&start, &end, options.easing.unwrap_or_default(), frames),
+        fps,
+    })
+}
+
+/// Render frame `index` of the zoom on `tasks` and pass it through `finish` on the same
+/// thread. The frame waits for one of the shared slots and completes tagged with its index.
+fn spawn_frame<T, F>(
+    tasks: &mut JoinSet<Result<(usize, T), RenderError>>,
+    state: Arc<AppState>,
+    plan: &ZoomPlan,
+    index: usize,
+    render_options: RenderOptions,
+    finish: F,
+) where
+    T: Send + 'static,
+    F: FnOnce(RgbImage) -> Result<T, String> + Send + 'static,
+{
+    let slots = frame_slots();
+    let (fractal_type, params) = (plan.fractal_type.clone(), plan.views[index].clone());
+    tasks.spawn(async move {
+        let _slot = slots
+            .acquire_owned()
+            .await
+            .map_err(|e| RenderError::Internal(format!("Frame slots closed: {}", e)))?;
+        // Rendering and encoding are CPU-bound, keep them off the async workers
+        let frame = tokio::task::spawn_blocking(move || {
+            let (img, _) = render(&state, &fractal_type, params, &render_options)?;
+            finish(img).map_err(RenderError::Internal)
+        })
+        .await
+        .unwrap_or_else(|e| Err(RenderError::Internal(format!("Frame task failed: {}", e))))?;
+        Ok((index, frame))
+    });
+}
+
+/// Render every frame of the zoom in parallel, see `spawn_frame`. Frames complete in any
+/// order; dropping the set cancels the ones still waiting for a slot.
+fn spawn_frames<T, F>(
+    state: Arc<AppState>,
+    plan: &ZoomPlan,
+    render_options: RenderOptions,
+    finish: F,
+) -> JoinSet<Result<(usize, T), RenderError>>
+where
+    T: Send + 'static,
+    F: Fn(RgbImage) -> Result<T, String> + Clone + Send + 'static,
+{
+    let mut tasks = JoinSet::new();
+    for index in 0..plan.views.len() {
+        let (state, render_options, finish) =
+            (state.clone(), render_options.clone(), finish.clone());
+        spawn_frame(&mut tasks, state, plan, index, render_options, finish);
+    }
+    tasks
+}
+
+fn frame_failed(e: tokio::task::JoinError) -> RenderError {
+    RenderError::Internal(format!("Frame task failed: {}", e))
+}
+
+/// Render and encode every frame of the zoom, in parallel, and assemble the animation
+async fn animate_zoom(
+    state: Arc<AppState>,
+    plan: ZoomPlan,
+    format: ZoomFormat,
+    options: &ZoomAnimationOptions,
+    render_options: RenderOptions,
+) -> Result<Vec<u8>, RenderError> {
+    let webp = EncodeOptions::from_params(None, None, options.quality, options.lossless)
+        .map_err(RenderError::BadRequest)?
+        .webp;
+    let (width, height) = (plan.views[0].width, plan.views[0].height);
+    let (frames, fps) = (plan.views.len(), plan.fps);
+
+    let mut tasks = spawn_frames(state, &plan, render_options, move |img| match format {
+        ZoomFormat::Webp => encode_animation_frame(&img, &webp),
+        _ => encode_apng_frame(&img, &PngOptions::default()),
+    });
+    let mut encoded = vec![Vec::new(); frames];
+    while let Some(finished) = tasks.join_next().await {
+        let (index, frame) = finished.unwrap_or_else(|e| Err(frame_failed(e)))?;
+        encoded[index] = frame;
+    }
+
+    let animation = match format {
+        ZoomFormat::Webp => {
+            let duration = (1000.0 / fps as f64).round() as u32;
+            let mut webp = AnimatedWebPWriter::new(width, height, duration);
+            for frame in &encoded {
+                webp.push(frame);
+            }
+            webp.finish()
+        }
+        _ => {
+            let mut apng = ApngWriter::new(width, height, frames as u32, (1, fps as u16));
+            for frame in &encoded {
+                apng.push(frame).map_err(RenderError::Internal)?;
+            }
+            apng.finish().map_err(RenderError::Internal)?
+        }
+    };
+    Ok(animation)
+}
+
+/// Render the zoom in parallel and stream it as WebM, encoding frames in order as they are
+/// ready. Errors before the first frame is encoded are answered with their status; later ones
+/// end the stream early.
+#[cfg(feature = "video")]
+async fn stream_zoom_video(
+    state: Arc<AppState>,
+    plan: ZoomPlan,
+    video: VideoOptions,
+    render_options: RenderOptions,
+) -> Response {
+    use futures::stream::{self, StreamExt};
+    use std::collections::BTreeMap;
+    use tokio::sync::mpsc;
+
+    let (width, height) = (plan.views[0].width, plan.views[0].height);
+    let frames = plan.views.len() as u32;
+    let mut encoder = match WebmEncoder::new(width, height, plan.fps, frames, &video) {
+        Ok(encoder) => encoder,
+        Err(e) => return RenderError::Internal(e).into_response(),
+    };
+
+    // Frames render in any order; hand them to the encoder in order. Only the frames within
+    // VIDEO_FRAME_WINDOW of the next one due are started, so at most that many are held
+    // while an earlier frame is still rendering.
+    let (frame_tx, mut frame_rx) = mpsc::channel::<Result<RgbImage, RenderError>>(4);
+    tokio::spawn(async move {
+        let mut tasks = JoinSet::new();
+        let mut pending = BTreeMap::new();
+        let (mut next, mut spawned) = (0, 0);
+        loop {
+            while spawned < plan.views.len() && spawned < next + VIDEO_FRAME_WINDOW {
+                let (state, render_options) = (state.clone(), render_options.clone());
+                spawn_frame(&mut tasks, state, &plan, spawned, render_options, Ok);
+                spawned += 1;
+            }
+            let Some(finished) = tasks.join_next().await else {
+                return;
+            };
+            match finished.unwrap_or_else(|e| Err(frame_failed(e))) {
+                Ok((index, img)) => {
+                    pending.insert(index, img);
+                    while let Some(img) = pending.remove(&next) {
+                        if frame_tx.send(Ok(img)).await.is_err() {
+                            return;
+                        }
+                        next += 1;
+                    }
+                }
+                Err(e) => {
+                    let _ = frame_tx.send(Err(e)).await;
+                    return;
+                }
+            }
+        }
+    });
+
+    // Encoding is CPU-bound, keep it off the async workers. It stops when the client goes
+    // away and the body drops the receiver.
+    let (bytes_tx, mut bytes_rx) = mpsc::channel::<Result<Vec<u8>, RenderError>>(4);
+    tokio::task::spawn_blocking(move || {
+        while let Some(frame) = frame_rx.blocking_recv() {
+            let bytes = frame.and_then(|img| encoder.push(&img).map_err(RenderError::Internal));
+            if bytes.as_ref().is_ok_and(Vec::is_empty) {
+                continue;
+            }
+            let failed = bytes.is_err();
+            if bytes_tx.blocking_send(bytes).is_err() || failed {
+                return;
+            }
+        }
+        let _ = bytes_tx.blocking_send(encoder.finish().map_err(RenderError::Internal));
+    });
+
+    let first = match bytes_rx.recv().await {
+        Some(Ok(bytes)) => bytes,
+        Some(Err(e)) => return e.into_response(),
+        None => return RenderError::Internal("Video encoder stopped".to_string()).into_response(),
+    };
+    let rest = stream::unfold(bytes_rx, |mut bytes_rx| async move {
+        let bytes = bytes_rx.recv().await?.map_err(|e| {
+            let message = e.message();
+            tracing::warn!("Zoom video ended early: {}", message);
+            std::io::Error::other(message)
+        });
+        Some((bytes, bytes_rx))
+    });
+    let body = Body::from_stream(stream::iter([Ok(first)]).chain(rest));
+    animation_response("video/webm", frames, body)
+}
+
+#[cfg(not(feature = "video"))]
+async fn stream_zoom_video(
+    _state: Arc<AppState>,
+    _plan: ZoomPlan,
+    _video: VideoOptions,
+    _render_options: RenderOptions,
+) -> Response {
+    use crate::rendering::webm_encoder::not_enabled;
+    use crate::ErrorResponse;
+    use axum::http::StatusCode;
+
+    let error = not_enabled();
+    (
+        StatusCode::NOT_IMPLEMENTED,
+        axum::Json(ErrorResponse { error }),
+    )
+        .into_response()
+}
+
+fn animation_response(content_type: &str, frames: u32, body: impl Into<Body>) -> Response {
+    (
+        [
+            (header::CONTENT_TYPE, content_type.to_string()),
+            (
+                header::HeaderName::from_static("x-animation-frames"),
+                frames.to_string(),
+            ),
+        ],
+        body.into(),
+    )
+        .into_response()
+}
+
+// Zoom animation endpoint: an APNG, animated WebP or WebM video from one view to another
+pub async fn zoom_animation(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Query(query): Query<FractalQuery>,
+    Query(options): Query<ZoomAnimationOptions>,
+) -> Response {
+    let render_options = RenderOptions::billed_to(&headers);
+    let format = options.format.unwrap_or_default();
+    if format != ZoomFormat::Webm
+        && (options.keyframe_interval.is_some() || options.bitrate.is_some())
+    {
+        return RenderError::BadRequest(
+            "keyframe_interval and bitrate only apply with format=webm.".to_string(),
+        )
+        .into_response();
+    }
+    let plan = match plan_zoom(query, &options) {
+        Ok(plan) => plan,
+        Err(e) => return e.into_response(),
+    };
+    let frames = plan.views.len() as u32;
+
+    let content_type = match format {
+        ZoomFormat::Apng => "image/apng",
+        ZoomFormat::Webp => "image/webp",
+        ZoomFormat::Webm => {
+            return match VideoOptions::from_params(
+                plan.fps,
+                options.keyframe_interval,
+                options.bitrate,
+            ) {
+                Ok(video) => stream_zoom_video(state, plan, video, render_options).await,
+                Err(error) => RenderError::BadRequest(error).into_response(),
+            };
+        }
+    };
+    match animate_zoom(state, plan, format, &options, render_options).await {
+        Ok(animation) => animation_response(content_type, frames, animation),
+        Err(e) => e.into_response(),
+    }
+}
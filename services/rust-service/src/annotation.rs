@@ -0,0 +1,354 @@
+//! Figure annotation (`annotate=true`): plane axes with tick labels for the rendered bounds,
+//! a palette legend with its iteration scale, and a parameter summary under the image.
+
+use crate::fractals::create_fractal;
+use crate::fractals::traits::{FractalParams, PlaneBounds};
+use crate::rendering::colors::{
+    normalized_to_color, ColorScheme, Coloring, PaletteCycle, DEFAULT_BOUNDARY_WIDTH,
+};
+use crate::rendering::compositor::{Canvas, Margins, Rect};
+use crate::rendering::text::{text_height, text_width};
+use image::RgbImage;
+
+const BACKGROUND: [u8; 3] = [255, 255, 255];
+const INK: [u8; 3] = [40, 40, 40];
+
+/// Outer padding, gap between a tick and its label, and tick length, at text scale 1
+const PADDING: u32 = 8;
+const GAP: u32 = 3;
+const TICK_LENGTH: u32 = 5;
+const LEGEND_BAR_WIDTH: u32 = 16;
+
+/// Types colored by escape iteration count, whose palette maps onto 0..max_iterations
+const ITERATION_COLORED: &[&str] = &[
+    "mandelbrot",
+    "julia",
+    "nova",
+    "magnet1",
+    "magnet2",
+    "custom",
+    "hybrid",
+];
+
+/// Tick positions and labels along one axis
+struct Axis {
+    name: &'static str,
+    min: f64,
+    max: f64,
+    ticks: Vec<(f64, String)>,
+}
+
+impl Axis {
+    /// Round-numbered ticks, as many as fit when each label needs `label_space` pixels
+    fn new(
+        name: &'static str,
+        min: f64,
+        max: f64,
+        length: u32,
+        label_space: impl Fn(&[(f64, String)]) -> u32,
+    ) -> Self {
+        let mut target = 10;
+        loop {
+            let ticks = nice_ticks(min, max, target as f64);
+            let spacing = length / ticks.len().max(1) as u32;
+            if target <= 2 || spacing >= label_space(&ticks) {
+                return Self {
+                    name,
+                    min,
+                    max,
+                    ticks,
+                };
+            }
+            target -= 1;
+        }
+    }
+
+    /// Offset in pixels from the `min` end of an axis `length` pixels long
+    fn offset(&self, value: f64, length: u32) -> u32 {
+        let fraction = (value - self.min) / (self.max - self.min);
+        ((fraction * length as f64) as u32).min(length.saturating_sub(1))
+    }
+
+    fn widest_label(&self, scale: u32) -> u32 {
+        widest(&self.ticks, scale)
+    }
+}
+
+fn widest<T>(labels: &[(T, String)], scale: u32) -> u32 {
+    labels
+        .iter()
+        .map(|(_, label)| text_width(label, scale))
+        .max()
+        .unwrap_or(0)
+}
+
+/// Ticks at multiples of 1, 2 or 5 times a power of ten, about `target` of them
+pub fn nice_ticks(min: f64, max: f64, target: f64) -> Vec<(f64, String)> {
+    let span = max - min;
+    if !(span.is_finite() && span > 0.0) {
+        return Vec::new();
+    }
+
+    let raw_step = span / target;
+    let magnitude = 10f64.powf(raw_step.log10().floor());
+    let step = magnitude
+        * match raw_step / magnitude {
+            n if n < 1.5 => 1.0,
+            n if n < 3.0 => 2.0,
+            n if n < 7.0 => 5.0,
+            _ => 10.0,
+        };
+    let decimals = (-step.log10().floor()).clamp(0.0, 15.0) as usize;
+
+    let mut ticks = Vec::new();
+    let mut index = (min / step).ceil();
+    while index * step <= max + step * 1e-9 {
+        let value = index * step;
+        // Avoid printing "-0.00" for the tick at zero
+        let shown = if value.abs() < step * 1e-9 {
+            0.0
+        } else {
+            value
+        };
+        ticks.push((value, format!("{:.*}", decimals, shown)));
+        index += 1.0;
+    }
+    ticks
+}
+
+/// "key=value" pairs for every parameter that is set, fractal type first
+pub fn parameter_summary(fractal_type: &str, params: &FractalParams) -> Vec<String> {
+    let mut entries = vec![format!("type={}", fractal_type)];
+    if let Ok(serde_json::Value::Object(fields)) = serde_json::to_value(params) {
+        for (key, value) in fields {
+            match value {
+                serde_json::Value::Null => {}
+                serde_json::Value::String(text) => entries.push(format!("{}={}", key, text)),
+                other => entries.push(format!("{}={}", key, other)),
+            }
+        }
+    }
+    entries
+}
+
+/// Greedily pack entries into lines no wider than `max_width`
+fn wrap(entries: &[String], scale: u32, max_width: u32) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    for entry in entries {
+        match lines.last_mut() {
+            Some(line) if text_width(&format!("{}  {}", line, entry), scale) <= max_width => {
+                line.push_str("  ");
+                line.push_str(entry);
+            }
+            _ => lines.push(entry.clone()),
+        }
+    }
+    lines
+}
+
+/// The rendered image framed as a figure
+pub fn annotate(img: &RgbImage, fractal_type: &str, params: &FractalParams) -> RgbImage {
+    let scale = if img.width().min(img.height()) >= 600 {
+        2
+    } else {
+        1
+    };
+    let padding = PADDING * scale;
+    let gap = GAP * scale;
+    let tick = TICK_LENGTH * scale;
+    let line_height = text_height(scale) + gap;
+
+    let view = create_fractal(fractal_type).and_then(|fractal| fractal.plane_view());
+    let axes = view.map(|view| {
+        let PlaneBounds {
+            x_min,
+            x_max,
+            y_min,
+            y_max,
+        } = view.bounds(params);
+        (
+            Axis::new(view.axes.0, x_min, x_max, img.width(), |ticks| {
+                widest(ticks, scale) + 4 * gap
+            }),
+            Axis::new(view.axes.1, y_min, y_max, img.height(), |_| 3 * line_height),
+        )
+    });
+    // Histogram coloring places colors by the share of escaping pixels, orbit traps by
+    // closeness to the trap and distance coloring by pixels from the boundary, not by iterations
+    let coloring = Coloring::from_param(params.coloring.as_deref()).unwrap_or_default();
+    let legend_title = match coloring {
+        Coloring::Linear | Coloring::BinaryDecomposition => "iterations",
+        Coloring::Histogram => "escaped",
+        Coloring::OrbitTrap => "trap",
+        Coloring::Distance => "boundary",
+        Coloring::StripeAverage => "stripes",
+        Coloring::AtomDomain | Coloring::Period => "period",
+    };
+    let legend = ITERATION_COLORED
+        .contains(&fractal_type)
+        .then(|| match coloring {
+            Coloring::Linear | Coloring::BinaryDecomposition => {
+                let max = params.max_iterations;
+                [
+                    (1.0, max.to_string()),
+                    (0.5, (max / 2).to_string()),
+                    (0.0, "0".to_string()),
+                ]
+            }
+            Coloring::Histogram => [
+                (1.0, "100%".to_string()),
+                (0.5, "50%".to_string()),
+                (0.0, "0%".to_string()),
+            ],
+            Coloring::OrbitTrap => [
+                (1.0, "near".to_string()),
+                (0.5, String::new()),
+                (0.0, "far".to_string()),
+            ],
+            Coloring::StripeAverage => [
+                (1.0, "1".to_string()),
+                (0.5, "0.5".to_string()),
+                (0.0, "0".to_string()),
+            ],
+            // Periods step around the palette by the golden ratio rather than along it
+            Coloring::AtomDomain | Coloring::Period => [
+                (0.618, "2".to_string()),
+                (0.236, "3".to_string()),
+                (0.0, "1".to_string()),
+            ],
+            // The palette fades with the square of the distance past one boundary width
+            Coloring::Distance => {
+                let width = params.boundary_width.unwrap_or(DEFAULT_BOUNDARY_WIDTH);
+                [
+                    (1.0, format!("{}px", width)),
+                    (0.25, format!("{}px", 2.0 * width)),
+                    (0.0, "far".to_string()),
+                ]
+            }
+        });
+
+    // Reserve room for labels on every side they appear
+    let mut margins = Margins {
+        left: padding,
+        top: padding,
+        right: padding,
+        bottom: padding,
+    };
+    if let Some((x_axis, y_axis)) = &axes {
+        let half_x_label = x_axis.widest_label(scale) / 2;
+        margins.left = (padding + y_axis.widest_label(scale) + gap + tick).max(half_x_label);
+        margins.right = margins.right.max(half_x_label + padding);
+        // Room for the vertical axis name, clear of the topmost tick label
+        margins.top += line_height + text_height(scale) / 2;
+        margins.bottom += tick + gap + 2 * line_height;
+    }
+    if let Some(labels) = &legend {
+        let legend_width = (LEGEND_BAR_WIDTH * scale + gap + widest(labels, scale))
+            .max(text_width(legend_title, scale));
+        margins.right = margins.right.max(2 * padding + legend_width + padding);
+        margins.top = margins.top.max(padding + line_height);
+    }
+    let canvas_width = margins.left + img.width() + margins.right;
+    let summary = wrap(
+        &parameter_summary(fractal_type, params),
+        scale,
+        canvas_width - 2 * padding,
+    );
+    margins.bottom += gap + summary.len() as u32 * line_height;
+
+    let (mut canvas, rect) = Canvas::with_margins(img, margins, BACKGROUND);
+    canvas.frame_rect(rect, 1, INK);
+    let bottom = rect.y + rect.height;
+
+    if let Some((x_axis, y_axis)) = &axes {
+        let label_y = bottom + 1 + tick + gap;
+        for (value, label) in &x_axis.ticks {
+            let x = rect.x + x_axis.offset(*value, rect.width);
+            canvas.fill_rect(Rect::new(x, bottom + 1, 1, tick), INK);
+            let label_x = x.saturating_sub(text_width(label, scale) / 2);
+            canvas.text(label_x, label_y, label, scale, INK);
+        }
+        let name_x = rect.x + rect.width.saturating_sub(text_width(x_axis.name, scale)) / 2;
+        canvas.text(name_x, label_y + line_height, x_axis.name, scale, INK);
+
+        let tick_x = rect.x - 1 - tick;
+        for (value, label) in &y_axis.ticks {
+            // The vertical axis points up
+            let y = bottom - 1 - y_axis.offset(*value, rect.height);
+            canvas.fill_rect(Rect::new(tick_x, y, tick, 1), INK);
+            let label_x = tick_x.saturating_sub(gap + text_width(label, scale));
+            let label_y = y.saturating_sub(text_height(scale) / 2);
+            canvas.text(label_x, label_y, label, scale, INK);
+        }
+        canvas.text(padding, padding, y_axis.name, scale, INK);
+    }
+
+    if let Some(labels) = &legend {
+        let scheme = ColorScheme::from_str(params.color_scheme.as_deref().unwrap_or("default"));
+        let bar = Rect::new(
+            rect.x + rect.width + 2 * padding,
+            rect.y,
+            LEGEND_BAR_WIDTH * scale,
+            rect.height,
+        );
+        // The iteration colorings' bar shows the palette as shifted and repeated over the range
+        let cycle = PaletteCycle::from_params(params).unwrap_or_default();
+        for row in 0..bar.height {
+            let normalized = 1.0 - row as f64 / (bar.height.max(2) - 1) as f64;
+            let position = match coloring {
+                Coloring::Linear | Coloring::BinaryDecomposition => {
+                    let max = params.max_iterations;
+                    cycle.position(normalized * max as f64, max)
+                }
+                Coloring::Histogram => cycle.wrap(normalized),
+                _ => normalized,
+            };
+            let color = normalized_to_color(position, &scheme);
+            canvas.fill_rect(Rect::new(bar.x, bar.y + row, bar.width, 1), color);
+        }
+        canvas.frame_rect(bar, 1, INK);
+        canvas.text(bar.x, padding, legend_title, scale, INK);
+
+        for (position, label) in labels {
+            let y = bar.y + ((1.0 - position) * (bar.height - 1) as f64) as u32;
+            let label_y = y
+                .saturating_sub(text_height(scale) / 2)
+                .clamp(bar.y, bottom.saturating_sub(text_height(scale)).max(bar.y));
+            canvas.text(bar.x + bar.width + gap, label_y, label, scale, INK);
+        }
+    }
+
+    let summary_y = canvas.height() - padding - summary.len() as u32 * line_height;
+    for (index, line) in summary.iter().enumerate() {
+        canvas.text(
+            padding,
+            summary_y + index as u32 * line_height,
+            line,
+            scale,
+            INK,
+        );
+    }
+
+    canvas.into_image()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn annotates_images_shorter_than_their_labels() {
+        for (width, height) in (1..=8).flat_map(|height| [(2, height), (300, height)]) {
+            for fractal_type in ["mandelbrot", "sierpinski"] {
+                let params = FractalParams {
+                    width,
+                    height,
+                    ..FractalParams::default()
+                };
+                let img = RgbImage::new(width, height);
+                let annotated = annotate(&img, fractal_type, &params);
+                assert!(annotated.width() >= width && annotated.height() >= height);
+            }
+        }
+    }
+}
@@ -0,0 +1,136 @@
+//! Version 2 of the render API. Views are given as explicit plane bounds instead of
+//! center + zoom, and type-specific parameters are grouped under `params`. Requests are
+//! adapted onto the same internal parameters that v1 query strings use.
+
+use crate::defaults;
+use crate::fractals::create_fractal;
+use crate::fractals::traits::PlaneBounds;
+use crate::pipeline::AppState;
+use crate::query::FractalQuery;
+use crate::{generate_fractal, ErrorResponse, OutputOptions};
+use axum::{
+    extract::{Query, State},
+    http::{HeaderMap, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+};
+use schemars::JsonSchema;
+use serde::Deserialize;
+use std::sync::Arc;
+
+/// Response header reporting the plane region actually rendered
+const VIEW_BOUNDS_HEADER: &str = "X-View-Bounds";
+
+#[derive(Clone, Copy, Debug, Deserialize, JsonSchema)]
+pub struct Bounds {
+    pub x_min: f64,
+    pub x_max: f64,
+    pub y_min: f64,
+    pub y_max: f64,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct FractalRequestV2 {
+    #[serde(rename = "type")]
+    pub fractal_type: String,
+    pub width: Option<u32>,
+    /// Derived from width and the bounds' aspect ratio when omitted
+    pub height: Option<u32>,
+    /// Plane region to show; the whole region stays visible if the aspect ratios differ
+    pub bounds: Option<Bounds>,
+    pub max_iterations: Option<u32>,
+    pub color_scheme: Option<String>,
+    /// Type-specific parameters, named as in v1 (e.g. julia_c_real, samples, seed)
+    #[serde(default)]
+    pub params: FractalQuery,
+}
+
+impl FractalRequestV2 {
+    /// Adapt onto the internal center + zoom parameters
+    pub fn into_query(self) -> Result<FractalQuery, String> {
+        let params = self.params;
+        if params.zoom.is_some() || params.center_x.is_some() || params.center_y.is_some() {
+            return Err(
+                "zoom, center_x and center_y are v1 parameters; use bounds in v2.".to_string(),
+            );
+        }
+
+        let mut query = FractalQuery {
+            fractal_type: Some(self.fractal_type),
+            width: self.width.or(params.width),
+            height: self.height.or(params.height),
+            max_iterations: self.max_iterations.or(params.max_iterations),
+            color_scheme: self.color_scheme.or(params.color_scheme),
+            ..params
+        };
+
+        let Some(bounds) = self.bounds else {
+            return Ok(query);
+        };
+
+        let fractal_type = query.fractal_type();
+        let view = create_fractal(&fractal_type)
+            .ok_or_else(|| format!("Unknown fractal type: {}", fractal_type))?
+            .plane_view()
+            .ok_or_else(|| format!("bounds are not supported for type={}", fractal_type))?;
+
+        let span_x = bounds.x_max - bounds.x_min;
+        let span_y = bounds.y_max - bounds.y_min;
+        if !(span_x.is_finite() && span_y.is_finite() && span_x > 0.0 && span_y > 0.0) {
+            return Err(
+                "Invalid bounds. x_max must exceed x_min and y_max must exceed y_min.".to_string(),
+            );
+        }
+
+        let width = query.width.unwrap_or(defaults::current().width);
+        let height = query
+            .height
+            .unwrap_or_else(|| ((width as f64 * span_y / span_x).round() as u32).max(1));
+        let aspect_ratio = width as f64 / height as f64;
+
+        // Fit whichever axis is tighter so the whole region is visible
+        let half_height = (span_y / 2.0).max(span_x / 2.0 / aspect_ratio);
+        query.width = Some(width);
+        query.height = Some(height);
+        query.zoom = Some(view.half_height / half_height);
+        query.center_x = Some((bounds.x_min + bounds.x_max) / 2.0 - view.origin.0);
+        query.center_y = Some((bounds.y_min + bounds.y_max) / 2.0 - view.origin.1);
+
+        Ok(query)
+    }
+}
+
+/// Plane region covered by the (v1) parameters, for types rendered over a region
+fn view_bounds(query: &FractalQuery) -> Option<PlaneBounds> {
+    let view = create_fractal(&query.fractal_type())?.plane_view()?;
+    Some(view.bounds(&query.clone().into_params()))
+}
+
+// v2 render endpoint: JSON body with bounds, PNG response
+pub async fn generate_fractal_v2(
+    state: State<Arc<AppState>>,
+    headers: HeaderMap,
+    output: Query<OutputOptions>,
+    axum::Json(request): axum::Json<FractalRequestV2>,
+) -> Response {
+    let query = match request.into_query() {
+        Ok(query) => query,
+        Err(error) => {
+            return (StatusCode::BAD_REQUEST, axum::Json(ErrorResponse { error })).into_response();
+        }
+    };
+
+    let bounds = view_bounds(&query);
+    let mut response = generate_fractal(state, headers, Query(query), output).await;
+
+    if let Some(bounds) = bounds.filter(|_| response.status().is_success()) {
+        let value = format!(
+            "{},{},{},{}",
+            bounds.x_min, bounds.x_max, bounds.y_min, bounds.y_max
+        );
+        if let Ok(value) = HeaderValue::from_str(&value) {
+            response.headers_mut().insert(VIEW_BOUNDS_HEADER, value);
+        }
+    }
+
+    response
+}
@@ -0,0 +1,73 @@
+//! Small audio synthesis toolkit for sonification: sequences of sine tones with short fades,
+//! encoded as 16-bit mono PCM WAV.
+
+use std::f64::consts::TAU;
+
+/// Notes fade in and out over this long so their boundaries don't click
+const FADE_SECONDS: f64 = 0.005;
+
+/// Peak amplitude as a fraction of full scale
+const AMPLITUDE: f64 = 0.6;
+
+/// Frequency a `fraction` (0 to 1) of the way from `min` to `max` on a logarithmic scale, so
+/// equal steps sound like equal intervals
+pub fn pitch(fraction: f64, min: f64, max: f64) -> f64 {
+    min * (max / min).powf(fraction.clamp(0.0, 1.0))
+}
+
+/// Equal-length notes in sequence; `None` is a rest
+pub fn synthesize(notes: &[Option<f64>], note_seconds: f64, sample_rate: u32) -> Vec<i16> {
+    let note_samples = (note_seconds * sample_rate as f64).round().max(1.0) as usize;
+    let fade = ((FADE_SECONDS * sample_rate as f64) as usize).clamp(1, note_samples.div_ceil(2));
+    let mut samples = Vec::with_capacity(notes.len() * note_samples);
+
+    // The phase carries over between notes so consecutive tones join smoothly
+    let mut phase: f64 = 0.0;
+    for note in notes {
+        let Some(frequency) = note else {
+            samples.extend(std::iter::repeat_n(0, note_samples));
+            continue;
+        };
+        let step = TAU * frequency / sample_rate as f64;
+        for i in 0..note_samples {
+            let edge = i.min(note_samples - 1 - i);
+            let envelope = (edge as f64 / fade as f64).min(1.0);
+            samples.push((phase.sin() * envelope * AMPLITUDE * i16::MAX as f64) as i16);
+            phase = (phase + step) % TAU;
+        }
+    }
+
+    samples
+}
+
+/// RIFF/WAVE container for mono 16-bit samples
+pub fn encode_wav(samples: &[i16], sample_rate: u32) -> Vec<u8> {
+    const CHANNELS: u16 = 1;
+    const BYTES_PER_SAMPLE: u16 = 2;
+
+    let data_len = (samples.len() * BYTES_PER_SAMPLE as usize) as u32;
+    let mut wav = Vec::with_capacity(44 + data_len as usize);
+
+    wav.extend_from_slice(b"RIFF");
+    wav.extend_from_slice(&(36 + data_len).to_le_bytes());
+    wav.extend_from_slice(b"WAVE");
+
+    // Format chunk: uncompressed PCM
+    wav.extend_from_slice(b"fmt ");
+    wav.extend_from_slice(&16u32.to_le_bytes());
+    wav.extend_from_slice(&1u16.to_le_bytes());
+    wav.extend_from_slice(&CHANNELS.to_le_bytes());
+    wav.extend_from_slice(&sample_rate.to_le_bytes());
+    let block_align = CHANNELS * BYTES_PER_SAMPLE;
+    wav.extend_from_slice(&(sample_rate * block_align as u32).to_le_bytes());
+    wav.extend_from_slice(&block_align.to_le_bytes());
+    wav.extend_from_slice(&(BYTES_PER_SAMPLE * 8).to_le_bytes());
+
+    wav.extend_from_slice(b"data");
+    wav.extend_from_slice(&data_len.to_le_bytes());
+    for sample in samples {
+        wav.extend_from_slice(&sample.to_le_bytes());
+    }
+
+    wav
+}
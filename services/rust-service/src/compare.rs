@@ -0,0 +1,382 @@
+//! Pixel comparison against an image rendered elsewhere (another backend, an older release),
+//! so a client can check that switching renderers won't make the picture jump. The same view
+//! can also be rendered on two of this service's own backends and returned side by side, to
+//! validate a backend before making it the default.
+
+use crate::fractals::kernels::Kernel;
+use crate::fractals::traits::FractalParams;
+use crate::pipeline::{render, AppState, RenderOptions};
+use crate::query::FractalQuery;
+use crate::rendering::colors::{normalized_to_color, ColorScheme};
+use crate::rendering::encoder::{create_image_response, encode_image, EncodeOptions, OutputFormat};
+use crate::tuning;
+use crate::utils::validation::validate_dimensions;
+use crate::ErrorResponse;
+use axum::{
+    extract::{Query, State},
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use image::{ImageBuffer, Rgb, RgbImage};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::io::Cursor;
+use std::sync::Arc;
+
+/// Gap between the panels and above the heat strip, in pixels
+const PANEL_GAP: u32 = 4;
+
+/// Height of the difference heat strip under each panel
+const STRIP_HEIGHT: u32 = 16;
+
+const GAP_COLOR: [u8; 3] = [48, 48, 48];
+
+#[derive(Deserialize, JsonSchema)]
+pub struct CompareRequest {
+    /// Base64-encoded PNG (or a `data:image/png;base64,` URI) to compare against
+    reference_png: String,
+    /// Largest per-channel difference still counted as equal (default 0, pixel-perfect)
+    tolerance: Option<u8>,
+    /// Also return an image of where the renders differ
+    include_diff: Option<bool>,
+    /// Render parameters; width and height default to the reference's size
+    #[serde(flatten)]
+    query: FractalQuery,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct CompareResponse {
+    /// No channel of any pixel differs by more than the tolerance
+    matches: bool,
+    width: u32,
+    height: u32,
+    /// Largest per-channel difference, 0-255
+    max_deviation: u8,
+    /// First pixel (x, y) with the largest difference
+    max_deviation_at: Option<[u32; 2]>,
+    /// Mean per-channel difference over the whole image
+    mean_deviation: f64,
+    /// Pixels with a channel differing by more than the tolerance
+    differing_pixels: u64,
+    /// PNG data URI, white where the renders differ beyond the tolerance and dark grey
+    /// (scaled by the difference) where they differ within it
+    diff_png: Option<String>,
+}
+
+/// Per-pixel statistics of `actual` against `reference`, which have the same size
+fn compare(reference: &RgbImage, actual: &RgbImage, tolerance: u8) -> (CompareResponse, RgbImage) {
+    let (width, height) = actual.dimensions();
+    let mut diff: RgbImage = ImageBuffer::new(width, height);
+    let mut max_deviation = 0;
+    let mut max_deviation_at = None;
+    let mut total = 0u64;
+    let mut differing_pixels = 0;
+
+    for ((x, y, expected), got) in reference.enumerate_pixels().zip(actual.pixels()) {
+        let channels: [u8; 3] = std::array::from_fn(|c| expected[c].abs_diff(got[c]));
+        let deviation = channels.into_iter().max().unwrap_or(0);
+        total += channels.into_iter().map(u64::from).sum::<u64>();
+
+        if deviation > max_deviation {
+            max_deviation = deviation;
+            max_deviation_at = Some([x, y]);
+        }
+        let shade = if deviation > tolerance {
+            differing_pixels += 1;
+            255
+        } else {
+            // Differences within the tolerance stay visible but dim
+            (u32::from(deviation) * 96 / u32::from(tolerance.max(1))) as u8
+        };
+        diff.put_pixel(x, y, Rgb([shade; 3]));
+    }
+
+    let response = CompareResponse {
+        matches: differing_pixels == 0,
+        width,
+        height,
+        max_deviation,
+        max_deviation_at,
+        mean_deviation: total as f64 / (width as f64 * height as f64 * 3.0),
+        differing_pixels,
+        diff_png: None,
+    };
+    (response, diff)
+}
+
+fn decode_reference(encoded: &str) -> Result<RgbImage, String> {
+    let encoded = encoded
+        .strip_prefix("data:image/png;base64,")
+        .unwrap_or(encoded);
+    let bytes = STANDARD
+        .decode(encoded.trim())
+        .map_err(|e| format!("Invalid reference_png. Expected base64: {}", e))?;
+    let invalid =
+        |e: image::ImageError| format!("Invalid reference_png. Cannot decode image: {}", e);
+
+    // Check the size from the header before decoding, like any other render size
+    let reader = || {
+        image::io::Reader::new(Cursor::new(&bytes))
+            .with_guessed_format()
+            .map_err(|e| format!("Invalid reference_png: {}", e))
+    };
+    let (width, height) = reader()?.into_dimensions().map_err(invalid)?;
+    validate_dimensions(width, height)?;
+    Ok(reader()?.decode().map_err(invalid)?.to_rgb8())
+}
+
+// Render the parameters and compare the result with the reference image
+pub async fn compare_render(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    axum::Json(request): axum::Json<CompareRequest>,
+) -> Response {
+    let options = RenderOptions::billed_to(&headers);
+
+    // Rendering is CPU-bound, keep it off the async workers
+    let result = tokio::task::spawn_blocking(move || {
+        let bad_request = |error: String| (StatusCode::BAD_REQUEST, error);
+        let reference = decode_reference(&request.reference_png).map_err(bad_request)?;
+
+        let mut query = request.query;
+        query.width = query.width.or(Some(reference.width()));
+        query.height = query.height.or(Some(reference.height()));
+        let (img, _) = render(&state, &query.fractal_type(), query.into_params(), &options)
+            .map_err(|e| (e.status(), e.message()))?;
+        if img.dimensions() != reference.dimensions() {
+            return Err(bad_request(format!(
+                "Reference is {}x{} but the render is {}x{}.",
+                reference.width(),
+                reference.height(),
+                img.width(),
+                img.height()
+            )));
+        }
+
+        let (mut response, diff) = compare(&reference, &img, request.tolerance.unwrap_or(0));
+        if request.include_diff.unwrap_or(false) {
+            let png = encode_image(&diff, OutputFormat::Png, &EncodeOptions::default())
+                .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?;
+            response.diff_png = Some(format!("data:image/png;base64,{}", STANDARD.encode(png)));
+        }
+        Ok(response)
+    })
+    .await
+    .unwrap_or_else(|e| {
+        Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Comparison task failed: {}", e),
+        ))
+    });
+
+    match result {
+        Ok(response) => (StatusCode::OK, axum::Json(response)).into_response(),
+        Err((status, error)) => (status, axum::Json(ErrorResponse { error })).into_response(),
+    }
+}
+
+/// One way of rendering a view, for comparison against another
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Backend {
+    /// An escape-time row kernel (mandelbrot only)
+    Kernel(Kernel),
+    /// Coarse first pass refined near the boundary (see `adaptive`)
+    Adaptive,
+}
+
+impl Backend {
+    /// Names accepted by `parse`
+    const NAMES: [&'static str; 4] = ["scalar", "simd4", "simd8", "adaptive"];
+
+    /// Types the adaptive pass changes; the others ignore `adaptive`
+    const ADAPTIVE_TYPES: [&'static str; 6] = [
+        "mandelbrot",
+        "julia",
+        "magnet1",
+        "magnet2",
+        "nova",
+        "hybrid",
+    ];
+
+    fn parse(name: &str, param: &str) -> Result<Self, String> {
+        if name.eq_ignore_ascii_case("adaptive") {
+            return Ok(Backend::Adaptive);
+        }
+        Kernel::from_str(name).map(Backend::Kernel).ok_or_else(|| {
+            format!(
+                "Invalid {}. Must be one of: {}.",
+                param,
+                Self::NAMES.join(", ")
+            )
+        })
+    }
+
+    fn name(self) -> String {
+        match self {
+            Backend::Kernel(kernel) => format!("{:?}", kernel).to_lowercase(),
+            Backend::Adaptive => "adaptive".to_string(),
+        }
+    }
+
+    /// Rendering `fractal_type` on this backend differs from the others
+    fn check_applies(self, fractal_type: &str) -> Result<(), String> {
+        let applies = match self {
+            Backend::Kernel(_) => fractal_type == "mandelbrot",
+            Backend::Adaptive => Self::ADAPTIVE_TYPES.contains(&fractal_type),
+        };
+        if !applies {
+            return Err(format!(
+                "The {} backend doesn't apply to type={}.",
+                self.name(),
+                fractal_type
+            ));
+        }
+        Ok(())
+    }
+
+    fn apply(self, params: &mut FractalParams) {
+        match self {
+            Backend::Kernel(kernel) => {
+                params.kernel = Some(kernel);
+                params.adaptive = Some(false);
+            }
+            Backend::Adaptive => params.adaptive = Some(true),
+        }
+    }
+}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct BackendComparisonQuery {
+    /// Backend for the left panel: scalar (default), simd4, simd8 or adaptive
+    backend_a: Option<String>,
+    /// Backend for the right panel (default: the kernel picked at startup)
+    backend_b: Option<String>,
+    /// Largest per-channel difference still counted as equal (default 0, pixel-perfect)
+    tolerance: Option<u8>,
+}
+
+/// The two panels side by side, each over a heat strip of the largest difference in its
+/// columns: black where the backends agree, brightest at the largest difference in the image
+fn composite(left: &RgbImage, right: &RgbImage) -> RgbImage {
+    let (width, height) = left.dimensions();
+    let column_deviation: Vec<u8> = (0..width)
+        .map(|x| {
+            (0..height)
+                .flat_map(|y| {
+                    let (a, b) = (left.get_pixel(x, y), right.get_pixel(x, y));
+                    (0..3).map(move |c| a[c].abs_diff(b[c]))
+                })
+                .max()
+                .unwrap_or(0)
+        })
+        .collect();
+    let largest = column_deviation.iter().copied().max().unwrap_or(0);
+
+    let strip_top = height + PANEL_GAP;
+    ImageBuffer::from_fn(width * 2 + PANEL_GAP, strip_top + STRIP_HEIGHT, |x, y| {
+        let (panel, column) = match x {
+            x if x < width => (left, x),
+            x if x >= width + PANEL_GAP => (right, x - width - PANEL_GAP),
+            _ => return Rgb(GAP_COLOR),
+        };
+        if y < height {
+            return *panel.get_pixel(column, y);
+        }
+        if y < strip_top {
+            return Rgb(GAP_COLOR);
+        }
+        match column_deviation[column as usize] {
+            0 => Rgb([0, 0, 0]),
+            deviation => Rgb(normalized_to_color(
+                deviation as f64 / largest as f64,
+                &ColorScheme::Inferno,
+            )),
+        }
+    })
+}
+
+// Render one view on two backends and return them side by side with a difference heat strip
+pub async fn compare_backends(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Query(query): Query<FractalQuery>,
+    Query(backends): Query<BackendComparisonQuery>,
+) -> Response {
+    let options = RenderOptions::billed_to(&headers);
+
+    // Rendering is CPU-bound, keep it off the async workers
+    let result = tokio::task::spawn_blocking(move || {
+        let bad_request = |error: String| (StatusCode::BAD_REQUEST, error);
+        let fractal_type = query.fractal_type();
+        let backend_a = match backends.backend_a.as_deref() {
+            Some(name) => Backend::parse(name, "backend_a").map_err(bad_request)?,
+            None => Backend::Kernel(Kernel::Scalar),
+        };
+        let backend_b = match backends.backend_b.as_deref() {
+            Some(name) => Backend::parse(name, "backend_b").map_err(bad_request)?,
+            None => Backend::Kernel(tuning::current().kernel),
+        };
+        for backend in [backend_a, backend_b] {
+            backend.check_applies(&fractal_type).map_err(bad_request)?;
+        }
+
+        let params = query.into_params();
+        let mut panels = Vec::with_capacity(2);
+        for backend in [backend_a, backend_b] {
+            let mut params = params.clone();
+            backend.apply(&mut params);
+            let (img, metadata) = render(&state, &fractal_type, params, &options)
+                .map_err(|e| (e.status(), e.message()))?;
+            panels.push((img, metadata.render_time));
+        }
+        let [(left, left_time), (right, right_time)] =
+            <[_; 2]>::try_from(panels).expect("two backends rendered");
+
+        let (stats, _) = compare(&left, &right, backends.tolerance.unwrap_or(0));
+        let png = encode_image(
+            &composite(&left, &right),
+            OutputFormat::Png,
+            &EncodeOptions::default(),
+        )
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?;
+        let response_headers = vec![
+            ("X-Backend-A".to_string(), backend_a.name()),
+            ("X-Backend-B".to_string(), backend_b.name()),
+            (
+                "X-Backend-A-Time-Ms".to_string(),
+                left_time.as_millis().to_string(),
+            ),
+            (
+                "X-Backend-B-Time-Ms".to_string(),
+                right_time.as_millis().to_string(),
+            ),
+            ("X-Backends-Match".to_string(), stats.matches.to_string()),
+            (
+                "X-Max-Deviation".to_string(),
+                stats.max_deviation.to_string(),
+            ),
+            (
+                "X-Differing-Pixels".to_string(),
+                stats.differing_pixels.to_string(),
+            ),
+        ];
+        Ok(create_image_response(
+            png,
+            OutputFormat::Png,
+            &response_headers,
+        ))
+    })
+    .await
+    .unwrap_or_else(|e| {
+        Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Comparison task failed: {}", e),
+        ))
+    });
+
+    match result {
+        Ok(response) => response,
+        Err((status, error)) => (status, axum::Json(ErrorResponse { error })).into_response(),
+    }
+}
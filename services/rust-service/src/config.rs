@@ -0,0 +1,193 @@
+//! Live settings: the low-power, deprecation, quota, usage, palette and default-parameter
+//! settings can change without a restart. They are read from the environment, overridden by an
+//! optional settings file, and re-read on SIGHUP or when a watched file changes.
+//!
+//! Configuration (all optional):
+//! - `CONFIG_FILE`: `KEY=value` lines using the environment variable names (lines starting
+//!   with `#` are comments; a `#` inside a value is kept). Values here win over the
+//!   environment, so pushing a new file takes effect.
+//! - `CONFIG_WATCH_SECS`: also reload whenever `CONFIG_FILE`, `QUOTA_FILE` or `PALETTE_DIR` is
+//!   modified, checking this often
+//!
+//! Startup settings (tuning, plugins, queue, `USAGE_LOG`) still need a restart.
+
+use crate::defaults;
+use crate::palettes;
+use crate::pipeline::AppState;
+use crate::quota::Quotas;
+use crate::throttle::Throttle;
+use std::collections::BTreeMap;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, SystemTime};
+
+static FILE_SETTINGS: RwLock<BTreeMap<String, String>> = RwLock::new(BTreeMap::new());
+
+/// Value of a live setting: the settings file first, then the environment
+pub fn var(name: &str) -> Option<String> {
+    let settings = FILE_SETTINGS.read().unwrap_or_else(|e| e.into_inner());
+    settings
+        .get(name)
+        .cloned()
+        .or_else(|| std::env::var(name).ok())
+}
+
+/// (Re)read `CONFIG_FILE`; on error the previous settings stay in effect
+pub fn load_file() -> Result<(), String> {
+    let Ok(path) = std::env::var("CONFIG_FILE") else {
+        return Ok(());
+    };
+    let settings = read_file(&path)?;
+
+    tracing::info!("Loaded {} settings from {}", settings.len(), path);
+    *FILE_SETTINGS.write().unwrap_or_else(|e| e.into_inner()) = settings;
+    Ok(())
+}
+
+/// The settings in a `KEY=value` file, without applying them
+pub fn read_file(path: &str) -> Result<BTreeMap<String, String>, String> {
+    let text = std::fs::read_to_string(path).map_err(|e| format!("Cannot read {}: {}", path, e))?;
+    parse_settings(path, &text)
+}
+
+/// `KEY=value` lines; `path` only names the file in errors
+fn parse_settings(path: &str, text: &str) -> Result<BTreeMap<String, String>, String> {
+    let mut settings = BTreeMap::new();
+    for (number, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let Some((key, value)) = line.split_once('=') else {
+            return Err(format!("{} line {}: expected KEY=value", path, number + 1));
+        };
+        settings.insert(key.trim().to_string(), value.trim().to_string());
+    }
+    Ok(settings)
+}
+
+/// A piece of state that is rebuilt on reload. Readers keep the version they started with.
+pub struct Reloadable<T>(RwLock<Arc<T>>);
+
+impl<T> Reloadable<T> {
+    pub fn new(value: T) -> Self {
+        Self(RwLock::new(Arc::new(value)))
+    }
+
+    pub fn get(&self) -> Arc<T> {
+        self.0.read().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    pub fn set(&self, value: T) {
+        *self.0.write().unwrap_or_else(|e| e.into_inner()) = Arc::new(value);
+    }
+}
+
+/// Re-read the settings and rebuild everything that depends on them. A file that fails to
+/// load leaves its part of the configuration unchanged.
+pub fn reload(state: &AppState) {
+    if let Err(e) = load_file() {
+        tracing::error!("Keeping previous settings: {}", e);
+        return;
+    }
+
+    state.throttle.set(Throttle::from_env());
+    match Quotas::load() {
+        Ok(quotas) => state.quotas.set(quotas),
+        Err(e) => tracing::error!("Keeping previous quotas: {}", e),
+    }
+    state.legacy.reload();
+    state.usage.reload();
+    if let Err(e) = palettes::load() {
+        tracing::error!("Keeping previous palettes: {}", e);
+    }
+    if let Err(e) = defaults::load() {
+        tracing::error!("Keeping previous defaults: {}", e);
+    }
+    tracing::info!("Configuration reloaded");
+}
+
+/// Reload on SIGHUP, and on file changes when `CONFIG_WATCH_SECS` is set
+pub async fn watch(state: Arc<AppState>) {
+    let interval = var("CONFIG_WATCH_SECS")
+        .and_then(|value| value.parse::<u64>().ok())
+        .filter(|secs| *secs > 0)
+        .map(Duration::from_secs);
+
+    #[cfg(unix)]
+    let mut hangup = match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::hangup()) {
+        Ok(signal) => Some(signal),
+        Err(e) => {
+            tracing::error!("Cannot listen for SIGHUP: {}", e);
+            None
+        }
+    };
+
+    let mut modified = watched_files_modified();
+    loop {
+        let tick = async {
+            match interval {
+                Some(interval) => tokio::time::sleep(interval).await,
+                None => std::future::pending().await,
+            }
+        };
+
+        #[cfg(unix)]
+        let hangup_received = async {
+            match hangup.as_mut() {
+                Some(signal) => {
+                    signal.recv().await;
+                }
+                None => std::future::pending().await,
+            }
+        };
+        #[cfg(not(unix))]
+        let hangup_received = std::future::pending::<()>();
+
+        tokio::select! {
+            _ = hangup_received => {
+                tracing::info!("SIGHUP received, reloading configuration");
+            }
+            _ = tick => {
+                let now = watched_files_modified();
+                if now == modified {
+                    continue;
+                }
+                tracing::info!("Configuration files changed, reloading");
+            }
+        }
+
+        modified = watched_files_modified();
+        reload(&state);
+    }
+}
+
+/// Modification times of the settings and quota files and the palette directory
+fn watched_files_modified() -> Vec<Option<SystemTime>> {
+    [
+        std::env::var("CONFIG_FILE").ok(),
+        var("QUOTA_FILE"),
+        var("PALETTE_DIR"),
+    ]
+    .into_iter()
+    .map(|path| path.and_then(|path| std::fs::metadata(path).ok()?.modified().ok()))
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_hashes_inside_values() {
+        let settings = parse_settings(
+            "test.env",
+            "# Legacy routes\n  # indented comment\nLEGACY_WARNING=see issue #123\n\nQUOTA_WARN_AT = 0.8\n",
+        )
+        .unwrap();
+
+        assert_eq!(settings.len(), 2);
+        assert_eq!(settings["LEGACY_WARNING"], "see issue #123");
+        assert_eq!(settings["QUOTA_WARN_AT"], "0.8");
+        assert!(parse_settings("test.env", "NOT A SETTING").is_err());
+    }
+}
@@ -0,0 +1,168 @@
+//! Saliency-aware cropping: render a field larger than the requested image around the same
+//! view, slide a window of the requested size across it and return the window the aesthetic
+//! score rates highest. Handy for banners and thumbnails cut from arbitrary views.
+
+use crate::fractals::create_fractal;
+use crate::pipeline::{render, AppState, RenderOptions};
+use crate::query::FractalQuery;
+use crate::rendering::aesthetics::score_image;
+use crate::rendering::encoder::{create_image_response, encode_image, EncodeOptions, OutputFormat};
+use crate::utils::validation::validate_crop;
+use crate::ErrorResponse;
+use axum::{
+    extract::{Query, State},
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+};
+use image::{imageops, RgbImage};
+use schemars::JsonSchema;
+use serde::Deserialize;
+use std::sync::Arc;
+
+/// Longest edge of the field copy that candidate windows are scored on
+const SCORING_SIZE: u32 = 768;
+
+#[derive(Deserialize, JsonSchema)]
+pub struct CropOptions {
+    /// Field size per side relative to the requested image, 1-4 (default 2)
+    field: Option<f64>,
+    /// Window positions tried along each axis, 1-32 (default 9)
+    positions: Option<u32>,
+}
+
+/// The chosen window within the field
+struct Crop {
+    x: u32,
+    y: u32,
+    score: f64,
+}
+
+/// Offsets of `positions` windows of `window` pixels spread evenly across `field` pixels
+fn offsets(field: u32, window: u32, positions: u32) -> Vec<u32> {
+    let free = field - window;
+    if positions < 2 || free == 0 {
+        return vec![free / 2];
+    }
+    let mut offsets: Vec<u32> = (0..positions)
+        .map(|i| (u64::from(free) * u64::from(i) / u64::from(positions - 1)) as u32)
+        .collect();
+    offsets.dedup();
+    offsets
+}
+
+/// Score every window on a downscaled copy of the field; ties go to the most central window
+fn best_crop(field: &RgbImage, width: u32, height: u32, positions: u32) -> Crop {
+    let (field_width, field_height) = field.dimensions();
+    let scale = (SCORING_SIZE as f64 / field_width.max(field_height) as f64).min(1.0);
+    let scaled = |pixels: u32| ((pixels as f64 * scale).round() as u32).max(1);
+    let thumb = imageops::thumbnail(field, scaled(field_width), scaled(field_height));
+
+    let xs = offsets(field_width, width, positions);
+    let ys = offsets(field_height, height, positions);
+    let mut windows: Vec<(u32, u32)> = ys
+        .iter()
+        .flat_map(|&y| xs.iter().map(move |&x| (x, y)))
+        .collect();
+    let center = |offset: u32, free: u32| (offset as f64 - free as f64 / 2.0).abs();
+    windows.sort_by(|a, b| {
+        let a = center(a.0, field_width - width) + center(a.1, field_height - height);
+        let b = center(b.0, field_width - width) + center(b.1, field_height - height);
+        a.total_cmp(&b)
+    });
+
+    let mut best: Option<Crop> = None;
+    for (x, y) in windows {
+        let window = imageops::crop_imm(
+            &thumb,
+            ((x as f64 * scale) as u32).min(thumb.width() - 1),
+            ((y as f64 * scale) as u32).min(thumb.height() - 1),
+            scaled(width),
+            scaled(height),
+        )
+        .to_image();
+        let score = score_image(&window).score;
+        if best.as_ref().is_none_or(|best| score > best.score) {
+            best = Some(Crop { x, y, score });
+        }
+    }
+
+    best.unwrap_or(Crop {
+        x: 0,
+        y: 0,
+        score: 0.0,
+    })
+}
+
+// Automatic crop endpoint: the most interesting window of a larger field
+pub async fn crop(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Query(query): Query<FractalQuery>,
+    Query(options): Query<CropOptions>,
+) -> Response {
+    let render_options = RenderOptions::billed_to(&headers);
+    let field = options.field.unwrap_or(2.0);
+    let positions = options.positions.unwrap_or(9);
+
+    // Rendering is CPU-bound, keep it off the async workers
+    let result = tokio::task::spawn_blocking(move || {
+        let fractal_type = query.fractal_type();
+        let params = query.into_params();
+        let (width, height) = (params.width, params.height);
+        validate_crop(field, positions, width, height)
+            .map_err(|error| (StatusCode::BAD_REQUEST, error))?;
+
+        // Same center and pixel density as the requested image, just more of it
+        let mut field_params = params.clone();
+        field_params.width = (width as f64 * field).round() as u32;
+        field_params.height = (height as f64 * field).round() as u32;
+        let view = create_fractal(&fractal_type).and_then(|fractal| fractal.plane_view());
+        if view.is_some() {
+            field_params.zoom = params.zoom * height as f64 / field_params.height as f64;
+        }
+
+        let (field_img, metadata) =
+            render(&state, &fractal_type, field_params.clone(), &render_options)
+                .map_err(|e| (e.status(), e.message()))?;
+        let crop = best_crop(&field_img, width, height, positions);
+        let img = imageops::crop_imm(&field_img, crop.x, crop.y, width, height).to_image();
+
+        let mut response_headers = metadata.headers;
+        response_headers.push((
+            "X-Crop-Offset".to_string(),
+            format!("{},{}", crop.x, crop.y),
+        ));
+        response_headers.push(("X-Crop-Score".to_string(), format!("{:.4}", crop.score)));
+
+        // center_x/center_y that render the crop directly at the requested zoom
+        if let Some(view) = view {
+            let bounds = view.bounds(&field_params);
+            let px = (crop.x as f64 + width as f64 / 2.0) / field_params.width as f64;
+            let py = (crop.y as f64 + height as f64 / 2.0) / field_params.height as f64;
+            let center_x = bounds.x_min + px * (bounds.x_max - bounds.x_min) - view.origin.0;
+            let center_y = bounds.y_min + py * (bounds.y_max - bounds.y_min) - view.origin.1;
+            response_headers.push((
+                "X-Crop-Center".to_string(),
+                format!("{},{}", center_x, center_y),
+            ));
+        }
+
+        let png_bytes = encode_image(&img, OutputFormat::Png, &EncodeOptions::default())
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?;
+        Ok((png_bytes, response_headers))
+    })
+    .await
+    .unwrap_or_else(|e| {
+        Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Crop task failed: {}", e),
+        ))
+    });
+
+    match result {
+        Ok((png_bytes, response_headers)) => {
+            create_image_response(png_bytes, OutputFormat::Png, &response_headers)
+        }
+        Err((status, error)) => (status, axum::Json(ErrorResponse { error })).into_response(),
+    }
+}
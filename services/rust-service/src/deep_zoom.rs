@@ -0,0 +1,335 @@
+//! Deep Zoom (DZI) pyramids: every level of a view cut into tiles and zipped with the .dzi
+//! descriptor that OpenSeadragon and other deep-zoom viewers read, so a static frontend can pan
+//! and zoom smoothly without a render server behind it. Each tile is rendered directly at its
+//! level's scale, so coarse levels keep full detail instead of being downsampled from one
+//! huge image.
+
+use crate::fractals::create_fractal;
+use crate::fractals::traits::{FractalParams, PlaneView};
+use crate::pipeline::{render, AppState, RenderOptions};
+use crate::query::FractalQuery;
+use crate::rendering::colors::Coloring;
+use crate::rendering::dither::{Dither, GrayOutput};
+use crate::rendering::encoder::{encode_image, EncodeOptions, OutputFormat};
+use crate::rendering::zip_writer::ZipWriter;
+use crate::utils::http::append_headers;
+use crate::utils::validation::{validate_deep_zoom, validate_pyramid_pixels};
+use crate::ErrorResponse;
+use axum::{
+    extract::{Query, State},
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+};
+use schemars::JsonSchema;
+use serde::Deserialize;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Types whose pixels each depend only on their own plane point and whose first row is the
+/// view's y_min, so tiles rendered separately line up exactly
+pub const DEEP_ZOOM_TYPES: &[&str] = &[
+    "mandelbrot",
+    "julia",
+    "nova",
+    "magnet1",
+    "magnet2",
+    "custom",
+    "hybrid",
+    "newton",
+];
+
+pub const TILE_FORMATS: &[&str] = &["png", "jpeg", "webp"];
+
+/// 254 with a 1-pixel overlap on each side gives 256-pixel tiles, the Deep Zoom default
+const DEFAULT_TILE_SIZE: u32 = 254;
+const DEFAULT_OVERLAP: u32 = 1;
+
+#[derive(Deserialize, JsonSchema)]
+pub struct DeepZoomOptions {
+    /// Tile edge before overlap, 64-1024 (default 254)
+    tile_size: Option<u32>,
+    /// Pixels a tile repeats from each neighbour, 0-8 (default 1)
+    overlap: Option<u32>,
+    /// Tile format: png (default), jpeg or webp
+    format: Option<String>,
+    /// JPEG and WebP tile quality, 1-100
+    quality: Option<u8>,
+}
+
+/// One tile and the pixels it covers on its level
+#[derive(Clone, Copy, Debug)]
+struct Tile {
+    level: u32,
+    column: u32,
+    row: u32,
+    x: u32,
+    y: u32,
+    width: u32,
+    height: u32,
+}
+
+/// The zipped pyramid and what to report about it
+struct Pyramid {
+    zip: Vec<u8>,
+    /// Fractal type, which names the .dzi file and the zip
+    name: &'static str,
+    headers: Vec<(String, String)>,
+}
+
+/// Level of the full-size image; level 0 is a single pixel and each level doubles the last
+fn max_level(width: u32, height: u32) -> u32 {
+    let longest = width.max(height);
+    u32::BITS - (longest - 1).leading_zeros()
+}
+
+/// Start and length of each tile along one side of a level, overlaps included
+fn tile_spans(length: u32, tile_size: u32, overlap: u32) -> Vec<(u32, u32)> {
+    (0..length.div_ceil(tile_size))
+        .map(|index| {
+            let start = (index * tile_size).saturating_sub(overlap);
+            let end = ((index + 1) * tile_size + overlap).min(length);
+            (start, end - start)
+        })
+        .collect()
+}
+
+/// Every tile of the pyramid, coarsest level first
+fn pyramid(width: u32, height: u32, tile_size: u32, overlap: u32) -> Vec<Tile> {
+    let top = max_level(width, height);
+    let mut tiles = Vec::new();
+
+    for level in 0..=top {
+        let factor = 1u32 << (top - level);
+        let columns = tile_spans(width.div_ceil(factor), tile_size, overlap);
+        let rows = tile_spans(height.div_ceil(factor), tile_size, overlap);
+        for (row, &(y, tile_height)) in rows.iter().enumerate() {
+            for (column, &(x, tile_width)) in columns.iter().enumerate() {
+                tiles.push(Tile {
+                    level,
+                    column: column as u32,
+                    row: row as u32,
+                    x,
+                    y,
+                    width: tile_width,
+                    height: tile_height,
+                });
+            }
+        }
+    }
+
+    tiles
+}
+
+/// Parameters rendering exactly the tile: its level's pixel size, centered on its pixels
+fn tile_params(params: &FractalParams, view: &PlaneView, top: u32, tile: &Tile) -> FractalParams {
+    let bounds = view.bounds(params);
+    let factor = (1u64 << (top - tile.level)) as f64;
+    let pixel = (bounds.y_max - bounds.y_min) / params.height as f64 * factor;
+
+    let mut tile_params = params.clone();
+    tile_params.width = tile.width;
+    tile_params.height = tile.height;
+    tile_params.zoom = view.half_height / (pixel * tile.height as f64 / 2.0);
+    tile_params.center_x =
+        bounds.x_min + (tile.x as f64 + tile.width as f64 / 2.0) * pixel - view.origin.0;
+    tile_params.center_y =
+        bounds.y_min + (tile.y as f64 + tile.height as f64 / 2.0) * pixel - view.origin.1;
+    tile_params
+}
+
+/// Colorings and gray output that depend on the whole image, so separate tiles wouldn't match
+fn check_tileable(params: &FractalParams) -> Result<(), String> {
+    match Coloring::from_param(params.coloring.as_deref())? {
+        Coloring::Histogram | Coloring::OrbitTrap => {
+            return Err(format!(
+                "coloring={} scales colors across the whole image, so Deep Zoom tiles wouldn't match. Use another coloring.",
+                params.coloring.as_deref().unwrap_or_default()
+            ))
+        }
+        _ => {}
+    }
+    if let Some(gray) = GrayOutput::from_params(params)? {
+        if gray.equalize || gray.dither != Dither::None {
+            return Err(
+                "equalize and dithering depend on the whole image, so Deep Zoom tiles wouldn't match. Use dither_levels alone for gray tiles."
+                    .to_string(),
+            );
+        }
+    }
+    Ok(())
+}
+
+/// The .dzi descriptor viewers load first
+fn descriptor(width: u32, height: u32, tile_size: u32, overlap: u32, extension: &str) -> String {
+    format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+         <Image xmlns=\"http://schemas.microsoft.com/deepzoom/2008\" Format=\"{}\" Overlap=\"{}\" TileSize=\"{}\">\n  \
+         <Size Width=\"{}\" Height=\"{}\"/>\n\
+         </Image>\n",
+        extension, overlap, tile_size, width, height
+    )
+}
+
+/// Render every tile and zip the pyramid; errors carry the status to respond with
+fn build_pyramid(
+    state: &AppState,
+    query: FractalQuery,
+    options: DeepZoomOptions,
+    render_options: &RenderOptions,
+) -> Result<Pyramid, (StatusCode, String)> {
+    let bad_request = |error: String| (StatusCode::BAD_REQUEST, error);
+
+    let fractal_type = query.fractal_type();
+    let name = DEEP_ZOOM_TYPES
+        .iter()
+        .find(|name| name.eq_ignore_ascii_case(&fractal_type))
+        .copied()
+        .ok_or_else(|| {
+            bad_request(format!(
+                "Deep Zoom pyramids aren't available for type={}. Supported types: {}.",
+                fractal_type,
+                DEEP_ZOOM_TYPES.join(", ")
+            ))
+        })?;
+    let view = create_fractal(name)
+        .and_then(|fractal| fractal.plane_view())
+        .ok_or_else(|| bad_request(format!("type={} has no plane view.", name)))?;
+
+    let params = query.into_params();
+    let tile_size = options.tile_size.unwrap_or(DEFAULT_TILE_SIZE);
+    let overlap = options.overlap.unwrap_or(DEFAULT_OVERLAP);
+    validate_deep_zoom(params.width, params.height, tile_size, overlap).map_err(bad_request)?;
+    check_tileable(&params).map_err(bad_request)?;
+
+    let (format, extension) = match options.format.as_deref().map(str::to_lowercase).as_deref() {
+        None | Some("png") => (OutputFormat::Png, "png"),
+        Some("jpeg") | Some("jpg") => (OutputFormat::Jpeg, "jpg"),
+        Some("webp") => (OutputFormat::WebP, "webp"),
+        Some(_) => {
+            return Err(bad_request(format!(
+                "Invalid format. Deep Zoom tiles must be one of: {}.",
+                TILE_FORMATS.join(", ")
+            )))
+        }
+    };
+    let encode_options =
+        EncodeOptions::from_params(None, None, options.quality, None).map_err(bad_request)?;
+
+    let tiles = pyramid(params.width, params.height, tile_size, overlap);
+    let pixels = tiles
+        .iter()
+        .map(|tile| tile.width as u64 * tile.height as u64)
+        .sum();
+    validate_pyramid_pixels(pixels).map_err(bad_request)?;
+
+    // Refuse up front rather than running out of budget halfway through the tiles; the
+    // pyramid is billed once below, so the tile renders don't check or record usage
+    state
+        .quotas
+        .get()
+        .check(&state.usage, render_options.tenant.as_deref(), pixels)
+        .map_err(|exceeded| (exceeded.status, exceeded.message))?;
+    let tile_options = RenderOptions {
+        billed_by_caller: true,
+        ..render_options.clone()
+    };
+
+    let top = max_level(params.width, params.height);
+    let mut zip = ZipWriter::default();
+    let internal = |error: String| (StatusCode::INTERNAL_SERVER_ERROR, error);
+    zip.add(
+        &format!("{}.dzi", name),
+        descriptor(params.width, params.height, tile_size, overlap, extension).as_bytes(),
+    )
+    .map_err(internal)?;
+
+    // Tiles render one after another; each render already uses every core
+    let mut render_time = Duration::ZERO;
+    for tile in &tiles {
+        let (img, metadata) = render(
+            state,
+            name,
+            tile_params(&params, &view, top, tile),
+            &tile_options,
+        )
+        .map_err(|e| {
+            (
+                e.status(),
+                format!(
+                    "Tile {}/{}_{}: {}",
+                    tile.level,
+                    tile.column,
+                    tile.row,
+                    e.message()
+                ),
+            )
+        })?;
+        let bytes = encode_image(&img, format, &encode_options).map_err(internal)?;
+        zip.add(
+            &format!(
+                "{}_files/{}/{}_{}.{}",
+                name, tile.level, tile.column, tile.row, extension
+            ),
+            &bytes,
+        )
+        .map_err(internal)?;
+        render_time += metadata.render_time;
+    }
+    state
+        .usage
+        .record(render_options.tenant.as_deref(), pixels, render_time);
+
+    let headers = vec![
+        ("X-Dzi-Levels".to_string(), (top + 1).to_string()),
+        ("X-Dzi-Tiles".to_string(), tiles.len().to_string()),
+        (
+            "X-Render-Time-Ms".to_string(),
+            render_time.as_millis().to_string(),
+        ),
+    ];
+    Ok(Pyramid {
+        zip: zip.finish().map_err(internal)?,
+        name,
+        headers,
+    })
+}
+
+// Deep Zoom endpoint: the view's full tile pyramid as a zip
+pub async fn deep_zoom(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Query(query): Query<FractalQuery>,
+    Query(options): Query<DeepZoomOptions>,
+) -> Response {
+    let render_options = RenderOptions::billed_to(&headers);
+
+    // Rendering is CPU-bound, keep it off the async workers
+    let result =
+        tokio::task::spawn_blocking(move || build_pyramid(&state, query, options, &render_options))
+            .await
+            .unwrap_or_else(|e| {
+                Err((
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    format!("Deep Zoom task failed: {}", e),
+                ))
+            });
+
+    match result {
+        Ok(pyramid) => {
+            let mut response = Response::builder()
+                .status(StatusCode::OK)
+                .header("Content-Type", "application/zip")
+                .header("Content-Length", pyramid.zip.len().to_string())
+                .header(
+                    "Content-Disposition",
+                    format!("attachment; filename=\"{}_dzi.zip\"", pyramid.name),
+                )
+                .body(axum::body::Body::from(pyramid.zip))
+                .unwrap()
+                .into_response();
+            append_headers(response.headers_mut(), &pyramid.headers);
+            response
+        }
+        Err((status, error)) => (status, axum::Json(ErrorResponse { error })).into_response(),
+    }
+}
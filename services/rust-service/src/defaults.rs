@@ -0,0 +1,98 @@
+//! Deployment defaults: what a request falls back to when it leaves out width, height,
+//! max_iterations or color_scheme. The built-in 800x600, 100 iterations and `default` scheme
+//! suit an API; a kiosk or an embedded deployment can set its own so clients don't have to.
+//!
+//! Configuration (all optional, reloadable through `config`):
+//! - `DEFAULT_WIDTH` / `DEFAULT_HEIGHT`: 1-4096
+//! - `DEFAULT_MAX_ITERATIONS`: 1-10000
+//! - `DEFAULT_COLOR_SCHEME`: a scheme name, a loaded palette file, hex stops or `brand:` colors
+//!
+//! A set with an invalid value is rejected whole and the previous defaults stay in effect.
+//! `GET /api/v1/fractal/validate` shows a request with the defaults applied.
+
+use crate::config;
+use crate::fractals::traits::FractalParams;
+use crate::palettes;
+use crate::rendering::colors::ColorScheme;
+use crate::utils::validation::{validate_dimensions, validate_iterations};
+use schemars::JsonSchema;
+use serde::Serialize;
+use std::sync::RwLock;
+
+static DEFAULTS: RwLock<Option<FractalParams>> = RwLock::new(None);
+
+/// The defaults as configured, for reporting
+#[derive(Serialize, JsonSchema)]
+pub struct DeploymentDefaults {
+    pub width: u32,
+    pub height: u32,
+    pub max_iterations: u32,
+    pub color_scheme: String,
+}
+
+impl DeploymentDefaults {
+    pub fn current() -> Self {
+        let defaults = current();
+        Self {
+            width: defaults.width,
+            height: defaults.height,
+            max_iterations: defaults.max_iterations,
+            color_scheme: defaults
+                .color_scheme
+                .unwrap_or_else(|| "default".to_string()),
+        }
+    }
+}
+
+/// Parameters a request starts from: the built-in defaults with this deployment's overrides
+pub fn current() -> FractalParams {
+    DEFAULTS
+        .read()
+        .unwrap_or_else(|e| e.into_inner())
+        .clone()
+        .unwrap_or_default()
+}
+
+/// (Re)read the overrides; on error the previous defaults stay in effect
+pub fn load() -> Result<(), String> {
+    let mut defaults = FractalParams::default();
+    if let Some(width) = number("DEFAULT_WIDTH")? {
+        defaults.width = width;
+    }
+    if let Some(height) = number("DEFAULT_HEIGHT")? {
+        defaults.height = height;
+    }
+    if let Some(max_iterations) = number("DEFAULT_MAX_ITERATIONS")? {
+        defaults.max_iterations = max_iterations;
+    }
+    validate_dimensions(defaults.width, defaults.height)?;
+    validate_iterations(defaults.max_iterations)?;
+
+    if let Some(scheme) = config::var("DEFAULT_COLOR_SCHEME") {
+        if ColorScheme::is_palette(&scheme) {
+            ColorScheme::parse_palette(&scheme)?;
+        } else if !ColorScheme::NAMES.contains(&scheme.to_lowercase().as_str())
+            && palettes::lookup(&scheme).is_none()
+        {
+            return Err(format!(
+                "DEFAULT_COLOR_SCHEME '{}' is neither a scheme nor a loaded palette",
+                scheme
+            ));
+        }
+        defaults.color_scheme = Some(scheme);
+    }
+
+    *DEFAULTS.write().unwrap_or_else(|e| e.into_inner()) = Some(defaults);
+    Ok(())
+}
+
+fn number(name: &str) -> Result<Option<u32>, String> {
+    config::var(name)
+        .map(|value| {
+            value
+                .trim()
+                .parse()
+                .map_err(|_| format!("{} must be a whole number, got '{}'", name, value))
+        })
+        .transpose()
+}
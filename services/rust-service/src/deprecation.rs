@@ -0,0 +1,196 @@
+//! Deprecation headers and usage telemetry for the unversioned `/api/...` routes. They keep
+//! working, but every response says so and links to the versioned route that replaces it,
+//! and per-route counters show operators when nobody calls them any more.
+//!
+//! Configuration (all optional):
+//! - `LEGACY_SUNSET`: HTTP-date sent in a `Sunset` header, e.g. `Sat, 31 Jan 2027 00:00:00 GMT`
+//! - `LEGACY_WARNING`: text of a `Warning: 299` header; `{successor}` is replaced by the new path
+//!
+//! Both can be changed at runtime through the settings file (see `config`).
+
+use crate::config::{self, Reloadable};
+use crate::pipeline::AppState;
+use crate::utils::time::unix_now;
+use axum::{
+    extract::{Request, State},
+    http::{header::LINK, HeaderValue, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use schemars::JsonSchema;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+
+/// RFC 9745 deprecation marker
+pub const DEPRECATION_HEADER: &str = "Deprecation";
+
+/// RFC 8594 removal date
+pub const SUNSET_HEADER: &str = "Sunset";
+
+/// Versioned path that replaces a legacy one
+pub fn successor(path: &str) -> String {
+    match path {
+        "/api/mandelbrot" => "/api/v1/fractal?type=mandelbrot".to_string(),
+        _ => match path.strip_prefix("/api") {
+            Some(rest) => format!("/api/v1{}", rest),
+            None => path.to_string(),
+        },
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+struct RouteUsage {
+    requests: u64,
+    last_seen_unix: u64,
+}
+
+/// Per-deployment deprecation settings
+struct LegacySettings {
+    sunset: Option<HeaderValue>,
+    warning: Option<String>,
+}
+
+impl LegacySettings {
+    fn from_env() -> Self {
+        let sunset = config::var("LEGACY_SUNSET")
+            .and_then(|value| parse_header_value("LEGACY_SUNSET", &value));
+        let warning = config::var("LEGACY_WARNING")
+            .filter(|value| parse_header_value("LEGACY_WARNING", value).is_some());
+
+        Self { sunset, warning }
+    }
+}
+
+/// Deprecation settings plus usage counters for each legacy route
+pub struct LegacyUsage {
+    settings: Reloadable<LegacySettings>,
+    started_unix: u64,
+    routes: Mutex<BTreeMap<String, RouteUsage>>,
+}
+
+impl LegacyUsage {
+    pub fn from_env() -> Self {
+        Self {
+            settings: Reloadable::new(LegacySettings::from_env()),
+            started_unix: unix_now(),
+            routes: Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    /// Pick up changed settings; the counters carry on
+    pub fn reload(&self) {
+        self.settings.set(LegacySettings::from_env());
+    }
+
+    /// Count a call to a legacy route, logging the first one since startup
+    fn record(&self, path: &str) {
+        let mut routes = self.routes.lock().unwrap_or_else(|e| e.into_inner());
+        let usage = routes.entry(path.to_string()).or_default();
+        if usage.requests == 0 {
+            tracing::warn!(
+                "Deprecated route {} called; clients should move to {}",
+                path,
+                successor(path)
+            );
+        }
+        usage.requests += 1;
+        usage.last_seen_unix = unix_now();
+    }
+
+    /// Headers announcing the deprecation of `path`
+    fn headers(&self, path: &str) -> Vec<(&'static str, HeaderValue)> {
+        let settings = self.settings.get();
+        let successor = successor(path);
+        let mut headers = vec![(DEPRECATION_HEADER, HeaderValue::from_static("true"))];
+
+        if let Ok(link) =
+            HeaderValue::from_str(&format!("<{}>; rel=\"successor-version\"", successor))
+        {
+            headers.push((LINK.as_str(), link));
+        }
+        if let Some(sunset) = &settings.sunset {
+            headers.push((SUNSET_HEADER, sunset.clone()));
+        }
+        if let Some(warning) = &settings.warning {
+            let text = warning.replace("{successor}", &successor).replace('"', "'");
+            if let Ok(value) = HeaderValue::from_str(&format!("299 - \"{}\"", text)) {
+                headers.push(("Warning", value));
+            }
+        }
+
+        headers
+    }
+
+    pub fn report(&self) -> LegacyUsageReport {
+        let routes = self.routes.lock().unwrap_or_else(|e| e.into_inner());
+        LegacyUsageReport {
+            counting_since_unix: self.started_unix,
+            sunset: self
+                .settings
+                .get()
+                .sunset
+                .as_ref()
+                .and_then(|value| value.to_str().ok())
+                .map(str::to_string),
+            routes: routes
+                .iter()
+                .map(|(path, usage)| LegacyRouteReport {
+                    path: path.clone(),
+                    successor: successor(path),
+                    requests: usage.requests,
+                    last_seen_unix: usage.last_seen_unix,
+                })
+                .collect(),
+        }
+    }
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct LegacyUsageReport {
+    /// Counters reset on restart; this is when they started
+    pub counting_since_unix: u64,
+    pub sunset: Option<String>,
+    /// Legacy routes called at least once since startup
+    pub routes: Vec<LegacyRouteReport>,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct LegacyRouteReport {
+    pub path: String,
+    pub successor: String,
+    pub requests: u64,
+    pub last_seen_unix: u64,
+}
+
+fn parse_header_value(name: &str, value: &str) -> Option<HeaderValue> {
+    match HeaderValue::from_str(value) {
+        Ok(value) => Some(value),
+        Err(_) => {
+            tracing::warn!("Ignoring invalid {}={}", name, value);
+            None
+        }
+    }
+}
+
+// Middleware for the legacy router: count the call, then mark the response deprecated
+pub async fn mark_deprecated(
+    State(state): State<Arc<AppState>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let path = request.uri().path().to_string();
+    state.legacy.record(&path);
+
+    let mut response = next.run(request).await;
+    for (name, value) in state.legacy.headers(&path) {
+        response.headers_mut().insert(name, value);
+    }
+
+    response
+}
+
+// Usage of the deprecated routes since startup
+pub async fn legacy_usage(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    (StatusCode::OK, axum::Json(state.legacy.report()))
+}
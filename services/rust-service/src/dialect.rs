@@ -0,0 +1,180 @@
+//! Migration shim for clients of the Python fractal service, which spelled some parameters
+//! differently (`cx`, `cy`, `iters`). Query strings are rewritten to the names used here; JSON
+//! bodies, tool arguments and queue messages accept the old names through serde aliases on
+//! `FractalQuery`. Every response from a render route carries an `X-Param-Dialect` header saying
+//! which spelling was seen, so frontends can find the calls left to migrate. The middleware is
+//! attached per route in `main`, so bodies of routes without render parameters aren't buffered.
+
+use crate::ErrorResponse;
+use axum::{
+    body::{to_bytes, Body},
+    extract::Request,
+    http::{header::CONTENT_TYPE, HeaderValue, StatusCode, Uri},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use serde_json::Value;
+
+/// Reports `python` or `rust`
+pub const DIALECT_HEADER: &str = "X-Param-Dialect";
+
+/// Python service parameter names and the names they stand for. Keep in step with the
+/// `#[serde(alias)]` attributes on `FractalQuery`.
+pub const PYTHON_PARAMS: &[(&str, &str)] = &[
+    ("cx", "center_x"),
+    ("cy", "center_y"),
+    ("iters", "max_iterations"),
+];
+
+/// Largest JSON body inspected, the same as axum's default limit for `Json` extractors
+const MAX_JSON_BODY: usize = 2 * 1024 * 1024;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Dialect {
+    Python,
+    Rust,
+}
+
+impl Dialect {
+    fn header_value(self) -> HeaderValue {
+        match self {
+            Dialect::Python => HeaderValue::from_static("python"),
+            Dialect::Rust => HeaderValue::from_static("rust"),
+        }
+    }
+}
+
+fn canonical_name(name: &str) -> Option<&'static str> {
+    PYTHON_PARAMS
+        .iter()
+        .find(|(python, _)| *python == name)
+        .map(|&(_, canonical)| canonical)
+}
+
+/// Error for a parameter given under both spellings
+fn conflict(python: &str, canonical: &str) -> String {
+    format!(
+        "{} and {} are the same parameter; send only one of them.",
+        python, canonical
+    )
+}
+
+/// Rename Python parameters in a query string, leaving values untouched
+fn translate_query(query: &str) -> Result<(String, bool), String> {
+    let names: Vec<&str> = query
+        .split('&')
+        .map(|pair| pair.split('=').next().unwrap_or(""))
+        .collect();
+
+    let mut translated = false;
+    let pairs: Vec<String> = query
+        .split('&')
+        .zip(&names)
+        .map(|(pair, name)| match canonical_name(name) {
+            Some(canonical) if names.contains(&canonical) => Err(conflict(name, canonical)),
+            Some(canonical) => {
+                translated = true;
+                Ok(format!("{}{}", canonical, &pair[name.len()..]))
+            }
+            None => Ok(pair.to_string()),
+        })
+        .collect::<Result<_, _>>()?;
+
+    Ok((pairs.join("&"), translated))
+}
+
+/// Whether any object in the body uses a Python parameter name
+fn json_uses_python_names(value: &Value) -> Result<bool, String> {
+    match value {
+        Value::Object(object) => {
+            let mut found = false;
+            for (name, field) in object {
+                if let Some(canonical) = canonical_name(name) {
+                    if object.contains_key(canonical) {
+                        return Err(conflict(name, canonical));
+                    }
+                    found = true;
+                }
+                found |= json_uses_python_names(field)?;
+            }
+            Ok(found)
+        }
+        Value::Array(items) => {
+            let mut found = false;
+            for item in items {
+                found |= json_uses_python_names(item)?;
+            }
+            Ok(found)
+        }
+        _ => Ok(false),
+    }
+}
+
+fn bad_request(error: String) -> Response {
+    (StatusCode::BAD_REQUEST, axum::Json(ErrorResponse { error })).into_response()
+}
+
+// Middleware for the render routes: translate Python parameter names and report the dialect
+pub async fn translate_params(request: Request, next: Next) -> Response {
+    let (mut parts, body) = request.into_parts();
+    let mut dialect = None;
+
+    if let Some(query) = parts.uri.query().filter(|query| !query.is_empty()) {
+        let (query, translated) = match translate_query(query) {
+            Ok(result) => result,
+            Err(error) => return bad_request(error),
+        };
+        if translated {
+            let path_and_query = format!("{}?{}", parts.uri.path(), query);
+            let mut uri = parts.uri.clone().into_parts();
+            uri.path_and_query = match path_and_query.parse() {
+                Ok(path_and_query) => Some(path_and_query),
+                Err(e) => return bad_request(format!("Invalid query string: {}", e)),
+            };
+            parts.uri = Uri::from_parts(uri).unwrap_or(parts.uri);
+            dialect = Some(Dialect::Python);
+        } else {
+            dialect = Some(Dialect::Rust);
+        }
+    }
+
+    let is_json = parts
+        .headers
+        .get(CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.starts_with("application/json"));
+    let body = if is_json {
+        let bytes = match to_bytes(body, MAX_JSON_BODY).await {
+            Ok(bytes) => bytes,
+            Err(e) => {
+                return (
+                    StatusCode::PAYLOAD_TOO_LARGE,
+                    axum::Json(ErrorResponse {
+                        error: format!("Request body too large: {}", e),
+                    }),
+                )
+                    .into_response()
+            }
+        };
+        // Malformed JSON is left for the route's extractor to reject
+        if let Ok(value) = serde_json::from_slice::<Value>(&bytes) {
+            match json_uses_python_names(&value) {
+                Ok(true) => dialect = Some(Dialect::Python),
+                Ok(false) => dialect = dialect.or(Some(Dialect::Rust)),
+                Err(error) => return bad_request(error),
+            }
+        }
+        Body::from(bytes)
+    } else {
+        body
+    };
+
+    let mut response = next.run(Request::from_parts(parts, body)).await;
+    if let Some(dialect) = dialect {
+        response
+            .headers_mut()
+            .insert(DIALECT_HEADER, dialect.header_value());
+    }
+
+    response
+}
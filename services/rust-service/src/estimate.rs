@@ -0,0 +1,157 @@
+//! Render-time prediction from a sparse sample: the requested view is rendered at a fraction of
+//! its pixels (same plane bounds, so the sample pixels form an even grid over the view), timed,
+//! and the time scaled up to the full pixel count. Unlike a flat pixels-per-second figure this
+//! sees how deep the view actually iterates, which is what dominates deep-zoom renders. The
+//! samples are rendered, throttled and billed like any other render.
+
+use crate::fractals::{create_fractal, FRACTAL_TYPES};
+use crate::pipeline::{render_with, AppState, RenderOptions};
+use crate::query::FractalQuery;
+use crate::utils::validation::validate_estimate_sample;
+use crate::warmup;
+use crate::ErrorResponse;
+use axum::{
+    extract::{Query, State},
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Share of the pixels rendered when no `sample` is given
+const DEFAULT_SAMPLE: f64 = 0.005;
+
+/// Most pixels one sample renders, whatever share `sample` asks for
+const MAX_SAMPLE_PIXELS: f64 = 65_536.0;
+
+/// Shortest sample edge in pixels, so tiny samples still cover the view
+const MIN_SAMPLE_EDGE: u32 = 8;
+
+/// Timed sample renders; the fastest counts, the first also warms caches
+const SAMPLE_RUNS: usize = 2;
+
+/// Types whose cost follows `samples` rather than the pixel count
+const SAMPLED_TYPES: &[&str] = &["buddhabrot", "nebulabrot"];
+
+#[derive(Deserialize, JsonSchema)]
+pub struct EstimateOptions {
+    /// Share of the pixels rendered for the estimate, 0.0001-0.25 (default 0.005), at most
+    /// 65536 pixels
+    sample: Option<f64>,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct EstimateResponse {
+    fractal_type: String,
+    width: u32,
+    height: u32,
+    sample_width: u32,
+    sample_height: u32,
+    /// Fastest render of the sample
+    sample_ms: f64,
+    /// Sample time scaled to the requested pixel count; comparable to `X-Render-Time-Ms`
+    predicted_ms: f64,
+    /// Pixel count over the warm-up baseline throughput (which includes PNG encoding), ignoring
+    /// the view; absent unless WARMUP ran at startup
+    baseline_ms: Option<f64>,
+}
+
+/// Types the estimate covers: rendered pixel by pixel over the complex plane
+fn estimable_types() -> Vec<&'static str> {
+    FRACTAL_TYPES
+        .iter()
+        .copied()
+        .filter(|name| !SAMPLED_TYPES.contains(name))
+        .filter(|name| create_fractal(name).is_some_and(|fractal| fractal.plane_view().is_some()))
+        .collect()
+}
+
+/// Time the sample renders; errors carry the status to respond with
+fn estimate(
+    state: &AppState,
+    query: FractalQuery,
+    sample: f64,
+    options: &RenderOptions,
+) -> Result<EstimateResponse, (StatusCode, String)> {
+    let bad_request = |error: String| (StatusCode::BAD_REQUEST, error);
+
+    let fractal_type = query.fractal_type();
+    let estimable = estimable_types();
+    if !estimable.contains(&fractal_type.as_str()) {
+        return Err(bad_request(format!(
+            "Render-time estimates cover {}; type={} is not one of them.",
+            estimable.join(", "),
+            fractal_type
+        )));
+    }
+    let fractal = create_fractal(&fractal_type)
+        .ok_or_else(|| bad_request(format!("Unknown fractal type {}", fractal_type)))?;
+
+    // Limits apply to the render being estimated, not the sample
+    let params = query.into_params();
+    fractal.validate_params(&params).map_err(bad_request)?;
+    let pixels = f64::from(params.width) * f64::from(params.height);
+
+    // Same bounds at a fraction of the pixels in each direction
+    let scale = sample.min(MAX_SAMPLE_PIXELS / pixels).sqrt();
+    let edge = |pixels: u32| {
+        ((pixels as f64 * scale).round() as u32).clamp(MIN_SAMPLE_EDGE.min(pixels), pixels)
+    };
+    let mut sample_params = params.clone();
+    sample_params.width = edge(params.width);
+    sample_params.height = edge(params.height);
+
+    let mut fastest = Duration::MAX;
+    for _ in 0..SAMPLE_RUNS {
+        let (_, metadata) = render_with(state, fractal.as_ref(), sample_params.clone(), options)
+            .map_err(|e| (e.status(), e.message()))?;
+        fastest = fastest.min(metadata.render_time);
+    }
+
+    let sample_pixels = f64::from(sample_params.width) * f64::from(sample_params.height);
+    let sample_ms = fastest.as_secs_f64() * 1000.0;
+
+    Ok(EstimateResponse {
+        fractal_type,
+        width: params.width,
+        height: params.height,
+        sample_width: sample_params.width,
+        sample_height: sample_params.height,
+        sample_ms,
+        predicted_ms: sample_ms * pixels / sample_pixels,
+        baseline_ms: warmup::current()
+            .map(|report| pixels / report.baseline_pixels_per_sec * 1000.0),
+    })
+}
+
+// Render-time estimate for a request, from a timed sample of its view
+pub async fn estimate_render(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Query(query): Query<FractalQuery>,
+    Query(options): Query<EstimateOptions>,
+) -> Response {
+    let render_options = RenderOptions::billed_to(&headers);
+    let sample = options.sample.unwrap_or(DEFAULT_SAMPLE);
+    if let Err(error) = validate_estimate_sample(sample) {
+        return (StatusCode::BAD_REQUEST, axum::Json(ErrorResponse { error })).into_response();
+    }
+
+    // Rendering is CPU-bound, keep it off the async workers
+    let result =
+        tokio::task::spawn_blocking(move || estimate(&state, query, sample, &render_options))
+            .await
+            .unwrap_or_else(|e| {
+                Err((
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    format!("Estimate task failed: {}", e),
+                ))
+            });
+
+    match result {
+        Ok(response) => (StatusCode::OK, axum::Json(response)).into_response(),
+        Err((status, error)) => (status, axum::Json(ErrorResponse { error })).into_response(),
+    }
+}
@@ -0,0 +1,158 @@
+//! "Explore nearby": render a handful of seeded random perturbations of a parameter set as
+//! thumbnails so users can discover interesting neighbouring views.
+
+use crate::pipeline::{render, AppState, RenderError, RenderOptions};
+use crate::query::FractalQuery;
+use crate::rendering::aesthetics::{score_image, AestheticScore};
+use crate::rendering::colors::ColorScheme;
+use crate::rendering::encoder::{encode_image, EncodeOptions, OutputFormat};
+use crate::utils::rng::Rng;
+use crate::utils::validation::validate_explore;
+use crate::ErrorResponse;
+use axum::{
+    extract::{Query, State},
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::sync::Arc;
+
+#[derive(Deserialize, JsonSchema)]
+pub struct ExploreOptions {
+    /// Number of variants to return
+    count: Option<u32>,
+    /// Longest thumbnail edge in pixels
+    thumb_size: Option<u32>,
+    /// Perturbation strength, relative to the current view
+    spread: Option<f64>,
+    /// Seed for the perturbations (distinct from the fractal's own `seed`)
+    explore_seed: Option<u64>,
+    /// Render twice as many candidates and keep the highest-scoring ones
+    curate: Option<bool>,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct ExploreVariant {
+    /// Full-size parameters reproducing this variant via /api/fractal
+    params: Value,
+    /// PNG thumbnail as a data URI
+    thumbnail: String,
+    aesthetics: AestheticScore,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct ExploreResponse {
+    base: Value,
+    variants: Vec<ExploreVariant>,
+}
+
+/// Randomly nudge zoom, center, Julia c and palette of the base parameters
+fn perturb(base: &FractalQuery, spread: f64, rng: &mut Rng) -> FractalQuery {
+    let mut variant = base.clone();
+    let zoom = base.zoom.unwrap_or(1.0);
+
+    // Zoom by up to e^(±2·spread), pan by up to `spread` of the visible half-height
+    let new_zoom = (zoom * (rng.range(-2.0, 2.0) * spread).exp()).clamp(1e-3, 1e10);
+    let pan = 2.0 / zoom * spread;
+    variant.zoom = Some(new_zoom);
+    variant.center_x = Some(base.center_x.unwrap_or(0.0) + rng.range(-pan, pan));
+    variant.center_y = Some(base.center_y.unwrap_or(0.0) + rng.range(-pan, pan));
+
+    // Julia constants move a little and stay inside the valid range
+    if let (Some(c_real), Some(c_imag)) = (base.julia_c_real, base.julia_c_imag) {
+        let delta = 0.1 * spread;
+        variant.julia_c_real = Some((c_real + rng.range(-delta, delta)).clamp(-2.0, 2.0));
+        variant.julia_c_imag = Some((c_imag + rng.range(-delta, delta)).clamp(-2.0, 2.0));
+    }
+
+    // Occasionally try a different palette
+    if rng.next_f64() < spread.min(1.0) {
+        let index = (rng.next_u64() % ColorScheme::NAMES.len() as u64) as usize;
+        variant.color_scheme = Some(ColorScheme::NAMES[index].to_string());
+    }
+
+    variant
+}
+
+fn render_thumbnail(
+    state: &AppState,
+    query: &FractalQuery,
+    thumb_size: u32,
+    options: &RenderOptions,
+) -> Result<(String, AestheticScore), RenderError> {
+    let mut params = query.clone().into_params();
+
+    // Preview mode: same view, longest edge scaled down to thumb_size
+    let longest = params.width.max(params.height) as f64;
+    let scale = (thumb_size as f64 / longest).min(1.0);
+    params.width = ((params.width as f64 * scale).round() as u32).max(1);
+    params.height = ((params.height as f64 * scale).round() as u32).max(1);
+
+    let (img, _) = render(state, &query.fractal_type(), params, options)?;
+    let aesthetics = score_image(&img);
+    let png_bytes = encode_image(&img, OutputFormat::Png, &EncodeOptions::default())
+        .map_err(RenderError::Internal)?;
+
+    Ok((
+        format!("data:image/png;base64,{}", STANDARD.encode(png_bytes)),
+        aesthetics,
+    ))
+}
+
+// Explore-nearby endpoint
+pub async fn explore(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Query(query): Query<FractalQuery>,
+    Query(options): Query<ExploreOptions>,
+) -> Response {
+    let render_options = RenderOptions::billed_to(&headers);
+    let count = options.count.unwrap_or(6);
+    let thumb_size = options.thumb_size.unwrap_or(160);
+    let spread = options.spread.unwrap_or(0.1);
+    let seed = options.explore_seed.unwrap_or(0);
+    let curate = options.curate.unwrap_or(false);
+
+    if let Err(error) = validate_explore(count, thumb_size, spread) {
+        return (StatusCode::BAD_REQUEST, axum::Json(ErrorResponse { error })).into_response();
+    }
+
+    // Rendering is CPU-bound, keep it off the async workers
+    let result = tokio::task::spawn_blocking(move || {
+        let mut rng = Rng::new(seed);
+        let candidates = if curate { count * 2 } else { count };
+        let mut variants = (0..candidates)
+            .map(|_| {
+                let variant = perturb(&query, spread, &mut rng);
+                let (thumbnail, aesthetics) =
+                    render_thumbnail(&state, &variant, thumb_size, &render_options)?;
+                Ok(ExploreVariant {
+                    params: variant.to_json(),
+                    thumbnail,
+                    aesthetics,
+                })
+            })
+            .collect::<Result<Vec<_>, RenderError>>()?;
+
+        // Keep the most interesting candidates, best first
+        if curate {
+            variants.sort_by(|a, b| b.aesthetics.score.total_cmp(&a.aesthetics.score));
+            variants.truncate(count as usize);
+        }
+
+        Ok::<_, RenderError>(ExploreResponse {
+            base: query.to_json(),
+            variants,
+        })
+    })
+    .await
+    .unwrap_or_else(|e| Err(RenderError::Internal(format!("Explore task failed: {}", e))));
+
+    match result {
+        Ok(response) => (StatusCode::OK, axum::Json(response)).into_response(),
+        Err(error) => error.into_response(),
+    }
+}
@@ -0,0 +1,125 @@
+//! Supersampled color histogram for fractal flames. Every plotted point adds its palette color
+//! and a hit to its bin; the bins are box-filtered down to the image and tone-mapped with
+//! log density, brightness, gamma and vibrancy.
+//!
+//! Colors are summed in linear light, so bins where differently colored points land average to
+//! the color their light adds up to rather than a darker one.
+//!
+//! Bins are shared by all worker threads and updated atomically. The sums are integers, so
+//! the result doesn't depend on the order the threads add to them.
+
+use crate::rendering::gamma::{decode, encode_srgb};
+use image::{ImageBuffer, Rgb, RgbImage};
+use rayon::prelude::*;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Red, green and blue sums plus the hit count of one bin
+const CHANNELS: usize = 4;
+
+/// Linear light is summed in steps of 1 / LINEAR_SCALE, fine enough for the darkest sRGB values
+const LINEAR_SCALE: f64 = 65535.0;
+
+pub struct FlameHistogram {
+    /// Output image size
+    width: u32,
+    height: u32,
+    /// Bins per pixel along each axis
+    supersample: u32,
+    bins: Vec<AtomicU64>,
+}
+
+/// How bin densities become pixel colors
+#[derive(Clone, Copy, Debug)]
+pub struct ToneMapping {
+    pub brightness: f64,
+    pub gamma: f64,
+    /// 1 applies gamma to the density only, keeping colors saturated; 0 applies it per channel
+    pub vibrancy: f64,
+}
+
+impl FlameHistogram {
+    pub fn new(width: u32, height: u32, supersample: u32) -> Self {
+        let bins = (width * supersample) as usize * (height * supersample) as usize;
+        Self {
+            width,
+            height,
+            supersample,
+            bins: (0..bins * CHANNELS).map(|_| AtomicU64::new(0)).collect(),
+        }
+    }
+
+    /// Size of the bin grid
+    pub fn bin_size(&self) -> (u32, u32) {
+        (
+            self.width * self.supersample,
+            self.height * self.supersample,
+        )
+    }
+
+    /// Add a point at bin coordinates (x, y); points outside the grid are ignored
+    pub fn plot(&self, x: f64, y: f64, color: [u8; 3]) {
+        let (columns, rows) = self.bin_size();
+        if x < 0.0 || y < 0.0 || x >= columns as f64 || y >= rows as f64 {
+            return;
+        }
+        let start = (y as usize * columns as usize + x as usize) * CHANNELS;
+        let bin = &self.bins[start..start + CHANNELS];
+        for (sum, value) in bin.iter().zip(color) {
+            let linear = (decode(value) * LINEAR_SCALE).round() as u64;
+            sum.fetch_add(linear, Ordering::Relaxed);
+        }
+        bin[3].fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Box-filter each pixel's bins into linear (red, green, blue) sums in [0, 1] units and a
+    /// hit count
+    fn pixel(&self, x: u32, y: u32) -> ([f64; 3], f64) {
+        let columns = (self.width * self.supersample) as usize;
+        let mut color = [0.0; 3];
+        let mut hits = 0.0;
+        for row in y * self.supersample..(y + 1) * self.supersample {
+            for column in x * self.supersample..(x + 1) * self.supersample {
+                let start = (row as usize * columns + column as usize) * CHANNELS;
+                let bin = &self.bins[start..start + CHANNELS];
+                for (channel, sum) in color.iter_mut().zip(bin) {
+                    *channel += sum.load(Ordering::Relaxed) as f64 / LINEAR_SCALE;
+                }
+                hits += bin[3].load(Ordering::Relaxed) as f64;
+            }
+        }
+        (color, hits)
+    }
+
+    pub fn to_image(&self, tone: ToneMapping) -> RgbImage {
+        let (width, height) = (self.width, self.height);
+        let pixels: Vec<([f64; 3], f64)> = (0..height)
+            .into_par_iter()
+            .flat_map_iter(|y| (0..width).map(move |x| self.pixel(x, y)))
+            .collect();
+
+        // Log density relative to the densest pixel, so the image never depends on how
+        // many points were plotted in total
+        let max_hits = pixels.iter().map(|&(_, hits)| hits).fold(0.0, f64::max);
+        let log_max = (1.0 + max_hits).ln().max(f64::EPSILON);
+
+        let inverse_gamma = 1.0 / tone.gamma;
+        let mut img: RgbImage = ImageBuffer::new(width, height);
+        for (pixel, (color, hits)) in img.pixels_mut().zip(pixels) {
+            if hits == 0.0 {
+                continue;
+            }
+            let alpha = ((1.0 + hits).ln() / log_max * tone.brightness).min(1.0);
+            let alpha_gamma = alpha.powf(inverse_gamma);
+
+            *pixel = Rgb(std::array::from_fn(|channel| {
+                // The average color back in sRGB, where the tone curve applies
+                let mean = encode_srgb(color[channel] / hits);
+                let vibrant = mean * alpha_gamma;
+                let per_channel = (mean * alpha).powf(inverse_gamma);
+                let value = tone.vibrancy * vibrant + (1.0 - tone.vibrancy) * per_channel;
+                (value.clamp(0.0, 1.0) * 255.0).round() as u8
+            }));
+        }
+        img
+    }
+}
@@ -0,0 +1,365 @@
+//! Fractal flames: the chaos game over affine maps followed by nonlinear variations, plotted
+//! into a supersampled color histogram and tone-mapped with log density, gamma and vibrancy.
+//!
+//! A flame is described by its own JSON body (`POST /api/v1/flame`) rather than query
+//! parameters, since it is a list of transforms, each with its own variation mix.
+
+pub mod histogram;
+pub mod variations;
+
+use crate::defaults;
+use crate::fractals::traits::{default_validate_params, Fractal, FractalParams};
+use crate::pipeline::{render_with, AppState, RenderError, RenderOptions};
+use crate::rendering::colors::{normalized_to_color, ColorScheme};
+use crate::rendering::encoder::{
+    create_image_response, encode_image, EncodeOptions, OutputFormat,
+};
+use crate::utils::rng::Rng;
+use crate::utils::validation::{validate_flame, validate_flame_tone, validate_flame_transform};
+use crate::ErrorResponse;
+use axum::{
+    extract::State,
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+};
+use histogram::{FlameHistogram, ToneMapping};
+use image::RgbImage;
+use rayon::prelude::*;
+use schemars::JsonSchema;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use variations::Variation;
+
+const DEFAULT_POINTS: u64 = 4_000_000;
+const DEFAULT_SEED: u64 = 0xF1A3E;
+const DEFAULT_SUPERSAMPLE: u32 = 2;
+const DEFAULT_BRIGHTNESS: f64 = 1.0;
+const DEFAULT_GAMMA: f64 = 2.2;
+const DEFAULT_VIBRANCY: f64 = 1.0;
+
+/// Half of the visible height at zoom 1; flames mostly live in [-1, 1]
+const HALF_HEIGHT: f64 = 1.2;
+
+/// Number of independently seeded point chunks; fixed so results don't depend on thread count
+const POINT_CHUNKS: u64 = 64;
+
+/// Iterations discarded per chunk (and after a point escapes) before plotting
+const WARMUP_ITERATIONS: u32 = 20;
+
+/// Colors precomputed from the color scheme, indexed by palette position
+const PALETTE_SIZE: usize = 256;
+
+/// Request body of `POST /api/v1/flame`
+#[derive(Deserialize, JsonSchema)]
+pub struct FlameRequest {
+    /// Image size (default 800x600)
+    width: Option<u32>,
+    height: Option<u32>,
+    zoom: Option<f64>,
+    center_x: Option<f64>,
+    center_y: Option<f64>,
+    /// Points plotted in total (default 4000000, at most 16777216)
+    samples: Option<u64>,
+    seed: Option<u64>,
+    /// Histogram bins per pixel along each axis, 1-4 (default 2)
+    supersample: Option<u32>,
+    /// Multiplies the log density before clamping, 0.1-10 (default 1)
+    brightness: Option<f64>,
+    /// 0.5-8 (default 2.2)
+    gamma: Option<f64>,
+    /// 0-1 (default 1): 1 gamma-corrects the density only, 0 each color channel
+    vibrancy: Option<f64>,
+    /// Palette the transforms' `color` positions index into
+    color_scheme: Option<String>,
+    /// Up to 16 transforms; a built-in flame (with its own final transform) when omitted
+    transforms: Option<Vec<FlameTransform>>,
+    /// Applied to every point before plotting, without feeding back into the iteration
+    final_transform: Option<FlameTransform>,
+}
+
+/// One map of the flame: `affine`, then the weighted sum of `variations`, then `post`
+#[derive(Clone, Deserialize, JsonSchema)]
+pub struct FlameTransform {
+    /// Relative probability of picking this transform (default 1)
+    weight: Option<f64>,
+    /// Palette position in [0, 1] this transform pulls points' colors towards
+    color: Option<f64>,
+    /// [a, b, c, d, e, f]: x' = a x + b y + c, y' = d x + e y + f
+    affine: [f64; 6],
+    /// Optional affine map applied after the variations
+    post: Option<[f64; 6]>,
+    /// Variation name to weight (default {"linear": 1})
+    variations: Option<BTreeMap<String, f64>>,
+}
+
+/// A validated transform, ready to iterate
+#[derive(Clone, Debug)]
+struct Transform {
+    weight: f64,
+    color: f64,
+    affine: [f64; 6],
+    post: Option<[f64; 6]>,
+    variations: Vec<(Variation, f64)>,
+}
+
+impl Transform {
+    fn from_spec(spec: &FlameTransform, default_color: f64) -> Result<Self, String> {
+        let weight = spec.weight.unwrap_or(1.0);
+        let color = spec.color.unwrap_or(default_color);
+        let variations = match &spec.variations {
+            Some(variations) => variations
+                .iter()
+                .map(|(name, weight)| Ok((Variation::parse(name)?, *weight)))
+                .collect::<Result<Vec<_>, String>>()?,
+            None => vec![(Variation::Linear, 1.0)],
+        };
+        validate_flame_transform(
+            weight,
+            color,
+            &spec.affine,
+            spec.post.as_ref(),
+            variations.iter().map(|&(_, weight)| weight),
+        )?;
+
+        Ok(Self {
+            weight,
+            color,
+            affine: spec.affine,
+            post: spec.post,
+            variations,
+        })
+    }
+
+    fn apply(&self, point: (f64, f64), rng: &mut Rng) -> (f64, f64) {
+        let point = affine(&self.affine, point);
+        let mut sum = (0.0, 0.0);
+        for &(variation, weight) in &self.variations {
+            let (x, y) = variation.apply(point, rng);
+            sum = (sum.0 + weight * x, sum.1 + weight * y);
+        }
+        match &self.post {
+            Some(post) => affine(post, sum),
+            None => sum,
+        }
+    }
+}
+
+fn affine([a, b, c, d, e, f]: &[f64; 6], (x, y): (f64, f64)) -> (f64, f64) {
+    (a * x + b * y + c, d * x + e * y + f)
+}
+
+/// Flame drawn when a request has no transforms: three half-size copies, one swirled and one
+/// turned inside out, seen through a julia final transform. Returns the transforms and the
+/// final transform.
+fn default_flame() -> (Vec<FlameTransform>, FlameTransform) {
+    let transform = |color: f64, affine: [f64; 6], variations: &[(&str, f64)]| FlameTransform {
+        weight: None,
+        color: Some(color),
+        affine,
+        post: None,
+        variations: Some(
+            variations
+                .iter()
+                .map(|&(name, weight)| (name.to_string(), weight))
+                .collect(),
+        ),
+    };
+    let transforms = vec![
+        transform(
+            0.0,
+            [0.5, 0.0, -0.5, 0.0, 0.5, -0.5],
+            &[("linear", 0.5), ("swirl", 0.5)],
+        ),
+        transform(0.5, [0.5, 0.0, 0.5, 0.0, 0.5, -0.5], &[("spherical", 1.0)]),
+        transform(1.0, [0.5, 0.0, 0.0, 0.0, 0.5, 0.5], &[("linear", 1.0)]),
+    ];
+    let final_transform = transform(0.5, [1.0, 0.0, 0.0, 0.0, 1.0, 0.0], &[("julia", 1.0)]);
+    (transforms, final_transform)
+}
+
+/// A flame ready to render; size, view, sample count, seed and palette come from the
+/// `FractalParams` it is rendered with
+pub struct Flame {
+    transforms: Vec<Transform>,
+    final_transform: Option<Transform>,
+    supersample: u32,
+    tone: ToneMapping,
+}
+
+impl Flame {
+    /// The flame and the render parameters described by a request
+    pub fn from_request(request: FlameRequest) -> Result<(Self, FractalParams), String> {
+        let (specs, final_spec) = match request.transforms {
+            Some(transforms) => (transforms, request.final_transform),
+            None => {
+                let (transforms, final_transform) = default_flame();
+                (
+                    transforms,
+                    request.final_transform.or(Some(final_transform)),
+                )
+            }
+        };
+        let count = specs.len();
+        let transforms = specs
+            .iter()
+            .enumerate()
+            .map(|(index, spec)| {
+                // Spread unspecified colors over the palette
+                let default_color = index as f64 / (count.max(2) - 1) as f64;
+                Transform::from_spec(spec, default_color)
+                    .map_err(|e| format!("Transform {}: {}", index + 1, e))
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+        let final_transform = final_spec
+            .as_ref()
+            .map(|spec| Transform::from_spec(spec, 0.5))
+            .transpose()
+            .map_err(|e| format!("final_transform: {}", e))?;
+
+        let flame = Self {
+            transforms,
+            final_transform,
+            supersample: request.supersample.unwrap_or(DEFAULT_SUPERSAMPLE),
+            tone: ToneMapping {
+                brightness: request.brightness.unwrap_or(DEFAULT_BRIGHTNESS),
+                gamma: request.gamma.unwrap_or(DEFAULT_GAMMA),
+                vibrancy: request.vibrancy.unwrap_or(DEFAULT_VIBRANCY),
+            },
+        };
+        let defaults = defaults::current();
+        let params = FractalParams {
+            width: request.width.unwrap_or(defaults.width),
+            height: request.height.unwrap_or(defaults.height),
+            zoom: request.zoom.unwrap_or(defaults.zoom),
+            center_x: request.center_x.unwrap_or(defaults.center_x),
+            center_y: request.center_y.unwrap_or(defaults.center_y),
+            samples: Some(request.samples.unwrap_or(DEFAULT_POINTS)),
+            seed: request.seed,
+            color_scheme: request.color_scheme.or(defaults.color_scheme.clone()),
+            ..defaults
+        };
+        flame.validate_params(&params)?;
+
+        Ok((flame, params))
+    }
+
+    /// Pick a transform with probability proportional to its weight
+    fn choose(&self, rng: &mut Rng, total_weight: f64) -> &Transform {
+        let mut target = rng.next_f64() * total_weight;
+        for transform in &self.transforms {
+            if target < transform.weight {
+                return transform;
+            }
+            target -= transform.weight;
+        }
+        &self.transforms[self.transforms.len() - 1]
+    }
+
+    /// Run the chaos game in fixed, independently seeded chunks
+    fn accumulate(&self, params: &FractalParams, palette: &[[u8; 3]]) -> FlameHistogram {
+        let histogram = FlameHistogram::new(params.width, params.height, self.supersample);
+        let (columns, rows) = histogram.bin_size();
+        let bins_per_unit = rows as f64 / (2.0 * HALF_HEIGHT / params.zoom);
+        let seed = params.seed.unwrap_or(DEFAULT_SEED);
+        let points = params.samples.unwrap_or(DEFAULT_POINTS);
+        let total_weight: f64 = self.transforms.iter().map(|t| t.weight).sum();
+
+        (0..POINT_CHUNKS).into_par_iter().for_each(|chunk| {
+            let mut rng = Rng::for_chunk(seed, chunk);
+            let chunk_points = points / POINT_CHUNKS + u64::from(chunk < points % POINT_CHUNKS);
+            let mut point = (rng.range(-1.0, 1.0), rng.range(-1.0, 1.0));
+            let mut color = rng.next_f64();
+            let mut skip = WARMUP_ITERATIONS;
+
+            for _ in 0..chunk_points {
+                let transform = self.choose(&mut rng, total_weight);
+                point = transform.apply(point, &mut rng);
+                color = (color + transform.color) / 2.0;
+
+                // A point that escaped to infinity restarts somewhere random
+                if !(point.0.is_finite() && point.1.is_finite()) {
+                    point = (rng.range(-1.0, 1.0), rng.range(-1.0, 1.0));
+                    skip = WARMUP_ITERATIONS;
+                    continue;
+                }
+                if skip > 0 {
+                    skip -= 1;
+                    continue;
+                }
+
+                let (plotted, plotted_color) = match &self.final_transform {
+                    Some(last) => (last.apply(point, &mut rng), (color + last.color) / 2.0),
+                    None => (point, color),
+                };
+                let index = (plotted_color * (PALETTE_SIZE - 1) as f64).round() as usize;
+                histogram.plot(
+                    columns as f64 / 2.0 + (plotted.0 - params.center_x) * bins_per_unit,
+                    rows as f64 / 2.0 - (plotted.1 - params.center_y) * bins_per_unit,
+                    palette[index.min(PALETTE_SIZE - 1)],
+                );
+            }
+        });
+
+        histogram
+    }
+}
+
+impl Fractal for Flame {
+    fn generate(&self, params: FractalParams) -> Result<RgbImage, String> {
+        self.validate_params(&params)?;
+
+        let scheme = ColorScheme::from_str(params.color_scheme.as_deref().unwrap_or("default"));
+        let palette: Vec<[u8; 3]> = (0..PALETTE_SIZE)
+            .map(|index| normalized_to_color(index as f64 / (PALETTE_SIZE - 1) as f64, &scheme))
+            .collect();
+
+        Ok(self.accumulate(&params, &palette).to_image(self.tone))
+    }
+
+    fn name(&self) -> &str {
+        "flame"
+    }
+
+    fn validate_params(&self, params: &FractalParams) -> Result<(), String> {
+        default_validate_params(params)?;
+        validate_flame(
+            self.transforms.len(),
+            params.samples.unwrap_or(DEFAULT_POINTS),
+            params.width,
+            params.height,
+            self.supersample,
+        )?;
+        validate_flame_tone(self.tone.brightness, self.tone.gamma, self.tone.vibrancy)
+    }
+}
+
+// Flame endpoint: JSON body describing the flame, PNG response
+pub async fn flame(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    axum::Json(request): axum::Json<FlameRequest>,
+) -> Response {
+    let options = RenderOptions::billed_to(&headers);
+
+    // Rendering is CPU-bound, keep it off the async workers
+    let result = tokio::task::spawn_blocking(move || {
+        let (flame, params) = Flame::from_request(request).map_err(RenderError::BadRequest)?;
+        render_with(&state, &flame, params, &options)
+    })
+    .await
+    .unwrap_or_else(|e| Err(RenderError::Internal(format!("Flame task failed: {}", e))));
+
+    let (img, metadata) = match result {
+        Ok(rendered) => rendered,
+        Err(e) => return e.into_response(),
+    };
+    match encode_image(&img, OutputFormat::Png, &EncodeOptions::default()) {
+        Ok(png_bytes) => create_image_response(png_bytes, OutputFormat::Png, &metadata.headers),
+        Err(error) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            axum::Json(ErrorResponse { error }),
+        )
+            .into_response(),
+    }
+}
@@ -0,0 +1,192 @@
+//! Nonlinear variation functions from "The Fractal Flame Algorithm" (Draves & Reckase).
+//! A flame transform applies its affine map and then a weighted sum of these.
+
+use crate::utils::rng::Rng;
+use std::f64::consts::PI;
+
+/// Names accepted as keys of a transform's `variations` object
+pub const VARIATION_NAMES: &[&str] = &[
+    "linear",
+    "sinusoidal",
+    "spherical",
+    "swirl",
+    "horseshoe",
+    "polar",
+    "handkerchief",
+    "heart",
+    "disc",
+    "spiral",
+    "hyperbolic",
+    "diamond",
+    "ex",
+    "julia",
+    "bent",
+    "fisheye",
+    "exponential",
+    "power",
+    "cosine",
+    "eyefish",
+    "bubble",
+    "cylinder",
+    "tangent",
+    "cross",
+];
+
+/// Keeps divisions by the radius finite at the origin
+const EPSILON: f64 = 1e-10;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Variation {
+    Linear,
+    Sinusoidal,
+    Spherical,
+    Swirl,
+    Horseshoe,
+    Polar,
+    Handkerchief,
+    Heart,
+    Disc,
+    Spiral,
+    Hyperbolic,
+    Diamond,
+    Ex,
+    Julia,
+    Bent,
+    Fisheye,
+    Exponential,
+    Power,
+    Cosine,
+    Eyefish,
+    Bubble,
+    Cylinder,
+    Tangent,
+    Cross,
+}
+
+impl Variation {
+    pub fn parse(name: &str) -> Result<Self, String> {
+        let variation = match name.to_lowercase().as_str() {
+            "linear" => Variation::Linear,
+            "sinusoidal" => Variation::Sinusoidal,
+            "spherical" => Variation::Spherical,
+            "swirl" => Variation::Swirl,
+            "horseshoe" => Variation::Horseshoe,
+            "polar" => Variation::Polar,
+            "handkerchief" => Variation::Handkerchief,
+            "heart" => Variation::Heart,
+            "disc" => Variation::Disc,
+            "spiral" => Variation::Spiral,
+            "hyperbolic" => Variation::Hyperbolic,
+            "diamond" => Variation::Diamond,
+            "ex" => Variation::Ex,
+            "julia" => Variation::Julia,
+            "bent" => Variation::Bent,
+            "fisheye" => Variation::Fisheye,
+            "exponential" => Variation::Exponential,
+            "power" => Variation::Power,
+            "cosine" => Variation::Cosine,
+            "eyefish" => Variation::Eyefish,
+            "bubble" => Variation::Bubble,
+            "cylinder" => Variation::Cylinder,
+            "tangent" => Variation::Tangent,
+            "cross" => Variation::Cross,
+            _ => {
+                return Err(format!(
+                    "Invalid variation {}. Must be one of: {}.",
+                    name,
+                    VARIATION_NAMES.join(", ")
+                ))
+            }
+        };
+        Ok(variation)
+    }
+
+    /// Apply the variation to an affine-transformed point. `rng` supplies the random choice
+    /// some variations make (julia picks one of two roots).
+    pub fn apply(self, (x, y): (f64, f64), rng: &mut Rng) -> (f64, f64) {
+        let r2 = x * x + y * y;
+        let r = r2.sqrt();
+        // The paper measures theta from the y axis
+        let theta = x.atan2(y);
+
+        match self {
+            Variation::Linear => (x, y),
+            Variation::Sinusoidal => (x.sin(), y.sin()),
+            Variation::Spherical => {
+                let scale = 1.0 / (r2 + EPSILON);
+                (x * scale, y * scale)
+            }
+            Variation::Swirl => {
+                let (sin, cos) = r2.sin_cos();
+                (x * sin - y * cos, x * cos + y * sin)
+            }
+            Variation::Horseshoe => {
+                let scale = 1.0 / (r + EPSILON);
+                ((x - y) * (x + y) * scale, 2.0 * x * y * scale)
+            }
+            Variation::Polar => (theta / PI, r - 1.0),
+            Variation::Handkerchief => (r * (theta + r).sin(), r * (theta - r).cos()),
+            Variation::Heart => (r * (theta * r).sin(), -r * (theta * r).cos()),
+            Variation::Disc => {
+                let (sin, cos) = (PI * r).sin_cos();
+                (theta / PI * sin, theta / PI * cos)
+            }
+            Variation::Spiral => {
+                let scale = 1.0 / (r + EPSILON);
+                (
+                    scale * (theta.cos() + r.sin()),
+                    scale * (theta.sin() - r.cos()),
+                )
+            }
+            Variation::Hyperbolic => (theta.sin() / (r + EPSILON), r * theta.cos()),
+            Variation::Diamond => (theta.sin() * r.cos(), theta.cos() * r.sin()),
+            Variation::Ex => {
+                let p0 = (theta + r).sin().powi(3);
+                let p1 = (theta - r).cos().powi(3);
+                (r * (p0 + p1), r * (p0 - p1))
+            }
+            Variation::Julia => {
+                let omega = if rng.next_u64() & 1 == 0 { 0.0 } else { PI };
+                let (sin, cos) = (theta / 2.0 + omega).sin_cos();
+                (r.sqrt() * cos, r.sqrt() * sin)
+            }
+            Variation::Bent => match (x >= 0.0, y >= 0.0) {
+                (true, true) => (x, y),
+                (false, true) => (2.0 * x, y),
+                (true, false) => (x, y / 2.0),
+                (false, false) => (2.0 * x, y / 2.0),
+            },
+            Variation::Fisheye => {
+                let scale = 2.0 / (r + 1.0);
+                (scale * y, scale * x)
+            }
+            Variation::Exponential => {
+                let scale = (x - 1.0).exp();
+                let (sin, cos) = (PI * y).sin_cos();
+                (scale * cos, scale * sin)
+            }
+            Variation::Power => {
+                let scale = r.powf(theta.sin());
+                (scale * theta.cos(), scale * theta.sin())
+            }
+            Variation::Cosine => {
+                let (sin, cos) = (PI * x).sin_cos();
+                (cos * y.cosh(), -sin * y.sinh())
+            }
+            Variation::Eyefish => {
+                let scale = 2.0 / (r + 1.0);
+                (scale * x, scale * y)
+            }
+            Variation::Bubble => {
+                let scale = 4.0 / (r2 + 4.0);
+                (scale * x, scale * y)
+            }
+            Variation::Cylinder => (x.sin(), y),
+            Variation::Tangent => (x.sin() / y.cos(), y.tan()),
+            Variation::Cross => {
+                let scale = 1.0 / ((x * x - y * y).abs() + EPSILON);
+                (scale * x, scale * y)
+            }
+        }
+    }
+}
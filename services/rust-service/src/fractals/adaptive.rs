@@ -0,0 +1,188 @@
+//! Adaptive iteration for escape-time renders (`adaptive=true`): every pixel is first iterated
+//! with a low limit, then only unresolved pixels bordering resolved ones are re-iterated at the
+//! full limit, spreading outward for as long as re-iterated pixels keep escaping. Unresolved
+//! regions with no escaping pixel around them (the set's interior) are never re-iterated.
+
+use super::traits::StatsHeaders;
+use crate::rendering::colors::Escape;
+use rayon::prelude::*;
+
+/// First-pass limit as a fraction of max_iterations
+const FIRST_PASS_DIVISOR: u32 = 8;
+
+/// Lowest first-pass limit
+const MIN_FIRST_PASS: u32 = 32;
+
+/// Work done by an adaptive render, reported in response headers
+#[derive(Clone, Copy, Debug, Default)]
+pub struct AdaptiveStats {
+    /// Pixels re-iterated at the full limit
+    pub refined: u64,
+    /// Iterations computed over both passes
+    pub iterations: u64,
+    /// Iterations a full-limit render would have computed
+    pub full_iterations: u64,
+}
+
+impl AdaptiveStats {
+    /// Share of the full render's iterations that were skipped
+    pub fn savings(&self) -> f64 {
+        if self.full_iterations == 0 {
+            return 0.0;
+        }
+        1.0 - self.iterations as f64 / self.full_iterations as f64
+    }
+
+    pub fn headers(&self) -> Vec<(String, String)> {
+        vec![
+            (
+                "X-Adaptive-Refined-Pixels".to_string(),
+                self.refined.to_string(),
+            ),
+            (
+                "X-Adaptive-Iterations".to_string(),
+                format!("{}/{}", self.iterations, self.full_iterations),
+            ),
+            (
+                "X-Adaptive-Savings".to_string(),
+                format!("{:.3}", self.savings()),
+            ),
+        ]
+    }
+}
+
+/// First pass of an escape-time render, kept for the second: coloring, and antialiasing, which
+/// samples `escape_at` between pixels
+pub struct FirstPass<F> {
+    pub escapes: Vec<Escape>,
+    pub stats: StatsHeaders,
+    /// One point at pixel coordinates with the given iteration limit
+    pub escape_at: F,
+}
+
+/// First pass of an escape-time render from a per-pixel `escape(x, y, limit)`: every pixel at
+/// max_iterations, or adaptively when asked, with the response headers reporting the savings
+pub fn iterate_pixels<F>(
+    width: u32,
+    height: u32,
+    max_iterations: u32,
+    adaptive: bool,
+    escape: F,
+) -> (Vec<Escape>, Vec<(String, String)>)
+where
+    F: Fn(u32, u32, u32) -> Escape + Sync,
+{
+    if adaptive {
+        let (escapes, stats) = iterate_adaptively(width, height, max_iterations, escape);
+        return (escapes, stats.headers());
+    }
+
+    let escapes = (0..height)
+        .into_par_iter()
+        .flat_map(|y| {
+            (0..width)
+                .map(|x| escape(x, y, max_iterations))
+                .collect::<Vec<_>>()
+        })
+        .collect();
+    (escapes, Vec::new())
+}
+
+/// Escape results for every pixel, row-major. `escape(x, y, limit)` iterates one pixel with the
+/// given iteration limit.
+pub fn iterate_adaptively<F>(
+    width: u32,
+    height: u32,
+    max_iterations: u32,
+    escape: F,
+) -> (Vec<Escape>, AdaptiveStats)
+where
+    F: Fn(u32, u32, u32) -> Escape + Sync,
+{
+    let first_limit = (max_iterations / FIRST_PASS_DIVISOR)
+        .max(MIN_FIRST_PASS)
+        .min(max_iterations);
+    let columns = width as usize;
+    let position = |index: usize| ((index % columns) as u32, (index / columns) as u32);
+
+    // First pass: every pixel at the low limit
+    let mut escapes: Vec<Escape> = (0..width as usize * height as usize)
+        .into_par_iter()
+        .map(|index| {
+            let (x, y) = position(index);
+            escape(x, y, first_limit)
+        })
+        .collect();
+    let mut stats = AdaptiveStats {
+        iterations: escapes.iter().map(|e| u64::from(e.iterations)).sum(),
+        ..AdaptiveStats::default()
+    };
+
+    // Pixels that reached the low limit may still escape before the full one
+    let mut unresolved: Vec<bool> = escapes
+        .iter()
+        .map(|escape| escape.iterations >= first_limit)
+        .collect();
+    let neighbours = |index: usize| {
+        let (x, y) = position(index);
+        let (x, y) = (x as i64, y as i64);
+        (-1..=1)
+            .flat_map(move |dy| (-1..=1).map(move |dx| (x + dx, y + dy)))
+            .filter(move |&(nx, ny)| {
+                (nx, ny) != (x, y)
+                    && (0..width as i64).contains(&nx)
+                    && (0..height as i64).contains(&ny)
+            })
+            .map(move |(nx, ny)| ny as usize * columns + nx as usize)
+    };
+
+    // Re-iterate from the boundary with the escaped region inward until the wave stops escaping
+    let mut frontier: Vec<usize> = if first_limit < max_iterations {
+        (0..escapes.len())
+            .filter(|&index| unresolved[index] && neighbours(index).any(|n| !unresolved[n]))
+            .collect()
+    } else {
+        // The first pass already ran at the full limit
+        Vec::new()
+    };
+    while !frontier.is_empty() {
+        let refined: Vec<Escape> = frontier
+            .par_iter()
+            .map(|&index| {
+                let (x, y) = position(index);
+                escape(x, y, max_iterations)
+            })
+            .collect();
+        for (&index, &result) in frontier.iter().zip(&refined) {
+            escapes[index] = result;
+            unresolved[index] = false;
+            stats.refined += 1;
+            stats.iterations += u64::from(result.iterations);
+        }
+
+        // Escaping pixels may have unresolved neighbours that escape too
+        let mut next = Vec::new();
+        for (&index, result) in frontier.iter().zip(&refined) {
+            if result.iterations >= max_iterations {
+                continue;
+            }
+            for neighbour in neighbours(index) {
+                if unresolved[neighbour] {
+                    unresolved[neighbour] = false;
+                    next.push(neighbour);
+                }
+            }
+        }
+        frontier = next;
+    }
+
+    // Whatever is left is taken to be inside the set
+    for (escape, unresolved) in escapes.iter_mut().zip(&unresolved) {
+        if *unresolved {
+            escape.iterations = max_iterations;
+        }
+    }
+    stats.full_iterations = escapes.iter().map(|e| u64::from(e.iterations)).sum();
+
+    (escapes, stats)
+}
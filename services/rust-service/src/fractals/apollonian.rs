@@ -0,0 +1,168 @@
+//! Apollonian gasket: starting from three mutually tangent circles inside a fourth, keep
+//! filling every curved-triangle gap with the circle tangent to its three sides.
+
+use super::traits::{default_validate_params, Fractal, FractalParams};
+use crate::rendering::canvas::Canvas;
+use crate::rendering::colors::{normalized_to_color, ColorScheme, GeometryColors};
+use crate::rendering::vector::{VectorBuilder, VectorFormat};
+use crate::utils::complex::Complex;
+use crate::utils::validation::{validate_max_curvature, validate_recursion_depth};
+use image::RgbImage;
+
+/// Fraction of the image left as margin around the outer circle
+const PADDING: f64 = 0.05;
+
+/// Circles under this radius in pixels (and everything nested in their gaps) are skipped
+const MIN_RADIUS_PX: f64 = 0.5;
+
+const OUTLINE_COLOR: [u8; 3] = [40, 40, 40];
+
+/// Circle by signed curvature (negative for the enclosing circle) and center
+#[derive(Clone, Copy, Debug)]
+struct Circle {
+    curvature: f64,
+    center: Complex,
+}
+
+impl Circle {
+    /// The other circle tangent to `a`, `b` and `c`, given one solution `opposite`.
+    /// Descartes' theorem has two roots for both the curvatures and the curvature-weighted
+    /// centers, and they sum to twice the sum over the three sides.
+    fn reflect(a: Circle, b: Circle, c: Circle, opposite: Circle) -> Circle {
+        let curvature = 2.0 * (a.curvature + b.curvature + c.curvature) - opposite.curvature;
+        let weighted = (a.weighted_center() + b.weighted_center() + c.weighted_center()).scale(2.0)
+            - opposite.weighted_center();
+        Circle {
+            curvature,
+            center: weighted.scale(1.0 / curvature),
+        }
+    }
+
+    fn weighted_center(self) -> Complex {
+        self.center.scale(self.curvature)
+    }
+}
+
+/// Gasket construction with the cutoffs for one render
+struct Gasket {
+    max_depth: u32,
+    max_curvature: f64,
+    /// Circles generated so far, with the generation that produced them
+    circles: Vec<(Circle, u32)>,
+}
+
+impl Gasket {
+    fn fill_gap(&mut self, sides: [Circle; 3], opposite: Circle, generation: u32) {
+        if generation > self.max_depth {
+            return;
+        }
+
+        let [a, b, c] = sides;
+        let circle = Circle::reflect(a, b, c, opposite);
+        // Circles nested in this one's gaps are smaller still, so the whole branch can go
+        if !(circle.curvature.is_finite() && circle.curvature <= self.max_curvature) {
+            return;
+        }
+        self.circles.push((circle, generation));
+
+        self.fill_gap([a, b, circle], c, generation + 1);
+        self.fill_gap([a, c, circle], b, generation + 1);
+        self.fill_gap([b, c, circle], a, generation + 1);
+    }
+}
+
+pub struct ApollonianGasket;
+
+impl ApollonianGasket {
+    fn paint<C: Canvas>(params: FractalParams) -> Result<C, String> {
+        let depth = params.recursion_depth.unwrap_or(7);
+        let scheme = ColorScheme::from_str(params.color_scheme.as_deref().unwrap_or("default"));
+        let colors = GeometryColors::from_params(&params)?;
+
+        let mut canvas = C::blank(params.width, params.height, colors.background);
+
+        // The outer circle has radius 1 (curvature -1) and fills the shorter side
+        let scale = params.width.min(params.height) as f64 / 2.0 * (1.0 - 2.0 * PADDING);
+        let pixel_cutoff = scale / MIN_RADIUS_PX;
+        let max_curvature = params
+            .max_curvature
+            .map_or(pixel_cutoff, |limit| limit.min(pixel_cutoff));
+
+        let outer = Circle {
+            curvature: -1.0,
+            center: Complex::ZERO,
+        };
+        // Three equal circles touching each other and the outer one
+        let inner_curvature = 1.0 + 2.0 / 3f64.sqrt();
+        let distance = 1.0 - 1.0 / inner_curvature;
+        let [a, b, c] = [90.0f64, 210.0, 330.0].map(|degrees| {
+            let angle = degrees.to_radians();
+            Circle {
+                curvature: inner_curvature,
+                center: Complex::new(distance * angle.cos(), distance * angle.sin()),
+            }
+        });
+
+        let mut gasket = Gasket {
+            max_depth: depth,
+            max_curvature,
+            circles: vec![(a, 0), (b, 0), (c, 0)],
+        };
+        gasket.fill_gap([a, b, c], outer, 1);
+        gasket.fill_gap([outer, a, b], c, 1);
+        gasket.fill_gap([outer, b, c], a, 1);
+        gasket.fill_gap([outer, a, c], b, 1);
+
+        // y axis up, origin at the image center
+        let to_pixel = |point: Complex| {
+            (
+                params.width as f64 / 2.0 + point.re * scale,
+                params.height as f64 / 2.0 - point.im * scale,
+            )
+        };
+
+        for (circle, generation) in &gasket.circles {
+            let color = colors.fill.unwrap_or_else(|| {
+                normalized_to_color(*generation as f64 / (depth + 1) as f64, &scheme)
+            });
+            canvas.disc(to_pixel(circle.center), scale / circle.curvature, color);
+        }
+        let outline = colors.stroke.unwrap_or(OUTLINE_COLOR);
+        canvas.ring(to_pixel(outer.center), scale, 2.0, outline);
+
+        Ok(canvas)
+    }
+}
+
+impl Fractal for ApollonianGasket {
+    fn generate(&self, params: FractalParams) -> Result<RgbImage, String> {
+        self.validate_params(&params)?;
+        Self::paint(params)
+    }
+
+    fn generate_vector(
+        &self,
+        params: FractalParams,
+        format: VectorFormat,
+    ) -> Result<Vec<u8>, String> {
+        self.validate_params(&params)?;
+        Self::paint::<VectorBuilder>(params)?.build(format)
+    }
+
+    fn name(&self) -> &str {
+        "apollonian"
+    }
+
+    fn validate_params(&self, params: &FractalParams) -> Result<(), String> {
+        default_validate_params(params)?;
+
+        if let Some(depth) = params.recursion_depth {
+            validate_recursion_depth(depth)?;
+        }
+        if let Some(max_curvature) = params.max_curvature {
+            validate_max_curvature(max_curvature)?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,267 @@
+//! Strange attractors: iterate a chaotic map for many points and plot where they land as a
+//! log-tone-mapped density image.
+
+use super::traits::{default_validate_params, Fractal, FractalParams};
+use crate::rendering::colors::ColorScheme;
+use crate::rendering::density::{DensityBuffer, ToneCurve, ToneMap};
+use crate::utils::rng::Rng;
+use crate::utils::validation::{validate_attractor_coefficient, validate_point_count};
+use image::RgbImage;
+use rayon::prelude::*;
+use std::ops::RangeInclusive;
+
+/// Values accepted by `attractor`
+pub const ATTRACTOR_MAPS: &[&str] = &["clifford", "dejong", "lorenz"];
+
+const DEFAULT_POINTS: u64 = 2_000_000;
+const DEFAULT_SEED: u64 = 0xA77;
+
+/// Number of independently seeded point chunks; fixed so results don't depend on thread count
+const POINT_CHUNKS: u64 = 64;
+
+/// Iterations discarded per chunk before plotting, so points settle onto the attractor
+const WARMUP_ITERATIONS: u32 = 200;
+
+/// Points used to estimate the attractor's bounding box
+const BOUNDS_SAMPLES: u32 = 50_000;
+
+/// Fraction of the image left as margin around the attractor
+const PADDING: f64 = 0.05;
+
+/// Euler step for the Lorenz system, in its time units
+const LORENZ_DT: f64 = 0.005;
+
+type Point = (f64, f64, f64);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Map {
+    /// x' = sin(a y) + c cos(a x), y' = sin(b x) + d cos(b y)
+    Clifford,
+    /// x' = sin(a y) - cos(b x), y' = sin(c x) - cos(d y)
+    DeJong,
+    /// Lorenz system with sigma = a, rho = b, beta = c, seen from the side (x against z)
+    Lorenz,
+}
+
+impl Map {
+    fn parse(name: &str) -> Result<Self, String> {
+        match name.to_lowercase().as_str() {
+            "clifford" => Ok(Map::Clifford),
+            "dejong" => Ok(Map::DeJong),
+            "lorenz" => Ok(Map::Lorenz),
+            _ => Err(format!(
+                "Invalid attractor. Must be one of: {}.",
+                ATTRACTOR_MAPS.join(", ")
+            )),
+        }
+    }
+
+    /// Coefficients a, b, c, d that draw a well-known picture
+    fn defaults(self) -> [f64; 4] {
+        match self {
+            Map::Clifford => [-1.4, 1.6, 1.0, 0.7],
+            Map::DeJong => [1.4, -2.3, 2.4, -2.1],
+            Map::Lorenz => [10.0, 28.0, 8.0 / 3.0, 0.0],
+        }
+    }
+
+    /// Accepted range of each coefficient, None for coefficients the map doesn't use
+    fn ranges(self) -> [Option<RangeInclusive<f64>>; 4] {
+        match self {
+            Map::Clifford | Map::DeJong => std::array::from_fn(|_| Some(-5.0..=5.0)),
+            Map::Lorenz => [Some(0.1..=50.0), Some(0.1..=100.0), Some(0.1..=10.0), None],
+        }
+    }
+
+    fn step(self, [a, b, c, d]: [f64; 4], (x, y, z): Point) -> Point {
+        match self {
+            Map::Clifford => (
+                (a * y).sin() + c * (a * x).cos(),
+                (b * x).sin() + d * (b * y).cos(),
+                0.0,
+            ),
+            Map::DeJong => (
+                (a * y).sin() - (b * x).cos(),
+                (c * x).sin() - (d * y).cos(),
+                0.0,
+            ),
+            Map::Lorenz => (
+                x + LORENZ_DT * a * (y - x),
+                y + LORENZ_DT * (x * (b - z) - y),
+                z + LORENZ_DT * (x * y - c * z),
+            ),
+        }
+    }
+
+    /// Plane the points are plotted in (y axis up)
+    fn project(self, (x, y, z): Point) -> (f64, f64) {
+        match self {
+            Map::Clifford | Map::DeJong => (x, y),
+            Map::Lorenz => (x, z),
+        }
+    }
+
+    /// Starting point of a chunk; off the Lorenz system's fixed point at the origin
+    fn start(self, rng: &mut Rng) -> Point {
+        match self {
+            Map::Clifford | Map::DeJong => (rng.range(-1.0, 1.0), rng.range(-1.0, 1.0), 0.0),
+            Map::Lorenz => (
+                rng.range(-1.0, 1.0),
+                rng.range(-1.0, 1.0),
+                rng.range(20.0, 30.0),
+            ),
+        }
+    }
+}
+
+/// A map with its coefficients
+struct Attractor {
+    map: Map,
+    coefficients: [f64; 4],
+}
+
+impl Attractor {
+    fn from_params(params: &FractalParams) -> Result<Self, String> {
+        let map = Map::parse(params.attractor.as_deref().unwrap_or("clifford"))?;
+        let defaults = map.defaults();
+        let given = [
+            params.attractor_a,
+            params.attractor_b,
+            params.attractor_c,
+            params.attractor_d,
+        ];
+
+        let mut coefficients = defaults;
+        for (index, ((value, range), name)) in given
+            .into_iter()
+            .zip(map.ranges())
+            .zip(["attractor_a", "attractor_b", "attractor_c", "attractor_d"])
+            .enumerate()
+        {
+            match (value, range) {
+                (Some(value), Some(range)) => {
+                    validate_attractor_coefficient(name, value, &range)?;
+                    coefficients[index] = value;
+                }
+                (Some(_), None) => {
+                    return Err(format!("{} is not used by this attractor.", name));
+                }
+                (None, _) => {}
+            }
+        }
+
+        Ok(Self { map, coefficients })
+    }
+
+    fn step(&self, point: Point) -> Point {
+        self.map.step(self.coefficients, point)
+    }
+
+    /// Approximate bounding box of the projected attractor: (min_x, min_y, max_x, max_y)
+    fn bounds(&self, seed: u64) -> Result<(f64, f64, f64, f64), String> {
+        let mut rng = Rng::new(seed);
+        let mut point = self.map.start(&mut rng);
+        for _ in 0..WARMUP_ITERATIONS {
+            point = self.step(point);
+        }
+
+        let (x, y) = self.map.project(point);
+        let mut bounds = (x, y, x, y);
+        for _ in 0..BOUNDS_SAMPLES {
+            point = self.step(point);
+            let (x, y) = self.map.project(point);
+            bounds = (
+                bounds.0.min(x),
+                bounds.1.min(y),
+                bounds.2.max(x),
+                bounds.3.max(y),
+            );
+        }
+
+        let (min_x, min_y, max_x, max_y) = bounds;
+        if [min_x, min_y, max_x, max_y].iter().all(|v| v.is_finite()) {
+            Ok(bounds)
+        } else {
+            Err("Attractor diverges with these coefficients.".to_string())
+        }
+    }
+
+    /// Iterate `points` points in fixed chunks and count the hits per pixel. zoom scales
+    /// around the attractor's centre and center_x/center_y pan in attractor units.
+    fn accumulate(&self, params: &FractalParams, points: u64) -> Result<DensityBuffer, String> {
+        let seed = params.seed.unwrap_or(DEFAULT_SEED);
+        let (width, height) = (params.width, params.height);
+
+        // Fit the attractor into the image, preserving its aspect ratio (y axis points up)
+        let (min_x, min_y, max_x, max_y) = self.bounds(seed)?;
+        let span_x = (max_x - min_x).max(1e-9);
+        let span_y = (max_y - min_y).max(1e-9);
+        let usable = 1.0 - 2.0 * PADDING;
+        let pixels_per_unit =
+            (width as f64 * usable / span_x).min(height as f64 * usable / span_y) * params.zoom;
+        let mid_x = (min_x + max_x) / 2.0 + params.center_x;
+        let mid_y = (min_y + max_y) / 2.0 + params.center_y;
+
+        let histogram = (0..POINT_CHUNKS)
+            .into_par_iter()
+            .fold(
+                || DensityBuffer::new(width, height),
+                |mut histogram, chunk| {
+                    let mut rng = Rng::for_chunk(seed, chunk);
+                    let chunk_points =
+                        points / POINT_CHUNKS + u64::from(chunk < points % POINT_CHUNKS);
+
+                    let mut point = self.map.start(&mut rng);
+                    for _ in 0..WARMUP_ITERATIONS {
+                        point = self.step(point);
+                    }
+                    for _ in 0..chunk_points {
+                        point = self.step(point);
+                        let (x, y) = self.map.project(point);
+                        histogram.plot(
+                            width as f64 / 2.0 + (x - mid_x) * pixels_per_unit,
+                            height as f64 / 2.0 - (y - mid_y) * pixels_per_unit,
+                        );
+                    }
+                    histogram
+                },
+            )
+            .reduce(|| DensityBuffer::new(width, height), DensityBuffer::merge);
+
+        Ok(histogram)
+    }
+}
+
+pub struct StrangeAttractor;
+
+impl Fractal for StrangeAttractor {
+    fn generate(&self, params: FractalParams) -> Result<RgbImage, String> {
+        self.validate_params(&params)?;
+
+        let attractor = Attractor::from_params(&params)?;
+        let tone = ToneMap::from_params(&params, ToneCurve::Log)?;
+        let points = params.samples.unwrap_or(DEFAULT_POINTS);
+        let scheme = ColorScheme::from_str(params.color_scheme.as_deref().unwrap_or("default"));
+
+        Ok(attractor
+            .accumulate(&params, points)?
+            .to_image(tone, &scheme))
+    }
+
+    fn name(&self) -> &str {
+        "attractor"
+    }
+
+    fn validate_params(&self, params: &FractalParams) -> Result<(), String> {
+        default_validate_params(params)?;
+
+        // samples is the number of plotted points
+        if let Some(points) = params.samples {
+            validate_point_count(points)?;
+        }
+        Attractor::from_params(params)?;
+        ToneMap::from_params(params, ToneCurve::Log)?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,44 @@
+use super::ifs::{AffineTransform, IfsSystem, DEFAULT_TONE_CURVE};
+use super::traits::{default_validate_params, Fractal, FractalParams};
+use crate::rendering::density::ToneMap;
+use crate::utils::validation::validate_point_count;
+use image::RgbImage;
+
+const DEFAULT_POINTS: u64 = 500_000;
+
+/// Barnsley's original four transforms: stem, successively smaller leaflets, left and right leaflets
+const FERN_TRANSFORMS: [AffineTransform; 4] = [
+    AffineTransform::new([0.0, 0.0, 0.0, 0.16, 0.0, 0.0], 0.01),
+    AffineTransform::new([0.85, 0.04, -0.04, 0.85, 0.0, 1.6], 0.85),
+    AffineTransform::new([0.2, -0.26, 0.23, 0.22, 0.0, 1.6], 0.07),
+    AffineTransform::new([-0.15, 0.28, 0.26, 0.24, 0.0, 0.44], 0.07),
+];
+
+pub struct BarnsleyFern;
+
+impl Fractal for BarnsleyFern {
+    fn generate(&self, params: FractalParams) -> Result<RgbImage, String> {
+        self.validate_params(&params)?;
+
+        let points = params.samples.unwrap_or(DEFAULT_POINTS);
+        let system = IfsSystem::new(FERN_TRANSFORMS.to_vec());
+
+        system.render(&params, points)
+    }
+
+    fn name(&self) -> &str {
+        "barnsley"
+    }
+
+    fn validate_params(&self, params: &FractalParams) -> Result<(), String> {
+        default_validate_params(params)?;
+
+        // samples is the number of plotted points
+        if let Some(points) = params.samples {
+            validate_point_count(points)?;
+        }
+        ToneMap::from_params(params, DEFAULT_TONE_CURVE)?;
+
+        Ok(())
+    }
+}
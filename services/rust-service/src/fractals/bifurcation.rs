@@ -0,0 +1,130 @@
+//! Bifurcation diagram of the logistic map x' = r x (1 - x): for every rate r along the
+//! horizontal axis, the values the orbit keeps visiting once it has settled.
+
+use super::traits::{default_validate_params, Fractal, FractalParams};
+use crate::rendering::colors::{normalized_to_color, ColorScheme};
+use crate::rendering::density::{ToneCurve, ToneMap};
+use crate::utils::validation::{validate_interval, validate_points_per_column};
+use image::{ImageBuffer, Rgb, RgbImage};
+use rayon::prelude::*;
+
+/// Rates shown by default: the period-doubling cascade into chaos
+const DEFAULT_R_RANGE: (f64, f64) = (2.5, 4.0);
+const DEFAULT_Y_RANGE: (f64, f64) = (0.0, 1.0);
+
+/// Orbits stay in [0, 1] only for rates up to 4
+const R_LIMITS: std::ops::RangeInclusive<f64> = 0.0..=4.0;
+const Y_LIMITS: std::ops::RangeInclusive<f64> = -1.0..=2.0;
+
+const DEFAULT_POINTS_PER_COLUMN: u64 = 2000;
+
+/// Iterations discarded before plotting, so the orbit settles onto its attractor
+const WARMUP_ITERATIONS: u32 = 1000;
+
+/// Rates sampled across each column's width, which smooths the vertical branches
+const RATES_PER_COLUMN: u32 = 4;
+
+/// The r and y intervals to draw
+struct Window {
+    r: (f64, f64),
+    y: (f64, f64),
+}
+
+impl Window {
+    fn from_params(params: &FractalParams) -> Result<Self, String> {
+        let r = (
+            params.bifurcation_r_min.unwrap_or(DEFAULT_R_RANGE.0),
+            params.bifurcation_r_max.unwrap_or(DEFAULT_R_RANGE.1),
+        );
+        let y = (
+            params.bifurcation_y_min.unwrap_or(DEFAULT_Y_RANGE.0),
+            params.bifurcation_y_max.unwrap_or(DEFAULT_Y_RANGE.1),
+        );
+        validate_interval("bifurcation_r", r.0, r.1, &R_LIMITS)?;
+        validate_interval("bifurcation_y", y.0, y.1, &Y_LIMITS)?;
+        Ok(Self { r, y })
+    }
+
+    /// Hit counts of one column, top row first
+    fn column(&self, column: u32, width: u32, height: u32, points: u64) -> Vec<u32> {
+        let mut counts = vec![0u32; height as usize];
+        let (y_min, y_max) = self.y;
+        let rows_per_unit = height as f64 / (y_max - y_min);
+
+        for sub in 0..RATES_PER_COLUMN {
+            let offset = (sub as f64 + 0.5) / RATES_PER_COLUMN as f64;
+            let r = self.r.0 + (column as f64 + offset) / width as f64 * (self.r.1 - self.r.0);
+            let rate_points = points / RATES_PER_COLUMN as u64
+                + u64::from((sub as u64) < points % RATES_PER_COLUMN as u64);
+
+            let mut x = 0.5;
+            for _ in 0..WARMUP_ITERATIONS {
+                x = r * x * (1.0 - x);
+            }
+            for _ in 0..rate_points {
+                x = r * x * (1.0 - x);
+                let row = (y_max - x) * rows_per_unit;
+                if row >= 0.0 && row < height as f64 {
+                    let count = &mut counts[row as usize];
+                    *count = count.saturating_add(1);
+                }
+            }
+        }
+
+        counts
+    }
+}
+
+pub struct LogisticBifurcation;
+
+impl Fractal for LogisticBifurcation {
+    fn generate(&self, params: FractalParams) -> Result<RgbImage, String> {
+        self.validate_params(&params)?;
+
+        let window = Window::from_params(&params)?;
+        let tone = ToneMap::from_params(&params, ToneCurve::Log)?;
+        let (width, height) = (params.width, params.height);
+        let points = params.samples.unwrap_or(DEFAULT_POINTS_PER_COLUMN);
+        let scheme = ColorScheme::from_str(params.color_scheme.as_deref().unwrap_or("default"));
+
+        // Each column is an independent orbit, so columns render in parallel. Counts are
+        // tone-mapped per column: a chaotic band spreads its points over many rows and would
+        // otherwise vanish next to a periodic orbit that hits the same few rows every time.
+        let columns: Vec<Vec<[u8; 3]>> = (0..width)
+            .into_par_iter()
+            .map(|column| {
+                let counts = window.column(column, width, height, points);
+                let field: Vec<f64> = counts.iter().map(|&count| count as f64).collect();
+                counts
+                    .iter()
+                    .zip(tone.apply(&field))
+                    .map(|(&count, value)| match count {
+                        0 => [0, 0, 0],
+                        _ => normalized_to_color(value, &scheme),
+                    })
+                    .collect()
+            })
+            .collect();
+
+        let img: RgbImage =
+            ImageBuffer::from_fn(width, height, |x, y| Rgb(columns[x as usize][y as usize]));
+        Ok(img)
+    }
+
+    fn name(&self) -> &str {
+        "bifurcation"
+    }
+
+    fn validate_params(&self, params: &FractalParams) -> Result<(), String> {
+        default_validate_params(params)?;
+
+        // samples is the number of plotted points per column
+        if let Some(points) = params.samples {
+            validate_points_per_column(points)?;
+        }
+        Window::from_params(params)?;
+        ToneMap::from_params(params, ToneCurve::Log)?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,140 @@
+use super::traits::{default_validate_params, Fractal, FractalParams, PlaneView};
+use crate::rendering::colors::ColorScheme;
+use crate::rendering::density::{DensityBuffer, ToneCurve, ToneMap};
+use crate::utils::rng::Rng;
+use crate::utils::validation::validate_sample_budget;
+use image::RgbImage;
+use rayon::prelude::*;
+
+pub const DEFAULT_SAMPLES: u64 = 1_000_000;
+pub const DEFAULT_SEED: u64 = 0x5EED;
+
+/// Number of independently seeded sample chunks; fixed so results don't depend on thread count
+const SAMPLE_CHUNKS: u64 = 64;
+
+/// Region of the complex plane that c is sampled from (bounds the escape radius)
+const SAMPLE_MIN: (f64, f64) = (-2.0, -2.0);
+const SAMPLE_MAX: (f64, f64) = (2.0, 2.0);
+
+/// Buddhabrot: density of the escape orbits of points outside the Mandelbrot set
+pub struct Buddhabrot;
+
+/// Pixel mapping of the rendered view
+#[derive(Clone, Copy)]
+pub struct View {
+    min_x: f64,
+    min_y: f64,
+    pixels_per_unit: f64,
+}
+
+impl View {
+    pub fn new(params: &FractalParams) -> Self {
+        let scale = 2.0 / params.zoom;
+        let aspect_ratio = params.width as f64 / params.height as f64;
+        Self {
+            min_x: params.center_x - scale * aspect_ratio,
+            min_y: params.center_y - scale,
+            pixels_per_unit: params.height as f64 / (2.0 * scale),
+        }
+    }
+
+    fn to_pixel(self, x: f64, y: f64) -> (f64, f64) {
+        (
+            (x - self.min_x) * self.pixels_per_unit,
+            (y - self.min_y) * self.pixels_per_unit,
+        )
+    }
+}
+
+impl Fractal for Buddhabrot {
+    fn generate(&self, params: FractalParams) -> Result<RgbImage, String> {
+        self.validate_params(&params)?;
+
+        let tone = ToneMap::from_params(&params, ToneCurve::Sqrt)?;
+        let histogram = accumulate_orbits(&params, params.max_iterations);
+
+        // Classic Buddhabrot look unless a palette was requested
+        let scheme = ColorScheme::from_str(params.color_scheme.as_deref().unwrap_or("grayscale"));
+
+        Ok(histogram.to_image(tone, &scheme))
+    }
+
+    fn name(&self) -> &str {
+        "buddhabrot"
+    }
+
+    fn plane_view(&self) -> Option<PlaneView> {
+        Some(PlaneView::centered(2.0))
+    }
+
+    fn validate_params(&self, params: &FractalParams) -> Result<(), String> {
+        default_validate_params(params)?;
+        ToneMap::from_params(params, ToneCurve::Sqrt)?;
+        validate_sample_budget(
+            params.samples.unwrap_or(DEFAULT_SAMPLES),
+            params.max_iterations,
+        )
+    }
+}
+
+/// Sample c values, and plot the orbits of those that escape within max_iterations
+pub fn accumulate_orbits(params: &FractalParams, max_iterations: u32) -> DensityBuffer {
+    let view = View::new(params);
+    let samples = params.samples.unwrap_or(DEFAULT_SAMPLES);
+    let seed = params.seed.unwrap_or(DEFAULT_SEED);
+    let (width, height) = (params.width, params.height);
+
+    (0..SAMPLE_CHUNKS)
+        .into_par_iter()
+        .fold(
+            || DensityBuffer::new(width, height),
+            |mut histogram, chunk| {
+                let mut rng = Rng::for_chunk(seed, chunk);
+                let chunk_samples =
+                    samples / SAMPLE_CHUNKS + u64::from(chunk < samples % SAMPLE_CHUNKS);
+                let mut orbit = Vec::with_capacity(max_iterations as usize);
+
+                for _ in 0..chunk_samples {
+                    let cx = rng.range(SAMPLE_MIN.0, SAMPLE_MAX.0);
+                    let cy = rng.range(SAMPLE_MIN.1, SAMPLE_MAX.1);
+
+                    if escaping_orbit(cx, cy, max_iterations, &mut orbit) {
+                        for &(x, y) in &orbit {
+                            let (px, py) = view.to_pixel(x, y);
+                            histogram.plot(px, py);
+                        }
+                    }
+                }
+
+                histogram
+            },
+        )
+        .reduce(|| DensityBuffer::new(width, height), DensityBuffer::merge)
+}
+
+/// Fill `orbit` with the iterates of c and report whether it escaped
+fn escaping_orbit(cx: f64, cy: f64, max_iterations: u32, orbit: &mut Vec<(f64, f64)>) -> bool {
+    orbit.clear();
+
+    // Points in the main cardioid or period-2 bulb never escape
+    let q = (cx - 0.25) * (cx - 0.25) + cy * cy;
+    if q * (q + (cx - 0.25)) <= 0.25 * cy * cy || (cx + 1.0) * (cx + 1.0) + cy * cy <= 0.0625 {
+        return false;
+    }
+
+    let mut x = 0.0;
+    let mut y = 0.0;
+
+    for _ in 0..max_iterations {
+        let x_temp = x * x - y * y + cx;
+        y = 2.0 * x * y + cy;
+        x = x_temp;
+
+        if x * x + y * y > 4.0 {
+            return true;
+        }
+        orbit.push((x, y));
+    }
+
+    false
+}
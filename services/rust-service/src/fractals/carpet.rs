@@ -0,0 +1,69 @@
+use super::subdivision::{render_subdivided, Cell};
+use super::traits::{default_validate_params, Fractal, FractalParams};
+use crate::rendering::canvas::Canvas;
+use crate::rendering::colors::{ColorScheme, GeometryColors};
+use crate::rendering::vector::{VectorBuilder, VectorFormat};
+use crate::utils::validation::validate_recursion_depth;
+use image::RgbImage;
+
+/// Every cell of the 3x3 grid except the center
+const CARPET_CELLS: [Cell; 8] = [
+    (0, 0),
+    (1, 0),
+    (2, 0),
+    (0, 1),
+    (2, 1),
+    (0, 2),
+    (1, 2),
+    (2, 2),
+];
+
+/// Sierpinski carpet: repeatedly remove the center ninth of each square
+pub struct SierpinskiCarpet;
+
+impl SierpinskiCarpet {
+    fn paint<C: Canvas>(params: FractalParams) -> Result<C, String> {
+        let depth = params.recursion_depth.unwrap_or(4);
+        let scheme = ColorScheme::from_str(params.color_scheme.as_deref().unwrap_or("default"));
+        let colors = GeometryColors::from_params(&params)?;
+
+        Ok(render_subdivided(
+            params.width,
+            params.height,
+            &CARPET_CELLS,
+            depth,
+            &scheme,
+            &colors,
+        ))
+    }
+}
+
+impl Fractal for SierpinskiCarpet {
+    fn generate(&self, params: FractalParams) -> Result<RgbImage, String> {
+        self.validate_params(&params)?;
+        Self::paint(params)
+    }
+
+    fn generate_vector(
+        &self,
+        params: FractalParams,
+        format: VectorFormat,
+    ) -> Result<Vec<u8>, String> {
+        self.validate_params(&params)?;
+        Self::paint::<VectorBuilder>(params)?.build(format)
+    }
+
+    fn name(&self) -> &str {
+        "carpet"
+    }
+
+    fn validate_params(&self, params: &FractalParams) -> Result<(), String> {
+        default_validate_params(params)?;
+
+        if let Some(depth) = params.recursion_depth {
+            validate_recursion_depth(depth)?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,214 @@
+use super::traits::{
+    default_validate_params, reject_distance_coloring, reject_period_coloring, Fractal,
+    FractalParams, PlaneView, StatsHeaders,
+};
+use crate::rendering::colors::{
+    color_escapes, Channel, ColorScheme, Coloring, Escape, PaletteCycle,
+};
+use crate::rendering::orbit_trap::{OrbitTrap, TrapTracker};
+use crate::rendering::shading::Shading;
+use crate::rendering::Rgb16Image;
+use crate::utils::complex::Complex;
+use crate::utils::expression::Formula;
+use crate::utils::validation::{validate_formula_budget, validate_julia_params};
+use image::RgbImage;
+use rayon::prelude::*;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+/// Squared magnitude beyond which the orbit is considered escaped
+const BAILOUT_SQR: f64 = 4.0;
+
+/// Wall-clock limit for one render, on top of the work budget checked up front
+const TIME_BUDGET: Duration = Duration::from_secs(10);
+
+/// Escape-time fractal of a user-supplied formula, iterating z = f(z, c). z starts at the pixel;
+/// c is the pixel too, or julia_c_* when given (Julia-style).
+pub struct CustomFormula;
+
+impl CustomFormula {
+    /// First pass of a render: escape time and closest trap approach for every pixel
+    fn first_pass(&self, params: &FractalParams) -> Result<Vec<Escape>, String> {
+        let trap = OrbitTrap::from_params(params)?;
+
+        let FractalParams {
+            width,
+            height,
+            zoom,
+            center_x,
+            center_y,
+            max_iterations,
+            julia_c_real,
+            julia_c_imag,
+            ref formula,
+            ..
+        } = *params;
+
+        let formula = Formula::parse(formula.as_deref().unwrap_or("z^2 + c"))?;
+        validate_formula_budget(width, height, max_iterations, formula.op_count())?;
+
+        // Julia-style rendering needs both parts of c
+        let julia_c = match (julia_c_real, julia_c_imag) {
+            (Some(c_real), Some(c_imag)) => Some(Complex::new(c_real, c_imag)),
+            (None, None) => None,
+            _ => {
+                return Err(
+                    "julia_c_real and julia_c_imag must be given together for custom".to_string(),
+                )
+            }
+        };
+
+        // Calculate the complex plane bounds
+        let aspect_ratio = width as f64 / height as f64;
+        let scale = 4.0 / zoom;
+        let min_x = center_x - scale * aspect_ratio;
+        let max_x = center_x + scale * aspect_ratio;
+        let min_y = center_y - scale;
+        let max_y = center_y + scale;
+
+        // Rows give up once the time budget is spent
+        let started = Instant::now();
+        let timed_out = AtomicBool::new(false);
+
+        // Every pixel in parallel
+        let escapes: Vec<Escape> = (0..height)
+            .into_par_iter()
+            .flat_map(|y| {
+                let formula = &formula;
+                let timed_out = &timed_out;
+                let trap = trap.as_ref();
+                let mut stack = formula.stack();
+                (0..width)
+                    .map(move |x| {
+                        if timed_out.load(Ordering::Relaxed) || started.elapsed() > TIME_BUDGET {
+                            timed_out.store(true, Ordering::Relaxed);
+                            return Escape::from(max_iterations);
+                        }
+
+                        // Map pixel coordinates to complex plane
+                        let px = min_x + (x as f64 / width as f64) * (max_x - min_x);
+                        let py = min_y + (y as f64 / height as f64) * (max_y - min_y);
+                        let pixel = Complex::new(px, py);
+
+                        // Compute the formula's iteration
+                        let c = julia_c.unwrap_or(pixel);
+                        formula_escape(formula, pixel, c, max_iterations, &mut stack, trap)
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+
+        if timed_out.load(Ordering::Relaxed) {
+            return Err(format!(
+                "Formula render exceeded the {} s time budget. Lower max_iterations or the image size.",
+                TIME_BUDGET.as_secs()
+            ));
+        }
+
+        Ok(escapes)
+    }
+
+    /// The render at 8 or 16 bits per channel
+    fn generate_at_depth<C: Channel>(&self, params: FractalParams) -> Result<C::Image, String> {
+        self.validate_params(&params)?;
+        let cycle = PaletteCycle::from_params(&params)?;
+        let shading = Shading::from_params(&params)?;
+
+        // First pass: escape time and closest trap approach for every pixel
+        let escapes = self.first_pass(&params)?;
+
+        let FractalParams {
+            width,
+            height,
+            max_iterations,
+            color_scheme,
+            coloring,
+            ..
+        } = params;
+        let scheme = ColorScheme::from_str(color_scheme.as_deref().unwrap_or("default"));
+        let coloring = Coloring::from_param(coloring.as_deref())?;
+
+        // Second pass: map escapes to color, lighting slopes if asked
+        let mut pixels = color_escapes(&escapes, max_iterations, &scheme, coloring, &cycle);
+        shading.apply(&mut pixels, &escapes, width, height, max_iterations);
+
+        Ok(C::image(width, height, pixels))
+    }
+}
+
+impl Fractal for CustomFormula {
+    fn generate(&self, params: FractalParams) -> Result<RgbImage, String> {
+        self.generate_at_depth::<u8>(params)
+    }
+
+    fn generate16(
+        &self,
+        params: FractalParams,
+    ) -> Result<(Rgb16Image, Vec<(String, String)>), String> {
+        self.generate_at_depth::<u16>(params)
+            .map(|img| (img, Vec::new()))
+    }
+
+    fn generate_escapes(
+        &self,
+        params: FractalParams,
+        _final_z: bool,
+    ) -> Result<(Vec<Escape>, StatsHeaders), String> {
+        self.validate_params(&params)?;
+        self.first_pass(&params).map(|escapes| (escapes, Vec::new()))
+    }
+
+    fn name(&self) -> &str {
+        "custom"
+    }
+
+    fn plane_view(&self) -> Option<PlaneView> {
+        Some(PlaneView::centered(4.0))
+    }
+
+    fn validate_params(&self, params: &FractalParams) -> Result<(), String> {
+        default_validate_params(params)?;
+        reject_distance_coloring(params, self.name())?;
+        reject_period_coloring(params, self.name())?;
+        if params.adaptive == Some(true) {
+            return Err("adaptive is not supported for type=custom.".to_string());
+        }
+
+        // Validate the formula and its cost
+        if let Some(formula) = &params.formula {
+            let formula = Formula::parse(formula)?;
+            validate_formula_budget(
+                params.width,
+                params.height,
+                params.max_iterations,
+                formula.op_count(),
+            )?;
+        }
+        if let (Some(c_real), Some(c_imag)) = (params.julia_c_real, params.julia_c_imag) {
+            validate_julia_params(c_real, c_imag)?;
+        }
+
+        Ok(())
+    }
+}
+
+fn formula_escape(
+    formula: &Formula,
+    mut z: Complex,
+    c: Complex,
+    max_iterations: u32,
+    stack: &mut Vec<Complex>,
+    trap: Option<&OrbitTrap>,
+) -> Escape {
+    let mut tracker = TrapTracker::new(trap);
+    for iteration in 0..max_iterations {
+        // Overflow and poles count as escaping
+        if !z.is_finite() || z.norm_sqr() > BAILOUT_SQR {
+            return tracker.escape(iteration).with_final_z(z.re, z.im);
+        }
+        z = formula.eval(z, c, stack);
+        tracker.visit(z.re, z.im);
+    }
+
+    tracker.escape(max_iterations)
+}
@@ -0,0 +1,44 @@
+use super::ifs::{IfsSystem, DEFAULT_TONE_CURVE};
+use super::traits::{default_validate_params, Fractal, FractalParams};
+use crate::rendering::density::ToneMap;
+use crate::utils::validation::{parse_ifs_transforms, validate_point_count};
+use image::RgbImage;
+
+const DEFAULT_POINTS: u64 = 500_000;
+
+/// Iterated function system built from client-supplied affine transforms
+pub struct CustomIfs;
+
+impl Fractal for CustomIfs {
+    fn generate(&self, params: FractalParams) -> Result<RgbImage, String> {
+        self.validate_params(&params)?;
+
+        let transforms =
+            parse_ifs_transforms(params.ifs_transforms.as_deref().unwrap_or_default())?;
+        let points = params.samples.unwrap_or(DEFAULT_POINTS);
+        let system = IfsSystem::new(transforms);
+
+        system.render(&params, points)
+    }
+
+    fn name(&self) -> &str {
+        "ifs"
+    }
+
+    fn validate_params(&self, params: &FractalParams) -> Result<(), String> {
+        default_validate_params(params)?;
+
+        match &params.ifs_transforms {
+            Some(transforms) => parse_ifs_transforms(transforms)?,
+            None => return Err("Missing ifs_transforms for type=ifs.".to_string()),
+        };
+
+        // samples is the number of plotted points
+        if let Some(points) = params.samples {
+            validate_point_count(points)?;
+        }
+        ToneMap::from_params(params, DEFAULT_TONE_CURVE)?;
+
+        Ok(())
+    }
+}
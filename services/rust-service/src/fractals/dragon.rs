@@ -0,0 +1,77 @@
+use super::traits::{default_validate_params, Fractal, FractalParams};
+use crate::rendering::canvas::Canvas;
+use crate::rendering::colors::{ColorScheme, GeometryColors};
+use crate::rendering::draw::{draw_fitted_segments, Segment};
+use crate::rendering::vector::{VectorBuilder, VectorFormat};
+use crate::utils::validation::validate_recursion_depth;
+use image::RgbImage;
+
+/// Heighway dragon: a strip of paper folded in half `recursion_depth` times, unfolded to right angles
+pub struct DragonCurve;
+
+impl DragonCurve {
+    fn paint<C: Canvas>(params: FractalParams) -> Result<C, String> {
+        let depth = params.recursion_depth.unwrap_or(10);
+        let scheme = ColorScheme::from_str(params.color_scheme.as_deref().unwrap_or("default"));
+        let colors = GeometryColors::from_params(&params)?;
+
+        let mut canvas = C::blank(params.width, params.height, colors.background);
+
+        let points = dragon_points(depth);
+        let segments: Vec<Segment> = points.windows(2).map(|pair| (pair[0], pair[1])).collect();
+        let thickness = params.line_thickness.unwrap_or(1) as f64;
+        draw_fitted_segments(&mut canvas, &segments, &scheme, colors.stroke, thickness);
+
+        Ok(canvas)
+    }
+}
+
+impl Fractal for DragonCurve {
+    fn generate(&self, params: FractalParams) -> Result<RgbImage, String> {
+        self.validate_params(&params)?;
+        Self::paint(params)
+    }
+
+    fn generate_vector(
+        &self,
+        params: FractalParams,
+        format: VectorFormat,
+    ) -> Result<Vec<u8>, String> {
+        self.validate_params(&params)?;
+        Self::paint::<VectorBuilder>(params)?.build(format)
+    }
+
+    fn name(&self) -> &str {
+        "dragon"
+    }
+
+    fn validate_params(&self, params: &FractalParams) -> Result<(), String> {
+        default_validate_params(params)?;
+
+        // recursion_depth is the number of folds
+        if let Some(depth) = params.recursion_depth {
+            validate_recursion_depth(depth)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Vertices of the unfolded strip. Each fold appends a copy of the curve so far, rotated
+/// 90 degrees about its end point and traversed backwards.
+fn dragon_points(folds: u32) -> Vec<(f64, f64)> {
+    let mut points = vec![(0.0, 0.0), (1.0, 0.0)];
+
+    for _ in 0..folds {
+        let (pivot_x, pivot_y) = points[points.len() - 1];
+        let rotated: Vec<(f64, f64)> = points
+            .iter()
+            .rev()
+            .skip(1)
+            .map(|&(x, y)| (pivot_x - (y - pivot_y), pivot_y + (x - pivot_x)))
+            .collect();
+        points.extend(rotated);
+    }
+
+    points
+}
@@ -0,0 +1,98 @@
+use super::traits::{default_validate_params, Fractal, FractalParams};
+use crate::rendering::canvas::Canvas;
+use crate::rendering::colors::{ColorScheme, GeometryColors};
+use crate::rendering::draw::{draw_fitted_segments, Segment};
+use crate::rendering::vector::{VectorBuilder, VectorFormat};
+use crate::utils::validation::validate_recursion_depth;
+use image::RgbImage;
+
+/// Order 10 is already ~1M segments, past the point of visible detail at 4096px
+const MAX_ORDER: u32 = 10;
+
+/// Hilbert space-filling curve; the gradient runs along the traversal order
+pub struct HilbertCurve;
+
+impl HilbertCurve {
+    fn paint<C: Canvas>(params: FractalParams) -> Result<C, String> {
+        let order = params.recursion_depth.unwrap_or(5);
+        let scheme = ColorScheme::from_str(params.color_scheme.as_deref().unwrap_or("default"));
+        let colors = GeometryColors::from_params(&params)?;
+
+        let mut canvas = C::blank(params.width, params.height, colors.background);
+
+        let side = 1u64 << order;
+        let points: Vec<(f64, f64)> = (0..side * side)
+            .map(|index| hilbert_point(side, index))
+            .collect();
+        let segments: Vec<Segment> = points.windows(2).map(|pair| (pair[0], pair[1])).collect();
+        let thickness = params.line_thickness.unwrap_or(1) as f64;
+        draw_fitted_segments(&mut canvas, &segments, &scheme, colors.stroke, thickness);
+
+        Ok(canvas)
+    }
+}
+
+impl Fractal for HilbertCurve {
+    fn generate(&self, params: FractalParams) -> Result<RgbImage, String> {
+        self.validate_params(&params)?;
+        Self::paint(params)
+    }
+
+    fn generate_vector(
+        &self,
+        params: FractalParams,
+        format: VectorFormat,
+    ) -> Result<Vec<u8>, String> {
+        self.validate_params(&params)?;
+        Self::paint::<VectorBuilder>(params)?.build(format)
+    }
+
+    fn name(&self) -> &str {
+        "hilbert"
+    }
+
+    fn validate_params(&self, params: &FractalParams) -> Result<(), String> {
+        default_validate_params(params)?;
+
+        // recursion_depth is the curve order
+        if let Some(order) = params.recursion_depth {
+            validate_recursion_depth(order)?;
+            if order > MAX_ORDER {
+                return Err(format!(
+                    "Invalid recursion_depth for hilbert. Must be between 1 and {}.",
+                    MAX_ORDER
+                ));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Cell (x, y) visited at position `index` along a Hilbert curve covering a side x side grid
+fn hilbert_point(side: u64, index: u64) -> (f64, f64) {
+    let (mut x, mut y) = (0u64, 0u64);
+    let mut remaining = index;
+    let mut scale = 1;
+
+    while scale < side {
+        let rx = 1 & (remaining / 2);
+        let ry = 1 & (remaining ^ rx);
+
+        // Rotate the quadrant so the sub-curves join end to end
+        if ry == 0 {
+            if rx == 1 {
+                x = scale - 1 - x;
+                y = scale - 1 - y;
+            }
+            std::mem::swap(&mut x, &mut y);
+        }
+
+        x += scale * rx;
+        y += scale * ry;
+        remaining /= 4;
+        scale *= 2;
+    }
+
+    (x as f64, y as f64)
+}
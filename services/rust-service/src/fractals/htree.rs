@@ -0,0 +1,116 @@
+//! H-tree: an H whose four tips each carry a smaller H, drawn as alternating horizontal and
+//! vertical segments that shrink by a factor of sqrt(2) at every step.
+
+use super::traits::{default_validate_params, Fractal, FractalParams};
+use crate::rendering::canvas::Canvas;
+use crate::rendering::colors::{normalized_to_color, ColorScheme, GeometryColors};
+use crate::rendering::draw::Segment;
+use crate::rendering::vector::{VectorBuilder, VectorFormat};
+use crate::utils::validation::validate_recursion_depth;
+use image::RgbImage;
+
+/// Collect the tree's segments by level, stopping once they would be under a pixel;
+/// deeper levels wouldn't change the image
+fn collect(
+    levels: &mut Vec<Vec<Segment>>,
+    max_levels: u32,
+    center: (f64, f64),
+    length: f64,
+    level: u32,
+) {
+    if level >= max_levels || length < 1.0 {
+        return;
+    }
+
+    let half = length / 2.0;
+    let (start, end) = if level.is_multiple_of(2) {
+        ((center.0 - half, center.1), (center.0 + half, center.1))
+    } else {
+        ((center.0, center.1 - half), (center.0, center.1 + half))
+    };
+    if levels.len() <= level as usize {
+        levels.push(Vec::new());
+    }
+    levels[level as usize].push((start, end));
+
+    let next = length / std::f64::consts::SQRT_2;
+    collect(levels, max_levels, start, next, level + 1);
+    collect(levels, max_levels, end, next, level + 1);
+}
+
+pub struct HTree;
+
+impl HTree {
+    fn paint<C: Canvas>(params: FractalParams) -> Result<C, String> {
+        let colors = GeometryColors::from_params(&params)?;
+
+        let FractalParams {
+            width,
+            height,
+            recursion_depth,
+            color_scheme,
+            line_thickness,
+            ..
+        } = params;
+
+        let depth = recursion_depth.unwrap_or(6);
+        let thickness = line_thickness.unwrap_or(1);
+        let scheme = ColorScheme::from_str(color_scheme.as_deref().unwrap_or("default"));
+
+        let mut canvas = C::blank(width, height, colors.background);
+
+        // Horizontal levels add up to just under twice the first segment, vertical levels to
+        // just under sqrt(2) times it; fit both inside the padded image
+        let padding = 20.0 + thickness as f64 / 2.0;
+        let usable = |size: u32| (size as f64 - 2.0 * padding).max(1.0);
+        let length = (usable(width) / 2.0).min(usable(height) / std::f64::consts::SQRT_2);
+
+        // Each H is a horizontal and a vertical level
+        let max_levels = 2 * depth;
+        let mut levels = Vec::new();
+        let center = (width as f64 / 2.0, height as f64 / 2.0);
+        collect(&mut levels, max_levels, center, length, 0);
+
+        // Deepest first, so the trunk stays on top where thick strokes overlap
+        for (level, segments) in levels.iter().enumerate().rev() {
+            let color = colors
+                .stroke
+                .unwrap_or_else(|| normalized_to_color(level as f64 / max_levels as f64, &scheme));
+            for &(start, end) in segments {
+                canvas.line(start, end, thickness as f64, color);
+            }
+        }
+
+        Ok(canvas)
+    }
+}
+
+impl Fractal for HTree {
+    fn generate(&self, params: FractalParams) -> Result<RgbImage, String> {
+        self.validate_params(&params)?;
+        Self::paint(params)
+    }
+
+    fn generate_vector(
+        &self,
+        params: FractalParams,
+        format: VectorFormat,
+    ) -> Result<Vec<u8>, String> {
+        self.validate_params(&params)?;
+        Self::paint::<VectorBuilder>(params)?.build(format)
+    }
+
+    fn name(&self) -> &str {
+        "htree"
+    }
+
+    fn validate_params(&self, params: &FractalParams) -> Result<(), String> {
+        default_validate_params(params)?;
+
+        if let Some(depth) = params.recursion_depth {
+            validate_recursion_depth(depth)?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,273 @@
+use super::adaptive::{iterate_pixels, FirstPass};
+use super::antialias::Antialias;
+use super::kernels::MandelbrotVariant;
+use super::traits::{
+    default_validate_params, reject_distance_coloring, reject_period_coloring, Fractal,
+    FractalParams, PlaneView, StatsHeaders,
+};
+use crate::rendering::colors::{
+    color_escapes, Channel, ColorScheme, Coloring, Escape, PaletteCycle,
+};
+use crate::rendering::orbit_trap::{OrbitTrap, TrapTracker};
+use crate::rendering::shading::Shading;
+use crate::rendering::Rgb16Image;
+use crate::utils::validation::validate_julia_params;
+use image::RgbImage;
+
+/// Longest accepted hybrid_pattern
+const MAX_PATTERN_LENGTH: usize = 16;
+
+/// Pattern used when none is given: alternate Mandelbrot and Burning Ship steps
+const DEFAULT_PATTERN: &str = "MB";
+
+/// Letters accepted in hybrid_pattern
+const HYBRID_LETTERS: [char; 7] = ['M', 'J', 'B', 'T', 'C', 'P', 'F'];
+
+/// One step of a hybrid iteration
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum HybridFormula {
+    /// A member of the Mandelbrot family, shared with `variant`: M (classic), C (Celtic),
+    /// P (Perpendicular Mandelbrot) or F (Buffalo)
+    Family(MandelbrotVariant),
+    /// J: z^2 with the fixed Julia constant instead of c
+    Julia(f64, f64),
+    /// B: (|x| + i|y|)^2 + c
+    BurningShip,
+    /// T: conj(z)^2 + c
+    Tricorn,
+}
+
+impl HybridFormula {
+    /// Steps of a pattern such as "MMJB", applied in turn and repeated
+    fn parse_pattern(pattern: &str, julia_c: Option<(f64, f64)>) -> Result<Vec<Self>, String> {
+        if pattern.is_empty() || pattern.chars().count() > MAX_PATTERN_LENGTH {
+            return Err(format!(
+                "Invalid hybrid_pattern. Length must be between 1 and {}.",
+                MAX_PATTERN_LENGTH
+            ));
+        }
+
+        pattern
+            .chars()
+            .map(|letter| match letter.to_ascii_uppercase() {
+                'M' => Ok(HybridFormula::Family(MandelbrotVariant::Classic)),
+                'J' => julia_c
+                    .map(|(c_real, c_imag)| HybridFormula::Julia(c_real, c_imag))
+                    .ok_or_else(|| {
+                        "julia_c_real and julia_c_imag are required for J steps in hybrid_pattern"
+                            .to_string()
+                    }),
+                'B' => Ok(HybridFormula::BurningShip),
+                'T' => Ok(HybridFormula::Tricorn),
+                'C' => Ok(HybridFormula::Family(MandelbrotVariant::Celtic)),
+                'P' => Ok(HybridFormula::Family(
+                    MandelbrotVariant::PerpendicularMandelbrot,
+                )),
+                'F' => Ok(HybridFormula::Family(MandelbrotVariant::Buffalo)),
+                _ => Err(format!(
+                    "Invalid hybrid_pattern. Letters must be one of: {}.",
+                    HYBRID_LETTERS.map(String::from).join(", ")
+                )),
+            })
+            .collect()
+    }
+
+    #[inline]
+    fn step(self, x: f64, y: f64, cx: f64, cy: f64) -> (f64, f64) {
+        match self {
+            HybridFormula::Family(variant) => variant.step(x, y, cx, cy),
+            HybridFormula::Julia(kx, ky) => (x * x - y * y + kx, 2.0 * x * y + ky),
+            HybridFormula::BurningShip => (x * x - y * y + cx, 2.0 * (x * y).abs() + cy),
+            HybridFormula::Tricorn => (x * x - y * y + cx, -2.0 * x * y + cy),
+        }
+    }
+}
+
+/// Hybrid escape-time fractal over the c plane: each iteration from z = 0 takes the next step
+/// of hybrid_pattern, cycling through it, so e.g. "MB" alternates Mandelbrot and Burning Ship.
+pub struct HybridFractal;
+
+impl HybridFractal {
+    fn view(&self) -> PlaneView {
+        PlaneView {
+            half_height: 1.5,
+            origin: (-0.5, 0.0),
+            axes: ("Re c", "Im c"),
+        }
+    }
+}
+
+impl HybridFractal {
+    /// First pass of a render: escape time and closest trap approach for every pixel
+    fn first_pass(
+        &self,
+        params: &FractalParams,
+    ) -> Result<FirstPass<impl Fn(f64, f64, u32) -> Escape + Sync>, String> {
+        let trap = OrbitTrap::from_params(params)?;
+
+        let FractalParams {
+            width,
+            height,
+            max_iterations,
+            julia_c_real,
+            julia_c_imag,
+            ref hybrid_pattern,
+            adaptive,
+            ..
+        } = *params;
+
+        let julia_c = julia_c_real.zip(julia_c_imag);
+        let pattern = HybridFormula::parse_pattern(
+            hybrid_pattern.as_deref().unwrap_or(DEFAULT_PATTERN),
+            julia_c,
+        )?;
+
+        // Calculate the complex plane bounds
+        let bounds = self.view().bounds(params);
+
+        // One point at pixel coordinates with the given iteration limit
+        let escape_at = move |x: f64, y: f64, limit: u32| {
+            // Map pixel coordinates to complex plane
+            let cx = bounds.x_min + (x / width as f64) * (bounds.x_max - bounds.x_min);
+            let cy = bounds.y_min + (y / height as f64) * (bounds.y_max - bounds.y_min);
+
+            // Compute hybrid iteration
+            hybrid_escape(&pattern, cx, cy, limit, trap.as_ref())
+        };
+
+        // Escape time and closest trap approach for every pixel, in parallel
+        let (escapes, stats) = iterate_pixels(
+            width,
+            height,
+            max_iterations,
+            adaptive.unwrap_or(false),
+            |x, y, limit| escape_at(x as f64, y as f64, limit),
+        );
+
+        Ok(FirstPass {
+            escapes,
+            stats,
+            escape_at,
+        })
+    }
+
+    /// The render at 8 or 16 bits per channel
+    fn generate_at_depth<C: Channel>(
+        &self,
+        params: FractalParams,
+    ) -> Result<(C::Image, StatsHeaders), String> {
+        self.validate_params(&params)?;
+        let cycle = PaletteCycle::from_params(&params)?;
+        let antialias = Antialias::from_params(&params)?;
+        let shading = Shading::from_params(&params)?;
+
+        // First pass: escape time and closest trap approach for every pixel
+        let FirstPass {
+            escapes,
+            mut stats,
+            escape_at,
+        } = self.first_pass(&params)?;
+
+        let FractalParams {
+            width,
+            height,
+            max_iterations,
+            color_scheme,
+            coloring,
+            ..
+        } = params;
+        let scheme = ColorScheme::from_str(color_scheme.as_deref().unwrap_or("default"));
+        let coloring = Coloring::from_param(coloring.as_deref())?;
+
+        // Second pass: map escapes to color, supersampling edges and lighting slopes if asked
+        let (mut pixels, aa_stats) = antialias.color(
+            &escapes,
+            width,
+            height,
+            |x, y| escape_at(x, y, max_iterations),
+            |escapes| color_escapes(escapes, max_iterations, &scheme, coloring, &cycle),
+        );
+        stats.extend(aa_stats);
+        shading.apply(&mut pixels, &escapes, width, height, max_iterations);
+
+        Ok((C::image(width, height, pixels), stats))
+    }
+}
+
+impl Fractal for HybridFractal {
+    fn generate(&self, params: FractalParams) -> Result<RgbImage, String> {
+        self.generate_with_stats(params).map(|(img, _)| img)
+    }
+
+    fn generate_with_stats(
+        &self,
+        params: FractalParams,
+    ) -> Result<(RgbImage, Vec<(String, String)>), String> {
+        self.generate_at_depth::<u8>(params)
+    }
+
+    fn generate16(
+        &self,
+        params: FractalParams,
+    ) -> Result<(Rgb16Image, Vec<(String, String)>), String> {
+        self.generate_at_depth::<u16>(params)
+    }
+
+    fn generate_escapes(
+        &self,
+        params: FractalParams,
+        _final_z: bool,
+    ) -> Result<(Vec<Escape>, StatsHeaders), String> {
+        self.validate_params(&params)?;
+        self.first_pass(&params)
+            .map(|pass| (pass.escapes, pass.stats))
+    }
+
+    fn name(&self) -> &str {
+        "hybrid"
+    }
+
+    fn plane_view(&self) -> Option<PlaneView> {
+        Some(self.view())
+    }
+
+    fn validate_params(&self, params: &FractalParams) -> Result<(), String> {
+        default_validate_params(params)?;
+        reject_distance_coloring(params, self.name())?;
+        reject_period_coloring(params, self.name())?;
+
+        let julia_c = params.julia_c_real.zip(params.julia_c_imag);
+        if let Some((c_real, c_imag)) = julia_c {
+            validate_julia_params(c_real, c_imag)?;
+        }
+        if let Some(pattern) = &params.hybrid_pattern {
+            HybridFormula::parse_pattern(pattern, julia_c)?;
+        }
+
+        Ok(())
+    }
+}
+
+fn hybrid_escape(
+    pattern: &[HybridFormula],
+    cx: f64,
+    cy: f64,
+    max_iterations: u32,
+    trap: Option<&OrbitTrap>,
+) -> Escape {
+    let mut tracker = TrapTracker::new(trap);
+    let mut x = 0.0;
+    let mut y = 0.0;
+    let mut iteration = 0;
+
+    for formula in pattern.iter().cycle() {
+        if x * x + y * y > 4.0 || iteration >= max_iterations {
+            break;
+        }
+        (x, y) = formula.step(x, y, cx, cy);
+        tracker.visit(x, y);
+        iteration += 1;
+    }
+
+    tracker.escape(iteration).with_final_z(x, y)
+}
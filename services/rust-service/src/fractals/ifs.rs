@@ -0,0 +1,177 @@
+//! Shared iterated function system (IFS) machinery: the chaos game plus point-cloud
+//! rasterization into a density buffer.
+
+use super::traits::FractalParams;
+use crate::rendering::colors::ColorScheme;
+use crate::rendering::density::{DensityBuffer, ToneCurve, ToneMap};
+use crate::utils::rng::Rng;
+use image::RgbImage;
+use rayon::prelude::*;
+
+pub const DEFAULT_SEED: u64 = 0x1F5;
+
+/// Point clouds are sparse; a log curve keeps single hits visible
+pub const DEFAULT_TONE_CURVE: ToneCurve = ToneCurve::Log;
+
+/// Number of independently seeded point chunks; fixed so results don't depend on thread count
+const POINT_CHUNKS: u64 = 64;
+
+/// Iterations discarded per chunk before plotting, so points settle onto the attractor
+const WARMUP_ITERATIONS: u32 = 20;
+
+/// Points used to estimate the attractor's bounding box
+const BOUNDS_SAMPLES: u32 = 20_000;
+
+/// Fraction of the image left as margin around the attractor
+const PADDING: f64 = 0.05;
+
+/// (x, y) -> (a x + b y + e, c x + d y + f), chosen with the given probability
+#[derive(Clone, Copy, Debug)]
+pub struct AffineTransform {
+    pub a: f64,
+    pub b: f64,
+    pub c: f64,
+    pub d: f64,
+    pub e: f64,
+    pub f: f64,
+    pub probability: f64,
+}
+
+impl AffineTransform {
+    pub const fn new(coefficients: [f64; 6], probability: f64) -> Self {
+        let [a, b, c, d, e, f] = coefficients;
+        Self {
+            a,
+            b,
+            c,
+            d,
+            e,
+            f,
+            probability,
+        }
+    }
+
+    /// Largest factor by which the linear part stretches any vector (its top singular value).
+    /// Below 1 means the transform is a contraction.
+    pub fn max_stretch(&self) -> f64 {
+        let sum_squares = self.a * self.a + self.b * self.b + self.c * self.c + self.d * self.d;
+        let det = self.a * self.d - self.b * self.c;
+        let discriminant = (sum_squares * sum_squares - 4.0 * det * det).max(0.0);
+        ((sum_squares + discriminant.sqrt()) / 2.0).sqrt()
+    }
+
+    fn apply(&self, (x, y): (f64, f64)) -> (f64, f64) {
+        (
+            self.a * x + self.b * y + self.e,
+            self.c * x + self.d * y + self.f,
+        )
+    }
+}
+
+pub struct IfsSystem {
+    transforms: Vec<AffineTransform>,
+    cumulative: Vec<f64>,
+}
+
+impl IfsSystem {
+    pub fn new(transforms: Vec<AffineTransform>) -> Self {
+        // Normalize probabilities so they don't have to sum to exactly 1
+        let total: f64 = transforms.iter().map(|t| t.probability).sum();
+        let cumulative = transforms
+            .iter()
+            .scan(0.0, |acc, t| {
+                *acc += t.probability / total;
+                Some(*acc)
+            })
+            .collect();
+
+        Self {
+            transforms,
+            cumulative,
+        }
+    }
+
+    fn step(&self, point: (f64, f64), rng: &mut Rng) -> (f64, f64) {
+        let roll = rng.next_f64();
+        let index = self
+            .cumulative
+            .iter()
+            .position(|&threshold| roll < threshold)
+            .unwrap_or(self.transforms.len() - 1);
+        self.transforms[index].apply(point)
+    }
+
+    /// Approximate bounding box of the attractor: (min_x, min_y, max_x, max_y)
+    fn bounds(&self, seed: u64) -> (f64, f64, f64, f64) {
+        let mut rng = Rng::new(seed);
+        let mut point = (0.0, 0.0);
+        for _ in 0..WARMUP_ITERATIONS {
+            point = self.step(point, &mut rng);
+        }
+
+        let mut bounds = (point.0, point.1, point.0, point.1);
+        for _ in 0..BOUNDS_SAMPLES {
+            point = self.step(point, &mut rng);
+            bounds.0 = bounds.0.min(point.0);
+            bounds.1 = bounds.1.min(point.1);
+            bounds.2 = bounds.2.max(point.0);
+            bounds.3 = bounds.3.max(point.1);
+        }
+        bounds
+    }
+
+    /// Play the chaos game and accumulate `points` hits into a histogram fitted to the image.
+    /// zoom scales around the attractor's centre and center_x/center_y pan in IFS units.
+    pub fn accumulate(&self, params: &FractalParams, points: u64) -> DensityBuffer {
+        let seed = params.seed.unwrap_or(DEFAULT_SEED);
+        let (width, height) = (params.width, params.height);
+
+        // Fit the attractor into the image, preserving its aspect ratio (y axis points up)
+        let (min_x, min_y, max_x, max_y) = self.bounds(seed);
+        let span_x = (max_x - min_x).max(1e-9);
+        let span_y = (max_y - min_y).max(1e-9);
+        let usable = 1.0 - 2.0 * PADDING;
+        let pixels_per_unit =
+            (width as f64 * usable / span_x).min(height as f64 * usable / span_y) * params.zoom;
+        let mid_x = (min_x + max_x) / 2.0 + params.center_x;
+        let mid_y = (min_y + max_y) / 2.0 + params.center_y;
+
+        let to_pixel = |(x, y): (f64, f64)| {
+            (
+                width as f64 / 2.0 + (x - mid_x) * pixels_per_unit,
+                height as f64 / 2.0 - (y - mid_y) * pixels_per_unit,
+            )
+        };
+
+        (0..POINT_CHUNKS)
+            .into_par_iter()
+            .fold(
+                || DensityBuffer::new(width, height),
+                |mut histogram, chunk| {
+                    let mut rng = Rng::for_chunk(seed, chunk);
+                    let chunk_points =
+                        points / POINT_CHUNKS + u64::from(chunk < points % POINT_CHUNKS);
+
+                    let mut point = (0.0, 0.0);
+                    for _ in 0..WARMUP_ITERATIONS {
+                        point = self.step(point, &mut rng);
+                    }
+
+                    for _ in 0..chunk_points {
+                        point = self.step(point, &mut rng);
+                        let (px, py) = to_pixel(point);
+                        histogram.plot(px, py);
+                    }
+
+                    histogram
+                },
+            )
+            .reduce(|| DensityBuffer::new(width, height), DensityBuffer::merge)
+    }
+
+    pub fn render(&self, params: &FractalParams, points: u64) -> Result<RgbImage, String> {
+        let tone = ToneMap::from_params(params, DEFAULT_TONE_CURVE)?;
+        let scheme = ColorScheme::from_str(params.color_scheme.as_deref().unwrap_or("default"));
+        Ok(self.accumulate(params, points).to_image(tone, &scheme))
+    }
+}
@@ -1,14 +1,26 @@
-use super::traits::{default_validate_params, Fractal, FractalParams};
-use crate::rendering::colors::{iterations_to_color, ColorScheme};
-use crate::utils::validation::validate_julia_params;
-use image::{ImageBuffer, Rgb, RgbImage};
-use rayon::prelude::*;
+use super::adaptive::{iterate_pixels, FirstPass};
+use super::antialias::Antialias;
+use super::kernels::{cycle_period, estimate_distance, DISTANCE_BAILOUT_SQR};
+use super::traits::{default_validate_params, Fractal, FractalParams, PlaneView, StatsHeaders};
+use crate::rendering::colors::{
+    color_escapes, Channel, ColorScheme, Coloring, Escape, PaletteCycle, DEFAULT_BOUNDARY_WIDTH,
+};
+use crate::rendering::orbit_trap::{OrbitTrap, TrapTracker};
+use crate::rendering::shading::Shading;
+use crate::rendering::Rgb16Image;
+use crate::utils::complex::Complex;
+use crate::utils::validation::{parse_julia_coefficients, validate_julia_params};
+use image::RgbImage;
 
 pub struct JuliaSet;
 
-impl Fractal for JuliaSet {
-    fn generate(&self, params: FractalParams) -> Result<RgbImage, String> {
-        self.validate_params(&params)?;
+impl JuliaSet {
+    /// First pass of a render: escape time and closest trap approach for every pixel
+    fn first_pass(
+        &self,
+        params: &FractalParams,
+    ) -> Result<FirstPass<impl Fn(f64, f64, u32) -> Escape + Sync>, String> {
+        let trap = OrbitTrap::from_params(params)?;
 
         let FractalParams {
             width,
@@ -17,19 +29,41 @@ impl Fractal for JuliaSet {
             center_x,
             center_y,
             max_iterations,
-            color_scheme,
+            ref coloring,
             julia_c_real,
             julia_c_imag,
+            ref julia_coefficients,
+            boundary_width,
+            adaptive,
             ..
-        } = params;
+        } = *params;
 
-        // Julia set requires c values
-        let c_real = julia_c_real.ok_or("julia_c_real parameter is required for Julia set")?;
-        let c_imag = julia_c_imag.ok_or("julia_c_imag parameter is required for Julia set")?;
+        let map = match julia_coefficients {
+            // z = p(z) + c, where c defaults to 0
+            Some(coefficients) => {
+                let coefficients = parse_julia_coefficients(coefficients)?;
+                let c =
+                    match (julia_c_real, julia_c_imag) {
+                        (Some(c_real), Some(c_imag)) => Complex::new(c_real, c_imag),
+                        (None, None) => Complex::ZERO,
+                        _ => return Err(
+                            "julia_c_real and julia_c_imag must be given together for Julia set"
+                                .to_string(),
+                        ),
+                    };
+                JuliaMap::polynomial(coefficients, c)
+            }
+            None => {
+                // Julia set requires c values
+                let c_real =
+                    julia_c_real.ok_or("julia_c_real parameter is required for Julia set")?;
+                let c_imag =
+                    julia_c_imag.ok_or("julia_c_imag parameter is required for Julia set")?;
+                JuliaMap::Quadratic(c_real, c_imag)
+            }
+        };
 
-        validate_julia_params(c_real, c_imag)?;
-
-        let scheme = ColorScheme::from_str(color_scheme.as_deref().unwrap_or("default"));
+        let coloring = Coloring::from_param(coloring.as_deref())?;
 
         // Calculate the complex plane bounds
         let aspect_ratio = width as f64 / height as f64;
@@ -39,53 +73,268 @@ impl Fractal for JuliaSet {
         let min_y = center_y - scale;
         let max_y = center_y + scale;
 
-        // Pre-calculate all pixel data in parallel (clone scheme per row for parallel capture)
-        let pixels: Vec<[u8; 3]> = (0..height)
-            .into_par_iter()
-            .flat_map(|y| {
-                let scheme = scheme.clone();
-                (0..width)
-                    .map(move |x| {
-                        // Map pixel coordinates to complex plane
-                        let zx = min_x + (x as f64 / width as f64) * (max_x - min_x);
-                        let zy = min_y + (y as f64 / height as f64) * (max_y - min_y);
-
-                        // Compute Julia iteration
-                        let iterations = julia_iterations(zx, zy, c_real, c_imag, max_iterations);
-
-                        // Map iterations to color
-                        iterations_to_color(iterations, max_iterations, &scheme)
-                    })
-                    .collect::<Vec<_>>()
-            })
-            .collect();
-
-        // Create image buffer and fill with computed pixels
-        let mut img: RgbImage = ImageBuffer::new(width, height);
-        for (idx, pixel) in img.pixels_mut().enumerate() {
-            *pixel = Rgb(pixels[idx]);
-        }
+        // Distances to the boundary are measured in boundary widths
+        let pixel_size = (max_x - min_x) / width as f64;
+        let boundary = (coloring == Coloring::Distance)
+            .then(|| pixel_size * boundary_width.unwrap_or(DEFAULT_BOUNDARY_WIDTH));
+
+        // One point at pixel coordinates with the given iteration limit
+        let escape_at = move |x: f64, y: f64, limit: u32| {
+            // Map pixel coordinates to complex plane
+            let zx = min_x + (x / width as f64) * (max_x - min_x);
+            let zy = min_y + (y / height as f64) * (max_y - min_y);
+
+            // Compute Julia iteration
+            let escape = match boundary {
+                Some(unit) => map.distance(zx, zy, limit, unit),
+                None => map.escape(zx, zy, limit, trap.as_ref()),
+            };
+            // Orbits still bounded are followed on to find their cycle
+            match escape.final_z {
+                Some(z) if coloring == Coloring::Period && escape.iterations >= limit => {
+                    escape.with_period(map.period(z))
+                }
+                _ => escape,
+            }
+        };
+
+        // Escape time and closest trap approach for every pixel, in parallel
+        let (escapes, stats) = iterate_pixels(
+            width,
+            height,
+            max_iterations,
+            adaptive.unwrap_or(false),
+            |x, y, limit| escape_at(x as f64, y as f64, limit),
+        );
+
+        Ok(FirstPass {
+            escapes,
+            stats,
+            escape_at,
+        })
+    }
+
+    /// The render at 8 or 16 bits per channel
+    fn generate_at_depth<C: Channel>(
+        &self,
+        params: FractalParams,
+    ) -> Result<(C::Image, StatsHeaders), String> {
+        self.validate_params(&params)?;
+        let cycle = PaletteCycle::from_params(&params)?;
+        let antialias = Antialias::from_params(&params)?;
+        let shading = Shading::from_params(&params)?;
+
+        // First pass: escape time and closest trap approach for every pixel
+        let FirstPass {
+            escapes,
+            mut stats,
+            escape_at,
+        } = self.first_pass(&params)?;
+
+        let FractalParams {
+            width,
+            height,
+            max_iterations,
+            color_scheme,
+            coloring,
+            ..
+        } = params;
+        let scheme = ColorScheme::from_str(color_scheme.as_deref().unwrap_or("default"));
+        let coloring = Coloring::from_param(coloring.as_deref())?;
+
+        // Second pass: map escapes to color, supersampling edges and lighting slopes if asked
+        let (mut pixels, aa_stats) = antialias.color(
+            &escapes,
+            width,
+            height,
+            |x, y| escape_at(x, y, max_iterations),
+            |escapes| color_escapes(escapes, max_iterations, &scheme, coloring, &cycle),
+        );
+        stats.extend(aa_stats);
+        shading.apply(&mut pixels, &escapes, width, height, max_iterations);
+
+        Ok((C::image(width, height, pixels), stats))
+    }
+}
+
+impl Fractal for JuliaSet {
+    fn generate(&self, params: FractalParams) -> Result<RgbImage, String> {
+        self.generate_with_stats(params).map(|(img, _)| img)
+    }
 
-        Ok(img)
+    fn generate_with_stats(
+        &self,
+        params: FractalParams,
+    ) -> Result<(RgbImage, Vec<(String, String)>), String> {
+        self.generate_at_depth::<u8>(params)
+    }
+
+    fn generate16(
+        &self,
+        params: FractalParams,
+    ) -> Result<(Rgb16Image, Vec<(String, String)>), String> {
+        self.generate_at_depth::<u16>(params)
+    }
+
+    fn generate_escapes(
+        &self,
+        params: FractalParams,
+        _final_z: bool,
+    ) -> Result<(Vec<Escape>, StatsHeaders), String> {
+        self.validate_params(&params)?;
+        self.first_pass(&params)
+            .map(|pass| (pass.escapes, pass.stats))
     }
 
     fn name(&self) -> &str {
         "julia"
     }
 
+    fn plane_view(&self) -> Option<PlaneView> {
+        Some(PlaneView::centered(4.0))
+    }
+
     fn validate_params(&self, params: &FractalParams) -> Result<(), String> {
         default_validate_params(params)?;
 
         // Validate Julia-specific parameters
-        if let (Some(c_real), Some(c_imag)) = (params.julia_c_real, params.julia_c_imag) {
-            validate_julia_params(c_real, c_imag)?;
+        match (params.julia_c_real, params.julia_c_imag) {
+            (Some(c_real), Some(c_imag)) => validate_julia_params(c_real, c_imag)?,
+            // Polynomial maps default c to 0, the quadratic one needs it
+            (None, None) if params.julia_coefficients.is_some() => {}
+            (None, _) if params.julia_coefficients.is_none() => {
+                return Err("julia_c_real parameter is required for Julia set".to_string())
+            }
+            (_, None) if params.julia_coefficients.is_none() => {
+                return Err("julia_c_imag parameter is required for Julia set".to_string())
+            }
+            _ => {
+                return Err(
+                    "julia_c_real and julia_c_imag must be given together for Julia set"
+                        .to_string(),
+                )
+            }
+        }
+        if let Some(coefficients) = &params.julia_coefficients {
+            parse_julia_coefficients(coefficients)?;
         }
 
         Ok(())
     }
 }
 
-fn julia_iterations(mut zx: f64, mut zy: f64, cx: f64, cy: f64, max_iterations: u32) -> u32 {
+/// The map iterated from each pixel
+enum JuliaMap {
+    /// z^2 + c
+    Quadratic(f64, f64),
+    /// p(z) + c for p with these coefficients, highest degree first (c folded into the constant
+    /// term), and the squared radius beyond which orbits can't come back
+    Polynomial {
+        coefficients: Vec<Complex>,
+        bailout_sqr: f64,
+    },
+}
+
+impl JuliaMap {
+    fn polynomial(mut coefficients: Vec<Complex>, c: Complex) -> Self {
+        let constant = coefficients.len() - 1;
+        coefficients[constant] = coefficients[constant] + c;
+
+        // Past R = (2 + sum of the lower coefficients' magnitudes) / |leading| (and 1),
+        // |p(z)| >= 2 |z| so the orbit escapes
+        let leading = coefficients[0].norm();
+        let lower: f64 = coefficients[1..].iter().map(|a| a.norm()).sum();
+        let radius = ((2.0 + lower) / leading).max(2.0);
+
+        JuliaMap::Polynomial {
+            coefficients,
+            bailout_sqr: radius * radius,
+        }
+    }
+
+    fn escape(&self, zx: f64, zy: f64, max_iterations: u32, trap: Option<&OrbitTrap>) -> Escape {
+        match self {
+            JuliaMap::Quadratic(c_real, c_imag) => {
+                julia_escape(zx, zy, *c_real, *c_imag, max_iterations, trap)
+            }
+            JuliaMap::Polynomial {
+                coefficients,
+                bailout_sqr,
+            } => polynomial_julia_escape(
+                Complex::new(zx, zy),
+                coefficients,
+                *bailout_sqr,
+                max_iterations,
+                trap,
+            ),
+        }
+    }
+
+    /// Period of the cycle z has settled into
+    fn period(&self, z: (f64, f64)) -> Option<u32> {
+        match self {
+            JuliaMap::Quadratic(c_real, c_imag) => {
+                cycle_period(z, |x, y| (x * x - y * y + c_real, 2.0 * x * y + c_imag))
+            }
+            JuliaMap::Polynomial { coefficients, .. } => cycle_period(z, |x, y| {
+                let z = Complex::new(x, y);
+                let value = coefficients[1..]
+                    .iter()
+                    .fold(coefficients[0], |acc, &a| acc * z + a);
+                (value.re, value.im)
+            }),
+        }
+    }
+
+    /// Escape time of z, tracking z' to estimate the distance to the boundary in multiples of
+    /// `unit`
+    fn distance(&self, zx: f64, zy: f64, max_iterations: u32, unit: f64) -> Escape {
+        let mut z = Complex::new(zx, zy);
+        let mut derivative = Complex::ONE;
+
+        for iteration in 0..max_iterations {
+            match self {
+                JuliaMap::Quadratic(c_real, c_imag) => {
+                    derivative = (z * derivative).scale(2.0);
+                    z = z * z + Complex::new(*c_real, *c_imag);
+                }
+                // p and p' together by Horner's rule
+                JuliaMap::Polynomial { coefficients, .. } => {
+                    let (value, slope) = coefficients[1..]
+                        .iter()
+                        .fold((coefficients[0], Complex::ZERO), |(value, slope), &a| {
+                            (value * z + a, slope * z + value)
+                        });
+                    derivative = slope * derivative;
+                    z = value;
+                }
+            }
+
+            let norm_sqr = z.norm_sqr();
+            if norm_sqr > DISTANCE_BAILOUT_SQR {
+                let distance = estimate_distance(norm_sqr, derivative.norm_sqr());
+                return Escape::with_boundary_distance(iteration, distance / unit);
+            }
+        }
+
+        Escape::from(max_iterations)
+    }
+}
+
+pub fn julia_iterations(zx: f64, zy: f64, cx: f64, cy: f64, max_iterations: u32) -> u32 {
+    julia_escape(zx, zy, cx, cy, max_iterations, None).iterations
+}
+
+#[inline]
+fn julia_escape(
+    mut zx: f64,
+    mut zy: f64,
+    cx: f64,
+    cy: f64,
+    max_iterations: u32,
+    trap: Option<&OrbitTrap>,
+) -> Escape {
+    let mut tracker = TrapTracker::new(trap);
     let mut iteration = 0;
 
     // Julia set: z = z^2 + c where c is constant
@@ -93,8 +342,31 @@ fn julia_iterations(mut zx: f64, mut zy: f64, cx: f64, cy: f64, max_iterations:
         let zx_temp = zx * zx - zy * zy + cx;
         zy = 2.0 * zx * zy + cy;
         zx = zx_temp;
+        tracker.visit(zx, zy);
+        iteration += 1;
+    }
+
+    tracker.escape(iteration).with_final_z(zx, zy)
+}
+
+/// Escape time of z under p, evaluated by Horner's rule
+fn polynomial_julia_escape(
+    mut z: Complex,
+    coefficients: &[Complex],
+    bailout_sqr: f64,
+    max_iterations: u32,
+    trap: Option<&OrbitTrap>,
+) -> Escape {
+    let mut tracker = TrapTracker::new(trap);
+    let mut iteration = 0;
+
+    while z.norm_sqr() <= bailout_sqr && iteration < max_iterations {
+        z = coefficients[1..]
+            .iter()
+            .fold(coefficients[0], |acc, &a| acc * z + a);
+        tracker.visit(z.re, z.im);
         iteration += 1;
     }
 
-    iteration
+    tracker.escape(iteration).with_final_z(z.re, z.im)
 }
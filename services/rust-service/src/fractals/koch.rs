@@ -1,25 +1,33 @@
 use super::traits::{default_validate_params, Fractal, FractalParams};
+use crate::rendering::canvas::Canvas;
+use crate::rendering::colors::GeometryColors;
+use crate::rendering::vector::{VectorBuilder, VectorFormat};
 use crate::utils::validation::validate_recursion_depth;
-use image::{ImageBuffer, Rgb, RgbImage};
+use image::RgbImage;
+
+type Segment = ((f64, f64), (f64, f64));
+
+/// Line color unless stroke_color is given
+const DEFAULT_STROKE: [u8; 3] = [0, 100, 200];
 
 pub struct KochSnowflake;
 
-impl Fractal for KochSnowflake {
-    fn generate(&self, params: FractalParams) -> Result<RgbImage, String> {
-        self.validate_params(&params)?;
+impl KochSnowflake {
+    fn paint<C: Canvas>(params: FractalParams) -> Result<C, String> {
+        let colors = GeometryColors::from_params(&params)?;
 
         let FractalParams {
             width,
             height,
             recursion_depth,
+            line_thickness,
             ..
         } = params;
 
         let depth = recursion_depth.unwrap_or(4);
         validate_recursion_depth(depth)?;
 
-        // Create white background
-        let mut img: RgbImage = ImageBuffer::from_pixel(width, height, Rgb([255, 255, 255]));
+        let mut canvas = C::blank(width, height, colors.background);
 
         // Define the three vertices of an equilateral triangle
         // Center it and scale to fit the image with padding
@@ -43,11 +51,29 @@ impl Fractal for KochSnowflake {
         koch_curve(p3, p1, depth, &mut lines);
 
         // Draw all lines
+        let stroke = colors.stroke.unwrap_or(DEFAULT_STROKE);
+        let thickness = line_thickness.unwrap_or(1) as f64;
         for (start, end) in lines {
-            draw_line(&mut img, start, end, Rgb([0, 100, 200]));
+            canvas.line(start, end, thickness, stroke);
         }
 
-        Ok(img)
+        Ok(canvas)
+    }
+}
+
+impl Fractal for KochSnowflake {
+    fn generate(&self, params: FractalParams) -> Result<RgbImage, String> {
+        self.validate_params(&params)?;
+        Self::paint(params)
+    }
+
+    fn generate_vector(
+        &self,
+        params: FractalParams,
+        format: VectorFormat,
+    ) -> Result<Vec<u8>, String> {
+        self.validate_params(&params)?;
+        Self::paint::<VectorBuilder>(params)?.build(format)
     }
 
     fn name(&self) -> &str {
@@ -70,7 +96,7 @@ fn koch_curve(
     start: (f64, f64),
     end: (f64, f64),
     depth: u32,
-    lines: &mut Vec<((f64, f64), (f64, f64))>,
+    lines: &mut Vec<Segment>,
 ) {
     if depth == 0 {
         lines.push((start, end));
@@ -101,40 +127,3 @@ fn koch_curve(
         koch_curve(p2, end, depth - 1, lines);
     }
 }
-
-fn draw_line(img: &mut RgbImage, start: (f64, f64), end: (f64, f64), color: Rgb<u8>) {
-    // Bresenham's line algorithm
-    let x0 = start.0 as i32;
-    let y0 = start.1 as i32;
-    let x1 = end.0 as i32;
-    let y1 = end.1 as i32;
-
-    let dx = (x1 - x0).abs();
-    let dy = -(y1 - y0).abs();
-    let sx = if x0 < x1 { 1 } else { -1 };
-    let sy = if y0 < y1 { 1 } else { -1 };
-    let mut err = dx + dy;
-
-    let mut x = x0;
-    let mut y = y0;
-
-    loop {
-        if x >= 0 && x < img.width() as i32 && y >= 0 && y < img.height() as i32 {
-            img.put_pixel(x as u32, y as u32, color);
-        }
-
-        if x == x1 && y == y1 {
-            break;
-        }
-
-        let e2 = 2 * err;
-        if e2 >= dy {
-            err += dy;
-            x += sx;
-        }
-        if e2 <= dx {
-            err += dx;
-            y += sy;
-        }
-    }
-}
@@ -0,0 +1,184 @@
+//! L-system interpreter: rewrite an axiom with the production rules, then trace the result
+//! with turtle graphics (F/G draw forward, f moves, +/- turn, | turns around, [ ] push/pop).
+
+use super::traits::{default_validate_params, Fractal, FractalParams};
+use crate::rendering::canvas::Canvas;
+use crate::rendering::colors::{ColorScheme, GeometryColors};
+use crate::rendering::draw::{draw_fitted_segments, Segment};
+use crate::rendering::vector::{VectorBuilder, VectorFormat};
+use crate::utils::validation::{
+    parse_lsystem_rules, validate_lsystem_angle, validate_lsystem_axiom, validate_recursion_depth,
+    MAX_LSYSTEM_SYMBOLS,
+};
+use image::RgbImage;
+
+struct Preset {
+    name: &'static str,
+    axiom: &'static str,
+    rules: &'static str,
+    angle: f64,
+    depth: u32,
+}
+
+const PRESETS: [Preset; 3] = [
+    Preset {
+        name: "koch",
+        axiom: "F",
+        rules: "F=F+F--F+F",
+        angle: 60.0,
+        depth: 4,
+    },
+    Preset {
+        name: "dragon",
+        axiom: "FX",
+        rules: "X=X+YF+;Y=-FX-Y",
+        angle: 90.0,
+        depth: 10,
+    },
+    Preset {
+        name: "plant",
+        axiom: "X",
+        rules: "X=F+[[X]-X]-F[-FX]+X;F=FF",
+        angle: 25.0,
+        depth: 5,
+    },
+];
+
+fn find_preset(name: &str) -> Result<&'static Preset, String> {
+    PRESETS
+        .iter()
+        .find(|preset| preset.name.eq_ignore_ascii_case(name))
+        .ok_or_else(|| {
+            let names: Vec<&str> = PRESETS.iter().map(|preset| preset.name).collect();
+            format!(
+                "Invalid lsystem_preset. Must be one of: {}.",
+                names.join(", ")
+            )
+        })
+}
+
+/// Apply the rules `depth` times, failing once the string outgrows the symbol cap
+fn expand(axiom: &str, rules: &[(char, String)], depth: u32) -> Result<Vec<char>, String> {
+    let mut current: Vec<char> = axiom.chars().collect();
+
+    for _ in 0..depth {
+        let mut next = Vec::with_capacity(current.len() * 2);
+        for symbol in current {
+            match rules.iter().find(|(predecessor, _)| *predecessor == symbol) {
+                Some((_, successor)) => next.extend(successor.chars()),
+                None => next.push(symbol),
+            }
+            if next.len() > MAX_LSYSTEM_SYMBOLS {
+                return Err(format!(
+                    "L-system expansion exceeds {} symbols. Lower recursion_depth or simplify the rules.",
+                    MAX_LSYSTEM_SYMBOLS
+                ));
+            }
+        }
+        current = next;
+    }
+
+    Ok(current)
+}
+
+/// Trace the symbols with a unit-step turtle starting at the origin, heading up (y axis up)
+fn trace(symbols: &[char], angle: f64) -> Vec<Segment> {
+    let turn = angle.to_radians();
+    let mut position = (0.0, 0.0);
+    let mut heading = std::f64::consts::FRAC_PI_2;
+    let mut stack = Vec::new();
+    let mut segments = Vec::new();
+
+    for &symbol in symbols {
+        match symbol {
+            'F' | 'G' | 'f' => {
+                let next = (position.0 + heading.cos(), position.1 + heading.sin());
+                if symbol != 'f' {
+                    segments.push((position, next));
+                }
+                position = next;
+            }
+            '+' => heading += turn,
+            '-' => heading -= turn,
+            '|' => heading += std::f64::consts::PI,
+            '[' => stack.push((position, heading)),
+            ']' => {
+                if let Some((saved_position, saved_heading)) = stack.pop() {
+                    position = saved_position;
+                    heading = saved_heading;
+                }
+            }
+            // Other symbols only steer the rewriting
+            _ => {}
+        }
+    }
+
+    segments
+}
+
+pub struct LSystem;
+
+impl LSystem {
+    fn paint<C: Canvas>(params: FractalParams) -> Result<C, String> {
+        let preset = find_preset(params.lsystem_preset.as_deref().unwrap_or("koch"))?;
+        let axiom = params.lsystem_axiom.as_deref().unwrap_or(preset.axiom);
+        let rules = parse_lsystem_rules(params.lsystem_rules.as_deref().unwrap_or(preset.rules))?;
+        let angle = params.lsystem_angle.unwrap_or(preset.angle);
+        let depth = params.recursion_depth.unwrap_or(preset.depth);
+
+        let symbols = expand(axiom, &rules, depth)?;
+        let segments = trace(&symbols, angle);
+        let scheme = ColorScheme::from_str(params.color_scheme.as_deref().unwrap_or("default"));
+        let colors = GeometryColors::from_params(&params)?;
+
+        let mut canvas = C::blank(params.width, params.height, colors.background);
+        let thickness = params.line_thickness.unwrap_or(1) as f64;
+        draw_fitted_segments(&mut canvas, &segments, &scheme, colors.stroke, thickness);
+
+        Ok(canvas)
+    }
+}
+
+impl Fractal for LSystem {
+    fn generate(&self, params: FractalParams) -> Result<RgbImage, String> {
+        self.validate_params(&params)?;
+        Self::paint(params)
+    }
+
+    fn generate_vector(
+        &self,
+        params: FractalParams,
+        format: VectorFormat,
+    ) -> Result<Vec<u8>, String> {
+        self.validate_params(&params)?;
+        Self::paint::<VectorBuilder>(params)?.build(format)
+    }
+
+    fn name(&self) -> &str {
+        "lsystem"
+    }
+
+    fn validate_params(&self, params: &FractalParams) -> Result<(), String> {
+        default_validate_params(params)?;
+
+        if let Some(preset) = &params.lsystem_preset {
+            find_preset(preset)?;
+        }
+        if let Some(axiom) = &params.lsystem_axiom {
+            validate_lsystem_axiom(axiom)?;
+        }
+        if let Some(rules) = &params.lsystem_rules {
+            parse_lsystem_rules(rules)?;
+        }
+        if let Some(angle) = params.lsystem_angle {
+            validate_lsystem_angle(angle)?;
+        }
+
+        // recursion_depth is the number of rewriting passes
+        if let Some(depth) = params.recursion_depth {
+            validate_recursion_depth(depth)?;
+        }
+
+        Ok(())
+    }
+}
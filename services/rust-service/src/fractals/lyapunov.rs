@@ -0,0 +1,129 @@
+use super::traits::{default_validate_params, Fractal, FractalParams, PlaneView};
+use crate::rendering::colors::{exponent_to_color, ColorScheme};
+use crate::utils::validation::parse_lyapunov_sequence;
+use image::{ImageBuffer, Rgb, RgbImage};
+use rayon::prelude::*;
+
+/// Centre of the classic Markus-Lyapunov view; center_x/center_y offset from here
+const DEFAULT_CENTER: (f64, f64) = (3.0, 3.0);
+
+/// Iterations discarded before measuring, so the orbit settles onto its attractor
+const WARMUP_ITERATIONS: u32 = 50;
+
+/// Lyapunov (Markus) fractal over the (a, b) rate plane of the logistic map,
+/// where the rate alternates between a and b following the A/B sequence.
+pub struct LyapunovFractal;
+
+impl Fractal for LyapunovFractal {
+    fn generate(&self, params: FractalParams) -> Result<RgbImage, String> {
+        self.validate_params(&params)?;
+
+        let FractalParams {
+            width,
+            height,
+            zoom,
+            center_x,
+            center_y,
+            max_iterations,
+            color_scheme,
+            lyapunov_sequence,
+            ..
+        } = params;
+
+        let sequence = parse_lyapunov_sequence(lyapunov_sequence.as_deref().unwrap_or("AB"))?;
+
+        let scheme = ColorScheme::from_str(color_scheme.as_deref().unwrap_or("default"));
+
+        // Calculate the (a, b) parameter bounds; a runs left to right, b bottom to top
+        let aspect_ratio = width as f64 / height as f64;
+        let scale = 1.0 / zoom;
+        let min_a = DEFAULT_CENTER.0 + center_x - scale * aspect_ratio;
+        let max_a = DEFAULT_CENTER.0 + center_x + scale * aspect_ratio;
+        let min_b = DEFAULT_CENTER.1 + center_y - scale;
+        let max_b = DEFAULT_CENTER.1 + center_y + scale;
+
+        // Pre-calculate all pixel data in parallel (clone scheme per row for parallel capture)
+        let pixels: Vec<[u8; 3]> = (0..height)
+            .into_par_iter()
+            .flat_map(|y| {
+                let scheme = scheme.clone();
+                let sequence = &sequence;
+                (0..width)
+                    .map(move |x| {
+                        // Map pixel coordinates to the parameter plane
+                        let a = min_a + (x as f64 / width as f64) * (max_a - min_a);
+                        let b = max_b - (y as f64 / height as f64) * (max_b - min_b);
+
+                        // Compute the Lyapunov exponent
+                        let exponent = lyapunov_exponent(a, b, sequence, max_iterations);
+
+                        // Map the signed exponent to color
+                        exponent_to_color(exponent, &scheme)
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+
+        // Create image buffer and fill with computed pixels
+        let mut img: RgbImage = ImageBuffer::new(width, height);
+        for (idx, pixel) in img.pixels_mut().enumerate() {
+            *pixel = Rgb(pixels[idx]);
+        }
+
+        Ok(img)
+    }
+
+    fn name(&self) -> &str {
+        "lyapunov"
+    }
+
+    fn plane_view(&self) -> Option<PlaneView> {
+        Some(PlaneView {
+            half_height: 1.0,
+            origin: DEFAULT_CENTER,
+            axes: ("a", "b"),
+        })
+    }
+
+    fn validate_params(&self, params: &FractalParams) -> Result<(), String> {
+        default_validate_params(params)?;
+
+        // Validate the rate sequence
+        if let Some(sequence) = &params.lyapunov_sequence {
+            parse_lyapunov_sequence(sequence)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Average of ln|r (1 - 2x)| along the logistic map orbit x = r x (1 - x)
+fn lyapunov_exponent(a: f64, b: f64, sequence: &[bool], max_iterations: u32) -> f64 {
+    let rate = |n: u32| {
+        if sequence[n as usize % sequence.len()] {
+            b
+        } else {
+            a
+        }
+    };
+
+    let mut x = 0.5;
+    for n in 0..WARMUP_ITERATIONS {
+        x = rate(n) * x * (1.0 - x);
+    }
+
+    let mut sum = 0.0;
+    for n in 0..max_iterations {
+        let r = rate(n + WARMUP_ITERATIONS);
+        x = r * x * (1.0 - x);
+
+        let derivative = (r * (1.0 - 2.0 * x)).abs();
+        if derivative == 0.0 {
+            // Superstable orbit
+            return f64::NEG_INFINITY;
+        }
+        sum += derivative.ln();
+    }
+
+    sum / max_iterations as f64
+}
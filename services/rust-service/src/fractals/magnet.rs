@@ -0,0 +1,239 @@
+use super::adaptive::{iterate_pixels, FirstPass};
+use super::antialias::Antialias;
+use super::traits::{
+    default_validate_params, reject_angle_coloring, reject_distance_coloring,
+    reject_period_coloring, Fractal, FractalParams, PlaneView, StatsHeaders,
+};
+use crate::rendering::colors::{
+    color_escapes, Channel, ColorScheme, Coloring, Escape, PaletteCycle,
+};
+use crate::rendering::orbit_trap::{OrbitTrap, TrapTracker};
+use crate::rendering::shading::Shading;
+use crate::rendering::Rgb16Image;
+use crate::utils::complex::Complex;
+use image::RgbImage;
+
+/// Squared magnitude beyond which the orbit is considered escaped
+const BAILOUT_SQR: f64 = 10_000.0;
+
+/// Squared distance from the fixed point z = 1 at which the orbit is considered converged
+const CONVERGENCE_TOLERANCE_SQR: f64 = 1e-12;
+
+/// Which of the two rational maps from the Ising-model renormalization is iterated
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MagnetKind {
+    /// z = ((z^2 + c - 1) / (2z + c - 2))^2
+    TypeOne,
+    /// z = ((z^3 + 3(c - 1)z + (c - 1)(c - 2)) / (3z^2 + 3(c - 2)z + (c - 1)(c - 2) + 1))^2
+    TypeTwo,
+}
+
+impl MagnetKind {
+    /// Centre of the parameter-plane view; center_x/center_y offset from here
+    fn origin(self) -> (f64, f64) {
+        match self {
+            MagnetKind::TypeOne => (1.5, 0.0),
+            MagnetKind::TypeTwo => (1.0, 0.0),
+        }
+    }
+
+    fn step(self, z: Complex, c: Complex) -> Complex {
+        let one = Complex::ONE;
+        let two = Complex::new(2.0, 0.0);
+        let three = Complex::new(3.0, 0.0);
+
+        let ratio = match self {
+            MagnetKind::TypeOne => (z * z + c - one) / (two * z + c - two),
+            MagnetKind::TypeTwo => {
+                let c1 = c - one;
+                let c2 = c - two;
+                let numerator = z * z * z + three * c1 * z + c1 * c2;
+                let denominator = three * z * z + three * c2 * z + c1 * c2 + one;
+                numerator / denominator
+            }
+        };
+        ratio * ratio
+    }
+}
+
+/// Magnet fractals over the c parameter plane, iterated from z = 0. Orbits either escape to
+/// infinity or settle on the fixed point z = 1; both are colored by how quickly they did so.
+pub struct MagnetFractal {
+    kind: MagnetKind,
+}
+
+impl MagnetFractal {
+    pub const fn new(kind: MagnetKind) -> Self {
+        Self { kind }
+    }
+
+    fn view(&self) -> PlaneView {
+        PlaneView {
+            half_height: 2.5,
+            origin: self.kind.origin(),
+            axes: ("Re c", "Im c"),
+        }
+    }
+}
+
+impl MagnetFractal {
+    /// First pass of a render: escape time and closest trap approach for every pixel
+    fn first_pass(
+        &self,
+        params: &FractalParams,
+    ) -> Result<FirstPass<impl Fn(f64, f64, u32) -> Escape + Sync>, String> {
+        let trap = OrbitTrap::from_params(params)?;
+
+        let FractalParams {
+            width,
+            height,
+            max_iterations,
+            adaptive,
+            ..
+        } = *params;
+
+        let kind = self.kind;
+
+        // Calculate the complex plane bounds
+        let bounds = self.view().bounds(params);
+
+        // One point at pixel coordinates with the given iteration limit
+        let escape_at = move |x: f64, y: f64, limit: u32| {
+            // Map pixel coordinates to complex plane
+            let re = bounds.x_min + (x / width as f64) * (bounds.x_max - bounds.x_min);
+            let im = bounds.y_min + (y / height as f64) * (bounds.y_max - bounds.y_min);
+
+            // Compute Magnet iteration
+            magnet_escape(kind, Complex::new(re, im), limit, trap.as_ref())
+        };
+
+        // Escape time and closest trap approach for every pixel, in parallel
+        let (escapes, stats) = iterate_pixels(
+            width,
+            height,
+            max_iterations,
+            adaptive.unwrap_or(false),
+            |x, y, limit| escape_at(x as f64, y as f64, limit),
+        );
+
+        Ok(FirstPass {
+            escapes,
+            stats,
+            escape_at,
+        })
+    }
+
+    /// The render at 8 or 16 bits per channel
+    fn generate_at_depth<C: Channel>(
+        &self,
+        params: FractalParams,
+    ) -> Result<(C::Image, StatsHeaders), String> {
+        self.validate_params(&params)?;
+        let cycle = PaletteCycle::from_params(&params)?;
+        let antialias = Antialias::from_params(&params)?;
+        let shading = Shading::from_params(&params)?;
+
+        // First pass: escape time and closest trap approach for every pixel
+        let FirstPass {
+            escapes,
+            mut stats,
+            escape_at,
+        } = self.first_pass(&params)?;
+
+        let FractalParams {
+            width,
+            height,
+            max_iterations,
+            color_scheme,
+            coloring,
+            ..
+        } = params;
+        let scheme = ColorScheme::from_str(color_scheme.as_deref().unwrap_or("default"));
+        let coloring = Coloring::from_param(coloring.as_deref())?;
+
+        // Second pass: map escapes to color, supersampling edges and lighting slopes if asked
+        let (mut pixels, aa_stats) = antialias.color(
+            &escapes,
+            width,
+            height,
+            |x, y| escape_at(x, y, max_iterations),
+            |escapes| color_escapes(escapes, max_iterations, &scheme, coloring, &cycle),
+        );
+        stats.extend(aa_stats);
+        shading.apply(&mut pixels, &escapes, width, height, max_iterations);
+
+        Ok((C::image(width, height, pixels), stats))
+    }
+}
+
+impl Fractal for MagnetFractal {
+    fn generate(&self, params: FractalParams) -> Result<RgbImage, String> {
+        self.generate_with_stats(params).map(|(img, _)| img)
+    }
+
+    fn generate_with_stats(
+        &self,
+        params: FractalParams,
+    ) -> Result<(RgbImage, Vec<(String, String)>), String> {
+        self.generate_at_depth::<u8>(params)
+    }
+
+    fn generate16(
+        &self,
+        params: FractalParams,
+    ) -> Result<(Rgb16Image, Vec<(String, String)>), String> {
+        self.generate_at_depth::<u16>(params)
+    }
+
+    fn generate_escapes(
+        &self,
+        params: FractalParams,
+        _final_z: bool,
+    ) -> Result<(Vec<Escape>, StatsHeaders), String> {
+        self.validate_params(&params)?;
+        self.first_pass(&params)
+            .map(|pass| (pass.escapes, pass.stats))
+    }
+
+    fn name(&self) -> &str {
+        match self.kind {
+            MagnetKind::TypeOne => "magnet1",
+            MagnetKind::TypeTwo => "magnet2",
+        }
+    }
+
+    fn plane_view(&self) -> Option<PlaneView> {
+        Some(self.view())
+    }
+
+    fn validate_params(&self, params: &FractalParams) -> Result<(), String> {
+        default_validate_params(params)?;
+        reject_distance_coloring(params, self.name())?;
+        reject_angle_coloring(params, self.name())?;
+        reject_period_coloring(params, self.name())
+    }
+}
+
+fn magnet_escape(
+    kind: MagnetKind,
+    c: Complex,
+    max_iterations: u32,
+    trap: Option<&OrbitTrap>,
+) -> Escape {
+    let mut tracker = TrapTracker::new(trap);
+    let mut z = Complex::ZERO;
+    for iteration in 0..max_iterations {
+        z = kind.step(z, c);
+        tracker.visit(z.re, z.im);
+
+        // A zero denominator sends the orbit to infinity, which counts as escaping
+        if !z.is_finite() || z.norm_sqr() > BAILOUT_SQR {
+            return tracker.escape(iteration);
+        }
+        if (z - Complex::ONE).norm_sqr() < CONVERGENCE_TOLERANCE_SQR {
+            return tracker.escape(iteration);
+        }
+    }
+
+    tracker.escape(max_iterations)
+}
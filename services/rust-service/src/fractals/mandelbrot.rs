@@ -1,13 +1,31 @@
-use super::traits::{Fractal, FractalParams};
-use crate::rendering::colors::{iterations_to_color, ColorScheme};
-use image::{ImageBuffer, Rgb, RgbImage};
+use super::adaptive::{iterate_adaptively, FirstPass};
+use super::antialias::Antialias;
+use super::kernels::{
+    cycle_period, mandelbrot_distance, mandelbrot_escape, mandelbrot_row, MandelbrotVariant,
+};
+use super::traits::{default_validate_params, Fractal, FractalParams, PlaneView, StatsHeaders};
+use crate::rendering::colors::{
+    color_escapes, Channel, ColorScheme, Coloring, Escape, PaletteCycle, DEFAULT_BOUNDARY_WIDTH,
+};
+use crate::rendering::orbit_trap::OrbitTrap;
+use crate::rendering::shading::Shading;
+use crate::rendering::Rgb16Image;
+use crate::tuning;
+use image::RgbImage;
 use rayon::prelude::*;
 
 pub struct MandelbrotSet;
 
-impl Fractal for MandelbrotSet {
-    fn generate(&self, params: FractalParams) -> Result<RgbImage, String> {
-        self.validate_params(&params)?;
+impl MandelbrotSet {
+    /// First pass of a render: escape time and closest trap approach for every pixel. `final_z`
+    /// asks for the orbit's last point at every pixel, which the row kernels don't return.
+    fn first_pass(
+        &self,
+        params: &FractalParams,
+        final_z: bool,
+    ) -> Result<FirstPass<impl Fn(f64, f64, u32) -> Escape + Sync>, String> {
+        let trap = OrbitTrap::from_params(params)?;
+        let shading = Shading::from_params(params)?;
 
         let FractalParams {
             width,
@@ -16,11 +34,19 @@ impl Fractal for MandelbrotSet {
             center_x,
             center_y,
             max_iterations,
-            color_scheme,
+            ref coloring,
+            ref variant,
+            boundary_width,
+            adaptive,
+            kernel,
             ..
-        } = params;
+        } = *params;
 
-        let scheme = ColorScheme::from_str(color_scheme.as_deref().unwrap_or("default"));
+        let variant = match variant {
+            Some(name) => MandelbrotVariant::parse(name)?,
+            None => MandelbrotVariant::Classic,
+        };
+        let coloring = Coloring::from_param(coloring.as_deref())?;
 
         // Calculate the complex plane bounds
         let aspect_ratio = width as f64 / height as f64;
@@ -30,52 +56,173 @@ impl Fractal for MandelbrotSet {
         let min_y = center_y - scale;
         let max_y = center_y + scale;
 
-        // Pre-calculate all pixel data in parallel (clone scheme per row for parallel capture)
-        let pixels: Vec<[u8; 3]> = (0..height)
-            .into_par_iter()
-            .flat_map(|y| {
-                let scheme = scheme.clone();
-                (0..width)
-                    .map(move |x| {
-                        // Map pixel coordinates to complex plane
-                        let cx = min_x + (x as f64 / width as f64) * (max_x - min_x);
-                        let cy = min_y + (y as f64 / height as f64) * (max_y - min_y);
-
-                        // Compute Mandelbrot iteration
-                        let iterations = mandelbrot_iterations(cx, cy, max_iterations);
-
-                        // Map iterations to color
-                        iterations_to_color(iterations, max_iterations, &scheme)
-                    })
-                    .collect::<Vec<_>>()
-            })
-            .collect();
-
-        // Create image buffer and fill with computed pixels
-        let mut img: RgbImage = ImageBuffer::new(width, height);
-        for (idx, pixel) in img.pixels_mut().enumerate() {
-            *pixel = Rgb(pixels[idx]);
-        }
+        // Kernel and tile size picked by the startup calibration, unless the kernel is forced
+        let tuning = tuning::current();
+        let kernel = kernel.unwrap_or(tuning.kernel);
+        let dx = (max_x - min_x) / width as f64;
+        let boundary = dx * boundary_width.unwrap_or(DEFAULT_BOUNDARY_WIDTH);
+        let has_trap = trap.is_some();
+
+        // One point at pixel coordinates with the given iteration limit, for what the row
+        // kernels don't cover and for edge sub-samples
+        let escape_at = move |x: f64, y: f64, limit: u32| {
+            let cx = min_x + x * dx;
+            let cy = min_y + (y / height as f64) * (max_y - min_y);
+            match coloring {
+                // Derivatives are tracked alongside z
+                Coloring::Distance => mandelbrot_distance(cx, cy, limit, boundary),
+                // Orbits still bounded are followed on to find their cycle
+                Coloring::Period => {
+                    let escape = mandelbrot_escape(variant, cx, cy, limit, None);
+                    match escape.final_z {
+                        Some(z) if escape.iterations >= limit => {
+                            escape.with_period(cycle_period(z, |x, y| variant.step(x, y, cx, cy)))
+                        }
+                        _ => escape,
+                    }
+                }
+                // Every step is measured against the trap, if there is one
+                _ => mandelbrot_escape(variant, cx, cy, limit, trap.as_ref()),
+            }
+        };
+        // The row kernels return iteration counts only, without the orbit's last point that
+        // smooths the heights for shading
+        let per_pixel = final_z
+            || has_trap
+            || shading != Shading::None
+            || matches!(
+                coloring,
+                Coloring::Distance | Coloring::BinaryDecomposition | Coloring::Period
+            );
+
+        // Escape time and closest trap approach for every pixel, in parallel
+        let mut stats = Vec::new();
+        let escapes: Vec<Escape> = if adaptive.unwrap_or(false) {
+            let (escapes, adaptive_stats) =
+                iterate_adaptively(width, height, max_iterations, |x, y, limit| {
+                    escape_at(x as f64, y as f64, limit)
+                });
+            stats = adaptive_stats.headers();
+            escapes
+        } else {
+            (0..height)
+                .into_par_iter()
+                .with_min_len(tuning.tile_rows)
+                .flat_map(|y| {
+                    if per_pixel {
+                        return (0..width)
+                            .map(|x| escape_at(x as f64, y as f64, max_iterations))
+                            .collect::<Vec<_>>();
+                    }
+                    let cy = min_y + (y as f64 / height as f64) * (max_y - min_y);
 
-        Ok(img)
+                    // Compute Mandelbrot iterations for the whole row
+                    mandelbrot_row(kernel, variant, min_x, dx, cy, width, max_iterations)
+                        .into_iter()
+                        .map(Escape::from)
+                        .collect()
+                })
+                .collect()
+        };
+
+        Ok(FirstPass {
+            escapes,
+            stats,
+            escape_at,
+        })
     }
 
-    fn name(&self) -> &str {
-        "mandelbrot"
+    /// The render at 8 or 16 bits per channel
+    fn generate_at_depth<C: Channel>(
+        &self,
+        params: FractalParams,
+    ) -> Result<(C::Image, StatsHeaders), String> {
+        self.validate_params(&params)?;
+        let cycle = PaletteCycle::from_params(&params)?;
+        let antialias = Antialias::from_params(&params)?;
+        let shading = Shading::from_params(&params)?;
+
+        // First pass: escape time and closest trap approach for every pixel
+        let FirstPass {
+            escapes,
+            mut stats,
+            escape_at,
+        } = self.first_pass(&params, false)?;
+
+        let FractalParams {
+            width,
+            height,
+            max_iterations,
+            color_scheme,
+            coloring,
+            ..
+        } = params;
+        let scheme = ColorScheme::from_str(color_scheme.as_deref().unwrap_or("default"));
+        let coloring = Coloring::from_param(coloring.as_deref())?;
+
+        // Second pass: map escapes to color, supersampling edges and lighting slopes if asked
+        let (mut pixels, aa_stats) = antialias.color(
+            &escapes,
+            width,
+            height,
+            |x, y| escape_at(x, y, max_iterations),
+            |escapes| color_escapes(escapes, max_iterations, &scheme, coloring, &cycle),
+        );
+        stats.extend(aa_stats);
+        shading.apply(&mut pixels, &escapes, width, height, max_iterations);
+
+        Ok((C::image(width, height, pixels), stats))
     }
 }
 
-fn mandelbrot_iterations(cx: f64, cy: f64, max_iterations: u32) -> u32 {
-    let mut x = 0.0;
-    let mut y = 0.0;
-    let mut iteration = 0;
+impl Fractal for MandelbrotSet {
+    fn generate(&self, params: FractalParams) -> Result<RgbImage, String> {
+        self.generate_with_stats(params).map(|(img, _)| img)
+    }
+
+    fn generate_with_stats(
+        &self,
+        params: FractalParams,
+    ) -> Result<(RgbImage, Vec<(String, String)>), String> {
+        self.generate_at_depth::<u8>(params)
+    }
+
+    fn generate16(
+        &self,
+        params: FractalParams,
+    ) -> Result<(Rgb16Image, Vec<(String, String)>), String> {
+        self.generate_at_depth::<u16>(params)
+    }
+
+    fn generate_escapes(
+        &self,
+        params: FractalParams,
+        final_z: bool,
+    ) -> Result<(Vec<Escape>, StatsHeaders), String> {
+        self.validate_params(&params)?;
+        self.first_pass(&params, final_z)
+            .map(|pass| (pass.escapes, pass.stats))
+    }
 
-    while x * x + y * y <= 4.0 && iteration < max_iterations {
-        let x_temp = x * x - y * y + cx;
-        y = 2.0 * x * y + cy;
-        x = x_temp;
-        iteration += 1;
+    fn name(&self) -> &str {
+        "mandelbrot"
     }
 
-    iteration
+    fn plane_view(&self) -> Option<PlaneView> {
+        Some(PlaneView::centered(4.0))
+    }
+
+    fn validate_params(&self, params: &FractalParams) -> Result<(), String> {
+        default_validate_params(params)?;
+
+        if let Some(variant) = &params.variant {
+            let variant = MandelbrotVariant::parse(variant)?;
+            let coloring = Coloring::from_param(params.coloring.as_deref())?;
+            if coloring == Coloring::Distance && variant != MandelbrotVariant::Classic {
+                return Err("coloring=distance needs variant=classic".to_string());
+            }
+        }
+
+        Ok(())
+    }
 }
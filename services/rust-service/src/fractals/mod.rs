@@ -3,3 +3,146 @@ pub mod mandelbrot;
 pub mod julia;
 pub mod sierpinski;
 pub mod koch;
+pub mod dragon;
+pub mod hilbert;
+pub mod levy;
+pub mod newton;
+pub mod nova;
+pub mod lyapunov;
+pub mod kernels;
+pub mod buddhabrot;
+pub mod nebulabrot;
+pub mod ifs;
+pub mod barnsley;
+pub mod custom_ifs;
+pub mod lsystem;
+pub mod vicsek;
+pub mod subdivision;
+pub mod carpet;
+pub mod apollonian;
+pub mod htree;
+pub mod attractor;
+pub mod bifurcation;
+pub mod plasma;
+pub mod magnet;
+pub mod custom;
+pub mod hybrid;
+pub mod adaptive;
+pub mod antialias;
+
+use apollonian::ApollonianGasket;
+use attractor::StrangeAttractor;
+use barnsley::BarnsleyFern;
+use bifurcation::LogisticBifurcation;
+use buddhabrot::Buddhabrot;
+use carpet::SierpinskiCarpet;
+use custom::CustomFormula;
+use custom_ifs::CustomIfs;
+use dragon::DragonCurve;
+use hilbert::HilbertCurve;
+use htree::HTree;
+use hybrid::HybridFractal;
+use julia::JuliaSet;
+use koch::KochSnowflake;
+use levy::LevyCCurve;
+use lsystem::LSystem;
+use lyapunov::LyapunovFractal;
+use magnet::{MagnetFractal, MagnetKind};
+use mandelbrot::MandelbrotSet;
+use nebulabrot::Nebulabrot;
+use newton::NewtonFractal;
+use nova::NovaFractal;
+use plasma::Plasma;
+use sierpinski::SierpinskiTriangle;
+use traits::Fractal;
+use vicsek::VicsekFractal;
+
+/// Every fractal type accepted by the `type` parameter
+pub const FRACTAL_TYPES: &[&str] = &[
+    "mandelbrot",
+    "julia",
+    "sierpinski",
+    "koch",
+    "dragon",
+    "hilbert",
+    "levy",
+    "newton",
+    "nova",
+    "magnet1",
+    "magnet2",
+    "lyapunov",
+    "buddhabrot",
+    "nebulabrot",
+    "barnsley",
+    "ifs",
+    "lsystem",
+    "vicsek",
+    "carpet",
+    "apollonian",
+    "htree",
+    "attractor",
+    "bifurcation",
+    "plasma",
+    "custom",
+    "hybrid",
+];
+
+/// Types drawn from lines and shapes, which also render as `format=svg`, `pdf` and `eps`
+pub const VECTOR_TYPES: &[&str] = &[
+    "sierpinski",
+    "koch",
+    "dragon",
+    "hilbert",
+    "levy",
+    "lsystem",
+    "vicsek",
+    "carpet",
+    "apollonian",
+    "htree",
+];
+
+/// Types colored from per-pixel iteration counts, which also return them uncolored as
+/// `format=raw`, `format=npy` and the iteration matrix
+pub const ESCAPE_TIME_TYPES: &[&str] = &[
+    "mandelbrot",
+    "julia",
+    "nova",
+    "magnet1",
+    "magnet2",
+    "custom",
+    "hybrid",
+];
+
+/// Select fractal implementation based on type
+pub fn create_fractal(fractal_type: &str) -> Option<Box<dyn Fractal>> {
+    let fractal: Box<dyn Fractal> = match fractal_type.to_lowercase().as_str() {
+        "mandelbrot" => Box::new(MandelbrotSet),
+        "julia" => Box::new(JuliaSet),
+        "sierpinski" => Box::new(SierpinskiTriangle),
+        "koch" => Box::new(KochSnowflake),
+        "dragon" => Box::new(DragonCurve),
+        "hilbert" => Box::new(HilbertCurve),
+        "levy" => Box::new(LevyCCurve),
+        "newton" => Box::new(NewtonFractal),
+        "nova" => Box::new(NovaFractal),
+        "magnet1" => Box::new(MagnetFractal::new(MagnetKind::TypeOne)),
+        "magnet2" => Box::new(MagnetFractal::new(MagnetKind::TypeTwo)),
+        "lyapunov" => Box::new(LyapunovFractal),
+        "buddhabrot" => Box::new(Buddhabrot),
+        "nebulabrot" => Box::new(Nebulabrot),
+        "barnsley" => Box::new(BarnsleyFern),
+        "ifs" => Box::new(CustomIfs),
+        "lsystem" => Box::new(LSystem),
+        "vicsek" => Box::new(VicsekFractal),
+        "carpet" => Box::new(SierpinskiCarpet),
+        "apollonian" => Box::new(ApollonianGasket),
+        "htree" => Box::new(HTree),
+        "attractor" => Box::new(StrangeAttractor),
+        "bifurcation" => Box::new(LogisticBifurcation),
+        "plasma" => Box::new(Plasma),
+        "custom" => Box::new(CustomFormula),
+        "hybrid" => Box::new(HybridFractal),
+        _ => return None,
+    };
+    Some(fractal)
+}
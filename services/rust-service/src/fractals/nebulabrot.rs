@@ -0,0 +1,75 @@
+use super::buddhabrot::{accumulate_orbits, DEFAULT_SAMPLES};
+use super::traits::{default_validate_params, Fractal, FractalParams, PlaneView};
+use crate::rendering::density::{ToneCurve, ToneMap};
+use crate::utils::validation::{validate_iteration_limit, validate_sample_budget};
+use image::{ImageBuffer, Rgb, RgbImage};
+
+// Classic Nebulabrot layering: long orbits in red, short ones in blue
+const DEFAULT_RED_ITERATIONS: u32 = 1000;
+const DEFAULT_GREEN_ITERATIONS: u32 = 200;
+const DEFAULT_BLUE_ITERATIONS: u32 = 20;
+
+/// Nebulabrot: three Buddhabrot density layers with different iteration limits in R/G/B
+pub struct Nebulabrot;
+
+impl Nebulabrot {
+    /// Red, green and blue iteration limits, defaults filled in
+    pub fn channel_iterations(params: &FractalParams) -> [u32; 3] {
+        [
+            params.red_iterations.unwrap_or(DEFAULT_RED_ITERATIONS),
+            params.green_iterations.unwrap_or(DEFAULT_GREEN_ITERATIONS),
+            params.blue_iterations.unwrap_or(DEFAULT_BLUE_ITERATIONS),
+        ]
+    }
+}
+
+impl Fractal for Nebulabrot {
+    fn generate(&self, params: FractalParams) -> Result<RgbImage, String> {
+        self.validate_params(&params)?;
+
+        // Accumulate one density layer per channel, each tone-mapped against its own peak
+        let tone = ToneMap::from_params(&params, ToneCurve::Sqrt)?;
+        let layers: Vec<Vec<f64>> = Self::channel_iterations(&params)
+            .iter()
+            .map(|&iterations| accumulate_orbits(&params, iterations).normalized(tone))
+            .collect();
+
+        let mut img: RgbImage = ImageBuffer::new(params.width, params.height);
+        for (idx, pixel) in img.pixels_mut().enumerate() {
+            *pixel = Rgb([
+                (layers[0][idx] * 255.0) as u8,
+                (layers[1][idx] * 255.0) as u8,
+                (layers[2][idx] * 255.0) as u8,
+            ]);
+        }
+
+        Ok(img)
+    }
+
+    fn name(&self) -> &str {
+        "nebulabrot"
+    }
+
+    fn plane_view(&self) -> Option<PlaneView> {
+        Some(PlaneView::centered(2.0))
+    }
+
+    fn validate_params(&self, params: &FractalParams) -> Result<(), String> {
+        default_validate_params(params)?;
+        ToneMap::from_params(params, ToneCurve::Sqrt)?;
+
+        let channels = Self::channel_iterations(params);
+        for (name, iterations) in ["red_iterations", "green_iterations", "blue_iterations"]
+            .into_iter()
+            .zip(channels)
+        {
+            validate_iteration_limit(name, iterations)?;
+        }
+
+        // All three layers count against the sample budget
+        validate_sample_budget(
+            params.samples.unwrap_or(DEFAULT_SAMPLES),
+            channels.iter().sum(),
+        )
+    }
+}
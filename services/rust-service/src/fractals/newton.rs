@@ -0,0 +1,135 @@
+use super::traits::{default_validate_params, Fractal, FractalParams, PlaneView};
+use crate::rendering::colors::{root_to_color, ColorScheme};
+use crate::utils::complex::{Complex, Polynomial};
+use crate::utils::validation::{parse_newton_coefficients, validate_newton_degree};
+use image::{ImageBuffer, Rgb, RgbImage};
+use rayon::prelude::*;
+
+/// Squared distance at which a point is considered to have reached a root
+const ROOT_TOLERANCE_SQR: f64 = 1e-6;
+
+pub struct NewtonFractal;
+
+impl Fractal for NewtonFractal {
+    fn generate(&self, params: FractalParams) -> Result<RgbImage, String> {
+        self.validate_params(&params)?;
+
+        let FractalParams {
+            width,
+            height,
+            zoom,
+            center_x,
+            center_y,
+            max_iterations,
+            color_scheme,
+            newton_degree,
+            newton_coefficients,
+            ..
+        } = params;
+
+        // Coefficients take precedence over the z^n - 1 degree shorthand
+        let polynomial = match newton_coefficients {
+            Some(coefficients) => Polynomial::new(parse_newton_coefficients(&coefficients)?),
+            None => Polynomial::unity_roots(newton_degree.unwrap_or(3)),
+        };
+        let roots = polynomial.roots();
+
+        let scheme = ColorScheme::from_str(color_scheme.as_deref().unwrap_or("default"));
+
+        // Calculate the complex plane bounds (Newton basins live near the unit circle)
+        let aspect_ratio = width as f64 / height as f64;
+        let scale = 2.0 / zoom;
+        let min_x = center_x - scale * aspect_ratio;
+        let max_x = center_x + scale * aspect_ratio;
+        let min_y = center_y - scale;
+        let max_y = center_y + scale;
+
+        // Pre-calculate all pixel data in parallel (clone scheme per row for parallel capture)
+        let pixels: Vec<[u8; 3]> = (0..height)
+            .into_par_iter()
+            .flat_map(|y| {
+                let scheme = scheme.clone();
+                let polynomial = &polynomial;
+                let roots = &roots;
+                (0..width)
+                    .map(move |x| {
+                        // Map pixel coordinates to complex plane
+                        let zx = min_x + (x as f64 / width as f64) * (max_x - min_x);
+                        let zy = min_y + (y as f64 / height as f64) * (max_y - min_y);
+
+                        // Run Newton-Raphson until the point settles on a root
+                        let (root_index, iterations) = newton_iterations(
+                            Complex::new(zx, zy),
+                            polynomial,
+                            roots,
+                            max_iterations,
+                        );
+
+                        // Map root and convergence speed to color
+                        root_to_color(root_index, roots.len(), iterations, max_iterations, &scheme)
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+
+        // Create image buffer and fill with computed pixels
+        let mut img: RgbImage = ImageBuffer::new(width, height);
+        for (idx, pixel) in img.pixels_mut().enumerate() {
+            *pixel = Rgb(pixels[idx]);
+        }
+
+        Ok(img)
+    }
+
+    fn name(&self) -> &str {
+        "newton"
+    }
+
+    fn plane_view(&self) -> Option<PlaneView> {
+        Some(PlaneView::centered(2.0))
+    }
+
+    fn validate_params(&self, params: &FractalParams) -> Result<(), String> {
+        default_validate_params(params)?;
+
+        // Validate Newton-specific parameters
+        if let Some(degree) = params.newton_degree {
+            validate_newton_degree(degree)?;
+        }
+        if let Some(coefficients) = &params.newton_coefficients {
+            parse_newton_coefficients(coefficients)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Returns the index of the root the point converged to (if any) and the iteration count
+fn newton_iterations(
+    mut z: Complex,
+    polynomial: &Polynomial,
+    roots: &[Complex],
+    max_iterations: u32,
+) -> (Option<usize>, u32) {
+    for iteration in 0..max_iterations {
+        if let Some(index) = roots
+            .iter()
+            .position(|root| (z - *root).norm_sqr() < ROOT_TOLERANCE_SQR)
+        {
+            return (Some(index), iteration);
+        }
+
+        // Newton step: z = z - p(z) / p'(z)
+        let (value, derivative) = polynomial.eval_with_derivative(z);
+        if derivative.norm_sqr() == 0.0 {
+            break;
+        }
+        z = z - value / derivative;
+
+        if !z.is_finite() {
+            break;
+        }
+    }
+
+    (None, max_iterations)
+}
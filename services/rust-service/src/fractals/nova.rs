@@ -0,0 +1,239 @@
+use super::adaptive::{iterate_pixels, FirstPass};
+use super::antialias::Antialias;
+use super::traits::{
+    default_validate_params, reject_angle_coloring, reject_distance_coloring,
+    reject_period_coloring, Fractal, FractalParams, PlaneView, StatsHeaders,
+};
+use crate::rendering::colors::{
+    color_escapes, Channel, ColorScheme, Coloring, Escape, PaletteCycle,
+};
+use crate::rendering::orbit_trap::{OrbitTrap, TrapTracker};
+use crate::rendering::shading::Shading;
+use crate::rendering::Rgb16Image;
+use crate::utils::complex::{Complex, Polynomial};
+use crate::utils::validation::{
+    validate_julia_params, validate_newton_degree, validate_relaxation,
+};
+use image::RgbImage;
+
+/// Squared step size below which the orbit is considered converged
+const CONVERGENCE_TOLERANCE_SQR: f64 = 1e-10;
+
+/// Nova fractal: relaxed Newton iteration z = z - R * p(z) / p'(z) + c with p(z) = z^n - 1.
+/// Without julia_c_* the pixel is c and z starts at 1 (Mandelbrot-style); with them the pixel is z0.
+pub struct NovaFractal;
+
+impl NovaFractal {
+    /// First pass of a render: escape time and closest trap approach for every pixel
+    fn first_pass(
+        &self,
+        params: &FractalParams,
+    ) -> Result<FirstPass<impl Fn(f64, f64, u32) -> Escape + Sync>, String> {
+        let trap = OrbitTrap::from_params(params)?;
+
+        let FractalParams {
+            width,
+            height,
+            zoom,
+            center_x,
+            center_y,
+            max_iterations,
+            julia_c_real,
+            julia_c_imag,
+            newton_degree,
+            relaxation,
+            adaptive,
+            ..
+        } = *params;
+
+        let polynomial = Polynomial::unity_roots(newton_degree.unwrap_or(3));
+        let relaxation = relaxation.unwrap_or(1.0);
+
+        // Julia-style rendering needs both parts of c
+        let julia_c = match (julia_c_real, julia_c_imag) {
+            (Some(c_real), Some(c_imag)) => Some(Complex::new(c_real, c_imag)),
+            (None, None) => None,
+            _ => {
+                return Err(
+                    "julia_c_real and julia_c_imag must be given together for Nova".to_string(),
+                )
+            }
+        };
+
+        // Calculate the complex plane bounds
+        let aspect_ratio = width as f64 / height as f64;
+        let scale = 2.0 / zoom;
+        let min_x = center_x - scale * aspect_ratio;
+        let max_x = center_x + scale * aspect_ratio;
+        let min_y = center_y - scale;
+        let max_y = center_y + scale;
+
+        // One point at pixel coordinates with the given iteration limit
+        let escape_at = move |x: f64, y: f64, limit: u32| {
+            // Map pixel coordinates to complex plane
+            let px = min_x + (x / width as f64) * (max_x - min_x);
+            let py = min_y + (y / height as f64) * (max_y - min_y);
+            let pixel = Complex::new(px, py);
+
+            let (z, c) = match julia_c {
+                Some(c) => (pixel, c),
+                None => (Complex::ONE, pixel),
+            };
+
+            // Compute Nova iteration
+            nova_escape(z, c, &polynomial, relaxation, limit, trap.as_ref())
+        };
+
+        // Escape time and closest trap approach for every pixel, in parallel
+        let (escapes, stats) = iterate_pixels(
+            width,
+            height,
+            max_iterations,
+            adaptive.unwrap_or(false),
+            |x, y, limit| escape_at(x as f64, y as f64, limit),
+        );
+
+        Ok(FirstPass {
+            escapes,
+            stats,
+            escape_at,
+        })
+    }
+
+    /// The render at 8 or 16 bits per channel
+    fn generate_at_depth<C: Channel>(
+        &self,
+        params: FractalParams,
+    ) -> Result<(C::Image, StatsHeaders), String> {
+        self.validate_params(&params)?;
+        let cycle = PaletteCycle::from_params(&params)?;
+        let antialias = Antialias::from_params(&params)?;
+        let shading = Shading::from_params(&params)?;
+
+        // First pass: escape time and closest trap approach for every pixel
+        let FirstPass {
+            escapes,
+            mut stats,
+            escape_at,
+        } = self.first_pass(&params)?;
+
+        let FractalParams {
+            width,
+            height,
+            max_iterations,
+            color_scheme,
+            coloring,
+            ..
+        } = params;
+        let scheme = ColorScheme::from_str(color_scheme.as_deref().unwrap_or("default"));
+        let coloring = Coloring::from_param(coloring.as_deref())?;
+
+        // Second pass: map escapes to color, supersampling edges and lighting slopes if asked
+        let (mut pixels, aa_stats) = antialias.color(
+            &escapes,
+            width,
+            height,
+            |x, y| escape_at(x, y, max_iterations),
+            |escapes| color_escapes(escapes, max_iterations, &scheme, coloring, &cycle),
+        );
+        stats.extend(aa_stats);
+        shading.apply(&mut pixels, &escapes, width, height, max_iterations);
+
+        Ok((C::image(width, height, pixels), stats))
+    }
+}
+
+impl Fractal for NovaFractal {
+    fn generate(&self, params: FractalParams) -> Result<RgbImage, String> {
+        self.generate_with_stats(params).map(|(img, _)| img)
+    }
+
+    fn generate_with_stats(
+        &self,
+        params: FractalParams,
+    ) -> Result<(RgbImage, Vec<(String, String)>), String> {
+        self.generate_at_depth::<u8>(params)
+    }
+
+    fn generate16(
+        &self,
+        params: FractalParams,
+    ) -> Result<(Rgb16Image, Vec<(String, String)>), String> {
+        self.generate_at_depth::<u16>(params)
+    }
+
+    fn generate_escapes(
+        &self,
+        params: FractalParams,
+        _final_z: bool,
+    ) -> Result<(Vec<Escape>, StatsHeaders), String> {
+        self.validate_params(&params)?;
+        self.first_pass(&params)
+            .map(|pass| (pass.escapes, pass.stats))
+    }
+
+    fn name(&self) -> &str {
+        "nova"
+    }
+
+    fn plane_view(&self) -> Option<PlaneView> {
+        Some(PlaneView::centered(2.0))
+    }
+
+    fn validate_params(&self, params: &FractalParams) -> Result<(), String> {
+        default_validate_params(params)?;
+        reject_distance_coloring(params, self.name())?;
+        reject_angle_coloring(params, self.name())?;
+        reject_period_coloring(params, self.name())?;
+
+        // Validate Nova-specific parameters
+        if let Some(degree) = params.newton_degree {
+            validate_newton_degree(degree)?;
+        }
+        if let Some(relaxation) = params.relaxation {
+            validate_relaxation(relaxation)?;
+        }
+        match (params.julia_c_real, params.julia_c_imag) {
+            (Some(c_real), Some(c_imag)) => validate_julia_params(c_real, c_imag)?,
+            (None, None) => {}
+            _ => {
+                return Err(
+                    "julia_c_real and julia_c_imag must be given together for Nova".to_string(),
+                )
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn nova_escape(
+    mut z: Complex,
+    c: Complex,
+    polynomial: &Polynomial,
+    relaxation: f64,
+    max_iterations: u32,
+    trap: Option<&OrbitTrap>,
+) -> Escape {
+    let mut tracker = TrapTracker::new(trap);
+    for iteration in 0..max_iterations {
+        let (value, derivative) = polynomial.eval_with_derivative(z);
+        if derivative.norm_sqr() == 0.0 {
+            return tracker.escape(max_iterations);
+        }
+
+        let next = z - (value / derivative) * Complex::new(relaxation, 0.0) + c;
+        if !next.is_finite() {
+            return tracker.escape(iteration);
+        }
+        tracker.visit(next.re, next.im);
+
+        // Converged points are colored by how quickly they settled
+        if (next - z).norm_sqr() < CONVERGENCE_TOLERANCE_SQR {
+            return tracker.escape(iteration);
+        }
+        z = next;
+    }
+
+    tracker.escape(max_iterations)
+}
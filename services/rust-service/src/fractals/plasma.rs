@@ -0,0 +1,141 @@
+//! Plasma: diamond-square midpoint displacement on a (2^n + 1)-sided grid, shown as a
+//! heightmap through the color scheme. Every random offset comes from the seed, so the same
+//! parameters always produce the same terrain.
+
+use super::traits::{default_validate_params, Fractal, FractalParams};
+use crate::rendering::colors::{normalized_to_color, ColorScheme};
+use crate::utils::rng::Rng;
+use crate::utils::validation::validate_roughness;
+use image::{ImageBuffer, Rgb, RgbImage};
+
+const DEFAULT_ROUGHNESS: f64 = 0.5;
+const DEFAULT_SEED: u64 = 0x9_1A5A;
+
+/// Square grid of heights, row-major
+struct Heightmap {
+    side: usize,
+    heights: Vec<f32>,
+}
+
+impl Heightmap {
+    /// Smallest 2^n + 1 grid covering `width` x `height`; the image is its top-left corner
+    fn covering(width: u32, height: u32) -> Self {
+        let side = (width.max(height) as usize - 1).next_power_of_two() + 1;
+        Self {
+            side,
+            heights: vec![0.0; side * side],
+        }
+    }
+
+    fn get(&self, x: usize, y: usize) -> f32 {
+        self.heights[y * self.side + x]
+    }
+
+    fn set(&mut self, x: usize, y: usize, value: f32) {
+        self.heights[y * self.side + x] = value;
+    }
+
+    /// Mean of the in-grid points `half` away along the axes, for the square step
+    fn edge_mean(&self, x: usize, y: usize, half: usize) -> f32 {
+        let neighbours = [
+            (x.checked_sub(half), Some(y)),
+            (Some(x + half).filter(|&x| x < self.side), Some(y)),
+            (Some(x), y.checked_sub(half)),
+            (Some(x), Some(y + half).filter(|&y| y < self.side)),
+        ];
+        let (sum, count) = neighbours
+            .iter()
+            .filter_map(|&(x, y)| Some(self.get(x?, y?)))
+            .fold((0.0, 0), |(sum, count), h| (sum + h, count + 1));
+        sum / count as f32
+    }
+
+    /// Seed the corners, then halve the step until every point is set. Offsets shrink by
+    /// `roughness` per step, so low values give smooth hills and high values jagged noise.
+    fn diamond_square(&mut self, roughness: f64, seed: u64) {
+        let mut rng = Rng::new(seed);
+        let last = self.side - 1;
+        for (x, y) in [(0, 0), (last, 0), (0, last), (last, last)] {
+            self.set(x, y, rng.range(-1.0, 1.0) as f32);
+        }
+
+        let mut step = last;
+        let mut scale = 1.0;
+        while step > 1 {
+            let half = step / 2;
+
+            // Diamond step: the centre of each square from its four corners
+            for y in (half..self.side).step_by(step) {
+                for x in (half..self.side).step_by(step) {
+                    let mean = (self.get(x - half, y - half)
+                        + self.get(x + half, y - half)
+                        + self.get(x - half, y + half)
+                        + self.get(x + half, y + half))
+                        / 4.0;
+                    self.set(x, y, mean + rng.range(-scale, scale) as f32);
+                }
+            }
+
+            // Square step: the edge midpoints from their (up to four) neighbours
+            for y in (0..self.side).step_by(half) {
+                let first = if (y / half).is_multiple_of(2) {
+                    half
+                } else {
+                    0
+                };
+                for x in (first..self.side).step_by(step) {
+                    let mean = self.edge_mean(x, y, half);
+                    self.set(x, y, mean + rng.range(-scale, scale) as f32);
+                }
+            }
+
+            step = half;
+            scale *= roughness;
+        }
+    }
+}
+
+pub struct Plasma;
+
+impl Fractal for Plasma {
+    fn generate(&self, params: FractalParams) -> Result<RgbImage, String> {
+        self.validate_params(&params)?;
+
+        let (width, height) = (params.width, params.height);
+        let roughness = params.roughness.unwrap_or(DEFAULT_ROUGHNESS);
+        let scheme = ColorScheme::from_str(params.color_scheme.as_deref().unwrap_or("default"));
+
+        let mut map = Heightmap::covering(width, height);
+        map.diamond_square(roughness, params.seed.unwrap_or(DEFAULT_SEED));
+
+        // Stretch the visible heights over the whole palette
+        let visible = |x: u32, y: u32| map.get(x as usize, y as usize);
+        let (min, max) = (0..height)
+            .flat_map(|y| (0..width).map(move |x| (x, y)))
+            .map(|(x, y)| visible(x, y))
+            .fold((f32::MAX, f32::MIN), |(min, max), h| {
+                (min.min(h), max.max(h))
+            });
+        let span = (max - min).max(f32::EPSILON);
+
+        let img: RgbImage = ImageBuffer::from_fn(width, height, |x, y| {
+            let normalized = ((visible(x, y) - min) / span) as f64;
+            Rgb(normalized_to_color(normalized, &scheme))
+        });
+        Ok(img)
+    }
+
+    fn name(&self) -> &str {
+        "plasma"
+    }
+
+    fn validate_params(&self, params: &FractalParams) -> Result<(), String> {
+        default_validate_params(params)?;
+
+        if let Some(roughness) = params.roughness {
+            validate_roughness(roughness)?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,79 @@
+//! Shared square-subdivision renderer for the 3x3 grid fractals (Vicsek, Sierpinski carpet):
+//! split a square into nine cells, keep some of them, and recurse into those.
+
+use crate::rendering::canvas::Canvas;
+use crate::rendering::colors::{normalized_to_color, ColorScheme, GeometryColors};
+
+/// Cell (column, row) of the 3x3 grid, each 0..3
+pub type Cell = (u32, u32);
+
+/// Fixed inputs of one render, shared by every level of the recursion
+struct Subdivision<'a> {
+    cells: &'a [Cell],
+    max_depth: u32,
+    scheme: &'a ColorScheme,
+    fill: Option<[u8; 3]>,
+    /// Outer square, for placing leaf squares on the color gradient
+    frame_origin: (f64, f64),
+    frame_size: f64,
+}
+
+impl Subdivision<'_> {
+    fn draw(&self, canvas: &mut impl Canvas, origin: (f64, f64), size: f64, depth: u32) {
+        let cell = size / 3.0;
+
+        // Stop once the cells would be under a pixel; deeper levels wouldn't change the image
+        if depth >= self.max_depth || cell < 1.0 {
+            canvas.square(origin, size, self.color(origin, size));
+            return;
+        }
+
+        for &(column, row) in self.cells {
+            let cell_origin = (
+                origin.0 + column as f64 * cell,
+                origin.1 + row as f64 * cell,
+            );
+            self.draw(canvas, cell_origin, cell, depth + 1);
+        }
+    }
+
+    fn color(&self, origin: (f64, f64), size: f64) -> [u8; 3] {
+        if let Some(fill) = self.fill {
+            return fill;
+        }
+        let x = origin.0 + size / 2.0 - self.frame_origin.0;
+        let y = origin.1 + size / 2.0 - self.frame_origin.1;
+        let normalized = (x + y) / (2.0 * self.frame_size);
+        normalized_to_color(normalized.clamp(0.0, 1.0), self.scheme)
+    }
+}
+
+/// Draw the fractal kept by `cells` on a blank canvas, centered with padding. Squares take
+/// the fill color, or the color scheme running diagonally across the square, top-left to
+/// bottom-right.
+pub fn render_subdivided<C: Canvas>(
+    width: u32,
+    height: u32,
+    cells: &[Cell],
+    max_depth: u32,
+    scheme: &ColorScheme,
+    colors: &GeometryColors,
+) -> C {
+    let mut canvas = C::blank(width, height, colors.background);
+
+    let padding = 20.0;
+    let size = (width.min(height) as f64 - 2.0 * padding).max(1.0);
+    let origin = ((width as f64 - size) / 2.0, (height as f64 - size) / 2.0);
+
+    let subdivision = Subdivision {
+        cells,
+        max_depth,
+        scheme,
+        fill: colors.fill,
+        frame_origin: origin,
+        frame_size: size,
+    };
+    subdivision.draw(&mut canvas, origin, size, 0);
+
+    canvas
+}
@@ -1,7 +1,19 @@
+use super::antialias::Antialias;
+use super::kernels::Kernel;
+use super::{ESCAPE_TIME_TYPES, VECTOR_TYPES};
+use crate::rendering::colors::{ColorScheme, Coloring, Escape, GeometryColors};
+use crate::rendering::orbit_trap::OrbitTrap;
+use crate::rendering::shading::Shading;
+use crate::rendering::vector::VectorFormat;
+use crate::rendering::{widen, Rgb16Image};
+use crate::utils::validation::{
+    validate_boundary_width, validate_dimensions, validate_interpolation, validate_iterations,
+    validate_line_thickness, validate_zoom,
+};
 use image::RgbImage;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 
-#[derive(Deserialize, Clone, Debug)]
+#[derive(Deserialize, Serialize, Clone, Debug)]
 pub struct FractalParams {
     pub width: u32,
     pub height: u32,
@@ -9,14 +21,126 @@ pub struct FractalParams {
     pub center_x: f64,
     pub center_y: f64,
     pub max_iterations: u32,
+    // Scheme name, or a custom palette's hex stops (see `palette` on the query)
     pub color_scheme: Option<String>,
+    // Between palette stops in rgb (linear light) or oklab; the query applies oklab to
+    // color_scheme, so this is only checked
+    pub interpolation: Option<String>,
+    // Escape-time palette spacing (linear, histogram or orbit_trap)
+    pub coloring: Option<String>,
+    // Orbit trap for coloring=orbit_trap: shape (point, cross, circle), center and radius
+    pub trap_shape: Option<String>,
+    pub trap_x: Option<f64>,
+    pub trap_y: Option<f64>,
+    pub trap_radius: Option<f64>,
+    // Pixels within this estimated distance of the boundary for coloring=distance
+    pub boundary_width: Option<f64>,
+    // Stripes per turn around the origin for coloring=stripe_average
+    pub stripe_density: Option<f64>,
+    // Palette shift (0-1), repeats across the escape range and iteration spacing (linear or log)
+    pub color_offset: Option<f64>,
+    pub color_scale: Option<f64>,
+    pub color_spacing: Option<String>,
+    // Iterate at a low limit first and refine only pixels near the boundary
+    pub adaptive: Option<bool>,
+    // Supersample high-contrast pixels (none or adaptive) and the escape-time difference to a
+    // neighbour that counts as high contrast
+    pub aa: Option<String>,
+    pub aa_threshold: Option<f64>,
+    // Light the escape time as a height field (none or lambert) from a direction in degrees
+    pub shading: Option<String>,
+    pub light_azimuth: Option<f64>,
+    pub light_elevation: Option<f64>,
+    // Escape-time kernel instead of the calibrated one; only set by backend comparisons
+    #[serde(skip)]
+    pub kernel: Option<Kernel>,
+
+    // Mandelbrot family member (classic, celtic, buffalo, ...)
+    pub variant: Option<String>,
 
     // Julia-specific parameters
     pub julia_c_real: Option<f64>,
     pub julia_c_imag: Option<f64>,
+    pub julia_coefficients: Option<String>,
+
+    // Hybrid step pattern, one letter per formula (e.g. MMJB)
+    pub hybrid_pattern: Option<String>,
 
     // Geometric fractal parameters
     pub recursion_depth: Option<u32>,
+
+    // Newton-specific parameters
+    pub newton_degree: Option<u32>,
+    pub newton_coefficients: Option<String>,
+
+    // Nova-specific parameters
+    pub relaxation: Option<f64>,
+
+    // Lyapunov-specific parameters
+    pub lyapunov_sequence: Option<String>,
+
+    // Sampled (Monte Carlo) fractal parameters
+    pub samples: Option<u64>,
+    pub seed: Option<u64>,
+    // Tone-mapping of density renders: linear, sqrt, log or aces, and exposure in stops
+    pub tone_map: Option<String>,
+    pub exposure: Option<f64>,
+
+    // Nebulabrot per-channel iteration limits
+    pub red_iterations: Option<u32>,
+    pub green_iterations: Option<u32>,
+    pub blue_iterations: Option<u32>,
+
+    // User-defined IFS transforms (JSON-encoded)
+    pub ifs_transforms: Option<String>,
+
+    // L-system parameters (recursion_depth sets the rewriting passes)
+    pub lsystem_preset: Option<String>,
+    pub lsystem_axiom: Option<String>,
+    pub lsystem_rules: Option<String>,
+    pub lsystem_angle: Option<f64>,
+
+    // Vicsek sub-type (plus or x)
+    pub vicsek_variant: Option<String>,
+
+    // Apollonian gasket curvature cutoff (outer circle = 1)
+    pub max_curvature: Option<f64>,
+
+    // Stroke width in pixels for line-drawn fractals (koch, dragon, hilbert, levy, lsystem, htree)
+    pub line_thickness: Option<u32>,
+
+    // Solid colors for geometric fractals (6-digit hex): background, lines and filled shapes
+    pub background_color: Option<String>,
+    pub stroke_color: Option<String>,
+    pub fill_color: Option<String>,
+
+    // Strange attractor map (clifford, dejong, lorenz) and its coefficients
+    pub attractor: Option<String>,
+    pub attractor_a: Option<f64>,
+    pub attractor_b: Option<f64>,
+    pub attractor_c: Option<f64>,
+    pub attractor_d: Option<f64>,
+
+    // Logistic map bifurcation diagram: rates along x, orbit values along y
+    pub bifurcation_r_min: Option<f64>,
+    pub bifurcation_r_max: Option<f64>,
+    pub bifurcation_y_min: Option<f64>,
+    pub bifurcation_y_max: Option<f64>,
+
+    // Plasma (diamond-square) displacement falloff per subdivision; seed picks the terrain
+    pub roughness: Option<f64>,
+
+    // User-defined escape-time formula of z and c (type=custom)
+    pub formula: Option<String>,
+
+    // Color vision deficiency to simulate on the finished image
+    pub simulate: Option<String>,
+    // Power-law gamma the finished image is encoded for instead of sRGB
+    pub gamma: Option<f64>,
+    // Reduce the finished image to gray levels for low-depth output such as laser engraving
+    pub dither: Option<String>,
+    pub dither_levels: Option<u32>,
+    pub equalize: Option<bool>,
 }
 
 impl Default for FractalParams {
@@ -29,9 +153,68 @@ impl Default for FractalParams {
             center_y: 0.0,
             max_iterations: 100,
             color_scheme: None,
+            interpolation: None,
+            coloring: None,
+            trap_shape: None,
+            trap_x: None,
+            trap_y: None,
+            trap_radius: None,
+            boundary_width: None,
+            stripe_density: None,
+            color_offset: None,
+            color_scale: None,
+            color_spacing: None,
+            adaptive: None,
+            aa: None,
+            aa_threshold: None,
+            shading: None,
+            light_azimuth: None,
+            light_elevation: None,
+            kernel: None,
+            variant: None,
             julia_c_real: None,
             julia_c_imag: None,
+            julia_coefficients: None,
+            hybrid_pattern: None,
             recursion_depth: None,
+            newton_degree: None,
+            newton_coefficients: None,
+            relaxation: None,
+            lyapunov_sequence: None,
+            samples: None,
+            seed: None,
+            tone_map: None,
+            exposure: None,
+            red_iterations: None,
+            green_iterations: None,
+            blue_iterations: None,
+            ifs_transforms: None,
+            lsystem_preset: None,
+            lsystem_axiom: None,
+            lsystem_rules: None,
+            lsystem_angle: None,
+            vicsek_variant: None,
+            max_curvature: None,
+            line_thickness: None,
+            background_color: None,
+            stroke_color: None,
+            fill_color: None,
+            attractor: None,
+            attractor_a: None,
+            attractor_b: None,
+            attractor_c: None,
+            attractor_d: None,
+            bifurcation_r_min: None,
+            bifurcation_r_max: None,
+            bifurcation_y_min: None,
+            bifurcation_y_max: None,
+            roughness: None,
+            formula: None,
+            simulate: None,
+            gamma: None,
+            dither: None,
+            dither_levels: None,
+            equalize: None,
         }
     }
 }
@@ -39,27 +222,182 @@ impl Default for FractalParams {
 /// Common parameter validation shared by all fractal types.
 /// Call this from fractal-specific validate_params before doing type-specific checks.
 pub fn default_validate_params(params: &FractalParams) -> Result<(), String> {
-    if params.width == 0 || params.height == 0 || params.width > 4096 || params.height > 4096 {
-        return Err(
-            "Invalid dimensions. Width and height must be between 1 and 4096.".to_string(),
-        );
+    validate_dimensions(params.width, params.height)?;
+    validate_zoom(params.zoom)?;
+    validate_iterations(params.max_iterations)?;
+    if let Some(palette) = params
+        .color_scheme
+        .as_deref()
+        .filter(|s| ColorScheme::is_palette(s))
+    {
+        ColorScheme::parse_palette(palette)?;
+    }
+    if let Some(interpolation) = &params.interpolation {
+        validate_interpolation(interpolation)?;
+    }
+    if let Some(coloring) = &params.coloring {
+        Coloring::parse(coloring)?;
+    }
+    OrbitTrap::from_params(params)?;
+    GeometryColors::from_params(params)?;
+    Antialias::from_params(params)?;
+    Shading::from_params(params)?;
+    if let Some(width) = params.boundary_width {
+        validate_boundary_width(width)?;
     }
+    if let Some(thickness) = params.line_thickness {
+        validate_line_thickness(thickness)?;
+    }
+
+    Ok(())
+}
 
-    if params.zoom <= 0.0 || params.zoom > 1e10 {
-        return Err("Invalid zoom. Must be between 0 and 1e10.".to_string());
+/// For escape-time types that can't estimate distances to their boundary
+pub fn reject_distance_coloring(params: &FractalParams, name: &str) -> Result<(), String> {
+    if Coloring::from_param(params.coloring.as_deref())? == Coloring::Distance {
+        return Err(format!(
+            "coloring=distance is not supported for type={}. Use mandelbrot or julia.",
+            name
+        ));
     }
+    Ok(())
+}
 
-    if params.max_iterations == 0 || params.max_iterations > 10000 {
-        return Err("Invalid max_iterations. Must be between 1 and 10000.".to_string());
+/// For escape-time types whose orbits also stop by converging, so there's no escape angle
+pub fn reject_angle_coloring(params: &FractalParams, name: &str) -> Result<(), String> {
+    let coloring = Coloring::from_param(params.coloring.as_deref())?;
+    if matches!(
+        coloring,
+        Coloring::BinaryDecomposition | Coloring::StripeAverage
+    ) {
+        return Err(format!(
+            "coloring={} is not supported for type={}. Use mandelbrot, julia, hybrid or custom.",
+            params
+                .coloring
+                .as_deref()
+                .unwrap_or_default()
+                .to_lowercase(),
+            name
+        ));
     }
+    Ok(())
+}
 
+/// For escape-time types whose interior orbits aren't followed on to find their cycle
+pub fn reject_period_coloring(params: &FractalParams, name: &str) -> Result<(), String> {
+    if Coloring::from_param(params.coloring.as_deref())? == Coloring::Period {
+        return Err(format!(
+            "coloring=period is not supported for type={}. Use mandelbrot or julia.",
+            name
+        ));
+    }
     Ok(())
 }
 
+/// How center_x/center_y/zoom map onto the plane for fractals rendered over a region
+#[derive(Clone, Copy, Debug)]
+pub struct PlaneView {
+    /// Half of the visible height at zoom 1 (the width follows the image aspect ratio)
+    pub half_height: f64,
+    /// Plane point shown when center_x and center_y are 0
+    pub origin: (f64, f64),
+    /// Names of the horizontal and vertical plane axes
+    pub axes: (&'static str, &'static str),
+}
+
+/// Plane region covered by a render
+#[derive(Clone, Copy, Debug)]
+pub struct PlaneBounds {
+    pub x_min: f64,
+    pub x_max: f64,
+    pub y_min: f64,
+    pub y_max: f64,
+}
+
+impl PlaneView {
+    /// View of the complex plane centered on the origin
+    pub const fn centered(half_height: f64) -> Self {
+        Self {
+            half_height,
+            origin: (0.0, 0.0),
+            axes: ("Re", "Im"),
+        }
+    }
+
+    /// Region shown for the given center, zoom and image size
+    pub fn bounds(&self, params: &FractalParams) -> PlaneBounds {
+        let half_height = self.half_height / params.zoom;
+        let half_width = half_height * params.width as f64 / params.height as f64;
+        let center_x = self.origin.0 + params.center_x;
+        let center_y = self.origin.1 + params.center_y;
+
+        PlaneBounds {
+            x_min: center_x - half_width,
+            x_max: center_x + half_width,
+            y_min: center_y - half_height,
+            y_max: center_y + half_height,
+        }
+    }
+}
+
+/// Response headers describing a render, from `generate_with_stats`
+pub type StatsHeaders = Vec<(String, String)>;
+
 pub trait Fractal: Send + Sync {
     /// Generate the fractal image with the given parameters
     fn generate(&self, params: FractalParams) -> Result<RgbImage, String>;
 
+    /// Generate the image along with response headers describing the render (such as the work
+    /// an adaptive render saved); none by default
+    fn generate_with_stats(
+        &self,
+        params: FractalParams,
+    ) -> Result<(RgbImage, Vec<(String, String)>), String> {
+        self.generate(params).map(|img| (img, Vec::new()))
+    }
+
+    /// `generate_with_stats` at 16 bits per channel, for `bit_depth=16`. By default the 8-bit
+    /// image is widened, which keeps its levels; types whose coloring has finer gradations than
+    /// 8 bits can hold override this to color at full precision.
+    fn generate16(
+        &self,
+        params: FractalParams,
+    ) -> Result<(Rgb16Image, Vec<(String, String)>), String> {
+        self.generate_with_stats(params)
+            .map(|(img, stats)| (widen(&img), stats))
+    }
+
+    /// The drawing as an SVG, PDF or EPS document, for `format=svg`, `pdf` and `eps`. Only types
+    /// in `VECTOR_TYPES`, which are built from lines and shapes, override this; the rest have no
+    /// vector form.
+    fn generate_vector(
+        &self,
+        _params: FractalParams,
+        format: VectorFormat,
+    ) -> Result<Vec<u8>, String> {
+        Err(format!(
+            "format={} isn't available for type={}. Vector types: {}.",
+            format.name(),
+            self.name(),
+            VECTOR_TYPES.join(", ")
+        ))
+    }
+
+    /// Uncolored first-pass results for every pixel, row by row, for `format=raw`, `format=npy`
+    /// and the iteration matrix. With `final_z`, the orbit's last point is kept wherever the type
+    /// records it, for smooth iteration counts. Only types in `ESCAPE_TIME_TYPES` override this.
+    fn generate_escapes(
+        &self,
+        _params: FractalParams,
+        _final_z: bool,
+    ) -> Result<(Vec<Escape>, StatsHeaders), String> {
+        Err(format!(
+            "Iteration data isn't available for type={}. Escape-time types: {}.",
+            self.name(),
+            ESCAPE_TIME_TYPES.join(", ")
+        ))
+    }
+
     /// Get the name of this fractal type
     fn name(&self) -> &str;
 
@@ -67,4 +405,9 @@ pub trait Fractal: Send + Sync {
     fn validate_params(&self, params: &FractalParams) -> Result<(), String> {
         default_validate_params(params)
     }
+
+    /// Plane mapping for region-based fractals; None for self-framing geometric ones
+    fn plane_view(&self) -> Option<PlaneView> {
+        None
+    }
 }
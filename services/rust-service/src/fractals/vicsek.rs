@@ -0,0 +1,103 @@
+use super::subdivision::{render_subdivided, Cell};
+use super::traits::{default_validate_params, Fractal, FractalParams};
+use crate::rendering::canvas::Canvas;
+use crate::rendering::colors::{ColorScheme, GeometryColors};
+use crate::rendering::vector::{VectorBuilder, VectorFormat};
+use crate::utils::validation::validate_recursion_depth;
+use image::RgbImage;
+
+/// Values accepted by `vicsek_variant`
+pub const VICSEK_VARIANTS: &[&str] = &["plus", "x"];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Variant {
+    /// Center square and its four edge neighbours
+    Plus,
+    /// Center square and the four corners
+    Cross,
+}
+
+impl Variant {
+    fn parse(name: &str) -> Result<Self, String> {
+        match name.to_lowercase().as_str() {
+            "plus" => Ok(Variant::Plus),
+            "x" => Ok(Variant::Cross),
+            _ => Err(format!(
+                "Invalid vicsek_variant. Must be one of: {}.",
+                VICSEK_VARIANTS.join(", ")
+            )),
+        }
+    }
+
+    /// Cells (column, row) of the 3x3 grid kept at each subdivision
+    fn cells(self) -> [Cell; 5] {
+        match self {
+            Variant::Plus => [(1, 0), (0, 1), (1, 1), (2, 1), (1, 2)],
+            Variant::Cross => [(0, 0), (2, 0), (1, 1), (0, 2), (2, 2)],
+        }
+    }
+}
+
+/// Vicsek (box) fractal: split each square into a 3x3 grid and keep five of the cells
+pub struct VicsekFractal;
+
+impl VicsekFractal {
+    fn paint<C: Canvas>(params: FractalParams) -> Result<C, String> {
+        let colors = GeometryColors::from_params(&params)?;
+
+        let FractalParams {
+            width,
+            height,
+            recursion_depth,
+            color_scheme,
+            vicsek_variant,
+            ..
+        } = params;
+
+        let depth = recursion_depth.unwrap_or(4);
+        let variant = Variant::parse(vicsek_variant.as_deref().unwrap_or("plus"))?;
+        let scheme = ColorScheme::from_str(color_scheme.as_deref().unwrap_or("default"));
+
+        Ok(render_subdivided(
+            width,
+            height,
+            &variant.cells(),
+            depth,
+            &scheme,
+            &colors,
+        ))
+    }
+}
+
+impl Fractal for VicsekFractal {
+    fn generate(&self, params: FractalParams) -> Result<RgbImage, String> {
+        self.validate_params(&params)?;
+        Self::paint(params)
+    }
+
+    fn generate_vector(
+        &self,
+        params: FractalParams,
+        format: VectorFormat,
+    ) -> Result<Vec<u8>, String> {
+        self.validate_params(&params)?;
+        Self::paint::<VectorBuilder>(params)?.build(format)
+    }
+
+    fn name(&self) -> &str {
+        "vicsek"
+    }
+
+    fn validate_params(&self, params: &FractalParams) -> Result<(), String> {
+        default_validate_params(params)?;
+
+        if let Some(depth) = params.recursion_depth {
+            validate_recursion_depth(depth)?;
+        }
+        if let Some(variant) = &params.vicsek_variant {
+            Variant::parse(variant)?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,239 @@
+//! Readiness checks for the files and services a deployment is configured to use. Rendering
+//! only needs the CPU, so a failing dependency marks the service degraded but still serving,
+//! unless the dependency is listed as required.
+//!
+//! Checks (skipped when their setting is unset):
+//! - `config_file`: `CONFIG_FILE` still parses
+//! - `quota_file`: `QUOTA_FILE` still parses
+//! - `usage_log`: `USAGE_LOG` can be appended to
+//! - `nats`: the first `NATS_URL` server accepts a TCP connection (`nats-queue` builds only)
+//!
+//! Configuration (all optional):
+//! - `HEALTH_REQUIRED`: comma-separated checks (`config_file`, `quota_file`, `usage_log`,
+//!   `nats`) that make the service unavailable (503) when they fail or aren't configured
+//! - `HEALTH_TIMEOUT_MS`: time limit for each check (default 1000)
+
+use crate::config;
+use crate::quota;
+use axum::{
+    http::StatusCode,
+    response::{IntoResponse, Response},
+};
+use schemars::JsonSchema;
+use serde::Serialize;
+use std::future::Future;
+use std::time::{Duration, Instant};
+#[cfg(feature = "nats-queue")]
+use tokio::net::TcpStream;
+
+const DEFAULT_TIMEOUT_MS: u64 = 1000;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum CheckStatus {
+    Ok,
+    Failed,
+    /// Not configured for this deployment
+    Skipped,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct DependencyCheck {
+    /// config_file, quota_file, usage_log or nats
+    pub name: &'static str,
+    pub status: CheckStatus,
+    /// Listed in HEALTH_REQUIRED
+    pub required: bool,
+    /// Time the probe took; absent for skipped checks
+    pub latency_ms: Option<f64>,
+    pub detail: String,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Readiness {
+    /// Every configured dependency answered
+    Ready,
+    /// An optional dependency failed; CPU renders are still served
+    Degraded,
+    /// A required dependency failed or isn't configured (HTTP 503)
+    Unavailable,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct ReadinessResponse {
+    pub status: Readiness,
+    pub checks: Vec<DependencyCheck>,
+}
+
+struct HealthSettings {
+    required: Vec<String>,
+    timeout: Duration,
+}
+
+impl HealthSettings {
+    fn from_env() -> Self {
+        let required = config::var("HEALTH_REQUIRED")
+            .map(|names| {
+                names
+                    .split(',')
+                    .map(|name| name.trim().to_lowercase())
+                    .filter(|name| !name.is_empty())
+                    .collect()
+            })
+            .unwrap_or_default();
+        let timeout_ms = config::var("HEALTH_TIMEOUT_MS")
+            .and_then(|value| value.parse().ok())
+            .filter(|&ms: &u64| ms > 0)
+            .unwrap_or(DEFAULT_TIMEOUT_MS);
+
+        Self {
+            required,
+            timeout: Duration::from_millis(timeout_ms),
+        }
+    }
+}
+
+/// Run `probe` under the time limit, or report the check skipped when there is none
+async fn check<F>(
+    name: &'static str,
+    probe: Option<F>,
+    skipped: &str,
+    settings: &HealthSettings,
+) -> DependencyCheck
+where
+    F: Future<Output = Result<String, String>>,
+{
+    let required = settings.required.iter().any(|required| required == name);
+    let Some(probe) = probe else {
+        return DependencyCheck {
+            name,
+            status: CheckStatus::Skipped,
+            required,
+            latency_ms: None,
+            detail: skipped.to_string(),
+        };
+    };
+
+    let started = Instant::now();
+    let result = tokio::time::timeout(settings.timeout, probe)
+        .await
+        .unwrap_or_else(|_| {
+            Err(format!(
+                "Timed out after {}ms",
+                settings.timeout.as_millis()
+            ))
+        });
+    let (status, detail) = match result {
+        Ok(detail) => (CheckStatus::Ok, detail),
+        Err(error) => (CheckStatus::Failed, error),
+    };
+
+    DependencyCheck {
+        name,
+        status,
+        required,
+        latency_ms: Some(started.elapsed().as_secs_f64() * 1000.0),
+        detail,
+    }
+}
+
+/// The settings file still parses, so the next reload will apply
+async fn probe_config_file(path: &str) -> Result<String, String> {
+    let settings = config::read_file(path)?;
+    Ok(format!("{} settings in {}", settings.len(), path))
+}
+
+/// The quota file still parses, so the next reload keeps per-tenant budgets
+async fn probe_quota_file(path: &str) -> Result<String, String> {
+    let tenants = quota::check_file(path).map_err(|e| format!("{}: {}", path, e))?;
+    Ok(format!("{} tenants in {}", tenants, path))
+}
+
+/// Usage events can still be appended to the log
+async fn probe_usage_log(path: &str) -> Result<String, String> {
+    tokio::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .await
+        .map_err(|e| format!("Cannot append to {}: {}", path, e))?;
+    Ok(format!("{} is writable", path))
+}
+
+/// The first NATS server accepts connections
+#[cfg(feature = "nats-queue")]
+async fn probe_nats(url: &str) -> Result<String, String> {
+    let server = url.split(',').next().unwrap_or(url).trim();
+    let rest = server.split_once("://").map_or(server, |(_, rest)| rest);
+    let host = rest.rsplit_once('@').map_or(rest, |(_, host)| host);
+    let host = host.split('/').next().unwrap_or(host);
+    let address = if host.ends_with(']') || !host.contains(':') {
+        format!("{}:4222", host)
+    } else {
+        host.to_string()
+    };
+
+    TcpStream::connect(&address)
+        .await
+        .map_err(|e| format!("Cannot connect to {}: {}", address, e))?;
+    Ok(format!("Connected to {}", address))
+}
+
+/// Probe every dependency concurrently
+async fn readiness_report() -> ReadinessResponse {
+    let settings = HealthSettings::from_env();
+    let config_file = std::env::var("CONFIG_FILE")
+        .ok()
+        .map(|path| async move { probe_config_file(&path).await });
+    let quota_file =
+        config::var("QUOTA_FILE").map(|path| async move { probe_quota_file(&path).await });
+    let usage_log = std::env::var("USAGE_LOG")
+        .ok()
+        .map(|path| async move { probe_usage_log(&path).await });
+    #[cfg(feature = "nats-queue")]
+    let nats = std::env::var("NATS_URL")
+        .ok()
+        .map(|url| async move { probe_nats(&url).await });
+    #[cfg(not(feature = "nats-queue"))]
+    let nats = None::<std::future::Ready<Result<String, String>>>;
+
+    let (config_file, quota_file, usage_log, nats) = tokio::join!(
+        check("config_file", config_file, "CONFIG_FILE not set", &settings),
+        check("quota_file", quota_file, "QUOTA_FILE not set", &settings),
+        check("usage_log", usage_log, "USAGE_LOG not set", &settings),
+        check(
+            "nats",
+            nats,
+            "NATS_URL not set or built without nats-queue",
+            &settings
+        ),
+    );
+    let checks = vec![config_file, quota_file, usage_log, nats];
+
+    let status = if checks
+        .iter()
+        .any(|check| check.required && check.status != CheckStatus::Ok)
+    {
+        Readiness::Unavailable
+    } else if checks
+        .iter()
+        .any(|check| check.status == CheckStatus::Failed)
+    {
+        Readiness::Degraded
+    } else {
+        Readiness::Ready
+    };
+
+    ReadinessResponse { status, checks }
+}
+
+// Readiness probe: 503 only when a required dependency is down
+pub async fn readiness() -> Response {
+    let report = readiness_report().await;
+    let status = match report.status {
+        Readiness::Unavailable => StatusCode::SERVICE_UNAVAILABLE,
+        Readiness::Ready | Readiness::Degraded => StatusCode::OK,
+    };
+    (status, axum::Json(report)).into_response()
+}
@@ -0,0 +1,93 @@
+//! `response=json`: the encoded image as a base64 data URI alongside the parameters it was
+//! rendered with and its statistics, for frontends that want both from a single fetch instead of
+//! reading them from response headers.
+
+use crate::manifest::{canonical_params, Manifest};
+use crate::plugins::RenderMetadata;
+use crate::utils::http::append_headers;
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use schemars::JsonSchema;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+/// How the rendered image is returned
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ResponseMode {
+    /// The encoded bytes as the body, metadata in headers
+    #[default]
+    Image,
+    /// A JSON object holding the image as a data URI and the metadata
+    Json,
+}
+
+impl ResponseMode {
+    /// Names accepted for `response`
+    pub const NAMES: [&'static str; 2] = ["image", "json"];
+
+    /// Mode for the optional `response` request parameter, `Image` when absent
+    pub fn from_param(name: Option<&str>) -> Result<Self, String> {
+        match name.map(str::to_lowercase).as_deref() {
+            None | Some("image") => Ok(ResponseMode::Image),
+            Some("json") => Ok(ResponseMode::Json),
+            Some(_) => Err(format!(
+                "Invalid response. Must be one of: {}.",
+                Self::NAMES.join(", ")
+            )),
+        }
+    }
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct ImageJsonResponse {
+    fractal_type: String,
+    /// Media type of the encoded image, e.g. image/png
+    content_type: String,
+    /// The encoded image as `data:<content_type>;base64,...`, usable directly as an img src
+    image: String,
+    /// Parameters exactly as rendered (after defaults and any low-power caps), keys sorted
+    params: Value,
+    /// Time spent rendering, before encoding
+    render_time_ms: u64,
+    /// Statistics the render reported, by response header name: adaptive iteration and
+    /// antialiasing counts, low-power degradation and remaining quota where they apply
+    stats: BTreeMap<String, String>,
+    /// Reproducibility manifest, with manifest=true
+    #[serde(skip_serializing_if = "Option::is_none")]
+    manifest: Option<Manifest>,
+}
+
+/// The encoded image and its render metadata as a JSON body. Headers are still sent as they
+/// would be with the image.
+pub fn create_image_json_response(
+    bytes: &[u8],
+    content_type: &str,
+    metadata: &RenderMetadata,
+    manifest: Option<Manifest>,
+    extra_headers: &[(String, String)],
+) -> Response {
+    let body = ImageJsonResponse {
+        fractal_type: metadata.fractal_type.clone(),
+        content_type: content_type.to_string(),
+        image: format!("data:{};base64,{}", content_type, STANDARD.encode(bytes)),
+        params: canonical_params(&metadata.params),
+        render_time_ms: metadata.render_time.as_millis() as u64,
+        stats: metadata.headers.iter().cloned().collect(),
+        manifest,
+    };
+
+    let json = serde_json::to_vec(&body).unwrap_or_default();
+    let builder = Response::builder()
+        .status(StatusCode::OK)
+        .header("Content-Type", "application/json")
+        .header("Content-Length", json.len().to_string());
+
+    let mut response = builder
+        .body(axum::body::Body::from(json))
+        .unwrap()
+        .into_response();
+    append_headers(response.headers_mut(), extra_headers);
+    response
+}
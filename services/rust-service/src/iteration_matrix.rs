@@ -0,0 +1,108 @@
+//! Per-pixel iteration counts of an escape-time render as JSON, for web clients that color them
+//! themselves (e.g. uploading them as a WebGL texture). Larger renders can be averaged down in
+//! square blocks; the matrix is capped either way, since JSON costs several bytes per count and
+//! `format=raw` serves big buffers better.
+
+use crate::pipeline::{render_escapes, AppState, RenderOptions};
+use crate::query::FractalQuery;
+use crate::rendering::colors::Escape;
+use crate::utils::validation::validate_iteration_matrix;
+use crate::ErrorResponse;
+use axum::{
+    extract::{Query, State},
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+#[derive(Deserialize, JsonSchema)]
+pub struct IterationMatrixOptions {
+    /// Average blocks of this many pixels per side into one count, 1-16 (default 1)
+    downsample: Option<u32>,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct IterationMatrixResponse {
+    fractal_type: String,
+    /// Columns of the matrix, width / downsample rounded up
+    width: u32,
+    /// Rows of the matrix, height / downsample rounded up
+    height: u32,
+    downsample: u32,
+    /// Count meaning inside the set; lower than requested when the render was degraded
+    max_iterations: u32,
+    /// Row-major from the top-left, `width` x `height` counts
+    iterations: Vec<u32>,
+}
+
+/// Mean count of each `factor` x `factor` block, rounded; blocks on the right and bottom edges
+/// average the pixels they cover
+fn downsample(escapes: &[Escape], width: u32, height: u32, factor: u32) -> Vec<u32> {
+    if factor == 1 {
+        return escapes.iter().map(|escape| escape.iterations).collect();
+    }
+    let (columns, rows) = (width.div_ceil(factor), height.div_ceil(factor));
+    let mut cells = Vec::with_capacity((columns * rows) as usize);
+    for row in 0..rows {
+        let (y0, y1) = (row * factor, ((row + 1) * factor).min(height));
+        for column in 0..columns {
+            let (x0, x1) = (column * factor, ((column + 1) * factor).min(width));
+            let mut sum = 0u64;
+            for y in y0..y1 {
+                for x in x0..x1 {
+                    sum += u64::from(escapes[(y * width + x) as usize].iterations);
+                }
+            }
+            let count = u64::from((x1 - x0) * (y1 - y0));
+            cells.push(((sum + count / 2) / count) as u32);
+        }
+    }
+    cells
+}
+
+// Iteration counts of an escape-time render as a JSON matrix
+pub async fn iteration_matrix(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Query(query): Query<FractalQuery>,
+    Query(options): Query<IterationMatrixOptions>,
+) -> Response {
+    let render_options = RenderOptions::billed_to(&headers);
+    let factor = options.downsample.unwrap_or(1);
+
+    // Rendering is CPU-bound, keep it off the async workers
+    let result = tokio::task::spawn_blocking(move || {
+        let fractal_type = query.fractal_type();
+        let params = query.into_params();
+        // Refuse oversized matrices before spending a render on them
+        validate_iteration_matrix(params.width, params.height, factor)
+            .map_err(|error| (StatusCode::BAD_REQUEST, error))?;
+
+        let (escapes, metadata) =
+            render_escapes(&state, &fractal_type, params, false, &render_options)
+                .map_err(|e| (e.status(), e.message()))?;
+        let params = &metadata.params;
+        Ok(IterationMatrixResponse {
+            width: params.width.div_ceil(factor),
+            height: params.height.div_ceil(factor),
+            downsample: factor,
+            max_iterations: params.max_iterations,
+            iterations: downsample(&escapes, params.width, params.height, factor),
+            fractal_type: metadata.fractal_type,
+        })
+    })
+    .await
+    .unwrap_or_else(|e| {
+        Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Iteration matrix task failed: {}", e),
+        ))
+    });
+
+    match result {
+        Ok(response) => (StatusCode::OK, axum::Json(response)).into_response(),
+        Err((status, error)) => (status, axum::Json(ErrorResponse { error })).into_response(),
+    }
+}
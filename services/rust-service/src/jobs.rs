@@ -0,0 +1,131 @@
+//! Recent render jobs for a "latest renders" page. Every job finished by the queue consumer
+//! gets a small thumbnail in its result; jobs sent with `"public": true` are also kept, newest
+//! first, in an in-memory feed served at `GET /api/jobs/recent`. The feed holds the last
+//! FEED_LENGTH public jobs and starts empty on each restart.
+
+use crate::pipeline::AppState;
+use crate::utils::validation::validate_recent_jobs_limit;
+use crate::ErrorResponse;
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+
+/// Public jobs kept for the feed
+pub const FEED_LENGTH: usize = 50;
+
+/// Jobs returned when no `limit` is given
+const DEFAULT_LIMIT: usize = 20;
+
+#[cfg(feature = "nats-queue")]
+pub use thumbnails::thumbnail;
+
+#[derive(Clone, Debug, Serialize, JsonSchema)]
+pub struct RecentJob {
+    /// The id the job was submitted with, if any
+    pub request_id: Option<String>,
+    pub fractal_type: String,
+    pub width: u32,
+    pub height: u32,
+    /// Seconds since the Unix epoch
+    pub completed_at: u64,
+    /// Parameters the job rendered with, enough to render it again
+    pub params: Value,
+    /// PNG data URI, longest edge at most THUMBNAIL_SIZE pixels
+    pub thumbnail: String,
+}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct RecentJobsQuery {
+    /// Jobs to return, newest first, 1-50 (default 20)
+    limit: Option<usize>,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct RecentJobsResponse {
+    jobs: Vec<RecentJob>,
+}
+
+/// Public jobs, newest first
+#[derive(Default)]
+pub struct RecentJobs {
+    jobs: Mutex<VecDeque<RecentJob>>,
+}
+
+impl RecentJobs {
+    #[cfg(feature = "nats-queue")]
+    pub fn record(&self, job: RecentJob) {
+        let mut jobs = self.jobs.lock().unwrap_or_else(|e| e.into_inner());
+        jobs.push_front(job);
+        jobs.truncate(FEED_LENGTH);
+    }
+
+    pub fn latest(&self, limit: usize) -> Vec<RecentJob> {
+        let jobs = self.jobs.lock().unwrap_or_else(|e| e.into_inner());
+        jobs.iter().take(limit).cloned().collect()
+    }
+}
+
+#[cfg(feature = "nats-queue")]
+mod thumbnails {
+    use crate::rendering::encoder::{encode_image, EncodeOptions, OutputFormat};
+    use crate::rendering::gamma::{decode, linear_to_srgb};
+    use image::{ImageBuffer, RgbImage};
+
+    /// Longest thumbnail edge in pixels
+    pub const THUMBNAIL_SIZE: u32 = 128;
+
+    /// The image scaled down to fit THUMBNAIL_SIZE, as PNG bytes. Each thumbnail pixel averages
+    /// the block of pixels it covers in linear light.
+    pub fn thumbnail(img: &RgbImage) -> Result<Vec<u8>, String> {
+        let (width, height) = img.dimensions();
+        let scale = (THUMBNAIL_SIZE as f64 / width.max(height) as f64).min(1.0);
+        let thumb_width = ((width as f64 * scale).round() as u32).max(1);
+        let thumb_height = ((height as f64 * scale).round() as u32).max(1);
+
+        let thumb = ImageBuffer::from_fn(thumb_width, thumb_height, |x, y| {
+            let (x0, x1) = block(x, thumb_width, width);
+            let (y0, y1) = block(y, thumb_height, height);
+            let mut sum = [0.0; 3];
+            for source_y in y0..y1 {
+                for source_x in x0..x1 {
+                    let pixel = img.get_pixel(source_x, source_y);
+                    for (total, &channel) in sum.iter_mut().zip(&pixel.0) {
+                        *total += decode(channel);
+                    }
+                }
+            }
+            let count = ((x1 - x0) * (y1 - y0)) as f64;
+            image::Rgb(sum.map(|total| linear_to_srgb(total / count)))
+        });
+        encode_image(&thumb, OutputFormat::Png, &EncodeOptions::default())
+    }
+
+    /// Source pixels [start, end) covered by thumbnail pixel `index`, never empty
+    fn block(index: u32, thumb_length: u32, length: u32) -> (u32, u32) {
+        let start = (index as u64 * length as u64 / thumb_length as u64) as u32;
+        let end = ((index as u64 + 1) * length as u64 / thumb_length as u64) as u32;
+        (start, end.max(start + 1).min(length))
+    }
+}
+
+// Feed of recent public render jobs with thumbnails
+pub async fn recent_jobs(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<RecentJobsQuery>,
+) -> Response {
+    let limit = query.limit.unwrap_or(DEFAULT_LIMIT);
+    if let Err(error) = validate_recent_jobs_limit(limit) {
+        return (StatusCode::BAD_REQUEST, axum::Json(ErrorResponse { error })).into_response();
+    }
+    let response = RecentJobsResponse {
+        jobs: state.jobs.latest(limit),
+    };
+    (StatusCode::OK, axum::Json(response)).into_response()
+}
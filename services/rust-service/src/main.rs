@@ -1,52 +1,203 @@
+// The render_fractal tool schema (json! macro) nests deeper than the default limit
+#![recursion_limit = "256"]
+
+mod analyze;
+mod animate;
+mod annotation;
+mod api_v2;
+mod audio;
+mod compare;
+mod config;
+mod crop;
+mod deep_zoom;
+mod defaults;
+mod deprecation;
+mod dialect;
+mod estimate;
+mod explore;
+mod flame;
 mod fractals;
+mod health;
+mod image_json;
+mod iteration_matrix;
+mod jobs;
+mod manifest;
+mod montage;
+mod overlay;
+mod palettes;
+mod pipeline;
+mod plugins;
+mod query;
+mod quota;
+#[cfg(feature = "nats-queue")]
+mod queue;
 mod rendering;
+mod schema;
+mod self_test;
+mod sonify;
+mod streaming;
+mod throttle;
+mod tool_server;
+mod tuning;
+mod usage;
 mod utils;
+mod warmup;
 
 use axum::{
-    extract::Query,
-    http::StatusCode,
+    extract::{DefaultBodyLimit, Query, State},
+    http::{header::ACCEPT_LANGUAGE, HeaderMap, StatusCode},
+    middleware,
     response::{IntoResponse, Response},
-    routing::get,
+    routing::{get, post},
     Router,
 };
-use fractals::julia::JuliaSet;
-use fractals::koch::KochSnowflake;
-use fractals::mandelbrot::MandelbrotSet;
-use fractals::sierpinski::SierpinskiTriangle;
-use fractals::traits::{Fractal, FractalParams};
-use rendering::png_encoder::{create_png_response, encode_png};
+use config::Reloadable;
+use deprecation::LegacyUsage;
+use fractals::FRACTAL_TYPES;
+use image_json::{create_image_json_response, ResponseMode};
+use jobs::RecentJobs;
+use manifest::Manifest;
+use overlay::{Caption, Overlay};
+use pipeline::{
+    render, render16, render_escapes, render_vector, render_with_escapes, AppState, RenderError,
+    RenderOptions,
+};
+use plugins::builtin::RenderTimingHook;
+use plugins::PluginRegistry;
+use query::FractalQuery;
+use quota::Quotas;
+use rendering::aesthetics::{score_image, AestheticScore};
+use rendering::encoder::{
+    create_image_response, encode_image, encode_image16, EncodeOptions, OutputFormat,
+};
+use rendering::raw_data::{create_raw_response, encode_raw, RawOptions};
+use rendering::text_art::{create_text_response, escape_density, render_text, TextOptions};
+use rendering::vector::{create_vector_response, VectorFormat};
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use throttle::{Throttle, POWER_MODE_HEADER};
 use tower_http::cors::{Any, CorsLayer};
+use usage::UsageLedger;
+use utils::locale::Locale;
+use utils::validation::validate_bit_depth;
 
-#[derive(Deserialize)]
-struct FractalQuery {
-    #[serde(rename = "type")]
-    fractal_type: Option<String>,
+#[derive(Serialize, JsonSchema)]
+struct HealthResponse {
+    status: String,
+    service: String,
+}
 
-    // Common parameters
-    width: Option<u32>,
-    height: Option<u32>,
-    zoom: Option<f64>,
-    center_x: Option<f64>,
-    center_y: Option<f64>,
-    max_iterations: Option<u32>,
-    color_scheme: Option<String>,
+#[derive(Serialize, JsonSchema)]
+struct InfoResponse {
+    service: String,
+    version: String,
+    fractal_types: Vec<String>,
+    post_render_hooks: Vec<String>,
+    tuning: tuning::TuningDecision,
+    /// Startup warm-up baseline; absent unless WARMUP is enabled
+    warmup: Option<warmup::WarmupReport>,
+}
 
-    // Julia-specific parameters
-    julia_c_real: Option<f64>,
-    julia_c_imag: Option<f64>,
+#[derive(Serialize, JsonSchema)]
+struct StatsResponse {
+    fractal_type: String,
+    width: u32,
+    height: u32,
+    render_time_ms: u128,
+    aesthetics: AestheticScore,
+    /// The same numbers written for the client's locale, when one was requested
+    #[serde(skip_serializing_if = "Option::is_none")]
+    formatted: Option<FormattedStats>,
+}
 
-    // Geometric fractal parameters
-    recursion_depth: Option<u32>,
+#[derive(Serialize, JsonSchema)]
+struct ValidateResponse {
+    valid: bool,
+    /// Why the request would be rejected
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+    fractal_type: String,
+    /// The request's parameters with this deployment's defaults filled in; unset options are
+    /// left out
+    params: serde_json::Value,
+    /// What omitted width, height, max_iterations and color_scheme fall back to
+    defaults: defaults::DeploymentDefaults,
 }
 
-#[derive(Serialize)]
-struct HealthResponse {
-    status: String,
-    service: String,
+#[derive(Serialize, JsonSchema)]
+struct FormattedStats {
+    locale: String,
+    render_time_ms: String,
+    edge_density: String,
+    color_entropy: String,
+    contrast: String,
+    score: String,
+}
+
+impl FormattedStats {
+    fn new(locale: Locale, render_time_ms: u128, aesthetics: &AestheticScore) -> Self {
+        Self {
+            render_time_ms: locale.format(render_time_ms as f64, 0),
+            edge_density: locale.format(aesthetics.edge_density, 3),
+            color_entropy: locale.format(aesthetics.color_entropy, 3),
+            contrast: locale.format(aesthetics.contrast, 3),
+            score: locale.format(aesthetics.score, 3),
+            locale: locale.tag,
+        }
+    }
+}
+
+#[derive(Deserialize, JsonSchema)]
+struct StatsOptions {
+    /// Locale for the formatted numbers; falls back to Accept-Language
+    locale: Option<String>,
+}
+
+#[derive(Deserialize, JsonSchema)]
+struct OutputOptions {
+    /// Attach a reproducibility manifest in the X-Render-Manifest header
+    manifest: Option<bool>,
+    /// Tag PNG output with its manifest and parameters in text chunks (default true)
+    embed_metadata: Option<bool>,
+    /// zlib level 0-9: lower is faster, higher is smaller
+    compression: Option<u32>,
+    /// PNG scanline filter (none, sub, up, average, paeth, adaptive)
+    png_filter: Option<String>,
+    /// Quality 1-100 for format=jpeg (default 85), lossy format=webp (default 80) and
+    /// format=avif (default 70)
+    quality: Option<u8>,
+    /// Exact pixels for format=webp instead of the smaller lossy mode
+    lossless: Option<bool>,
+    /// Draw over the image: comma-separated axes, grid and scalebar
+    overlay: Option<String>,
+    /// Text for a corner of the image, with placeholders such as {center_x} and {zoom}
+    caption: Option<String>,
+    /// Corner for the caption: top_left, top_right, bottom_left or bottom_right (default)
+    caption_position: Option<String>,
+    /// Frame the image as a figure: plane axes, palette legend and parameter summary
+    annotate: Option<bool>,
+    /// Response format: png (default), jpeg, webp, avif (in builds with the avif feature), tiff,
+    /// bmp, svg / pdf / eps for the geometric types, ascii / ansi / braille text, or raw / npy
+    /// iteration data for the escape-time types
+    format: Option<String>,
+    /// Samples of format=raw and format=npy: iterations (u32, default) or smooth (f32, NaN
+    /// inside the set)
+    data: Option<String>,
+    /// Characters per line of text output (default 80)
+    columns: Option<u32>,
+    /// Density ramp for ascii and ansi output (standard, detailed, blocks)
+    charset: Option<String>,
+    /// Dense characters for sparse pixels in text output, for light backgrounds
+    invert: Option<bool>,
+    /// Bits per channel: 8 (default) or 16 (png and tiff), for re-grading colors without banding
+    bit_depth: Option<u8>,
+    /// image (default) for the encoded bytes, or json for the image as a base64 data URI
+    /// together with the parameters used, render time and statistics
+    response: Option<String>,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, JsonSchema)]
 struct ErrorResponse {
     error: String,
 }
@@ -60,65 +211,461 @@ async fn health() -> impl IntoResponse {
     (StatusCode::OK, axum::Json(response))
 }
 
-// Unified fractal generation endpoint
-async fn generate_fractal(Query(query): Query<FractalQuery>) -> Response {
-    let fractal_type = query.fractal_type.unwrap_or_else(|| "mandelbrot".to_string());
-
-    // Create FractalParams with defaults
-    let params = FractalParams {
-        width: query.width.unwrap_or(800),
-        height: query.height.unwrap_or(600),
-        zoom: query.zoom.unwrap_or(1.0),
-        center_x: query.center_x.unwrap_or(0.0),
-        center_y: query.center_y.unwrap_or(0.0),
-        max_iterations: query.max_iterations.unwrap_or(100),
-        color_scheme: query.color_scheme,
-        julia_c_real: query.julia_c_real,
-        julia_c_imag: query.julia_c_imag,
-        recursion_depth: query.recursion_depth,
+// Service capabilities and the active performance tuning
+async fn info(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    let response = InfoResponse {
+        service: "rust-service".to_string(),
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        fractal_types: FRACTAL_TYPES.iter().map(|t| t.to_string()).collect(),
+        post_render_hooks: state
+            .plugins
+            .hook_names()
+            .iter()
+            .map(|name| name.to_string())
+            .collect(),
+        tuning: tuning::current(),
+        warmup: warmup::current(),
     };
+    (StatusCode::OK, axum::Json(response))
+}
 
-    // Select fractal implementation based on type
-    let fractal: Box<dyn Fractal> = match fractal_type.to_lowercase().as_str() {
-        "mandelbrot" => Box::new(MandelbrotSet),
-        "julia" => Box::new(JuliaSet),
-        "sierpinski" => Box::new(SierpinskiTriangle),
-        "koch" => Box::new(KochSnowflake),
-        _ => {
-            let error = ErrorResponse {
-                error: format!(
-                    "Unknown fractal type: {}. Supported types: mandelbrot, julia, sierpinski, koch",
-                    fractal_type
-                ),
-            };
-            return (StatusCode::BAD_REQUEST, axum::Json(error)).into_response();
+// Unified fractal generation endpoint
+async fn generate_fractal(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Query(query): Query<FractalQuery>,
+    Query(output): Query<OutputOptions>,
+) -> Response {
+    let fractal_type = query.fractal_type();
+    let encode_options = match EncodeOptions::from_params(
+        output.compression,
+        output.png_filter.as_deref(),
+        output.quality,
+        output.lossless,
+    ) {
+        Ok(encode_options) => encode_options,
+        Err(error) => {
+            return (StatusCode::BAD_REQUEST, axum::Json(ErrorResponse { error })).into_response();
         }
     };
-
-    // Generate the fractal
-    match fractal.generate(params) {
-        Ok(img) => {
-            // Encode as PNG
-            match encode_png(img) {
-                Ok(png_bytes) => create_png_response(png_bytes),
-                Err(e) => {
-                    let error = ErrorResponse { error: e };
-                    (StatusCode::INTERNAL_SERVER_ERROR, axum::Json(error)).into_response()
-                }
+    let text_options = match TextOptions::from_params(
+        output.format.as_deref(),
+        output.columns,
+        output.charset.as_deref(),
+        output.invert,
+    ) {
+        Ok(text_options) => text_options,
+        Err(error) => {
+            return (StatusCode::BAD_REQUEST, axum::Json(ErrorResponse { error })).into_response();
+        }
+    };
+    let raw_options =
+        match RawOptions::from_params(output.format.as_deref(), output.data.as_deref()) {
+            Ok(raw_options) => raw_options,
+            Err(error) => {
+                return (StatusCode::BAD_REQUEST, axum::Json(ErrorResponse { error }))
+                    .into_response();
             }
+        };
+    let response_mode = match ResponseMode::from_param(output.response.as_deref()) {
+        Ok(response_mode) => response_mode,
+        Err(error) => {
+            return (StatusCode::BAD_REQUEST, axum::Json(ErrorResponse { error })).into_response();
+        }
+    };
+    // Text and iteration data aren't images to put in a data URI
+    if response_mode == ResponseMode::Json && (text_options.is_some() || raw_options.is_some()) {
+        let error = format!(
+            "response=json isn't available with format={}.",
+            output.format.as_deref().unwrap_or_default()
+        );
+        return (StatusCode::BAD_REQUEST, axum::Json(ErrorResponse { error })).into_response();
+    }
+    // Refuse formats this build can't encode before spending a render on them
+    if let Some(Err(error)) =
+        OutputFormat::from_name(output.format.as_deref()).map(OutputFormat::check_enabled)
+    {
+        return (StatusCode::NOT_IMPLEMENTED, axum::Json(ErrorResponse { error })).into_response();
+    }
+    let options = RenderOptions {
+        low_power: headers
+            .get(POWER_MODE_HEADER)
+            .and_then(|value| value.to_str().ok())
+            .is_some_and(|value| value.eq_ignore_ascii_case("low")),
+        tenant: usage::tenant(&headers),
+        ..RenderOptions::default()
+    };
+
+    let overlay = match output.overlay.as_deref().map(|layers| {
+        Overlay::parse(layers)
+            .and_then(|overlay| Overlay::check_applies(&fractal_type).map(|_| overlay))
+    }) {
+        Some(Err(error)) => {
+            return (StatusCode::BAD_REQUEST, axum::Json(ErrorResponse { error })).into_response();
+        }
+        Some(Ok(overlay)) => Some(overlay),
+        None => None,
+    };
+    let caption = match output
+        .caption
+        .as_deref()
+        .map(|caption| Caption::parse(caption, output.caption_position.as_deref()))
+    {
+        Some(Err(error)) => {
+            return (StatusCode::BAD_REQUEST, axum::Json(ErrorResponse { error })).into_response();
         }
+        Some(Ok(caption)) => Some(caption),
+        None => None,
+    };
+
+    let bit_depth = output.bit_depth.unwrap_or(8);
+    if let Err(error) = validate_bit_depth(bit_depth) {
+        return (StatusCode::BAD_REQUEST, axum::Json(ErrorResponse { error })).into_response();
+    }
+    if let Some(vector_format) = VectorFormat::from_name(output.format.as_deref()) {
+        return generate_fractal_vector(
+            &state,
+            &fractal_type,
+            query,
+            &output,
+            bit_depth,
+            vector_format,
+            &options,
+        )
+        .unwrap_or_else(|e| e.into_response());
+    }
+    if let Some(raw_options) = raw_options {
+        return generate_fractal_raw(
+            &state,
+            &fractal_type,
+            query,
+            &output,
+            bit_depth,
+            &raw_options,
+            &options,
+        )
+        .unwrap_or_else(|e| e.into_response());
+    }
+    if bit_depth == 16 {
+        return generate_fractal16(
+            &state,
+            &fractal_type,
+            query,
+            &output,
+            &encode_options,
+            &options,
+        )
+        .unwrap_or_else(|e| e.into_response());
+    }
+
+    let mut params = query.into_params();
+    // Text needs only a few pixels per character, not the whole image
+    if let Some(text_options) = &text_options {
+        (params.width, params.height) = text_options.render_size(params.width, params.height);
+    }
+    let rendered = match text_options {
+        Some(_) => render_with_escapes(&state, &fractal_type, params, &options),
+        None => render(&state, &fractal_type, params, &options)
+            .map(|(img, metadata)| (img, None, metadata)),
+    };
+    let (img, escapes, metadata) = match rendered {
+        Ok(rendered) => rendered,
+        Err(e) => return e.into_response(),
+    };
+
+    // PNG unless another format was asked for
+    let format = OutputFormat::from_name(output.format.as_deref()).unwrap_or_default();
+    let embed = format == OutputFormat::Png
+        && text_options.is_none()
+        && output.embed_metadata.unwrap_or(true);
+
+    let mut response_headers = metadata.headers.clone();
+    let manifest =
+        (output.manifest.unwrap_or(false) || embed).then(|| Manifest::new(&state, &img, &metadata));
+    if let Some(manifest) = manifest
+        .as_ref()
+        .filter(|_| output.manifest.unwrap_or(false))
+    {
+        response_headers.push(manifest.to_header());
+    }
+
+    // Overlay, caption and annotate after hashing, so the manifest still describes the bare render
+    let mut img = img;
+    if let Some(overlay) = overlay {
+        overlay.draw(&mut img, &metadata.fractal_type, &metadata.params);
+    }
+    if let Some(caption) = &caption {
+        caption.draw(&mut img, &metadata.fractal_type, &metadata.params);
+    }
+    let img = if output.annotate.unwrap_or(false) {
+        annotation::annotate(&img, &metadata.fractal_type, &metadata.params)
+    } else {
+        img
+    };
+
+    // Text for terminals and screen readers instead of an image, shaped by escape time unless a
+    // figure was drawn over the render
+    if let Some(text_options) = text_options {
+        let figure = output.annotate.unwrap_or(false)
+            || output.overlay.is_some()
+            || output.caption.is_some();
+        let density = escapes
+            .filter(|_| !figure)
+            .map(|escapes| escape_density(&escapes, metadata.params.max_iterations));
+        let text = render_text(&img, density.as_deref(), &text_options);
+        return create_text_response(text, &response_headers);
+    }
+
+    let encoded = encode_image(&img, format, &encode_options).and_then(|bytes| match &manifest {
+        Some(manifest) if embed => manifest.embed_in_png(&bytes, &metadata.params),
+        _ => Ok(bytes),
+    });
+    match encoded {
+        Ok(bytes) if response_mode == ResponseMode::Json => create_image_json_response(
+            &bytes,
+            format.content_type(),
+            &metadata,
+            manifest.filter(|_| output.manifest.unwrap_or(false)),
+            &response_headers,
+        ),
+        Ok(bytes) => create_image_response(bytes, format, &response_headers),
         Err(e) => {
             let error = ErrorResponse { error: e };
-            (StatusCode::BAD_REQUEST, axum::Json(error)).into_response()
+            (StatusCode::INTERNAL_SERVER_ERROR, axum::Json(error)).into_response()
+        }
+    }
+}
+
+// 16-bit PNG or TIFF for color grading. Annotated figures, overlays, captions, text and manifests
+// describe the 8-bit render, so they aren't offered at this depth.
+fn generate_fractal16(
+    state: &AppState,
+    fractal_type: &str,
+    query: FractalQuery,
+    output: &OutputOptions,
+    encode_options: &EncodeOptions,
+    options: &RenderOptions,
+) -> Result<Response, RenderError> {
+    let unsupported = [
+        ("annotate", output.annotate.unwrap_or(false)),
+        ("overlay", output.overlay.is_some()),
+        ("caption", output.caption.is_some()),
+        ("manifest", output.manifest.unwrap_or(false)),
+    ];
+    if let Some((option, _)) = unsupported.iter().find(|(_, requested)| *requested) {
+        return Err(RenderError::BadRequest(format!(
+            "{} isn't available with bit_depth=16.",
+            option
+        )));
+    }
+    let format = match OutputFormat::from_name(output.format.as_deref()) {
+        Some(format) if format.has_16_bit() => format,
+        _ => {
+            return Err(RenderError::BadRequest(format!(
+                "format={} isn't available with bit_depth=16. Use png or tiff.",
+                output.format.as_deref().unwrap_or_default()
+            )));
+        }
+    };
+
+    let response_mode =
+        ResponseMode::from_param(output.response.as_deref()).map_err(RenderError::BadRequest)?;
+
+    let (img, metadata) = render16(state, fractal_type, query.into_params(), options)?;
+    let bytes = encode_image16(&img, format, encode_options).map_err(RenderError::Internal)?;
+    Ok(match response_mode {
+        ResponseMode::Json => create_image_json_response(
+            &bytes,
+            format.content_type(),
+            &metadata,
+            None,
+            &metadata.headers,
+        ),
+        ResponseMode::Image => create_image_response(bytes, format, &metadata.headers),
+    })
+}
+
+// Resolution-independent SVG, PDF or EPS for the geometric types. The figure options draw on
+// pixels, so they aren't offered with them.
+fn generate_fractal_vector(
+    state: &AppState,
+    fractal_type: &str,
+    query: FractalQuery,
+    output: &OutputOptions,
+    bit_depth: u8,
+    format: VectorFormat,
+    options: &RenderOptions,
+) -> Result<Response, RenderError> {
+    let unsupported = [
+        ("annotate", output.annotate.unwrap_or(false)),
+        ("overlay", output.overlay.is_some()),
+        ("caption", output.caption.is_some()),
+        ("manifest", output.manifest.unwrap_or(false)),
+        ("bit_depth=16", bit_depth == 16),
+    ];
+    if let Some((option, _)) = unsupported.iter().find(|(_, requested)| *requested) {
+        return Err(RenderError::BadRequest(format!(
+            "{} isn't available with format={}.",
+            option,
+            format.name()
+        )));
+    }
+
+    let response_mode =
+        ResponseMode::from_param(output.response.as_deref()).map_err(RenderError::BadRequest)?;
+
+    let (document, metadata) =
+        render_vector(state, fractal_type, query.into_params(), format, options)?;
+    Ok(match response_mode {
+        ResponseMode::Json => create_image_json_response(
+            &document,
+            format.content_type(),
+            &metadata,
+            None,
+            &metadata.headers,
+        ),
+        ResponseMode::Image => create_vector_response(document, format, &metadata.headers),
+    })
+}
+
+// Uncolored iteration counts of the escape-time types, for analysis and re-coloring offline.
+// Like vector output, there are no pixels for the figure options to draw on.
+fn generate_fractal_raw(
+    state: &AppState,
+    fractal_type: &str,
+    query: FractalQuery,
+    output: &OutputOptions,
+    bit_depth: u8,
+    raw_options: &RawOptions,
+    options: &RenderOptions,
+) -> Result<Response, RenderError> {
+    let unsupported = [
+        ("annotate", output.annotate.unwrap_or(false)),
+        ("overlay", output.overlay.is_some()),
+        ("caption", output.caption.is_some()),
+        ("manifest", output.manifest.unwrap_or(false)),
+        ("bit_depth=16", bit_depth == 16),
+    ];
+    if let Some((option, _)) = unsupported.iter().find(|(_, requested)| *requested) {
+        return Err(RenderError::BadRequest(format!(
+            "{} isn't available with format=raw or format=npy.",
+            option
+        )));
+    }
+
+    let (escapes, metadata) = render_escapes(
+        state,
+        fractal_type,
+        query.into_params(),
+        raw_options.needs_final_z(),
+        options,
+    )?;
+    let params = &metadata.params;
+    let bytes = encode_raw(
+        &escapes,
+        params.width,
+        params.height,
+        params.max_iterations,
+        raw_options,
+    );
+    Ok(create_raw_response(
+        bytes,
+        &metadata.fractal_type,
+        params.width,
+        params.height,
+        params.max_iterations,
+        raw_options,
+        &metadata.headers,
+    ))
+}
+
+// Render statistics (timing and aesthetic scores) as JSON instead of an image
+async fn fractal_stats(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Query(query): Query<FractalQuery>,
+    Query(options): Query<StatsOptions>,
+) -> Response {
+    let fractal_type = query.fractal_type();
+    let locale = match options.locale {
+        Some(tag) => Some(Locale::from_tag(&tag)),
+        None => headers
+            .get(ACCEPT_LANGUAGE)
+            .and_then(|value| value.to_str().ok())
+            .and_then(Locale::from_accept_language),
+    };
+
+    match render(
+        &state,
+        &fractal_type,
+        query.into_params(),
+        &RenderOptions::billed_to(&headers),
+    ) {
+        Ok((img, metadata)) => {
+            let render_time_ms = metadata.render_time.as_millis();
+            let aesthetics = score_image(&img);
+            let response = StatsResponse {
+                fractal_type: metadata.fractal_type,
+                width: img.width(),
+                height: img.height(),
+                render_time_ms,
+                aesthetics,
+                formatted: locale
+                    .map(|locale| FormattedStats::new(locale, render_time_ms, &aesthetics)),
+            };
+            (StatusCode::OK, axum::Json(response)).into_response()
         }
+        Err(e) => e.into_response(),
     }
 }
 
+// Dry run: resolve and validate a request without rendering it
+async fn validate_fractal(Query(query): Query<FractalQuery>) -> impl IntoResponse {
+    let fractal_type = query.fractal_type();
+    let params = query.into_params();
+    let error = match fractals::create_fractal(&fractal_type) {
+        Some(fractal) => fractal.validate_params(&params).err(),
+        None => Some(format!(
+            "Unknown fractal type: {}. Supported types: {}",
+            fractal_type,
+            FRACTAL_TYPES.join(", ")
+        )),
+    };
+
+    let mut resolved = serde_json::to_value(&params).unwrap_or_default();
+    if let Some(object) = resolved.as_object_mut() {
+        object.retain(|_, field| !field.is_null());
+    }
+    let response = ValidateResponse {
+        valid: error.is_none(),
+        error,
+        fractal_type,
+        params: resolved,
+        defaults: defaults::DeploymentDefaults::current(),
+    };
+    (StatusCode::OK, axum::Json(response))
+}
+
+// Same as the unified endpoint, with parameters in a JSON body (e.g. long ifs_transforms lists)
+async fn generate_fractal_post(
+    state: State<Arc<AppState>>,
+    headers: HeaderMap,
+    output: Query<OutputOptions>,
+    axum::Json(query): axum::Json<FractalQuery>,
+) -> Response {
+    generate_fractal(state, headers, Query(query), output).await
+}
+
 // Legacy endpoint for backwards compatibility
-async fn generate_mandelbrot(query: Query<FractalQuery>) -> Response {
+async fn generate_mandelbrot(
+    state: State<Arc<AppState>>,
+    headers: HeaderMap,
+    query: Query<FractalQuery>,
+    output: Query<OutputOptions>,
+) -> Response {
     let mut query = query.0;
     query.fractal_type = Some("mandelbrot".to_string());
-    generate_fractal(Query(query)).await
+    generate_fractal(state, headers, Query(query), output).await
 }
 
 #[tokio::main]
@@ -126,32 +673,258 @@ async fn main() {
     // Initialize tracing
     tracing_subscriber::fmt::init();
 
+    // Pick the fastest kernel/tile size for this host
+    let decision = tuning::init();
+
+    // Render every fractal type once, check it and exit instead of serving
+    if std::env::args().any(|arg| arg == "--self-test") {
+        std::process::exit(self_test::run());
+    }
+
+    tracing::info!(
+        "Render tuning ({}): kernel={:?}, tile_rows={}, took {}ms",
+        decision.source,
+        decision.kernel,
+        decision.tile_rows,
+        decision.calibration_ms
+    );
+
+    // Prime the thread pool and render paths before serving, and measure baseline throughput
+    if warmup::enabled() {
+        match warmup::run() {
+            Ok(report) => tracing::info!(
+                "Warm-up rendered {} types in {}ms; baseline {:.0} pixels/sec",
+                report.fractal_types.len(),
+                report.warmup_ms,
+                report.baseline_pixels_per_sec
+            ),
+            Err(e) => tracing::error!("Warm-up failed: {}", e),
+        }
+    }
+
+    // Register post-render hooks
+    let mut plugins = PluginRegistry::default();
+    plugins.register(Box::new(RenderTimingHook));
+    #[cfg(feature = "dynamic-plugins")]
+    if let Err(e) = plugins.load_from_env() {
+        tracing::error!("{}", e);
+    }
+
+    // Settings that can change at runtime; the file overrides the environment
+    if let Err(e) = config::load_file() {
+        tracing::error!("Ignoring settings file: {}", e);
+    }
+    if let Err(e) = palettes::load() {
+        tracing::error!("No palette files: {}", e);
+    }
+    if let Err(e) = defaults::load() {
+        tracing::error!("Using built-in defaults: {}", e);
+    }
+    let state = Arc::new(AppState {
+        plugins,
+        throttle: Reloadable::new(Throttle::from_env()),
+        legacy: LegacyUsage::from_env(),
+        usage: UsageLedger::from_env(),
+        quotas: Reloadable::new(Quotas::from_env()),
+        jobs: RecentJobs::default(),
+    });
+
+    // Reload them on SIGHUP or when the settings/quota files change
+    tokio::spawn(config::watch(state.clone()));
+
+    // Consume render requests from the message queue alongside HTTP
+    #[cfg(feature = "nats-queue")]
+    tokio::spawn(queue::run_consumer(state.clone()));
+
     // Configure CORS
     let cors = CorsLayer::new()
         .allow_origin(Any)
         .allow_methods(Any)
         .allow_headers(Any);
 
+    // Python service parameter names, only on the routes that take render parameters
+    let dialect_layer = middleware::from_fn(dialect::translate_params);
+
+    // Versioned routers; v1 takes center + zoom query parameters, v2 takes plane bounds
+    let v1 = Router::new()
+        .route(
+            "/fractal",
+            get(generate_fractal).post(generate_fractal_post),
+        )
+        .route("/fractal/stats", get(fractal_stats))
+        .route("/tool", post(tool_server::handle_tool_request))
+        .route("/zoom/stream", get(streaming::zoom_stream))
+        .route("/explore", get(explore::explore))
+        .route_layer(dialect_layer.clone())
+        .route("/manifest/verify", post(manifest::verify_manifest));
+    let v2 = Router::new()
+        .route("/fractal", post(api_v2::generate_fractal_v2))
+        .route_layer(dialect_layer.clone());
+
+    // Unversioned routes from before versioning, served as v1 with deprecation headers
+    let legacy = Router::new()
+        .nest("/api", v1.clone())
+        .route(
+            "/api/mandelbrot",
+            get(generate_mandelbrot).layer(dialect_layer.clone()),
+        )
+        .layer(middleware::from_fn_with_state(
+            state.clone(),
+            deprecation::mark_deprecated,
+        ));
+
+    // Routes added since versioning exist only under /api/v1
+    let render_routes = Router::new()
+        .route("/montage", post(montage::montage))
+        .route("/fractal/compare", post(compare::compare_render))
+        .route("/fractal/compare/backends", get(compare::compare_backends))
+        .route("/fractal/crop", get(crop::crop))
+        .route("/fractal/dzi", get(deep_zoom::deep_zoom))
+        .route("/fractal/estimate", get(estimate::estimate_render))
+        .route(
+            "/fractal/iterations",
+            get(iteration_matrix::iteration_matrix),
+        )
+        .route("/fractal/validate", get(validate_fractal))
+        .route("/palette/stream", get(streaming::palette_stream))
+        .route("/sonify", get(sonify::sonify))
+        .route("/animate", get(animate::animate))
+        .route("/animate/zoom", get(animate::zoom_animation))
+        .route("/analyze/area", get(analyze::area))
+        .route_layer(dialect_layer);
+    let v1 = v1
+        .merge(render_routes)
+        .route("/flame", post(flame::flame))
+        .route(
+            "/decode-metadata",
+            post(manifest::decode_metadata).layer(DefaultBodyLimit::max(manifest::MAX_PNG_UPLOAD)),
+        );
+
     // Build router
     let app = Router::new()
         .route("/health", get(health))
-        .route("/api/fractal", get(generate_fractal))
-        .route("/api/mandelbrot", get(generate_mandelbrot)) // Legacy endpoint
-        .layer(cors);
+        .route("/health/ready", get(health::readiness))
+        .route("/api/info", get(info))
+        .route("/api/schema/v1", get(schema::schema_v1))
+        .route("/api/deprecations", get(deprecation::legacy_usage))
+        .route("/api/usage", get(usage::usage_report))
+        .route("/api/usage/reset", post(usage::reset_usage))
+        .route("/api/palettes", get(palettes::list_palettes))
+        .route("/api/jobs/recent", get(jobs::recent_jobs))
+        .nest("/api/v1", v1)
+        .nest("/api/v2", v2)
+        .merge(legacy)
+        .layer(cors)
+        .with_state(state.clone());
 
-    // Start server
-    let listener = tokio::net::TcpListener::bind("0.0.0.0:8001")
+    // Start server; PORT=0 takes any free port, which is logged below
+    let port = std::env::var("PORT").map_or(8001, |port| {
+        port.parse::<u16>().expect("PORT must be a port number")
+    });
+    let listener = tokio::net::TcpListener::bind(("0.0.0.0", port))
         .await
         .expect("Failed to bind to address");
+    let address = listener.local_addr().expect("Failed to read bound address");
 
-    tracing::info!("Rust service listening on http://0.0.0.0:8001");
+    tracing::info!("Rust service listening on http://{}", address);
     tracing::info!("Health check: http://0.0.0.0:8001/health");
-    tracing::info!("Unified endpoint: http://0.0.0.0:8001/api/fractal");
-    tracing::info!("  - Mandelbrot: ?type=mandelbrot");
+    tracing::info!("Readiness (dependency checks): http://0.0.0.0:8001/health/ready");
+    tracing::info!("Service info: http://0.0.0.0:8001/api/info");
+    tracing::info!("JSON Schema: http://0.0.0.0:8001/api/schema/v1");
+    tracing::info!("Unified endpoint (v1): http://0.0.0.0:8001/api/v1/fractal");
+    tracing::info!("  - Mandelbrot: ?type=mandelbrot&variant=classic, celtic, perpendicular_mandelbrot, perpendicular_burning_ship or buffalo");
     tracing::info!("  - Julia: ?type=julia&julia_c_real=-0.7&julia_c_imag=0.27");
+    tracing::info!("  - Polynomial Julia: ?type=julia&julia_coefficients=1,0,0,-0.5+0.25i");
     tracing::info!("  - Sierpinski: ?type=sierpinski&recursion_depth=6");
     tracing::info!("  - Koch: ?type=koch&recursion_depth=4");
-    tracing::info!("Legacy Mandelbrot endpoint: http://0.0.0.0:8001/api/mandelbrot");
+    tracing::info!("  - Dragon: ?type=dragon&recursion_depth=10");
+    tracing::info!("  - Hilbert: ?type=hilbert&recursion_depth=5");
+    tracing::info!("  - Levy C curve: ?type=levy&recursion_depth=10");
+    tracing::info!("  - Vicsek: ?type=vicsek&recursion_depth=4&vicsek_variant=plus or x");
+    tracing::info!("  - Sierpinski carpet: ?type=carpet&recursion_depth=5");
+    tracing::info!("  - Apollonian gasket: ?type=apollonian&recursion_depth=7&max_curvature=500");
+    tracing::info!("  - H-tree: ?type=htree&recursion_depth=6&line_thickness=2");
+    tracing::info!("  - Stroke width for line-drawn types: ?type=dragon&recursion_depth=12&line_thickness=3");
+    tracing::info!("  - Geometric colors: ?type=koch&background_color=0b1021&stroke_color=f5f5f5 (fill_color for sierpinski, carpet, vicsek)");
+    tracing::info!("  - Strange attractor: ?type=attractor&attractor=clifford, dejong or lorenz&attractor_a=-1.4&samples=2000000");
+    tracing::info!("  - Bifurcation diagram: ?type=bifurcation&bifurcation_r_min=3.4&bifurcation_r_max=4&samples=2000");
+    tracing::info!("  - Plasma terrain: ?type=plasma&roughness=0.5&seed=42&color_scheme=rainbow");
+    tracing::info!("  - Newton: ?type=newton&newton_degree=3 or &newton_coefficients=1,0,-2,2");
+    tracing::info!("  - Nova: ?type=nova&relaxation=1.0");
+    tracing::info!("  - Magnet: ?type=magnet1 or magnet2&max_iterations=200");
+    tracing::info!("  - Lyapunov: ?type=lyapunov&lyapunov_sequence=BBABA");
+    tracing::info!("  - Custom formula: ?type=custom&formula=z^3 + c*sin(z)");
+    tracing::info!("  - Hybrid: ?type=hybrid&hybrid_pattern=MMJB&julia_c_real=-0.4&julia_c_imag=0.6");
+    tracing::info!("  - Buddhabrot: ?type=buddhabrot&samples=1000000&seed=42");
+    tracing::info!("  - Nebulabrot: ?type=nebulabrot&red_iterations=1000&green_iterations=200&blue_iterations=20");
+    tracing::info!("  - Barnsley fern: ?type=barnsley&samples=500000&seed=7");
+    tracing::info!("  - Density tone mapping: ?type=buddhabrot&tone_map=aces&exposure=1.5");
+    tracing::info!("  - Custom IFS: ?type=ifs&ifs_transforms=[{{\"coefficients\":[0.5,0,0,0.5,0,0],\"probability\":1}},...] or POST a JSON body");
+    tracing::info!("  - L-system: ?type=lsystem&lsystem_preset=plant or &lsystem_axiom=F&lsystem_rules=F=F+F--F+F&lsystem_angle=60");
+    tracing::info!("  - Color-blind safe: &color_scheme=viridis or cividis, preview with &simulate=deuteranopia");
+    tracing::info!("  - Power-law output instead of sRGB: &gamma=2.2 (1 for linear light)");
+    tracing::info!("  - Engraver-ready black and white: &color_scheme=grayscale&dither=floyd_steinberg&equalize=true");
+    tracing::info!("  - Scientific colormaps: &color_scheme=magma, plasma, inferno or turbo");
+    tracing::info!("  - Custom gradients: &palette=000764,206bcb,edffff,ffaa00 or &brand_colors=1a1446,d52b1e,ffd700");
+    tracing::info!("  - Perceptual palette blending: &palette=0000ff,ffa500&interpolation=oklab");
+    tracing::info!("  - Palette files (PALETTE_DIR, .gpl/.map): &color_scheme=<file name>, listed at /api/palettes");
+    tracing::info!("  - Binary decomposition: ?type=mandelbrot&coloring=binary_decomposition&max_iterations=50");
+    tracing::info!("  - Stripe average: ?type=mandelbrot&coloring=stripe_average&stripe_density=5&color_scheme=magma");
+    tracing::info!("  - Palette cycling: &color_offset=0.3&color_scale=8&color_spacing=log");
+    tracing::info!("  - Bulbs by period: ?type=mandelbrot&coloring=period or atom_domain&max_iterations=1000&color_scheme=rainbow");
+    tracing::info!("  - Edge anti-aliasing: &aa=adaptive&aa_threshold=0.1 (escape-time types except custom)");
+    tracing::info!("  - Embossed lighting: &shading=lambert&light_azimuth=45&light_elevation=45");
+    tracing::info!("Recent public queue renders: http://0.0.0.0:8001/api/jobs/recent?limit=20");
+    tracing::info!("  - PNG size vs speed: &compression=0-9&png_filter=up");
+    tracing::info!("  - 16-bit PNG for color grading: &bit_depth=16");
+    tracing::info!("  - Figure with axes, legend and parameters: &annotate=true");
+    tracing::info!("  - Axes, grid and scale bar over the image: &overlay=axes,grid,scalebar");
+    tracing::info!("  - Caption: &caption=x={{center_x}} y={{center_y}} zoom={{zoom}}&caption_position=bottom_right");
+    tracing::info!("  - Text for terminals: &format=ascii&columns=80&charset=blocks or &format=braille");
+    tracing::info!("  - Colored text for terminals and chat-ops: &format=ansi&columns=100");
+    tracing::info!("  - Smaller lossy images: &format=jpeg&quality=85");
+    tracing::info!("  - WebP images: &format=webp&quality=80, or &format=webp&lossless=true");
+    tracing::info!("  - AVIF images (--features avif): &format=avif&quality=70");
+    tracing::info!("  - TIFF / BMP for print and science tools: &format=tiff&bit_depth=16 or &format=bmp");
+    tracing::info!("  - Vector output: ?type=koch&recursion_depth=5&format=svg (geometric types)");
+    tracing::info!("  - Print-ready vectors: ?type=sierpinski&format=pdf or format=eps");
+    tracing::info!("  - Image and metadata in one fetch: ?type=mandelbrot&response=json");
+    tracing::info!("  - Iteration data: ?type=mandelbrot&format=npy&data=smooth (escape-time types)");
+    tracing::info!("  - Reproducibility manifest: &manifest=true (X-Render-Manifest header)");
+    tracing::info!("  - Julia morph (GIF): /api/v1/animate?julia_c_real=-0.8&julia_c_imag=0.156&to_c_real=-0.7&to_c_imag=0.27&frames=36&fps=12, or ?path=circle&radius=0.7885");
+    tracing::info!("  - Zoom animation (APNG/WebP): /api/v1/animate/zoom?type=mandelbrot&to_center_x=-0.745&to_center_y=0.1&to_zoom=200&frames=60&easing=ease_in_out&format=apng or webp");
+    tracing::info!("  - Zoom video (--features video): /api/v1/animate/zoom?to_zoom=200&frames=120&format=webm&keyframe_interval=48&bitrate=2000");
+    tracing::info!("  - Sonification (WAV): /api/v1/sonify?type=mandelbrot&mode=scanline or orbit&notes=64&note_ms=120");
+    tracing::info!("Render stats (JSON): http://0.0.0.0:8001/api/v1/fractal/stats (&locale=de-DE for formatted numbers)");
+    tracing::info!("Iteration counts (JSON) for client-side coloring: http://0.0.0.0:8001/api/v1/fractal/iterations?width=256&height=192&downsample=2");
+    tracing::info!("Verify manifest: POST http://0.0.0.0:8001/api/v1/manifest/verify");
+    tracing::info!("Parameters embedded in a saved PNG: POST http://0.0.0.0:8001/api/v1/decode-metadata (PNG body)");
+    tracing::info!("Most interesting crop of a larger field: http://0.0.0.0:8001/api/v1/fractal/crop?width=1200&height=300&field=2");
+    tracing::info!("Render-time estimate from a sparse sample: http://0.0.0.0:8001/api/v1/fractal/estimate?zoom=400&center_x=-0.745&max_iterations=5000");
+    tracing::info!("Dry run with this deployment's defaults applied: http://0.0.0.0:8001/api/v1/fractal/validate?type=julia");
+    tracing::info!("Monte-Carlo area of the Mandelbrot set in a view: http://0.0.0.0:8001/api/v1/analyze/area?samples=1000000&seed=7&max_iterations=1000");
+    tracing::info!("Compare with another backend's render: POST http://0.0.0.0:8001/api/v1/fractal/compare {{\"reference_png\":\"<base64>\",\"type\":\"mandelbrot\"}}");
+    tracing::info!("Compare two backends side by side: http://0.0.0.0:8001/api/v1/fractal/compare/backends?type=mandelbrot&backend_a=scalar&backend_b=adaptive");
+    tracing::info!("Bounds-based endpoint (v2): POST http://0.0.0.0:8001/api/v2/fractal {{\"type\":\"mandelbrot\",\"bounds\":{{\"x_min\":-2.5,\"x_max\":1,\"y_min\":-1.2,\"y_max\":1.2}}}}");
+    tracing::info!(
+        "Deprecated: unversioned /api/... routes and /api/mandelbrot (Deprecation + Link headers)"
+    );
+    tracing::info!("Montage: POST http://0.0.0.0:8001/api/v1/montage {{\"layout\":\"grid\",\"panels\":[{{\"type\":\"julia\"}},...]}}");
+    tracing::info!("Fractal flame: POST http://0.0.0.0:8001/api/v1/flame {{\"transforms\":[{{\"affine\":[0.5,0,0,0,0.5,0],\"variations\":{{\"swirl\":1}}}},...]}}");
+    tracing::info!("Legacy route usage: http://0.0.0.0:8001/api/deprecations");
+    tracing::info!("Usage per API key: http://0.0.0.0:8001/api/usage?window=month (X-API-Key header)");
+    tracing::info!("Reset a tenant's monthly quota (admin key): POST http://0.0.0.0:8001/api/usage/reset?tenant=<id>");
+    tracing::info!("Reload settings and quotas: kill -HUP {}", std::process::id());
+    tracing::info!("Check every fractal type renders as expected: rust-service --self-test");
+    tracing::info!("JSON-RPC tool server: POST http://0.0.0.0:8001/api/v1/tool");
+    tracing::info!("Zoom stream (WebSocket): ws://0.0.0.0:8001/api/v1/zoom/stream");
+    tracing::info!("Palette crossfade (WebSocket): ws://0.0.0.0:8001/api/v1/palette/stream?color_scheme=fire&to_color_scheme=ice&frames=30");
+    tracing::info!("Explore nearby: http://0.0.0.0:8001/api/v1/explore?count=6&spread=0.1");
+    tracing::info!("Deep Zoom tile pyramid (zip for OpenSeadragon): http://0.0.0.0:8001/api/v1/fractal/dzi?type=mandelbrot&width=4096&height=4096");
+    tracing::info!(
+        "Post-render hooks: {}",
+        state.plugins.hook_names().join(", ")
+    );
 
     axum::serve(listener, app)
         .await
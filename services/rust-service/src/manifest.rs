@@ -0,0 +1,247 @@
+//! Reproducibility manifests: everything needed to reproduce a render bit-for-bit, plus an
+//! endpoint that re-renders a manifest and checks the result against the recorded hash. PNG
+//! renders carry their manifest in a text chunk, which another endpoint reads back.
+
+use crate::annotation::parameter_summary;
+use crate::fractals::kernels::Kernel;
+use crate::fractals::traits::FractalParams;
+use crate::pipeline::{render, AppState, RenderError, RenderOptions};
+use crate::plugins::RenderMetadata;
+use crate::rendering::png_encoder::{insert_text, read_text};
+use crate::tuning;
+use crate::ErrorResponse;
+use axum::{
+    body::Bytes,
+    extract::State,
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use image::RgbImage;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+/// Response header carrying the base64-encoded manifest JSON
+pub const MANIFEST_HEADER: &str = "X-Render-Manifest";
+
+/// PNG text keyword of the manifest JSON
+pub const PNG_KEYWORD: &str = "render-manifest";
+
+/// Largest PNG accepted for metadata decoding; only its text chunks are read
+pub const MAX_PNG_UPLOAD: usize = 64 * 1024 * 1024;
+
+/// All rendering is done on the CPU
+const BACKEND: &str = "cpu";
+
+/// Rust never changes the floating-point environment, so this is fixed
+const ROUNDING_MODE: &str = "ieee754-round-to-nearest-even";
+
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
+pub struct Manifest {
+    pub version: String,
+    pub backend: String,
+    pub arch: String,
+    pub kernel: Kernel,
+    pub rounding_mode: String,
+    pub seed: Option<u64>,
+    pub fractal_type: String,
+    /// Parameters exactly as rendered (after defaults and any low-power caps), keys sorted
+    pub params: Value,
+    /// Post-render hooks that ran, in order
+    pub post_render_hooks: Vec<String>,
+    /// SHA-256 of the raw RGB pixels, hex-encoded
+    pub image_sha256: String,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct DecodedMetadata {
+    /// Manifest embedded by this service, ready for /api/v1/manifest/verify; absent for PNGs
+    /// written elsewhere or with embed_metadata=false
+    manifest: Option<Manifest>,
+    /// Every text chunk, keyword to text
+    text: BTreeMap<String, String>,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct VerifyResponse {
+    matches: bool,
+    expected_sha256: String,
+    actual_sha256: String,
+    /// Environment fields that differ from the manifest and may explain a mismatch
+    environment_differences: Vec<String>,
+}
+
+/// SHA-256 of the raw pixel data, so the hash doesn't depend on PNG encoder settings
+pub fn image_hash(img: &RgbImage) -> String {
+    Sha256::digest(img.as_raw())
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+/// Canonical form of the parameters: unset options dropped, keys in serde_json's sorted map order
+pub fn canonical_params(params: &FractalParams) -> Value {
+    let mut params = serde_json::to_value(params).unwrap_or_default();
+    if let Some(object) = params.as_object_mut() {
+        object.retain(|_, field| !field.is_null());
+    }
+    params
+}
+
+impl Manifest {
+    pub fn new(state: &AppState, img: &RgbImage, metadata: &RenderMetadata) -> Self {
+        let params = canonical_params(&metadata.params);
+        let tuning = tuning::current();
+
+        Self {
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            backend: BACKEND.to_string(),
+            arch: tuning.arch.to_string(),
+            kernel: tuning.kernel,
+            rounding_mode: ROUNDING_MODE.to_string(),
+            seed: metadata.params.seed,
+            fractal_type: metadata.fractal_type.clone(),
+            params,
+            post_render_hooks: hook_names(state),
+            image_sha256: image_hash(img),
+        }
+    }
+
+    /// Header form of the manifest
+    pub fn to_header(&self) -> (String, String) {
+        let json = serde_json::to_string(self).unwrap_or_default();
+        (MANIFEST_HEADER.to_string(), STANDARD.encode(json))
+    }
+
+    /// The PNG with the manifest in text chunks, alongside the producing software and a
+    /// readable parameter summary for image viewers
+    pub fn embed_in_png(&self, png: &[u8], params: &FractalParams) -> Result<Vec<u8>, String> {
+        let software = format!("rust-service {}", self.version);
+        let description = parameter_summary(&self.fractal_type, params).join(" ");
+        let json = serde_json::to_string(self).map_err(|e| e.to_string())?;
+        insert_text(
+            png,
+            &[
+                ("Software", &software),
+                ("Description", &description),
+                (PNG_KEYWORD, &json),
+            ],
+        )
+    }
+
+    /// Fields of the current server environment that differ from this manifest
+    fn environment_differences(&self, state: &AppState) -> Vec<String> {
+        let tuning = tuning::current();
+        let checks = [
+            (
+                "version",
+                self.version.clone(),
+                env!("CARGO_PKG_VERSION").to_string(),
+            ),
+            ("backend", self.backend.clone(), BACKEND.to_string()),
+            ("arch", self.arch.clone(), tuning.arch.to_string()),
+            (
+                "kernel",
+                format!("{:?}", self.kernel).to_lowercase(),
+                format!("{:?}", tuning.kernel).to_lowercase(),
+            ),
+            (
+                "rounding_mode",
+                self.rounding_mode.clone(),
+                ROUNDING_MODE.to_string(),
+            ),
+            (
+                "post_render_hooks",
+                self.post_render_hooks.join(","),
+                hook_names(state).join(","),
+            ),
+        ];
+
+        checks
+            .into_iter()
+            .filter(|(_, expected, actual)| expected != actual)
+            .map(|(field, expected, actual)| {
+                format!("{}: manifest {}, server {}", field, expected, actual)
+            })
+            .collect()
+    }
+}
+
+fn hook_names(state: &AppState) -> Vec<String> {
+    state
+        .plugins
+        .hook_names()
+        .iter()
+        .map(|name| name.to_string())
+        .collect()
+}
+
+// Re-render a manifest and compare the pixel hash
+pub async fn verify_manifest(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    axum::Json(manifest): axum::Json<Manifest>,
+) -> Response {
+    let params: FractalParams = match serde_json::from_value(manifest.params.clone()) {
+        Ok(params) => params,
+        Err(e) => {
+            let error = ErrorResponse {
+                error: format!("Invalid manifest params: {}", e),
+            };
+            return (StatusCode::BAD_REQUEST, axum::Json(error)).into_response();
+        }
+    };
+
+    // Rendering is CPU-bound, keep it off the async workers
+    let options = RenderOptions::billed_to(&headers);
+    let result = tokio::task::spawn_blocking(move || {
+        let (img, _) = render(&state, &manifest.fractal_type, params, &options)?;
+        let actual_sha256 = image_hash(&img);
+
+        Ok::<_, RenderError>(VerifyResponse {
+            matches: actual_sha256 == manifest.image_sha256,
+            environment_differences: manifest.environment_differences(&state),
+            expected_sha256: manifest.image_sha256,
+            actual_sha256,
+        })
+    })
+    .await
+    .unwrap_or_else(|e| {
+        Err(RenderError::Internal(format!(
+            "Verification task failed: {}",
+            e
+        )))
+    });
+
+    match result {
+        Ok(response) => (StatusCode::OK, axum::Json(response)).into_response(),
+        Err(error) => error.into_response(),
+    }
+}
+
+// Read back the parameters a PNG render was tagged with
+pub async fn decode_metadata(body: Bytes) -> Response {
+    let decoded = read_text(&body).and_then(|entries| {
+        let manifest = entries
+            .iter()
+            .find(|(keyword, _)| keyword == PNG_KEYWORD)
+            .map(|(_, json)| serde_json::from_str::<Manifest>(json))
+            .transpose()
+            .map_err(|e| format!("Invalid embedded manifest: {}", e))?;
+        Ok(DecodedMetadata {
+            manifest,
+            text: entries.into_iter().collect(),
+        })
+    });
+
+    match decoded {
+        Ok(response) => (StatusCode::OK, axum::Json(response)).into_response(),
+        Err(error) => {
+            (StatusCode::BAD_REQUEST, axum::Json(ErrorResponse { error })).into_response()
+        }
+    }
+}
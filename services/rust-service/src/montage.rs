@@ -0,0 +1,267 @@
+//! Montage: render several parameter sets and arrange them on one image with a layout
+//! template, for side-by-side comparisons and teaching material.
+
+use crate::pipeline::{render, AppState, RenderOptions};
+use crate::query::FractalQuery;
+use crate::rendering::compositor::{Canvas, Rect};
+use crate::rendering::encoder::{create_image_response, encode_image, EncodeOptions, OutputFormat};
+use crate::rendering::text::text_height;
+use crate::utils::validation::{validate_dimensions, validate_montage};
+use crate::ErrorResponse;
+use axum::{
+    extract::State,
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+};
+use schemars::JsonSchema;
+use serde::Deserialize;
+use std::sync::Arc;
+
+pub const MONTAGE_LAYOUTS: &[&str] = &["grid", "golden", "filmstrip"];
+
+const BACKGROUND: [u8; 3] = [255, 255, 255];
+const FRAME_COLOR: [u8; 3] = [200, 200, 200];
+const CAPTION_COLOR: [u8; 3] = [40, 40, 40];
+const CAPTION_SCALE: u32 = 2;
+const CAPTION_PADDING: u32 = 4;
+
+/// Panels smaller than this on either side are rejected rather than rendered as slivers
+const MIN_PANEL_SIZE: u32 = 16;
+
+#[derive(Deserialize, JsonSchema)]
+pub struct MontageRequest {
+    /// grid (default), golden or filmstrip
+    layout: Option<String>,
+    /// Grid columns; defaults to a layout as close to square as possible
+    columns: Option<u32>,
+    /// Panel size for grid and filmstrip; golden uses tile_height as the largest panel's height
+    tile_width: Option<u32>,
+    tile_height: Option<u32>,
+    /// Gap between panels and around the edge, in pixels
+    border: Option<u32>,
+    /// Write a caption under each panel (its `caption`, or the fractal type)
+    captions: Option<bool>,
+    panels: Vec<MontagePanel>,
+}
+
+/// One parameter set; width and height are set by the layout
+#[derive(Deserialize, JsonSchema)]
+pub struct MontagePanel {
+    caption: Option<String>,
+    #[serde(flatten)]
+    query: FractalQuery,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Layout {
+    Grid { columns: u32 },
+    Golden,
+    Filmstrip,
+}
+
+impl Layout {
+    fn parse(name: &str, columns: Option<u32>, count: u32) -> Result<Self, String> {
+        match name.to_lowercase().as_str() {
+            "grid" => Ok(Layout::Grid {
+                // As square as possible: the smallest column count whose square fits them all
+                columns: columns
+                    .unwrap_or_else(|| (1..=count).find(|c| c * c >= count).unwrap_or(1)),
+            }),
+            "golden" => Ok(Layout::Golden),
+            "filmstrip" => Ok(Layout::Filmstrip),
+            _ => Err(format!(
+                "Invalid layout. Must be one of: {}.",
+                MONTAGE_LAYOUTS.join(", ")
+            )),
+        }
+    }
+
+    /// Size of the area the regions tile, and one region per panel. A region holds the
+    /// panel, its caption strip and the gap before the next region.
+    fn regions(self, count: u32, tile: (u32, u32), chrome: (u32, u32)) -> ((u32, u32), Vec<Rect>) {
+        let region_width = tile.0 + chrome.0;
+        let region_height = tile.1 + chrome.1;
+
+        match self {
+            Layout::Grid { columns } => {
+                let rows = count.div_ceil(columns);
+                let regions = (0..count)
+                    .map(|index| {
+                        Rect::new(
+                            (index % columns) * region_width,
+                            (index / columns) * region_height,
+                            region_width,
+                            region_height,
+                        )
+                    })
+                    .collect();
+                (
+                    (columns.min(count) * region_width, rows * region_height),
+                    regions,
+                )
+            }
+            Layout::Filmstrip => Layout::Grid { columns: count }.regions(count, tile, chrome),
+            Layout::Golden => {
+                let phi = (1.0 + 5f64.sqrt()) / 2.0;
+                let size = ((region_height as f64 * phi).round() as u32, region_height);
+                (size, golden_regions(Rect::new(0, 0, size.0, size.1), count))
+            }
+        }
+    }
+}
+
+/// Split `area` along a golden spiral: each panel takes a square off the remaining rectangle,
+/// cutting from the left, top, right and bottom in turn; the last panel gets what is left
+fn golden_regions(area: Rect, count: u32) -> Vec<Rect> {
+    let mut remaining = area;
+    let mut regions = Vec::with_capacity(count as usize);
+
+    for index in 0..count.saturating_sub(1) {
+        let side = remaining.width.min(remaining.height);
+        let Rect {
+            x,
+            y,
+            width,
+            height,
+        } = remaining;
+        let (square, rest) = match index % 4 {
+            0 => (
+                Rect::new(x, y, side, height),
+                Rect::new(x + side, y, width - side, height),
+            ),
+            1 => (
+                Rect::new(x, y, width, side),
+                Rect::new(x, y + side, width, height - side),
+            ),
+            2 => (
+                Rect::new(x + width - side, y, side, height),
+                Rect::new(x, y, width - side, height),
+            ),
+            _ => (
+                Rect::new(x, y + height - side, width, side),
+                Rect::new(x, y, width, height - side),
+            ),
+        };
+        regions.push(square);
+        remaining = rest;
+    }
+    regions.push(remaining);
+
+    regions
+}
+
+/// Compose the montage; errors carry the status to respond with
+fn build_montage(
+    state: &AppState,
+    request: MontageRequest,
+    options: &RenderOptions,
+) -> Result<Vec<u8>, (StatusCode, String)> {
+    let bad_request = |error: String| (StatusCode::BAD_REQUEST, error);
+
+    let count = request.panels.len() as u32;
+    let tile = (
+        request.tile_width.unwrap_or(320),
+        request.tile_height.unwrap_or(240),
+    );
+    let border = request.border.unwrap_or(8);
+    validate_montage(count, tile.0, tile.1, border, request.columns).map_err(bad_request)?;
+
+    let layout = Layout::parse(
+        request.layout.as_deref().unwrap_or("grid"),
+        request.columns,
+        count,
+    )
+    .map_err(bad_request)?;
+
+    let captions = request.captions.unwrap_or(true);
+    let caption_height = if captions {
+        text_height(CAPTION_SCALE) + 2 * CAPTION_PADDING
+    } else {
+        0
+    };
+
+    // The regions tile the area; the canvas adds one more border on the right and bottom
+    let ((width, height), regions) = layout.regions(count, tile, (border, border + caption_height));
+    let canvas_size = (width + border, height + border);
+    validate_dimensions(canvas_size.0, canvas_size.1).map_err(|_| {
+        bad_request(format!(
+            "Montage would be {}x{}; it must fit in 4096x4096. Use fewer or smaller panels.",
+            canvas_size.0, canvas_size.1
+        ))
+    })?;
+
+    let mut canvas = Canvas::new(canvas_size.0, canvas_size.1, BACKGROUND);
+    for (index, (panel, region)) in request.panels.into_iter().zip(regions).enumerate() {
+        // Small golden-layout panels give up their caption before they get too small
+        let available_height = region.height.saturating_sub(border);
+        let panel_caption_height = if available_height >= MIN_PANEL_SIZE + caption_height {
+            caption_height
+        } else {
+            0
+        };
+        let image_rect = Rect::new(
+            region.x + border,
+            region.y + border,
+            region.width.saturating_sub(border),
+            available_height - panel_caption_height,
+        );
+        if image_rect.width < MIN_PANEL_SIZE || image_rect.height < MIN_PANEL_SIZE {
+            return Err(bad_request(format!(
+                "Panel {} would be under {}px; use fewer panels or a larger tile size.",
+                index + 1,
+                MIN_PANEL_SIZE
+            )));
+        }
+
+        let fractal_type = panel.query.fractal_type();
+        let mut params = panel.query.into_params();
+        params.width = image_rect.width;
+        params.height = image_rect.height;
+        let (img, _) = render(state, &fractal_type, params, options)
+            .map_err(|e| (e.status(), format!("Panel {}: {}", index + 1, e.message())))?;
+
+        canvas.blit(&img, image_rect.x, image_rect.y);
+        canvas.frame_rect(image_rect, 1, FRAME_COLOR);
+        if panel_caption_height > 0 {
+            let caption_rect = Rect::new(
+                image_rect.x,
+                image_rect.y + image_rect.height,
+                image_rect.width,
+                panel_caption_height,
+            );
+            let text = panel.caption.unwrap_or(fractal_type);
+            canvas.caption(caption_rect, &text, CAPTION_SCALE, CAPTION_COLOR);
+        }
+    }
+
+    encode_image(
+        &canvas.into_image(),
+        OutputFormat::Png,
+        &EncodeOptions::default(),
+    )
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))
+}
+
+// Montage endpoint: JSON body with the panels, PNG response
+pub async fn montage(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    axum::Json(request): axum::Json<MontageRequest>,
+) -> Response {
+    let options = RenderOptions::billed_to(&headers);
+
+    // Rendering is CPU-bound, keep it off the async workers
+    let result = tokio::task::spawn_blocking(move || build_montage(&state, request, &options))
+        .await
+        .unwrap_or_else(|e| {
+            Err((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Montage task failed: {}", e),
+            ))
+        });
+
+    match result {
+        Ok(png_bytes) => create_image_response(png_bytes, OutputFormat::Png, &[]),
+        Err((status, error)) => (status, axum::Json(ErrorResponse { error })).into_response(),
+    }
+}
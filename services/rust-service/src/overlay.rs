@@ -0,0 +1,443 @@
+//! Plane overlays drawn over the render itself (`overlay=axes,grid,scalebar`), unlike
+//! `annotate`, which frames the image in margins. Lines and labels are light with a dark
+//! shadow so they read over any palette. A `caption` goes in a corner the same way, over a
+//! translucent box, for labelled galleries and watermarks.
+
+use crate::annotation::nice_ticks;
+use crate::fractals::create_fractal;
+use crate::fractals::traits::{FractalParams, PlaneBounds};
+use crate::rendering::draw::{blend_pixel, draw_line};
+use crate::rendering::text::{draw_text, fit_text, text_height, text_width};
+use image::RgbImage;
+
+const INK: [u8; 3] = [255, 255, 255];
+const SHADOW: [u8; 3] = [0, 0, 0];
+
+/// Opacity of the axes, and of the grid lines that shouldn't hide the fractal
+const AXIS_ALPHA: f64 = 0.9;
+const GRID_ALPHA: f64 = 0.3;
+
+/// Distance from the image edge, gap between a tick and its label, and tick length, at text
+/// scale 1
+const MARGIN: u32 = 8;
+const GAP: u32 = 3;
+const TICK_LENGTH: u32 = 4;
+
+/// Ticks along each axis, before thinning to fit their labels
+const TARGET_TICKS: f64 = 8.0;
+
+/// Longest the scale bar gets, as a share of the image width
+const SCALE_BAR_SHARE: f64 = 0.25;
+
+/// Layers requested with `overlay`
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Overlay {
+    /// Real and imaginary axes through the origin with labelled ticks, pinned to the image edge
+    /// when the origin is out of view
+    pub axes: bool,
+    /// Faint lines at every tick
+    pub grid: bool,
+    /// A bar of round plane length in the bottom-left corner
+    pub scalebar: bool,
+}
+
+impl Overlay {
+    /// Layer names accepted in the comma-separated `overlay` list
+    pub const NAMES: [&'static str; 3] = ["axes", "grid", "scalebar"];
+
+    pub fn parse(layers: &str) -> Result<Self, String> {
+        let mut overlay = Overlay::default();
+        for layer in layers
+            .split(',')
+            .map(str::trim)
+            .filter(|layer| !layer.is_empty())
+        {
+            match layer.to_lowercase().as_str() {
+                "axes" => overlay.axes = true,
+                "grid" => overlay.grid = true,
+                "scalebar" => overlay.scalebar = true,
+                _ => {
+                    return Err(format!(
+                        "Invalid overlay layer '{}'. Must be a comma-separated list of: {}.",
+                        layer,
+                        Self::NAMES.join(", ")
+                    ))
+                }
+            }
+        }
+        if overlay == Overlay::default() {
+            return Err(format!(
+                "Invalid overlay. Must name at least one of: {}.",
+                Self::NAMES.join(", ")
+            ));
+        }
+        Ok(overlay)
+    }
+
+    /// Refuse types drawn without a plane view, which have no coordinates to show
+    pub fn check_applies(fractal_type: &str) -> Result<(), String> {
+        match create_fractal(fractal_type).and_then(|fractal| fractal.plane_view()) {
+            Some(_) => Ok(()),
+            None => Err(format!(
+                "overlay isn't available for type={}, which isn't drawn over a plane region.",
+                fractal_type
+            )),
+        }
+    }
+
+    /// Draw the layers over `img`, rendered from `params`
+    pub fn draw(&self, img: &mut RgbImage, fractal_type: &str, params: &FractalParams) {
+        let Some(view) = create_fractal(fractal_type).and_then(|fractal| fractal.plane_view())
+        else {
+            return;
+        };
+        let bounds = view.bounds(params);
+        let (width, height) = img.dimensions();
+        let scale = if width.min(height) >= 600 { 2 } else { 1 };
+        let plane = Plane {
+            bounds,
+            width: width as f64,
+            height: height as f64,
+        };
+
+        let x_ticks = fitted_ticks(bounds.x_min, bounds.x_max, width, |label| {
+            text_width(label, scale) + 4 * GAP * scale
+        });
+        let y_ticks = fitted_ticks(bounds.y_min, bounds.y_max, height, |_| {
+            3 * text_height(scale)
+        });
+
+        if self.grid {
+            for (value, _) in &x_ticks {
+                fade_line(img, plane.column(*value), true, GRID_ALPHA);
+            }
+            for (value, _) in &y_ticks {
+                fade_line(img, plane.row(*value), false, GRID_ALPHA);
+            }
+        }
+        // The scale bar moves up above the horizontal axis when that runs along the bottom
+        let mut floor = plane.height;
+        if self.axes {
+            let axis_row = draw_axes(img, &plane, &x_ticks, &y_ticks, scale) as f64;
+            let bar_height = ((MARGIN + 2 * GAP + 2) * scale + 2 * text_height(scale)) as f64;
+            if axis_row > plane.height - bar_height {
+                floor = axis_row;
+            }
+        }
+        if self.scalebar {
+            draw_scale_bar(img, &plane, scale, floor);
+        }
+    }
+}
+
+/// Maps plane coordinates to pixel positions; the imaginary axis points up
+struct Plane {
+    bounds: PlaneBounds,
+    width: f64,
+    height: f64,
+}
+
+impl Plane {
+    fn column(&self, x: f64) -> f64 {
+        (x - self.bounds.x_min) / (self.bounds.x_max - self.bounds.x_min) * self.width
+    }
+
+    fn row(&self, y: f64) -> f64 {
+        (self.bounds.y_max - y) / (self.bounds.y_max - self.bounds.y_min) * self.height
+    }
+}
+
+/// Round-numbered ticks, thinned until each label has `label_space` pixels
+fn fitted_ticks(
+    min: f64,
+    max: f64,
+    length: u32,
+    label_space: impl Fn(&str) -> u32,
+) -> Vec<(f64, String)> {
+    let mut target = TARGET_TICKS;
+    loop {
+        let ticks = nice_ticks(min, max, target);
+        let spacing = length / ticks.len().max(1) as u32;
+        let widest = ticks
+            .iter()
+            .map(|(_, label)| label_space(label))
+            .max()
+            .unwrap_or(0);
+        if target <= 2.0 || spacing >= widest {
+            return ticks;
+        }
+        target -= 1.0;
+    }
+}
+
+/// A full-length vertical (at column `at`) or horizontal (at row `at`) line at `alpha`
+fn fade_line(img: &mut RgbImage, at: f64, vertical: bool, alpha: f64) {
+    let at = at.floor() as i64;
+    let length = if vertical { img.height() } else { img.width() } as i64;
+    for along in 0..length {
+        if vertical {
+            blend_pixel(img, at, along, INK, alpha);
+        } else {
+            blend_pixel(img, along, at, INK, alpha);
+        }
+    }
+}
+
+/// Text in ink over a one-pixel shadow
+fn label(img: &mut RgbImage, x: i64, y: i64, text: &str, scale: u32) {
+    draw_text(img, x + 1, y + 1, text, scale, SHADOW);
+    draw_text(img, x, y, text, scale, INK);
+}
+
+/// Axes through the origin, or along the nearest edges when it's out of view, with ticks and
+/// labels on the side facing into the image. Labels that would be cut off by the edge are
+/// left out. Returns the row of the horizontal axis.
+fn draw_axes(
+    img: &mut RgbImage,
+    plane: &Plane,
+    x_ticks: &[(f64, String)],
+    y_ticks: &[(f64, String)],
+    scale: u32,
+) -> i64 {
+    let (width, height) = (img.width() as i64, img.height() as i64);
+    let (gap, tick) = ((GAP * scale) as i64, (TICK_LENGTH * scale) as i64);
+    let glyph_height = text_height(scale) as i64;
+
+    // Keep the horizontal axis' labels inside the image below it
+    let lowest_row = (height - 1 - tick - gap - glyph_height).max(0);
+    let axis_row = (plane.row(0.0).floor() as i64).clamp(0, lowest_row);
+    let axis_column = (plane.column(0.0).floor() as i64).clamp(0, width - 1);
+
+    fade_line(img, axis_row as f64, false, AXIS_ALPHA);
+    fade_line(img, axis_column as f64, true, AXIS_ALPHA);
+
+    for (value, text) in x_ticks {
+        let x = plane.column(*value).floor() as i64;
+        for offset in 0..tick {
+            blend_pixel(img, x, axis_row + 1 + offset, INK, AXIS_ALPHA);
+        }
+        let text_x = x - text_width(text, scale) as i64 / 2;
+        if text_x >= 0 && text_x + text_width(text, scale) as i64 <= width {
+            label(img, text_x, axis_row + 1 + tick + gap, text, scale);
+        }
+    }
+
+    // Ticks and labels to the right of the vertical axis, or to its left at the right edge
+    let widest = y_ticks
+        .iter()
+        .map(|(_, text)| text_width(text, scale) as i64)
+        .max()
+        .unwrap_or(0);
+    let leftward = axis_column + 1 + tick + gap + widest > width;
+    for (value, text) in y_ticks {
+        // The origin's label is already on the horizontal axis
+        if *value == 0.0 && plane.row(0.0).floor() as i64 == axis_row {
+            continue;
+        }
+        let y = plane.row(*value).floor() as i64;
+        for offset in 0..tick {
+            let x = if leftward {
+                axis_column - 1 - offset
+            } else {
+                axis_column + 1 + offset
+            };
+            blend_pixel(img, x, y, INK, AXIS_ALPHA);
+        }
+        let text_x = if leftward {
+            axis_column - tick - gap - text_width(text, scale) as i64
+        } else {
+            axis_column + 1 + tick + gap
+        };
+        let text_y = y - glyph_height / 2;
+        if text_y >= 0 && text_y + glyph_height <= height {
+            label(img, text_x, text_y, text, scale);
+        }
+    }
+
+    axis_row
+}
+
+/// A bar of round plane length in the bottom-left corner, above row `floor`, with its length
+/// written above it
+fn draw_scale_bar(img: &mut RgbImage, plane: &Plane, scale: u32, floor: f64) {
+    let per_pixel = (plane.bounds.x_max - plane.bounds.x_min) / plane.width;
+    let longest = per_pixel * plane.width * SCALE_BAR_SHARE;
+    if !(longest.is_finite() && longest > 0.0) {
+        return;
+    }
+    let length = round_down(longest);
+    let pixels = length / per_pixel;
+
+    let margin = (MARGIN * scale) as f64;
+    let thickness = (2 * scale) as f64;
+    let y = floor - margin - thickness / 2.0;
+    let (start, end) = ((margin, y), (margin + pixels, y));
+    draw_line(
+        img,
+        (start.0 + 1.0, y + 1.0),
+        (end.0 + 1.0, y + 1.0),
+        thickness,
+        SHADOW,
+    );
+    draw_line(img, start, end, thickness, INK);
+
+    let text = format_length(length);
+    let text_y = y - thickness - (GAP + 1) as f64 * scale as f64 - text_height(scale) as f64;
+    label(img, margin as i64, text_y as i64, &text, scale);
+}
+
+/// The largest 1, 2 or 5 times a power of ten no greater than `value`
+fn round_down(value: f64) -> f64 {
+    let magnitude = 10f64.powf(value.log10().floor());
+    let leading = value / magnitude;
+    magnitude
+        * if leading >= 5.0 {
+            5.0
+        } else if leading >= 2.0 {
+            2.0
+        } else {
+            1.0
+        }
+}
+
+/// Plain decimals down to thousandths, scientific notation below
+fn format_length(length: f64) -> String {
+    if length >= 1e-3 {
+        let decimals = (-length.log10().floor()).max(0.0) as usize;
+        format!("{:.*}", decimals, length)
+    } else {
+        format!("{:e}", length)
+    }
+}
+
+/// Most characters in a caption, before placeholders are filled in
+pub const MAX_CAPTION_LENGTH: usize = 120;
+
+/// Placeholders a caption may contain, each replaced by the render's value
+pub const CAPTION_FIELDS: [&str; 7] = [
+    "type",
+    "center_x",
+    "center_y",
+    "zoom",
+    "max_iterations",
+    "width",
+    "height",
+];
+
+/// Opacity of the box behind a caption
+const CAPTION_BACKDROP_ALPHA: f64 = 0.55;
+
+/// Corner of the image a caption is drawn in
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Corner {
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    #[default]
+    BottomRight,
+}
+
+impl Corner {
+    /// Names accepted by `parse`
+    pub const NAMES: [&'static str; 4] = ["top_left", "top_right", "bottom_left", "bottom_right"];
+
+    pub fn parse(name: &str) -> Result<Self, String> {
+        match name.to_lowercase().as_str() {
+            "top_left" => Ok(Corner::TopLeft),
+            "top_right" => Ok(Corner::TopRight),
+            "bottom_left" => Ok(Corner::BottomLeft),
+            "bottom_right" => Ok(Corner::BottomRight),
+            _ => Err(format!(
+                "Invalid caption_position. Must be one of: {}.",
+                Self::NAMES.join(", ")
+            )),
+        }
+    }
+}
+
+/// A line of text over a translucent box in one corner (`caption`), with placeholders such as
+/// `{zoom}` filled in from the render's parameters
+#[derive(Clone, Debug, PartialEq)]
+pub struct Caption {
+    template: String,
+    corner: Corner,
+}
+
+impl Caption {
+    pub fn parse(template: &str, position: Option<&str>) -> Result<Self, String> {
+        let length = template.chars().count();
+        if length == 0 || length > MAX_CAPTION_LENGTH {
+            return Err(format!(
+                "Invalid caption. Must be between 1 and {} characters.",
+                MAX_CAPTION_LENGTH
+            ));
+        }
+        for field in placeholders(template) {
+            if !CAPTION_FIELDS.contains(&field) {
+                return Err(format!(
+                    "Invalid caption placeholder '{{{}}}'. Must be one of: {}.",
+                    field,
+                    CAPTION_FIELDS.join(", ")
+                ));
+            }
+        }
+        let corner = position.map(Corner::parse).transpose()?.unwrap_or_default();
+        Ok(Self {
+            template: template.to_string(),
+            corner,
+        })
+    }
+
+    /// The caption with its placeholders filled in
+    pub fn text(&self, fractal_type: &str, params: &FractalParams) -> String {
+        let mut text = self.template.clone();
+        for field in CAPTION_FIELDS {
+            let value = match field {
+                "type" => fractal_type.to_string(),
+                "center_x" => params.center_x.to_string(),
+                "center_y" => params.center_y.to_string(),
+                "zoom" => params.zoom.to_string(),
+                "max_iterations" => params.max_iterations.to_string(),
+                "width" => params.width.to_string(),
+                _ => params.height.to_string(),
+            };
+            text = text.replace(&format!("{{{}}}", field), &value);
+        }
+        text
+    }
+
+    /// Draw the caption over `img`, shortened with ".." if it doesn't fit the width
+    pub fn draw(&self, img: &mut RgbImage, fractal_type: &str, params: &FractalParams) {
+        let (width, height) = (img.width() as i64, img.height() as i64);
+        let scale = if width.min(height) >= 600 { 2 } else { 1 };
+        let (margin, padding) = ((MARGIN * scale) as i64, (GAP * scale) as i64);
+
+        let room = (width - 2 * margin - 2 * padding).max(0) as u32;
+        let text = fit_text(&self.text(fractal_type, params), scale, room);
+        let box_width = text_width(&text, scale) as i64 + 2 * padding;
+        let box_height = text_height(scale) as i64 + 2 * padding;
+
+        let left = match self.corner {
+            Corner::TopLeft | Corner::BottomLeft => margin,
+            Corner::TopRight | Corner::BottomRight => width - margin - box_width,
+        };
+        let top = match self.corner {
+            Corner::TopLeft | Corner::TopRight => margin,
+            Corner::BottomLeft | Corner::BottomRight => height - margin - box_height,
+        };
+        for y in top..top + box_height {
+            for x in left..left + box_width {
+                blend_pixel(img, x, y, SHADOW, CAPTION_BACKDROP_ALPHA);
+            }
+        }
+        draw_text(img, left + padding, top + padding, &text, scale, INK);
+    }
+}
+
+/// Names between braces in a caption template
+fn placeholders(template: &str) -> impl Iterator<Item = &str> {
+    template
+        .split('{')
+        .skip(1)
+        .filter_map(|rest| rest.split_once('}').map(|(name, _)| name))
+}
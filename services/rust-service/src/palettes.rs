@@ -0,0 +1,197 @@
+//! Palettes loaded from files: GIMP `.gpl` palettes and Fractint `.map` files in a directory
+//! become named color schemes, so `color_scheme=<name>` picks them up alongside the built-in
+//! ones. `GET /api/palettes` lists what's available.
+//!
+//! Configuration (optional):
+//! - `PALETTE_DIR`: directory scanned for `*.gpl` and `*.map`; each file's lowercased stem is
+//!   its name. Re-scanned on reload (see `config`), so palettes can be added without a restart.
+//!
+//! A file's colors are spread evenly over the gradient, first to last, like a custom palette.
+//! Names that clash with a built-in scheme or would read as a hex stop are skipped.
+
+use crate::config;
+use crate::rendering::colors::ColorScheme;
+use axum::{http::StatusCode, response::IntoResponse};
+use schemars::JsonSchema;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::sync::RwLock;
+
+/// Most colors read from one palette file
+pub const MAX_FILE_COLORS: usize = 1024;
+
+/// Longest palette name
+const MAX_NAME_LENGTH: usize = 64;
+
+static REGISTRY: RwLock<BTreeMap<String, LoadedPalette>> = RwLock::new(BTreeMap::new());
+
+#[derive(Clone, Debug, Serialize, JsonSchema)]
+pub struct LoadedPalette {
+    pub name: String,
+    /// File name within PALETTE_DIR
+    pub file: String,
+    /// "gpl" or "map"
+    pub format: &'static str,
+    #[serde(skip)]
+    colors: Vec<[u8; 3]>,
+    pub color_count: usize,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct PaletteListing {
+    /// Scheme names built into the service
+    pub builtin: Vec<&'static str>,
+    /// Palettes loaded from PALETTE_DIR, by name
+    pub loaded: Vec<LoadedPalette>,
+}
+
+/// Colors of a loaded palette, case-insensitively by name
+pub fn lookup(name: &str) -> Option<Vec<[u8; 3]>> {
+    let registry = REGISTRY.read().unwrap_or_else(|e| e.into_inner());
+    registry
+        .get(&name.to_lowercase())
+        .map(|palette| palette.colors.clone())
+}
+
+/// Names of the loaded palettes, sorted
+pub fn names() -> Vec<String> {
+    let registry = REGISTRY.read().unwrap_or_else(|e| e.into_inner());
+    registry.keys().cloned().collect()
+}
+
+/// (Re)scan `PALETTE_DIR`. Files that fail to parse are logged and skipped; a missing or
+/// unreadable directory leaves the previous palettes in place.
+pub fn load() -> Result<(), String> {
+    let Some(directory) = config::var("PALETTE_DIR") else {
+        return Ok(());
+    };
+    let entries = std::fs::read_dir(&directory)
+        .map_err(|e| format!("Cannot read palette directory {}: {}", directory, e))?;
+
+    let mut palettes = BTreeMap::new();
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let format = match path.extension().and_then(|ext| ext.to_str()) {
+            Some(ext) if ext.eq_ignore_ascii_case("gpl") => "gpl",
+            Some(ext) if ext.eq_ignore_ascii_case("map") => "map",
+            _ => continue,
+        };
+        match load_file(&path, format) {
+            Ok(palette) => {
+                palettes.insert(palette.name.clone(), palette);
+            }
+            Err(e) => tracing::warn!("Skipping palette {}: {}", path.display(), e),
+        }
+    }
+
+    tracing::info!("Loaded {} palettes from {}", palettes.len(), directory);
+    *REGISTRY.write().unwrap_or_else(|e| e.into_inner()) = palettes;
+    Ok(())
+}
+
+fn load_file(path: &Path, format: &'static str) -> Result<LoadedPalette, String> {
+    let name = path
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .unwrap_or_default()
+        .to_lowercase();
+    validate_name(&name)?;
+
+    let text = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+    let colors = match format {
+        "gpl" => parse_gpl(&text)?,
+        _ => parse_map(&text)?,
+    };
+    if !(2..=MAX_FILE_COLORS).contains(&colors.len()) {
+        return Err(format!(
+            "has {} colors; palettes need between 2 and {}",
+            colors.len(),
+            MAX_FILE_COLORS
+        ));
+    }
+
+    Ok(LoadedPalette {
+        name,
+        file: path
+            .file_name()
+            .map(|file| file.to_string_lossy().into_owned())
+            .unwrap_or_default(),
+        format,
+        color_count: colors.len(),
+        colors,
+    })
+}
+
+fn validate_name(name: &str) -> Result<(), String> {
+    if name.is_empty()
+        || name.len() > MAX_NAME_LENGTH
+        || !name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+    {
+        return Err(format!(
+            "name '{}' must be 1-{} letters, digits, '_' or '-'",
+            name, MAX_NAME_LENGTH
+        ));
+    }
+    if ColorScheme::NAMES.contains(&name) {
+        return Err(format!("name '{}' is a built-in scheme", name));
+    }
+    if ColorScheme::is_palette(name) {
+        return Err(format!("name '{}' would read as a hex color", name));
+    }
+    Ok(())
+}
+
+/// GIMP palette: a `GIMP Palette` header, optional `Name:` / `Columns:` lines, `#` comments,
+/// then one `R G B [color name]` line per color
+fn parse_gpl(text: &str) -> Result<Vec<[u8; 3]>, String> {
+    let mut lines = text.lines().enumerate();
+    match lines.next() {
+        Some((_, header)) if header.trim() == "GIMP Palette" => {}
+        _ => return Err("missing 'GIMP Palette' header".to_string()),
+    }
+
+    let mut colors = Vec::new();
+    for (number, line) in lines {
+        let line = line.trim();
+        if line.is_empty()
+            || line.starts_with('#')
+            || line.starts_with("Name:")
+            || line.starts_with("Columns:")
+        {
+            continue;
+        }
+        colors.push(parse_rgb(line).ok_or_else(|| format!("line {}: expected R G B", number + 1))?);
+    }
+    Ok(colors)
+}
+
+/// Fractint map: one `R G B [comment]` line per color, usually 256 of them
+fn parse_map(text: &str) -> Result<Vec<[u8; 3]>, String> {
+    text.lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(number, line)| {
+            parse_rgb(line).ok_or_else(|| format!("line {}: expected R G B", number + 1))
+        })
+        .collect()
+}
+
+/// Leading three 0-255 values of a whitespace-separated line; the rest is a name or comment
+fn parse_rgb(line: &str) -> Option<[u8; 3]> {
+    let mut values = line.split_whitespace().map(|value| value.parse::<u8>());
+    let mut channel = || values.next()?.ok();
+    Some([channel()?, channel()?, channel()?])
+}
+
+// Built-in schemes and palettes loaded from files
+pub async fn list_palettes() -> impl IntoResponse {
+    let registry = REGISTRY.read().unwrap_or_else(|e| e.into_inner());
+    let listing = PaletteListing {
+        builtin: ColorScheme::NAMES.to_vec(),
+        loaded: registry.values().cloned().collect(),
+    };
+    (StatusCode::OK, axum::Json(listing))
+}
@@ -0,0 +1,469 @@
+use crate::config::Reloadable;
+use crate::deprecation::LegacyUsage;
+use crate::fractals::create_fractal;
+use crate::fractals::traits::{Fractal, FractalParams, StatsHeaders};
+use crate::fractals::{ESCAPE_TIME_TYPES, FRACTAL_TYPES};
+use crate::jobs::RecentJobs;
+use crate::plugins::{PluginRegistry, RenderMetadata};
+use crate::quota::{QuotaExceeded, Quotas};
+use crate::rendering::color_vision::{self, ColorVisionDeficiency};
+use crate::rendering::colors::{
+    color_escapes, Channel, ColorScheme, Coloring, Escape, PaletteCycle,
+};
+use crate::rendering::dither::GrayOutput;
+use crate::rendering::gamma;
+use crate::rendering::shading::Shading;
+use crate::rendering::vector::VectorFormat;
+use crate::rendering::{narrow, Rgb16Image};
+use crate::throttle::Throttle;
+use crate::usage::{self, UsageLedger};
+use crate::utils::validation::validate_gamma;
+use crate::ErrorResponse;
+use axum::http::{header::RETRY_AFTER, HeaderMap, HeaderValue, StatusCode};
+use axum::response::{IntoResponse, Response};
+use image::RgbImage;
+use std::time::Instant;
+
+pub struct AppState {
+    pub plugins: PluginRegistry,
+    pub throttle: Reloadable<Throttle>,
+    pub legacy: LegacyUsage,
+    pub usage: UsageLedger,
+    pub quotas: Reloadable<Quotas>,
+    pub jobs: RecentJobs,
+}
+
+/// Per-request switches that aren't fractal parameters
+#[derive(Clone, Debug, Default)]
+pub struct RenderOptions {
+    /// Client asked for low-power rendering
+    pub low_power: bool,
+    /// Tenant the render is billed to; anonymous when unset
+    pub tenant: Option<String>,
+    /// The caller checks the quota and records usage for a batch of renders itself (e.g. a
+    /// tile pyramid), so single renders skip both
+    pub billed_by_caller: bool,
+}
+
+impl RenderOptions {
+    /// Default options, billed to the tenant of the request's API key
+    pub fn billed_to(headers: &HeaderMap) -> Self {
+        Self {
+            tenant: usage::tenant(headers),
+            ..Self::default()
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum RenderError {
+    /// The request itself was invalid (unknown type, bad parameters)
+    BadRequest(String),
+    /// Rendering or post-processing failed on our side
+    Internal(String),
+    /// The tenant's monthly budget can't cover the render
+    QuotaExceeded(QuotaExceeded),
+}
+
+impl RenderError {
+    pub fn status(&self) -> StatusCode {
+        match self {
+            RenderError::BadRequest(_) => StatusCode::BAD_REQUEST,
+            RenderError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            RenderError::QuotaExceeded(exceeded) => exceeded.status,
+        }
+    }
+
+    pub fn message(self) -> String {
+        match self {
+            RenderError::BadRequest(message) | RenderError::Internal(message) => message,
+            RenderError::QuotaExceeded(exceeded) => exceeded.message,
+        }
+    }
+}
+
+impl IntoResponse for RenderError {
+    fn into_response(self) -> Response {
+        let retry_after = match &self {
+            RenderError::QuotaExceeded(exceeded) => Some(exceeded.retry_after),
+            _ => None,
+        };
+        let status = self.status();
+        let mut response = (
+            status,
+            axum::Json(ErrorResponse {
+                error: self.message(),
+            }),
+        )
+            .into_response();
+        if let Some(seconds) = retry_after {
+            response
+                .headers_mut()
+                .insert(RETRY_AFTER, HeaderValue::from(seconds));
+        }
+        response
+    }
+}
+
+/// Render a fractal and run the post-render hooks, ready for encoding
+pub fn render(
+    state: &AppState,
+    fractal_type: &str,
+    params: FractalParams,
+    options: &RenderOptions,
+) -> Result<(RgbImage, RenderMetadata), RenderError> {
+    render_with(state, lookup(fractal_type)?.as_ref(), params, options)
+}
+
+/// Same as `render`, for a fractal that isn't selected by type name (e.g. a flame built from
+/// its own request body)
+pub fn render_with(
+    state: &AppState,
+    fractal: &dyn Fractal,
+    params: FractalParams,
+    options: &RenderOptions,
+) -> Result<(RgbImage, RenderMetadata), RenderError> {
+    let deficiency = parse_deficiency(&params)?;
+    let gray_output = GrayOutput::from_params(&params).map_err(RenderError::BadRequest)?;
+    let (img, metadata) = generate(state, fractal, params, options, |fractal, params| {
+        fractal.generate_with_stats(params)
+    })?;
+    post_process(state, img, metadata, deficiency, gray_output)
+}
+
+/// `render` together with the uncolored first pass of the escape-time types, for text output
+/// that shapes its characters by escape time; `None` for the other types. The image is colored
+/// from that same pass, so there is no antialiasing: text cells average several pixels anyway.
+pub fn render_with_escapes(
+    state: &AppState,
+    fractal_type: &str,
+    params: FractalParams,
+    options: &RenderOptions,
+) -> Result<(RgbImage, Option<Vec<Escape>>, RenderMetadata), RenderError> {
+    let fractal = lookup(fractal_type)?;
+    let escape_time = ESCAPE_TIME_TYPES.contains(&fractal.name());
+    let deficiency = parse_deficiency(&params)?;
+    let gray_output = GrayOutput::from_params(&params).map_err(RenderError::BadRequest)?;
+    let ((img, escapes), metadata) = generate(
+        state,
+        fractal.as_ref(),
+        params,
+        options,
+        |fractal, params| {
+            if !escape_time {
+                return fractal
+                    .generate_with_stats(params)
+                    .map(|(img, stats)| ((img, None), stats));
+            }
+            let (escapes, stats) = fractal.generate_escapes(params.clone(), false)?;
+            let img = color_first_pass(&escapes, &params)?;
+            Ok(((img, Some(escapes)), stats))
+        },
+    )?;
+    let (img, metadata) = post_process(state, img, metadata, deficiency, gray_output)?;
+    Ok((img, escapes, metadata))
+}
+
+/// Color-vision simulation, output gamma, gray reduction and the post-render hooks, in that order
+fn post_process(
+    state: &AppState,
+    mut img: RgbImage,
+    mut metadata: RenderMetadata,
+    deficiency: Option<ColorVisionDeficiency>,
+    gray_output: Option<GrayOutput>,
+) -> Result<(RgbImage, RenderMetadata), RenderError> {
+    // Preview how the image looks to color-blind viewers
+    if let Some(deficiency) = deficiency {
+        color_vision::simulate(&mut img, deficiency);
+    }
+
+    // Encode for a power-law display instead of sRGB
+    if let Some(gamma) = metadata.params.gamma {
+        gamma::apply_output_gamma(&mut img, gamma);
+    }
+
+    // Reduce to a few gray levels for engravers and other low-depth devices
+    if let Some(gray_output) = gray_output {
+        gray_output.apply(&mut img);
+    }
+
+    // Let registered plugins observe/transform the result
+    state
+        .plugins
+        .run(&mut img, &mut metadata)
+        .map_err(RenderError::Internal)?;
+
+    Ok((img, metadata))
+}
+
+/// `render` at 16 bits per channel, for `bit_depth=16`. Post-render hooks take 8-bit images,
+/// so they run on a rounded copy: hooks that only observe keep working, and a hook that
+/// changes the pixels fails the render rather than being silently dropped.
+pub fn render16(
+    state: &AppState,
+    fractal_type: &str,
+    params: FractalParams,
+    options: &RenderOptions,
+) -> Result<(Rgb16Image, RenderMetadata), RenderError> {
+    let fractal = lookup(fractal_type)?;
+    let deficiency = parse_deficiency(&params)?;
+    if params.dither.is_some() || params.dither_levels.is_some() || params.equalize.is_some() {
+        return Err(RenderError::BadRequest(
+            "dither, dither_levels and equalize aren't available with bit_depth=16.".to_string(),
+        ));
+    }
+    let (mut img, mut metadata) = generate(
+        state,
+        fractal.as_ref(),
+        params,
+        options,
+        |fractal, params| fractal.generate16(params),
+    )?;
+
+    if let Some(deficiency) = deficiency {
+        color_vision::simulate16(&mut img, deficiency);
+    }
+    if let Some(gamma) = metadata.params.gamma {
+        gamma::apply_output_gamma16(&mut img, gamma);
+    }
+
+    if !state.plugins.is_empty() {
+        let rounded = narrow(&img);
+        let mut observed = rounded.clone();
+        state
+            .plugins
+            .run(&mut observed, &mut metadata)
+            .map_err(RenderError::Internal)?;
+        if observed != rounded {
+            return Err(RenderError::BadRequest(
+                "bit_depth=16 isn't available while a post-render hook changes images.".to_string(),
+            ));
+        }
+    }
+
+    Ok((img, metadata))
+}
+
+/// `render` as an SVG, PDF or EPS document, for those formats on the types in `VECTOR_TYPES`.
+/// The pixel post-processing isn't offered with them. Hooks that use pixels run on a raster
+/// of the same drawing: those that only observe keep working, and one that changes the pixels
+/// fails the render rather than being silently dropped. The rest get a blank image of the size.
+pub fn render_vector(
+    state: &AppState,
+    fractal_type: &str,
+    params: FractalParams,
+    format: VectorFormat,
+    options: &RenderOptions,
+) -> Result<(Vec<u8>, RenderMetadata), RenderError> {
+    let fractal = lookup(fractal_type)?;
+    let unsupported = [
+        ("simulate", params.simulate.is_some()),
+        ("gamma", params.gamma.is_some()),
+        ("dither", params.dither.is_some()),
+        ("dither_levels", params.dither_levels.is_some()),
+        ("equalize", params.equalize.is_some()),
+    ];
+    if let Some((option, _)) = unsupported.iter().find(|(_, requested)| *requested) {
+        return Err(RenderError::BadRequest(format!(
+            "{} isn't available with format={}.",
+            option,
+            format.name()
+        )));
+    }
+
+    let (document, mut metadata) = generate(
+        state,
+        fractal.as_ref(),
+        params,
+        options,
+        |fractal, params| {
+            fractal
+                .generate_vector(params, format)
+                .map(|document| (document, Vec::new()))
+        },
+    )?;
+
+    // Only hooks that use pixels are worth rasterizing the drawing for
+    if state.plugins.uses_pixels() {
+        let raster = fractal
+            .generate(metadata.params.clone())
+            .map_err(RenderError::BadRequest)?;
+        let mut observed = raster.clone();
+        state
+            .plugins
+            .run(&mut observed, &mut metadata)
+            .map_err(RenderError::Internal)?;
+        if observed != raster {
+            return Err(RenderError::BadRequest(format!(
+                "format={} isn't available while a post-render hook changes images.",
+                format.name()
+            )));
+        }
+    } else if !state.plugins.is_empty() {
+        let (width, height) = (metadata.params.width, metadata.params.height);
+        state
+            .plugins
+            .run(&mut RgbImage::new(width, height), &mut metadata)
+            .map_err(RenderError::Internal)?;
+    }
+
+    Ok((document, metadata))
+}
+
+/// The uncolored first pass of an escape-time render, for `format=raw` / `format=npy` and the
+/// iteration matrix; `final_z` keeps each orbit's last point for smoothing. The color options
+/// are ignored, and those changing pixels after coloring are refused like with vector output.
+pub fn render_escapes(
+    state: &AppState,
+    fractal_type: &str,
+    params: FractalParams,
+    final_z: bool,
+    options: &RenderOptions,
+) -> Result<(Vec<Escape>, RenderMetadata), RenderError> {
+    let fractal = lookup(fractal_type)?;
+    let unsupported = [
+        ("simulate", params.simulate.is_some()),
+        ("gamma", params.gamma.is_some()),
+        ("dither", params.dither.is_some()),
+        ("dither_levels", params.dither_levels.is_some()),
+        ("equalize", params.equalize.is_some()),
+    ];
+    if let Some((option, _)) = unsupported.iter().find(|(_, requested)| *requested) {
+        return Err(RenderError::BadRequest(format!(
+            "{} isn't available with iteration data.",
+            option
+        )));
+    }
+
+    let (escapes, mut metadata) = generate(
+        state,
+        fractal.as_ref(),
+        params,
+        options,
+        |fractal, params| fractal.generate_escapes(params, final_z),
+    )?;
+
+    // Hooks that use pixels see the first pass colored; it isn't iterated again for them
+    if state.plugins.uses_pixels() {
+        let raster =
+            color_first_pass(&escapes, &metadata.params).map_err(RenderError::BadRequest)?;
+        let mut observed = raster.clone();
+        state
+            .plugins
+            .run(&mut observed, &mut metadata)
+            .map_err(RenderError::Internal)?;
+        if observed != raster {
+            return Err(RenderError::BadRequest(
+                "Iteration data isn't available while a post-render hook changes images."
+                    .to_string(),
+            ));
+        }
+    } else if !state.plugins.is_empty() {
+        let (width, height) = (metadata.params.width, metadata.params.height);
+        state
+            .plugins
+            .run(&mut RgbImage::new(width, height), &mut metadata)
+            .map_err(RenderError::Internal)?;
+    }
+
+    Ok((escapes, metadata))
+}
+
+/// The second pass of an escape-time render over first-pass results already at hand: the
+/// image the type renders itself, less antialiasing, which would iterate edge pixels again
+fn color_first_pass(escapes: &[Escape], params: &FractalParams) -> Result<RgbImage, String> {
+    let scheme = ColorScheme::from_str(params.color_scheme.as_deref().unwrap_or("default"));
+    let coloring = Coloring::from_param(params.coloring.as_deref())?;
+    let cycle = PaletteCycle::from_params(params)?;
+    let shading = Shading::from_params(params)?;
+
+    let FractalParams {
+        width,
+        height,
+        max_iterations,
+        ..
+    } = *params;
+    let mut pixels = color_escapes::<u8>(escapes, max_iterations, &scheme, coloring, &cycle);
+    shading.apply(&mut pixels, escapes, width, height, max_iterations);
+    Ok(u8::image(width, height, pixels))
+}
+
+fn lookup(fractal_type: &str) -> Result<Box<dyn Fractal>, RenderError> {
+    create_fractal(fractal_type).ok_or_else(|| {
+        RenderError::BadRequest(format!(
+            "Unknown fractal type: {}. Supported types: {}",
+            fractal_type,
+            FRACTAL_TYPES.join(", ")
+        ))
+    })
+}
+
+fn parse_deficiency(params: &FractalParams) -> Result<Option<ColorVisionDeficiency>, RenderError> {
+    params
+        .simulate
+        .as_deref()
+        .map(ColorVisionDeficiency::parse)
+        .transpose()
+        .map_err(RenderError::BadRequest)
+}
+
+/// Throttling, quotas, the render itself and usage accounting, shared by both bit depths,
+/// vector documents and raw data
+fn generate<I: Send>(
+    state: &AppState,
+    fractal: &dyn Fractal,
+    mut params: FractalParams,
+    options: &RenderOptions,
+    generate: impl FnOnce(&dyn Fractal, FractalParams) -> Result<(I, StatsHeaders), String> + Send,
+) -> Result<(I, RenderMetadata), RenderError> {
+    if let Some(gamma) = params.gamma {
+        validate_gamma(gamma).map_err(RenderError::BadRequest)?;
+    }
+
+    tracing::debug!("Generating {} fractal", fractal.name());
+
+    // Settings in effect for this render, even if they are reloaded meanwhile
+    let throttle = state.throttle.get();
+    let quotas = state.quotas.get();
+
+    // Throttle when configured hours/load or the client call for low-power mode
+    let low_power = throttle.evaluate(options.low_power);
+    let power_headers = low_power
+        .map(|reason| throttle.degrade(fractal.name(), &mut params, reason))
+        .unwrap_or_default();
+
+    if !options.billed_by_caller {
+        quotas
+            .check(
+                &state.usage,
+                options.tenant.as_deref(),
+                params.width as u64 * params.height as u64,
+            )
+            .map_err(RenderError::QuotaExceeded)?;
+    }
+
+    // Generate the fractal
+    let started = Instant::now();
+    let generated = match low_power {
+        Some(_) => throttle.install(|| generate(fractal, params.clone())),
+        None => generate(fractal, params.clone()),
+    };
+    let (img, stats_headers) = generated.map_err(RenderError::BadRequest)?;
+    let render_time = started.elapsed();
+    if !options.billed_by_caller {
+        state.usage.record(
+            options.tenant.as_deref(),
+            params.width as u64 * params.height as u64,
+            render_time,
+        );
+    }
+
+    let mut metadata = RenderMetadata::new(fractal.name(), params, render_time);
+    metadata.headers.extend(stats_headers);
+    metadata.headers.extend(power_headers);
+    metadata
+        .headers
+        .extend(quotas.headers(&state.usage, options.tenant.as_deref()));
+
+    Ok((img, metadata))
+}
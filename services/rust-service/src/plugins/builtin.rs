@@ -0,0 +1,34 @@
+use super::{PostRenderHook, RenderMetadata};
+use image::RgbImage;
+
+/// Logs every render and reports its duration in an `X-Render-Time-Ms` header
+pub struct RenderTimingHook;
+
+impl PostRenderHook for RenderTimingHook {
+    fn name(&self) -> &str {
+        "render-timing"
+    }
+
+    fn process(&self, img: &mut RgbImage, metadata: &mut RenderMetadata) -> Result<(), String> {
+        let millis = metadata.render_time.as_millis();
+
+        tracing::info!(
+            "Rendered {} fractal ({}x{}, max_iterations={}) in {}ms",
+            metadata.fractal_type,
+            img.width(),
+            img.height(),
+            metadata.params.max_iterations,
+            millis
+        );
+
+        metadata
+            .headers
+            .push(("X-Render-Time-Ms".to_string(), millis.to_string()));
+
+        Ok(())
+    }
+
+    fn uses_pixels(&self) -> bool {
+        false
+    }
+}
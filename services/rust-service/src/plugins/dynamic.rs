@@ -0,0 +1,51 @@
+//! Loading of post-render hooks from shared libraries.
+//!
+//! A plugin library must export a `primenexus_create_hook` function returning a
+//! `Box<Box<dyn PostRenderHook>>` turned into a raw pointer. Rust trait objects have no
+//! stable ABI, so plugins must be built with the same compiler and `PostRenderHook`
+//! definition as the service.
+
+use super::{PluginRegistry, PostRenderHook};
+use libloading::{Library, Symbol};
+
+const CREATE_SYMBOL: &[u8] = b"primenexus_create_hook";
+
+type CreateHook = unsafe extern "C" fn() -> *mut Box<dyn PostRenderHook>;
+
+impl PluginRegistry {
+    /// Load every library listed in `PLUGIN_PATHS` (colon-separated)
+    pub fn load_from_env(&mut self) -> Result<(), String> {
+        let Ok(paths) = std::env::var("PLUGIN_PATHS") else {
+            return Ok(());
+        };
+
+        for path in paths.split(':').filter(|p| !p.is_empty()) {
+            self.load_library(path)?;
+        }
+
+        Ok(())
+    }
+
+    pub fn load_library(&mut self, path: &str) -> Result<(), String> {
+        // SAFETY: loading a library runs its initializers; only trusted paths should be configured
+        let library = unsafe { Library::new(path) }
+            .map_err(|e| format!("Failed to load plugin {}: {}", path, e))?;
+
+        let hook = unsafe {
+            let create: Symbol<CreateHook> = library
+                .get(CREATE_SYMBOL)
+                .map_err(|e| format!("Plugin {} has no hook constructor: {}", path, e))?;
+
+            let raw = create();
+            if raw.is_null() {
+                return Err(format!("Plugin {} returned a null hook", path));
+            }
+            *Box::from_raw(raw)
+        };
+
+        self.register(hook);
+        self.libraries.push(library);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,82 @@
+pub mod builtin;
+#[cfg(feature = "dynamic-plugins")]
+pub mod dynamic;
+
+use crate::fractals::traits::FractalParams;
+use image::RgbImage;
+use std::time::Duration;
+
+/// Information about a finished render, passed to every post-render hook
+#[derive(Clone, Debug)]
+pub struct RenderMetadata {
+    pub fractal_type: String,
+    pub params: FractalParams,
+    pub render_time: Duration,
+
+    /// Extra response headers contributed by hooks
+    pub headers: Vec<(String, String)>,
+}
+
+impl RenderMetadata {
+    pub fn new(fractal_type: &str, params: FractalParams, render_time: Duration) -> Self {
+        Self {
+            fractal_type: fractal_type.to_string(),
+            params,
+            render_time,
+            headers: Vec::new(),
+        }
+    }
+}
+
+pub trait PostRenderHook: Send + Sync {
+    /// Get the name of this hook (used in logs and errors)
+    fn name(&self) -> &str;
+
+    /// Observe or transform the rendered image and its metadata before encoding
+    fn process(&self, img: &mut RgbImage, metadata: &mut RenderMetadata) -> Result<(), String>;
+
+    /// Whether `process` reads or changes the pixels. Hooks that only use the metadata and the
+    /// image size return false, so output without a raster of its own (vector documents,
+    /// iteration data) isn't rasterized just for them.
+    fn uses_pixels(&self) -> bool {
+        true
+    }
+}
+
+/// Hooks registered at startup, run in registration order after every render
+#[derive(Default)]
+pub struct PluginRegistry {
+    hooks: Vec<Box<dyn PostRenderHook>>,
+
+    // Declared after hooks so the hooks are dropped before their libraries are unloaded
+    #[cfg(feature = "dynamic-plugins")]
+    libraries: Vec<libloading::Library>,
+}
+
+impl PluginRegistry {
+    pub fn register(&mut self, hook: Box<dyn PostRenderHook>) {
+        tracing::info!("Registered post-render hook: {}", hook.name());
+        self.hooks.push(hook);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.hooks.is_empty()
+    }
+
+    /// Whether any hook needs the rendered pixels, see `PostRenderHook::uses_pixels`
+    pub fn uses_pixels(&self) -> bool {
+        self.hooks.iter().any(|hook| hook.uses_pixels())
+    }
+
+    pub fn hook_names(&self) -> Vec<&str> {
+        self.hooks.iter().map(|hook| hook.name()).collect()
+    }
+
+    pub fn run(&self, img: &mut RgbImage, metadata: &mut RenderMetadata) -> Result<(), String> {
+        for hook in &self.hooks {
+            hook.process(img, metadata)
+                .map_err(|e| format!("Post-render hook '{}' failed: {}", hook.name(), e))?;
+        }
+        Ok(())
+    }
+}
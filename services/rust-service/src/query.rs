@@ -0,0 +1,327 @@
+use crate::defaults;
+use crate::fractals::traits::FractalParams;
+use crate::rendering::colors::{BRAND_PREFIX, OKLAB_PREFIX};
+use crate::utils::locale::parse_decimal;
+use crate::utils::validation::IfsTransformSpec;
+use schemars::JsonSchema;
+use serde::de::Error as _;
+use serde::{Deserialize, Deserializer, Serialize};
+
+/// Raw request parameters shared by the HTTP query string and the tool server arguments
+#[derive(Deserialize, Serialize, Clone, Debug, Default, JsonSchema)]
+pub struct FractalQuery {
+    #[serde(rename = "type")]
+    pub fractal_type: Option<String>,
+
+    // Common parameters (aliases are the Python service's names, see `dialect`)
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    #[serde(default, deserialize_with = "locale_f64")]
+    pub zoom: Option<f64>,
+    #[serde(default, alias = "cx", deserialize_with = "locale_f64")]
+    pub center_x: Option<f64>,
+    #[serde(default, alias = "cy", deserialize_with = "locale_f64")]
+    pub center_y: Option<f64>,
+    #[serde(alias = "iters")]
+    pub max_iterations: Option<u32>,
+    pub color_scheme: Option<String>,
+    // Custom gradient as comma-separated hex stops (e.g. 000764,206bcb,edffff,ffaa00); takes
+    // the place of color_scheme
+    pub palette: Option<String>,
+    // 2-5 brand colors (e.g. d52b1e,ffffff) joined by a smooth Oklab gradient; takes the place
+    // of palette and color_scheme
+    pub brand_colors: Option<String>,
+    // Interpolate between palette stops in rgb (linear light, the default) or oklab
+    pub interpolation: Option<String>,
+    // Escape-time palette spacing (linear, histogram or orbit_trap)
+    pub coloring: Option<String>,
+    // Orbit trap for coloring=orbit_trap: shape (point, cross, circle), center and radius
+    pub trap_shape: Option<String>,
+    #[serde(default, deserialize_with = "locale_f64")]
+    pub trap_x: Option<f64>,
+    #[serde(default, deserialize_with = "locale_f64")]
+    pub trap_y: Option<f64>,
+    #[serde(default, deserialize_with = "locale_f64")]
+    pub trap_radius: Option<f64>,
+    // Pixels within this estimated distance of the boundary for coloring=distance
+    #[serde(default, deserialize_with = "locale_f64")]
+    pub boundary_width: Option<f64>,
+    // Stripes per turn around the origin for coloring=stripe_average
+    #[serde(default, deserialize_with = "locale_f64")]
+    pub stripe_density: Option<f64>,
+    // Shift the palette by this fraction (0-1), repeat it color_scale times across the escape
+    // range, and space escape times linearly or by their logarithm (color_spacing=log)
+    #[serde(default, deserialize_with = "locale_f64")]
+    pub color_offset: Option<f64>,
+    #[serde(default, deserialize_with = "locale_f64")]
+    pub color_scale: Option<f64>,
+    pub color_spacing: Option<String>,
+    // Iterate at a low limit first and refine only pixels near the boundary
+    pub adaptive: Option<bool>,
+    // Supersample only high-contrast pixels (aa=adaptive) and the contrast that counts
+    pub aa: Option<String>,
+    pub aa_threshold: Option<f64>,
+    // Slope lighting (shading=lambert) and the light's direction in degrees
+    pub shading: Option<String>,
+    pub light_azimuth: Option<f64>,
+    pub light_elevation: Option<f64>,
+
+    // Mandelbrot family member (classic, celtic, buffalo, ...)
+    pub variant: Option<String>,
+
+    // Julia-specific parameters
+    #[serde(default, deserialize_with = "locale_f64")]
+    pub julia_c_real: Option<f64>,
+    #[serde(default, deserialize_with = "locale_f64")]
+    pub julia_c_imag: Option<f64>,
+    // Complex polynomial p for z = p(z) + c, comma-separated and highest degree first
+    pub julia_coefficients: Option<String>,
+
+    // Hybrid step pattern, one letter per formula (e.g. MMJB)
+    pub hybrid_pattern: Option<String>,
+
+    // Geometric fractal parameters
+    pub recursion_depth: Option<u32>,
+
+    // Newton-specific parameters
+    pub newton_degree: Option<u32>,
+    pub newton_coefficients: Option<String>,
+
+    // Nova-specific parameters
+    #[serde(default, deserialize_with = "locale_f64")]
+    pub relaxation: Option<f64>,
+
+    // Lyapunov-specific parameters
+    pub lyapunov_sequence: Option<String>,
+
+    // Sampled (Monte Carlo) fractal parameters
+    pub samples: Option<u64>,
+    pub seed: Option<u64>,
+    // Tone-mapping of density renders: linear, sqrt, log or aces, and exposure in stops
+    pub tone_map: Option<String>,
+    #[serde(default, deserialize_with = "locale_f64")]
+    pub exposure: Option<f64>,
+
+    // Nebulabrot per-channel iteration limits
+    pub red_iterations: Option<u32>,
+    pub green_iterations: Option<u32>,
+    pub blue_iterations: Option<u32>,
+
+    // User-defined IFS transforms: a JSON string in query strings, a string or array in JSON bodies
+    #[serde(default, deserialize_with = "json_as_string")]
+    #[schemars(with = "Option<IfsTransformsSchema>")]
+    pub ifs_transforms: Option<String>,
+
+    // L-system parameters (recursion_depth sets the rewriting passes)
+    pub lsystem_preset: Option<String>,
+    pub lsystem_axiom: Option<String>,
+    pub lsystem_rules: Option<String>,
+    #[serde(default, deserialize_with = "locale_f64")]
+    pub lsystem_angle: Option<f64>,
+
+    // Vicsek sub-type (plus or x)
+    pub vicsek_variant: Option<String>,
+
+    // Apollonian gasket curvature cutoff (outer circle = 1)
+    #[serde(default, deserialize_with = "locale_f64")]
+    pub max_curvature: Option<f64>,
+
+    // Stroke width in pixels for line-drawn fractals (H-tree)
+    pub line_thickness: Option<u32>,
+
+    // Solid colors for geometric fractals (6-digit hex): the background, every line
+    // (stroke_color) and every filled shape (fill_color), in place of the scheme's gradient
+    pub background_color: Option<String>,
+    pub stroke_color: Option<String>,
+    pub fill_color: Option<String>,
+
+    // Strange attractor map (clifford, dejong, lorenz) and its coefficients
+    pub attractor: Option<String>,
+    #[serde(default, deserialize_with = "locale_f64")]
+    pub attractor_a: Option<f64>,
+    #[serde(default, deserialize_with = "locale_f64")]
+    pub attractor_b: Option<f64>,
+    #[serde(default, deserialize_with = "locale_f64")]
+    pub attractor_c: Option<f64>,
+    #[serde(default, deserialize_with = "locale_f64")]
+    pub attractor_d: Option<f64>,
+
+    // Logistic map bifurcation diagram: rates along x, orbit values along y
+    #[serde(default, deserialize_with = "locale_f64")]
+    pub bifurcation_r_min: Option<f64>,
+    #[serde(default, deserialize_with = "locale_f64")]
+    pub bifurcation_r_max: Option<f64>,
+    #[serde(default, deserialize_with = "locale_f64")]
+    pub bifurcation_y_min: Option<f64>,
+    #[serde(default, deserialize_with = "locale_f64")]
+    pub bifurcation_y_max: Option<f64>,
+
+    // Plasma (diamond-square) displacement falloff per subdivision; seed picks the terrain
+    #[serde(default, deserialize_with = "locale_f64")]
+    pub roughness: Option<f64>,
+
+    // User-defined escape-time formula of z and c, e.g. z^3 + c*sin(z)
+    pub formula: Option<String>,
+
+    // Color vision deficiency to simulate on the finished image
+    pub simulate: Option<String>,
+    // Power-law gamma the finished image is encoded for instead of sRGB (1-3)
+    #[serde(default, deserialize_with = "locale_f64")]
+    pub gamma: Option<f64>,
+    // Gray output for low-depth devices: none, ordered or floyd_steinberg dithering, to
+    // dither_levels evenly spaced grays (2-256), after optional histogram equalization
+    pub dither: Option<String>,
+    pub dither_levels: Option<u32>,
+    pub equalize: Option<bool>,
+}
+
+/// IFS transforms as JSON-encoded text or as an inline list (schema only)
+#[derive(JsonSchema)]
+#[serde(untagged)]
+#[allow(dead_code)]
+enum IfsTransformsSchema {
+    Encoded(String),
+    Inline(Vec<IfsTransformSpec>),
+}
+
+/// Accept JSON numbers as well as numeric strings with either decimal separator ("0,285")
+fn locale_f64<'de, D>(deserializer: D) -> Result<Option<f64>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum NumberOrText {
+        Number(f64),
+        Text(String),
+    }
+
+    match Option::<NumberOrText>::deserialize(deserializer)? {
+        None => Ok(None),
+        Some(NumberOrText::Number(value)) => Ok(Some(value)),
+        Some(NumberOrText::Text(text)) => parse_decimal(&text).map(Some).map_err(D::Error::custom),
+    }
+}
+
+/// Accept either a JSON-encoded string or inline JSON, keeping the JSON text either way
+fn json_as_string<'de, D>(deserializer: D) -> Result<Option<String>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let value = Option::<serde_json::Value>::deserialize(deserializer)?;
+    Ok(match value {
+        None | Some(serde_json::Value::Null) => None,
+        Some(serde_json::Value::String(text)) => Some(text),
+        Some(value) => Some(value.to_string()),
+    })
+}
+
+impl FractalQuery {
+    /// JSON object of the parameters that are set (unset ones are omitted)
+    pub fn to_json(&self) -> serde_json::Value {
+        let mut value = serde_json::to_value(self).unwrap_or_default();
+        if let Some(object) = value.as_object_mut() {
+            object.retain(|_, field| !field.is_null());
+        }
+        value
+    }
+
+    pub fn fractal_type(&self) -> String {
+        self.fractal_type
+            .clone()
+            .unwrap_or_else(|| "mandelbrot".to_string())
+    }
+
+    /// Create FractalParams, filling in defaults for anything not supplied
+    pub fn into_params(self) -> FractalParams {
+        let defaults = defaults::current();
+        let oklab = self
+            .interpolation
+            .as_deref()
+            .is_some_and(|interpolation| interpolation.eq_ignore_ascii_case("oklab"));
+
+        FractalParams {
+            width: self.width.unwrap_or(defaults.width),
+            height: self.height.unwrap_or(defaults.height),
+            zoom: self.zoom.unwrap_or(defaults.zoom),
+            center_x: self.center_x.unwrap_or(defaults.center_x),
+            center_y: self.center_y.unwrap_or(defaults.center_y),
+            max_iterations: self.max_iterations.unwrap_or(defaults.max_iterations),
+            color_scheme: self
+                .brand_colors
+                .map(|colors| format!("{}{}", BRAND_PREFIX, colors))
+                .or(self.palette)
+                .or(self.color_scheme)
+                .or(defaults.color_scheme)
+                .map(|scheme| {
+                    if oklab {
+                        format!("{}{}", OKLAB_PREFIX, scheme)
+                    } else {
+                        scheme
+                    }
+                }),
+            interpolation: self.interpolation,
+            coloring: self.coloring,
+            trap_shape: self.trap_shape,
+            trap_x: self.trap_x,
+            trap_y: self.trap_y,
+            trap_radius: self.trap_radius,
+            boundary_width: self.boundary_width,
+            stripe_density: self.stripe_density,
+            color_offset: self.color_offset,
+            color_scale: self.color_scale,
+            color_spacing: self.color_spacing,
+            adaptive: self.adaptive,
+            aa: self.aa,
+            aa_threshold: self.aa_threshold,
+            shading: self.shading,
+            light_azimuth: self.light_azimuth,
+            light_elevation: self.light_elevation,
+            kernel: None,
+            variant: self.variant,
+            julia_c_real: self.julia_c_real,
+            julia_c_imag: self.julia_c_imag,
+            julia_coefficients: self.julia_coefficients,
+            hybrid_pattern: self.hybrid_pattern,
+            recursion_depth: self.recursion_depth,
+            newton_degree: self.newton_degree,
+            newton_coefficients: self.newton_coefficients,
+            relaxation: self.relaxation,
+            lyapunov_sequence: self.lyapunov_sequence,
+            samples: self.samples,
+            seed: self.seed,
+            tone_map: self.tone_map,
+            exposure: self.exposure,
+            red_iterations: self.red_iterations,
+            green_iterations: self.green_iterations,
+            blue_iterations: self.blue_iterations,
+            ifs_transforms: self.ifs_transforms,
+            lsystem_preset: self.lsystem_preset,
+            lsystem_axiom: self.lsystem_axiom,
+            lsystem_rules: self.lsystem_rules,
+            lsystem_angle: self.lsystem_angle,
+            vicsek_variant: self.vicsek_variant,
+            max_curvature: self.max_curvature,
+            line_thickness: self.line_thickness,
+            background_color: self.background_color,
+            stroke_color: self.stroke_color,
+            fill_color: self.fill_color,
+            attractor: self.attractor,
+            attractor_a: self.attractor_a,
+            attractor_b: self.attractor_b,
+            attractor_c: self.attractor_c,
+            attractor_d: self.attractor_d,
+            bifurcation_r_min: self.bifurcation_r_min,
+            bifurcation_r_max: self.bifurcation_r_max,
+            bifurcation_y_min: self.bifurcation_y_min,
+            bifurcation_y_max: self.bifurcation_y_max,
+            roughness: self.roughness,
+            formula: self.formula,
+            simulate: self.simulate,
+            gamma: self.gamma,
+            dither: self.dither,
+            dither_levels: self.dither_levels,
+            equalize: self.equalize,
+        }
+    }
+}
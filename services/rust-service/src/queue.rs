@@ -0,0 +1,229 @@
+//! Message-queue consumer mode: render requests arrive on a NATS subject and results are
+//! published to the message's reply subject (or `NATS_RESULT_SUBJECT` when none is set).
+
+use crate::jobs::{thumbnail, RecentJob};
+use crate::pipeline::{render, AppState, RenderError, RenderOptions};
+use crate::query::FractalQuery;
+use crate::rendering::encoder::{encode_image, EncodeOptions, OutputFormat};
+use crate::utils::time::unix_now;
+use async_nats::Message;
+use base64::{engine::general_purpose::STANDARD, Engine};
+use futures::StreamExt;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+const DEFAULT_SUBJECT: &str = "fractal.render";
+const QUEUE_GROUP: &str = "rust-service";
+
+#[derive(Deserialize)]
+struct QueueRequest {
+    /// Opaque id echoed back so callers can correlate results
+    request_id: Option<String>,
+    /// List the finished render in the `/api/jobs/recent` feed
+    public: Option<bool>,
+    #[serde(flatten)]
+    query: FractalQuery,
+}
+
+#[derive(Serialize)]
+struct QueueResult {
+    request_id: Option<String>,
+    status: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    content_type: Option<&'static str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    data: Option<String>,
+    /// Base64 PNG at most THUMBNAIL_SIZE pixels on its longest edge
+    #[serde(skip_serializing_if = "Option::is_none")]
+    thumbnail: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+    /// HTTP status the same error gets from the REST API (400, 429, 500, ...)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error_status: Option<u16>,
+}
+
+struct QueueConfig {
+    url: String,
+    subject: String,
+    result_subject: Option<String>,
+    /// Messages rendered at once; the rest wait in the subscription
+    max_in_flight: usize,
+}
+
+impl QueueConfig {
+    /// Queue mode is enabled by setting `NATS_URL`
+    fn from_env() -> Option<Self> {
+        let url = std::env::var("NATS_URL").ok()?;
+        Some(Self {
+            url,
+            subject: std::env::var("NATS_SUBJECT").unwrap_or_else(|_| DEFAULT_SUBJECT.to_string()),
+            result_subject: std::env::var("NATS_RESULT_SUBJECT").ok(),
+            max_in_flight: std::env::var("NATS_MAX_IN_FLIGHT")
+                .ok()
+                .and_then(|value| value.parse::<usize>().ok())
+                .filter(|&limit| limit > 0)
+                .unwrap_or_else(|| {
+                    std::thread::available_parallelism().map_or(1, |cores| cores.get())
+                }),
+        })
+    }
+}
+
+fn process_message(state: &AppState, payload: &[u8]) -> QueueResult {
+    let request: QueueRequest = match serde_json::from_slice(payload) {
+        Ok(request) => request,
+        Err(e) => {
+            return QueueResult {
+                request_id: None,
+                status: "error",
+                content_type: None,
+                data: None,
+                thumbnail: None,
+                error: Some(format!("Invalid render request: {}", e)),
+                error_status: Some(400),
+            }
+        }
+    };
+
+    let fractal_type = request.query.fractal_type();
+    let params = request.query.to_json();
+    let rendered = render(
+        state,
+        &fractal_type,
+        request.query.into_params(),
+        &RenderOptions::default(),
+    )
+    .and_then(|(img, _)| {
+        let thumb = thumbnail(&img).map_err(RenderError::Internal)?;
+        let (width, height) = img.dimensions();
+        let png_bytes = encode_image(&img, OutputFormat::Png, &EncodeOptions::default())
+            .map_err(RenderError::Internal)?;
+        Ok((png_bytes, thumb, width, height))
+    });
+
+    match rendered {
+        Ok((png_bytes, thumb, width, height)) => {
+            if request.public == Some(true) {
+                state.jobs.record(RecentJob {
+                    request_id: request.request_id.clone(),
+                    fractal_type,
+                    width,
+                    height,
+                    completed_at: unix_now(),
+                    params,
+                    thumbnail: format!("data:image/png;base64,{}", STANDARD.encode(&thumb)),
+                });
+            }
+            QueueResult {
+                request_id: request.request_id,
+                status: "ok",
+                content_type: Some("image/png"),
+                data: Some(STANDARD.encode(png_bytes)),
+                thumbnail: Some(STANDARD.encode(thumb)),
+                error: None,
+                error_status: None,
+            }
+        }
+        Err(e) => QueueResult {
+            request_id: request.request_id,
+            status: "error",
+            content_type: None,
+            data: None,
+            thumbnail: None,
+            error_status: Some(e.status().as_u16()),
+            error: Some(e.message()),
+        },
+    }
+}
+
+async fn handle_message(
+    client: async_nats::Client,
+    state: Arc<AppState>,
+    result_subject: Option<String>,
+    message: Message,
+    // Held until the result is published, so a slot frees only when the work is done
+    _permit: OwnedSemaphorePermit,
+) {
+    let Some(reply) = message
+        .reply
+        .map(|subject| subject.to_string())
+        .or(result_subject)
+    else {
+        tracing::warn!(
+            "Dropping render request on {} without a reply subject",
+            message.subject
+        );
+        return;
+    };
+
+    // Rendering is CPU-bound, keep it off the async workers
+    let payload = message.payload;
+    let result = match tokio::task::spawn_blocking(move || process_message(&state, &payload)).await
+    {
+        Ok(result) => result,
+        Err(e) => {
+            tracing::error!("Render task failed: {}", e);
+            return;
+        }
+    };
+
+    match serde_json::to_vec(&result) {
+        Ok(body) => {
+            if let Err(e) = client.publish(reply, body.into()).await {
+                tracing::error!("Failed to publish render result: {}", e);
+            }
+        }
+        Err(e) => tracing::error!("Failed to serialize render result: {}", e),
+    }
+}
+
+/// Subscribe to the render subject if `NATS_URL` is configured; runs until the connection closes
+pub async fn run_consumer(state: Arc<AppState>) {
+    let Some(config) = QueueConfig::from_env() else {
+        return;
+    };
+
+    let client = match async_nats::connect(&config.url).await {
+        Ok(client) => client,
+        Err(e) => {
+            tracing::error!("Failed to connect to NATS at {}: {}", config.url, e);
+            return;
+        }
+    };
+
+    // Queue group so multiple service instances share the load
+    let mut subscriber = match client
+        .queue_subscribe(config.subject.clone(), QUEUE_GROUP.to_string())
+        .await
+    {
+        Ok(subscriber) => subscriber,
+        Err(e) => {
+            tracing::error!("Failed to subscribe to {}: {}", config.subject, e);
+            return;
+        }
+    };
+
+    tracing::info!(
+        "Consuming render requests from NATS subject {} ({} at a time)",
+        config.subject,
+        config.max_in_flight
+    );
+
+    let in_flight = Arc::new(Semaphore::new(config.max_in_flight));
+    while let Some(message) = subscriber.next().await {
+        let Ok(permit) = in_flight.clone().acquire_owned().await else {
+            break;
+        };
+        tokio::spawn(handle_message(
+            client.clone(),
+            state.clone(),
+            config.result_subject.clone(),
+            message,
+            permit,
+        ));
+    }
+
+    tracing::warn!("NATS subscription to {} closed", config.subject);
+}
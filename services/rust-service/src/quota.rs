@@ -0,0 +1,236 @@
+//! Monthly render budgets per tenant, enforced against the usage ledger before each render.
+//! A tenant over budget gets 429 (with `Retry-After` until the next month) or 402, and every
+//! render for a budgeted tenant reports what is left; past `QUOTA_WARN_AT` a `Warning: 299`
+//! header says how much of the budget is used.
+//!
+//! Configuration (all optional; without budgets nothing is enforced):
+//! - `QUOTA_MONTHLY_PIXELS`, `QUOTA_MONTHLY_CPU_SECONDS`: budgets for every tenant, including
+//!   `anonymous`, that has no entry in the quota file
+//! - `QUOTA_EXCEEDED_STATUS`: 429 (default) or 402
+//! - `QUOTA_WARN_AT`: fraction of a budget after which responses carry a warning (default 0.8)
+//! - `QUOTA_FILE`: JSON object of per-tenant budgets keyed by tenant id, e.g.
+//!   `{"2bd806c97f0e00af": {"pixels": 5000000000, "cpu_seconds": 36000, "exceeded_status": 402}}`;
+//!   a missing budget is unlimited
+//!
+//! The admin key (`USAGE_ADMIN_KEY`) is never limited. Budgets can be changed at runtime by
+//! editing the quota file or the settings file (see `config`).
+
+use crate::config;
+use crate::usage::{next_month_start, UsageLedger, ANONYMOUS};
+use crate::utils::time::unix_now;
+use axum::http::StatusCode;
+use serde::Deserialize;
+use std::collections::HashMap;
+
+pub const PIXELS_REMAINING_HEADER: &str = "X-Quota-Pixels-Remaining";
+pub const CPU_SECONDS_REMAINING_HEADER: &str = "X-Quota-Cpu-Seconds-Remaining";
+
+const DEFAULT_WARN_AT: f64 = 0.8;
+
+#[derive(Clone, Copy, Debug, Default, Deserialize)]
+struct Budget {
+    pixels: Option<u64>,
+    cpu_seconds: Option<f64>,
+    exceeded_status: Option<u16>,
+}
+
+impl Budget {
+    fn is_limited(&self) -> bool {
+        self.pixels.is_some() || self.cpu_seconds.is_some()
+    }
+}
+
+/// A render refused because the tenant's budget is used up
+#[derive(Debug)]
+pub struct QuotaExceeded {
+    pub status: StatusCode,
+    pub message: String,
+    /// Seconds until the budget renews at the start of next month
+    pub retry_after: u64,
+}
+
+pub struct Quotas {
+    default: Budget,
+    tenants: HashMap<String, Budget>,
+    exceeded_status: StatusCode,
+    warn_at: f64,
+}
+
+impl Quotas {
+    /// Budgets from the current settings, without any per-tenant ones if the quota file is broken
+    pub fn from_env() -> Self {
+        Self::load().unwrap_or_else(|e| {
+            tracing::error!("Ignoring quota file: {}", e);
+            Self::with_tenants(HashMap::new())
+        })
+    }
+
+    /// Budgets from the current settings; fails if the quota file can't be used
+    pub fn load() -> Result<Self, String> {
+        let tenants = match config::var("QUOTA_FILE") {
+            Some(path) => {
+                let tenants = load_tenants(&path).map_err(|e| format!("{}: {}", path, e))?;
+                tracing::info!("Loaded quotas for {} tenants from {}", tenants.len(), path);
+                tenants
+            }
+            None => HashMap::new(),
+        };
+        Ok(Self::with_tenants(tenants))
+    }
+
+    fn with_tenants(tenants: HashMap<String, Budget>) -> Self {
+        let default = Budget {
+            pixels: env_number("QUOTA_MONTHLY_PIXELS"),
+            cpu_seconds: env_number("QUOTA_MONTHLY_CPU_SECONDS"),
+            exceeded_status: None,
+        };
+        let exceeded_status = env_number::<u16>("QUOTA_EXCEEDED_STATUS")
+            .and_then(|code| parse_status("QUOTA_EXCEEDED_STATUS", code))
+            .unwrap_or(StatusCode::TOO_MANY_REQUESTS);
+        let warn_at = env_number::<f64>("QUOTA_WARN_AT")
+            .filter(|fraction| (0.0..=1.0).contains(fraction))
+            .unwrap_or(DEFAULT_WARN_AT);
+
+        Self {
+            default,
+            tenants,
+            exceeded_status,
+            warn_at,
+        }
+    }
+
+    /// Budget of `tenant`, unless it is unlimited
+    fn budget(&self, ledger: &UsageLedger, tenant: &str) -> Option<Budget> {
+        if ledger.is_admin(tenant) {
+            return None;
+        }
+        let budget = self.tenants.get(tenant).copied().unwrap_or(self.default);
+        budget.is_limited().then_some(budget)
+    }
+
+    /// Refuse a render of `pixels` when the tenant's budget can't cover it
+    pub fn check(
+        &self,
+        ledger: &UsageLedger,
+        tenant: Option<&str>,
+        pixels: u64,
+    ) -> Result<(), QuotaExceeded> {
+        let tenant = tenant.unwrap_or(ANONYMOUS);
+        let Some(budget) = self.budget(ledger, tenant) else {
+            return Ok(());
+        };
+        let used = ledger.quota_usage(tenant);
+
+        let exceeded = match (budget.pixels, budget.cpu_seconds) {
+            (Some(limit), _) if used.pixels + pixels > limit => Some(format!(
+                "Monthly pixel budget exceeded: {} of {} used, this render needs {}.",
+                used.pixels, limit, pixels
+            )),
+            (_, Some(limit)) if used.cpu_seconds >= limit => Some(format!(
+                "Monthly CPU budget exceeded: {:.1} of {} seconds used.",
+                used.cpu_seconds, limit
+            )),
+            _ => None,
+        };
+
+        match exceeded {
+            Some(message) => {
+                let now = unix_now();
+                Err(QuotaExceeded {
+                    status: budget
+                        .exceeded_status
+                        .and_then(|code| StatusCode::from_u16(code).ok())
+                        .unwrap_or(self.exceeded_status),
+                    message,
+                    retry_after: next_month_start(now) - now,
+                })
+            }
+            None => Ok(()),
+        }
+    }
+
+    /// Remaining budget after a render, with a warning once most of it is used
+    pub fn headers(&self, ledger: &UsageLedger, tenant: Option<&str>) -> Vec<(String, String)> {
+        let tenant = tenant.unwrap_or(ANONYMOUS);
+        let Some(budget) = self.budget(ledger, tenant) else {
+            return Vec::new();
+        };
+        let used = ledger.quota_usage(tenant);
+
+        let mut headers = Vec::new();
+        let mut warnings = Vec::new();
+        if let Some(limit) = budget.pixels {
+            headers.push((
+                PIXELS_REMAINING_HEADER.to_string(),
+                limit.saturating_sub(used.pixels).to_string(),
+            ));
+            warnings.extend(self.warning("pixel", used.pixels as f64, limit as f64));
+        }
+        if let Some(limit) = budget.cpu_seconds {
+            headers.push((
+                CPU_SECONDS_REMAINING_HEADER.to_string(),
+                format!("{:.1}", (limit - used.cpu_seconds).max(0.0)),
+            ));
+            warnings.extend(self.warning("CPU", used.cpu_seconds, limit));
+        }
+        if !warnings.is_empty() {
+            headers.push((
+                "Warning".to_string(),
+                format!("299 - \"{}\"", warnings.join("; ")),
+            ));
+        }
+
+        headers
+    }
+
+    fn warning(&self, resource: &str, used: f64, limit: f64) -> Option<String> {
+        let fraction = if limit > 0.0 { used / limit } else { 1.0 };
+        (fraction >= self.warn_at).then(|| {
+            format!(
+                "Monthly {} budget {:.0}% used",
+                resource,
+                fraction.min(1.0) * 100.0
+            )
+        })
+    }
+}
+
+/// Number of tenants in a quota file, or why it can't be used
+pub fn check_file(path: &str) -> Result<usize, String> {
+    load_tenants(path).map(|tenants| tenants.len())
+}
+
+fn load_tenants(path: &str) -> Result<HashMap<String, Budget>, String> {
+    let text = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+    let tenants: HashMap<String, Budget> =
+        serde_json::from_str(&text).map_err(|e| e.to_string())?;
+    for (tenant, budget) in &tenants {
+        if let Some(code) = budget.exceeded_status {
+            parse_status(&format!("exceeded_status of {}", tenant), code)
+                .ok_or_else(|| format!("exceeded_status of {} must be 402 or 429", tenant))?;
+        }
+    }
+    Ok(tenants)
+}
+
+fn parse_status(name: &str, code: u16) -> Option<StatusCode> {
+    match code {
+        402 => Some(StatusCode::PAYMENT_REQUIRED),
+        429 => Some(StatusCode::TOO_MANY_REQUESTS),
+        _ => {
+            tracing::warn!("Ignoring {}={}; must be 402 or 429", name, code);
+            None
+        }
+    }
+}
+
+fn env_number<T: std::str::FromStr>(name: &str) -> Option<T> {
+    let value = config::var(name)?;
+    match value.trim().parse() {
+        Ok(number) => Some(number),
+        Err(_) => {
+            tracing::warn!("Ignoring invalid {}={}", name, value);
+            None
+        }
+    }
+}
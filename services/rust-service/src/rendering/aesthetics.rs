@@ -0,0 +1,108 @@
+//! Heuristic aesthetic scoring used to weed out boring renders (flat fills, pure noise).
+//! Images are scored on a thumbnail so the cost doesn't depend on render size.
+
+use image::{imageops, RgbImage};
+use schemars::JsonSchema;
+use serde::Serialize;
+
+/// Longest edge of the thumbnail that gets scored
+const SCORING_SIZE: u32 = 256;
+
+/// Luma gradient above which a pixel counts as an edge
+const EDGE_THRESHOLD: f64 = 24.0;
+
+/// Colors are quantized to 3 bits per channel for the entropy histogram
+const COLOR_BINS: usize = 512;
+
+#[derive(Clone, Copy, Debug, Serialize, JsonSchema)]
+pub struct AestheticScore {
+    /// Fraction of pixels on an edge, 0..1
+    pub edge_density: f64,
+    /// Shannon entropy of the quantized color histogram, normalized to 0..1
+    pub color_entropy: f64,
+    /// Luma standard deviation, normalized to 0..1
+    pub contrast: f64,
+    /// Weighted overall score, 0..1 (higher is more interesting)
+    pub score: f64,
+}
+
+pub fn score_image(img: &RgbImage) -> AestheticScore {
+    let (width, height) = img.dimensions();
+    let scale = (SCORING_SIZE as f64 / width.max(height) as f64).min(1.0);
+    let thumb = imageops::thumbnail(
+        img,
+        ((width as f64 * scale) as u32).max(1),
+        ((height as f64 * scale) as u32).max(1),
+    );
+
+    let edge_density = edge_density(&thumb);
+    let color_entropy = color_entropy(&thumb);
+    let contrast = contrast(&thumb);
+
+    // Edges are good up to a point; beyond ~50% the image reads as noise
+    let edge_term = (edge_density / 0.15).min(1.0) * (1.0 - ((edge_density - 0.5).max(0.0) / 0.5));
+    let score = 0.4 * edge_term + 0.35 * color_entropy + 0.25 * contrast;
+
+    AestheticScore {
+        edge_density,
+        color_entropy,
+        contrast,
+        score,
+    }
+}
+
+fn luma(pixel: &image::Rgb<u8>) -> f64 {
+    let [r, g, b] = pixel.0;
+    0.299 * r as f64 + 0.587 * g as f64 + 0.114 * b as f64
+}
+
+fn edge_density(img: &RgbImage) -> f64 {
+    let (width, height) = img.dimensions();
+    if width < 2 || height < 2 {
+        return 0.0;
+    }
+
+    let mut edges = 0;
+    for y in 0..height - 1 {
+        for x in 0..width - 1 {
+            let here = luma(img.get_pixel(x, y));
+            let dx = (luma(img.get_pixel(x + 1, y)) - here).abs();
+            let dy = (luma(img.get_pixel(x, y + 1)) - here).abs();
+            if dx + dy > EDGE_THRESHOLD {
+                edges += 1;
+            }
+        }
+    }
+
+    edges as f64 / ((width - 1) * (height - 1)) as f64
+}
+
+fn color_entropy(img: &RgbImage) -> f64 {
+    let mut histogram = [0u32; COLOR_BINS];
+    for pixel in img.pixels() {
+        let [r, g, b] = pixel.0;
+        let bin = ((r as usize >> 5) << 6) | ((g as usize >> 5) << 3) | (b as usize >> 5);
+        histogram[bin] += 1;
+    }
+
+    let total = (img.width() * img.height()).max(1) as f64;
+    let entropy: f64 = histogram
+        .iter()
+        .filter(|&&count| count > 0)
+        .map(|&count| {
+            let p = count as f64 / total;
+            -p * p.log2()
+        })
+        .sum();
+
+    entropy / (COLOR_BINS as f64).log2()
+}
+
+fn contrast(img: &RgbImage) -> f64 {
+    let values: Vec<f64> = img.pixels().map(luma).collect();
+    let count = values.len().max(1) as f64;
+    let mean = values.iter().sum::<f64>() / count;
+    let variance = values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / count;
+
+    (variance.sqrt() / 128.0).min(1.0)
+}
@@ -0,0 +1,98 @@
+//! Drawing surface for the geometric fractals. Each type draws through `Canvas` once, and the
+//! same drawing becomes a raster image or, with `format=svg`, `pdf` or `eps`, a vector
+//! document.
+
+use super::circles::{draw_circle, fill_circle};
+use super::draw::draw_line;
+use image::{ImageBuffer, Rgb, RgbImage};
+
+/// Shapes the geometric types are made of. Coordinates are in pixels with y down.
+pub trait Canvas: Sized {
+    /// Canvas of the given size filled with `background`
+    fn blank(width: u32, height: u32, background: [u8; 3]) -> Self;
+
+    fn size(&self) -> (u32, u32);
+
+    /// Line `width` pixels wide with round caps
+    fn line(&mut self, start: (f64, f64), end: (f64, f64), width: f64, color: [u8; 3]);
+
+    /// Filled triangle
+    fn triangle(&mut self, corners: [(f64, f64); 3], color: [u8; 3]);
+
+    /// Filled axis-aligned square with its top-left corner at `origin`
+    fn square(&mut self, origin: (f64, f64), size: f64, color: [u8; 3]);
+
+    /// Filled circle
+    fn disc(&mut self, center: (f64, f64), radius: f64, color: [u8; 3]);
+
+    /// Circle outline `width` pixels wide
+    fn ring(&mut self, center: (f64, f64), radius: f64, width: f64, color: [u8; 3]);
+}
+
+impl Canvas for RgbImage {
+    fn blank(width: u32, height: u32, background: [u8; 3]) -> Self {
+        ImageBuffer::from_pixel(width, height, Rgb(background))
+    }
+
+    fn size(&self) -> (u32, u32) {
+        self.dimensions()
+    }
+
+    fn line(&mut self, start: (f64, f64), end: (f64, f64), width: f64, color: [u8; 3]) {
+        draw_line(self, start, end, width, color);
+    }
+
+    fn triangle(&mut self, corners: [(f64, f64); 3], color: [u8; 3]) {
+        let [p1, p2, p3] = corners;
+        let min_x = p1.0.min(p2.0).min(p3.0) as i32;
+        let max_x = p1.0.max(p2.0).max(p3.0) as i32;
+        let min_y = p1.1.min(p2.1).min(p3.1) as i32;
+        let max_y = p1.1.max(p2.1).max(p3.1) as i32;
+
+        // Set every pixel whose corner lies inside; the shapes tile, so edges stay hard
+        for y in min_y.max(0)..=max_y.min(self.height() as i32 - 1) {
+            for x in min_x.max(0)..=max_x.min(self.width() as i32 - 1) {
+                if is_inside_triangle((x as f64, y as f64), p1, p2, p3) {
+                    self.put_pixel(x as u32, y as u32, Rgb(color));
+                }
+            }
+        }
+    }
+
+    fn square(&mut self, origin: (f64, f64), size: f64, color: [u8; 3]) {
+        let min_x = origin.0.floor().max(0.0) as u32;
+        let min_y = origin.1.floor().max(0.0) as u32;
+        let max_x = ((origin.0 + size).ceil() as u32).min(self.width());
+        let max_y = ((origin.1 + size).ceil() as u32).min(self.height());
+
+        for y in min_y..max_y {
+            for x in min_x..max_x {
+                self.put_pixel(x, y, Rgb(color));
+            }
+        }
+    }
+
+    fn disc(&mut self, center: (f64, f64), radius: f64, color: [u8; 3]) {
+        fill_circle(self, center, radius, color);
+    }
+
+    fn ring(&mut self, center: (f64, f64), radius: f64, width: f64, color: [u8; 3]) {
+        draw_circle(self, center, radius, width, color);
+    }
+}
+
+/// Whether `p` lies inside or on the triangle, by the sign of its side of each edge
+fn is_inside_triangle(p: (f64, f64), v1: (f64, f64), v2: (f64, f64), v3: (f64, f64)) -> bool {
+    let d1 = sign(p, v1, v2);
+    let d2 = sign(p, v2, v3);
+    let d3 = sign(p, v3, v1);
+
+    let has_neg = d1 < 0.0 || d2 < 0.0 || d3 < 0.0;
+    let has_pos = d1 > 0.0 || d2 > 0.0 || d3 > 0.0;
+
+    !(has_neg && has_pos)
+}
+
+fn sign(p1: (f64, f64), p2: (f64, f64), p3: (f64, f64)) -> f64 {
+    (p1.0 - p3.0) * (p2.1 - p3.1) - (p2.0 - p3.0) * (p1.1 - p3.1)
+}
@@ -0,0 +1,52 @@
+//! Anti-aliased circle rasterization. Edge pixels are blended by their approximate coverage,
+//! estimated from the distance between the pixel center and the circle.
+
+use super::draw::{blend_pixel, span};
+use image::RgbImage;
+
+/// Filled disc with an anti-aliased edge; center and radius in pixels
+pub fn fill_circle(img: &mut RgbImage, center: (f64, f64), radius: f64, color: [u8; 3]) {
+    if !(radius > 0.0 && center.0.is_finite() && center.1.is_finite()) {
+        return;
+    }
+
+    let extent = radius + 1.0;
+    for y in span(center.1, extent, img.height()) {
+        let dy = y as f64 + 0.5 - center.1;
+        for x in span(center.0, extent, img.width()) {
+            let dx = x as f64 + 0.5 - center.0;
+            // Coverage ramps from 1 half a pixel inside the edge to 0 half a pixel outside
+            let coverage = radius + 0.5 - (dx * dx + dy * dy).sqrt();
+            blend_pixel(img, x, y, color, coverage.clamp(0.0, 1.0));
+        }
+    }
+}
+
+/// Anti-aliased circle outline of the given stroke width; center and radius in pixels
+pub fn draw_circle(
+    img: &mut RgbImage,
+    center: (f64, f64),
+    radius: f64,
+    stroke_width: f64,
+    color: [u8; 3],
+) {
+    if !(radius > 0.0 && stroke_width > 0.0 && center.0.is_finite() && center.1.is_finite()) {
+        return;
+    }
+
+    let half_width = stroke_width / 2.0;
+    let extent = radius + half_width + 1.0;
+    let inner = (radius - half_width - 1.0).max(0.0);
+    for y in span(center.1, extent, img.height()) {
+        let dy = y as f64 + 0.5 - center.1;
+        for x in span(center.0, extent, img.width()) {
+            let dx = x as f64 + 0.5 - center.0;
+            let distance = (dx * dx + dy * dy).sqrt();
+            if distance < inner {
+                continue;
+            }
+            let coverage = half_width + 0.5 - (distance - radius).abs();
+            blend_pixel(img, x, y, color, coverage.clamp(0.0, 1.0));
+        }
+    }
+}
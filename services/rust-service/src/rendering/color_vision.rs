@@ -0,0 +1,75 @@
+//! Color vision deficiency simulation, so palettes can be checked for color-blind viewers.
+//! Uses the Machado et al. (2009) matrices at full severity, applied in linear RGB.
+
+use super::gamma::{decode, decode_srgb, linear_to_srgb, linear_to_srgb16};
+use super::Rgb16Image;
+use image::RgbImage;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ColorVisionDeficiency {
+    /// Missing long-wavelength (red) cones
+    Protanopia,
+    /// Missing medium-wavelength (green) cones
+    Deuteranopia,
+    /// Missing short-wavelength (blue) cones
+    Tritanopia,
+}
+
+impl ColorVisionDeficiency {
+    /// Names accepted by `parse`
+    pub const NAMES: [&'static str; 3] = ["protanopia", "deuteranopia", "tritanopia"];
+
+    pub fn parse(name: &str) -> Result<Self, String> {
+        match name.to_lowercase().as_str() {
+            "protanopia" => Ok(ColorVisionDeficiency::Protanopia),
+            "deuteranopia" => Ok(ColorVisionDeficiency::Deuteranopia),
+            "tritanopia" => Ok(ColorVisionDeficiency::Tritanopia),
+            _ => Err(format!(
+                "Invalid simulate. Must be one of: {}.",
+                Self::NAMES.join(", ")
+            )),
+        }
+    }
+
+    fn matrix(self) -> [[f64; 3]; 3] {
+        match self {
+            ColorVisionDeficiency::Protanopia => [
+                [0.152286, 1.052583, -0.204868],
+                [0.114503, 0.786281, 0.099216],
+                [-0.003882, -0.048116, 1.051998],
+            ],
+            ColorVisionDeficiency::Deuteranopia => [
+                [0.367322, 0.860646, -0.227968],
+                [0.280085, 0.672501, 0.047413],
+                [-0.011820, 0.042940, 0.968881],
+            ],
+            ColorVisionDeficiency::Tritanopia => [
+                [1.255528, -0.076749, -0.178779],
+                [-0.078411, 0.930809, 0.147602],
+                [0.004733, 0.691367, 0.303900],
+            ],
+        }
+    }
+}
+
+/// Recolor the image in place to approximate how it appears with the given deficiency
+pub fn simulate(img: &mut RgbImage, deficiency: ColorVisionDeficiency) {
+    let matrix = deficiency.matrix();
+    for pixel in img.pixels_mut() {
+        let linear = pixel.0.map(decode);
+        pixel.0 = matrix.map(|row| {
+            linear_to_srgb(row[0] * linear[0] + row[1] * linear[1] + row[2] * linear[2])
+        });
+    }
+}
+
+/// `simulate` for a 16-bit image
+pub fn simulate16(img: &mut Rgb16Image, deficiency: ColorVisionDeficiency) {
+    let matrix = deficiency.matrix();
+    for pixel in img.pixels_mut() {
+        let linear = pixel.0.map(|channel| decode_srgb(channel as f64 / 65535.0));
+        pixel.0 = matrix.map(|row| {
+            linear_to_srgb16(row[0] * linear[0] + row[1] * linear[1] + row[2] * linear[2])
+        });
+    }
+}
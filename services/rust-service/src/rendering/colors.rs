@@ -1,3 +1,12 @@
+use super::gamma::{decode, decode_srgb, linear_to_srgb, linear_to_srgb16};
+use super::oklab::BrandGradient;
+use super::Rgb16Image;
+use crate::fractals::traits::FractalParams;
+use crate::palettes;
+use crate::utils::validation::validate_palette_cycle;
+use image::{ImageBuffer, RgbImage};
+use rayon::prelude::*;
+
 #[derive(Clone)]
 pub enum ColorScheme {
     Default,
@@ -5,20 +14,301 @@ pub enum ColorScheme {
     Ice,
     Rainbow,
     Grayscale,
+    /// Perceptually uniform blue-green-yellow, readable with red-green color blindness
+    Viridis,
+    /// Blue-yellow variant of viridis optimized for deuteranopia and protanopia
+    Cividis,
+    /// Perceptually uniform black-purple-orange-cream
+    Magma,
+    /// Perceptually uniform blue-magenta-yellow
+    Plasma,
+    /// Perceptually uniform black-purple-orange-yellow
+    Inferno,
+    /// Rainbow with smooth lightness, for detail rather than exact values
+    Turbo,
+    /// Client-supplied stops, evenly spaced and linearly interpolated
+    Custom(Vec<[u8; 3]>),
+    /// Client-supplied brand colors joined by an evenly paced Oklab gradient
+    Brand(BrandGradient),
+    /// Another scheme's stops, evenly spaced and interpolated in Oklab (`interpolation=oklab`)
+    Oklab(BrandGradient),
 }
 
+/// Most stops in a custom palette
+pub const MAX_PALETTE_STOPS: usize = 32;
+
+/// Most colors in a brand palette
+pub const MAX_BRAND_COLORS: usize = 5;
+
+/// Marks a color_scheme value as brand colors, e.g. `brand:d52b1e,ffffff`
+pub const BRAND_PREFIX: &str = "brand:";
+
+/// Marks a color_scheme value as interpolated in Oklab, e.g. `oklab:viridis`
+pub const OKLAB_PREFIX: &str = "oklab:";
+
+/// Values accepted for `interpolation`: between palette stops in linear-light RGB or in Oklab
+pub const INTERPOLATIONS: [&str; 2] = ["rgb", "oklab"];
+
+// Palette stops sampled at equal steps from the matplotlib colormaps
+const VIRIDIS_STOPS: [[u8; 3]; 9] = [
+    [68, 1, 84],
+    [71, 45, 123],
+    [59, 82, 139],
+    [44, 114, 142],
+    [33, 145, 140],
+    [40, 174, 128],
+    [94, 201, 98],
+    [173, 220, 48],
+    [253, 231, 37],
+];
+
+const CIVIDIS_STOPS: [[u8; 3]; 9] = [
+    [0, 34, 78],
+    [18, 53, 112],
+    [59, 73, 108],
+    [87, 92, 109],
+    [112, 113, 115],
+    [138, 134, 120],
+    [165, 156, 116],
+    [195, 180, 102],
+    [254, 232, 56],
+];
+
+const MAGMA_STOPS: [[u8; 3]; 11] = [
+    [0, 0, 4],
+    [20, 14, 54],
+    [59, 15, 112],
+    [100, 26, 128],
+    [140, 41, 129],
+    [183, 55, 121],
+    [222, 73, 104],
+    [247, 112, 92],
+    [254, 159, 109],
+    [254, 207, 146],
+    [252, 253, 191],
+];
+
+const PLASMA_STOPS: [[u8; 3]; 11] = [
+    [13, 8, 135],
+    [65, 4, 157],
+    [106, 0, 168],
+    [143, 13, 164],
+    [177, 42, 144],
+    [204, 71, 120],
+    [225, 100, 98],
+    [242, 132, 75],
+    [252, 166, 54],
+    [252, 206, 37],
+    [240, 249, 33],
+];
+
+const INFERNO_STOPS: [[u8; 3]; 11] = [
+    [0, 0, 4],
+    [22, 11, 57],
+    [66, 10, 104],
+    [106, 23, 110],
+    [147, 38, 103],
+    [188, 55, 84],
+    [221, 81, 58],
+    [243, 120, 25],
+    [252, 165, 10],
+    [246, 215, 70],
+    [252, 255, 164],
+];
+
+// Turbo's inner stops from Google's polynomial fit, its ends from the lookup table
+const TURBO_STOPS: [[u8; 3]; 11] = [
+    [48, 18, 59],
+    [73, 88, 221],
+    [47, 158, 245],
+    [39, 215, 195],
+    [78, 249, 131],
+    [150, 250, 80],
+    [223, 220, 50],
+    [255, 163, 35],
+    [244, 92, 23],
+    [184, 32, 8],
+    [122, 4, 3],
+];
+
 impl ColorScheme {
+    /// Names accepted by `from_str`
+    pub const NAMES: [&'static str; 11] = [
+        "default",
+        "fire",
+        "ice",
+        "rainbow",
+        "grayscale",
+        "viridis",
+        "cividis",
+        "magma",
+        "plasma",
+        "inferno",
+        "turbo",
+    ];
+
+    /// A named scheme (built in or loaded from a palette file), or a custom palette when `s` is
+    /// a comma-separated list of stops (or brand colors)
     pub fn from_str(s: &str) -> Self {
+        if let Some(scheme) = s.strip_prefix(OKLAB_PREFIX) {
+            return Self::from_str(scheme).in_oklab();
+        }
+        if Self::is_palette(s) {
+            return Self::parse_palette(s).unwrap_or(ColorScheme::Default);
+        }
         match s.to_lowercase().as_str() {
             "fire" => ColorScheme::Fire,
             "ice" => ColorScheme::Ice,
             "rainbow" => ColorScheme::Rainbow,
             "grayscale" => ColorScheme::Grayscale,
-            _ => ColorScheme::Default,
+            "viridis" => ColorScheme::Viridis,
+            "cividis" => ColorScheme::Cividis,
+            "magma" => ColorScheme::Magma,
+            "plasma" => ColorScheme::Plasma,
+            "inferno" => ColorScheme::Inferno,
+            "turbo" => ColorScheme::Turbo,
+            _ => palettes::lookup(s)
+                .map(ColorScheme::Custom)
+                .unwrap_or(ColorScheme::Default),
+        }
+    }
+
+    /// Whether a color_scheme value is a list of hex stops or brand colors rather than a scheme
+    /// name. No name is made of hex digits alone, so a lone stop counts too and fails palette
+    /// validation.
+    pub fn is_palette(s: &str) -> bool {
+        let s = s.strip_prefix(OKLAB_PREFIX).unwrap_or(s);
+        s.starts_with(BRAND_PREFIX)
+            || s.contains(',')
+            || s.starts_with('#')
+            || (!s.is_empty() && s.chars().all(|c| c.is_ascii_hexdigit()))
+    }
+
+    /// Custom palette from hex stops such as `000764,206bcb,edffff,ffaa00` (a leading `#` on a
+    /// stop is allowed), or brand colors such as `brand:d52b1e,ffffff`
+    pub fn parse_palette(palette: &str) -> Result<Self, String> {
+        if let Some(palette) = palette.strip_prefix(OKLAB_PREFIX) {
+            return Self::parse_palette(palette).map(Self::in_oklab);
         }
+        if let Some(colors) = palette.strip_prefix(BRAND_PREFIX) {
+            return Self::parse_brand(colors);
+        }
+
+        let stops = palette
+            .split(',')
+            .map(|stop| {
+                parse_hex_color(stop).ok_or_else(|| {
+                    format!(
+                        "Invalid palette stop '{}'. Stops are 6-digit hex colors like 206bcb.",
+                        stop.trim()
+                    )
+                })
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+
+        if !(2..=MAX_PALETTE_STOPS).contains(&stops.len()) {
+            return Err(format!(
+                "Invalid palette. Must have between 2 and {} stops.",
+                MAX_PALETTE_STOPS
+            ));
+        }
+        Ok(ColorScheme::Custom(stops))
+    }
+
+    /// Brand palette from 2-5 hex colors such as `d52b1e,ffffff`
+    fn parse_brand(colors: &str) -> Result<Self, String> {
+        let colors = colors
+            .split(',')
+            .map(|color| {
+                parse_hex_color(color).ok_or_else(|| {
+                    format!(
+                        "Invalid brand color '{}'. Colors are 6-digit hex like d52b1e.",
+                        color.trim()
+                    )
+                })
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+
+        if !(2..=MAX_BRAND_COLORS).contains(&colors.len()) {
+            return Err(format!(
+                "Invalid brand_colors. Must have between 2 and {} colors.",
+                MAX_BRAND_COLORS
+            ));
+        }
+        Ok(ColorScheme::Brand(BrandGradient::new(&colors)))
+    }
+
+    /// The same stops interpolated in Oklab, where the midpoint between two saturated colors
+    /// keeps its chroma instead of passing through gray. Formula and brand schemes are
+    /// unchanged.
+    pub fn in_oklab(self) -> Self {
+        match self.stops() {
+            Some(stops) => ColorScheme::Oklab(BrandGradient::evenly_spaced(stops)),
+            None => self,
+        }
+    }
+
+    /// Evenly spaced stops of the gradient schemes; None for formula and Oklab schemes
+    fn stops(&self) -> Option<&[[u8; 3]]> {
+        match self {
+            ColorScheme::Viridis => Some(&VIRIDIS_STOPS),
+            ColorScheme::Cividis => Some(&CIVIDIS_STOPS),
+            ColorScheme::Magma => Some(&MAGMA_STOPS),
+            ColorScheme::Plasma => Some(&PLASMA_STOPS),
+            ColorScheme::Inferno => Some(&INFERNO_STOPS),
+            ColorScheme::Turbo => Some(&TURBO_STOPS),
+            ColorScheme::Custom(stops) => Some(stops),
+            _ => None,
+        }
+    }
+}
+
+/// Background drawn behind the geometric types unless background_color is given
+pub const DEFAULT_BACKGROUND: [u8; 3] = [255, 255, 255];
+
+/// Solid colors for the geometric types from `background_color`, `stroke_color` and
+/// `fill_color`. Strokes and fills without a color keep the type's own coloring, usually the
+/// scheme's gradient.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct GeometryColors {
+    pub background: [u8; 3],
+    /// Color of every line, for line-drawn types
+    pub stroke: Option<[u8; 3]>,
+    /// Color of every filled shape, for filled types
+    pub fill: Option<[u8; 3]>,
+}
+
+impl GeometryColors {
+    pub fn from_params(params: &FractalParams) -> Result<Self, String> {
+        let parse = |name: &str, color: Option<&str>| {
+            color
+                .map(|color| {
+                    parse_hex_color(color).ok_or_else(|| {
+                        format!("Invalid {}. Colors are 6-digit hex like d52b1e.", name)
+                    })
+                })
+                .transpose()
+        };
+        Ok(Self {
+            background: parse("background_color", params.background_color.as_deref())?
+                .unwrap_or(DEFAULT_BACKGROUND),
+            stroke: parse("stroke_color", params.stroke_color.as_deref())?,
+            fill: parse("fill_color", params.fill_color.as_deref())?,
+        })
     }
 }
 
+/// A 6-digit hex color, with or without a leading `#`
+fn parse_hex_color(color: &str) -> Option<[u8; 3]> {
+    let hex = color.trim();
+    let hex = hex.strip_prefix('#').unwrap_or(hex);
+    if hex.len() != 6 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+    Some([channel(0)?, channel(2)?, channel(4)?])
+}
+
 pub fn iterations_to_color(iterations: u32, max_iterations: u32, scheme: &ColorScheme) -> [u8; 3] {
     if iterations == max_iterations {
         // Inside the set - black
@@ -26,26 +316,465 @@ pub fn iterations_to_color(iterations: u32, max_iterations: u32, scheme: &ColorS
     }
 
     let normalized = iterations as f64 / max_iterations as f64;
+    normalized_to_color(normalized, scheme)
+}
+
+/// Pixels drawn as boundary by coloring=distance when boundary_width isn't given
+pub const DEFAULT_BOUNDARY_WIDTH: f64 = 1.0;
+
+/// How escape times are spread over the palette
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Coloring {
+    /// In proportion to max_iterations
+    #[default]
+    Linear,
+    /// By the share of escaping pixels that escape no later, so the palette is used evenly
+    /// however high max_iterations is
+    Histogram,
+    /// By how close the orbit comes to the orbit trap, inside the set as well as outside
+    OrbitTrap,
+    /// By the estimated distance to the set's boundary, which stays crisp at low max_iterations
+    Distance,
+    /// Linear, with the palette inverted where the orbit escaped below the real axis, which
+    /// splits each escape band into the cells of the external rays
+    BinaryDecomposition,
+    /// By the average over the orbit of a sine of its points' angles, which draws smooth
+    /// stripes across the escape bands
+    StripeAverage,
+    /// By the step at which the orbit came closest to 0, which outlines the atom domains
+    /// around each bulb, inside the set as well as outside
+    AtomDomain,
+    /// The set's interior by the period of the cycle its orbits settle into, one color per
+    /// bulb; escaping pixels are shaded gray by escape time
+    Period,
+}
+
+impl Coloring {
+    /// Names accepted by `parse`
+    pub const NAMES: [&'static str; 8] = [
+        "linear",
+        "histogram",
+        "orbit_trap",
+        "distance",
+        "binary_decomposition",
+        "stripe_average",
+        "atom_domain",
+        "period",
+    ];
+
+    pub fn parse(name: &str) -> Result<Self, String> {
+        match name.to_lowercase().as_str() {
+            "linear" => Ok(Coloring::Linear),
+            "histogram" => Ok(Coloring::Histogram),
+            "orbit_trap" => Ok(Coloring::OrbitTrap),
+            "distance" => Ok(Coloring::Distance),
+            "binary_decomposition" => Ok(Coloring::BinaryDecomposition),
+            "stripe_average" => Ok(Coloring::StripeAverage),
+            "atom_domain" => Ok(Coloring::AtomDomain),
+            "period" => Ok(Coloring::Period),
+            _ => Err(format!(
+                "Invalid coloring. Must be one of: {}.",
+                Self::NAMES.join(", ")
+            )),
+        }
+    }
+
+    /// The optional `coloring` request parameter, linear when absent
+    pub fn from_param(name: Option<&str>) -> Result<Self, String> {
+        Ok(name.map(Self::parse).transpose()?.unwrap_or_default())
+    }
+}
+
+/// How escape times are spaced before they are placed on the palette
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ColorSpacing {
+    /// In proportion to the iteration count
+    #[default]
+    Linear,
+    /// By the logarithm of the iteration count, which spreads the many late escapes near the
+    /// boundary of a deep zoom over more of the palette
+    Log,
+}
+
+/// Shift and repeat the palette across the escape range: positions are spaced, multiplied by
+/// `scale` (how many times the palette repeats), offset by `offset` and wrapped into [0, 1).
+/// Applies to the iteration-based colorings: linear, histogram and binary_decomposition.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PaletteCycle {
+    pub offset: f64,
+    pub scale: f64,
+    pub spacing: ColorSpacing,
+}
+
+impl Default for PaletteCycle {
+    fn default() -> Self {
+        Self {
+            offset: 0.0,
+            scale: 1.0,
+            spacing: ColorSpacing::Linear,
+        }
+    }
+}
+
+impl PaletteCycle {
+    /// From `color_offset`, `color_scale` and `color_spacing`; the palette once, unshifted,
+    /// when none are given
+    pub fn from_params(params: &FractalParams) -> Result<Self, String> {
+        let offset = params.color_offset.unwrap_or(0.0);
+        let scale = params.color_scale.unwrap_or(1.0);
+        validate_palette_cycle(offset, scale)?;
+        let spacing = match params.color_spacing.as_deref().map(str::to_lowercase) {
+            None => ColorSpacing::Linear,
+            Some(name) if name == "linear" => ColorSpacing::Linear,
+            Some(name) if name == "log" => ColorSpacing::Log,
+            Some(_) => return Err("Invalid color_spacing. Must be linear or log.".to_string()),
+        };
+        Ok(Self {
+            offset,
+            scale,
+            spacing,
+        })
+    }
+
+    /// Palette position for an escape after `iterations` of `max_iterations`
+    pub fn position(&self, iterations: f64, max_iterations: u32) -> f64 {
+        let spaced = match self.spacing {
+            ColorSpacing::Linear => iterations / max_iterations as f64,
+            ColorSpacing::Log => {
+                iterations.ln_1p() / (max_iterations as f64).ln_1p().max(f64::EPSILON)
+            }
+        };
+        self.wrap(spaced)
+    }
+
+    /// Scale and offset a position in [0, 1], wrapping around the palette's end
+    pub fn wrap(&self, position: f64) -> f64 {
+        if *self == Self::default() {
+            return position;
+        }
+        (self.offset + self.scale * position).rem_euclid(1.0)
+    }
+}
+
+/// First-pass result for one pixel of an escape-time render
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Escape {
+    /// Steps taken, where `max_iterations` means inside the set
+    pub iterations: u32,
+    /// Closest approach of the orbit to the orbit trap; infinite when no trap is set
+    pub trap_distance: f64,
+    /// Estimated distance to the set's boundary in boundary widths; infinite when not estimated
+    pub boundary_distance: f64,
+    /// The orbit's last point, past the bailout for escaping pixels; `None` when not recorded
+    pub final_z: Option<(f64, f64)>,
+    /// Average stripe term of the orbit, from 0 to 1; `None` when not tracked
+    pub stripe_average: Option<f64>,
+    /// Atom domain period for coloring=atom_domain, or the period of the attracting cycle an
+    /// interior orbit settled into for coloring=period; `None` when not measured or not found
+    pub period: Option<u32>,
+}
+
+impl Escape {
+    pub fn with_boundary_distance(iterations: u32, boundary_distance: f64) -> Self {
+        Self {
+            boundary_distance,
+            ..Self::from(iterations)
+        }
+    }
+
+    /// The same result with the period of the cycle the orbit settled into
+    pub fn with_period(self, period: Option<u32>) -> Self {
+        Self { period, ..self }
+    }
+
+    /// The same result with the orbit's last point, for binary decomposition
+    pub fn with_final_z(self, x: f64, y: f64) -> Self {
+        Self {
+            final_z: Some((x, y)),
+            ..self
+        }
+    }
 
+    /// Escape time smoothed with how far past the bailout the orbit landed, when known;
+    /// `None` inside the set
+    pub fn smooth_iterations(&self, max_iterations: u32) -> Option<f64> {
+        if self.iterations >= max_iterations {
+            return None;
+        }
+        let mut iterations = self.iterations as f64;
+        if let Some((x, y)) = self.final_z {
+            let modulus = (x * x + y * y).sqrt();
+            if modulus > 1.0 {
+                iterations += 1.0 - modulus.ln().ln() / std::f64::consts::LN_2;
+            }
+        }
+        Some(iterations)
+    }
+}
+
+impl From<u32> for Escape {
+    fn from(iterations: u32) -> Self {
+        Self {
+            iterations,
+            trap_distance: f64::INFINITY,
+            boundary_distance: f64::INFINITY,
+            final_z: None,
+            stripe_average: None,
+            period: None,
+        }
+    }
+}
+
+/// Precision of a rendered channel: u8 for ordinary images, u16 for `bit_depth=16` output
+pub trait Channel: image::Primitive + Send + Sync {
+    /// Image of this precision
+    type Image;
+
+    /// Image from pixels in row order
+    fn image(width: u32, height: u32, pixels: Vec<[Self; 3]>) -> Self::Image;
+
+    /// The scheme's color at `normalized`, as `normalized_to_color` gives it
+    fn gradient(normalized: f64, scheme: &ColorScheme) -> [Self; 3];
+
+    /// A channel given on the 0-255 scale
+    fn from_level(level: f64) -> Self;
+
+    /// The sRGB-encoded channel in linear light, for blending
+    fn to_linear(self) -> f64;
+
+    /// Linear light back to an sRGB-encoded channel
+    fn from_linear(value: f64) -> Self;
+}
+
+impl Channel for u8 {
+    type Image = RgbImage;
+
+    fn image(width: u32, height: u32, pixels: Vec<[u8; 3]>) -> RgbImage {
+        ImageBuffer::from_raw(width, height, pixels.into_flattened())
+            .expect("one pixel per position")
+    }
+
+    fn gradient(normalized: f64, scheme: &ColorScheme) -> [u8; 3] {
+        normalized_to_color(normalized, scheme)
+    }
+
+    fn from_level(level: f64) -> u8 {
+        level as u8
+    }
+
+    fn to_linear(self) -> f64 {
+        decode(self)
+    }
+
+    fn from_linear(value: f64) -> u8 {
+        linear_to_srgb(value)
+    }
+}
+
+impl Channel for u16 {
+    type Image = Rgb16Image;
+
+    fn image(width: u32, height: u32, pixels: Vec<[u16; 3]>) -> Rgb16Image {
+        ImageBuffer::from_raw(width, height, pixels.into_flattened())
+            .expect("one pixel per position")
+    }
+
+    fn gradient(normalized: f64, scheme: &ColorScheme) -> [u16; 3] {
+        normalized_to_color16(normalized, scheme)
+    }
+
+    fn from_level(level: f64) -> u16 {
+        widen_channel(level)
+    }
+
+    fn to_linear(self) -> f64 {
+        decode_srgb(self as f64 / 65535.0)
+    }
+
+    fn from_linear(value: f64) -> u16 {
+        linear_to_srgb16(value)
+    }
+}
+
+/// Second pass of an escape-time render: color a buffer of first-pass results, at 8 or 16 bits
+/// per channel
+pub fn color_escapes<C: Channel>(
+    escapes: &[Escape],
+    max_iterations: u32,
+    scheme: &ColorScheme,
+    coloring: Coloring,
+    cycle: &PaletteCycle,
+) -> Vec<[C; 3]> {
+    // The palette by iteration count, black inside the set
+    let escape_color = |iterations: u32| {
+        if iterations >= max_iterations {
+            return [C::DEFAULT_MIN_VALUE; 3];
+        }
+        C::gradient(cycle.position(iterations as f64, max_iterations), scheme)
+    };
+
+    match coloring {
+        Coloring::Linear => escapes
+            .par_iter()
+            .map(|escape| escape_color(escape.iterations))
+            .collect(),
+        Coloring::Histogram => {
+            // Pixels escaping after each iteration count, then the running share up to each
+            let mut histogram = vec![0u64; max_iterations as usize];
+            for escape in escapes {
+                if escape.iterations < max_iterations {
+                    histogram[escape.iterations as usize] += 1;
+                }
+            }
+            let escaped: u64 = histogram.iter().sum();
+            let mut cumulative = 0;
+            let shares: Vec<f64> = histogram
+                .iter()
+                .map(|&pixels| {
+                    cumulative += pixels;
+                    cumulative as f64 / escaped.max(1) as f64
+                })
+                .collect();
+
+            escapes
+                .par_iter()
+                .map(|escape| match shares.get(escape.iterations as usize) {
+                    Some(&share) => C::gradient(cycle.wrap(share), scheme),
+                    None => [C::DEFAULT_MIN_VALUE; 3],
+                })
+                .collect()
+        }
+        Coloring::OrbitTrap => {
+            // Closest approaches relative to the farthest in the image, the nearest at the
+            // palette's end; the square root gives the detail near the trap more of the palette
+            let farthest = escapes
+                .iter()
+                .map(|escape| escape.trap_distance)
+                .filter(|distance| distance.is_finite())
+                .fold(0.0, f64::max);
+
+            escapes
+                .par_iter()
+                .map(|escape| {
+                    let distance = escape.trap_distance;
+                    if !(distance.is_finite() && farthest > 0.0) {
+                        return C::gradient(0.0, scheme);
+                    }
+                    C::gradient(1.0 - (distance / farthest).sqrt(), scheme)
+                })
+                .collect()
+        }
+        Coloring::Distance => escapes
+            .par_iter()
+            .map(|escape| {
+                if escape.iterations >= max_iterations {
+                    return [C::DEFAULT_MIN_VALUE; 3];
+                }
+                // The palette's end within one boundary width, fading with the square of the
+                // distance beyond it
+                let distance = escape.boundary_distance.max(1.0);
+                C::gradient(1.0 / (distance * distance), scheme)
+            })
+            .collect(),
+        Coloring::BinaryDecomposition => escapes
+            .par_iter()
+            .map(|escape| {
+                let color = escape_color(escape.iterations);
+                // The sign of Im(z) at escape halves the external angle range; the lower half
+                // takes the inverted color
+                match escape.final_z {
+                    Some((_, y)) if escape.iterations < max_iterations && y < 0.0 => {
+                        color.map(|channel| C::DEFAULT_MAX_VALUE - channel)
+                    }
+                    _ => color,
+                }
+            })
+            .collect(),
+        Coloring::StripeAverage => escapes
+            .par_iter()
+            .map(|escape| match escape.stripe_average {
+                Some(average) if escape.iterations < max_iterations => C::gradient(average, scheme),
+                _ => [C::DEFAULT_MIN_VALUE; 3],
+            })
+            .collect(),
+        Coloring::AtomDomain => escapes
+            .par_iter()
+            .map(|escape| match escape.period {
+                Some(period) => period_to_color(period, scheme),
+                None => [C::DEFAULT_MIN_VALUE; 3],
+            })
+            .collect(),
+        Coloring::Period => escapes
+            .par_iter()
+            .map(|escape| {
+                if escape.iterations < max_iterations {
+                    let gray =
+                        C::from_level(escape.iterations as f64 / max_iterations as f64 * 96.0);
+                    return [gray, gray, gray];
+                }
+                match escape.period {
+                    Some(period) => period_to_color(period, scheme),
+                    None => [C::DEFAULT_MIN_VALUE; 3],
+                }
+            })
+            .collect(),
+    }
+}
+
+/// Spread consecutive periods far apart on the gradient (steps of the golden ratio), so
+/// neighbouring bulbs and domains get distinct colors whatever the palette
+pub fn period_to_color<C: Channel>(period: u32, scheme: &ColorScheme) -> [C; 3] {
+    const GOLDEN_RATIO_CONJUGATE: f64 = 0.618_033_988_749_895;
+    let position = (f64::from(period.saturating_sub(1)) * GOLDEN_RATIO_CONJUGATE).fract();
+    C::gradient(position, scheme)
+}
+
+/// Map a value in [0, 1] onto the color scheme's gradient
+pub fn normalized_to_color(normalized: f64, scheme: &ColorScheme) -> [u8; 3] {
+    match scheme {
+        ColorScheme::Brand(gradient) | ColorScheme::Oklab(gradient) => {
+            gradient.color_at(normalized)
+        }
+        _ => match scheme.stops() {
+            Some(stops) => interpolate_stops(stops, normalized),
+            None => formula_channels(normalized, scheme).map(|channel| channel as u8),
+        },
+    }
+}
+
+/// `normalized_to_color` at 16 bits per channel, with gradients interpolated before rounding
+/// instead of after
+pub fn normalized_to_color16(normalized: f64, scheme: &ColorScheme) -> [u16; 3] {
     match scheme {
-        ColorScheme::Default => {
-            let r = (normalized * 255.0) as u8;
-            let g = ((normalized * 2.0).sin() * 127.0 + 128.0) as u8;
-            let b = ((normalized * 3.0).sin() * 127.0 + 128.0) as u8;
-            [r, g, b]
-        }
-        ColorScheme::Fire => {
-            let r = (normalized * 255.0).min(255.0) as u8;
-            let g = (normalized * 200.0).min(255.0) as u8;
-            let b = (normalized * 50.0).min(255.0) as u8;
-            [r, g, b]
-        }
-        ColorScheme::Ice => {
-            let r = (normalized * 100.0).min(255.0) as u8;
-            let g = (normalized * 200.0).min(255.0) as u8;
-            let b = (normalized * 255.0).min(255.0) as u8;
-            [r, g, b]
+        ColorScheme::Brand(gradient) | ColorScheme::Oklab(gradient) => {
+            gradient.linear_at(normalized).map(linear_to_srgb16)
         }
+        _ => match scheme.stops() {
+            Some(stops) => interpolate_stops_linear(stops, normalized).map(linear_to_srgb16),
+            None => formula_channels(normalized, scheme).map(widen_channel),
+        },
+    }
+}
+
+/// Channels of the schemes defined by a formula rather than stops, on the 0-255 scale and
+/// unquantized
+fn formula_channels(normalized: f64, scheme: &ColorScheme) -> [f64; 3] {
+    match scheme {
+        ColorScheme::Default => [
+            normalized * 255.0,
+            (normalized * 2.0).sin() * 127.0 + 128.0,
+            (normalized * 3.0).sin() * 127.0 + 128.0,
+        ],
+        ColorScheme::Fire => [
+            (normalized * 255.0).min(255.0),
+            (normalized * 200.0).min(255.0),
+            (normalized * 50.0).min(255.0),
+        ],
+        ColorScheme::Ice => [
+            (normalized * 100.0).min(255.0),
+            (normalized * 200.0).min(255.0),
+            (normalized * 255.0).min(255.0),
+        ],
         ColorScheme::Rainbow => {
             let hue = normalized * 6.0;
             let sector = hue as u32 % 6;
@@ -64,11 +793,84 @@ pub fn iterations_to_color(iterations: u32, max_iterations: u32, scheme: &ColorS
                 _ => (1.0, p, q),
             };
 
-            [(r * 255.0) as u8, (g * 255.0) as u8, (b * 255.0) as u8]
-        }
-        ColorScheme::Grayscale => {
-            let gray = (normalized * 255.0) as u8;
-            [gray, gray, gray]
+            [r * 255.0, g * 255.0, b * 255.0]
         }
+        // Grayscale; the gradient schemes never get here
+        _ => [normalized * 255.0; 3],
+    }
+}
+
+/// Linearly interpolate between evenly spaced palette stops
+fn interpolate_stops(stops: &[[u8; 3]], normalized: f64) -> [u8; 3] {
+    let position = normalized.clamp(0.0, 1.0) * (stops.len() - 1) as f64;
+    let index = (position as usize).min(stops.len() - 2);
+    let fraction = position - index as f64;
+    mix(stops[index], stops[index + 1], fraction)
+}
+
+/// `interpolate_stops` in linear light, before encoding
+fn interpolate_stops_linear(stops: &[[u8; 3]], normalized: f64) -> [f64; 3] {
+    let position = normalized.clamp(0.0, 1.0) * (stops.len() - 1) as f64;
+    let index = (position as usize).min(stops.len() - 2);
+    let fraction = position - index as f64;
+    let (from, to) = (stops[index], stops[index + 1]);
+    [0, 1, 2].map(|channel| {
+        let (from, to) = (decode(from[channel]), decode(to[channel]));
+        from + (to - from) * fraction
+    })
+}
+
+/// A 0-255 channel value to 16 bits, keeping its fraction
+fn widen_channel(value: f64) -> u16 {
+    (value.clamp(0.0, 255.0) * 257.0).round() as u16
+}
+
+/// The color `fraction` (0 to 1) of the way from `from` to `to`, blended in linear light so
+/// midpoints don't darken. Mixing two palettes' colors for the same value interpolates between
+/// the palettes.
+pub fn mix(from: [u8; 3], to: [u8; 3], fraction: f64) -> [u8; 3] {
+    [0, 1, 2].map(|channel| {
+        let (from, to) = (decode(from[channel]), decode(to[channel]));
+        linear_to_srgb(from + (to - from) * fraction)
+    })
+}
+
+/// Map a signed exponent (e.g. Lyapunov) to color: stable regions (negative) use the scheme
+/// gradient, chaotic regions (positive) fade from dark blue to black.
+pub fn exponent_to_color(exponent: f64, scheme: &ColorScheme) -> [u8; 3] {
+    if exponent.is_nan() {
+        return [0, 0, 0];
+    }
+
+    if exponent <= 0.0 {
+        let stability = (-exponent / 2.0).min(1.0);
+        normalized_to_color(stability, scheme)
+    } else {
+        let chaos = exponent.min(1.0);
+        [0, 0, (160.0 * (1.0 - chaos)) as u8]
     }
 }
+
+/// Color a convergence-based fractal pixel by the root it reached.
+/// Each root gets its own hue from the scheme; faster convergence is brighter.
+pub fn root_to_color(
+    root_index: Option<usize>,
+    root_count: usize,
+    iterations: u32,
+    max_iterations: u32,
+    scheme: &ColorScheme,
+) -> [u8; 3] {
+    let Some(index) = root_index else {
+        // Did not converge - black
+        return [0, 0, 0];
+    };
+
+    let base = normalized_to_color((index as f64 + 0.5) / root_count.max(1) as f64, scheme);
+    let shade = 1.0 - 0.85 * (iterations as f64 / max_iterations as f64).min(1.0);
+
+    [
+        (base[0] as f64 * shade) as u8,
+        (base[1] as f64 * shade) as u8,
+        (base[2] as f64 * shade) as u8,
+    ]
+}
@@ -0,0 +1,114 @@
+//! Compositor for building one output image out of rendered ones: a canvas to place images
+//! on, margins around them, and frames, lines and text.
+
+use super::text::{draw_text, fit_text, text_height, text_width};
+use image::{ImageBuffer, Rgb, RgbImage};
+
+/// Axis-aligned rectangle in canvas pixels
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Rect {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl Rect {
+    pub const fn new(x: u32, y: u32, width: u32, height: u32) -> Self {
+        Self {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+}
+
+/// Space reserved around an image, in pixels
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Margins {
+    pub left: u32,
+    pub top: u32,
+    pub right: u32,
+    pub bottom: u32,
+}
+
+pub struct Canvas {
+    img: RgbImage,
+}
+
+impl Canvas {
+    pub fn new(width: u32, height: u32, background: [u8; 3]) -> Self {
+        Self {
+            img: ImageBuffer::from_pixel(width, height, Rgb(background)),
+        }
+    }
+
+    /// Canvas holding `img` inside the given margins; returns where the image was placed
+    pub fn with_margins(img: &RgbImage, margins: Margins, background: [u8; 3]) -> (Self, Rect) {
+        let rect = Rect::new(margins.left, margins.top, img.width(), img.height());
+        let mut canvas = Self::new(
+            margins.left + img.width() + margins.right,
+            margins.top + img.height() + margins.bottom,
+            background,
+        );
+        canvas.blit(img, rect.x, rect.y);
+        (canvas, rect)
+    }
+
+    pub fn height(&self) -> u32 {
+        self.img.height()
+    }
+
+    /// Copy `src` with its top-left corner at (x, y), clipping at the canvas edges
+    pub fn blit(&mut self, src: &RgbImage, x: u32, y: u32) {
+        let width = src.width().min(self.img.width().saturating_sub(x));
+        let height = src.height().min(self.img.height().saturating_sub(y));
+        for row in 0..height {
+            for column in 0..width {
+                self.img
+                    .put_pixel(x + column, y + row, *src.get_pixel(column, row));
+            }
+        }
+    }
+
+    pub fn fill_rect(&mut self, rect: Rect, color: [u8; 3]) {
+        let right = (rect.x + rect.width).min(self.img.width());
+        let bottom = (rect.y + rect.height).min(self.img.height());
+        for y in rect.y..bottom {
+            for x in rect.x..right {
+                self.img.put_pixel(x, y, Rgb(color));
+            }
+        }
+    }
+
+    /// Frame of the given thickness drawn just outside `rect`
+    pub fn frame_rect(&mut self, rect: Rect, thickness: u32, color: [u8; 3]) {
+        let x = rect.x.saturating_sub(thickness);
+        let y = rect.y.saturating_sub(thickness);
+        let width = rect.x + rect.width + thickness - x;
+        let height = rect.y + rect.height + thickness - y;
+
+        self.fill_rect(Rect::new(x, y, width, thickness), color);
+        self.fill_rect(Rect::new(x, rect.y + rect.height, width, thickness), color);
+        self.fill_rect(Rect::new(x, y, thickness, height), color);
+        self.fill_rect(Rect::new(rect.x + rect.width, y, thickness, height), color);
+    }
+
+    /// Draw `text` centered in `rect`, shortened to fit its width
+    pub fn caption(&mut self, rect: Rect, text: &str, scale: u32, color: [u8; 3]) {
+        let text = fit_text(text, scale, rect.width);
+        let x = rect.x + rect.width.saturating_sub(text_width(&text, scale)) / 2;
+        let y = rect.y + rect.height.saturating_sub(text_height(scale)) / 2;
+        draw_text(&mut self.img, x as i64, y as i64, &text, scale, color);
+    }
+
+    /// Draw `text` with its top-left corner at (x, y)
+    pub fn text(&mut self, x: u32, y: u32, text: &str, scale: u32, color: [u8; 3]) {
+        draw_text(&mut self.img, x as i64, y as i64, text, scale, color);
+    }
+
+    pub fn into_image(self) -> RgbImage {
+        self.img
+    }
+}
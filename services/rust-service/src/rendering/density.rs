@@ -0,0 +1,160 @@
+use crate::fractals::traits::FractalParams;
+use crate::rendering::colors::{normalized_to_color, ColorScheme};
+use crate::utils::validation::validate_exposure;
+use image::{ImageBuffer, Rgb, RgbImage};
+
+/// Share of white the average lit pixel lands at under the ACES curve, before exposure
+const ACES_KEY: f64 = 0.35;
+
+/// How the floating-point density field is compressed into [0, 1]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ToneCurve {
+    /// density / max; only the densest areas stand out
+    Linear,
+    /// Square root of density / max; keeps orbit densities (Buddhabrot) contrasty
+    Sqrt,
+    /// ln(1 + density) / ln(1 + max); lifts sparse point clouds (IFS)
+    Log,
+    /// Filmic curve (Narkowicz's ACES fit) keyed to the average lit pixel: a toe that keeps
+    /// faint areas dark and a shoulder that rolls the densest ones off instead of clipping
+    Aces,
+}
+
+impl ToneCurve {
+    /// Values accepted for `tone_map`
+    pub const NAMES: [&'static str; 4] = ["linear", "sqrt", "log", "aces"];
+
+    pub fn parse(name: &str) -> Result<Self, String> {
+        match name.to_lowercase().as_str() {
+            "linear" => Ok(ToneCurve::Linear),
+            "sqrt" => Ok(ToneCurve::Sqrt),
+            "log" => Ok(ToneCurve::Log),
+            "aces" => Ok(ToneCurve::Aces),
+            _ => Err(format!(
+                "Invalid tone_map. Must be one of: {}.",
+                Self::NAMES.join(", ")
+            )),
+        }
+    }
+}
+
+/// Tone-mapping stage between the density field and quantization, set with `tone_map` and
+/// `exposure`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ToneMap {
+    pub curve: ToneCurve,
+    /// Stops of brightening (negative darkens) applied to the field before the curve
+    pub exposure: f64,
+}
+
+impl ToneMap {
+    /// `default` is the curve the type uses unless tone_map is given
+    pub fn from_params(params: &FractalParams, default: ToneCurve) -> Result<Self, String> {
+        let curve = params
+            .tone_map
+            .as_deref()
+            .map(ToneCurve::parse)
+            .transpose()?
+            .unwrap_or(default);
+        let exposure = params.exposure.unwrap_or(0.0);
+        validate_exposure(exposure)?;
+        Ok(Self { curve, exposure })
+    }
+
+    /// Scale a density field to [0, 1]. Empty pixels stay at 0; at zero exposure the densest
+    /// pixel reaches 1 under every curve but ACES, which keys on the average instead.
+    pub fn apply(&self, field: &[f64]) -> Vec<f64> {
+        let gain = self.exposure.exp2();
+        let max = field
+            .iter()
+            .copied()
+            .fold(0.0, f64::max)
+            .max(f64::MIN_POSITIVE);
+        let (lit_sum, lit) = field
+            .iter()
+            .filter(|&&density| density > 0.0)
+            .fold((0.0, 0usize), |(sum, count), &density| {
+                (sum + density, count + 1)
+            });
+        let mean = if lit > 0 { lit_sum / lit as f64 } else { 1.0 };
+
+        field
+            .iter()
+            .map(|&density| {
+                if density <= 0.0 {
+                    return 0.0;
+                }
+                let value = match self.curve {
+                    ToneCurve::Linear => gain * density / max,
+                    ToneCurve::Sqrt => (gain * density / max).sqrt(),
+                    ToneCurve::Log => (1.0 + gain * density).ln() / (1.0 + max).ln(),
+                    ToneCurve::Aces => aces(gain * ACES_KEY * density / mean),
+                };
+                value.min(1.0)
+            })
+            .collect()
+    }
+}
+
+/// Narkowicz's fit of the ACES filmic curve
+fn aces(x: f64) -> f64 {
+    (x * (2.51 * x + 0.03)) / (x * (2.43 * x + 0.59) + 0.14)
+}
+
+/// Hit-count histogram for point-cloud fractals (Buddhabrot, IFS). Each worker thread
+/// fills its own buffer and the buffers are merged at the end.
+#[derive(Clone, Debug)]
+pub struct DensityBuffer {
+    width: u32,
+    height: u32,
+    counts: Vec<u32>,
+}
+
+impl DensityBuffer {
+    pub fn new(width: u32, height: u32) -> Self {
+        Self {
+            width,
+            height,
+            counts: vec![0; (width * height) as usize],
+        }
+    }
+
+    /// Record a hit at pixel (x, y); points outside the image are ignored
+    pub fn plot(&mut self, x: f64, y: f64) {
+        if x < 0.0 || y < 0.0 || x >= self.width as f64 || y >= self.height as f64 {
+            return;
+        }
+        let idx = y as usize * self.width as usize + x as usize;
+        self.counts[idx] = self.counts[idx].saturating_add(1);
+    }
+
+    pub fn merge(mut self, other: DensityBuffer) -> DensityBuffer {
+        for (count, extra) in self.counts.iter_mut().zip(other.counts) {
+            *count = count.saturating_add(extra);
+        }
+        self
+    }
+
+    /// Hit counts as a floating-point density field, unclipped, for the tone-mapping stage
+    pub fn field(&self) -> Vec<f64> {
+        self.counts.iter().map(|&count| count as f64).collect()
+    }
+
+    /// Hit counts tone-mapped to [0, 1] so faint detail stays visible
+    pub fn normalized(&self, tone: ToneMap) -> Vec<f64> {
+        tone.apply(&self.field())
+    }
+
+    /// Tone-map the histogram through the color scheme; empty pixels stay black
+    pub fn to_image(&self, tone: ToneMap, scheme: &ColorScheme) -> RgbImage {
+        let mut img: RgbImage = ImageBuffer::new(self.width, self.height);
+        for (pixel, value) in img.pixels_mut().zip(self.normalized(tone)) {
+            *pixel = if value == 0.0 {
+                Rgb([0, 0, 0])
+            } else {
+                Rgb(normalized_to_color(value, scheme))
+            };
+        }
+        img
+    }
+}
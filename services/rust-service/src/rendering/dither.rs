@@ -0,0 +1,191 @@
+//! Gray output for low-depth devices such as laser engravers and e-ink: the finished image is
+//! reduced to luminance, optionally histogram-equalized, and quantized to a few evenly spaced
+//! gray levels, with ordered (Bayer) or Floyd-Steinberg dithering. Dithering works in linear
+//! light, so an area of dots keeps the brightness of the gray it stands for.
+
+use super::gamma::{decode, linear_to_srgb};
+use crate::fractals::traits::FractalParams;
+use crate::utils::validation::validate_dither_levels;
+use image::{Rgb, RgbImage};
+
+/// Gray levels when dithering without dither_levels: black and white
+pub const DEFAULT_DITHER_LEVELS: u32 = 2;
+
+/// 8x8 Bayer matrix; entry / 64 is the threshold at that position
+const BAYER: [[u8; 8]; 8] = [
+    [0, 32, 8, 40, 2, 34, 10, 42],
+    [48, 16, 56, 24, 50, 18, 58, 26],
+    [12, 44, 4, 36, 14, 46, 6, 38],
+    [60, 28, 52, 20, 62, 30, 54, 22],
+    [3, 35, 11, 43, 1, 33, 9, 41],
+    [51, 19, 59, 27, 49, 17, 57, 25],
+    [15, 47, 7, 39, 13, 45, 5, 37],
+    [63, 31, 55, 23, 61, 29, 53, 21],
+];
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Dither {
+    /// Round each pixel to the nearest level
+    #[default]
+    None,
+    /// Threshold against a repeating 8x8 Bayer pattern: regular crosshatch, no drift
+    Ordered,
+    /// Diffuse each pixel's rounding error onto its unvisited neighbours: finer, organic grain
+    FloydSteinberg,
+}
+
+impl Dither {
+    /// Names accepted by `parse`
+    pub const NAMES: [&'static str; 3] = ["none", "ordered", "floyd_steinberg"];
+
+    pub fn parse(name: &str) -> Result<Self, String> {
+        match name.to_lowercase().as_str() {
+            "none" => Ok(Dither::None),
+            "ordered" => Ok(Dither::Ordered),
+            "floyd_steinberg" => Ok(Dither::FloydSteinberg),
+            _ => Err(format!(
+                "Invalid dither. Must be one of: {}.",
+                Self::NAMES.join(", ")
+            )),
+        }
+    }
+}
+
+/// Gray reduction requested with `dither`, `dither_levels` and `equalize`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct GrayOutput {
+    pub dither: Dither,
+    /// Evenly spaced gray levels in the output, 2-256
+    pub levels: u32,
+    /// Spread the grays so each level covers about the same share of pixels first
+    pub equalize: bool,
+}
+
+impl GrayOutput {
+    /// None unless one of the parameters is given
+    pub fn from_params(params: &FractalParams) -> Result<Option<Self>, String> {
+        let dither = params.dither.as_deref().map(Dither::parse).transpose()?;
+        if let Some(levels) = params.dither_levels {
+            validate_dither_levels(levels)?;
+        }
+        if dither.is_none() && params.dither_levels.is_none() && params.equalize.is_none() {
+            return Ok(None);
+        }
+
+        let dither = dither.unwrap_or_default();
+        // Equalizing alone keeps every gray; dithering alone defaults to black and white
+        let levels = params.dither_levels.unwrap_or(if dither == Dither::None {
+            256
+        } else {
+            DEFAULT_DITHER_LEVELS
+        });
+        Ok(Some(Self {
+            dither,
+            levels,
+            equalize: params.equalize.unwrap_or(false),
+        }))
+    }
+
+    pub fn apply(&self, img: &mut RgbImage) {
+        let (width, height) = (img.width() as usize, img.height() as usize);
+
+        // Rec. 709 luminance, back in sRGB steps so equalizing spreads what the eye sees
+        let mut grays: Vec<u8> = img
+            .pixels()
+            .map(|pixel| {
+                let [r, g, b] = pixel.0.map(decode);
+                linear_to_srgb(0.2126 * r + 0.7152 * g + 0.0722 * b)
+            })
+            .collect();
+        if self.equalize {
+            equalize(&mut grays);
+        }
+
+        // Output levels evenly spaced in sRGB, and where each sits in linear light
+        let steps = self.levels - 1;
+        let level_values: Vec<u8> = (0..self.levels)
+            .map(|level| (level as f64 * 255.0 / steps as f64).round() as u8)
+            .collect();
+        let level_linear: Vec<f64> = level_values.iter().map(|&value| decode(value)).collect();
+
+        // Levels just below and above a linear value, and how far it lies toward the upper
+        let bracket = |linear: f64| {
+            let linear = linear.clamp(0.0, 1.0);
+            let upper = level_linear
+                .iter()
+                .position(|&level| level >= linear)
+                .unwrap_or(level_linear.len() - 1)
+                .max(1);
+            let (low, high) = (level_linear[upper - 1], level_linear[upper]);
+            let fraction = if high > low {
+                (linear - low) / (high - low)
+            } else {
+                0.0
+            };
+            (upper - 1, upper, fraction)
+        };
+
+        let mut linear: Vec<f64> = grays.iter().map(|&gray| decode(gray)).collect();
+        let mut output = vec![0u8; linear.len()];
+        for y in 0..height {
+            for x in 0..width {
+                let index = y * width + x;
+                let (lower, upper, fraction) = bracket(linear[index]);
+                let threshold = match self.dither {
+                    Dither::Ordered => (BAYER[y % 8][x % 8] as f64 + 0.5) / 64.0,
+                    Dither::None | Dither::FloydSteinberg => 0.5,
+                };
+                let chosen = if fraction > threshold { upper } else { lower };
+                output[index] = level_values[chosen];
+
+                if self.dither == Dither::FloydSteinberg {
+                    let error = linear[index] - level_linear[chosen];
+                    let mut spread = |dx: isize, dy: usize, weight: f64| {
+                        let nx = x as isize + dx;
+                        if nx >= 0 && (nx as usize) < width && y + dy < height {
+                            linear[(y + dy) * width + nx as usize] += error * weight;
+                        }
+                    };
+                    spread(1, 0, 7.0 / 16.0);
+                    spread(-1, 1, 3.0 / 16.0);
+                    spread(0, 1, 5.0 / 16.0);
+                    spread(1, 1, 1.0 / 16.0);
+                }
+            }
+        }
+
+        for (pixel, gray) in img.pixels_mut().zip(output) {
+            *pixel = Rgb([gray; 3]);
+        }
+    }
+}
+
+/// Map each gray to its rank in the image, so the grays in use spread evenly from black to white
+fn equalize(grays: &mut [u8]) {
+    let mut histogram = [0usize; 256];
+    for &gray in grays.iter() {
+        histogram[gray as usize] += 1;
+    }
+
+    let mut cumulative = [0usize; 256];
+    let mut running = 0;
+    for (gray, count) in histogram.iter().enumerate() {
+        running += count;
+        cumulative[gray] = running;
+    }
+
+    // The darkest gray in use maps to black
+    let darkest = cumulative
+        .iter()
+        .copied()
+        .find(|&count| count > 0)
+        .unwrap_or(0);
+    let span = grays.len().saturating_sub(darkest);
+    if span == 0 {
+        return;
+    }
+    for gray in grays.iter_mut() {
+        let rank = cumulative[*gray as usize] - darkest;
+        *gray = (rank as f64 * 255.0 / span as f64).round() as u8;
+    }
+}
@@ -0,0 +1,185 @@
+//! Anti-aliased drawing primitives shared by the geometric fractals. Every pixel a shape
+//! partly covers is blended toward its color in linear light rather than set outright.
+
+use super::canvas::Canvas;
+use super::colors::{mix, normalized_to_color, ColorScheme};
+use image::{Rgb, RgbImage};
+use std::ops::Range;
+
+/// Line segment between two points: (start, end)
+pub type Segment = ((f64, f64), (f64, f64));
+
+/// Fraction of the image left as margin around fitted drawings
+const PADDING: f64 = 0.05;
+
+/// Blend `color` over the pixel at (x, y) with the given opacity, in linear light, ignoring
+/// out-of-bounds pixels
+pub fn blend_pixel(img: &mut RgbImage, x: i64, y: i64, color: [u8; 3], alpha: f64) {
+    if x < 0 || y < 0 || x >= img.width() as i64 || y >= img.height() as i64 || alpha <= 0.0 {
+        return;
+    }
+
+    let alpha = alpha.min(1.0);
+    let pixel = img.get_pixel_mut(x as u32, y as u32);
+    *pixel = Rgb(mix(pixel.0, color, alpha));
+}
+
+/// Pixel range covering [center - extent, center + extent], clipped to 0..limit
+pub fn span(center: f64, extent: f64, limit: u32) -> Range<i64> {
+    let start = (center - extent).floor().max(0.0) as i64;
+    let end = ((center + extent).ceil() as i64 + 1).min(limit as i64);
+    start..end.max(start)
+}
+
+/// Draw an anti-aliased line `width` pixels wide, clipping anything outside the image.
+/// Hairlines (width 1 or less) use Xiaolin Wu's algorithm; wider strokes are blended by
+/// their coverage of each pixel and get round caps, so polylines join without gaps.
+pub fn draw_line(
+    img: &mut RgbImage,
+    start: (f64, f64),
+    end: (f64, f64),
+    width: f64,
+    color: [u8; 3],
+) {
+    let finite = [start.0, start.1, end.0, end.1]
+        .iter()
+        .all(|v| v.is_finite());
+    if !finite || width <= 0.0 {
+        return;
+    }
+    if width <= 1.0 {
+        draw_hairline(img, start, end, color);
+    } else {
+        draw_stroke(img, start, end, width, color);
+    }
+}
+
+/// Xiaolin Wu's line: one pixel per step along the major axis, split between the two pixels
+/// the line passes between on the minor axis by how close it runs to each
+fn draw_hairline(img: &mut RgbImage, start: (f64, f64), end: (f64, f64), color: [u8; 3]) {
+    // Pixel centers at whole coordinates
+    let (mut x0, mut y0) = (start.0 - 0.5, start.1 - 0.5);
+    let (mut x1, mut y1) = (end.0 - 0.5, end.1 - 0.5);
+
+    // Step along x; steep lines are drawn transposed
+    let steep = (y1 - y0).abs() > (x1 - x0).abs();
+    if steep {
+        std::mem::swap(&mut x0, &mut y0);
+        std::mem::swap(&mut x1, &mut y1);
+    }
+    if x0 > x1 {
+        std::mem::swap(&mut x0, &mut x1);
+        std::mem::swap(&mut y0, &mut y1);
+    }
+    let limit = if steep { img.height() } else { img.width() } as i64;
+
+    let mut plot = |major: i64, minor: f64, alpha: f64| {
+        let row = minor.floor();
+        let below = minor - row;
+        let row = row as i64;
+        for (minor, alpha) in [(row, alpha * (1.0 - below)), (row + 1, alpha * below)] {
+            if steep {
+                blend_pixel(img, minor, major, color, alpha);
+            } else {
+                blend_pixel(img, major, minor, color, alpha);
+            }
+        }
+    };
+
+    let dx = x1 - x0;
+    let gradient = if dx == 0.0 { 1.0 } else { (y1 - y0) / dx };
+
+    // Endpoints are weighted by how much of their pixel the line reaches into
+    let first = x0.round();
+    let first_y = y0 + gradient * (first - x0);
+    let last = x1.round();
+    let last_y = y1 + gradient * (last - x1);
+    if first == last {
+        plot(first as i64, (first_y + last_y) / 2.0, x1 - x0);
+        return;
+    }
+    plot(first as i64, first_y, 0.5 - (x0 - first));
+    plot(last as i64, last_y, 0.5 + (x1 - last));
+
+    // Interior pixels, skipping any outside the image along the major axis
+    let from = (first as i64 + 1).max(0);
+    let to = (last as i64).min(limit);
+    for major in from..to {
+        plot(major, first_y + gradient * (major as f64 - first), 1.0);
+    }
+}
+
+/// A stroke `width` pixels wide with round caps, each pixel blended by how far its center
+/// lies inside the stroke's edge
+fn draw_stroke(img: &mut RgbImage, start: (f64, f64), end: (f64, f64), width: f64, color: [u8; 3]) {
+    let half_width = width / 2.0;
+    let extent = half_width + 1.0;
+    let (dx, dy) = (end.0 - start.0, end.1 - start.1);
+    let length_sqr = dx * dx + dy * dy;
+
+    let mid = ((start.0 + end.0) / 2.0, (start.1 + end.1) / 2.0);
+    let xs = span(mid.0, dx.abs() / 2.0 + extent, img.width());
+    let ys = span(mid.1, dy.abs() / 2.0 + extent, img.height());
+    for y in ys {
+        let py = y as f64 + 0.5;
+        for x in xs.clone() {
+            let px = x as f64 + 0.5;
+            // Closest point on the segment
+            let t = if length_sqr == 0.0 {
+                0.0
+            } else {
+                (((px - start.0) * dx + (py - start.1) * dy) / length_sqr).clamp(0.0, 1.0)
+            };
+            let (cx, cy) = (start.0 + t * dx, start.1 + t * dy);
+            let distance = ((px - cx).powi(2) + (py - cy).powi(2)).sqrt();
+            let coverage = half_width + 0.5 - distance;
+            blend_pixel(img, x, y, color, coverage.clamp(0.0, 1.0));
+        }
+    }
+}
+
+/// Scale segments (y axis up) to fit the canvas, preserving aspect ratio, and draw them
+/// `stroke_width` pixels wide in `stroke`, or with the scheme's gradient running along the segment
+/// order
+pub fn draw_fitted_segments(
+    canvas: &mut impl Canvas,
+    segments: &[Segment],
+    scheme: &ColorScheme,
+    stroke: Option<[u8; 3]>,
+    stroke_width: f64,
+) {
+    if segments.is_empty() {
+        return;
+    }
+
+    let (width, height) = canvas.size();
+    let (min_x, min_y, max_x, max_y) = segments.iter().fold(
+        (f64::MAX, f64::MAX, f64::MIN, f64::MIN),
+        |bounds, &(start, end)| {
+            (
+                bounds.0.min(start.0).min(end.0),
+                bounds.1.min(start.1).min(end.1),
+                bounds.2.max(start.0).max(end.0),
+                bounds.3.max(start.1).max(end.1),
+            )
+        },
+    );
+    let usable = 1.0 - 2.0 * PADDING;
+    let scale = (width as f64 * usable / (max_x - min_x).max(1e-9))
+        .min(height as f64 * usable / (max_y - min_y).max(1e-9));
+    let mid_x = (min_x + max_x) / 2.0;
+    let mid_y = (min_y + max_y) / 2.0;
+    let to_pixel = |(x, y): (f64, f64)| {
+        (
+            width as f64 / 2.0 + (x - mid_x) * scale,
+            height as f64 / 2.0 - (y - mid_y) * scale,
+        )
+    };
+
+    let count = segments.len();
+    for (index, &(start, end)) in segments.iter().enumerate() {
+        let color =
+            stroke.unwrap_or_else(|| normalized_to_color(index as f64 / count as f64, scheme));
+        canvas.line(to_pixel(start), to_pixel(end), stroke_width, color);
+    }
+}
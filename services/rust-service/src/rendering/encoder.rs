@@ -0,0 +1,178 @@
+//! One entry point for the raster formats a render is returned in. Each format's encoder
+//! keeps its own options; `EncodeOptions` carries all of them, so callers choose the format
+//! at the last moment without threading a different set of options for each.
+
+use super::avif_encoder::{self, encode_avif, AvifOptions};
+use super::jpeg_encoder::{encode_jpeg, JpegOptions};
+use super::png_encoder::{encode_png16_with, encode_png_with, PngOptions};
+use super::webp_encoder::{encode_webp, WebPOptions};
+use super::Rgb16Image;
+use crate::utils::http::append_headers;
+use axum::{
+    http::StatusCode,
+    response::{IntoResponse, Response},
+};
+use image::{EncodableLayout, ImageBuffer, ImageOutputFormat, PixelWithColorType, RgbImage};
+use std::io::Cursor;
+
+/// Raster format of an encoded image
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum OutputFormat {
+    #[default]
+    Png,
+    Jpeg,
+    WebP,
+    /// Only encoded in builds with the `avif` feature
+    Avif,
+    /// Uncompressed, for scientific and print tools that don't read PNG
+    Tiff,
+    /// Uncompressed, for the same tools where TIFF isn't read either
+    Bmp,
+}
+
+impl OutputFormat {
+    /// The raster format a `format` parameter names, PNG when it is absent; `None` for svg,
+    /// the text formats and unknown names, which aren't encoded from pixels here
+    pub fn from_name(format: Option<&str>) -> Option<Self> {
+        match format.map(str::to_lowercase).as_deref() {
+            None | Some("png") => Some(OutputFormat::Png),
+            Some("jpeg") | Some("jpg") => Some(OutputFormat::Jpeg),
+            Some("webp") => Some(OutputFormat::WebP),
+            Some("avif") => Some(OutputFormat::Avif),
+            Some("tiff") | Some("tif") => Some(OutputFormat::Tiff),
+            Some("bmp") => Some(OutputFormat::Bmp),
+            Some(_) => None,
+        }
+    }
+
+    pub fn content_type(self) -> &'static str {
+        match self {
+            OutputFormat::Png => "image/png",
+            OutputFormat::Jpeg => "image/jpeg",
+            OutputFormat::WebP => "image/webp",
+            OutputFormat::Avif => "image/avif",
+            OutputFormat::Tiff => "image/tiff",
+            OutputFormat::Bmp => "image/bmp",
+        }
+    }
+
+    /// Whether `encode_image16` can write the format at 16 bits per channel
+    pub fn has_16_bit(self) -> bool {
+        matches!(self, OutputFormat::Png | OutputFormat::Tiff)
+    }
+
+    /// Error for a format this build can't encode, checked before rendering
+    pub fn check_enabled(self) -> Result<(), String> {
+        match self {
+            OutputFormat::Avif if !avif_encoder::ENABLED => Err(avif_encoder::not_enabled()),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Options for every raster format; only those of the format being encoded are used
+#[derive(Clone, Copy, Debug, Default)]
+pub struct EncodeOptions {
+    pub png: PngOptions,
+    pub jpeg: JpegOptions,
+    pub webp: WebPOptions,
+    pub avif: AvifOptions,
+}
+
+impl EncodeOptions {
+    /// Build from the optional `compression` / `png_filter` / `quality` / `lossless` request
+    /// parameters. `quality` sets the JPEG, lossy WebP and AVIF quality alike.
+    pub fn from_params(
+        compression: Option<u32>,
+        png_filter: Option<&str>,
+        quality: Option<u8>,
+        lossless: Option<bool>,
+    ) -> Result<Self, String> {
+        let png = PngOptions::from_params(compression, png_filter)?;
+        if let Some(quality) = quality {
+            if !(1..=100).contains(&quality) {
+                return Err("Invalid quality. Must be between 1 and 100.".to_string());
+            }
+        }
+        let lossless = lossless.unwrap_or(false);
+        if lossless && quality.is_some() {
+            return Err("quality doesn't apply with lossless=true.".to_string());
+        }
+
+        let defaults = Self::default();
+        Ok(Self {
+            png,
+            jpeg: JpegOptions {
+                quality: quality.unwrap_or(defaults.jpeg.quality),
+            },
+            webp: WebPOptions {
+                lossless,
+                quality: quality.unwrap_or(defaults.webp.quality),
+            },
+            avif: AvifOptions {
+                quality: quality.unwrap_or(defaults.avif.quality),
+            },
+        })
+    }
+}
+
+pub fn encode_image(
+    img: &RgbImage,
+    format: OutputFormat,
+    options: &EncodeOptions,
+) -> Result<Vec<u8>, String> {
+    match format {
+        OutputFormat::Png => encode_png_with(img, &options.png),
+        OutputFormat::Jpeg => encode_jpeg(img, &options.jpeg),
+        OutputFormat::WebP => encode_webp(img, &options.webp),
+        OutputFormat::Avif => encode_avif(img, &options.avif),
+        OutputFormat::Tiff => encode_uncompressed(img, ImageOutputFormat::Tiff),
+        OutputFormat::Bmp => encode_uncompressed(img, ImageOutputFormat::Bmp),
+    }
+}
+
+/// 16 bits per channel, for `bit_depth=16`; only formats where `has_16_bit` holds
+pub fn encode_image16(
+    img: &Rgb16Image,
+    format: OutputFormat,
+    options: &EncodeOptions,
+) -> Result<Vec<u8>, String> {
+    match format {
+        OutputFormat::Png => encode_png16_with(img, &options.png),
+        OutputFormat::Tiff => encode_uncompressed(img, ImageOutputFormat::Tiff),
+        _ => Err("16 bits per channel is only available as PNG or TIFF.".to_string()),
+    }
+}
+
+/// TIFF and BMP through the image crate, whose encoders for them don't compress
+fn encode_uncompressed<P>(
+    img: &ImageBuffer<P, Vec<P::Subpixel>>,
+    format: ImageOutputFormat,
+) -> Result<Vec<u8>, String>
+where
+    P: PixelWithColorType,
+    [P::Subpixel]: EncodableLayout,
+{
+    let mut bytes = Cursor::new(Vec::new());
+    img.write_to(&mut bytes, format.clone())
+        .map_err(|e| format!("Failed to encode {:?}: {}", format, e))?;
+    Ok(bytes.into_inner())
+}
+
+pub fn create_image_response(
+    bytes: Vec<u8>,
+    format: OutputFormat,
+    extra_headers: &[(String, String)],
+) -> Response {
+    let builder = Response::builder()
+        .status(StatusCode::OK)
+        .header("Content-Type", format.content_type())
+        .header("Content-Length", bytes.len().to_string());
+
+    let mut response = builder
+        .body(axum::body::Body::from(bytes))
+        .unwrap()
+        .into_response();
+    append_headers(response.headers_mut(), extra_headers);
+    response
+}
@@ -0,0 +1,113 @@
+//! Encapsulated PostScript for `format=eps`, to place in LaTeX documents and print workflows.
+//! The bounding box is the drawing at one point per pixel, with the y axis flipped so shapes keep
+//! their pixel coordinates. Short procedures defined in the prolog keep the body compact.
+
+use super::vector::{color_operands, number, Shape};
+
+/// Points a stroked path is limited to before it is stroked and continued, since some
+/// interpreters cap path length. Round caps and joins hide the seam.
+const MAX_PATH_POINTS: usize = 1_000;
+
+const PROLOG: &str = "%%BeginProlog
+/M {moveto} bind def
+/L {lineto} bind def
+/S {stroke} bind def
+/F {closepath fill} bind def
+/C {setrgbcolor} bind def
+/W {setlinewidth} bind def
+/R {rectfill} bind def
+/D {newpath 0 360 arc closepath fill} bind def
+/O {newpath 0 360 arc closepath stroke} bind def
+%%EndProlog
+";
+
+/// The drawing as an EPS document
+pub fn write_eps(width: u32, height: u32, shapes: &[Shape]) -> String {
+    let mut eps = format!(
+        "%!PS-Adobe-3.0 EPSF-3.0
+%%BoundingBox: 0 0 {width} {height}
+%%HiResBoundingBox: 0 0 {width} {height}
+%%Creator: rust-service {}
+%%LanguageLevel: 2
+%%Pages: 1
+%%EndComments
+{PROLOG}%%Page: 1 1
+gsave
+0 {height} translate 1 -1 scale
+1 setlinecap 1 setlinejoin
+",
+        env!("CARGO_PKG_VERSION")
+    );
+    for shape in shapes {
+        draw(&mut eps, shape);
+    }
+    eps.push_str("grestore\nshowpage\n%%EOF\n");
+    eps
+}
+
+fn draw(eps: &mut String, shape: &Shape) {
+    match shape {
+        Shape::Stroke {
+            color,
+            width,
+            polylines,
+        } => {
+            eps.push_str(&format!(
+                "{} C {} W\n",
+                color_operands(*color),
+                number(*width)
+            ));
+            for polyline in polylines {
+                // Chunks share their end points, so the pieces join up
+                let mut start = 0;
+                while start + 1 < polyline.len() {
+                    let end = (start + MAX_PATH_POINTS).min(polyline.len());
+                    for (i, &(x, y)) in polyline[start..end].iter().enumerate() {
+                        let operator = if i == 0 { "M" } else { "L" };
+                        eps.push_str(&format!("{} {} {}\n", number(x), number(y), operator));
+                    }
+                    eps.push_str("S\n");
+                    start = end - 1;
+                }
+            }
+        }
+        Shape::Polygon { points, fill } => {
+            eps.push_str(&format!("{} C newpath\n", color_operands(*fill)));
+            for (i, &(x, y)) in points.iter().enumerate() {
+                let operator = if i == 0 { "M" } else { "L" };
+                eps.push_str(&format!("{} {} {}\n", number(x), number(y), operator));
+            }
+            eps.push_str("F\n");
+        }
+        Shape::Rect { origin, size, fill } => eps.push_str(&format!(
+            "{} C {} {} {} {} R\n",
+            color_operands(*fill),
+            number(origin.0),
+            number(origin.1),
+            number(size.0),
+            number(size.1)
+        )),
+        Shape::Circle {
+            center,
+            radius,
+            color,
+            stroke,
+        } => {
+            let circle = format!(
+                "{} {} {}",
+                number(center.0),
+                number(center.1),
+                number(*radius)
+            );
+            match stroke {
+                Some(width) => eps.push_str(&format!(
+                    "{} C {} W {} O\n",
+                    color_operands(*color),
+                    number(*width),
+                    circle
+                )),
+                None => eps.push_str(&format!("{} C {} D\n", color_operands(*color), circle)),
+            }
+        }
+    }
+}
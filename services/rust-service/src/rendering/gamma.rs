@@ -0,0 +1,71 @@
+//! Linear light. 8-bit colors are sRGB-encoded, so their values aren't proportional to light:
+//! averaging or interpolating them directly darkens blends and bands gradients. Blends are done
+//! on linear values instead and encoded back to sRGB at the end, or, with the `gamma` request
+//! parameter, to a plain power-law curve for displays and tools that expect one.
+
+use super::Rgb16Image;
+use image::RgbImage;
+use std::sync::OnceLock;
+
+static DECODE: OnceLock<[f64; 256]> = OnceLock::new();
+
+/// sRGB-encoded channel to linear light in [0, 1]
+pub fn srgb_to_linear(value: u8) -> f64 {
+    decode_srgb(value as f64 / 255.0)
+}
+
+/// sRGB-encoded value in [0, 1] to linear light, unquantized
+pub fn decode_srgb(v: f64) -> f64 {
+    if v <= 0.04045 {
+        v / 12.92
+    } else {
+        ((v + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+/// Linear light to an sRGB-encoded value in [0, 1], unquantized
+pub fn encode_srgb(value: f64) -> f64 {
+    let v = value.clamp(0.0, 1.0);
+    if v <= 0.0031308 {
+        v * 12.92
+    } else {
+        1.055 * v.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+/// Linear light to an sRGB-encoded channel
+pub fn linear_to_srgb(value: f64) -> u8 {
+    (encode_srgb(value) * 255.0).round() as u8
+}
+
+/// Linear light to a 16-bit sRGB-encoded channel
+pub fn linear_to_srgb16(value: f64) -> u16 {
+    (encode_srgb(value) * 65535.0).round() as u16
+}
+
+/// `srgb_to_linear` from a table, for per-pixel use
+#[inline]
+pub fn decode(value: u8) -> f64 {
+    DECODE.get_or_init(|| std::array::from_fn(|v| srgb_to_linear(v as u8)))[value as usize]
+}
+
+/// Re-encode an sRGB image for a display with a power-law response of `gamma`
+pub fn apply_output_gamma(img: &mut RgbImage, gamma: f64) {
+    let inverse = 1.0 / gamma;
+    let table: [u8; 256] =
+        std::array::from_fn(|v| (decode(v as u8).powf(inverse) * 255.0).round() as u8);
+    for pixel in img.pixels_mut() {
+        pixel.0 = pixel.0.map(|channel| table[channel as usize]);
+    }
+}
+
+/// `apply_output_gamma` for a 16-bit image
+pub fn apply_output_gamma16(img: &mut Rgb16Image, gamma: f64) {
+    let inverse = 1.0 / gamma;
+    let table: Vec<u16> = (0..=u16::MAX)
+        .map(|v| (decode_srgb(v as f64 / 65535.0).powf(inverse) * 65535.0).round() as u16)
+        .collect();
+    for pixel in img.pixels_mut() {
+        pixel.0 = pixel.0.map(|channel| table[channel as usize]);
+    }
+}
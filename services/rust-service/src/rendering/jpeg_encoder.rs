@@ -0,0 +1,30 @@
+//! JPEG encoding for `format=jpeg`: lossy, but a fraction of a PNG's size for the smooth
+//! gradients of escape-time renders, which suits images embedded in web pages.
+
+use image::codecs::jpeg::JpegEncoder;
+use image::{ColorType, RgbImage};
+
+/// Quality unless `quality` is given; artifacts are hard to spot in gradients above ~80
+pub const DEFAULT_JPEG_QUALITY: u8 = 85;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct JpegOptions {
+    /// 1 (smallest) to 100 (best)
+    pub quality: u8,
+}
+
+impl Default for JpegOptions {
+    fn default() -> Self {
+        Self {
+            quality: DEFAULT_JPEG_QUALITY,
+        }
+    }
+}
+
+pub fn encode_jpeg(img: &RgbImage, options: &JpegOptions) -> Result<Vec<u8>, String> {
+    let mut bytes = Vec::new();
+    JpegEncoder::new_with_quality(&mut bytes, options.quality)
+        .encode(img.as_raw(), img.width(), img.height(), ColorType::Rgb8)
+        .map_err(|e| format!("Failed to encode JPEG: {}", e))?;
+    Ok(bytes)
+}
@@ -1,3 +1,46 @@
+pub mod aesthetics;
+pub mod avif_encoder;
+pub mod canvas;
+pub mod circles;
+pub mod color_vision;
 pub mod colors;
+pub mod compositor;
+pub mod density;
+pub mod dither;
+pub mod draw;
+pub mod encoder;
+pub mod eps_writer;
+pub mod gamma;
+pub mod jpeg_encoder;
+pub mod oklab;
+pub mod orbit_trap;
+pub mod pdf_writer;
 pub mod png_encoder;
+pub mod raw_data;
+pub mod shading;
 pub mod svg_builder;
+pub mod text;
+pub mod text_art;
+pub mod vector;
+pub mod webm_encoder;
+pub mod webp_encoder;
+pub mod zip_writer;
+
+/// RGB image at 16 bits per channel, for `bit_depth=16` output
+pub type Rgb16Image = image::ImageBuffer<image::Rgb<u16>, Vec<u16>>;
+
+/// An 8-bit image at 16 bits per channel, each level scaled to the same fraction of full
+pub fn widen(img: &image::RgbImage) -> Rgb16Image {
+    let samples = img.as_raw().iter().map(|&channel| channel as u16 * 257).collect();
+    image::ImageBuffer::from_raw(img.width(), img.height(), samples).expect("same dimensions")
+}
+
+/// A 16-bit image rounded to 8 bits per channel
+pub fn narrow(img: &Rgb16Image) -> image::RgbImage {
+    let samples = img
+        .as_raw()
+        .iter()
+        .map(|&channel| ((channel as u32 + 128) / 257) as u8)
+        .collect();
+    image::ImageBuffer::from_raw(img.width(), img.height(), samples).expect("same dimensions")
+}
@@ -0,0 +1,123 @@
+//! Oklab (Björn Ottosson, 2020) gradients for brand palettes: a few fixed colors joined by a
+//! gradient that passes through each of them and changes at an even perceptual rate. Stops are
+//! interpolated in Oklab, where straight lines look like smooth blends, and placed along the
+//! gradient in proportion to the Oklab distance between neighbours. The same interpolation serves
+//! `interpolation=oklab` for the stops of any other gradient scheme, spaced evenly.
+
+use super::gamma::{decode, linear_to_srgb};
+
+pub fn srgb_to_oklab(color: [u8; 3]) -> [f64; 3] {
+    let [r, g, b] = color.map(decode);
+
+    let l = 0.4122214708 * r + 0.5363325363 * g + 0.0514459929 * b;
+    let m = 0.2119034982 * r + 0.6806995451 * g + 0.1073969566 * b;
+    let s = 0.0883024619 * r + 0.2817188376 * g + 0.6299787005 * b;
+    let [l, m, s] = [l, m, s].map(f64::cbrt);
+
+    [
+        0.2104542553 * l + 0.7936177850 * m - 0.0040720468 * s,
+        1.9779984951 * l - 2.4285922050 * m + 0.4505937099 * s,
+        0.0259040371 * l + 0.7827717662 * m - 0.8086757660 * s,
+    ]
+}
+
+/// Back to sRGB; colors outside the gamut are clipped per channel
+pub fn oklab_to_srgb(lab: [f64; 3]) -> [u8; 3] {
+    oklab_to_linear(lab).map(linear_to_srgb)
+}
+
+/// Back to linear-light RGB, unclipped
+pub fn oklab_to_linear(lab: [f64; 3]) -> [f64; 3] {
+    let [lightness, a, b] = lab;
+
+    let l = lightness + 0.3963377774 * a + 0.2158037573 * b;
+    let m = lightness - 0.1055613458 * a - 0.0638541728 * b;
+    let s = lightness - 0.0894841775 * a - 1.2914855480 * b;
+    let [l, m, s] = [l, m, s].map(|v| v * v * v);
+
+    [
+        4.0767416621 * l - 3.3077115913 * m + 0.2309699292 * s,
+        -1.2684380046 * l + 2.6097574011 * m - 0.3413193965 * s,
+        -0.0041960863 * l - 0.7034186147 * m + 1.7076147010 * s,
+    ]
+}
+
+/// A gradient through fixed colors, evenly paced in Oklab
+#[derive(Clone, Debug)]
+pub struct BrandGradient {
+    /// The colors in Oklab
+    stops: Vec<[f64; 3]>,
+    /// Where each color sits along the gradient, from 0 to 1
+    positions: Vec<f64>,
+}
+
+impl BrandGradient {
+    /// Gradient through `colors` in order; needs at least two
+    pub fn new(colors: &[[u8; 3]]) -> Self {
+        let stops: Vec<[f64; 3]> = colors.iter().copied().map(srgb_to_oklab).collect();
+
+        // Cumulative Oklab distance, so each stretch gets room in proportion to how far it goes
+        let mut travelled = vec![0.0];
+        for pair in stops.windows(2) {
+            let step = (0..3)
+                .map(|i| (pair[1][i] - pair[0][i]).powi(2))
+                .sum::<f64>()
+                .sqrt();
+            travelled.push(travelled[travelled.len() - 1] + step);
+        }
+        let total = travelled[travelled.len() - 1];
+
+        // Identical colors have no distance to share out; space them evenly
+        let last = (stops.len() - 1) as f64;
+        let positions = travelled
+            .iter()
+            .enumerate()
+            .map(|(i, &distance)| {
+                if total > 0.0 {
+                    distance / total
+                } else {
+                    i as f64 / last
+                }
+            })
+            .collect();
+
+        Self { stops, positions }
+    }
+
+    /// Gradient through `colors` at equal steps instead of paced by distance, for a scheme's
+    /// stops interpolated in Oklab; needs at least two
+    pub fn evenly_spaced(colors: &[[u8; 3]]) -> Self {
+        let last = (colors.len() - 1) as f64;
+        Self {
+            stops: colors.iter().copied().map(srgb_to_oklab).collect(),
+            positions: (0..colors.len()).map(|i| i as f64 / last).collect(),
+        }
+    }
+
+    /// The color at `t` (0 to 1) along the gradient
+    pub fn color_at(&self, t: f64) -> [u8; 3] {
+        oklab_to_srgb(self.oklab_at(t))
+    }
+
+    /// `color_at` in linear light, before quantizing
+    pub fn linear_at(&self, t: f64) -> [f64; 3] {
+        oklab_to_linear(self.oklab_at(t))
+    }
+
+    fn oklab_at(&self, t: f64) -> [f64; 3] {
+        let t = t.clamp(0.0, 1.0);
+        let segment = self.positions[1..]
+            .iter()
+            .position(|&end| t <= end)
+            .unwrap_or(self.positions.len() - 2);
+
+        let (start, end) = (self.positions[segment], self.positions[segment + 1]);
+        let fraction = if end > start {
+            (t - start) / (end - start)
+        } else {
+            0.0
+        };
+        let (from, to) = (self.stops[segment], self.stops[segment + 1]);
+        [0, 1, 2].map(|i| from[i] + (to[i] - from[i]) * fraction)
+    }
+}
@@ -0,0 +1,205 @@
+//! Orbit traps for `coloring=orbit_trap`: rather than by how fast its orbit escapes, each pixel
+//! is colored by how close its orbit comes to a shape in the plane (a point, a cross of two
+//! lines or a circle). Escape-time kernels track the closest approach while they iterate and hand
+//! it to the color stage alongside the iteration count.
+//!
+//! `coloring=stripe_average` rides on the same tracking: instead of a distance, each orbit point
+//! contributes 0.5 sin(stripe_density arg z) + 0.5, and the pixel is colored by the average.
+//! The average is blended between the last two steps by how far past the bailout the orbit
+//! landed, which softens the seams between escape bands.
+//!
+//! `coloring=atom_domain` tracks the closest approach to the origin like a point trap, but keeps
+//! the step it happened at: pixels sharing that step form the atom domain of a period-p bulb.
+
+use super::colors::{Coloring, Escape};
+use crate::fractals::traits::FractalParams;
+use crate::utils::validation::{validate_orbit_trap, validate_stripe_density};
+
+/// Stripes around the origin when stripe_density isn't given
+pub const DEFAULT_STRIPE_DENSITY: f64 = 5.0;
+
+/// Escape radius the stripe blend assumes, that of the quadratic kernels
+const ESCAPE_RADIUS: f64 = 2.0;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TrapShape {
+    /// Distance to (trap_x, trap_y)
+    #[default]
+    Point,
+    /// Distance to the nearer of the lines x = trap_x and y = trap_y
+    Cross,
+    /// Distance to the circle of trap_radius around (trap_x, trap_y)
+    Circle,
+}
+
+impl TrapShape {
+    /// Names accepted by `parse`
+    pub const NAMES: [&'static str; 3] = ["point", "cross", "circle"];
+
+    pub fn parse(name: &str) -> Result<Self, String> {
+        match name.to_lowercase().as_str() {
+            "point" => Ok(TrapShape::Point),
+            "cross" => Ok(TrapShape::Cross),
+            "circle" => Ok(TrapShape::Circle),
+            _ => Err(format!(
+                "Invalid trap_shape. Must be one of: {}.",
+                Self::NAMES.join(", ")
+            )),
+        }
+    }
+}
+
+/// What each orbit point is measured by
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum OrbitTrap {
+    /// coloring=orbit_trap: distance to a shape
+    Shape {
+        shape: TrapShape,
+        center: (f64, f64),
+        radius: f64,
+    },
+    /// coloring=stripe_average: the stripe term of the point's angle
+    Stripes { density: f64 },
+    /// coloring=atom_domain: distance to the origin, and the step of the closest approach
+    AtomDomain,
+}
+
+impl OrbitTrap {
+    /// The trap described by trap_shape / trap_x / trap_y / trap_radius, the stripes of
+    /// stripe_density or the atom domain; `None` unless the request asks for coloring=orbit_trap,
+    /// stripe_average or atom_domain
+    pub fn from_params(params: &FractalParams) -> Result<Option<Self>, String> {
+        match Coloring::from_param(params.coloring.as_deref())? {
+            Coloring::OrbitTrap => {}
+            Coloring::AtomDomain => return Ok(Some(OrbitTrap::AtomDomain)),
+            Coloring::StripeAverage => {
+                let density = params.stripe_density.unwrap_or(DEFAULT_STRIPE_DENSITY);
+                validate_stripe_density(density)?;
+                return Ok(Some(OrbitTrap::Stripes { density }));
+            }
+            _ => return Ok(None),
+        }
+
+        let shape = params
+            .trap_shape
+            .as_deref()
+            .map(TrapShape::parse)
+            .transpose()?
+            .unwrap_or_default();
+        let center = (params.trap_x.unwrap_or(0.0), params.trap_y.unwrap_or(0.0));
+        let radius = params.trap_radius.unwrap_or(1.0);
+        validate_orbit_trap(center.0, center.1, radius)?;
+
+        Ok(Some(OrbitTrap::Shape {
+            shape,
+            center,
+            radius,
+        }))
+    }
+}
+
+/// Distance from (x, y) to a trap shape
+#[inline]
+fn shape_distance(shape: TrapShape, center: (f64, f64), radius: f64, x: f64, y: f64) -> f64 {
+    let dx = x - center.0;
+    let dy = y - center.1;
+    match shape {
+        TrapShape::Point => dx.hypot(dy),
+        TrapShape::Cross => dx.abs().min(dy.abs()),
+        TrapShape::Circle => (dx.hypot(dy) - radius).abs(),
+    }
+}
+
+/// Closest approach of one orbit to the trap so far, or its stripe terms; does nothing without
+/// a trap
+pub struct TrapTracker<'a> {
+    trap: Option<&'a OrbitTrap>,
+    closest: f64,
+    /// Orbit points seen
+    visits: u32,
+    /// Step of the closest approach, counting the first orbit point as 1
+    closest_step: u32,
+    stripe_sum: f64,
+    last_stripe: f64,
+    last_point: (f64, f64),
+}
+
+impl<'a> TrapTracker<'a> {
+    pub fn new(trap: Option<&'a OrbitTrap>) -> Self {
+        Self {
+            trap,
+            closest: f64::INFINITY,
+            visits: 0,
+            closest_step: 0,
+            stripe_sum: 0.0,
+            last_stripe: 0.0,
+            last_point: (0.0, 0.0),
+        }
+    }
+
+    /// Measure one orbit point. Non-finite points are ignored, since `min` skips NaN.
+    #[inline]
+    pub fn visit(&mut self, x: f64, y: f64) {
+        match self.trap {
+            Some(&OrbitTrap::Shape {
+                shape,
+                center,
+                radius,
+            }) => {
+                self.closest = self
+                    .closest
+                    .min(shape_distance(shape, center, radius, x, y));
+            }
+            Some(&OrbitTrap::Stripes { density }) => {
+                self.last_stripe = 0.5 * (density * y.atan2(x)).sin() + 0.5;
+                self.stripe_sum += self.last_stripe;
+                self.visits += 1;
+                self.last_point = (x, y);
+            }
+            Some(OrbitTrap::AtomDomain) => {
+                self.visits += 1;
+                let distance = x.hypot(y);
+                if distance < self.closest {
+                    self.closest = distance;
+                    self.closest_step = self.visits;
+                }
+            }
+            None => {}
+        }
+    }
+
+    /// The pixel's first-pass result once its orbit stopped after `iterations` steps
+    pub fn escape(&self, iterations: u32) -> Escape {
+        Escape {
+            trap_distance: self.closest,
+            stripe_average: self.stripe_average(),
+            period: matches!(self.trap, Some(OrbitTrap::AtomDomain))
+                .then_some(self.closest_step)
+                .filter(|&step| step > 0),
+            ..Escape::from(iterations)
+        }
+    }
+
+    /// Average stripe term, between the averages with and without the last point by how far
+    /// past the escape radius that point lies
+    fn stripe_average(&self) -> Option<f64> {
+        if !matches!(self.trap, Some(OrbitTrap::Stripes { .. })) {
+            return None;
+        }
+        let terms = (self.visits > 0).then_some(self.visits as f64)?;
+        let average = self.stripe_sum / terms;
+        if terms < 2.0 {
+            return Some(average);
+        }
+        let previous = (self.stripe_sum - self.last_stripe) / (terms - 1.0);
+
+        let radius = self.last_point.0.hypot(self.last_point.1);
+        let fraction = 1.0 + (ESCAPE_RADIUS.ln() / radius.ln()).log2();
+        let fraction = if fraction.is_finite() {
+            fraction.clamp(0.0, 1.0)
+        } else {
+            1.0
+        };
+        Some(fraction * average + (1.0 - fraction) * previous)
+    }
+}
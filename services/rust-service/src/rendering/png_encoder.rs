@@ -1,28 +1,642 @@
-use axum::{
-    http::StatusCode,
-    response::{IntoResponse, Response},
-};
+//! PNG encoding. Scanline filtering and deflate both run in parallel: the filtered image is
+//! split into chunks that are compressed independently and joined with sync flushes into a
+//! single zlib stream, trading a little size (no shared dictionary across chunks) for latency.
+
+use super::Rgb16Image;
+use flate2::read::ZlibDecoder;
+use flate2::{Compress, Compression, Crc, FlushCompress, Status};
 use image::RgbImage;
+use rayon::prelude::*;
+use std::io::Read;
+
+const PNG_SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1A, b'\n'];
+
+/// RGB samples per pixel
+const CHANNELS: usize = 3;
+
+/// Filtered bytes per deflate chunk; smaller chunks parallelize better but compress worse
+const DEFLATE_CHUNK_SIZE: usize = 256 * 1024;
+
+/// Largest IDAT chunk written
+const MAX_IDAT_SIZE: usize = 1 << 20;
+
+/// Length of the signature plus the IHDR chunk, which must come first
+const HEADER_END: usize = 8 + 12 + 13;
+
+/// Most bytes a compressed text chunk may inflate to when read
+const MAX_TEXT_SIZE: u64 = 1 << 20;
+
+/// PNG scanline filter applied before compression
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PngFilter {
+    None,
+    Sub,
+    Up,
+    Average,
+    Paeth,
+    /// Pick the filter with the smallest output per row (best size, slowest)
+    Adaptive,
+}
+
+impl PngFilter {
+    /// Names accepted by `parse`
+    pub const NAMES: [&'static str; 6] = ["none", "sub", "up", "average", "paeth", "adaptive"];
+
+    pub fn parse(name: &str) -> Result<Self, String> {
+        match name.to_lowercase().as_str() {
+            "none" => Ok(PngFilter::None),
+            "sub" => Ok(PngFilter::Sub),
+            "up" => Ok(PngFilter::Up),
+            "average" => Ok(PngFilter::Average),
+            "paeth" => Ok(PngFilter::Paeth),
+            "adaptive" => Ok(PngFilter::Adaptive),
+            _ => Err(format!(
+                "Invalid png_filter. Must be one of: {}.",
+                Self::NAMES.join(", ")
+            )),
+        }
+    }
+
+    /// Filter type byte written at the start of each scanline
+    fn type_byte(self) -> u8 {
+        match self {
+            PngFilter::None => 0,
+            PngFilter::Sub => 1,
+            PngFilter::Up => 2,
+            PngFilter::Average => 3,
+            PngFilter::Paeth => 4,
+            PngFilter::Adaptive => unreachable!("adaptive picks a concrete filter per row"),
+        }
+    }
+}
+
+/// Per-request trade-off between PNG size and encoding latency
+#[derive(Clone, Copy, Debug)]
+pub struct PngOptions {
+    /// zlib level, 0 (store) to 9 (smallest)
+    pub compression: u32,
+    pub filter: PngFilter,
+}
+
+impl Default for PngOptions {
+    fn default() -> Self {
+        Self {
+            compression: 6,
+            filter: PngFilter::Adaptive,
+        }
+    }
+}
+
+impl PngOptions {
+    /// Build from the optional `compression` / `png_filter` request parameters
+    pub fn from_params(compression: Option<u32>, filter: Option<&str>) -> Result<Self, String> {
+        let defaults = Self::default();
+        let compression = compression.unwrap_or(defaults.compression);
+        if compression > 9 {
+            return Err("Invalid compression. Must be between 0 and 9.".to_string());
+        }
+        let filter = filter.map(PngFilter::parse).transpose()?;
+
+        Ok(Self {
+            compression,
+            filter: filter.unwrap_or(defaults.filter),
+        })
+    }
+}
+
+pub fn encode_png_with(img: &RgbImage, options: &PngOptions) -> Result<Vec<u8>, String> {
+    let (width, height) = img.dimensions();
+    encode_samples(width, height, img.as_raw(), 8, options)
+}
 
-pub fn encode_png(img: RgbImage) -> Result<Vec<u8>, String> {
-    let mut png_bytes: Vec<u8> = Vec::new();
+/// 16-bit truecolor PNG, for `bit_depth=16`
+pub fn encode_png16_with(img: &Rgb16Image, options: &PngOptions) -> Result<Vec<u8>, String> {
     let (width, height) = img.dimensions();
-    let encoder = image::codecs::png::PngEncoder::new(&mut png_bytes);
-    let raw_pixels = img.into_raw();
+    // PNG stores 16-bit samples most significant byte first
+    let samples: Vec<u8> = img
+        .as_raw()
+        .par_iter()
+        .flat_map_iter(|sample| sample.to_be_bytes())
+        .collect();
+    encode_samples(width, height, &samples, 16, options)
+}
+
+/// PNG from packed RGB samples of `bit_depth` bits, as bytes in scanline order
+fn encode_samples(
+    width: u32,
+    height: u32,
+    samples: &[u8],
+    bit_depth: u8,
+    options: &PngOptions,
+) -> Result<Vec<u8>, String> {
+    let compressed = compress_samples(width, height, samples, bit_depth, options)?;
+
+    let mut png = Vec::with_capacity(compressed.len() + 64);
+    png.extend_from_slice(&PNG_SIGNATURE);
+    write_chunk(&mut png, b"IHDR", &header(width, height, bit_depth));
+
+    for data in compressed.chunks(MAX_IDAT_SIZE) {
+        write_chunk(&mut png, b"IDAT", data);
+    }
+    write_chunk(&mut png, b"IEND", &[]);
+
+    Ok(png)
+}
+
+/// Filtered, deflated scanlines: the zlib stream an image's IDAT chunks carry
+fn compress_samples(
+    width: u32,
+    height: u32,
+    samples: &[u8],
+    bit_depth: u8,
+    options: &PngOptions,
+) -> Result<Vec<u8>, String> {
+    let bytes_per_pixel = CHANNELS * bit_depth as usize / 8;
+    let filtered = filter_scanlines(samples, width, height, bytes_per_pixel, options.filter);
+    deflate_parallel(&filtered, options.compression)
+}
+
+/// IHDR data: truecolor, default compression/filter methods, no interlace
+fn header(width: u32, height: u32, bit_depth: u8) -> Vec<u8> {
+    let mut header = Vec::with_capacity(13);
+    header.extend_from_slice(&width.to_be_bytes());
+    header.extend_from_slice(&height.to_be_bytes());
+    header.extend_from_slice(&[bit_depth, 2, 0, 0, 0]);
+    header
+}
+
+/// Image data of one APNG frame. Frames compress independently, so they can be encoded in
+/// parallel and handed to `ApngWriter` in order.
+pub fn encode_apng_frame(img: &RgbImage, options: &PngOptions) -> Result<Vec<u8>, String> {
+    let (width, height) = img.dimensions();
+    compress_samples(width, height, img.as_raw(), 8, options)
+}
+
+/// Animated PNG assembled from frames encoded by `encode_apng_frame`, all of the canvas size.
+/// Viewers without APNG support show the first frame.
+pub struct ApngWriter {
+    png: Vec<u8>,
+    width: u32,
+    height: u32,
+    frames: u32,
+    written: u32,
+    /// Seconds each frame is shown, as numerator and denominator
+    delay: (u16, u16),
+    /// Sequence number of the next fcTL or fdAT chunk
+    sequence: u32,
+}
+
+impl ApngWriter {
+    pub fn new(width: u32, height: u32, frames: u32, delay: (u16, u16)) -> Self {
+        let mut png = Vec::new();
+        png.extend_from_slice(&PNG_SIGNATURE);
+        write_chunk(&mut png, b"IHDR", &header(width, height, 8));
+
+        // Frame count, then plays: 0 loops forever
+        let mut control = Vec::with_capacity(8);
+        control.extend_from_slice(&frames.to_be_bytes());
+        control.extend_from_slice(&0u32.to_be_bytes());
+        write_chunk(&mut png, b"acTL", &control);
+
+        Self {
+            png,
+            width,
+            height,
+            frames,
+            written: 0,
+            delay,
+            sequence: 0,
+        }
+    }
+
+    pub fn push(&mut self, frame: &[u8]) -> Result<(), String> {
+        if self.written == self.frames {
+            return Err(format!("APNG already has its {} frames", self.frames));
+        }
+
+        // Whole canvas, replaced rather than blended: dispose none, blend source
+        let mut control = Vec::with_capacity(26);
+        control.extend_from_slice(&self.next_sequence().to_be_bytes());
+        control.extend_from_slice(&self.width.to_be_bytes());
+        control.extend_from_slice(&self.height.to_be_bytes());
+        control.extend_from_slice(&[0; 8]);
+        control.extend_from_slice(&self.delay.0.to_be_bytes());
+        control.extend_from_slice(&self.delay.1.to_be_bytes());
+        control.extend_from_slice(&[0, 0]);
+        write_chunk(&mut self.png, b"fcTL", &control);
+
+        // The first frame doubles as the default image; the rest carry sequence numbers
+        for data in frame.chunks(MAX_IDAT_SIZE) {
+            if self.written == 0 {
+                write_chunk(&mut self.png, b"IDAT", data);
+            } else {
+                let mut chunk = Vec::with_capacity(data.len() + 4);
+                chunk.extend_from_slice(&self.next_sequence().to_be_bytes());
+                chunk.extend_from_slice(data);
+                write_chunk(&mut self.png, b"fdAT", &chunk);
+            }
+        }
+        self.written += 1;
+        Ok(())
+    }
+
+    pub fn finish(mut self) -> Result<Vec<u8>, String> {
+        if self.written != self.frames {
+            return Err(format!(
+                "APNG has {} of its {} frames",
+                self.written, self.frames
+            ));
+        }
+        write_chunk(&mut self.png, b"IEND", &[]);
+        Ok(self.png)
+    }
+
+    fn next_sequence(&mut self) -> u32 {
+        self.sequence += 1;
+        self.sequence - 1
+    }
+}
+
+/// The PNG with a text chunk per (keyword, text) entry, right after IHDR so readers that stop
+/// early still see them. ASCII text goes in tEXt; anything else in iTXt, which is UTF-8.
+pub fn insert_text(png: &[u8], entries: &[(&str, &str)]) -> Result<Vec<u8>, String> {
+    if png.len() < HEADER_END || png[..8] != PNG_SIGNATURE || &png[12..16] != b"IHDR" {
+        return Err("Not a PNG with a leading IHDR chunk".to_string());
+    }
+
+    let mut out =
+        Vec::with_capacity(png.len() + entries.iter().map(|e| e.1.len() + 64).sum::<usize>());
+    out.extend_from_slice(&png[..HEADER_END]);
+    for (keyword, text) in entries {
+        if keyword.is_empty()
+            || keyword.len() > 79
+            || !keyword.bytes().all(|b| (32..=126).contains(&b))
+        {
+            return Err(format!("Invalid PNG text keyword: {:?}", keyword));
+        }
+        let mut data = Vec::with_capacity(keyword.len() + text.len() + 5);
+        data.extend_from_slice(keyword.as_bytes());
+        data.push(0);
+        if text.is_ascii() {
+            data.extend_from_slice(text.as_bytes());
+            write_chunk(&mut out, b"tEXt", &data);
+        } else {
+            // Uncompressed, with empty language tag and translated keyword
+            data.extend_from_slice(&[0, 0, 0, 0]);
+            data.extend_from_slice(text.as_bytes());
+            write_chunk(&mut out, b"iTXt", &data);
+        }
+    }
+    out.extend_from_slice(&png[HEADER_END..]);
+    Ok(out)
+}
+
+/// (keyword, text) of every tEXt, zTXt and iTXt chunk, in file order
+pub fn read_text(png: &[u8]) -> Result<Vec<(String, String)>, String> {
+    if png.len() < 8 || png[..8] != PNG_SIGNATURE {
+        return Err("Not a PNG file".to_string());
+    }
+
+    let mut entries = Vec::new();
+    let mut offset = 8;
+    while offset + 8 <= png.len() {
+        let length = u32::from_be_bytes(png[offset..offset + 4].try_into().unwrap()) as usize;
+        let kind = &png[offset + 4..offset + 8];
+        let data = png
+            .get(offset + 8..offset + 8 + length)
+            .ok_or("PNG is truncated")?;
+        match kind {
+            b"tEXt" => {
+                let (keyword, text) = split_keyword(data)?;
+                // Latin-1: every byte is the code point of the same value
+                entries.push((keyword, text.iter().map(|&b| b as char).collect()));
+            }
+            b"zTXt" => {
+                let (keyword, rest) = split_keyword(data)?;
+                let text = inflate(rest.get(1..).ok_or("zTXt chunk is truncated")?)?;
+                entries.push((keyword, text.iter().map(|&b| b as char).collect()));
+            }
+            b"iTXt" => {
+                let (keyword, rest) = split_keyword(data)?;
+                let (&compressed, rest) = rest.split_first().ok_or("iTXt chunk is truncated")?;
+                // Skip the compression method, then the language tag and translated keyword
+                let mut fields = rest
+                    .get(1..)
+                    .ok_or("iTXt chunk is truncated")?
+                    .splitn(3, |&b| b == 0);
+                let text = match (fields.next(), fields.next(), fields.next()) {
+                    (Some(_), Some(_), Some(text)) => text,
+                    _ => return Err("iTXt chunk is truncated".to_string()),
+                };
+                let text = if compressed == 1 {
+                    inflate(text)?
+                } else {
+                    text.to_vec()
+                };
+                let text = String::from_utf8(text).map_err(|_| "iTXt text isn't UTF-8")?;
+                entries.push((keyword, text));
+            }
+            b"IEND" => break,
+            _ => {}
+        }
+        offset += 12 + length;
+    }
+    Ok(entries)
+}
+
+/// Keyword up to the first null byte, and the bytes after it
+fn split_keyword(data: &[u8]) -> Result<(String, &[u8]), String> {
+    let end = data
+        .iter()
+        .position(|&b| b == 0)
+        .ok_or("Text chunk has no keyword")?;
+    let keyword = data[..end].iter().map(|&b| b as char).collect();
+    Ok((keyword, &data[end + 1..]))
+}
+
+fn inflate(data: &[u8]) -> Result<Vec<u8>, String> {
+    let mut text = Vec::new();
+    ZlibDecoder::new(data)
+        .take(MAX_TEXT_SIZE)
+        .read_to_end(&mut text)
+        .map_err(|e| format!("Invalid compressed text chunk: {}", e))?;
+    Ok(text)
+}
+
+fn write_chunk(png: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    png.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    png.extend_from_slice(kind);
+    png.extend_from_slice(data);
+
+    let mut crc = Crc::new();
+    crc.update(kind);
+    crc.update(data);
+    png.extend_from_slice(&crc.sum().to_be_bytes());
+}
+
+/// Filter every scanline (in parallel), each prefixed by its filter type byte
+fn filter_scanlines(
+    raw: &[u8],
+    width: u32,
+    height: u32,
+    bytes_per_pixel: usize,
+    filter: PngFilter,
+) -> Vec<u8> {
+    let stride = width as usize * bytes_per_pixel;
+    if stride == 0 {
+        return vec![0; height as usize];
+    }
+
+    let mut filtered = vec![0u8; (stride + 1) * height as usize];
+    filtered
+        .par_chunks_mut(stride + 1)
+        .enumerate()
+        .for_each(|(row, out)| {
+            let current = &raw[row * stride..(row + 1) * stride];
+            let previous = (row > 0).then(|| &raw[(row - 1) * stride..row * stride]);
+
+            match filter {
+                PngFilter::Adaptive => {
+                    // Minimum sum of absolute differences heuristic from the PNG spec
+                    let mut candidate = vec![0u8; stride];
+                    let mut best_score = u64::MAX;
+                    for option in [
+                        PngFilter::None,
+                        PngFilter::Sub,
+                        PngFilter::Up,
+                        PngFilter::Average,
+                        PngFilter::Paeth,
+                    ] {
+                        apply_filter(option, current, previous, bytes_per_pixel, &mut candidate);
+                        let score: u64 = candidate
+                            .iter()
+                            .map(|&byte| (byte as i8).unsigned_abs() as u64)
+                            .sum();
+                        if score < best_score {
+                            best_score = score;
+                            out[0] = option.type_byte();
+                            out[1..].copy_from_slice(&candidate);
+                        }
+                    }
+                }
+                _ => {
+                    out[0] = filter.type_byte();
+                    apply_filter(filter, current, previous, bytes_per_pixel, &mut out[1..]);
+                }
+            }
+        });
+
+    filtered
+}
+
+/// Filters predict each byte from the same byte of the pixel to the left (or above), so
+/// `bytes_per_pixel` is the distance back to it
+fn apply_filter(
+    filter: PngFilter,
+    current: &[u8],
+    previous: Option<&[u8]>,
+    bytes_per_pixel: usize,
+    out: &mut [u8],
+) {
+    for i in 0..current.len() {
+        let left = if i >= bytes_per_pixel {
+            current[i - bytes_per_pixel]
+        } else {
+            0
+        };
+        let up = previous.map_or(0, |row| row[i]);
+        let up_left = match previous {
+            Some(row) if i >= bytes_per_pixel => row[i - bytes_per_pixel],
+            _ => 0,
+        };
+
+        let predictor = match filter {
+            PngFilter::None | PngFilter::Adaptive => 0,
+            PngFilter::Sub => left,
+            PngFilter::Up => up,
+            PngFilter::Average => ((left as u16 + up as u16) / 2) as u8,
+            PngFilter::Paeth => paeth(left, up, up_left),
+        };
+        out[i] = current[i].wrapping_sub(predictor);
+    }
+}
+
+fn paeth(left: u8, up: u8, up_left: u8) -> u8 {
+    let estimate = left as i16 + up as i16 - up_left as i16;
+    let distance_left = (estimate - left as i16).abs();
+    let distance_up = (estimate - up as i16).abs();
+    let distance_up_left = (estimate - up_left as i16).abs();
 
-    encoder
-        .encode(&raw_pixels, width, height, image::ColorType::Rgb8)
-        .map_err(|e| format!("Failed to encode image: {}", e))?;
+    if distance_left <= distance_up && distance_left <= distance_up_left {
+        left
+    } else if distance_up <= distance_up_left {
+        up
+    } else {
+        up_left
+    }
+}
+
+/// zlib stream built from independently deflated chunks
+fn deflate_parallel(data: &[u8], level: u32) -> Result<Vec<u8>, String> {
+    let chunks: Vec<&[u8]> = data.chunks(DEFLATE_CHUNK_SIZE).collect();
+    let last = chunks.len().saturating_sub(1);
+
+    let compressed = chunks
+        .par_iter()
+        .enumerate()
+        .map(|(index, chunk)| {
+            // Sync flush ends each chunk on a byte boundary; only the last block is final
+            let flush = if index == last {
+                FlushCompress::Finish
+            } else {
+                FlushCompress::Sync
+            };
+            deflate_raw(chunk, level, flush)
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+
+    let mut stream = Vec::with_capacity(compressed.iter().map(Vec::len).sum::<usize>() + 6);
+    stream.extend_from_slice(&zlib_header(level));
+    for chunk in &compressed {
+        stream.extend_from_slice(chunk);
+    }
+    if compressed.is_empty() {
+        stream.extend(deflate_raw(&[], level, FlushCompress::Finish)?);
+    }
+    stream.extend_from_slice(&adler32(data).to_be_bytes());
+
+    Ok(stream)
+}
 
-    Ok(png_bytes)
+fn deflate_raw(data: &[u8], level: u32, flush: FlushCompress) -> Result<Vec<u8>, String> {
+    let mut compressor = Compress::new(Compression::new(level), false);
+    let mut output = Vec::with_capacity(data.len() / 2 + 64);
+
+    loop {
+        let consumed = compressor.total_in() as usize;
+        let status = compressor
+            .compress_vec(&data[consumed..], &mut output, flush)
+            .map_err(|e| format!("Failed to encode image: {}", e))?;
+
+        let done = match flush {
+            FlushCompress::Finish => status == Status::StreamEnd,
+            _ => compressor.total_in() as usize == data.len() && output.len() < output.capacity(),
+        };
+        if done {
+            return Ok(output);
+        }
+        output.reserve(output.capacity().max(64));
+    }
+}
+
+fn zlib_header(level: u32) -> [u8; 2] {
+    // CMF: deflate with a 32K window; FLG: level hint, padded so the header is divisible by 31
+    let cmf = 0x78u8;
+    let level_hint = match level {
+        0..=1 => 0u8,
+        2..=5 => 1,
+        6 => 2,
+        _ => 3,
+    };
+    let flg_base = level_hint << 6;
+    let check = 31 - ((cmf as u16 * 256 + flg_base as u16) % 31);
+    [cmf, flg_base + (check % 31) as u8]
 }
 
-pub fn create_png_response(png_bytes: Vec<u8>) -> Response {
-    Response::builder()
-        .status(StatusCode::OK)
-        .header("Content-Type", "image/png")
-        .header("Content-Length", png_bytes.len().to_string())
-        .body(axum::body::Body::from(png_bytes))
-        .unwrap()
-        .into_response()
+fn adler32(data: &[u8]) -> u32 {
+    const MODULUS: u32 = 65_521;
+    // Largest block that can't overflow the u32 sums before reducing
+    const BLOCK: usize = 5552;
+
+    let (mut a, mut b) = (1u32, 0u32);
+    for block in data.chunks(BLOCK) {
+        for &byte in block {
+            a += byte as u32;
+            b += a;
+        }
+        a %= MODULUS;
+        b %= MODULUS;
+    }
+    (b << 16) | a
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{DynamicImage, Rgb};
+
+    /// Gradients with some noise, so every filter has something to predict
+    fn test_image(width: u32, height: u32) -> RgbImage {
+        RgbImage::from_fn(width, height, |x, y| {
+            let noise = (x.wrapping_mul(2_654_435_761) ^ y.wrapping_mul(40_503)) >> 7;
+            Rgb([
+                (x * 255 / width.max(1)) as u8,
+                (y * 255 / height.max(1)) as u8,
+                noise as u8,
+            ])
+        })
+    }
+
+    fn decode(png: &[u8]) -> DynamicImage {
+        image::load_from_memory(png).expect("encoder output decodes")
+    }
+
+    /// 301 pixels wide is 904 filtered bytes per row, so 611 rows span three deflate chunks
+    /// with the last one partial
+    const MULTI_CHUNK: (u32, u32) = (301, 611);
+
+    #[test]
+    fn round_trips_every_compression_level() {
+        let img = test_image(MULTI_CHUNK.0, MULTI_CHUNK.1);
+        assert!(
+            (img.width() as usize * CHANNELS + 1) * img.height() as usize > 2 * DEFLATE_CHUNK_SIZE
+        );
+
+        for compression in 0..=9 {
+            let options = PngOptions {
+                compression,
+                filter: PngFilter::Paeth,
+            };
+            let png = encode_png_with(&img, &options).unwrap();
+            assert_eq!(decode(&png).to_rgb8(), img, "compression={}", compression);
+        }
+    }
+
+    #[test]
+    fn round_trips_every_filter() {
+        let img = test_image(MULTI_CHUNK.0, MULTI_CHUNK.1);
+
+        for name in PngFilter::NAMES {
+            let options = PngOptions {
+                compression: 6,
+                filter: PngFilter::parse(name).unwrap(),
+            };
+            let png = encode_png_with(&img, &options).unwrap();
+            assert_eq!(decode(&png).to_rgb8(), img, "png_filter={}", name);
+        }
+    }
+
+    #[test]
+    fn round_trips_small_and_odd_sizes() {
+        for (width, height) in [(1, 1), (1, 7), (7, 1), (3, 290), (3, 291), (97, 13)] {
+            let img = test_image(width, height);
+            let png = encode_png_with(&img, &PngOptions::default()).unwrap();
+            assert_eq!(decode(&png).to_rgb8(), img, "{}x{}", width, height);
+        }
+    }
+
+    #[test]
+    fn round_trips_16_bit_samples() {
+        let img = Rgb16Image::from_fn(MULTI_CHUNK.0, MULTI_CHUNK.1 / 2, |x, y| {
+            Rgb([(x * 211) as u16, (y * 89) as u16, (x * y) as u16])
+        });
+        let png = encode_png16_with(&img, &PngOptions::default()).unwrap();
+        assert_eq!(decode(&png).to_rgb16(), img);
+    }
+
+    #[test]
+    fn adler32_matches_the_reference_value() {
+        assert_eq!(adler32(b"Wikipedia"), 0x11E6_0398);
+        assert_eq!(adler32(&[]), 1);
+    }
 }
@@ -0,0 +1,195 @@
+//! Uncolored escape-time data for analysis and re-coloring offline. `raw` is the bare buffer:
+//! one little-endian sample per pixel, row-major from the top-left. `npy` puts NumPy's header in
+//! front of the same samples, so `numpy.load` returns a height x width array without being told
+//! the shape or type.
+
+use super::colors::Escape;
+use crate::utils::http::append_headers;
+use axum::{
+    http::StatusCode,
+    response::{IntoResponse, Response},
+};
+
+/// NumPy's magic string and the format version written, 1.0
+const NPY_MAGIC: &[u8] = b"\x93NUMPY\x01\x00";
+
+/// The header, with its length field, pads the data to start on this boundary
+const NPY_ALIGNMENT: usize = 64;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RawFormat {
+    Raw,
+    Npy,
+}
+
+impl RawFormat {
+    /// The format a `format` parameter names; `None` for images, text and unknown names
+    pub fn from_name(format: Option<&str>) -> Option<Self> {
+        match format.map(str::to_lowercase).as_deref() {
+            Some("raw") => Some(RawFormat::Raw),
+            Some("npy") => Some(RawFormat::Npy),
+            _ => None,
+        }
+    }
+
+    fn extension(self) -> &'static str {
+        match self {
+            RawFormat::Raw => "bin",
+            RawFormat::Npy => "npy",
+        }
+    }
+}
+
+/// Which value each sample holds
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum RawData {
+    /// Steps taken as u32, where max_iterations means inside the set
+    #[default]
+    Iterations,
+    /// Steps smoothed by how far past the bailout the orbit landed, as f32; NaN inside the set.
+    /// Types that don't record the orbit's last point give whole steps.
+    Smooth,
+}
+
+impl RawData {
+    /// Names accepted by `parse`
+    pub const NAMES: [&'static str; 2] = ["iterations", "smooth"];
+
+    pub fn parse(name: &str) -> Result<Self, String> {
+        match name.to_lowercase().as_str() {
+            "iterations" => Ok(RawData::Iterations),
+            "smooth" => Ok(RawData::Smooth),
+            _ => Err(format!(
+                "Invalid data. Must be one of: {}.",
+                Self::NAMES.join(", ")
+            )),
+        }
+    }
+
+    /// NumPy's type string for the samples
+    fn descr(self) -> &'static str {
+        match self {
+            RawData::Iterations => "<u4",
+            RawData::Smooth => "<f4",
+        }
+    }
+
+    /// Name of the sample type in the `X-Data-Type` header
+    fn type_name(self) -> &'static str {
+        match self {
+            RawData::Iterations => "uint32",
+            RawData::Smooth => "float32",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RawOptions {
+    pub format: RawFormat,
+    pub data: RawData,
+}
+
+impl RawOptions {
+    /// Build from the optional `format` / `data` request parameters; `None` when the response
+    /// isn't raw data
+    pub fn from_params(format: Option<&str>, data: Option<&str>) -> Result<Option<Self>, String> {
+        let data = data.map(RawData::parse).transpose()?;
+        match RawFormat::from_name(format) {
+            Some(format) => Ok(Some(Self {
+                format,
+                data: data.unwrap_or_default(),
+            })),
+            None if data.is_some() => {
+                Err("data only applies with format=raw or format=npy.".to_string())
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Whether the render should record each orbit's last point for smoothing
+    pub fn needs_final_z(&self) -> bool {
+        self.data == RawData::Smooth
+    }
+}
+
+/// The escapes of a `width` x `height` render as the response body
+pub fn encode_raw(
+    escapes: &[Escape],
+    width: u32,
+    height: u32,
+    max_iterations: u32,
+    options: &RawOptions,
+) -> Vec<u8> {
+    let mut bytes = match options.format {
+        RawFormat::Raw => Vec::with_capacity(escapes.len() * 4),
+        RawFormat::Npy => npy_header(width, height, options.data),
+    };
+    for escape in escapes {
+        match options.data {
+            RawData::Iterations => bytes.extend_from_slice(&escape.iterations.to_le_bytes()),
+            RawData::Smooth => {
+                let value = escape
+                    .smooth_iterations(max_iterations)
+                    .map_or(f32::NAN, |iterations| iterations as f32);
+                bytes.extend_from_slice(&value.to_le_bytes());
+            }
+        }
+    }
+    bytes
+}
+
+/// Magic, version, header length and the header dictionary, padded with spaces and a newline
+/// so the samples start aligned
+fn npy_header(width: u32, height: u32, data: RawData) -> Vec<u8> {
+    let mut header = format!(
+        "{{'descr': '{}', 'fortran_order': False, 'shape': ({}, {}), }}",
+        data.descr(),
+        height,
+        width
+    );
+    let unpadded = NPY_MAGIC.len() + 2 + header.len() + 1;
+    let padding = (NPY_ALIGNMENT - unpadded % NPY_ALIGNMENT) % NPY_ALIGNMENT;
+    header.push_str(&" ".repeat(padding));
+    header.push('\n');
+
+    let mut bytes = Vec::with_capacity(unpadded + padding + (width * height * 4) as usize);
+    bytes.extend_from_slice(NPY_MAGIC);
+    bytes.extend_from_slice(&(header.len() as u16).to_le_bytes());
+    bytes.extend_from_slice(header.as_bytes());
+    bytes
+}
+
+/// Binary response, with the shape and sample type in headers for `format=raw`, which has none
+pub fn create_raw_response(
+    bytes: Vec<u8>,
+    fractal_type: &str,
+    width: u32,
+    height: u32,
+    max_iterations: u32,
+    options: &RawOptions,
+    extra_headers: &[(String, String)],
+) -> Response {
+    let builder = Response::builder()
+        .status(StatusCode::OK)
+        .header("Content-Type", "application/octet-stream")
+        .header("Content-Length", bytes.len().to_string())
+        .header(
+            "Content-Disposition",
+            format!(
+                "attachment; filename=\"{}.{}\"",
+                fractal_type,
+                options.format.extension()
+            ),
+        )
+        .header("X-Data-Width", width.to_string())
+        .header("X-Data-Height", height.to_string())
+        .header("X-Data-Type", options.data.type_name())
+        .header("X-Max-Iterations", max_iterations.to_string());
+
+    let mut response = builder
+        .body(axum::body::Body::from(bytes))
+        .unwrap()
+        .into_response();
+    append_headers(response.headers_mut(), extra_headers);
+    response
+}
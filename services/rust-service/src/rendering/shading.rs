@@ -0,0 +1,124 @@
+//! Slope shading for escape-time renders (`shading=lambert`). The first pass is read as a
+//! height field: the smoothed escape time, which climbs toward the set, or under
+//! `coloring=distance` the estimated distance to the boundary, which falls toward it. Each
+//! exterior pixel's surface normal comes from the heights of its neighbours, and its color is
+//! scaled by Lambert's cosine law for a distant light, in linear light. A flat surface keeps its
+//! palette color; slopes facing the light brighten and slopes facing away darken.
+
+use super::colors::{Channel, Escape};
+use crate::fractals::traits::FractalParams;
+use crate::utils::validation::{validate_light_azimuth, validate_light_elevation};
+use rayon::prelude::*;
+
+/// Light from the upper right unless light_azimuth is given
+pub const DEFAULT_LIGHT_AZIMUTH: f64 = 45.0;
+
+/// Light halfway up the sky unless light_elevation is given
+pub const DEFAULT_LIGHT_ELEVATION: f64 = 45.0;
+
+/// Share of the light that still reaches slopes facing away from it
+const AMBIENT: f64 = 0.2;
+
+/// Height in pixels per unit of log escape time; how steep the relief looks
+const RELIEF: f64 = 40.0;
+
+/// Lighting requested with `shading`, `light_azimuth` and `light_elevation`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Shading {
+    None,
+    /// Diffuse light from `azimuth` degrees counterclockwise from the right edge, `elevation`
+    /// degrees above the image plane
+    Lambert {
+        azimuth: f64,
+        elevation: f64,
+    },
+}
+
+impl Shading {
+    /// Values accepted for `shading`
+    pub const NAMES: [&'static str; 2] = ["none", "lambert"];
+
+    pub fn from_params(params: &FractalParams) -> Result<Self, String> {
+        let azimuth = params.light_azimuth.unwrap_or(DEFAULT_LIGHT_AZIMUTH);
+        let elevation = params.light_elevation.unwrap_or(DEFAULT_LIGHT_ELEVATION);
+        validate_light_azimuth(azimuth)?;
+        validate_light_elevation(elevation)?;
+
+        match params.shading.as_deref().map(str::to_lowercase).as_deref() {
+            None | Some("none") => Ok(Shading::None),
+            Some("lambert") => Ok(Shading::Lambert { azimuth, elevation }),
+            Some(_) => Err(format!(
+                "Invalid shading. Must be one of: {}.",
+                Self::NAMES.join(", ")
+            )),
+        }
+    }
+
+    /// Light the colored `pixels` by the slope of the first-pass `escapes` under them. Pixels
+    /// inside the set are left as they are.
+    pub fn apply<C: Channel>(
+        self,
+        pixels: &mut [[C; 3]],
+        escapes: &[Escape],
+        width: u32,
+        height: u32,
+        max_iterations: u32,
+    ) {
+        let Shading::Lambert { azimuth, elevation } = self else {
+            return;
+        };
+
+        let heights: Vec<Option<f64>> = escapes
+            .par_iter()
+            .map(|escape| surface_height(escape, max_iterations))
+            .collect();
+
+        // Image y runs down, so a light toward the top has a negative y component
+        let (azimuth, elevation) = (azimuth.to_radians(), elevation.to_radians());
+        let light = (
+            elevation.cos() * azimuth.cos(),
+            -elevation.cos() * azimuth.sin(),
+            elevation.sin(),
+        );
+        let lit = |cosine: f64| AMBIENT + (1.0 - AMBIENT) * cosine.max(0.0);
+        let flat = lit(light.2);
+
+        let (width, height) = (width as usize, height as usize);
+        pixels
+            .par_iter_mut()
+            .enumerate()
+            .for_each(|(index, pixel)| {
+                let Some(own) = heights[index] else {
+                    return;
+                };
+                let (x, y) = (index % width, index / width);
+                // Neighbours off the image or inside the set count as level with the pixel
+                let at = |x: usize, y: usize| heights[y * width + x].unwrap_or(own);
+                let left = if x > 0 { at(x - 1, y) } else { own };
+                let right = if x + 1 < width { at(x + 1, y) } else { own };
+                let up = if y > 0 { at(x, y - 1) } else { own };
+                let down = if y + 1 < height { at(x, y + 1) } else { own };
+
+                let (slope_x, slope_y) = ((right - left) / 2.0, (down - up) / 2.0);
+                let length = (slope_x * slope_x + slope_y * slope_y + 1.0).sqrt();
+                let cosine = (-slope_x * light.0 - slope_y * light.1 + light.2) / length;
+                let factor = lit(cosine) / flat;
+
+                *pixel = pixel.map(|channel| C::from_linear(channel.to_linear() * factor));
+            });
+    }
+}
+
+/// Height of the surface at an exterior pixel in pixels, `None` inside the set
+fn surface_height(escape: &Escape, max_iterations: u32) -> Option<f64> {
+    if escape.iterations >= max_iterations {
+        return None;
+    }
+    if escape.boundary_distance.is_finite() {
+        return Some(-escape.boundary_distance);
+    }
+
+    // Smooth the escape bands with how far past the bailout the orbit landed, when known
+    let iterations = escape.smooth_iterations(max_iterations)?;
+    Some(RELIEF * iterations.max(0.0).ln_1p())
+}
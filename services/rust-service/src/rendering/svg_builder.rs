@@ -1,49 +1,96 @@
-pub struct SvgBuilder {
-    width: u32,
-    height: u32,
-    elements: Vec<String>,
+//! SVG documents for `format=svg`. Each stroke run becomes one path element, with a subpath per
+//! polyline.
+
+use super::vector::{number, Shape};
+
+/// The drawing as a standalone SVG document
+pub fn write_svg(width: u32, height: u32, shapes: &[Shape]) -> String {
+    let elements = shapes.iter().map(element).collect::<Vec<_>>();
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<svg xmlns="http://www.w3.org/2000/svg" width="{}" height="{}" viewBox="0 0 {} {}">
+{}
+</svg>
+"#,
+        width,
+        height,
+        width,
+        height,
+        elements.join("\n")
+    )
 }
 
-impl SvgBuilder {
-    pub fn new(width: u32, height: u32) -> Self {
-        Self {
+fn element(shape: &Shape) -> String {
+    match shape {
+        Shape::Stroke {
+            color,
             width,
-            height,
-            elements: Vec::new(),
+            polylines,
+        } => {
+            let data = polylines
+                .iter()
+                .map(|polyline| {
+                    polyline
+                        .iter()
+                        .enumerate()
+                        .map(|(i, &(x, y))| {
+                            let command = if i == 0 { 'M' } else { 'L' };
+                            format!("{}{} {}", command, number(x), number(y))
+                        })
+                        .collect::<Vec<_>>()
+                        .join(" ")
+                })
+                .collect::<Vec<_>>()
+                .join(" ");
+            format!(
+                r#"  <path d="{}" fill="none" stroke="{}" stroke-width="{}" stroke-linecap="round" stroke-linejoin="round"/>"#,
+                data,
+                hex(*color),
+                number(*width)
+            )
+        }
+        Shape::Polygon { points, fill } => {
+            let points = points
+                .iter()
+                .map(|&(x, y)| format!("{},{}", number(x), number(y)))
+                .collect::<Vec<_>>()
+                .join(" ");
+            format!(r#"  <polygon points="{}" fill="{}"/>"#, points, hex(*fill))
+        }
+        Shape::Rect { origin, size, fill } => format!(
+            r#"  <rect x="{}" y="{}" width="{}" height="{}" fill="{}"/>"#,
+            number(origin.0),
+            number(origin.1),
+            number(size.0),
+            number(size.1),
+            hex(*fill)
+        ),
+        Shape::Circle {
+            center,
+            radius,
+            color,
+            stroke,
+        } => {
+            let paint = match stroke {
+                Some(width) => format!(
+                    r#"fill="none" stroke="{}" stroke-width="{}""#,
+                    hex(*color),
+                    number(*width)
+                ),
+                None => format!(r#"fill="{}""#, hex(*color)),
+            };
+            format!(
+                r#"  <circle cx="{}" cy="{}" r="{}" {}/>"#,
+                number(center.0),
+                number(center.1),
+                number(*radius),
+                paint
+            )
         }
     }
+}
 
-    pub fn add_line(&mut self, x1: f64, y1: f64, x2: f64, y2: f64, stroke: &str, stroke_width: f64) {
-        self.elements.push(format!(
-            r#"  <line x1="{}" y1="{}" x2="{}" y2="{}" stroke="{}" stroke-width="{}"/>"#,
-            x1, y1, x2, y2, stroke, stroke_width
-        ));
-    }
-
-    pub fn add_polygon(&mut self, points: &[(f64, f64)], fill: &str, stroke: &str) {
-        let points_str = points
-            .iter()
-            .map(|(x, y)| format!("{},{}", x, y))
-            .collect::<Vec<_>>()
-            .join(" ");
-
-        self.elements.push(format!(
-            r#"  <polygon points="{}" fill="{}" stroke="{}"/>"#,
-            points_str, fill, stroke
-        ));
-    }
-
-    pub fn build(self) -> String {
-        format!(
-            r#"<?xml version="1.0" encoding="UTF-8"?>
-<svg xmlns="http://www.w3.org/2000/svg" width="{}" height="{}" viewBox="0 0 {} {}">
-{}
-</svg>"#,
-            self.width,
-            self.height,
-            self.width,
-            self.height,
-            self.elements.join("\n")
-        )
-    }
+/// `#rrggbb`
+fn hex(color: [u8; 3]) -> String {
+    format!("#{:02x}{:02x}{:02x}", color[0], color[1], color[2])
 }
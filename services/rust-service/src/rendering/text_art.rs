@@ -0,0 +1,416 @@
+//! Text renderings of an image for terminals, chat-ops and screen-reader-friendly contexts.
+//! `ascii` maps each character cell to a character from a density ramp, and `ansi` adds the
+//! cell's color as a 256-color escape; `braille` packs 2x4 on/off dots into each Unicode Braille
+//! character for four times the detail at the same size. Cells average the pixels they cover.
+//! Terminal cells are about twice as tall as they are wide, so the row count is halved to keep
+//! the image's proportions.
+//!
+//! Density is the pixels' brightness, or for the escape-time types their escape time, which
+//! keeps the shape independent of the palette.
+
+use super::colors::Escape;
+use super::encoder::OutputFormat;
+use crate::utils::http::append_headers;
+use axum::{
+    http::StatusCode,
+    response::{IntoResponse, Response},
+};
+use image::RgbImage;
+
+/// Most characters per line
+pub const MAX_COLUMNS: u32 = 400;
+
+/// Characters per line when none is given
+const DEFAULT_COLUMNS: u32 = 80;
+
+/// Codepoint of the empty Braille pattern; dots are bits added to it
+const BRAILLE_BASE: u32 = 0x2800;
+
+/// Bit of each dot in a Braille cell, indexed by [row][column]
+const BRAILLE_DOTS: [[u32; 2]; 4] = [[0x01, 0x08], [0x02, 0x10], [0x04, 0x20], [0x40, 0x80]];
+
+/// Pixels rendered per character cell across (and twice as many down), enough to average for
+/// ascii and ansi and one per Braille dot
+const SAMPLES_PER_CELL: u32 = 2;
+
+/// Longest side `validate_dimensions` accepts
+const MAX_RENDER_SIDE: u64 = 4096;
+
+/// Channel levels of the xterm 256-color palette's 6x6x6 cube, which starts at index 16
+const ANSI_CUBE_LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+/// Density ramps for `ascii` and `ansi`, emptiest first
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Charset {
+    /// Ten printable ASCII characters
+    #[default]
+    Standard,
+    /// Seventy printable ASCII characters, for finer gradients
+    Detailed,
+    /// Unicode shade blocks
+    Blocks,
+}
+
+impl Charset {
+    /// Names accepted by `parse`
+    pub const NAMES: [&'static str; 3] = ["standard", "detailed", "blocks"];
+
+    pub fn parse(name: &str) -> Result<Self, String> {
+        match name.to_lowercase().as_str() {
+            "standard" => Ok(Charset::Standard),
+            "detailed" => Ok(Charset::Detailed),
+            "blocks" => Ok(Charset::Blocks),
+            _ => Err(format!(
+                "Invalid charset. Must be one of: {}.",
+                Self::NAMES.join(", ")
+            )),
+        }
+    }
+
+    fn ramp(self) -> &'static str {
+        match self {
+            Charset::Standard => " .:-=+*#%@",
+            Charset::Detailed => {
+                " .'`^\",:;Il!i><~+_-?][}{1)(|\\/tfjrxnuvczXYUJCLQ0OZmwqpdbkhao*#MW&8%B@$"
+            }
+            Charset::Blocks => " ░▒▓█",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TextFormat {
+    Ascii(Charset),
+    /// `Ascii` with each character in its cell's color, as xterm 256-color escapes
+    Ansi(Charset),
+    Braille,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TextOptions {
+    pub format: TextFormat,
+    pub columns: u32,
+    /// Dense characters for sparse pixels, for dark text on a light background
+    pub invert: bool,
+}
+
+impl TextOptions {
+    /// Names accepted for `format`; all but `ascii`, `ansi` and `braille` mean no text rendering
+    pub const FORMATS: [&'static str; 14] = [
+        "png", "jpeg", "webp", "avif", "tiff", "bmp", "svg", "pdf", "eps", "raw", "npy", "ascii",
+        "ansi", "braille",
+    ];
+
+    /// Build from the optional `format` / `columns` / `charset` / `invert` request parameters;
+    /// `None` when the response should stay an image
+    pub fn from_params(
+        format: Option<&str>,
+        columns: Option<u32>,
+        charset: Option<&str>,
+        invert: Option<bool>,
+    ) -> Result<Option<Self>, String> {
+        let charset = charset.map(Charset::parse).transpose()?;
+        let format = match format.map(str::to_lowercase).as_deref() {
+            None | Some("svg") | Some("pdf") | Some("eps") | Some("raw") | Some("npy") => {
+                return Ok(None)
+            }
+            Some(name) if OutputFormat::from_name(Some(name)).is_some() => return Ok(None),
+            Some("ascii") => TextFormat::Ascii(charset.unwrap_or_default()),
+            Some("ansi") => TextFormat::Ansi(charset.unwrap_or_default()),
+            Some("braille") => TextFormat::Braille,
+            Some(_) => {
+                return Err(format!(
+                    "Invalid format. Must be one of: {}.",
+                    Self::FORMATS.join(", ")
+                ))
+            }
+        };
+
+        let columns = columns.unwrap_or(DEFAULT_COLUMNS);
+        if columns == 0 || columns > MAX_COLUMNS {
+            return Err(format!(
+                "Invalid columns. Must be between 1 and {}.",
+                MAX_COLUMNS
+            ));
+        }
+
+        Ok(Some(Self {
+            format,
+            columns,
+            invert: invert.unwrap_or(false),
+        }))
+    }
+
+    /// Size to render a `width` x `height` image at for this text: a few pixels per character at
+    /// the image's aspect ratio. Large images render smaller, as the cells would average their
+    /// detail away, and tiny ones larger, so every cell has pixels of its own.
+    pub fn render_size(&self, width: u32, height: u32) -> (u32, u32) {
+        let sampled = u64::from(self.columns * SAMPLES_PER_CELL);
+        let (width, height) = (u64::from(width), u64::from(height));
+        let scaled = ((height * sampled + width / 2) / width).max(1);
+        if scaled <= MAX_RENDER_SIDE {
+            return (sampled as u32, scaled as u32);
+        }
+        // Very tall images are narrowed to stay within the render size limit
+        let narrowed = (sampled * MAX_RENDER_SIDE / scaled).max(1);
+        (narrowed as u32, MAX_RENDER_SIDE as u32)
+    }
+}
+
+/// Density of each pixel of an escape-time render, from 0 to 1: escape time on a log scale
+/// from the first pixels to escape to the last, which are densest, with the set itself empty
+/// like the black it is drawn in
+pub fn escape_density(escapes: &[Escape], max_iterations: u32) -> Vec<f64> {
+    let escaped = || {
+        escapes
+            .iter()
+            .map(|escape| escape.iterations)
+            .filter(|&iterations| iterations < max_iterations)
+    };
+    let first = escaped().min().unwrap_or(0);
+    let scale = f64::from(escaped().max().unwrap_or(0) - first).ln_1p();
+    escapes
+        .iter()
+        .map(
+            |escape| match escape.iterations >= max_iterations || scale == 0.0 {
+                true => 0.0,
+                false => f64::from(escape.iterations - first).ln_1p() / scale,
+            },
+        )
+        .collect()
+}
+
+/// The image as lines of text, each ending in a newline. `density` gives each pixel's density
+/// from 0 to 1 in place of its brightness, e.g. from `escape_density`.
+pub fn render_text(img: &RgbImage, density: Option<&[f64]>, options: &TextOptions) -> String {
+    let (width, height) = img.dimensions();
+    let aspect = height as f64 / width as f64;
+    // No more columns than the image has pixels for, which also bounds the rows of very tall
+    // images to their height
+    let columns = options.columns.min(width.div_ceil(SAMPLES_PER_CELL));
+    let density_grid = |columns: u32, rows: u32| {
+        let grid = match density {
+            Some(density) => cell_means(width, height, columns, rows, |x, y| {
+                density[(y * width + x) as usize]
+            }),
+            None => cell_means(width, height, columns, rows, |x, y| {
+                let [r, g, b] = img.get_pixel(x, y).0;
+                0.2126 * r as f64 + 0.7152 * g as f64 + 0.0722 * b as f64
+            }),
+        };
+        stretch(grid)
+    };
+
+    match options.format {
+        TextFormat::Ascii(charset) | TextFormat::Ansi(charset) => {
+            let rows = ((columns as f64 * aspect / 2.0).round() as u32).max(1);
+            let mut grid = density_grid(columns, rows);
+            if options.invert {
+                grid.iter_mut().for_each(|value| *value = 1.0 - *value);
+            }
+            let colors = match options.format {
+                TextFormat::Ansi(_) => Some(color_grid(img, columns, rows)),
+                _ => None,
+            };
+
+            let ramp: Vec<char> = charset.ramp().chars().collect();
+            let last = ramp.len() - 1;
+            let mut text = String::with_capacity(((columns + 1) * rows) as usize);
+            for (row, values) in grid.chunks(columns as usize).enumerate() {
+                // Escapes only where the color changes, and never for blanks, which show none
+                let mut current = None;
+                for (column, value) in values.iter().enumerate() {
+                    let character = ramp[(value * last as f64).round() as usize];
+                    if let Some(colors) = &colors {
+                        let color = colors[row * columns as usize + column];
+                        if character != ' ' && current != Some(color) {
+                            text.push_str(&format!("\x1b[38;5;{}m", color));
+                            current = Some(color);
+                        }
+                    }
+                    text.push(character);
+                }
+                if current.is_some() {
+                    text.push_str("\x1b[0m");
+                }
+                text.push('\n');
+            }
+            text
+        }
+        TextFormat::Braille => {
+            // Dots are square: two per cell across, four down
+            let dot_columns = columns * 2;
+            let dot_rows = ((dot_columns as f64 * aspect).round() as u32).max(1);
+            let grid = density_grid(dot_columns, dot_rows);
+            let threshold = otsu_threshold(&grid);
+
+            let rows = dot_rows.div_ceil(4);
+            let mut text = String::with_capacity(((columns * 3 + 1) * rows) as usize);
+            for row in 0..rows {
+                for column in 0..columns {
+                    let mut pattern = 0;
+                    for (dy, bits) in BRAILLE_DOTS.iter().enumerate() {
+                        let y = row * 4 + dy as u32;
+                        if y >= dot_rows {
+                            break;
+                        }
+                        for (dx, bit) in bits.iter().enumerate() {
+                            let x = column * 2 + dx as u32;
+                            let value = grid[(y * dot_columns + x) as usize];
+                            if (value > threshold) != options.invert {
+                                pattern |= bit;
+                            }
+                        }
+                    }
+                    text.push(char::from_u32(BRAILLE_BASE + pattern).unwrap_or(' '));
+                }
+                text.push('\n');
+            }
+            text
+        }
+    }
+}
+
+/// Pixel span of each of `cells` cells along `pixels`; every cell covers at least one pixel,
+/// even when upsampling
+fn cell_span(cell: u32, cells: u32, pixels: u32) -> (u32, u32) {
+    let start = (u64::from(cell) * u64::from(pixels) / u64::from(cells)) as u32;
+    let end = (u64::from(cell + 1) * u64::from(pixels) / u64::from(cells)) as u32;
+    (start.min(pixels - 1), end.clamp(start + 1, pixels))
+}
+
+/// Mean of `value` over the pixels of each of `columns` x `rows` cells, row-major
+fn cell_means(
+    width: u32,
+    height: u32,
+    columns: u32,
+    rows: u32,
+    value: impl Fn(u32, u32) -> f64,
+) -> Vec<f64> {
+    let mut grid = Vec::with_capacity((columns * rows) as usize);
+    for row in 0..rows {
+        let (y0, y1) = cell_span(row, rows, height);
+        for column in 0..columns {
+            let (x0, x1) = cell_span(column, columns, width);
+            let mut sum = 0.0;
+            for y in y0..y1 {
+                for x in x0..x1 {
+                    sum += value(x, y);
+                }
+            }
+            grid.push(sum / ((x1 - x0) * (y1 - y0)) as f64);
+        }
+    }
+    grid
+}
+
+/// The values stretched so the smallest is 0 and the largest 1
+fn stretch(mut grid: Vec<f64>) -> Vec<f64> {
+    let min = grid.iter().copied().fold(f64::INFINITY, f64::min);
+    let max = grid.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+    let range = max - min;
+    for value in &mut grid {
+        *value = if range > 0.0 {
+            (*value - min) / range
+        } else {
+            0.0
+        };
+    }
+    grid
+}
+
+/// Mean color of each of `columns` x `rows` cells as the nearest xterm 256-color index
+fn color_grid(img: &RgbImage, columns: u32, rows: u32) -> Vec<u8> {
+    let (width, height) = img.dimensions();
+    let channel = |c: usize| {
+        cell_means(width, height, columns, rows, |x, y| {
+            img.get_pixel(x, y).0[c] as f64
+        })
+    };
+    let (reds, greens, blues) = (channel(0), channel(1), channel(2));
+    (0..reds.len())
+        .map(|i| ansi_color([reds[i], greens[i], blues[i]]))
+        .collect()
+}
+
+/// Nearest xterm 256-color index: the closer of the 6x6x6 color cube and the 24-step gray ramp
+fn ansi_color(color: [f64; 3]) -> u8 {
+    let distance =
+        |candidate: [f64; 3]| -> f64 { (0..3).map(|c| (color[c] - candidate[c]).powi(2)).sum() };
+
+    let steps = color.map(|channel| {
+        (0..ANSI_CUBE_LEVELS.len())
+            .min_by(|&a, &b| {
+                let a = (f64::from(ANSI_CUBE_LEVELS[a]) - channel).abs();
+                let b = (f64::from(ANSI_CUBE_LEVELS[b]) - channel).abs();
+                a.total_cmp(&b)
+            })
+            .unwrap_or(0)
+    });
+    let cube = steps.map(|step| f64::from(ANSI_CUBE_LEVELS[step]));
+    let cube_index = 16 + 36 * steps[0] + 6 * steps[1] + steps[2];
+
+    // Gray ramp 232-255 runs from 8 to 238 in steps of 10
+    let mean = color.iter().sum::<f64>() / 3.0;
+    let gray_step = ((mean - 8.0) / 10.0).round().clamp(0.0, 23.0);
+    let gray = 8.0 + gray_step * 10.0;
+
+    if distance([gray; 3]) < distance(cube) {
+        232 + gray_step as u8
+    } else {
+        cube_index as u8
+    }
+}
+
+/// Otsu's threshold: the cut between 0 and 1 that best separates the values into two groups
+fn otsu_threshold(values: &[f64]) -> f64 {
+    const BINS: usize = 256;
+
+    let mut histogram = [0u64; BINS];
+    for value in values {
+        histogram[((value * (BINS - 1) as f64).round() as usize).min(BINS - 1)] += 1;
+    }
+
+    let total = values.len() as f64;
+    let total_sum: f64 = histogram
+        .iter()
+        .enumerate()
+        .map(|(bin, &count)| bin as f64 * count as f64)
+        .sum();
+
+    let mut best = (0, f64::NEG_INFINITY);
+    let mut below_count = 0.0;
+    let mut below_sum = 0.0;
+    for (bin, &count) in histogram.iter().enumerate() {
+        below_count += count as f64;
+        below_sum += bin as f64 * count as f64;
+        let above_count = total - below_count;
+        if below_count == 0.0 || above_count == 0.0 {
+            continue;
+        }
+
+        // Between-class variance, up to a constant factor
+        let mean_difference = below_sum / below_count - (total_sum - below_sum) / above_count;
+        let variance = below_count * above_count * mean_difference * mean_difference;
+        if variance > best.1 {
+            best = (bin, variance);
+        }
+    }
+
+    // Values in the threshold bin count as dark
+    (best.0 as f64 + 0.5) / (BINS - 1) as f64
+}
+
+pub fn create_text_response(text: String, extra_headers: &[(String, String)]) -> Response {
+    let builder = Response::builder()
+        .status(StatusCode::OK)
+        .header("Content-Type", "text/plain; charset=utf-8")
+        .header("Content-Length", text.len().to_string());
+
+    let mut response = builder
+        .body(axum::body::Body::from(text))
+        .unwrap()
+        .into_response();
+    append_headers(response.headers_mut(), extra_headers);
+    response
+}
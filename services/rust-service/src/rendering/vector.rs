@@ -0,0 +1,282 @@
+//! Vector output for the geometric types: `format=svg`, `pdf` and `eps`. A drawing is collected
+//! once as format-neutral shapes, then written by the backend for the format asked for.
+//! Consecutive lines of the same color and width are joined into one stroked path, so a curve of
+//! a million segments stays a handful of paths in any of them.
+
+use super::canvas::Canvas;
+use super::eps_writer::write_eps;
+use super::pdf_writer::write_pdf;
+use super::svg_builder::write_svg;
+use crate::utils::http::append_headers;
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+
+/// Lines and shapes a document may hold before it is refused as too detailed
+pub const MAX_VECTOR_SHAPES: usize = 2_000_000;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum VectorFormat {
+    Svg,
+    /// A single page the size of the drawing, one point per pixel
+    Pdf,
+    /// Encapsulated PostScript with the drawing as its bounding box
+    Eps,
+}
+
+impl VectorFormat {
+    /// The vector format a `format` parameter names; `None` for raster, text and unknown names
+    pub fn from_name(format: Option<&str>) -> Option<Self> {
+        match format.map(str::to_lowercase).as_deref() {
+            Some("svg") => Some(VectorFormat::Svg),
+            Some("pdf") => Some(VectorFormat::Pdf),
+            Some("eps") => Some(VectorFormat::Eps),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            VectorFormat::Svg => "svg",
+            VectorFormat::Pdf => "pdf",
+            VectorFormat::Eps => "eps",
+        }
+    }
+
+    pub fn content_type(self) -> &'static str {
+        match self {
+            VectorFormat::Svg => "image/svg+xml",
+            VectorFormat::Pdf => "application/pdf",
+            VectorFormat::Eps => "application/postscript",
+        }
+    }
+}
+
+/// One element of a drawing, in pixels with y down
+#[derive(Clone, Debug)]
+pub enum Shape {
+    /// Polylines sharing a stroke, with round caps and joins
+    Stroke {
+        color: [u8; 3],
+        width: f64,
+        polylines: Vec<Vec<(f64, f64)>>,
+    },
+    Polygon {
+        points: Vec<(f64, f64)>,
+        fill: [u8; 3],
+    },
+    Rect {
+        origin: (f64, f64),
+        size: (f64, f64),
+        fill: [u8; 3],
+    },
+    /// Filled circle, or with `stroke` an outline of that width
+    Circle {
+        center: (f64, f64),
+        radius: f64,
+        color: [u8; 3],
+        stroke: Option<f64>,
+    },
+}
+
+pub struct VectorBuilder {
+    width: u32,
+    height: u32,
+    shapes: Vec<Shape>,
+    /// Run of lines pending until a line of another stroke or another shape arrives
+    stroke: Option<Shape>,
+    count: usize,
+}
+
+impl VectorBuilder {
+    pub fn new(width: u32, height: u32) -> Self {
+        Self {
+            width,
+            height,
+            shapes: Vec::new(),
+            stroke: None,
+            count: 0,
+        }
+    }
+
+    pub fn add_line(&mut self, start: (f64, f64), end: (f64, f64), color: [u8; 3], width: f64) {
+        if !self.count_shape() {
+            return;
+        }
+
+        let same_stroke = matches!(
+            &self.stroke,
+            Some(Shape::Stroke { color: run_color, width: run_width, .. })
+                if *run_color == color && *run_width == width
+        );
+        if !same_stroke {
+            self.flush_stroke();
+        }
+        let Shape::Stroke { polylines, .. } = self.stroke.get_or_insert_with(|| Shape::Stroke {
+            color,
+            width,
+            polylines: Vec::new(),
+        }) else {
+            unreachable!("only strokes are pending");
+        };
+
+        // Lines that continue from the previous one extend it instead of starting a polyline
+        match polylines.last_mut() {
+            Some(polyline) if polyline.last() == Some(&start) => polyline.push(end),
+            _ => polylines.push(vec![start, end]),
+        }
+    }
+
+    pub fn add_polygon(&mut self, points: &[(f64, f64)], fill: [u8; 3]) {
+        self.push(Shape::Polygon {
+            points: points.to_vec(),
+            fill,
+        });
+    }
+
+    pub fn add_rect(&mut self, origin: (f64, f64), size: (f64, f64), fill: [u8; 3]) {
+        self.push(Shape::Rect { origin, size, fill });
+    }
+
+    /// Filled circle, or with `stroke` an outline of that width
+    pub fn add_circle(
+        &mut self,
+        center: (f64, f64),
+        radius: f64,
+        color: [u8; 3],
+        stroke: Option<f64>,
+    ) {
+        self.push(Shape::Circle {
+            center,
+            radius,
+            color,
+            stroke,
+        });
+    }
+
+    /// The finished document, or an error once it has grown past `MAX_VECTOR_SHAPES`
+    pub fn build(mut self, format: VectorFormat) -> Result<Vec<u8>, String> {
+        if self.count > MAX_VECTOR_SHAPES {
+            return Err(format!(
+                "Too much detail for format={} (over {} shapes). Lower recursion_depth.",
+                format.name(),
+                MAX_VECTOR_SHAPES
+            ));
+        }
+        self.flush_stroke();
+
+        let (width, height) = (self.width, self.height);
+        Ok(match format {
+            VectorFormat::Svg => write_svg(width, height, &self.shapes).into_bytes(),
+            VectorFormat::Pdf => write_pdf(width, height, &self.shapes)?,
+            VectorFormat::Eps => write_eps(width, height, &self.shapes).into_bytes(),
+        })
+    }
+
+    /// Count one more shape; false once past the limit, when the drawing is discarded anyway
+    fn count_shape(&mut self) -> bool {
+        self.count += 1;
+        if self.count > MAX_VECTOR_SHAPES {
+            self.shapes.clear();
+            self.stroke = None;
+            return false;
+        }
+        true
+    }
+
+    fn push(&mut self, shape: Shape) {
+        if self.count_shape() {
+            self.flush_stroke();
+            self.shapes.push(shape);
+        }
+    }
+
+    fn flush_stroke(&mut self) {
+        if let Some(stroke) = self.stroke.take() {
+            self.shapes.push(stroke);
+        }
+    }
+}
+
+impl Canvas for VectorBuilder {
+    fn blank(width: u32, height: u32, background: [u8; 3]) -> Self {
+        let mut builder = Self::new(width, height);
+        builder.add_rect((0.0, 0.0), (width as f64, height as f64), background);
+        builder
+    }
+
+    fn size(&self) -> (u32, u32) {
+        (self.width, self.height)
+    }
+
+    fn line(&mut self, start: (f64, f64), end: (f64, f64), width: f64, color: [u8; 3]) {
+        let finite = [start.0, start.1, end.0, end.1]
+            .iter()
+            .all(|v| v.is_finite());
+        if finite && width > 0.0 {
+            self.add_line(start, end, color, width);
+        }
+    }
+
+    fn triangle(&mut self, corners: [(f64, f64); 3], color: [u8; 3]) {
+        self.add_polygon(&corners, color);
+    }
+
+    fn square(&mut self, origin: (f64, f64), size: f64, color: [u8; 3]) {
+        self.add_rect(origin, (size, size), color);
+    }
+
+    fn disc(&mut self, center: (f64, f64), radius: f64, color: [u8; 3]) {
+        if radius > 0.0 && center.0.is_finite() && center.1.is_finite() {
+            self.add_circle(center, radius, color, None);
+        }
+    }
+
+    fn ring(&mut self, center: (f64, f64), radius: f64, width: f64, color: [u8; 3]) {
+        if radius > 0.0 && width > 0.0 && center.0.is_finite() && center.1.is_finite() {
+            self.add_circle(center, radius, color, Some(width));
+        }
+    }
+}
+
+/// A coordinate to two decimals without trailing zeros; finer than any display or printer
+/// resolves
+pub fn number(value: f64) -> String {
+    let text = format!("{:.2}", value);
+    let text = text.trim_end_matches('0').trim_end_matches('.');
+    match text {
+        "-0" => "0".to_string(),
+        _ => text.to_string(),
+    }
+}
+
+/// Color operands for PDF and PostScript, each channel as a 0-1 fraction
+pub fn color_operands(color: [u8; 3]) -> String {
+    color
+        .map(|channel| {
+            let text = format!("{:.3}", channel as f64 / 255.0);
+            let text = text.trim_end_matches('0').trim_end_matches('.');
+            match text {
+                "" => "0".to_string(),
+                _ => text.to_string(),
+            }
+        })
+        .join(" ")
+}
+
+pub fn create_vector_response(
+    document: Vec<u8>,
+    format: VectorFormat,
+    extra_headers: &[(String, String)],
+) -> Response {
+    let builder = Response::builder()
+        .status(StatusCode::OK)
+        .header("Content-Type", format.content_type())
+        .header("Content-Length", document.len().to_string());
+
+    let mut response = builder
+        .body(axum::body::Body::from(document))
+        .unwrap()
+        .into_response();
+    append_headers(response.headers_mut(), extra_headers);
+    response
+}
@@ -0,0 +1,412 @@
+//! WebM video for `format=webm` zoom animations, built with the `video` feature. Frames are
+//! encoded as AV1 by rav1e, without reordering, and each is muxed as soon as it comes out into
+//! a segment and clusters of unknown size, so the video can be sent while later frames are
+//! still rendering. Builds without the feature still accept the name and answer that it isn't
+//! enabled.
+
+#[cfg(feature = "video")]
+use image::RgbImage;
+#[cfg(feature = "video")]
+use rav1e::prelude::*;
+
+/// Seconds from one keyframe to the next unless `keyframe_interval` is given
+const DEFAULT_KEYFRAME_SECONDS: u32 = 2;
+
+/// rav1e speed, 0 (smallest) to 10 (fastest); every frame of a video is encoded, so it runs
+/// faster than AVIF stills do
+#[cfg(feature = "video")]
+const VIDEO_SPEED: u8 = 9;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(not(feature = "video"), allow(dead_code))]
+pub struct VideoOptions {
+    /// Frames from one keyframe to the next
+    pub keyframe_interval: u32,
+    /// Target kilobits per second; constant quality when `None`
+    pub bitrate: Option<u32>,
+}
+
+impl VideoOptions {
+    pub fn from_params(
+        fps: u32,
+        keyframe_interval: Option<u32>,
+        bitrate: Option<u32>,
+    ) -> Result<Self, String> {
+        if keyframe_interval.is_some_and(|interval| !(1..=300).contains(&interval)) {
+            return Err("Invalid keyframe_interval. Must be between 1 and 300.".to_string());
+        }
+        if bitrate.is_some_and(|kbps| !(100..=50_000).contains(&kbps)) {
+            return Err("Invalid bitrate. Must be between 100 and 50000 kbps.".to_string());
+        }
+        Ok(Self {
+            keyframe_interval: keyframe_interval.unwrap_or(DEFAULT_KEYFRAME_SECONDS * fps),
+            bitrate,
+        })
+    }
+}
+
+/// Error for video requests to a build without the `video` feature
+#[cfg(not(feature = "video"))]
+pub fn not_enabled() -> String {
+    "format=webm isn't enabled in this build. Rebuild with --features video.".to_string()
+}
+
+/// Element IDs, written with their length marker as they appear in the file
+#[cfg(feature = "video")]
+mod ids {
+    pub const EBML: u32 = 0x1A45_DFA3;
+    pub const EBML_VERSION: u32 = 0x4286;
+    pub const EBML_READ_VERSION: u32 = 0x42F7;
+    pub const EBML_MAX_ID_LENGTH: u32 = 0x42F2;
+    pub const EBML_MAX_SIZE_LENGTH: u32 = 0x42F3;
+    pub const DOC_TYPE: u32 = 0x4282;
+    pub const DOC_TYPE_VERSION: u32 = 0x4287;
+    pub const DOC_TYPE_READ_VERSION: u32 = 0x4285;
+    pub const SEGMENT: u32 = 0x1853_8067;
+    pub const INFO: u32 = 0x1549_A966;
+    pub const TIMESTAMP_SCALE: u32 = 0x2A_D7B1;
+    pub const DURATION: u32 = 0x4489;
+    pub const MUXING_APP: u32 = 0x4D80;
+    pub const WRITING_APP: u32 = 0x5741;
+    pub const TRACKS: u32 = 0x1654_AE6B;
+    pub const TRACK_ENTRY: u32 = 0xAE;
+    pub const TRACK_NUMBER: u32 = 0xD7;
+    pub const TRACK_UID: u32 = 0x73C5;
+    pub const TRACK_TYPE: u32 = 0x83;
+    pub const CODEC_ID: u32 = 0x86;
+    pub const CODEC_PRIVATE: u32 = 0x63A2;
+    pub const DEFAULT_DURATION: u32 = 0x23_E383;
+    pub const VIDEO: u32 = 0xE0;
+    pub const PIXEL_WIDTH: u32 = 0xB0;
+    pub const PIXEL_HEIGHT: u32 = 0xBA;
+    pub const COLOUR: u32 = 0x55B0;
+    pub const MATRIX_COEFFICIENTS: u32 = 0x55B1;
+    pub const RANGE: u32 = 0x55B9;
+    pub const TRANSFER_CHARACTERISTICS: u32 = 0x55BA;
+    pub const PRIMARIES: u32 = 0x55BB;
+    pub const CLUSTER: u32 = 0x1F43_B675;
+    pub const TIMESTAMP: u32 = 0xE7;
+    pub const SIMPLE_BLOCK: u32 = 0xA3;
+}
+
+/// Size of an element whose end isn't known when it's written, in the one-byte form every
+/// demuxer understands
+#[cfg(feature = "video")]
+const UNKNOWN_SIZE: u8 = 0xFF;
+
+/// AV1 OBU type of the sequence header
+#[cfg(feature = "video")]
+const OBU_SEQUENCE_HEADER: u8 = 1;
+
+/// Streams a zoom as WebM: `push` each frame in order and send what it returns, then send
+/// what `finish` returns
+#[cfg(feature = "video")]
+pub struct WebmEncoder {
+    context: Context<u8>,
+    width: u32,
+    height: u32,
+    fps: u32,
+    frames: u32,
+    /// Start of the track's codec configuration, until the headers go out with the first
+    /// packet, whose sequence header completes it
+    config_record: Option<Vec<u8>>,
+    /// Timestamp in milliseconds of the open cluster
+    cluster: Option<u64>,
+    /// Y, Cb and Cr planes, reused for every frame
+    planes: [Vec<u8>; 3],
+}
+
+#[cfg(feature = "video")]
+impl WebmEncoder {
+    pub fn new(
+        width: u32,
+        height: u32,
+        fps: u32,
+        frames: u32,
+        options: &VideoOptions,
+    ) -> Result<Self, String> {
+        let mut config = EncoderConfig::with_speed_preset(VIDEO_SPEED);
+        config.width = width as usize;
+        config.height = height as usize;
+        config.time_base = Rational::new(1, fps as u64);
+        config.chroma_sampling = ChromaSampling::Cs420;
+        config.pixel_range = PixelRange::Limited;
+        config.color_description = Some(ColorDescription {
+            color_primaries: ColorPrimaries::BT709,
+            transfer_characteristics: TransferCharacteristics::BT709,
+            matrix_coefficients: MatrixCoefficients::BT709,
+        });
+        // Frames come out in the order they go in, one per packet
+        config.low_latency = true;
+        config.min_key_frame_interval = options.keyframe_interval as u64;
+        config.max_key_frame_interval = options.keyframe_interval as u64;
+        if let Some(kbps) = options.bitrate {
+            config.bitrate = kbps as i32 * 1000;
+        }
+        let context: Context<u8> = Config::new()
+            .with_encoder_config(config)
+            .new_context()
+            .map_err(|e| format!("Failed to start WebM encoder: {}", e))?;
+
+        let config_record = context.container_sequence_header();
+        Ok(Self {
+            context,
+            width,
+            height,
+            fps,
+            frames,
+            config_record: Some(config_record),
+            cluster: None,
+            planes: Default::default(),
+        })
+    }
+
+    /// Encode the next frame; returns the bytes that are ready, which may be none
+    pub fn push(&mut self, img: &RgbImage) -> Result<Vec<u8>, String> {
+        rgb_to_yuv420(img, &mut self.planes);
+        let (width, chroma_width) = (img.width() as usize, img.width().div_ceil(2) as usize);
+        let mut frame = self.context.new_frame();
+        for (plane, (samples, stride)) in
+            frame
+                .planes
+                .iter_mut()
+                .zip(self.planes.iter().zip([width, chroma_width, chroma_width]))
+        {
+            plane.copy_from_raw_u8(samples, stride, 1);
+        }
+        self.context
+            .send_frame(frame)
+            .map_err(|e| format!("Failed to encode WebM frame: {}", e))?;
+
+        let mut out = Vec::new();
+        self.drain(&mut out)?;
+        Ok(out)
+    }
+
+    /// Encode the frames rav1e is still holding; returns the rest of the video
+    pub fn finish(mut self) -> Result<Vec<u8>, String> {
+        self.context.flush();
+        let mut out = Vec::new();
+        while !self.drain(&mut out)? {}
+        Ok(out)
+    }
+
+    /// Mux every packet that's ready; true once the last has come out
+    fn drain(&mut self, out: &mut Vec<u8>) -> Result<bool, String> {
+        loop {
+            match self.context.receive_packet() {
+                Ok(packet) => {
+                    let timestamp =
+                        (packet.input_frameno * 1000 + self.fps as u64 / 2) / self.fps as u64;
+                    let key = packet.frame_type == FrameType::KEY;
+                    // Matroska leaves out the temporal delimiter that starts each packet
+                    let data = packet
+                        .data
+                        .strip_prefix(&[0x12, 0x00])
+                        .unwrap_or(&packet.data);
+                    self.write_block(out, timestamp, key, data);
+                }
+                Err(EncoderStatus::Encoded) => continue,
+                Err(EncoderStatus::NeedMoreData) => return Ok(false),
+                Err(EncoderStatus::LimitReached) => return Ok(true),
+                Err(e) => return Err(format!("Failed to encode WebM frame: {}", e)),
+            }
+        }
+    }
+
+    fn write_block(&mut self, out: &mut Vec<u8>, timestamp: u64, key: bool, data: &[u8]) {
+        if let Some(mut config_record) = self.config_record.take() {
+            config_record.extend_from_slice(sequence_header_obu(data).unwrap_or_default());
+            out.extend(header(
+                self.width,
+                self.height,
+                self.fps,
+                self.frames,
+                &config_record,
+            ));
+        }
+
+        // Clusters start at keyframes so players can seek to them, and whenever the block's
+        // offset from the cluster would overflow its 16 bits
+        let start = match self.cluster {
+            Some(start) if !key && timestamp - start <= i16::MAX as u64 => start,
+            _ => {
+                write_id(out, ids::CLUSTER);
+                out.push(UNKNOWN_SIZE);
+                uint(out, ids::TIMESTAMP, timestamp);
+                self.cluster = Some(timestamp);
+                timestamp
+            }
+        };
+
+        // Track 1, offset from the cluster, keyframe flag
+        let mut block = Vec::with_capacity(4 + data.len());
+        block.push(0x81);
+        block.extend_from_slice(&((timestamp - start) as i16).to_be_bytes());
+        block.push(if key { 0x80 } else { 0 });
+        block.extend_from_slice(data);
+        element(out, ids::SIMPLE_BLOCK, &block);
+    }
+}
+
+/// EBML header, the start of the segment, its info and the one AV1 track
+#[cfg(feature = "video")]
+fn header(width: u32, height: u32, fps: u32, frames: u32, config_record: &[u8]) -> Vec<u8> {
+    let mut out = Vec::new();
+
+    let mut ebml = Vec::new();
+    uint(&mut ebml, ids::EBML_VERSION, 1);
+    uint(&mut ebml, ids::EBML_READ_VERSION, 1);
+    uint(&mut ebml, ids::EBML_MAX_ID_LENGTH, 4);
+    uint(&mut ebml, ids::EBML_MAX_SIZE_LENGTH, 8);
+    element(&mut ebml, ids::DOC_TYPE, b"webm");
+    uint(&mut ebml, ids::DOC_TYPE_VERSION, 4);
+    uint(&mut ebml, ids::DOC_TYPE_READ_VERSION, 2);
+    element(&mut out, ids::EBML, &ebml);
+
+    write_id(&mut out, ids::SEGMENT);
+    out.push(UNKNOWN_SIZE);
+
+    // Timestamps in milliseconds
+    let mut info = Vec::new();
+    uint(&mut info, ids::TIMESTAMP_SCALE, 1_000_000);
+    element(
+        &mut info,
+        ids::DURATION,
+        &(frames as f64 * 1000.0 / fps as f64).to_be_bytes(),
+    );
+    element(&mut info, ids::MUXING_APP, b"rust-service");
+    element(&mut info, ids::WRITING_APP, b"rust-service");
+    element(&mut out, ids::INFO, &info);
+
+    // BT.709 in limited range, as the frames are converted
+    let mut colour = Vec::new();
+    uint(&mut colour, ids::MATRIX_COEFFICIENTS, 1);
+    uint(&mut colour, ids::RANGE, 1);
+    uint(&mut colour, ids::TRANSFER_CHARACTERISTICS, 1);
+    uint(&mut colour, ids::PRIMARIES, 1);
+    let mut video = Vec::new();
+    uint(&mut video, ids::PIXEL_WIDTH, width as u64);
+    uint(&mut video, ids::PIXEL_HEIGHT, height as u64);
+    element(&mut video, ids::COLOUR, &colour);
+
+    let mut track = Vec::new();
+    uint(&mut track, ids::TRACK_NUMBER, 1);
+    uint(&mut track, ids::TRACK_UID, 1);
+    uint(&mut track, ids::TRACK_TYPE, 1);
+    element(&mut track, ids::CODEC_ID, b"V_AV1");
+    element(&mut track, ids::CODEC_PRIVATE, config_record);
+    uint(
+        &mut track,
+        ids::DEFAULT_DURATION,
+        1_000_000_000 / fps as u64,
+    );
+    element(&mut track, ids::VIDEO, &video);
+    let mut tracks = Vec::new();
+    element(&mut tracks, ids::TRACK_ENTRY, &track);
+    element(&mut out, ids::TRACKS, &tracks);
+
+    out
+}
+
+/// The sequence header OBU at the start of a keyframe packet, which Matroska wants repeated
+/// in the codec configuration
+#[cfg(feature = "video")]
+fn sequence_header_obu(packet: &[u8]) -> Option<&[u8]> {
+    let mut offset = 0;
+    while offset < packet.len() {
+        let header = packet[offset];
+        let has_extension = header & 0b100 != 0;
+        let mut end = offset + 1 + has_extension as usize;
+        // rav1e writes every OBU with its size, as LEB128
+        let mut size = 0;
+        for shift in (0..56).step_by(7) {
+            let byte = *packet.get(end)?;
+            end += 1;
+            size |= ((byte & 0x7F) as usize) << shift;
+            if byte & 0x80 == 0 {
+                break;
+            }
+        }
+        end += size;
+        if (header >> 3) & 0xF == OBU_SEQUENCE_HEADER {
+            return packet.get(offset..end);
+        }
+        offset = end;
+    }
+    None
+}
+
+/// BT.709 limited-range Y at full size and Cb, Cr at half size in each direction, each from the
+/// average of its 2x2 block
+#[cfg(feature = "video")]
+fn rgb_to_yuv420(img: &RgbImage, planes: &mut [Vec<u8>; 3]) {
+    const KR: f32 = 0.2126;
+    const KB: f32 = 0.0722;
+    let luma = |[r, g, b]: [f32; 3]| KR * r + (1.0 - KR - KB) * g + KB * b;
+
+    let (width, height) = img.dimensions();
+    let [y_plane, cb_plane, cr_plane] = planes;
+    y_plane.clear();
+    y_plane.extend(img.pixels().map(|pixel| {
+        let rgb = pixel.0.map(|channel| channel as f32 / 255.0);
+        (16.0 + 219.0 * luma(rgb)).round() as u8
+    }));
+
+    cb_plane.clear();
+    cr_plane.clear();
+    for y in (0..height).step_by(2) {
+        for x in (0..width).step_by(2) {
+            let mut sum = [0.0f32; 3];
+            let mut count = 0.0;
+            for (bx, by) in [(x, y), (x + 1, y), (x, y + 1), (x + 1, y + 1)] {
+                if bx < width && by < height {
+                    let pixel = img.get_pixel(bx, by);
+                    for channel in 0..3 {
+                        sum[channel] += pixel[channel] as f32 / 255.0;
+                    }
+                    count += 1.0;
+                }
+            }
+            let rgb = sum.map(|channel| channel / count);
+            let y = luma(rgb);
+            let cb = (rgb[2] - y) / (2.0 * (1.0 - KB));
+            let cr = (rgb[0] - y) / (2.0 * (1.0 - KR));
+            cb_plane.push((128.0 + 224.0 * cb).round().clamp(16.0, 240.0) as u8);
+            cr_plane.push((128.0 + 224.0 * cr).round().clamp(16.0, 240.0) as u8);
+        }
+    }
+}
+
+#[cfg(feature = "video")]
+fn element(out: &mut Vec<u8>, id: u32, data: &[u8]) {
+    write_id(out, id);
+    write_size(out, data.len() as u64);
+    out.extend_from_slice(data);
+}
+
+/// Unsigned integer element in as few bytes as hold it
+#[cfg(feature = "video")]
+fn uint(out: &mut Vec<u8>, id: u32, value: u64) {
+    let bytes = value.to_be_bytes();
+    let zeros = bytes.iter().take_while(|&&byte| byte == 0).count().min(7);
+    element(out, id, &bytes[zeros..]);
+}
+
+#[cfg(feature = "video")]
+fn write_id(out: &mut Vec<u8>, id: u32) {
+    let bytes = id.to_be_bytes();
+    let zeros = bytes.iter().take_while(|&&byte| byte == 0).count();
+    out.extend_from_slice(&bytes[zeros..]);
+}
+
+/// Variable-length size in as few bytes as hold it; a length whose value bits are all ones
+/// would read as unknown
+#[cfg(feature = "video")]
+fn write_size(out: &mut Vec<u8>, size: u64) {
+    let length = (1..=8)
+        .find(|&length| size < (1 << (7 * length)) - 1)
+        .expect("element sizes fit in 56 bits");
+    let marked = size | 1 << (7 * length);
+    out.extend_from_slice(&marked.to_be_bytes()[8 - length..]);
+}
@@ -0,0 +1,124 @@
+//! Minimal zip archive writer for bundling already-compressed files such as PNG tiles. Entries
+//! are stored uncompressed (deflating PNG or JPEG data again gains next to nothing) and carry a
+//! fixed timestamp so the same input always produces the same archive.
+
+use flate2::Crc;
+
+const LOCAL_HEADER_SIGNATURE: u32 = 0x0403_4b50;
+const CENTRAL_HEADER_SIGNATURE: u32 = 0x0201_4b50;
+const END_OF_CENTRAL_DIRECTORY_SIGNATURE: u32 = 0x0605_4b50;
+
+/// Version 2.0, the first with directories and the baseline every reader supports
+const ZIP_VERSION: u16 = 20;
+
+/// General purpose flag bit 11: names are UTF-8
+const UTF8_NAMES: u16 = 1 << 11;
+
+const METHOD_STORED: u16 = 0;
+
+/// MS-DOS date of 1980-01-01, the earliest a zip can record; the time is midnight
+const DOS_DATE: u16 = (1 << 5) | 1;
+const DOS_TIME: u16 = 0;
+
+/// Zip archive built in memory. Without the zip64 extensions an archive holds at most 65535
+/// entries and 4 GiB, which `add` and `finish` check.
+#[derive(Default)]
+pub struct ZipWriter {
+    /// Local headers and file data, in order
+    data: Vec<u8>,
+    /// Central directory records, written after the data by `finish`
+    central_directory: Vec<u8>,
+    entries: u16,
+}
+
+impl ZipWriter {
+    /// Append a file; `name` uses `/` to separate directories
+    pub fn add(&mut self, name: &str, contents: &[u8]) -> Result<(), String> {
+        let offset = u32::try_from(self.data.len())
+            .map_err(|_| "Zip archive would exceed 4 GiB.".to_string())?;
+        let size = u32::try_from(contents.len())
+            .map_err(|_| format!("Zip entry {} would exceed 4 GiB.", name))?;
+        let name_length = u16::try_from(name.len())
+            .map_err(|_| format!("Zip entry name {} is too long.", name))?;
+        self.entries = self
+            .entries
+            .checked_add(1)
+            .ok_or_else(|| "Zip archive would exceed 65535 entries.".to_string())?;
+
+        let mut crc = Crc::new();
+        crc.update(contents);
+        let crc = crc.sum();
+
+        let local = &mut self.data;
+        put_u32(local, LOCAL_HEADER_SIGNATURE);
+        put_u16(local, ZIP_VERSION);
+        put_u16(local, UTF8_NAMES);
+        put_u16(local, METHOD_STORED);
+        put_u16(local, DOS_TIME);
+        put_u16(local, DOS_DATE);
+        put_u32(local, crc);
+        put_u32(local, size); // compressed
+        put_u32(local, size); // uncompressed
+        put_u16(local, name_length);
+        put_u16(local, 0); // extra field length
+        local.extend_from_slice(name.as_bytes());
+        local.extend_from_slice(contents);
+
+        let central = &mut self.central_directory;
+        put_u32(central, CENTRAL_HEADER_SIGNATURE);
+        put_u16(central, ZIP_VERSION); // made by
+        put_u16(central, ZIP_VERSION); // needed to extract
+        put_u16(central, UTF8_NAMES);
+        put_u16(central, METHOD_STORED);
+        put_u16(central, DOS_TIME);
+        put_u16(central, DOS_DATE);
+        put_u32(central, crc);
+        put_u32(central, size);
+        put_u32(central, size);
+        put_u16(central, name_length);
+        put_u16(central, 0); // extra field length
+        put_u16(central, 0); // comment length
+        put_u16(central, 0); // starting disk
+        put_u16(central, 0); // internal attributes
+        put_u32(central, 0); // external attributes
+        put_u32(central, offset);
+        central.extend_from_slice(name.as_bytes());
+
+        Ok(())
+    }
+
+    /// The complete archive: the entries, the central directory and its end record
+    pub fn finish(self) -> Result<Vec<u8>, String> {
+        let ZipWriter {
+            mut data,
+            central_directory,
+            entries,
+        } = self;
+        let too_large = |_| "Zip archive would exceed 4 GiB.".to_string();
+        let directory_offset = u32::try_from(data.len()).map_err(too_large)?;
+        let directory_size = u32::try_from(central_directory.len()).map_err(too_large)?;
+        directory_offset
+            .checked_add(directory_size)
+            .ok_or_else(|| "Zip archive would exceed 4 GiB.".to_string())?;
+
+        data.extend_from_slice(&central_directory);
+        put_u32(&mut data, END_OF_CENTRAL_DIRECTORY_SIGNATURE);
+        put_u16(&mut data, 0); // this disk
+        put_u16(&mut data, 0); // disk with the central directory
+        put_u16(&mut data, entries); // entries on this disk
+        put_u16(&mut data, entries); // entries in total
+        put_u32(&mut data, directory_size);
+        put_u32(&mut data, directory_offset);
+        put_u16(&mut data, 0); // comment length
+
+        Ok(data)
+    }
+}
+
+fn put_u16(buffer: &mut Vec<u8>, value: u16) {
+    buffer.extend_from_slice(&value.to_le_bytes());
+}
+
+fn put_u32(buffer: &mut Vec<u8>, value: u32) {
+    buffer.extend_from_slice(&value.to_le_bytes());
+}
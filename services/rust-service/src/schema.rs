@@ -0,0 +1,108 @@
+//! Versioned JSON Schema for every JSON request and response body, generated from the Rust
+//! types so clients can generate typed bindings and validate before sending.
+
+use crate::analyze::AreaResponse;
+use crate::animate::{AnimateOptions, ZoomAnimationOptions};
+use crate::api_v2::FractalRequestV2;
+use crate::compare::{BackendComparisonQuery, CompareRequest, CompareResponse};
+use crate::crop::CropOptions;
+use crate::deep_zoom::DeepZoomOptions;
+use crate::deprecation::LegacyUsageReport;
+use crate::estimate::{EstimateOptions, EstimateResponse};
+use crate::explore::{ExploreOptions, ExploreResponse};
+use crate::flame::FlameRequest;
+use crate::health::ReadinessResponse;
+use crate::image_json::ImageJsonResponse;
+use crate::iteration_matrix::{IterationMatrixOptions, IterationMatrixResponse};
+use crate::jobs::{RecentJobsQuery, RecentJobsResponse};
+use crate::manifest::{DecodedMetadata, Manifest, VerifyResponse};
+use crate::montage::MontageRequest;
+use crate::palettes::PaletteListing;
+use crate::query::FractalQuery;
+use crate::sonify::SonifyOptions;
+use crate::streaming::{ControlMessage, PaletteStreamOptions, ZoomStreamOptions};
+use crate::tool_server::{RpcRequest, RpcResponse};
+use crate::usage::{ResetQuery, ResetResponse, UsageQuery, UsageReport};
+use crate::{
+    ErrorResponse, HealthResponse, InfoResponse, OutputOptions, StatsOptions, StatsResponse,
+    ValidateResponse,
+};
+use axum::{http::StatusCode, response::IntoResponse};
+use schemars::gen::{SchemaGenerator, SchemaSettings};
+use serde_json::{json, Value};
+use std::sync::OnceLock;
+
+/// Bump when a body changes incompatibly, and serve the new schema under a new path
+pub const SCHEMA_VERSION: &str = "v1";
+
+static SCHEMA: OnceLock<Value> = OnceLock::new();
+
+fn build_schema() -> Value {
+    let mut generator = SchemaGenerator::new(SchemaSettings::draft07());
+
+    // Query-string parameters use the same shapes as JSON bodies
+    let requests = json!({
+        "fractal_query": generator.subschema_for::<FractalQuery>(),
+        "fractal_request_v2": generator.subschema_for::<FractalRequestV2>(),
+        "output_options": generator.subschema_for::<OutputOptions>(),
+        "stats_options": generator.subschema_for::<StatsOptions>(),
+        "explore_options": generator.subschema_for::<ExploreOptions>(),
+        "zoom_stream_options": generator.subschema_for::<ZoomStreamOptions>(),
+        "palette_stream_options": generator.subschema_for::<PaletteStreamOptions>(),
+        "manifest": generator.subschema_for::<Manifest>(),
+        "montage_request": generator.subschema_for::<MontageRequest>(),
+        "flame_request": generator.subschema_for::<FlameRequest>(),
+        "compare_request": generator.subschema_for::<CompareRequest>(),
+        "backend_comparison_query": generator.subschema_for::<BackendComparisonQuery>(),
+        "crop_options": generator.subschema_for::<CropOptions>(),
+        "deep_zoom_options": generator.subschema_for::<DeepZoomOptions>(),
+        "iteration_matrix_options": generator.subschema_for::<IterationMatrixOptions>(),
+        "estimate_options": generator.subschema_for::<EstimateOptions>(),
+        "sonify_options": generator.subschema_for::<SonifyOptions>(),
+        "animate_options": generator.subschema_for::<AnimateOptions>(),
+        "zoom_animation_options": generator.subschema_for::<ZoomAnimationOptions>(),
+        "rpc_request": generator.subschema_for::<RpcRequest>(),
+        "recent_jobs_query": generator.subschema_for::<RecentJobsQuery>(),
+        "usage_query": generator.subschema_for::<UsageQuery>(),
+        "usage_reset_query": generator.subschema_for::<ResetQuery>(),
+    });
+    let responses = json!({
+        "health": generator.subschema_for::<HealthResponse>(),
+        "readiness": generator.subschema_for::<ReadinessResponse>(),
+        "info": generator.subschema_for::<InfoResponse>(),
+        "stats": generator.subschema_for::<StatsResponse>(),
+        "image_json": generator.subschema_for::<ImageJsonResponse>(),
+        "validation": generator.subschema_for::<ValidateResponse>(),
+        "explore": generator.subschema_for::<ExploreResponse>(),
+        "zoom_stream_message": generator.subschema_for::<ControlMessage>(),
+        "manifest_verification": generator.subschema_for::<VerifyResponse>(),
+        "decoded_metadata": generator.subschema_for::<DecodedMetadata>(),
+        "comparison": generator.subschema_for::<CompareResponse>(),
+        "estimate": generator.subschema_for::<EstimateResponse>(),
+        "area": generator.subschema_for::<AreaResponse>(),
+        "iteration_matrix": generator.subschema_for::<IterationMatrixResponse>(),
+        "recent_jobs": generator.subschema_for::<RecentJobsResponse>(),
+        "rpc_response": generator.subschema_for::<RpcResponse>(),
+        "legacy_usage": generator.subschema_for::<LegacyUsageReport>(),
+        "usage": generator.subschema_for::<UsageReport>(),
+        "usage_reset": generator.subschema_for::<ResetResponse>(),
+        "palettes": generator.subschema_for::<PaletteListing>(),
+        "error": generator.subschema_for::<ErrorResponse>(),
+    });
+
+    json!({
+        "$schema": "http://json-schema.org/draft-07/schema#",
+        "$id": format!("/api/schema/{}", SCHEMA_VERSION),
+        "version": SCHEMA_VERSION,
+        "service_version": env!("CARGO_PKG_VERSION"),
+        "requests": requests,
+        "responses": responses,
+        "definitions": generator.definitions(),
+    })
+}
+
+// Schema endpoint; the document is generated once and cached
+pub async fn schema_v1() -> impl IntoResponse {
+    let schema = SCHEMA.get_or_init(build_schema);
+    (StatusCode::OK, axum::Json(schema.clone()))
+}
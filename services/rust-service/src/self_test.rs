@@ -0,0 +1,199 @@
+//! `rust-service --self-test`: render a tiny image of every fractal type and compare its pixel
+//! hash with the reference recorded for this build. Exits nonzero if any type fails to render
+//! or renders differently, so it works as a container healthcheck or post-deploy smoke test.
+//!
+//! After an intentional change to a renderer, replace its entry in `REFERENCES` with the hash
+//! the self-test reports.
+
+use crate::fractals::traits::FractalParams;
+use crate::fractals::{create_fractal, FRACTAL_TYPES};
+use crate::manifest::image_hash;
+use std::time::Instant;
+
+const WIDTH: u32 = 64;
+const HEIGHT: u32 = 48;
+
+/// Three half-scale copies: the Sierpinski triangle as a custom IFS
+const SIERPINSKI_IFS: &str = r#"[
+    {"coefficients": [0.5, 0, 0, 0.5, 0, 0], "probability": 1},
+    {"coefficients": [0.5, 0, 0, 0.5, 0.5, 0], "probability": 1},
+    {"coefficients": [0.5, 0, 0, 0.5, 0.25, 0.5], "probability": 1}
+]"#;
+
+/// SHA-256 of the raw RGB pixels of each type's self-test render
+const REFERENCES: &[(&str, &str)] = &[
+    (
+        "mandelbrot",
+        "40e9833588459473bf87b04d6d5caecf6bcbffda03e89b90ae8f3b67ac8694fb",
+    ),
+    (
+        "julia",
+        "6180bb18613bb3c751d80b3d611491bd23a6192fb55aa7b49e46a44173fc09b0",
+    ),
+    (
+        "sierpinski",
+        "ad01dae836745b64e725d09bbeeb49fe4a69609ea5477615df45528586e890b0",
+    ),
+    (
+        "koch",
+        "e5209cb867fe0c500b59049fd17e8c9b7121c91a7429f45b8e2018ca2f5132b9",
+    ),
+    (
+        "dragon",
+        "dccbce0caf5a29047d53ca9cb6a53656e70e80806ac12d539fb5920c184f00e4",
+    ),
+    (
+        "hilbert",
+        "7ed8f7d217ee5ba87be63624cd557dabdc2b0daf22910bbadb041578f70e5e66",
+    ),
+    (
+        "levy",
+        "849be1a8b171b1b0ac7fe2590a2fb06511b0eb682f1cd9b6c5a348e638a401b4",
+    ),
+    (
+        "newton",
+        "3bc431729572f0afb0f47f97487af70c6f6251ded1cf20e1526a9457c2126586",
+    ),
+    (
+        "nova",
+        "c16095130e2ae66edfddcb3f5f1fc5a2c2cafe7762111b0a93aeffde4ebaf74c",
+    ),
+    (
+        "magnet1",
+        "e8373f0136b16dfc04d26866af9d4d44eae8e440f29486f4a0fe808b2965c0f7",
+    ),
+    (
+        "magnet2",
+        "6c2cd1bbd2c0756798421a7c7d256e96de0cdde180200ad0205744b732b6d9cd",
+    ),
+    (
+        "lyapunov",
+        "29d502df24d8d9ef9acdfae0e3f235455aa48393645585405a7b71167d119fb7",
+    ),
+    (
+        "buddhabrot",
+        "b9bc7a4f0356d1738fc776ea7f5cc903aa1b2bcfdfa86356dc93bc8b7e900e35",
+    ),
+    (
+        "nebulabrot",
+        "7aeeef495f9b5f3a0b1c371f6c5634905415da6dd5505a3fef49a7436329ad97",
+    ),
+    (
+        "barnsley",
+        "d290a215a7a4973dee4f875c46d7d1767a58ba95943e2d47433b89aed8561b93",
+    ),
+    (
+        "ifs",
+        "3f34da99fdceed463f1b245d66f47fbfffebf0e609a82aa8bb8a25fbf3949e16",
+    ),
+    (
+        "lsystem",
+        "83da9c249d03a5110eef66655e77ff19886ba8a2f0d6e5b5c39d90289add8c9d",
+    ),
+    (
+        "vicsek",
+        "c285351c076c9c7e9fb635a3dd3acb8c4df31f82e0b11d607b5f378ccedd06af",
+    ),
+    (
+        "carpet",
+        "e693dd224580593bcc12395464a27f626ff280fb9a2874338be19ce59271169b",
+    ),
+    (
+        "apollonian",
+        "67dcab29102595cc755be5ed8ab14ce3ea50ca028be6522b74146cea31189a61",
+    ),
+    (
+        "htree",
+        "f0ff1ae1f4d2329001ee7ceef836da71657486f58f2a9bccce5e66581b628391",
+    ),
+    (
+        "attractor",
+        "b16f0436007123d3ec724918a851d2a49df6265c3748f5cb4775dc2bd9195cbc",
+    ),
+    (
+        "bifurcation",
+        "734d6a1371b4bc9ce57c4ddcd47ac1ebe4aee7c5b588c742d8435045f415fcd7",
+    ),
+    (
+        "plasma",
+        "d98e91c6ec7686ccb02d339b47eec89b62d3fb7e2cdc9ef6d63f609baeba6290",
+    ),
+    (
+        "custom",
+        "fefb71152d2f04f44011d7ccd52aafdbaf1819df7e1b477bb4f08adc097389de",
+    ),
+    (
+        "hybrid",
+        "1fe4f96a3b4706a74af5958ddd6b85804a6407b588e30832585a448202729352",
+    ),
+];
+
+/// Parameters for a type's test render: the defaults at a tiny size, plus whatever the type
+/// can't render without
+fn test_params(fractal_type: &str) -> FractalParams {
+    let mut params = FractalParams {
+        width: WIDTH,
+        height: HEIGHT,
+        ..FractalParams::default()
+    };
+    match fractal_type {
+        "julia" => {
+            params.julia_c_real = Some(-0.7);
+            params.julia_c_imag = Some(0.27);
+        }
+        "ifs" => params.ifs_transforms = Some(SIERPINSKI_IFS.to_string()),
+        _ => {}
+    }
+    params
+}
+
+/// Render and check every type, printing one line each; returns the process exit code
+pub fn run() -> i32 {
+    let mut failures = 0;
+
+    for &fractal_type in FRACTAL_TYPES {
+        let started = Instant::now();
+        let result = create_fractal(fractal_type)
+            .ok_or_else(|| "not registered".to_string())
+            .and_then(|fractal| fractal.generate(test_params(fractal_type)))
+            .map(|img| image_hash(&img));
+        let elapsed_ms = started.elapsed().as_secs_f64() * 1000.0;
+
+        let reference = REFERENCES
+            .iter()
+            .find(|(name, _)| *name == fractal_type)
+            .map(|(_, hash)| *hash);
+        match (result, reference) {
+            (Ok(hash), Some(reference)) if hash == reference => {
+                println!("ok       {:<12} {:>8.1} ms", fractal_type, elapsed_ms);
+            }
+            (Ok(hash), Some(reference)) => {
+                failures += 1;
+                println!(
+                    "MISMATCH {:<12} expected {} got {}",
+                    fractal_type, reference, hash
+                );
+            }
+            (Ok(hash), None) => {
+                failures += 1;
+                println!("MISSING  {:<12} no reference; got {}", fractal_type, hash);
+            }
+            (Err(e), _) => {
+                failures += 1;
+                println!("FAILED   {:<12} {}", fractal_type, e);
+            }
+        }
+    }
+
+    if failures == 0 {
+        println!("Self-test passed: {} fractal types", FRACTAL_TYPES.len());
+        0
+    } else {
+        println!(
+            "Self-test failed: {} of {} fractal types",
+            failures,
+            FRACTAL_TYPES.len()
+        );
+        1
+    }
+}
@@ -0,0 +1,199 @@
+//! Sonification: escape-time data played as sound, for listeners who can't see the image or to
+//! hear how an orbit behaves. `scanline` plays the escape times along the middle row of the
+//! view from left to right, slower escapes pitched higher and points inside the set as rests.
+//! `orbit` plays |z| at each step of the orbit of the view's center point until it escapes.
+
+use crate::audio::{encode_wav, pitch, synthesize};
+use crate::fractals::create_fractal;
+use crate::fractals::julia::julia_iterations;
+use crate::fractals::kernels::{mandelbrot_row, orbit, MandelbrotVariant};
+use crate::fractals::traits::{default_validate_params, FractalParams};
+use crate::query::FractalQuery;
+use crate::tuning;
+use crate::utils::validation::{validate_julia_params, validate_sonify};
+use crate::ErrorResponse;
+use axum::{
+    extract::Query,
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+};
+use schemars::JsonSchema;
+use serde::Deserialize;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum SonifyMode {
+    /// Escape times along the middle row of the view
+    #[default]
+    Scanline,
+    /// |z| along the orbit of the view's center point
+    Orbit,
+}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct SonifyOptions {
+    mode: Option<SonifyMode>,
+    /// Points along the scanline, or the most orbit steps to play (default 64)
+    notes: Option<u32>,
+    /// Length of each note in milliseconds (default 120)
+    note_ms: Option<u32>,
+    /// Pitch of the lowest value in Hz (default 220)
+    min_freq: Option<f64>,
+    /// Pitch of the highest value in Hz (default 880)
+    max_freq: Option<f64>,
+    /// Samples per second (default 22050)
+    sample_rate: Option<u32>,
+}
+
+/// The map being iterated: a Mandelbrot family member over c, or a Julia set with fixed c
+#[derive(Clone, Copy, Debug)]
+enum Map {
+    Mandelbrot(MandelbrotVariant),
+    Julia(f64, f64),
+}
+
+impl Map {
+    fn from_query(query: &FractalQuery) -> Result<Self, String> {
+        match query.fractal_type().to_lowercase().as_str() {
+            "mandelbrot" => Ok(Map::Mandelbrot(match &query.variant {
+                Some(variant) => MandelbrotVariant::parse(variant)?,
+                None => MandelbrotVariant::Classic,
+            })),
+            "julia" => match (query.julia_c_real, query.julia_c_imag) {
+                (Some(c_real), Some(c_imag)) => {
+                    validate_julia_params(c_real, c_imag)?;
+                    Ok(Map::Julia(c_real, c_imag))
+                }
+                _ => Err("julia_c_real and julia_c_imag are required for Julia set".to_string()),
+            },
+            other => Err(format!(
+                "Invalid type {}. Sonification supports mandelbrot and julia.",
+                other
+            )),
+        }
+    }
+}
+
+/// Escape times at `notes` points across the middle row of the view
+fn scanline(map: Map, fractal_type: &str, params: &FractalParams, notes: u32) -> Vec<u32> {
+    let Some(view) = create_fractal(fractal_type).and_then(|fractal| fractal.plane_view()) else {
+        return Vec::new();
+    };
+    let bounds = view.bounds(params);
+    let dx = (bounds.x_max - bounds.x_min) / notes as f64;
+    let cy = (bounds.y_min + bounds.y_max) / 2.0;
+    let max_iterations = params.max_iterations;
+
+    match map {
+        Map::Mandelbrot(variant) => mandelbrot_row(
+            tuning::current().kernel,
+            variant,
+            bounds.x_min,
+            dx,
+            cy,
+            notes,
+            max_iterations,
+        ),
+        Map::Julia(c_real, c_imag) => (0..notes)
+            .map(|x| {
+                let zx = bounds.x_min + x as f64 * dx;
+                julia_iterations(zx, cy, c_real, c_imag, max_iterations)
+            })
+            .collect(),
+    }
+}
+
+/// Frequencies (or rests) to play
+fn compose(
+    query: FractalQuery,
+    mode: SonifyMode,
+    notes: u32,
+    (min_freq, max_freq): (f64, f64),
+) -> Result<Vec<Option<f64>>, String> {
+    let map = Map::from_query(&query)?;
+    let fractal_type = query.fractal_type().to_lowercase();
+    let params = query.into_params();
+    default_validate_params(&params)?;
+
+    let tones = match mode {
+        SonifyMode::Scanline => {
+            // Escape times spread over the pitch range on a log scale, like the palettes
+            let max_iterations = params.max_iterations;
+            let range = (1.0 + max_iterations as f64).ln();
+            scanline(map, &fractal_type, &params, notes)
+                .into_iter()
+                .map(|iterations| {
+                    (iterations < max_iterations).then(|| {
+                        let fraction = (1.0 + iterations as f64).ln() / range;
+                        pitch(fraction, min_freq, max_freq)
+                    })
+                })
+                .collect()
+        }
+        SonifyMode::Orbit => {
+            let point = (params.center_x, params.center_y);
+            let steps = notes.min(params.max_iterations);
+            let points = match map {
+                Map::Mandelbrot(variant) => orbit(variant, (0.0, 0.0), point, steps),
+                Map::Julia(c_real, c_imag) => {
+                    orbit(MandelbrotVariant::Classic, point, (c_real, c_imag), steps)
+                }
+            };
+            // |z| stays within the escape radius 2 while the orbit is playing
+            points
+                .into_iter()
+                .map(|(x, y)| Some(pitch(x.hypot(y) / 2.0, min_freq, max_freq)))
+                .collect()
+        }
+    };
+
+    Ok(tones)
+}
+
+// Sonification endpoint: WAV audio of a scanline or an orbit
+pub async fn sonify(
+    Query(query): Query<FractalQuery>,
+    Query(options): Query<SonifyOptions>,
+) -> Response {
+    let mode = options.mode.unwrap_or_default();
+    let notes = options.notes.unwrap_or(64);
+    let note_ms = options.note_ms.unwrap_or(120);
+    let min_freq = options.min_freq.unwrap_or(220.0);
+    let max_freq = options.max_freq.unwrap_or(880.0);
+    let sample_rate = options.sample_rate.unwrap_or(22050);
+
+    if let Err(error) = validate_sonify(notes, note_ms, min_freq, max_freq, sample_rate) {
+        return (StatusCode::BAD_REQUEST, axum::Json(ErrorResponse { error })).into_response();
+    }
+
+    // Iterating and synthesizing are CPU-bound, keep them off the async workers
+    let result = tokio::task::spawn_blocking(move || {
+        let tones = compose(query, mode, notes, (min_freq, max_freq))?;
+        let samples = synthesize(&tones, note_ms as f64 / 1000.0, sample_rate);
+        Ok::<_, String>((tones.len(), encode_wav(&samples, sample_rate)))
+    })
+    .await
+    .unwrap_or_else(|e| Err(format!("Sonification task failed: {}", e)));
+
+    match result {
+        Ok((notes, wav)) => (
+            StatusCode::OK,
+            [
+                (header::CONTENT_TYPE, "audio/wav".to_string()),
+                (
+                    header::CONTENT_DISPOSITION,
+                    "inline; filename=\"fractal.wav\"".to_string(),
+                ),
+                (
+                    header::HeaderName::from_static("x-sonify-notes"),
+                    notes.to_string(),
+                ),
+            ],
+            wav,
+        )
+            .into_response(),
+        Err(error) => {
+            (StatusCode::BAD_REQUEST, axum::Json(ErrorResponse { error })).into_response()
+        }
+    }
+}
@@ -0,0 +1,397 @@
+//! WebSocket animation streams with tile-delta compression: zooms, and palette crossfades over
+//! fixed geometry. After the first (key) frame only the tiles that changed since what the client
+//! already holds are sent. See the README for the wire protocol.
+
+use crate::fractals::traits::FractalParams;
+use crate::pipeline::{render, AppState, RenderError, RenderOptions};
+use crate::query::FractalQuery;
+use crate::rendering::colors::mix;
+use crate::utils::validation::{validate_palette_stream, validate_zoom_stream};
+use crate::ErrorResponse;
+use axum::{
+    extract::{
+        ws::{Message, WebSocket, WebSocketUpgrade},
+        Query, State,
+    },
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+};
+use flate2::{write::ZlibEncoder, Compression};
+use image::{imageops, RgbImage};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::sync::Arc;
+
+/// Binary message type tag for a frame update
+const FRAME_MESSAGE: u8 = 1;
+
+#[derive(Deserialize, JsonSchema)]
+pub struct ZoomStreamOptions {
+    frames: Option<u32>,
+    /// Zoom multiplier applied between consecutive frames
+    zoom_factor: Option<f64>,
+    tile_size: Option<u32>,
+    /// Mean absolute per-channel difference below which a tile counts as unchanged
+    threshold: Option<u8>,
+}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct PaletteStreamOptions {
+    frames: Option<u32>,
+    /// Color scheme of the last frame; the first uses color_scheme
+    to_color_scheme: String,
+    tile_size: Option<u32>,
+    /// Mean absolute per-channel difference below which a tile counts as unchanged
+    threshold: Option<u8>,
+}
+
+#[derive(Serialize, JsonSchema)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum ControlMessage {
+    Init {
+        width: u32,
+        height: u32,
+        tile_size: u32,
+        columns: u32,
+        rows: u32,
+        frames: u32,
+    },
+    End {
+        frames: u32,
+        bytes_sent: usize,
+        /// Size of the frames as raw RGB8, for comparison
+        raw_frame_bytes: usize,
+    },
+    Error {
+        /// HTTP status the same error gets from the REST API (400, 429, 500, ...)
+        status: u16,
+        error: String,
+    },
+}
+
+impl From<RenderError> for ControlMessage {
+    fn from(error: RenderError) -> Self {
+        ControlMessage::Error {
+            status: error.status().as_u16(),
+            error: error.message(),
+        }
+    }
+}
+
+/// What changes from frame to frame
+enum Animation {
+    /// Zoom multiplier between consecutive frames
+    Zoom(f64),
+    /// Crossfade from the requested color_scheme to this one, geometry fixed
+    Palette(String),
+}
+
+/// How each frame is produced once the stream is set up
+enum FrameSource {
+    /// Rendered at a zoom multiplied by this factor per frame
+    Zoom(f64),
+    /// Mixed from the two end frames
+    Crossfade(RgbImage, RgbImage),
+}
+
+struct StreamSettings {
+    frames: u32,
+    animation: Animation,
+    tile_size: u32,
+    threshold: u8,
+}
+
+/// Tiles the client currently holds; updated only with what we actually send so lossy
+/// thresholds never accumulate drift
+struct TileDeltaEncoder {
+    tile_size: u32,
+    threshold: u8,
+    client_view: Option<RgbImage>,
+}
+
+impl TileDeltaEncoder {
+    fn new(tile_size: u32, threshold: u8) -> Self {
+        Self {
+            tile_size,
+            threshold,
+            client_view: None,
+        }
+    }
+
+    /// Encode a frame message containing every tile that differs from the client's copy
+    fn encode_frame(&mut self, index: u32, frame: &RgbImage) -> Result<Vec<u8>, String> {
+        let (width, height) = frame.dimensions();
+        let columns = width.div_ceil(self.tile_size);
+        let rows = height.div_ceil(self.tile_size);
+
+        let client_view = self
+            .client_view
+            .get_or_insert_with(|| RgbImage::new(width, height));
+        let keyframe = index == 0;
+
+        let mut tiles = Vec::new();
+        let mut pixels = ZlibEncoder::new(Vec::new(), Compression::fast());
+        for row in 0..rows {
+            for column in 0..columns {
+                let x = column * self.tile_size;
+                let y = row * self.tile_size;
+                let tile_width = self.tile_size.min(width - x);
+                let tile_height = self.tile_size.min(height - y);
+
+                let current = imageops::crop_imm(frame, x, y, tile_width, tile_height).to_image();
+                if !keyframe {
+                    let previous =
+                        imageops::crop_imm(client_view, x, y, tile_width, tile_height).to_image();
+                    if mean_difference(&current, &previous) <= self.threshold as f64 {
+                        continue;
+                    }
+                }
+
+                imageops::replace(client_view, &current, x as i64, y as i64);
+                pixels
+                    .write_all(current.as_raw())
+                    .map_err(|e| format!("Failed to compress tile: {}", e))?;
+                tiles.push((column as u16, row as u16));
+            }
+        }
+
+        let compressed = pixels
+            .finish()
+            .map_err(|e| format!("Failed to compress frame: {}", e))?;
+
+        let mut message = Vec::with_capacity(7 + tiles.len() * 4 + compressed.len());
+        message.push(FRAME_MESSAGE);
+        message.extend_from_slice(&index.to_le_bytes());
+        message.extend_from_slice(&(tiles.len() as u16).to_le_bytes());
+        for (column, row) in tiles {
+            message.extend_from_slice(&column.to_le_bytes());
+            message.extend_from_slice(&row.to_le_bytes());
+        }
+        message.extend_from_slice(&compressed);
+
+        Ok(message)
+    }
+}
+
+fn mean_difference(a: &RgbImage, b: &RgbImage) -> f64 {
+    let total: u64 = a
+        .as_raw()
+        .iter()
+        .zip(b.as_raw())
+        .map(|(x, y)| x.abs_diff(*y) as u64)
+        .sum();
+    total as f64 / a.as_raw().len().max(1) as f64
+}
+
+/// Each pixel `fraction` of the way from `from` to `to`; for two renders of the same geometry
+/// this is the render with the palettes interpolated
+fn crossfade(from: &RgbImage, to: &RgbImage, fraction: f64) -> RgbImage {
+    let mut frame = from.clone();
+    for (pixel, target) in frame.pixels_mut().zip(to.pixels()) {
+        pixel.0 = mix(pixel.0, target.0, fraction);
+    }
+    frame
+}
+
+// WebSocket zoom stream endpoint
+pub async fn zoom_stream(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Query(query): Query<FractalQuery>,
+    Query(options): Query<ZoomStreamOptions>,
+    ws: WebSocketUpgrade,
+) -> Response {
+    let frames = options.frames.unwrap_or(60);
+    let zoom_factor = options.zoom_factor.unwrap_or(1.05);
+    let tile_size = options.tile_size.unwrap_or(32);
+    let fractal_type = query.fractal_type();
+    let params = query.into_params();
+
+    if let Err(error) =
+        validate_zoom_stream(frames, zoom_factor, params.width, params.height, tile_size)
+    {
+        return (StatusCode::BAD_REQUEST, axum::Json(ErrorResponse { error })).into_response();
+    }
+
+    let settings = StreamSettings {
+        frames,
+        animation: Animation::Zoom(zoom_factor),
+        tile_size,
+        threshold: options.threshold.unwrap_or(0),
+    };
+    let render_options = RenderOptions::billed_to(&headers);
+    ws.on_upgrade(move |socket| {
+        stream_frames(
+            socket,
+            state,
+            fractal_type,
+            params,
+            settings,
+            render_options,
+        )
+    })
+}
+
+// WebSocket palette crossfade endpoint
+pub async fn palette_stream(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Query(query): Query<FractalQuery>,
+    Query(options): Query<PaletteStreamOptions>,
+    ws: WebSocketUpgrade,
+) -> Response {
+    let frames = options.frames.unwrap_or(30);
+    let tile_size = options.tile_size.unwrap_or(32);
+    let fractal_type = query.fractal_type();
+    let params = query.into_params();
+
+    if let Err(error) = validate_palette_stream(
+        frames,
+        &options.to_color_scheme,
+        params.width,
+        params.height,
+        tile_size,
+    ) {
+        return (StatusCode::BAD_REQUEST, axum::Json(ErrorResponse { error })).into_response();
+    }
+
+    let settings = StreamSettings {
+        frames,
+        animation: Animation::Palette(options.to_color_scheme),
+        tile_size,
+        threshold: options.threshold.unwrap_or(0),
+    };
+    let render_options = RenderOptions::billed_to(&headers);
+    ws.on_upgrade(move |socket| {
+        stream_frames(
+            socket,
+            state,
+            fractal_type,
+            params,
+            settings,
+            render_options,
+        )
+    })
+}
+
+async fn render_frame(
+    state: &Arc<AppState>,
+    fractal_type: &str,
+    params: FractalParams,
+    render_options: &RenderOptions,
+) -> Result<RgbImage, RenderError> {
+    // Rendering is CPU-bound, keep it off the async workers
+    let frame_state = state.clone();
+    let frame_type = fractal_type.to_string();
+    let frame_options = render_options.clone();
+    tokio::task::spawn_blocking(move || {
+        render(&frame_state, &frame_type, params, &frame_options).map(|(img, _)| img)
+    })
+    .await
+    .unwrap_or_else(|e| Err(RenderError::Internal(format!("Render task failed: {}", e))))
+}
+
+async fn stream_frames(
+    mut socket: WebSocket,
+    state: Arc<AppState>,
+    fractal_type: String,
+    base_params: FractalParams,
+    settings: StreamSettings,
+    render_options: RenderOptions,
+) {
+    let mut encoder = TileDeltaEncoder::new(settings.tile_size, settings.threshold);
+
+    let init = ControlMessage::Init {
+        width: base_params.width,
+        height: base_params.height,
+        tile_size: settings.tile_size,
+        columns: base_params.width.div_ceil(settings.tile_size),
+        rows: base_params.height.div_ceil(settings.tile_size),
+        frames: settings.frames,
+    };
+    if send_control(&mut socket, &init).await.is_err() {
+        return;
+    }
+
+    // A crossfade only needs its two end frames rendered
+    let source = match settings.animation {
+        Animation::Zoom(zoom_factor) => FrameSource::Zoom(zoom_factor),
+        Animation::Palette(to_color_scheme) => {
+            let to_params = FractalParams {
+                color_scheme: Some(to_color_scheme),
+                ..base_params.clone()
+            };
+            let from = render_frame(&state, &fractal_type, base_params.clone(), &render_options);
+            let ends = match from.await {
+                Ok(from) => render_frame(&state, &fractal_type, to_params, &render_options)
+                    .await
+                    .map(|to| FrameSource::Crossfade(from, to)),
+                Err(error) => Err(error),
+            };
+            match ends {
+                Ok(source) => source,
+                Err(error) => {
+                    let _ = send_control(&mut socket, &error.into()).await;
+                    return;
+                }
+            }
+        }
+    };
+
+    let mut bytes_sent = 0;
+
+    for index in 0..settings.frames {
+        let rendered = match &source {
+            FrameSource::Zoom(zoom_factor) => {
+                let params = FractalParams {
+                    zoom: base_params.zoom * zoom_factor.powi(index as i32),
+                    ..base_params.clone()
+                };
+                render_frame(&state, &fractal_type, params, &render_options).await
+            }
+            FrameSource::Crossfade(from, to) => {
+                let fraction = index as f64 / (settings.frames - 1).max(1) as f64;
+                Ok(crossfade(from, to, fraction))
+            }
+        };
+
+        let frame = match rendered {
+            Ok(frame) => frame,
+            Err(error) => {
+                let _ = send_control(&mut socket, &error.into()).await;
+                return;
+            }
+        };
+
+        let message = match encoder.encode_frame(index, &frame) {
+            Ok(message) => message,
+            Err(error) => {
+                let _ = send_control(&mut socket, &RenderError::Internal(error).into()).await;
+                return;
+            }
+        };
+
+        bytes_sent += message.len();
+        if socket.send(Message::Binary(message)).await.is_err() {
+            // Client went away
+            return;
+        }
+    }
+
+    let end = ControlMessage::End {
+        frames: settings.frames,
+        bytes_sent,
+        raw_frame_bytes: settings.frames as usize
+            * base_params.width as usize
+            * base_params.height as usize
+            * 3,
+    };
+    let _ = send_control(&mut socket, &end).await;
+    let _ = socket.close().await;
+}
+
+async fn send_control(socket: &mut WebSocket, message: &ControlMessage) -> Result<(), ()> {
+    let text = serde_json::to_string(message).map_err(|_| ())?;
+    socket.send(Message::Text(text)).await.map_err(|_| ())
+}
@@ -0,0 +1,186 @@
+//! Low-power mode: caps render threads and iteration budgets during configured hours,
+//! when host load is high, or when a client asks for it with `X-Power-Mode: low`.
+//!
+//! Configuration (all optional):
+//! - `LOW_POWER_HOURS`: UTC hour window such as `22-6`
+//! - `LOW_POWER_LOAD_THRESHOLD`: 1-minute load average that triggers the mode
+//! - `LOW_POWER_THREADS`: render threads while throttled (default 2)
+//! - `LOW_POWER_MAX_ITERATIONS`: iteration cap while throttled (default 500), Nebulabrot's
+//!   per-channel limits included
+//!
+//! All of them can be changed at runtime through the settings file (see `config`).
+
+use crate::config;
+use crate::fractals::nebulabrot::Nebulabrot;
+use crate::fractals::traits::FractalParams;
+use crate::utils::time::unix_now;
+use rayon::{ThreadPool, ThreadPoolBuilder};
+
+pub const POWER_MODE_HEADER: &str = "X-Power-Mode";
+
+const DEFAULT_THREADS: usize = 2;
+const DEFAULT_MAX_ITERATIONS: u32 = 500;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LowPowerReason {
+    Header,
+    Schedule,
+    Load,
+}
+
+impl LowPowerReason {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            LowPowerReason::Header => "header",
+            LowPowerReason::Schedule => "schedule",
+            LowPowerReason::Load => "load",
+        }
+    }
+}
+
+pub struct Throttle {
+    hours: Option<(u32, u32)>,
+    load_threshold: Option<f64>,
+    threads: usize,
+    max_iterations: u32,
+    pool: ThreadPool,
+}
+
+impl Throttle {
+    pub fn from_env() -> Self {
+        let hours = config::var("LOW_POWER_HOURS").and_then(|value| parse_hours(&value));
+        let load_threshold =
+            config::var("LOW_POWER_LOAD_THRESHOLD").and_then(|value| value.parse::<f64>().ok());
+        let threads = config::var("LOW_POWER_THREADS")
+            .and_then(|value| value.parse::<usize>().ok())
+            .filter(|threads| *threads > 0)
+            .unwrap_or(DEFAULT_THREADS);
+        let max_iterations = config::var("LOW_POWER_MAX_ITERATIONS")
+            .and_then(|value| value.parse::<u32>().ok())
+            .filter(|iterations| *iterations > 0)
+            .unwrap_or(DEFAULT_MAX_ITERATIONS);
+
+        let pool = ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .thread_name(|index| format!("low-power-{}", index))
+            .build()
+            .expect("Failed to build low-power thread pool");
+
+        Self {
+            hours,
+            load_threshold,
+            threads,
+            max_iterations,
+            pool,
+        }
+    }
+
+    /// Decide whether this request runs in low-power mode, and why
+    pub fn evaluate(&self, requested: bool) -> Option<LowPowerReason> {
+        if requested {
+            return Some(LowPowerReason::Header);
+        }
+
+        if let Some((start, end)) = self.hours {
+            let hour = current_utc_hour();
+            let in_window = if start <= end {
+                hour >= start && hour < end
+            } else {
+                // Window wraps past midnight, e.g. 22-6
+                hour >= start || hour < end
+            };
+            if in_window {
+                return Some(LowPowerReason::Schedule);
+            }
+        }
+
+        if let (Some(threshold), Some(load)) = (self.load_threshold, current_load()) {
+            if load > threshold {
+                return Some(LowPowerReason::Load);
+            }
+        }
+
+        None
+    }
+
+    /// Cap the iteration budget; returns headers describing the applied degradation
+    pub fn degrade(
+        &self,
+        fractal_type: &str,
+        params: &mut FractalParams,
+        reason: LowPowerReason,
+    ) -> Vec<(String, String)> {
+        let mut headers = vec![
+            (POWER_MODE_HEADER.to_string(), "low".to_string()),
+            ("X-Power-Reason".to_string(), reason.as_str().to_string()),
+            ("X-Thread-Cap".to_string(), self.threads.to_string()),
+        ];
+
+        if params.max_iterations > self.max_iterations {
+            headers.push((
+                "X-Degraded-Max-Iterations".to_string(),
+                format!(
+                    "{} (requested {})",
+                    self.max_iterations, params.max_iterations
+                ),
+            ));
+            params.max_iterations = self.max_iterations;
+        }
+
+        // Nebulabrot iterates each channel to its own limit, whatever max_iterations is
+        if fractal_type == "nebulabrot" {
+            let requested = Nebulabrot::channel_iterations(params);
+            if requested
+                .iter()
+                .any(|&iterations| iterations > self.max_iterations)
+            {
+                let [red, green, blue] =
+                    requested.map(|iterations| iterations.min(self.max_iterations));
+                headers.push((
+                    "X-Degraded-Channel-Iterations".to_string(),
+                    format!(
+                        "{},{},{} (requested {},{},{})",
+                        red, green, blue, requested[0], requested[1], requested[2]
+                    ),
+                ));
+                params.red_iterations = Some(red);
+                params.green_iterations = Some(green);
+                params.blue_iterations = Some(blue);
+            }
+        }
+
+        headers
+    }
+
+    /// Run a render on the capped thread pool
+    pub fn install<R: Send>(&self, render: impl FnOnce() -> R + Send) -> R {
+        self.pool.install(render)
+    }
+}
+
+fn parse_hours(value: &str) -> Option<(u32, u32)> {
+    let (start, end) = value.split_once('-')?;
+    let start = start.trim().parse::<u32>().ok().filter(|h| *h < 24);
+    let end = end.trim().parse::<u32>().ok().filter(|h| *h <= 24);
+    match (start, end) {
+        (Some(start), Some(end)) => Some((start, end)),
+        _ => {
+            tracing::warn!("Ignoring invalid LOW_POWER_HOURS={}", value);
+            None
+        }
+    }
+}
+
+fn current_utc_hour() -> u32 {
+    ((unix_now() / 3600) % 24) as u32
+}
+
+/// 1-minute load average (Linux only)
+fn current_load() -> Option<f64> {
+    std::fs::read_to_string("/proc/loadavg")
+        .ok()?
+        .split_whitespace()
+        .next()?
+        .parse()
+        .ok()
+}
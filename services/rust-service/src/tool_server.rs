@@ -0,0 +1,544 @@
+//! JSON-RPC 2.0 tool server following the Model Context Protocol `tools/*` methods,
+//! so AI assistants can request fractal renders with validated arguments.
+
+use crate::fractals::antialias::{Antialias, DEFAULT_AA_THRESHOLD};
+use crate::fractals::attractor::ATTRACTOR_MAPS;
+use crate::fractals::kernels::MANDELBROT_VARIANTS;
+use crate::fractals::vicsek::VICSEK_VARIANTS;
+use crate::fractals::FRACTAL_TYPES;
+use crate::palettes;
+use crate::pipeline::{render, AppState, RenderError, RenderOptions};
+use crate::query::FractalQuery;
+use crate::rendering::color_vision::ColorVisionDeficiency;
+use crate::rendering::colors::{ColorScheme, Coloring, DEFAULT_BOUNDARY_WIDTH, INTERPOLATIONS};
+use crate::rendering::density::ToneCurve;
+use crate::rendering::dither::Dither;
+use crate::rendering::encoder::{encode_image, EncodeOptions, OutputFormat};
+use crate::rendering::orbit_trap::{TrapShape, DEFAULT_STRIPE_DENSITY};
+use crate::rendering::shading::{Shading, DEFAULT_LIGHT_AZIMUTH, DEFAULT_LIGHT_ELEVATION};
+use crate::utils::expression::MAX_FORMULA_LENGTH;
+use crate::utils::validation::{MAX_EXPOSURE, MAX_IFS_TRANSFORMS, MAX_TRAP_EXTENT};
+use axum::{
+    extract::State,
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::sync::Arc;
+
+const PROTOCOL_VERSION: &str = "2024-11-05";
+const RENDER_TOOL: &str = "render_fractal";
+
+// Standard JSON-RPC error codes
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+const INTERNAL_ERROR: i64 = -32603;
+// Server-defined: the tenant's render budget is used up
+const QUOTA_EXCEEDED: i64 = -32000;
+
+#[derive(Deserialize, JsonSchema)]
+pub struct RpcRequest {
+    jsonrpc: String,
+    id: Option<Value>,
+    method: String,
+    #[serde(default)]
+    params: Value,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct RpcResponse {
+    jsonrpc: &'static str,
+    id: Value,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    result: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<RpcError>,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct RpcError {
+    code: i64,
+    message: String,
+}
+
+#[derive(Deserialize)]
+struct ToolCallParams {
+    name: String,
+    #[serde(default)]
+    arguments: Value,
+}
+
+impl RpcResponse {
+    fn success(id: Value, result: Value) -> Self {
+        Self {
+            jsonrpc: "2.0",
+            id,
+            result: Some(result),
+            error: None,
+        }
+    }
+
+    fn failure(id: Value, code: i64, message: String) -> Self {
+        Self {
+            jsonrpc: "2.0",
+            id,
+            result: None,
+            error: Some(RpcError { code, message }),
+        }
+    }
+}
+
+/// JSON Schema describing the arguments of the render tool
+fn render_tool_schema() -> Value {
+    // Built-in schemes, then palettes loaded from files
+    let mut scheme_names: Vec<String> = ColorScheme::NAMES.map(String::from).to_vec();
+    scheme_names.extend(palettes::names());
+
+    json!({
+        "type": "object",
+        "properties": {
+            "type": { "type": "string", "enum": FRACTAL_TYPES, "default": "mandelbrot" },
+            "width": { "type": "integer", "minimum": 1, "maximum": 4096, "default": 800 },
+            "height": { "type": "integer", "minimum": 1, "maximum": 4096, "default": 600 },
+            "zoom": { "type": "number", "exclusiveMinimum": 0, "maximum": 1e10, "default": 1.0 },
+            "center_x": { "type": "number", "default": 0.0 },
+            "center_y": { "type": "number", "default": 0.0 },
+            "max_iterations": { "type": "integer", "minimum": 1, "maximum": 10000, "default": 100 },
+            "color_scheme": { "type": "string", "enum": scheme_names },
+            "palette": {
+                "type": "string",
+                "pattern": "^#?[0-9A-Fa-f]{6}(,#?[0-9A-Fa-f]{6}){1,31}$",
+                "description": "Custom gradient replacing color_scheme: comma-separated hex stops from the lowest to the highest value, e.g. 000764,206bcb,edffff,ffaa00"
+            },
+            "brand_colors": {
+                "type": "string",
+                "pattern": "^#?[0-9A-Fa-f]{6}(,#?[0-9A-Fa-f]{6}){1,4}$",
+                "description": "2-5 brand colors, comma-separated hex, joined by a perceptually smooth gradient that passes through each; replaces palette and color_scheme"
+            },
+            "interpolation": {
+                "type": "string",
+                "enum": INTERPOLATIONS,
+                "default": "rgb",
+                "description": "Blend between palette stops (colormaps, palette, palette files) in linear-light rgb or in oklab, which keeps saturated midpoints from going gray"
+            },
+            "coloring": {
+                "type": "string",
+                "enum": Coloring::NAMES,
+                "default": "linear",
+                "description": "Escape-time types: spread colors by iteration count (linear), evenly over the image's escape times (histogram), by the orbit's closest approach to trap_shape (orbit_trap), by estimated distance to the boundary (distance; mandelbrot with variant=classic and julia only), split by the side of the real axis the orbit escapes on (binary_decomposition) by the orbit's average stripe_density sine of its angle (stripe_average; these two for mandelbrot, julia, hybrid and custom), by the step of the orbit's closest approach to 0 (atom_domain) or the interior by the period of its attracting cycle (period; mandelbrot and julia only)"
+            },
+            "trap_shape": {
+                "type": "string",
+                "enum": TrapShape::NAMES,
+                "default": "point",
+                "description": "coloring=orbit_trap: distance measured to the point (trap_x, trap_y), the lines x = trap_x and y = trap_y (cross) or the circle of trap_radius around the point"
+            },
+            "trap_x": { "type": "number", "minimum": -MAX_TRAP_EXTENT, "maximum": MAX_TRAP_EXTENT, "default": 0.0 },
+            "trap_y": { "type": "number", "minimum": -MAX_TRAP_EXTENT, "maximum": MAX_TRAP_EXTENT, "default": 0.0 },
+            "trap_radius": { "type": "number", "exclusiveMinimum": 0, "maximum": MAX_TRAP_EXTENT, "default": 1.0 },
+            "boundary_width": {
+                "type": "number",
+                "exclusiveMinimum": 0,
+                "maximum": 32,
+                "default": DEFAULT_BOUNDARY_WIDTH,
+                "description": "coloring=distance: pixels within this distance of the boundary get the palette's end color, at any zoom"
+            },
+            "stripe_density": {
+                "type": "number",
+                "exclusiveMinimum": 0,
+                "maximum": 32,
+                "default": DEFAULT_STRIPE_DENSITY,
+                "description": "coloring=stripe_average: stripes per turn around the origin"
+            },
+            "color_offset": {
+                "type": "number",
+                "minimum": 0,
+                "maximum": 1,
+                "default": 0.0,
+                "description": "Escape-time types with linear, histogram or binary_decomposition coloring: shift the palette by this fraction of its length, wrapping around"
+            },
+            "color_scale": {
+                "type": "number",
+                "exclusiveMinimum": 0,
+                "maximum": 1000,
+                "default": 1.0,
+                "description": "How many times the palette repeats across the escape range; raise it at high zoom for contrast without changing max_iterations"
+            },
+            "color_spacing": {
+                "type": "string",
+                "enum": ["linear", "log"],
+                "default": "linear",
+                "description": "Place escape times on the palette in proportion to the iteration count or to its logarithm"
+            },
+            "adaptive": {
+                "type": "boolean",
+                "default": false,
+                "description": "Escape-time types except custom: iterate at a low limit first and re-iterate only pixels next to escaping ones at max_iterations"
+            },
+            "aa": {
+                "type": "string",
+                "enum": Antialias::NAMES,
+                "default": "none",
+                "description": "Escape-time types except custom: adaptive supersamples only pixels whose escape time differs sharply from a neighbour's, on a 3x3 grid"
+            },
+            "aa_threshold": {
+                "type": "number",
+                "exclusiveMinimum": 0,
+                "maximum": 1,
+                "default": DEFAULT_AA_THRESHOLD,
+                "description": "Escape-time difference to a neighbour, relative to the larger, that marks a pixel for aa=adaptive"
+            },
+            "shading": {
+                "type": "string",
+                "enum": Shading::NAMES,
+                "default": "none",
+                "description": "Escape-time types: lambert lights the escape time (or distance estimate) as a height field for an embossed look"
+            },
+            "light_azimuth": {
+                "type": "number",
+                "minimum": 0,
+                "maximum": 360,
+                "default": DEFAULT_LIGHT_AZIMUTH,
+                "description": "shading=lambert: light direction in degrees counterclockwise from the right edge"
+            },
+            "light_elevation": {
+                "type": "number",
+                "minimum": 0,
+                "maximum": 90,
+                "default": DEFAULT_LIGHT_ELEVATION,
+                "description": "shading=lambert: light height above the image plane in degrees"
+            },
+            "variant": {
+                "type": "string",
+                "enum": MANDELBROT_VARIANTS,
+                "default": "classic",
+                "description": "Mandelbrot only: which member of the abs-variant family to render"
+            },
+            "julia_c_real": { "type": "number", "minimum": -2, "maximum": 2 },
+            "julia_c_imag": { "type": "number", "minimum": -2, "maximum": 2 },
+            "julia_coefficients": {
+                "type": "string",
+                "description": "Julia only: comma-separated complex coefficients of p in z = p(z) + c, highest degree first (e.g. 1,0,0,-0.5+0.25i; degree 2-12, magnitudes up to 100)"
+            },
+            "recursion_depth": { "type": "integer", "minimum": 1, "maximum": 12 },
+            "newton_degree": { "type": "integer", "minimum": 2, "maximum": 12 },
+            "newton_coefficients": {
+                "type": "string",
+                "description": "Comma-separated real coefficients, highest degree first"
+            },
+            "relaxation": { "type": "number", "exclusiveMinimum": 0, "maximum": 2 },
+            "lyapunov_sequence": { "type": "string", "pattern": "^[ABab]{1,64}$" },
+            "samples": {
+                "type": "integer",
+                "minimum": 1,
+                "description": "Monte Carlo samples (Buddhabrot), plotted points (IFS, attractor) or points per column (bifurcation)"
+            },
+            "seed": { "type": "integer", "minimum": 0 },
+            "tone_map": {
+                "type": "string",
+                "enum": ToneCurve::NAMES,
+                "description": "Density types (buddhabrot, nebulabrot, barnsley, ifs, attractor, bifurcation): curve from hit density to brightness; sqrt for buddhabrot and nebulabrot, log for the rest by default"
+            },
+            "exposure": {
+                "type": "number",
+                "minimum": -MAX_EXPOSURE,
+                "maximum": MAX_EXPOSURE,
+                "default": 0,
+                "description": "Density types: stops of brightening (negative darkens) before the tone curve"
+            },
+            "red_iterations": { "type": "integer", "minimum": 1, "maximum": 10000 },
+            "green_iterations": { "type": "integer", "minimum": 1, "maximum": 10000 },
+            "blue_iterations": { "type": "integer", "minimum": 1, "maximum": 10000 },
+            "ifs_transforms": {
+                "type": "array",
+                "minItems": 1,
+                "maxItems": MAX_IFS_TRANSFORMS,
+                "description": "Contractive affine maps (x, y) -> (a x + b y + e, c x + d y + f) for type=ifs",
+                "items": {
+                    "type": "object",
+                    "properties": {
+                        "coefficients": {
+                            "type": "array",
+                            "items": { "type": "number" },
+                            "minItems": 6,
+                            "maxItems": 6,
+                            "description": "[a, b, c, d, e, f]"
+                        },
+                        "probability": { "type": "number", "minimum": 0 }
+                    },
+                    "required": ["coefficients", "probability"]
+                }
+            },
+            "lsystem_preset": { "type": "string", "enum": ["koch", "dragon", "plant"] },
+            "lsystem_axiom": { "type": "string", "minLength": 1, "maxLength": 64 },
+            "lsystem_rules": {
+                "type": "string",
+                "maxLength": 256,
+                "description": "Semicolon-separated rewrite rules, e.g. X=X+YF+;Y=-FX-Y"
+            },
+            "lsystem_angle": { "type": "number", "minimum": -360, "maximum": 360 },
+            "vicsek_variant": { "type": "string", "enum": VICSEK_VARIANTS, "default": "plus" },
+            "max_curvature": {
+                "type": "number",
+                "minimum": 1,
+                "maximum": 1e6,
+                "description": "Apollonian gasket: skip circles curvier than this (outer circle = 1)"
+            },
+            "line_thickness": {
+                "type": "integer",
+                "minimum": 1,
+                "maximum": 32,
+                "description": "Line-drawn types (koch, dragon, hilbert, levy, lsystem, htree): anti-aliased stroke width in pixels"
+            },
+            "background_color": {
+                "type": "string",
+                "pattern": "^#?[0-9A-Fa-f]{6}$",
+                "default": "ffffff",
+                "description": "Geometric types: background color as 6-digit hex"
+            },
+            "stroke_color": {
+                "type": "string",
+                "pattern": "^#?[0-9A-Fa-f]{6}$",
+                "description": "Line-drawn geometric types (koch, dragon, hilbert, levy, lsystem, htree, apollonian outline): one line color as 6-digit hex instead of the scheme's gradient"
+            },
+            "fill_color": {
+                "type": "string",
+                "pattern": "^#?[0-9A-Fa-f]{6}$",
+                "description": "Filled geometric types (sierpinski, carpet, vicsek, apollonian circles): one fill color as 6-digit hex instead of the scheme's gradient"
+            },
+            "attractor": { "type": "string", "enum": ATTRACTOR_MAPS, "default": "clifford" },
+            "attractor_a": {
+                "type": "number",
+                "description": "Attractor coefficient; -5..5 for clifford/dejong, Lorenz sigma 0.1..50"
+            },
+            "attractor_b": {
+                "type": "number",
+                "description": "Attractor coefficient; -5..5 for clifford/dejong, Lorenz rho 0.1..100"
+            },
+            "attractor_c": {
+                "type": "number",
+                "description": "Attractor coefficient; -5..5 for clifford/dejong, Lorenz beta 0.1..10"
+            },
+            "attractor_d": {
+                "type": "number",
+                "description": "Attractor coefficient; -5..5 for clifford/dejong, unused by lorenz"
+            },
+            "bifurcation_r_min": {
+                "type": "number",
+                "minimum": 0,
+                "maximum": 4,
+                "default": 2.5,
+                "description": "Bifurcation: smallest logistic map rate, at the left edge"
+            },
+            "bifurcation_r_max": {
+                "type": "number",
+                "minimum": 0,
+                "maximum": 4,
+                "default": 4.0,
+                "description": "Bifurcation: largest rate, at the right edge"
+            },
+            "bifurcation_y_min": {
+                "type": "number",
+                "minimum": -1,
+                "maximum": 2,
+                "default": 0.0,
+                "description": "Bifurcation: orbit value at the bottom edge"
+            },
+            "bifurcation_y_max": {
+                "type": "number",
+                "minimum": -1,
+                "maximum": 2,
+                "default": 1.0,
+                "description": "Bifurcation: orbit value at the top edge"
+            },
+            "roughness": {
+                "type": "number",
+                "minimum": 0,
+                "maximum": 1,
+                "default": 0.5,
+                "description": "Plasma: how much each subdivision keeps of the previous displacement (0 smooth, 1 rough)"
+            },
+            "formula": {
+                "type": "string",
+                "maxLength": MAX_FORMULA_LENGTH,
+                "default": "z^2 + c",
+                "description": "Custom: iteration formula of z and c using + - * / ^, i, pi, e and sin, cos, tan, sinh, cosh, tanh, exp, log, sqrt, abs, conj, re, im"
+            },
+            "hybrid_pattern": {
+                "type": "string",
+                "pattern": "^[MJBTCPFmjbtcpf]{1,16}$",
+                "default": "MB",
+                "description": "Hybrid: formulas applied in turn, one letter each: M Mandelbrot, J Julia (needs julia_c_*), B Burning Ship, T Tricorn, C Celtic, P Perpendicular Mandelbrot, F Buffalo"
+            },
+            "simulate": {
+                "type": "string",
+                "enum": ColorVisionDeficiency::NAMES,
+                "description": "Recolor the result as seen with this color vision deficiency"
+            },
+            "gamma": {
+                "type": "number",
+                "minimum": 1,
+                "maximum": 3,
+                "description": "Encode the result for a display with this power-law gamma instead of sRGB"
+            },
+            "dither": {
+                "type": "string",
+                "enum": Dither::NAMES,
+                "default": "none",
+                "description": "Reduce the result to gray levels with ordered (Bayer) or Floyd-Steinberg dithering, e.g. for laser engraving"
+            },
+            "dither_levels": {
+                "type": "integer",
+                "minimum": 2,
+                "maximum": 256,
+                "description": "Evenly spaced gray levels in the output; 2 (black and white) when dithering"
+            },
+            "equalize": {
+                "type": "boolean",
+                "default": false,
+                "description": "Histogram-equalize the grays first so every level gets a similar share of pixels"
+            }
+        },
+        "additionalProperties": false
+    })
+}
+
+fn list_tools() -> Value {
+    json!({
+        "tools": [{
+            "name": RENDER_TOOL,
+            "description": "Render a fractal image and return it as a PNG",
+            "inputSchema": render_tool_schema()
+        }]
+    })
+}
+
+fn call_tool(state: &AppState, params: Value, options: &RenderOptions) -> Result<Value, RpcError> {
+    let call: ToolCallParams = serde_json::from_value(params).map_err(|e| RpcError {
+        code: INVALID_PARAMS,
+        message: format!("Invalid tools/call params: {}", e),
+    })?;
+
+    if call.name != RENDER_TOOL {
+        return Err(RpcError {
+            code: INVALID_PARAMS,
+            message: format!("Unknown tool: {}", call.name),
+        });
+    }
+
+    let arguments = if call.arguments.is_null() {
+        json!({})
+    } else {
+        call.arguments
+    };
+
+    // Reject arguments the schema doesn't know about instead of silently ignoring them
+    let schema = render_tool_schema();
+    if let Some(unknown) = arguments
+        .as_object()
+        .into_iter()
+        .flat_map(|args| args.keys())
+        .find(|key| schema["properties"].get(key.as_str()).is_none())
+    {
+        return Err(RpcError {
+            code: INVALID_PARAMS,
+            message: format!("Unknown argument for {}: {}", RENDER_TOOL, unknown),
+        });
+    }
+
+    let query: FractalQuery = serde_json::from_value(arguments).map_err(|e| RpcError {
+        code: INVALID_PARAMS,
+        message: format!("Invalid arguments for {}: {}", RENDER_TOOL, e),
+    })?;
+
+    let fractal_type = query.fractal_type();
+    let rendered =
+        render(state, &fractal_type, query.into_params(), options).and_then(|(img, _)| {
+            encode_image(&img, OutputFormat::Png, &EncodeOptions::default())
+                .map_err(RenderError::Internal)
+        });
+
+    match rendered {
+        Ok(png_bytes) => Ok(json!({
+            "content": [{
+                "type": "image",
+                "data": STANDARD.encode(png_bytes),
+                "mimeType": "image/png"
+            }],
+            "isError": false
+        })),
+        // Bad arguments are reported as tool errors so the assistant can correct itself
+        Err(RenderError::BadRequest(e)) => Ok(json!({
+            "content": [{ "type": "text", "text": e }],
+            "isError": true
+        })),
+        Err(RenderError::QuotaExceeded(exceeded)) => Err(RpcError {
+            code: QUOTA_EXCEEDED,
+            message: exceeded.message,
+        }),
+        Err(RenderError::Internal(message)) => Err(RpcError {
+            code: INTERNAL_ERROR,
+            message,
+        }),
+    }
+}
+
+fn dispatch(
+    state: &AppState,
+    request: RpcRequest,
+    options: &RenderOptions,
+) -> Result<Value, RpcError> {
+    if request.jsonrpc != "2.0" {
+        return Err(RpcError {
+            code: INVALID_REQUEST,
+            message: "Only JSON-RPC 2.0 is supported".to_string(),
+        });
+    }
+
+    match request.method.as_str() {
+        "initialize" => Ok(json!({
+            "protocolVersion": PROTOCOL_VERSION,
+            "capabilities": { "tools": {} },
+            "serverInfo": { "name": "rust-service", "version": env!("CARGO_PKG_VERSION") }
+        })),
+        "ping" => Ok(json!({})),
+        "tools/list" => Ok(list_tools()),
+        "tools/call" => call_tool(state, request.params, options),
+        _ => Err(RpcError {
+            code: METHOD_NOT_FOUND,
+            message: format!("Method not found: {}", request.method),
+        }),
+    }
+}
+
+// JSON-RPC tool endpoint
+pub async fn handle_tool_request(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    body: String,
+) -> Response {
+    let request: RpcRequest = match serde_json::from_str(&body) {
+        Ok(request) => request,
+        Err(e) => {
+            let response =
+                RpcResponse::failure(Value::Null, PARSE_ERROR, format!("Parse error: {}", e));
+            return (StatusCode::OK, axum::Json(response)).into_response();
+        }
+    };
+
+    // Notifications carry no id and expect no response
+    let Some(id) = request.id.clone() else {
+        return StatusCode::ACCEPTED.into_response();
+    };
+
+    let response = match dispatch(&state, request, &RenderOptions::billed_to(&headers)) {
+        Ok(result) => RpcResponse::success(id, result),
+        Err(error) => RpcResponse::failure(id, error.code, error.message),
+    };
+    (StatusCode::OK, axum::Json(response)).into_response()
+}
@@ -0,0 +1,136 @@
+//! Startup micro-calibration that picks the fastest escape-time kernel and parallel tile
+//! size for this host. `RENDER_KERNEL` / `RENDER_TILE_ROWS` override the measured choice.
+
+use crate::fractals::kernels::{mandelbrot_row, Kernel, MandelbrotVariant};
+use rayon::prelude::*;
+use schemars::JsonSchema;
+use serde::Serialize;
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+
+const TILE_ROW_CANDIDATES: [usize; 4] = [1, 4, 16, 64];
+
+// Calibration workload: a small view of the Mandelbrot boundary
+const CALIBRATION_WIDTH: u32 = 256;
+const CALIBRATION_HEIGHT: u32 = 128;
+const CALIBRATION_ITERATIONS: u32 = 200;
+
+static DECISION: OnceLock<TuningDecision> = OnceLock::new();
+
+#[derive(Clone, Debug, Serialize, JsonSchema)]
+pub struct TuningDecision {
+    pub kernel: Kernel,
+    /// Minimum number of rows each parallel task renders
+    pub tile_rows: usize,
+    /// "calibrated", "env" or "default"
+    pub source: &'static str,
+    pub calibration_ms: u128,
+    pub gpu_available: bool,
+    pub arch: &'static str,
+}
+
+impl Default for TuningDecision {
+    fn default() -> Self {
+        Self {
+            kernel: Kernel::Scalar,
+            tile_rows: 1,
+            source: "default",
+            calibration_ms: 0,
+            gpu_available: false,
+            arch: std::env::consts::ARCH,
+        }
+    }
+}
+
+/// The decision in effect; falls back to defaults when calibration hasn't run
+pub fn current() -> TuningDecision {
+    DECISION.get().cloned().unwrap_or_default()
+}
+
+/// Run the calibration once (or apply the env override) and store the result
+pub fn init() -> TuningDecision {
+    DECISION.get_or_init(decide).clone()
+}
+
+fn decide() -> TuningDecision {
+    let env_kernel = std::env::var("RENDER_KERNEL").ok();
+    let env_tile_rows = std::env::var("RENDER_TILE_ROWS").ok();
+
+    let kernel_override = env_kernel.as_deref().and_then(|value| {
+        let kernel = Kernel::from_str(value);
+        if kernel.is_none() {
+            tracing::warn!("Ignoring unknown RENDER_KERNEL={}", value);
+        }
+        kernel
+    });
+    let tile_rows_override = env_tile_rows.as_deref().and_then(|value| {
+        let tile_rows = value.parse::<usize>().ok().filter(|rows| *rows > 0);
+        if tile_rows.is_none() {
+            tracing::warn!("Ignoring invalid RENDER_TILE_ROWS={}", value);
+        }
+        tile_rows
+    });
+
+    if let (Some(kernel), Some(tile_rows)) = (kernel_override, tile_rows_override) {
+        return TuningDecision {
+            kernel,
+            tile_rows,
+            source: "env",
+            ..TuningDecision::default()
+        };
+    }
+
+    let started = Instant::now();
+
+    let kernel = kernel_override.unwrap_or_else(|| {
+        Kernel::ALL
+            .into_iter()
+            .min_by_key(|kernel| time_render(*kernel, 1))
+            .unwrap_or(Kernel::Scalar)
+    });
+    let tile_rows = tile_rows_override.unwrap_or_else(|| {
+        TILE_ROW_CANDIDATES
+            .into_iter()
+            .min_by_key(|tile_rows| time_render(kernel, *tile_rows))
+            .unwrap_or(1)
+    });
+
+    TuningDecision {
+        kernel,
+        tile_rows,
+        source: "calibrated",
+        calibration_ms: started.elapsed().as_millis(),
+        ..TuningDecision::default()
+    }
+}
+
+/// Best of three timings of the calibration workload
+fn time_render(kernel: Kernel, tile_rows: usize) -> Duration {
+    let dx = 3.0 / CALIBRATION_WIDTH as f64;
+    let dy = 2.0 / CALIBRATION_HEIGHT as f64;
+
+    (0..3)
+        .map(|_| {
+            let started = Instant::now();
+            let total: u64 = (0..CALIBRATION_HEIGHT)
+                .into_par_iter()
+                .with_min_len(tile_rows)
+                .map(|y| {
+                    let row = mandelbrot_row(
+                        kernel,
+                        MandelbrotVariant::Classic,
+                        -2.0,
+                        dx,
+                        -1.0 + y as f64 * dy,
+                        CALIBRATION_WIDTH,
+                        CALIBRATION_ITERATIONS,
+                    );
+                    row.iter().map(|&i| i as u64).sum::<u64>()
+                })
+                .sum();
+            std::hint::black_box(total);
+            started.elapsed()
+        })
+        .min()
+        .unwrap_or_default()
+}
@@ -0,0 +1,510 @@
+//! Per-tenant usage accounting for billing: renders, pixels, CPU time and cache hits per API
+//! key, kept in hourly buckets and reported over selectable windows by `GET /api/usage`.
+//!
+//! Clients identify themselves with an `X-API-Key` header. Keys are never stored; a tenant is
+//! the first 16 hex digits of the key's SHA-256. Requests without a key count as `anonymous`.
+//!
+//! Configuration (all optional):
+//! - `USAGE_LOG`: file every render is appended to as a JSON line, replayed on startup so
+//!   totals survive restarts. Without it, totals are kept in memory only.
+//! - `USAGE_ADMIN_KEY`: API key allowed to see every tenant's usage; other keys see their own
+//! - `USAGE_RETENTION_DAYS`: how long hourly buckets are kept (default 62, two billing months)
+//!
+//! The admin key and retention can be changed at runtime through the settings file (see
+//! `config`); `USAGE_LOG` is only read at startup.
+//!
+//! Monthly budgets are enforced against these totals by [`crate::quota`]; the admin key can
+//! reset a tenant's month with `POST /api/usage/reset` without touching the billing totals.
+
+use crate::config::{self, Reloadable};
+use crate::pipeline::AppState;
+use crate::utils::time::unix_now;
+use crate::ErrorResponse;
+use axum::{
+    extract::{Query, State},
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+pub const API_KEY_HEADER: &str = "X-API-Key";
+
+/// Tenant for requests without an API key
+pub const ANONYMOUS: &str = "anonymous";
+
+pub const USAGE_WINDOWS: &[&str] = &["1h", "24h", "7d", "30d", "month", "all"];
+
+const HOUR: u64 = 3600;
+const DEFAULT_RETENTION_DAYS: u64 = 62;
+
+/// Tenant id for an API key
+fn tenant_id(key: &str) -> String {
+    Sha256::digest(key.as_bytes())
+        .iter()
+        .take(8)
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+/// Tenant of the API key sent with a request, if any
+pub fn tenant(headers: &HeaderMap) -> Option<String> {
+    headers
+        .get(API_KEY_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .map(str::trim)
+        .filter(|key| !key.is_empty())
+        .map(tenant_id)
+}
+
+#[derive(Clone, Copy, Debug, Default, Serialize, JsonSchema)]
+pub struct UsageTotals {
+    pub renders: u64,
+    pub pixels: u64,
+    /// Wall-clock render time; renders run on the whole worker pool
+    pub cpu_seconds: f64,
+    /// Responses served without rendering. The service has no render cache yet, so this is 0.
+    pub cache_hits: u64,
+}
+
+impl UsageTotals {
+    fn add(&mut self, other: &UsageTotals) {
+        self.renders += other.renders;
+        self.pixels += other.pixels;
+        self.cpu_seconds += other.cpu_seconds;
+        self.cache_hits += other.cache_hits;
+    }
+
+    /// Usage added since `earlier` was taken
+    fn since(&self, earlier: &UsageTotals) -> UsageTotals {
+        UsageTotals {
+            renders: self.renders.saturating_sub(earlier.renders),
+            pixels: self.pixels.saturating_sub(earlier.pixels),
+            cpu_seconds: (self.cpu_seconds - earlier.cpu_seconds).max(0.0),
+            cache_hits: self.cache_hits.saturating_sub(earlier.cache_hits),
+        }
+    }
+}
+
+/// One render, or a quota reset, as written to the usage log
+#[derive(Serialize, Deserialize)]
+struct UsageEvent {
+    unix: u64,
+    tenant: String,
+    pixels: u64,
+    cpu_us: u64,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    reset: bool,
+}
+
+#[derive(Default)]
+struct Buckets {
+    /// Tenant -> start of hour (unix seconds) -> totals for that hour
+    hours: BTreeMap<String, BTreeMap<u64, UsageTotals>>,
+    /// Tenant -> start of the month and its totals when the tenant's quota was last reset
+    resets: BTreeMap<String, (u64, UsageTotals)>,
+}
+
+impl Buckets {
+    /// Add a render to its tenant's hour, or snapshot the month so far for a reset
+    fn apply(&mut self, event: &UsageEvent) {
+        if event.reset {
+            let month = month_start(event.unix);
+            let totals = self.month_to_date(&event.tenant, month);
+            self.resets.insert(event.tenant.clone(), (month, totals));
+            return;
+        }
+
+        let hours = self.hours.entry(event.tenant.clone()).or_default();
+        hours
+            .entry(hour_of(event.unix))
+            .or_default()
+            .add(&UsageTotals {
+                renders: 1,
+                pixels: event.pixels,
+                cpu_seconds: event.cpu_us as f64 / 1e6,
+                cache_hits: 0,
+            });
+    }
+
+    fn month_to_date(&self, tenant: &str, month: u64) -> UsageTotals {
+        self.hours
+            .get(tenant)
+            .map(|hours| sum_since(hours, month))
+            .unwrap_or_default()
+    }
+
+    fn quota_usage(&self, tenant: &str, now: u64) -> UsageTotals {
+        let month = month_start(now);
+        let totals = self.month_to_date(tenant, month);
+        match self.resets.get(tenant) {
+            Some((reset_month, at_reset)) if *reset_month == month => totals.since(at_reset),
+            _ => totals,
+        }
+    }
+
+    /// Drop a tenant's hours from before `cutoff`
+    fn expire(&mut self, tenant: &str, cutoff: u64) {
+        if let Some(hours) = self.hours.get_mut(tenant) {
+            if hours.range(..cutoff).next().is_some() {
+                *hours = hours.split_off(&cutoff);
+            }
+        }
+    }
+}
+
+/// Settings of the ledger that can change at runtime
+struct UsageSettings {
+    admin_tenant: Option<String>,
+    retention: u64,
+}
+
+impl UsageSettings {
+    fn from_env() -> Self {
+        let retention_days = config::var("USAGE_RETENTION_DAYS")
+            .and_then(|value| match value.parse::<u64>() {
+                Ok(days) if days > 0 => Some(days),
+                _ => {
+                    tracing::warn!("Ignoring invalid USAGE_RETENTION_DAYS={}", value);
+                    None
+                }
+            })
+            .unwrap_or(DEFAULT_RETENTION_DAYS);
+
+        Self {
+            admin_tenant: config::var("USAGE_ADMIN_KEY")
+                .filter(|key| !key.trim().is_empty())
+                .map(|key| tenant_id(key.trim())),
+            retention: retention_days * 24 * HOUR,
+        }
+    }
+}
+
+/// Hourly usage buckets per tenant, optionally backed by an append-only log
+pub struct UsageLedger {
+    settings: Reloadable<UsageSettings>,
+    started_unix: u64,
+    log: Option<Mutex<File>>,
+    buckets: Mutex<Buckets>,
+}
+
+impl UsageLedger {
+    pub fn from_env() -> Self {
+        let mut ledger = Self {
+            settings: Reloadable::new(UsageSettings::from_env()),
+            started_unix: unix_now(),
+            log: None,
+            buckets: Mutex::new(Buckets::default()),
+        };
+
+        if let Ok(path) = std::env::var("USAGE_LOG") {
+            ledger.replay(&path);
+            match OpenOptions::new().create(true).append(true).open(&path) {
+                Ok(file) => ledger.log = Some(Mutex::new(file)),
+                Err(e) => tracing::error!("Usage log {} not writable: {}", path, e),
+            }
+        }
+
+        ledger
+    }
+
+    /// Pick up a changed admin key or retention period; the totals are kept
+    pub fn reload(&self) {
+        self.settings.set(UsageSettings::from_env());
+    }
+
+    /// Load the events still inside the retention period from an existing log
+    fn replay(&mut self, path: &str) {
+        let Ok(file) = File::open(path) else {
+            return;
+        };
+
+        let cutoff = unix_now().saturating_sub(self.settings.get().retention);
+        let buckets = self.buckets.get_mut().unwrap_or_else(|e| e.into_inner());
+        let mut events = 0;
+        for line in BufReader::new(file).lines().map_while(Result::ok) {
+            match serde_json::from_str::<UsageEvent>(&line) {
+                Ok(event) if event.unix >= cutoff => {
+                    buckets.apply(&event);
+                    events += 1;
+                }
+                Ok(_) => {}
+                Err(e) => tracing::warn!("Skipping malformed usage log line: {}", e),
+            }
+        }
+        tracing::info!("Replayed {} usage events from {}", events, path);
+    }
+
+    /// Account one finished render to `tenant` (anonymous when `None`)
+    pub fn record(&self, tenant: Option<&str>, pixels: u64, render_time: Duration) {
+        let event = UsageEvent {
+            unix: unix_now(),
+            tenant: tenant.unwrap_or(ANONYMOUS).to_string(),
+            pixels,
+            cpu_us: render_time.as_micros() as u64,
+            reset: false,
+        };
+
+        {
+            let mut buckets = self.buckets.lock().unwrap_or_else(|e| e.into_inner());
+            buckets.apply(&event);
+            buckets.expire(
+                &event.tenant,
+                hour_of(event.unix.saturating_sub(self.settings.get().retention)),
+            );
+        }
+        self.append(&event);
+    }
+
+    /// Start counting `tenant`'s quota usage for this month from zero; returns what was cleared
+    pub fn reset(&self, tenant: &str) -> UsageTotals {
+        let event = UsageEvent {
+            unix: unix_now(),
+            tenant: tenant.to_string(),
+            pixels: 0,
+            cpu_us: 0,
+            reset: true,
+        };
+
+        let cleared = {
+            let mut buckets = self.buckets.lock().unwrap_or_else(|e| e.into_inner());
+            let cleared = buckets.quota_usage(tenant, event.unix);
+            buckets.apply(&event);
+            cleared
+        };
+        self.append(&event);
+        tracing::info!("Quota usage of tenant {} reset", tenant);
+        cleared
+    }
+
+    /// Usage counted against `tenant`'s monthly quota: this calendar month since the last reset
+    pub fn quota_usage(&self, tenant: &str) -> UsageTotals {
+        let buckets = self.buckets.lock().unwrap_or_else(|e| e.into_inner());
+        buckets.quota_usage(tenant, unix_now())
+    }
+
+    pub fn is_admin(&self, tenant: &str) -> bool {
+        self.settings.get().admin_tenant.as_deref() == Some(tenant)
+    }
+
+    fn append(&self, event: &UsageEvent) {
+        if let Some(log) = &self.log {
+            let mut file = log.lock().unwrap_or_else(|e| e.into_inner());
+            let line = serde_json::to_string(event).unwrap_or_default();
+            if let Err(e) = writeln!(file, "{}", line) {
+                tracing::error!("Failed to append to usage log: {}", e);
+            }
+        }
+    }
+
+    fn report(&self, window: Window, only: Option<&str>) -> UsageReport {
+        let to_unix = unix_now();
+        let from_unix = window.start(to_unix);
+        let buckets = self.buckets.lock().unwrap_or_else(|e| e.into_inner());
+
+        UsageReport {
+            window: window.name().to_string(),
+            from_unix: hour_of(from_unix),
+            to_unix,
+            retained_since_unix: hour_of(to_unix.saturating_sub(self.settings.get().retention)),
+            persistent: self.log.is_some(),
+            counting_since_unix: self.started_unix,
+            tenants: buckets
+                .hours
+                .iter()
+                .filter(|(tenant, _)| only.is_none_or(|only| only == tenant.as_str()))
+                .map(|(tenant, hours)| TenantUsage {
+                    tenant: tenant.clone(),
+                    totals: sum_since(hours, from_unix),
+                })
+                .filter(|usage| usage.totals.renders > 0 || usage.totals.cache_hits > 0)
+                .collect(),
+        }
+    }
+}
+
+fn sum_since(hours: &BTreeMap<u64, UsageTotals>, from_unix: u64) -> UsageTotals {
+    let mut totals = UsageTotals::default();
+    for bucket in hours.range(hour_of(from_unix)..).map(|(_, bucket)| bucket) {
+        totals.add(bucket);
+    }
+    totals
+}
+
+fn hour_of(unix: u64) -> u64 {
+    unix - unix % HOUR
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Window {
+    Hours(u64),
+    /// Since midnight UTC on the first of the current month
+    Month,
+    All,
+}
+
+impl Window {
+    fn parse(name: &str) -> Result<Self, String> {
+        match name.to_lowercase().as_str() {
+            "1h" => Ok(Window::Hours(1)),
+            "24h" => Ok(Window::Hours(24)),
+            "7d" => Ok(Window::Hours(7 * 24)),
+            "30d" => Ok(Window::Hours(30 * 24)),
+            "month" => Ok(Window::Month),
+            "all" => Ok(Window::All),
+            _ => Err(format!(
+                "Invalid window. Must be one of: {}.",
+                USAGE_WINDOWS.join(", ")
+            )),
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Window::Hours(1) => "1h",
+            Window::Hours(24) => "24h",
+            Window::Hours(168) => "7d",
+            Window::Hours(_) => "30d",
+            Window::Month => "month",
+            Window::All => "all",
+        }
+    }
+
+    /// First second of the window ending at `now`; hourly windows include the current hour
+    fn start(self, now: u64) -> u64 {
+        match self {
+            Window::Hours(hours) => hour_of(now).saturating_sub((hours - 1) * HOUR),
+            Window::Month => month_start(now),
+            Window::All => 0,
+        }
+    }
+}
+
+/// Midnight UTC on the first day of the month containing `unix`
+pub fn month_start(unix: u64) -> u64 {
+    let days = unix / 86_400;
+    // Civil-from-days (Howard Hinnant), reduced to the day of the month
+    let era_day = days + 719_468;
+    let day_of_era = era_day % 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day_of_month = day_of_year - (153 * month_index + 2) / 5;
+    (days - day_of_month) * 86_400
+}
+
+/// Midnight UTC on the first day of the month after the one containing `unix`
+pub fn next_month_start(unix: u64) -> u64 {
+    month_start(month_start(unix) + 32 * 86_400)
+}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct UsageQuery {
+    /// 1h, 24h (default), 7d, 30d, month (calendar month, UTC) or all
+    window: Option<String>,
+    /// Admin key only: report a single tenant
+    tenant: Option<String>,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct UsageReport {
+    pub window: String,
+    /// Windows are counted in whole hours, so this is the start of an hour
+    pub from_unix: u64,
+    pub to_unix: u64,
+    /// Older usage has been dropped
+    pub retained_since_unix: u64,
+    /// Whether totals are backed by the usage log, or reset on restart
+    pub persistent: bool,
+    pub counting_since_unix: u64,
+    /// Tenants with usage in the window
+    pub tenants: Vec<TenantUsage>,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct TenantUsage {
+    pub tenant: String,
+    #[serde(flatten)]
+    pub totals: UsageTotals,
+}
+
+// Usage per tenant over a window: every tenant for the admin key, otherwise the caller's own
+pub async fn usage_report(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Query(query): Query<UsageQuery>,
+) -> Response {
+    let error = |status: StatusCode, error: String| {
+        (status, axum::Json(ErrorResponse { error })).into_response()
+    };
+
+    let window = match Window::parse(query.window.as_deref().unwrap_or("24h")) {
+        Ok(window) => window,
+        Err(e) => return error(StatusCode::BAD_REQUEST, e),
+    };
+    let Some(caller) = tenant(&headers) else {
+        return error(
+            StatusCode::UNAUTHORIZED,
+            format!("Send your API key in {} to see its usage.", API_KEY_HEADER),
+        );
+    };
+
+    let ledger = &state.usage;
+    let is_admin = ledger.is_admin(&caller);
+    let only = match (is_admin, query.tenant) {
+        (true, tenant) => tenant,
+        (false, Some(tenant)) if tenant != caller => {
+            return error(
+                StatusCode::FORBIDDEN,
+                "Only the admin key can see other tenants' usage.".to_string(),
+            );
+        }
+        (false, _) => Some(caller),
+    };
+
+    (
+        StatusCode::OK,
+        axum::Json(ledger.report(window, only.as_deref())),
+    )
+        .into_response()
+}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct ResetQuery {
+    /// Tenant id as shown by `GET /api/usage`
+    tenant: String,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct ResetResponse {
+    pub tenant: String,
+    pub reset_unix: u64,
+    /// Quota usage that was cleared; billing totals are unchanged
+    pub cleared: UsageTotals,
+}
+
+// Admin only: clear a tenant's quota usage for the current month
+pub async fn reset_usage(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Query(query): Query<ResetQuery>,
+) -> Response {
+    if !tenant(&headers).is_some_and(|caller| state.usage.is_admin(&caller)) {
+        let error = "Only the admin key can reset quota usage.".to_string();
+        return (StatusCode::FORBIDDEN, axum::Json(ErrorResponse { error })).into_response();
+    }
+
+    let response = ResetResponse {
+        cleared: state.usage.reset(&query.tenant),
+        tenant: query.tenant,
+        reset_unix: unix_now(),
+    };
+    (StatusCode::OK, axum::Json(response)).into_response()
+}
@@ -0,0 +1,263 @@
+use std::ops::{Add, Div, Mul, Neg, Sub};
+
+/// Minimal complex number type for the convergence-based fractals
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Complex {
+    pub re: f64,
+    pub im: f64,
+}
+
+impl Complex {
+    pub const ZERO: Complex = Complex { re: 0.0, im: 0.0 };
+    pub const ONE: Complex = Complex { re: 1.0, im: 0.0 };
+
+    pub fn new(re: f64, im: f64) -> Self {
+        Self { re, im }
+    }
+
+    pub fn norm_sqr(self) -> f64 {
+        self.re * self.re + self.im * self.im
+    }
+
+    pub fn norm(self) -> f64 {
+        self.norm_sqr().sqrt()
+    }
+
+    pub fn is_finite(self) -> bool {
+        self.re.is_finite() && self.im.is_finite()
+    }
+
+    pub fn scale(self, factor: f64) -> Self {
+        Self::new(self.re * factor, self.im * factor)
+    }
+
+    pub fn conj(self) -> Self {
+        Self::new(self.re, -self.im)
+    }
+
+    pub fn arg(self) -> f64 {
+        self.im.atan2(self.re)
+    }
+
+    pub fn exp(self) -> Self {
+        let (sin, cos) = self.im.sin_cos();
+        Self::new(cos, sin).scale(self.re.exp())
+    }
+
+    /// Principal natural logarithm
+    pub fn ln(self) -> Self {
+        Self::new(self.norm().ln(), self.arg())
+    }
+
+    /// Principal square root
+    pub fn sqrt(self) -> Self {
+        let r = self.norm();
+        let re = ((r + self.re) / 2.0).sqrt();
+        let im = ((r - self.re) / 2.0).sqrt();
+        Self::new(re, if self.im < 0.0 { -im } else { im })
+    }
+
+    pub fn sin(self) -> Self {
+        Self::new(
+            self.re.sin() * self.im.cosh(),
+            self.re.cos() * self.im.sinh(),
+        )
+    }
+
+    pub fn cos(self) -> Self {
+        Self::new(
+            self.re.cos() * self.im.cosh(),
+            -self.re.sin() * self.im.sinh(),
+        )
+    }
+
+    pub fn tan(self) -> Self {
+        self.sin() / self.cos()
+    }
+
+    pub fn sinh(self) -> Self {
+        Self::new(
+            self.re.sinh() * self.im.cos(),
+            self.re.cosh() * self.im.sin(),
+        )
+    }
+
+    pub fn cosh(self) -> Self {
+        Self::new(
+            self.re.cosh() * self.im.cos(),
+            self.re.sinh() * self.im.sin(),
+        )
+    }
+
+    pub fn tanh(self) -> Self {
+        self.sinh() / self.cosh()
+    }
+
+    /// Integer power by repeated squaring, so z^2 is exactly z * z
+    pub fn powi(self, exponent: i32) -> Self {
+        let mut base = self;
+        let mut n = exponent.unsigned_abs();
+        let mut result = Complex::ONE;
+        let mut first = true;
+        while n > 0 {
+            if n & 1 == 1 {
+                result = if first { base } else { result * base };
+                first = false;
+            }
+            n >>= 1;
+            if n > 0 {
+                base = base * base;
+            }
+        }
+        if exponent < 0 {
+            Complex::ONE / result
+        } else {
+            result
+        }
+    }
+
+    /// Principal value of self^exponent
+    pub fn powc(self, exponent: Complex) -> Self {
+        if exponent == Complex::ZERO {
+            return Complex::ONE;
+        }
+        if self == Complex::ZERO {
+            return Complex::ZERO;
+        }
+        (self.ln() * exponent).exp()
+    }
+}
+
+impl Add for Complex {
+    type Output = Complex;
+
+    fn add(self, rhs: Complex) -> Complex {
+        Complex::new(self.re + rhs.re, self.im + rhs.im)
+    }
+}
+
+impl Sub for Complex {
+    type Output = Complex;
+
+    fn sub(self, rhs: Complex) -> Complex {
+        Complex::new(self.re - rhs.re, self.im - rhs.im)
+    }
+}
+
+impl Mul for Complex {
+    type Output = Complex;
+
+    fn mul(self, rhs: Complex) -> Complex {
+        Complex::new(
+            self.re * rhs.re - self.im * rhs.im,
+            self.re * rhs.im + self.im * rhs.re,
+        )
+    }
+}
+
+impl Div for Complex {
+    type Output = Complex;
+
+    fn div(self, rhs: Complex) -> Complex {
+        let denom = rhs.norm_sqr();
+        Complex::new(
+            (self.re * rhs.re + self.im * rhs.im) / denom,
+            (self.im * rhs.re - self.re * rhs.im) / denom,
+        )
+    }
+}
+
+impl Neg for Complex {
+    type Output = Complex;
+
+    fn neg(self) -> Complex {
+        Complex::new(-self.re, -self.im)
+    }
+}
+
+/// Polynomial with real coefficients, stored highest degree first
+#[derive(Clone, Debug)]
+pub struct Polynomial {
+    coefficients: Vec<f64>,
+}
+
+impl Polynomial {
+    pub fn new(coefficients: Vec<f64>) -> Self {
+        Self { coefficients }
+    }
+
+    /// z^n - 1
+    pub fn unity_roots(degree: u32) -> Self {
+        let mut coefficients = vec![0.0; degree as usize + 1];
+        coefficients[0] = 1.0;
+        coefficients[degree as usize] = -1.0;
+        Self::new(coefficients)
+    }
+
+    pub fn degree(&self) -> usize {
+        self.coefficients.len().saturating_sub(1)
+    }
+
+    /// Evaluate p(z) and p'(z) together using Horner's method
+    pub fn eval_with_derivative(&self, z: Complex) -> (Complex, Complex) {
+        let mut value = Complex::ZERO;
+        let mut derivative = Complex::ZERO;
+
+        for &coefficient in &self.coefficients {
+            derivative = derivative * z + value;
+            value = value * z + Complex::new(coefficient, 0.0);
+        }
+
+        (value, derivative)
+    }
+
+    pub fn eval(&self, z: Complex) -> Complex {
+        self.eval_with_derivative(z).0
+    }
+
+    /// Approximate all complex roots using the Durand-Kerner method
+    pub fn roots(&self) -> Vec<Complex> {
+        let degree = self.degree();
+        if degree == 0 {
+            return Vec::new();
+        }
+
+        // Normalize to a monic polynomial
+        let leading = self.coefficients[0];
+        let monic = Polynomial::new(self.coefficients.iter().map(|c| c / leading).collect());
+
+        // Standard starting points: powers of a non-real, non-unit complex number
+        let seed = Complex::new(0.4, 0.9);
+        let mut roots: Vec<Complex> = Vec::with_capacity(degree);
+        let mut current = Complex::ONE;
+        for _ in 0..degree {
+            roots.push(current);
+            current = current * seed;
+        }
+
+        for _ in 0..500 {
+            let mut max_delta: f64 = 0.0;
+
+            for i in 0..degree {
+                let mut denom = Complex::ONE;
+                for j in 0..degree {
+                    if i != j {
+                        denom = denom * (roots[i] - roots[j]);
+                    }
+                }
+
+                let delta = monic.eval(roots[i]) / denom;
+                if delta.is_finite() {
+                    roots[i] = roots[i] - delta;
+                    max_delta = max_delta.max(delta.norm());
+                }
+            }
+
+            if max_delta < 1e-12 {
+                break;
+            }
+        }
+
+        roots
+    }
+}
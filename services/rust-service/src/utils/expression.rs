@@ -0,0 +1,441 @@
+//! Parser and evaluator for user-supplied complex formulas such as `z^3 + c*sin(z)`. A formula
+//! is compiled once into a short stack program, which is then evaluated for every pixel and
+//! iteration without allocating. Only the names listed below are accepted.
+//!
+//! Grammar, loosest binding first (`^` is right-associative):
+//!
+//! ```text
+//! expr    = term (('+' | '-') term)*
+//! term    = unary (('*' | '/') unary)*
+//! unary   = ('-' | '+') unary | power
+//! power   = primary ('^' unary)?
+//! primary = number | name | function '(' expr ')' | '(' expr ')'
+//! ```
+
+use super::complex::Complex;
+use std::f64::consts::{E, PI};
+
+/// Longest formula accepted, in bytes
+pub const MAX_FORMULA_LENGTH: usize = 256;
+
+/// Most operations a compiled formula may contain
+pub const MAX_FORMULA_OPS: usize = 128;
+
+/// Deepest nesting of parentheses, function calls and signs
+const MAX_DEPTH: usize = 32;
+
+/// Integer exponents up to this size are computed by multiplication, the rest via exp/log
+const MAX_INTEGER_POWER: i32 = 64;
+
+/// Variables: the iterated value and the pixel (or Julia) constant
+pub const FORMULA_VARIABLES: &[&str] = &["z", "c"];
+
+/// Named constants
+pub const FORMULA_CONSTANTS: &[&str] = &["i", "pi", "e"];
+
+/// Functions of one complex argument
+pub const FORMULA_FUNCTIONS: &[&str] = &[
+    "sin", "cos", "tan", "sinh", "cosh", "tanh", "exp", "log", "sqrt", "abs", "conj", "re", "im",
+];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Function {
+    Sin,
+    Cos,
+    Tan,
+    Sinh,
+    Cosh,
+    Tanh,
+    Exp,
+    Log,
+    Sqrt,
+    Abs,
+    Conj,
+    Re,
+    Im,
+}
+
+impl Function {
+    fn parse(name: &str) -> Option<Self> {
+        let function = match name {
+            "sin" => Function::Sin,
+            "cos" => Function::Cos,
+            "tan" => Function::Tan,
+            "sinh" => Function::Sinh,
+            "cosh" => Function::Cosh,
+            "tanh" => Function::Tanh,
+            "exp" => Function::Exp,
+            "log" => Function::Log,
+            "sqrt" => Function::Sqrt,
+            "abs" => Function::Abs,
+            "conj" => Function::Conj,
+            "re" => Function::Re,
+            "im" => Function::Im,
+            _ => return None,
+        };
+        Some(function)
+    }
+
+    fn apply(self, z: Complex) -> Complex {
+        match self {
+            Function::Sin => z.sin(),
+            Function::Cos => z.cos(),
+            Function::Tan => z.tan(),
+            Function::Sinh => z.sinh(),
+            Function::Cosh => z.cosh(),
+            Function::Tanh => z.tanh(),
+            Function::Exp => z.exp(),
+            Function::Log => z.ln(),
+            Function::Sqrt => z.sqrt(),
+            Function::Abs => Complex::new(z.norm(), 0.0),
+            Function::Conj => z.conj(),
+            Function::Re => Complex::new(z.re, 0.0),
+            Function::Im => Complex::new(z.im, 0.0),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Op {
+    Const(Complex),
+    Z,
+    C,
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Neg,
+    PowInt(i32),
+    Pow,
+    Call(Function),
+}
+
+/// A compiled formula of z and c
+#[derive(Clone, Debug)]
+pub struct Formula {
+    ops: Vec<Op>,
+    /// Most values on the stack at once while evaluating
+    stack_size: usize,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Number(f64),
+    Name(String),
+    Symbol(char),
+}
+
+fn tokenize(source: &str) -> Result<Vec<(usize, Token)>, String> {
+    let mut tokens = Vec::new();
+    let mut chars = source.char_indices().peekable();
+
+    while let Some(&(position, ch)) = chars.peek() {
+        if ch.is_whitespace() {
+            chars.next();
+        } else if ch.is_ascii_digit() || ch == '.' {
+            let mut end = position;
+            while let Some(&(index, digit)) = chars.peek() {
+                if !(digit.is_ascii_digit() || digit == '.') {
+                    break;
+                }
+                end = index + digit.len_utf8();
+                chars.next();
+            }
+            let text = &source[position..end];
+            let value = text
+                .parse::<f64>()
+                .map_err(|_| format!("Invalid formula. Bad number {} at {}.", text, position))?;
+            tokens.push((position, Token::Number(value)));
+        } else if ch.is_ascii_alphabetic() {
+            let mut end = position;
+            while let Some(&(index, letter)) = chars.peek() {
+                if !letter.is_ascii_alphanumeric() {
+                    break;
+                }
+                end = index + letter.len_utf8();
+                chars.next();
+            }
+            let name = source[position..end].to_lowercase();
+            tokens.push((position, Token::Name(name)));
+        } else if "+-*/^()".contains(ch) {
+            tokens.push((position, Token::Symbol(ch)));
+            chars.next();
+        } else {
+            return Err(format!(
+                "Invalid formula. Unexpected character '{}' at {}.",
+                ch, position
+            ));
+        }
+    }
+
+    Ok(tokens)
+}
+
+/// Recursive-descent parser emitting postfix operations
+struct Parser {
+    tokens: Vec<(usize, Token)>,
+    next: usize,
+    /// Position reported when the input ends early
+    end: usize,
+    ops: Vec<Op>,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.next).map(|(_, token)| token)
+    }
+
+    fn position(&self) -> usize {
+        self.tokens
+            .get(self.next)
+            .map_or(self.end, |&(position, _)| position)
+    }
+
+    fn eat(&mut self, symbol: char) -> bool {
+        if self.peek() == Some(&Token::Symbol(symbol)) {
+            self.next += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn emit(&mut self, op: Op) -> Result<(), String> {
+        if self.ops.len() >= MAX_FORMULA_OPS {
+            return Err(format!(
+                "Invalid formula. Too complex; at most {} operations are allowed.",
+                MAX_FORMULA_OPS
+            ));
+        }
+        self.ops.push(op);
+        Ok(())
+    }
+
+    /// The constant emitted since `start`, if that is all that was emitted
+    fn constant_since(&self, start: usize) -> Option<Complex> {
+        match self.ops[start..] {
+            [Op::Const(value)] => Some(value),
+            _ => None,
+        }
+    }
+
+    fn expr(&mut self, depth: usize) -> Result<(), String> {
+        self.term(depth)?;
+        loop {
+            let op = if self.eat('+') {
+                Op::Add
+            } else if self.eat('-') {
+                Op::Sub
+            } else {
+                return Ok(());
+            };
+            self.term(depth)?;
+            self.emit(op)?;
+        }
+    }
+
+    fn term(&mut self, depth: usize) -> Result<(), String> {
+        self.unary(depth)?;
+        loop {
+            let op = if self.eat('*') {
+                Op::Mul
+            } else if self.eat('/') {
+                Op::Div
+            } else {
+                return Ok(());
+            };
+            self.unary(depth)?;
+            self.emit(op)?;
+        }
+    }
+
+    fn unary(&mut self, depth: usize) -> Result<(), String> {
+        if depth > MAX_DEPTH {
+            return Err(format!(
+                "Invalid formula. Nested more than {} levels deep.",
+                MAX_DEPTH
+            ));
+        }
+        if self.eat('+') {
+            return self.unary(depth + 1);
+        }
+        if self.eat('-') {
+            let start = self.ops.len();
+            self.unary(depth + 1)?;
+            // Fold negative literals so exponents like ^-2 stay integer powers
+            return match self.constant_since(start) {
+                Some(value) => {
+                    self.ops[start] = Op::Const(-value);
+                    Ok(())
+                }
+                None => self.emit(Op::Neg),
+            };
+        }
+        self.power(depth)
+    }
+
+    fn power(&mut self, depth: usize) -> Result<(), String> {
+        self.primary(depth)?;
+        if !self.eat('^') {
+            return Ok(());
+        }
+
+        let start = self.ops.len();
+        self.unary(depth + 1)?;
+        match self.constant_since(start) {
+            Some(value)
+                if value.im == 0.0
+                    && value.re.fract() == 0.0
+                    && value.re.abs() <= MAX_INTEGER_POWER as f64 =>
+            {
+                self.ops.truncate(start);
+                self.emit(Op::PowInt(value.re as i32))
+            }
+            _ => self.emit(Op::Pow),
+        }
+    }
+
+    fn primary(&mut self, depth: usize) -> Result<(), String> {
+        let position = self.position();
+        let Some((_, token)) = self.tokens.get(self.next).cloned() else {
+            return Err(format!(
+                "Invalid formula. Expected a value at {}.",
+                position
+            ));
+        };
+        self.next += 1;
+
+        match token {
+            Token::Number(value) => self.emit(Op::Const(Complex::new(value, 0.0))),
+            Token::Symbol('(') => {
+                self.expr(depth + 1)?;
+                self.expect_close()
+            }
+            Token::Symbol(symbol) => Err(format!(
+                "Invalid formula. Unexpected '{}' at {}.",
+                symbol, position
+            )),
+            Token::Name(name) => match name.as_str() {
+                "z" => self.emit(Op::Z),
+                "c" => self.emit(Op::C),
+                "i" => self.emit(Op::Const(Complex::new(0.0, 1.0))),
+                "pi" => self.emit(Op::Const(Complex::new(PI, 0.0))),
+                "e" => self.emit(Op::Const(Complex::new(E, 0.0))),
+                _ => {
+                    let function = Function::parse(&name).ok_or_else(|| {
+                        format!(
+                            "Invalid formula. Unknown name {} at {}. Allowed: {}, {}, {}.",
+                            name,
+                            position,
+                            FORMULA_VARIABLES.join(", "),
+                            FORMULA_CONSTANTS.join(", "),
+                            FORMULA_FUNCTIONS.join(", ")
+                        )
+                    })?;
+                    if !self.eat('(') {
+                        return Err(format!(
+                            "Invalid formula. Expected '(' after {} at {}.",
+                            name,
+                            self.position()
+                        ));
+                    }
+                    self.expr(depth + 1)?;
+                    self.expect_close()?;
+                    self.emit(Op::Call(function))
+                }
+            },
+        }
+    }
+
+    fn expect_close(&mut self) -> Result<(), String> {
+        if self.eat(')') {
+            Ok(())
+        } else {
+            Err(format!(
+                "Invalid formula. Expected ')' at {}.",
+                self.position()
+            ))
+        }
+    }
+}
+
+impl Formula {
+    pub fn parse(source: &str) -> Result<Self, String> {
+        if source.trim().is_empty() {
+            return Err("Invalid formula. It must not be empty.".to_string());
+        }
+        if source.len() > MAX_FORMULA_LENGTH {
+            return Err(format!(
+                "Invalid formula. Must be at most {} characters.",
+                MAX_FORMULA_LENGTH
+            ));
+        }
+
+        let mut parser = Parser {
+            tokens: tokenize(source)?,
+            next: 0,
+            end: source.len(),
+            ops: Vec::new(),
+        };
+        parser.expr(0)?;
+        if parser.next < parser.tokens.len() {
+            return Err(format!(
+                "Invalid formula. Expected an operator at {}.",
+                parser.position()
+            ));
+        }
+
+        let mut depth: usize = 0;
+        let mut stack_size = 0;
+        for op in &parser.ops {
+            match op {
+                Op::Const(_) | Op::Z | Op::C => depth += 1,
+                Op::Add | Op::Sub | Op::Mul | Op::Div | Op::Pow => depth -= 1,
+                Op::Neg | Op::PowInt(_) | Op::Call(_) => {}
+            }
+            stack_size = stack_size.max(depth);
+        }
+
+        Ok(Self {
+            ops: parser.ops,
+            stack_size,
+        })
+    }
+
+    /// Operations evaluated per iteration, for work budgets
+    pub fn op_count(&self) -> usize {
+        self.ops.len()
+    }
+
+    /// Scratch space for `eval`, reused between calls
+    pub fn stack(&self) -> Vec<Complex> {
+        Vec::with_capacity(self.stack_size)
+    }
+
+    pub fn eval(&self, z: Complex, c: Complex, stack: &mut Vec<Complex>) -> Complex {
+        stack.clear();
+        for &op in &self.ops {
+            let value = match op {
+                Op::Const(value) => value,
+                Op::Z => z,
+                Op::C => c,
+                Op::Neg => -stack.pop().unwrap_or_default(),
+                Op::PowInt(exponent) => stack.pop().unwrap_or_default().powi(exponent),
+                Op::Call(function) => function.apply(stack.pop().unwrap_or_default()),
+                Op::Add | Op::Sub | Op::Mul | Op::Div | Op::Pow => {
+                    let rhs = stack.pop().unwrap_or_default();
+                    let lhs = stack.pop().unwrap_or_default();
+                    match op {
+                        Op::Add => lhs + rhs,
+                        Op::Sub => lhs - rhs,
+                        Op::Mul => lhs * rhs,
+                        Op::Div => lhs / rhs,
+                        _ => lhs.powc(rhs),
+                    }
+                }
+            };
+            stack.push(value);
+        }
+        stack.pop().unwrap_or_default()
+    }
+}
@@ -0,0 +1,41 @@
+//! Response helpers shared by the output formats.
+
+use axum::http::{HeaderMap, HeaderName, HeaderValue};
+
+/// Append render headers to a response. Post-render plugins supply some of them, so a name or
+/// value that isn't valid HTTP is logged and left out instead of failing the response.
+pub fn append_headers(headers: &mut HeaderMap, extra_headers: &[(String, String)]) {
+    for (name, value) in extra_headers {
+        match (
+            HeaderName::try_from(name.as_str()),
+            HeaderValue::try_from(value.as_str()),
+        ) {
+            (Ok(name), Ok(value)) => {
+                headers.append(name, value);
+            }
+            _ => tracing::warn!("Dropping invalid response header {:?}: {:?}", name, value),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn skips_headers_that_are_not_valid_http() {
+        let mut headers = HeaderMap::new();
+        append_headers(
+            &mut headers,
+            &[
+                ("X-Plugin".to_string(), "ok".to_string()),
+                ("X Bad Name".to_string(), "value".to_string()),
+                ("X-Bad-Value".to_string(), "line\nbreak".to_string()),
+                ("X-Non-Ascii-Näme".to_string(), "value".to_string()),
+            ],
+        );
+
+        assert_eq!(headers.len(), 1);
+        assert_eq!(headers["x-plugin"], "ok");
+    }
+}
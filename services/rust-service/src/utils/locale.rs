@@ -0,0 +1,99 @@
+//! Locale-aware numbers: accept decimal commas in numeric strings ("0,285") and format
+//! output numbers the way the client's locale writes them.
+
+/// Languages that write 1.234,5
+const DOT_GROUPED: [&str; 12] = [
+    "de", "es", "it", "nl", "pt", "da", "tr", "id", "el", "ro", "hr", "sl",
+];
+
+/// Languages that write 1 234,5 (narrow no-break space)
+const SPACE_GROUPED: [&str; 12] = [
+    "fr", "ru", "pl", "sv", "fi", "nb", "no", "cs", "sk", "uk", "hu", "bg",
+];
+
+/// Number conventions for a locale
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Locale {
+    /// BCP 47 tag as supplied by the client, e.g. "de-DE"
+    pub tag: String,
+    decimal_separator: char,
+    group_separator: char,
+}
+
+impl Default for Locale {
+    fn default() -> Self {
+        Self::from_tag("en")
+    }
+}
+
+impl Locale {
+    /// Pick separators from the primary language subtag; unknown languages use "1,234.5"
+    pub fn from_tag(tag: &str) -> Self {
+        let language = tag
+            .split(['-', '_'])
+            .next()
+            .unwrap_or_default()
+            .to_lowercase();
+
+        let (decimal_separator, group_separator) = if DOT_GROUPED.contains(&language.as_str()) {
+            (',', '.')
+        } else if SPACE_GROUPED.contains(&language.as_str()) {
+            (',', '\u{202F}')
+        } else {
+            ('.', ',')
+        };
+
+        Self {
+            tag: tag.to_string(),
+            decimal_separator,
+            group_separator,
+        }
+    }
+
+    /// First language of an Accept-Language header, e.g. "fr-CH, fr;q=0.9, en;q=0.8"
+    pub fn from_accept_language(header: &str) -> Option<Self> {
+        let tag = header.split(',').next()?.split(';').next()?.trim();
+        (!tag.is_empty() && tag != "*").then(|| Self::from_tag(tag))
+    }
+
+    /// Format with a fixed number of decimals and grouped thousands
+    pub fn format(&self, value: f64, decimals: usize) -> String {
+        let text = format!("{:.*}", decimals, value.abs());
+        let (integer, fraction) = text.split_once('.').unwrap_or((&text, ""));
+
+        let mut grouped = String::new();
+        for (index, digit) in integer.chars().enumerate() {
+            if index > 0 && (integer.len() - index) % 3 == 0 {
+                grouped.push(self.group_separator);
+            }
+            grouped.push(digit);
+        }
+
+        let sign = if value < 0.0 && text.chars().any(|c| c.is_ascii_digit() && c != '0') {
+            "-"
+        } else {
+            ""
+        };
+        if fraction.is_empty() {
+            format!("{}{}", sign, grouped)
+        } else {
+            format!("{}{}{}{}", sign, grouped, self.decimal_separator, fraction)
+        }
+    }
+}
+
+/// Parse a number written with either decimal separator. When both separators appear the
+/// last one is the decimal mark and the other groups thousands ("1.234,5" or "1,234.5").
+pub fn parse_decimal(text: &str) -> Result<f64, String> {
+    let text = text.trim();
+    let normalized = match (text.rfind(','), text.rfind('.')) {
+        (Some(comma), Some(dot)) if comma > dot => text.replace('.', "").replace(',', "."),
+        (Some(_), Some(_)) => text.replace(',', ""),
+        (Some(_), None) => text.replace(',', "."),
+        _ => text.to_string(),
+    };
+
+    normalized
+        .parse::<f64>()
+        .map_err(|_| format!("Invalid number: {}", text))
+}
@@ -1 +1,7 @@
+pub mod complex;
+pub mod expression;
+pub mod http;
+pub mod locale;
+pub mod rng;
+pub mod time;
 pub mod validation;
@@ -1,4 +1,10 @@
-use crate::fractals::traits::FractalParams;
+use crate::fractals::ifs::AffineTransform;
+use crate::jobs::FEED_LENGTH;
+use crate::palettes;
+use crate::rendering::colors::{ColorScheme, INTERPOLATIONS};
+use crate::utils::complex::Complex;
+use schemars::JsonSchema;
+use serde::Deserialize;
 
 pub fn validate_dimensions(width: u32, height: u32) -> Result<(), String> {
     if width == 0 || height == 0 || width > 4096 || height > 4096 {
@@ -17,8 +23,13 @@ pub fn validate_zoom(zoom: f64) -> Result<(), String> {
 }
 
 pub fn validate_iterations(max_iterations: u32) -> Result<(), String> {
-    if max_iterations == 0 || max_iterations > 10000 {
-        return Err("Invalid max_iterations. Must be between 1 and 10000.".to_string());
+    validate_iteration_limit("max_iterations", max_iterations)
+}
+
+/// An iteration limit, reported under the parameter `name` it came from
+pub fn validate_iteration_limit(name: &str, iterations: u32) -> Result<(), String> {
+    if iterations == 0 || iterations > 10000 {
+        return Err(format!("Invalid {}. Must be between 1 and 10000.", name));
     }
     Ok(())
 }
@@ -38,3 +49,824 @@ pub fn validate_recursion_depth(depth: u32) -> Result<(), String> {
     }
     Ok(())
 }
+
+/// Relative to the outer circle, which has curvature 1
+pub fn validate_max_curvature(max_curvature: f64) -> Result<(), String> {
+    if !(max_curvature.is_finite() && (1.0..=1e6).contains(&max_curvature)) {
+        return Err("Invalid max_curvature. Must be between 1 and 1e6.".to_string());
+    }
+    Ok(())
+}
+
+pub fn validate_line_thickness(thickness: u32) -> Result<(), String> {
+    if !(1..=32).contains(&thickness) {
+        return Err("Invalid line_thickness. Must be between 1 and 32.".to_string());
+    }
+    Ok(())
+}
+
+pub fn validate_roughness(roughness: f64) -> Result<(), String> {
+    if !(0.0..=1.0).contains(&roughness) {
+        return Err("Invalid roughness. Must be between 0 and 1.".to_string());
+    }
+    Ok(())
+}
+
+pub fn validate_attractor_coefficient(
+    name: &str,
+    value: f64,
+    range: &std::ops::RangeInclusive<f64>,
+) -> Result<(), String> {
+    if !(value.is_finite() && range.contains(&value)) {
+        return Err(format!(
+            "Invalid {}. Must be between {} and {}.",
+            name,
+            range.start(),
+            range.end()
+        ));
+    }
+    Ok(())
+}
+
+/// A `name_min`..`name_max` interval inside `limits`, non-empty
+pub fn validate_interval(
+    name: &str,
+    min: f64,
+    max: f64,
+    limits: &std::ops::RangeInclusive<f64>,
+) -> Result<(), String> {
+    if !(limits.contains(&min) && limits.contains(&max)) {
+        return Err(format!(
+            "Invalid {0}_min/{0}_max. Must be between {1} and {2}.",
+            name,
+            limits.start(),
+            limits.end()
+        ));
+    }
+    if min >= max {
+        return Err(format!("Invalid {0}_min/{0}_max. {0}_max must exceed {0}_min.", name));
+    }
+    Ok(())
+}
+
+pub fn validate_newton_degree(degree: u32) -> Result<(), String> {
+    if !(2..=12).contains(&degree) {
+        return Err("Invalid newton_degree. Must be between 2 and 12.".to_string());
+    }
+    Ok(())
+}
+
+pub fn validate_relaxation(relaxation: f64) -> Result<(), String> {
+    if !(relaxation > 0.0 && relaxation <= 2.0) {
+        return Err("Invalid relaxation. Must be greater than 0 and at most 2.".to_string());
+    }
+    Ok(())
+}
+
+/// Boundary width in pixels for coloring=distance
+pub fn validate_boundary_width(width: f64) -> Result<(), String> {
+    if !(width > 0.0 && width <= 32.0) {
+        return Err("Invalid boundary_width. Must be greater than 0 and at most 32.".to_string());
+    }
+    Ok(())
+}
+
+/// Stripes per turn for coloring=stripe_average
+pub fn validate_stripe_density(density: f64) -> Result<(), String> {
+    if !(density > 0.0 && density <= 32.0) {
+        return Err("Invalid stripe_density. Must be greater than 0 and at most 32.".to_string());
+    }
+    Ok(())
+}
+
+/// Palette shift and repeats for color_offset and color_scale
+pub fn validate_palette_cycle(offset: f64, scale: f64) -> Result<(), String> {
+    if !(0.0..=1.0).contains(&offset) {
+        return Err("Invalid color_offset. Must be between 0 and 1.".to_string());
+    }
+    if !(scale > 0.0 && scale <= 1000.0) {
+        return Err("Invalid color_scale. Must be greater than 0 and at most 1000.".to_string());
+    }
+    Ok(())
+}
+
+/// Color space palette stops are interpolated in
+pub fn validate_interpolation(interpolation: &str) -> Result<(), String> {
+    if !INTERPOLATIONS.contains(&interpolation.to_lowercase().as_str()) {
+        return Err(format!(
+            "Invalid interpolation. Must be one of: {}.",
+            INTERPOLATIONS.join(", ")
+        ));
+    }
+    Ok(())
+}
+
+/// Direction of the light for shading=lambert, in degrees counterclockwise from the right edge
+pub fn validate_light_azimuth(azimuth: f64) -> Result<(), String> {
+    if !(0.0..=360.0).contains(&azimuth) {
+        return Err("Invalid light_azimuth. Must be between 0 and 360.".to_string());
+    }
+    Ok(())
+}
+
+/// Height of the light above the image plane for shading=lambert, in degrees
+pub fn validate_light_elevation(elevation: f64) -> Result<(), String> {
+    if !(0.0..=90.0).contains(&elevation) {
+        return Err("Invalid light_elevation. Must be between 0 and 90.".to_string());
+    }
+    Ok(())
+}
+
+/// Relative escape-time difference that makes a pixel an edge for aa=adaptive
+pub fn validate_aa_threshold(threshold: f64) -> Result<(), String> {
+    if !(threshold > 0.0 && threshold <= 1.0) {
+        return Err("Invalid aa_threshold. Must be greater than 0 and at most 1.".to_string());
+    }
+    Ok(())
+}
+
+/// Output encoding gamma: 1 leaves linear light, 2.2 suits a plain power-law display
+pub fn validate_gamma(gamma: f64) -> Result<(), String> {
+    if !(1.0..=3.0).contains(&gamma) {
+        return Err("Invalid gamma. Must be between 1 and 3.".to_string());
+    }
+    Ok(())
+}
+
+/// Gray levels for dithered output: 2 for pure black and white, up to one per 8-bit gray
+pub fn validate_dither_levels(levels: u32) -> Result<(), String> {
+    if !(2..=256).contains(&levels) {
+        return Err("Invalid dither_levels. Must be between 2 and 256.".to_string());
+    }
+    Ok(())
+}
+
+/// Largest tone-mapping exposure adjustment in stops either way
+pub const MAX_EXPOSURE: f64 = 10.0;
+
+/// Tone-mapping exposure in stops for density renders
+pub fn validate_exposure(exposure: f64) -> Result<(), String> {
+    if !(-MAX_EXPOSURE..=MAX_EXPOSURE).contains(&exposure) {
+        return Err(format!(
+            "Invalid exposure. Must be between -{} and {} stops.",
+            MAX_EXPOSURE, MAX_EXPOSURE
+        ));
+    }
+    Ok(())
+}
+
+/// PNG bits per channel: 8, or 16 for color grading without banding
+pub fn validate_bit_depth(bit_depth: u8) -> Result<(), String> {
+    if bit_depth != 8 && bit_depth != 16 {
+        return Err("Invalid bit_depth. Must be 8 or 16.".to_string());
+    }
+    Ok(())
+}
+
+pub fn validate_recent_jobs_limit(limit: usize) -> Result<(), String> {
+    if limit == 0 || limit > FEED_LENGTH {
+        return Err(format!("Invalid limit. Must be between 1 and {}.", FEED_LENGTH));
+    }
+    Ok(())
+}
+
+/// Largest trap_x/trap_y magnitude and trap_radius for coloring=orbit_trap
+pub const MAX_TRAP_EXTENT: f64 = 10.0;
+
+pub fn validate_orbit_trap(x: f64, y: f64, radius: f64) -> Result<(), String> {
+    if !(x.abs() <= MAX_TRAP_EXTENT && y.abs() <= MAX_TRAP_EXTENT) {
+        return Err(format!(
+            "Invalid trap_x/trap_y. Must be between -{0} and {0}.",
+            MAX_TRAP_EXTENT
+        ));
+    }
+    if !(radius > 0.0 && radius <= MAX_TRAP_EXTENT) {
+        return Err(format!(
+            "Invalid trap_radius. Must be greater than 0 and at most {}.",
+            MAX_TRAP_EXTENT
+        ));
+    }
+    Ok(())
+}
+
+/// Upper bound on samples * max_iterations for sampled renderers (Buddhabrot and friends)
+pub const MAX_SAMPLE_WORK: u64 = 2_000_000_000;
+
+pub fn validate_sample_budget(samples: u64, max_iterations: u32) -> Result<(), String> {
+    if samples == 0 || samples > 10_000_000 {
+        return Err("Invalid samples. Must be between 1 and 10000000.".to_string());
+    }
+    if samples * max_iterations as u64 > MAX_SAMPLE_WORK {
+        return Err(format!(
+            "Sample budget exceeded. samples * max_iterations must not exceed {}.",
+            MAX_SAMPLE_WORK
+        ));
+    }
+    Ok(())
+}
+
+/// Upper bound on pixels * max_iterations * formula operations for `type=custom`
+pub const MAX_FORMULA_WORK: u64 = 10_000_000_000;
+
+pub fn validate_formula_budget(
+    width: u32,
+    height: u32,
+    max_iterations: u32,
+    operations: usize,
+) -> Result<(), String> {
+    let work = u64::from(width) * u64::from(height) * u64::from(max_iterations) * operations as u64;
+    if work > MAX_FORMULA_WORK {
+        return Err(format!(
+            "Formula budget exceeded. width * height * max_iterations * formula operations must not exceed {}.",
+            MAX_FORMULA_WORK
+        ));
+    }
+    Ok(())
+}
+
+pub fn validate_point_count(points: u64) -> Result<(), String> {
+    if points == 0 || points > 50_000_000 {
+        return Err("Invalid samples. Point count must be between 1 and 50000000.".to_string());
+    }
+    Ok(())
+}
+
+pub fn validate_points_per_column(points: u64) -> Result<(), String> {
+    if points == 0 || points > 100_000 {
+        return Err("Invalid samples. Points per column must be between 1 and 100000.".to_string());
+    }
+    Ok(())
+}
+
+pub fn validate_explore(count: u32, thumb_size: u32, spread: f64) -> Result<(), String> {
+    if count == 0 || count > 24 {
+        return Err("Invalid count. Must be between 1 and 24.".to_string());
+    }
+    if !(16..=512).contains(&thumb_size) {
+        return Err("Invalid thumb_size. Must be between 16 and 512.".to_string());
+    }
+    if !(spread > 0.0 && spread <= 2.0) {
+        return Err("Invalid spread. Must be greater than 0 and at most 2.".to_string());
+    }
+    Ok(())
+}
+
+pub fn validate_crop(field: f64, positions: u32, width: u32, height: u32) -> Result<(), String> {
+    if !(1.0..=4.0).contains(&field) {
+        return Err("Invalid field. Must be between 1 and 4.".to_string());
+    }
+    if positions == 0 || positions > 32 {
+        return Err("Invalid positions. Must be between 1 and 32.".to_string());
+    }
+    if (width as f64 * field).round() > 4096.0 || (height as f64 * field).round() > 4096.0 {
+        return Err(
+            "Crop field too large. width and height times field must be at most 4096.".to_string(),
+        );
+    }
+    Ok(())
+}
+
+/// Longest side of a Deep Zoom image at its finest level
+pub const MAX_DZI_SIDE: u32 = 16_384;
+
+/// Pixels rendered across every level of a Deep Zoom pyramid, overlaps included; this also
+/// bounds the zip, which is built in memory
+pub const MAX_DZI_PIXELS: u64 = 67_108_864;
+
+pub fn validate_deep_zoom(
+    width: u32,
+    height: u32,
+    tile_size: u32,
+    overlap: u32,
+) -> Result<(), String> {
+    if width == 0 || height == 0 || width > MAX_DZI_SIDE || height > MAX_DZI_SIDE {
+        return Err(format!(
+            "Invalid dimensions. Width and height must be between 1 and {} for a Deep Zoom image.",
+            MAX_DZI_SIDE
+        ));
+    }
+    if !(64..=1024).contains(&tile_size) {
+        return Err("Invalid tile_size. Must be between 64 and 1024.".to_string());
+    }
+    if overlap > 8 {
+        return Err("Invalid overlap. Must be at most 8 pixels.".to_string());
+    }
+    Ok(())
+}
+
+pub fn validate_pyramid_pixels(pixels: u64) -> Result<(), String> {
+    if pixels > MAX_DZI_PIXELS {
+        return Err(format!(
+            "Deep Zoom pyramid too large: {} pixels across its levels, at most {} allowed. Use a smaller width and height.",
+            pixels, MAX_DZI_PIXELS
+        ));
+    }
+    Ok(())
+}
+
+/// Most cells in a JSON iteration matrix, which serializes to several bytes each
+pub const MAX_MATRIX_CELLS: u64 = 65_536;
+
+pub fn validate_iteration_matrix(width: u32, height: u32, downsample: u32) -> Result<(), String> {
+    if downsample == 0 || downsample > 16 {
+        return Err("Invalid downsample. Must be between 1 and 16.".to_string());
+    }
+    let cells = u64::from(width.div_ceil(downsample)) * u64::from(height.div_ceil(downsample));
+    if cells > MAX_MATRIX_CELLS {
+        return Err(format!(
+            "Iteration matrix too large: {} cells. Must be at most {}; raise downsample or lower width and height, or use format=raw.",
+            cells, MAX_MATRIX_CELLS
+        ));
+    }
+    Ok(())
+}
+
+pub fn validate_estimate_sample(sample: f64) -> Result<(), String> {
+    if !(0.0001..=0.25).contains(&sample) {
+        return Err("Invalid sample. Must be between 0.0001 and 0.25.".to_string());
+    }
+    Ok(())
+}
+
+pub const MAX_FLAME_TRANSFORMS: usize = 16;
+
+/// Histogram bins (pixels x supersample^2) a flame may allocate
+pub const MAX_FLAME_BINS: u64 = 8_388_608;
+
+/// Most points plotted for one flame
+pub const MAX_FLAME_SAMPLES: u64 = 1 << 24;
+
+pub fn validate_flame(
+    transforms: usize,
+    samples: u64,
+    width: u32,
+    height: u32,
+    supersample: u32,
+) -> Result<(), String> {
+    if transforms == 0 || transforms > MAX_FLAME_TRANSFORMS {
+        return Err(format!(
+            "Invalid transforms. A flame takes between 1 and {} transforms.",
+            MAX_FLAME_TRANSFORMS
+        ));
+    }
+    if samples == 0 || samples > MAX_FLAME_SAMPLES {
+        return Err(format!(
+            "Invalid samples. Must be between 1 and {}.",
+            MAX_FLAME_SAMPLES
+        ));
+    }
+    if !(1..=4).contains(&supersample) {
+        return Err("Invalid supersample. Must be between 1 and 4.".to_string());
+    }
+    let bins = width as u64 * height as u64 * (supersample * supersample) as u64;
+    if bins > MAX_FLAME_BINS {
+        return Err(format!(
+            "Flame too large: {}x{} at supersample {} needs {} histogram bins, at most {}. \
+             Use a smaller image or supersample.",
+            width, height, supersample, bins, MAX_FLAME_BINS
+        ));
+    }
+    Ok(())
+}
+
+pub fn validate_flame_tone(brightness: f64, gamma: f64, vibrancy: f64) -> Result<(), String> {
+    if !(0.1..=10.0).contains(&brightness) {
+        return Err("Invalid brightness. Must be between 0.1 and 10.".to_string());
+    }
+    if !(0.5..=8.0).contains(&gamma) {
+        return Err("Invalid gamma. Must be between 0.5 and 8.".to_string());
+    }
+    if !(0.0..=1.0).contains(&vibrancy) {
+        return Err("Invalid vibrancy. Must be between 0 and 1.".to_string());
+    }
+    Ok(())
+}
+
+pub fn validate_flame_transform(
+    weight: f64,
+    color: f64,
+    affine: &[f64; 6],
+    post: Option<&[f64; 6]>,
+    mut variation_weights: impl Iterator<Item = f64>,
+) -> Result<(), String> {
+    if !(weight.is_finite() && weight > 0.0 && weight <= 1e3) {
+        return Err("Invalid weight. Must be greater than 0 and at most 1000.".to_string());
+    }
+    if !(0.0..=1.0).contains(&color) {
+        return Err("Invalid color. Must be between 0 and 1.".to_string());
+    }
+    if affine
+        .iter()
+        .chain(post.into_iter().flatten())
+        .any(|c| !c.is_finite() || c.abs() > 1e3)
+    {
+        return Err("Invalid affine/post. Coefficients must be between -1e3 and 1e3.".to_string());
+    }
+    if variation_weights.any(|w| !w.is_finite() || w.abs() > 1e3) {
+        return Err("Invalid variations. Weights must be between -1e3 and 1e3.".to_string());
+    }
+    Ok(())
+}
+
+pub fn validate_montage(
+    panels: u32,
+    tile_width: u32,
+    tile_height: u32,
+    border: u32,
+    columns: Option<u32>,
+) -> Result<(), String> {
+    if panels == 0 || panels > 16 {
+        return Err("Invalid panels. A montage takes between 1 and 16 panels.".to_string());
+    }
+    if !(16..=1024).contains(&tile_width) || !(16..=1024).contains(&tile_height) {
+        return Err(
+            "Invalid tile size. tile_width and tile_height must be between 16 and 1024."
+                .to_string(),
+        );
+    }
+    if border > 64 {
+        return Err("Invalid border. Must be at most 64 pixels.".to_string());
+    }
+    if columns.is_some_and(|columns| columns == 0 || columns > 16) {
+        return Err("Invalid columns. Must be between 1 and 16.".to_string());
+    }
+    Ok(())
+}
+
+/// Most tiles a stream frame can carry: frame messages count and address tiles in u16
+pub const MAX_STREAM_TILES: u64 = u16::MAX as u64;
+
+fn validate_stream_tiles(width: u32, height: u32, tile_size: u32) -> Result<(), String> {
+    if !(8..=256).contains(&tile_size) {
+        return Err("Invalid tile_size. Must be between 8 and 256.".to_string());
+    }
+    let tiles = width.div_ceil(tile_size) as u64 * height.div_ceil(tile_size) as u64;
+    if tiles > MAX_STREAM_TILES {
+        return Err(format!(
+            "{}x{} at tile_size={} is {} tiles; a stream holds at most {}. Raise tile_size or shrink the image.",
+            width, height, tile_size, tiles, MAX_STREAM_TILES
+        ));
+    }
+    Ok(())
+}
+
+pub fn validate_zoom_stream(
+    frames: u32,
+    zoom_factor: f64,
+    width: u32,
+    height: u32,
+    tile_size: u32,
+) -> Result<(), String> {
+    if frames == 0 || frames > 600 {
+        return Err("Invalid frames. Must be between 1 and 600.".to_string());
+    }
+    if !(0.5..=2.0).contains(&zoom_factor) {
+        return Err("Invalid zoom_factor. Must be between 0.5 and 2.".to_string());
+    }
+    validate_stream_tiles(width, height, tile_size)
+}
+
+pub fn validate_palette_stream(
+    frames: u32,
+    to_color_scheme: &str,
+    width: u32,
+    height: u32,
+    tile_size: u32,
+) -> Result<(), String> {
+    if frames == 0 || frames > 600 {
+        return Err("Invalid frames. Must be between 1 and 600.".to_string());
+    }
+    if ColorScheme::is_palette(to_color_scheme) {
+        ColorScheme::parse_palette(to_color_scheme)?;
+    } else if !ColorScheme::NAMES.contains(&to_color_scheme.to_lowercase().as_str())
+        && palettes::lookup(to_color_scheme).is_none()
+    {
+        let mut names: Vec<String> = ColorScheme::NAMES.map(String::from).to_vec();
+        names.extend(palettes::names());
+        return Err(format!(
+            "Invalid to_color_scheme. Must be one of: {} or a palette of hex stops.",
+            names.join(", ")
+        ));
+    }
+    validate_stream_tiles(width, height, tile_size)
+}
+
+/// Longest sonification clip in milliseconds
+pub const MAX_SONIFY_MS: u64 = 120_000;
+
+pub fn validate_sonify(
+    notes: u32,
+    note_ms: u32,
+    min_freq: f64,
+    max_freq: f64,
+    sample_rate: u32,
+) -> Result<(), String> {
+    if notes == 0 || notes > 1024 {
+        return Err("Invalid notes. Must be between 1 and 1024.".to_string());
+    }
+    if !(10..=2000).contains(&note_ms) {
+        return Err("Invalid note_ms. Must be between 10 and 2000.".to_string());
+    }
+    if u64::from(notes) * u64::from(note_ms) > MAX_SONIFY_MS {
+        return Err(format!(
+            "Clip too long. notes x note_ms must be at most {} ms.",
+            MAX_SONIFY_MS
+        ));
+    }
+    if !(20.0..=20000.0).contains(&min_freq) || !(20.0..=20000.0).contains(&max_freq) {
+        return Err("Invalid min_freq/max_freq. Must be between 20 and 20000 Hz.".to_string());
+    }
+    if min_freq >= max_freq {
+        return Err("Invalid min_freq/max_freq. min_freq must be below max_freq.".to_string());
+    }
+    if !(8000..=48000).contains(&sample_rate) {
+        return Err("Invalid sample_rate. Must be between 8000 and 48000.".to_string());
+    }
+    Ok(())
+}
+
+/// Upper bound on frames * width * height * max_iterations for an animation
+pub const MAX_ANIMATION_WORK: u64 = 20_000_000_000;
+
+/// Upper bound on frames * width * height, which sets the time spent quantizing and the GIF size
+pub const MAX_ANIMATION_PIXELS: u64 = 50_000_000;
+
+pub fn validate_animation(
+    frames: u32,
+    fps: u32,
+    width: u32,
+    height: u32,
+    max_iterations: u32,
+) -> Result<(), String> {
+    if !(2..=300).contains(&frames) {
+        return Err("Invalid frames. Must be between 2 and 300.".to_string());
+    }
+    if !(1..=50).contains(&fps) {
+        return Err("Invalid fps. Must be between 1 and 50.".to_string());
+    }
+    let pixels = u64::from(frames) * u64::from(width) * u64::from(height);
+    if pixels > MAX_ANIMATION_PIXELS {
+        return Err(format!(
+            "Animation too large. frames * width * height must not exceed {}.",
+            MAX_ANIMATION_PIXELS
+        ));
+    }
+    if pixels * u64::from(max_iterations) > MAX_ANIMATION_WORK {
+        return Err(format!(
+            "Animation budget exceeded. frames * width * height * max_iterations must not exceed {}.",
+            MAX_ANIMATION_WORK
+        ));
+    }
+    Ok(())
+}
+
+/// Parse a Lyapunov rate sequence such as "AB" or "BBABA"; `true` selects rate b
+pub fn parse_lyapunov_sequence(sequence: &str) -> Result<Vec<bool>, String> {
+    if sequence.is_empty() || sequence.len() > 64 {
+        return Err("Invalid lyapunov_sequence. Length must be between 1 and 64.".to_string());
+    }
+
+    sequence
+        .chars()
+        .map(|c| match c.to_ascii_uppercase() {
+            'A' => Ok(false),
+            'B' => Ok(true),
+            _ => {
+                Err("Invalid lyapunov_sequence. Only the letters A and B are allowed.".to_string())
+            }
+        })
+        .collect()
+}
+
+/// Parse a comma-separated coefficient list (highest degree first), e.g. "1,0,0,-1" for z^3 - 1
+pub fn parse_newton_coefficients(coefficients: &str) -> Result<Vec<f64>, String> {
+    let parsed = coefficients
+        .split(',')
+        .map(|c| c.trim().parse::<f64>())
+        .collect::<Result<Vec<f64>, _>>()
+        .map_err(|_| {
+            "Invalid newton_coefficients. Expected a comma-separated list of numbers.".to_string()
+        })?;
+
+    if parsed.iter().any(|c| !c.is_finite() || c.abs() > 1e6) {
+        return Err(
+            "Invalid newton_coefficients. Coefficients must be between -1e6 and 1e6.".to_string(),
+        );
+    }
+
+    if parsed[0] == 0.0 {
+        return Err(
+            "Invalid newton_coefficients. Leading coefficient must be non-zero.".to_string(),
+        );
+    }
+
+    if !(3..=13).contains(&parsed.len()) {
+        return Err(
+            "Invalid newton_coefficients. Polynomial degree must be between 2 and 12.".to_string(),
+        );
+    }
+
+    Ok(parsed)
+}
+
+/// Largest degree of a Julia polynomial
+pub const MAX_JULIA_DEGREE: usize = 12;
+
+/// Largest magnitude of a Julia polynomial coefficient
+pub const MAX_JULIA_COEFFICIENT: f64 = 100.0;
+
+/// Parse a comma-separated list of complex coefficients (highest degree first) such as
+/// "1,0,0,-0.5+0.25i" for z^3 - 0.5 + 0.25i. Each is a real number, an imaginary one ("2i", "-i")
+/// or both ("0.3-0.1i").
+pub fn parse_julia_coefficients(coefficients: &str) -> Result<Vec<Complex>, String> {
+    let parsed = coefficients
+        .split(',')
+        .map(parse_complex)
+        .collect::<Option<Vec<Complex>>>()
+        .ok_or_else(|| {
+            "Invalid julia_coefficients. Expected a comma-separated list of numbers such as 1,0,-0.5+0.25i."
+                .to_string()
+        })?;
+
+    if parsed
+        .iter()
+        .any(|c| !c.is_finite() || c.norm() > MAX_JULIA_COEFFICIENT)
+    {
+        return Err(format!(
+            "Invalid julia_coefficients. Coefficient magnitudes must be at most {}.",
+            MAX_JULIA_COEFFICIENT
+        ));
+    }
+
+    if parsed[0].norm_sqr() == 0.0 {
+        return Err(
+            "Invalid julia_coefficients. Leading coefficient must be non-zero.".to_string(),
+        );
+    }
+
+    if !(3..=MAX_JULIA_DEGREE + 1).contains(&parsed.len()) {
+        return Err(format!(
+            "Invalid julia_coefficients. Polynomial degree must be between 2 and {}.",
+            MAX_JULIA_DEGREE
+        ));
+    }
+
+    Ok(parsed)
+}
+
+/// "a", "bi" or "a+bi" (either sign, whitespace ignored)
+fn parse_complex(text: &str) -> Option<Complex> {
+    let text: String = text.chars().filter(|c| !c.is_whitespace()).collect();
+    let Some(body) = text.strip_suffix('i') else {
+        return text.parse().ok().map(|re| Complex::new(re, 0.0));
+    };
+
+    // The imaginary part starts at the last sign that isn't an exponent's
+    let split = body
+        .char_indices()
+        .rev()
+        .find(|&(i, ch)| matches!(ch, '+' | '-') && i > 0 && !body[..i].ends_with(['e', 'E']))
+        .map(|(i, _)| i);
+    let (re, im) = match split {
+        Some(i) => (body[..i].parse().ok()?, &body[i..]),
+        None => (0.0, body),
+    };
+    let im = match im {
+        "" | "+" => 1.0,
+        "-" => -1.0,
+        im => im.parse().ok()?,
+    };
+
+    Some(Complex::new(re, im))
+}
+
+pub const MAX_IFS_TRANSFORMS: usize = 32;
+
+#[derive(Deserialize, JsonSchema)]
+pub struct IfsTransformSpec {
+    /// [a, b, c, d, e, f] for (x, y) -> (a x + b y + e, c x + d y + f)
+    coefficients: [f64; 6],
+    probability: f64,
+}
+
+/// Parse a JSON list of transforms such as
+/// `[{"coefficients":[0.5,0,0,0.5,0,0],"probability":1}, ...]` where coefficients are
+/// [a, b, c, d, e, f] for (x, y) -> (a x + b y + e, c x + d y + f)
+pub fn parse_ifs_transforms(transforms: &str) -> Result<Vec<AffineTransform>, String> {
+    let specs: Vec<IfsTransformSpec> = serde_json::from_str(transforms).map_err(|e| {
+        format!(
+            "Invalid ifs_transforms. Expected a JSON list of transforms: {}",
+            e
+        )
+    })?;
+
+    if specs.is_empty() || specs.len() > MAX_IFS_TRANSFORMS {
+        return Err(format!(
+            "Invalid ifs_transforms. Must contain between 1 and {} transforms.",
+            MAX_IFS_TRANSFORMS
+        ));
+    }
+
+    if specs.iter().any(|spec| {
+        spec.coefficients
+            .iter()
+            .any(|c| !c.is_finite() || c.abs() > 1e3)
+    }) {
+        return Err(
+            "Invalid ifs_transforms. Coefficients must be between -1e3 and 1e3.".to_string(),
+        );
+    }
+
+    if specs
+        .iter()
+        .any(|spec| !spec.probability.is_finite() || spec.probability < 0.0)
+    {
+        return Err("Invalid ifs_transforms. Probabilities must be non-negative.".to_string());
+    }
+    if specs.iter().all(|spec| spec.probability == 0.0) {
+        return Err(
+            "Invalid ifs_transforms. At least one probability must be positive.".to_string(),
+        );
+    }
+
+    let transforms: Vec<AffineTransform> = specs
+        .iter()
+        .map(|spec| AffineTransform::new(spec.coefficients, spec.probability))
+        .collect();
+
+    // Without contraction the chaos game diverges instead of settling onto an attractor
+    if let Some(index) = transforms
+        .iter()
+        .position(|transform| transform.max_stretch() >= 1.0)
+    {
+        return Err(format!(
+            "Invalid ifs_transforms. Transform {} is not a contraction (its linear part must shrink every vector).",
+            index
+        ));
+    }
+
+    Ok(transforms)
+}
+
+/// Upper bound on the length of an expanded L-system string
+pub const MAX_LSYSTEM_SYMBOLS: usize = 2_000_000;
+
+pub fn validate_lsystem_axiom(axiom: &str) -> Result<(), String> {
+    if axiom.is_empty() || axiom.chars().count() > 64 {
+        return Err("Invalid lsystem_axiom. Length must be between 1 and 64.".to_string());
+    }
+    Ok(())
+}
+
+pub fn validate_lsystem_angle(angle: f64) -> Result<(), String> {
+    if !(angle.is_finite() && angle.abs() <= 360.0) {
+        return Err("Invalid lsystem_angle. Must be between -360 and 360 degrees.".to_string());
+    }
+    Ok(())
+}
+
+/// Parse rewrite rules separated by semicolons, e.g. "X=X+YF+;Y=-FX-Y"
+pub fn parse_lsystem_rules(rules: &str) -> Result<Vec<(char, String)>, String> {
+    if rules.len() > 256 {
+        return Err("Invalid lsystem_rules. Must be at most 256 characters.".to_string());
+    }
+
+    let parsed = rules
+        .split(';')
+        .filter(|rule| !rule.trim().is_empty())
+        .map(|rule| {
+            let (predecessor, successor) = rule.split_once('=').ok_or_else(|| {
+                "Invalid lsystem_rules. Expected rules like F=F+F--F+F separated by semicolons."
+                    .to_string()
+            })?;
+            let mut chars = predecessor.trim().chars();
+            match (chars.next(), chars.next()) {
+                (Some(symbol), None) => Ok((symbol, successor.trim().to_string())),
+                _ => Err(
+                    "Invalid lsystem_rules. Each rule must rewrite a single symbol.".to_string(),
+                ),
+            }
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+
+    if parsed.is_empty() {
+        return Err("Invalid lsystem_rules. At least one rule is required.".to_string());
+    }
+
+    Ok(parsed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stream_tiles_fit_in_a_frame_header() {
+        // 255 x 257 = 65535 tiles, the most a u16 count addresses
+        assert!(validate_zoom_stream(60, 1.05, 2040, 2056, 8).is_ok());
+        assert!(validate_zoom_stream(60, 1.05, 2040, 2057, 8).is_err());
+        assert!(validate_palette_stream(30, "fire", 2040, 2056, 8).is_ok());
+        assert!(validate_palette_stream(30, "fire", 2041, 2056, 8).is_err());
+        assert!(validate_zoom_stream(60, 1.05, 4096, 4096, 8).is_err());
+    }
+}
@@ -0,0 +1,95 @@
+//! Optional startup warm-up: render a small standard set before accepting requests, so the
+//! first real requests don't pay for spinning up the thread pool and faulting in code, then
+//! time a second pass as a baseline throughput figure to compare hosts across a fleet.
+//!
+//! Configuration (all optional):
+//! - `WARMUP`: `true` (or `1`) to run the warm-up; off by default to keep startup fast
+
+use crate::fractals::create_fractal;
+use crate::fractals::traits::FractalParams;
+use crate::rendering::encoder::{encode_image, EncodeOptions, OutputFormat};
+use schemars::JsonSchema;
+use serde::Serialize;
+use std::sync::OnceLock;
+use std::time::Instant;
+
+const WIDTH: u32 = 320;
+const HEIGHT: u32 = 240;
+
+/// One escape-time, root-finding, sampled and geometric type, so each render path is exercised
+const STANDARD_SET: &[&str] = &["mandelbrot", "julia", "newton", "buddhabrot", "sierpinski"];
+
+static REPORT: OnceLock<WarmupReport> = OnceLock::new();
+
+#[derive(Clone, Debug, Serialize, JsonSchema)]
+pub struct WarmupReport {
+    /// Types rendered, each at `width` x `height` with default parameters
+    pub fractal_types: Vec<String>,
+    pub width: u32,
+    pub height: u32,
+    /// Wall time of the untimed first pass plus the timed second pass
+    pub warmup_ms: u128,
+    /// Pixels rendered and PNG-encoded per second over the second pass
+    pub baseline_pixels_per_sec: f64,
+}
+
+/// The startup result, when warm-up is enabled and has run
+pub fn current() -> Option<WarmupReport> {
+    REPORT.get().cloned()
+}
+
+pub fn enabled() -> bool {
+    std::env::var("WARMUP").is_ok_and(|value| matches!(value.to_lowercase().as_str(), "true" | "1"))
+}
+
+fn params(fractal_type: &str) -> FractalParams {
+    let mut params = FractalParams {
+        width: WIDTH,
+        height: HEIGHT,
+        ..FractalParams::default()
+    };
+    match fractal_type {
+        "julia" => {
+            params.julia_c_real = Some(-0.7);
+            params.julia_c_imag = Some(0.27);
+        }
+        "buddhabrot" => {
+            params.samples = Some(100_000);
+            params.seed = Some(1);
+        }
+        _ => {}
+    }
+    params
+}
+
+/// Render and encode the standard set once; returns the pixels produced
+fn render_set() -> Result<u64, String> {
+    let mut pixels = 0;
+    for &fractal_type in STANDARD_SET {
+        let fractal = create_fractal(fractal_type)
+            .ok_or_else(|| format!("Unknown warm-up type {}", fractal_type))?;
+        let img = fractal.generate(params(fractal_type))?;
+        pixels += u64::from(img.width()) * u64::from(img.height());
+        encode_image(&img, OutputFormat::Png, &EncodeOptions::default())?;
+    }
+    Ok(pixels)
+}
+
+/// Run the warm-up and store the report for `/api/info`
+pub fn run() -> Result<WarmupReport, String> {
+    let started = Instant::now();
+    render_set()?;
+
+    let timed = Instant::now();
+    let pixels = render_set()?;
+    let seconds = timed.elapsed().as_secs_f64().max(f64::EPSILON);
+
+    let report = WarmupReport {
+        fractal_types: STANDARD_SET.iter().map(|t| t.to_string()).collect(),
+        width: WIDTH,
+        height: HEIGHT,
+        warmup_ms: started.elapsed().as_millis(),
+        baseline_pixels_per_sec: pixels as f64 / seconds,
+    };
+    Ok(REPORT.get_or_init(|| report).clone())
+}
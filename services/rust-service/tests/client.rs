@@ -0,0 +1,102 @@
+//! End-to-end checks through `rust-service-client`: each test starts the service binary on a
+//! free port and talks to it over HTTP like any other consumer.
+
+use rust_service_client::{Client, Error, FractalRequest};
+use std::io::{BufRead, BufReader};
+use std::process::{Child, Command, Stdio};
+
+/// The service on an ephemeral port, killed when dropped
+struct Service {
+    child: Child,
+    client: Client,
+}
+
+impl Service {
+    fn start() -> Self {
+        let mut child = Command::new(env!("CARGO_BIN_EXE_rust-service"))
+            .env("PORT", "0")
+            .env("RUST_LOG", "info")
+            .env_remove("CONFIG_FILE")
+            .env_remove("NATS_URL")
+            .stdout(Stdio::piped())
+            .spawn()
+            .expect("service starts");
+
+        // The bound port is only known from the startup log
+        let mut lines = BufReader::new(child.stdout.take().unwrap()).lines();
+        let port = lines
+            .by_ref()
+            .map_while(Result::ok)
+            .find_map(|line| {
+                let (_, address) = line.split_once("listening on http://")?;
+                let (_, port) = address.rsplit_once(':')?;
+                let digits: String = port.chars().take_while(char::is_ascii_digit).collect();
+                digits.parse::<u16>().ok()
+            })
+            .expect("service logs its address");
+        // Keep reading so a full pipe never blocks the service's logging
+        std::thread::spawn(move || lines.for_each(drop));
+
+        let client = Client::new(&format!("http://127.0.0.1:{}", port)).unwrap();
+        Self { child, client }
+    }
+}
+
+impl Drop for Service {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+#[test]
+fn renders_a_png() {
+    let service = Service::start();
+    let response = service
+        .client
+        .render(&FractalRequest::mandelbrot().size(64, 48).max_iterations(50))
+        .unwrap();
+
+    assert_eq!(response.status, 200);
+    assert_eq!(response.header("Content-Type"), Some("image/png"));
+    assert!(response.body.starts_with(b"\x89PNG\r\n\x1a\n"));
+}
+
+#[test]
+fn serves_the_schema() {
+    let service = Service::start();
+    let schema = service.client.schema().unwrap();
+
+    assert_eq!(schema["$id"], "/api/schema/v1");
+    assert!(schema["definitions"]
+        .as_object()
+        .is_some_and(|d| !d.is_empty()));
+}
+
+#[test]
+fn validates_without_rendering() {
+    let service = Service::start();
+    let valid = service
+        .client
+        .validate(&FractalRequest::mandelbrot().size(64, 48))
+        .unwrap();
+    assert_eq!(valid["valid"], true);
+    assert_eq!(valid["params"]["width"], 64);
+
+    let invalid = service
+        .client
+        .validate(&FractalRequest::julia().size(64, 48))
+        .unwrap();
+    assert_eq!(invalid["valid"], false);
+}
+
+#[test]
+fn reports_api_errors() {
+    let service = Service::start();
+    let error = service
+        .client
+        .render(&FractalRequest::new("no-such-type"))
+        .unwrap_err();
+
+    assert!(matches!(error, Error::Api { status: 400, .. }), "{}", error);
+}